@@ -0,0 +1,342 @@
+//! Typed clients for the team API published at [`v1::BASE_URL`].
+//!
+//! The `client-async` feature provides [`Client`], and the `client-blocking` feature provides
+//! [`blocking::Client`]. Both can fetch the API from another base URL, and can keep the fetched
+//! files in a local directory, only downloading them again when they changed.
+
+use std::path::{Path, PathBuf};
+
+use crate::v1;
+
+/// Declare the methods fetching each endpoint of the API, for both clients
+macro_rules! endpoints {
+    ($kind:tt) => {
+        endpoints!(@method $kind
+            /// All the active teams
+            teams() -> v1::Teams = "teams.json");
+        endpoints!(@method $kind
+            /// An active team
+            team(name) -> v1::Team = "teams/{name}.json");
+        endpoints!(@method $kind
+            /// All the archived teams
+            archived_teams() -> v1::Teams = "archived-teams.json");
+        endpoints!(@method $kind
+            /// All the repositories, grouped by organization
+            repos() -> v1::Repos = "repos.json");
+        endpoints!(@method $kind
+            /// The GitHub organizations
+            orgs() -> v1::Orgs = "orgs.json");
+        endpoints!(@method $kind
+            /// All the people, keyed by GitHub username
+            people() -> v1::People = "people.json");
+        endpoints!(@method $kind
+            /// A person, by GitHub username
+            person(github) -> v1::Person = "people/{github}.json");
+        endpoints!(@method $kind
+            /// The available permissions, with the path of the endpoint of each
+            permissions() -> v1::Permissions = "permissions.json");
+        endpoints!(@method $kind
+            /// The changes of team memberships between the published revisions of the data
+            changes() -> v1::MembershipChanges = "changes.json");
+        endpoints!(@method $kind
+            /// The mailing lists
+            lists() -> v1::Lists = "lists.json");
+        endpoints!(@method $kind
+            /// The Zulip user groups
+            zulip_groups() -> v1::ZulipGroups = "zulip-groups.json");
+        endpoints!(@method $kind
+            /// The Zulip streams
+            zulip_streams() -> v1::ZulipStreams = "zulip-streams.json");
+        endpoints!(@method $kind
+            /// The Zulip organization roles given to team members
+            zulip_roles() -> v1::ZulipRoles = "zulip-roles.json");
+        endpoints!(@method $kind
+            /// The map from GitHub ids to Zulip ids
+            zulip_map() -> v1::ZulipMapping = "zulip-map.json");
+        endpoints!(@method $kind
+            /// The teams whose members can be asked to review RFCs by rfcbot
+            rfcbot() -> v1::Rfcbot = "rfcbot.json");
+    };
+    (@method async $(#[$attr:meta])* $name:ident($($arg:ident),*) -> $ty:ty = $path:literal) => {
+        $(#[$attr])*
+        pub async fn $name(&self, $($arg: &str),*) -> Result<$ty, Error> {
+            self.get(&format!($path, $($arg = $arg),*)).await
+        }
+    };
+    (@method blocking $(#[$attr:meta])* $name:ident($($arg:ident),*) -> $ty:ty = $path:literal) => {
+        $(#[$attr])*
+        pub fn $name(&self, $($arg: &str),*) -> Result<$ty, Error> {
+            self.get(&format!($path, $($arg = $arg),*))
+        }
+    };
+}
+
+/// Fetches the team API
+#[cfg(feature = "client-async")]
+#[derive(Debug, Clone)]
+pub struct Client {
+    http: reqwest::Client,
+    base_url: String,
+    cache: Option<Cache>,
+}
+
+#[cfg(feature = "client-async")]
+impl Client {
+    pub fn new() -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            base_url: v1::BASE_URL.to_string(),
+            cache: None,
+        }
+    }
+
+    /// Fetch the API from another URL, like a local build of the static API
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Keep the fetched files in this directory, and only download them again when they changed
+    pub fn with_cache_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.cache = Some(Cache { dir: dir.into() });
+        self
+    }
+
+    endpoints!(async);
+
+    /// The people with a permission, like `dev-desktop` or `bors.rust.review`
+    pub async fn permission(&self, name: &str) -> Result<v1::Permission, Error> {
+        self.get(&v1::Permissions::path(name)).await
+    }
+
+    /// Fetch any endpoint of the API, relative to the base URL
+    pub async fn get<T: serde::de::DeserializeOwned>(&self, path: &str) -> Result<T, Error> {
+        let mut request = self.http.get(format!("{}/{path}", self.base_url));
+        if let Some(etag) = self.cache.as_ref().and_then(|cache| cache.etag(path)) {
+            request = request.header(reqwest::header::IF_NONE_MATCH, etag);
+        }
+        let response = request.send().await?;
+        let body = match &self.cache {
+            Some(cache) if response.status() == reqwest::StatusCode::NOT_MODIFIED => {
+                cache.read(path)?
+            }
+            cache => {
+                let response = response.error_for_status()?;
+                let etag = etag(response.headers());
+                let body = response.bytes().await?.to_vec();
+                if let Some(cache) = cache {
+                    cache.store(path, etag.as_deref(), &body)?;
+                }
+                body
+            }
+        };
+        Ok(serde_json::from_slice(&body)?)
+    }
+}
+
+#[cfg(feature = "client-async")]
+impl Default for Client {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "client-blocking")]
+pub mod blocking {
+    use std::path::PathBuf;
+
+    use super::{etag, v1, Cache, Error};
+
+    /// Fetches the team API, blocking the current thread
+    #[derive(Debug, Clone)]
+    pub struct Client {
+        http: reqwest::blocking::Client,
+        base_url: String,
+        cache: Option<Cache>,
+    }
+
+    impl Client {
+        pub fn new() -> Self {
+            Self {
+                http: reqwest::blocking::Client::new(),
+                base_url: v1::BASE_URL.to_string(),
+                cache: None,
+            }
+        }
+
+        /// Fetch the API from another URL, like a local build of the static API
+        pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+            self.base_url = base_url.into().trim_end_matches('/').to_string();
+            self
+        }
+
+        /// Keep the fetched files in this directory, and only download them again when they
+        /// changed
+        pub fn with_cache_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+            self.cache = Some(Cache { dir: dir.into() });
+            self
+        }
+
+        endpoints!(blocking);
+
+        /// The people with a permission, like `dev-desktop` or `bors.rust.review`
+        pub fn permission(&self, name: &str) -> Result<v1::Permission, Error> {
+            self.get(&v1::Permissions::path(name))
+        }
+
+        /// Fetch any endpoint of the API, relative to the base URL
+        pub fn get<T: serde::de::DeserializeOwned>(&self, path: &str) -> Result<T, Error> {
+            let mut request = self.http.get(format!("{}/{path}", self.base_url));
+            if let Some(etag) = self.cache.as_ref().and_then(|cache| cache.etag(path)) {
+                request = request.header(reqwest::header::IF_NONE_MATCH, etag);
+            }
+            let response = request.send()?;
+            let body = match &self.cache {
+                Some(cache) if response.status() == reqwest::StatusCode::NOT_MODIFIED => {
+                    cache.read(path)?
+                }
+                cache => {
+                    let response = response.error_for_status()?;
+                    let etag = etag(response.headers());
+                    let body = response.bytes()?.to_vec();
+                    if let Some(cache) = cache {
+                        cache.store(path, etag.as_deref(), &body)?;
+                    }
+                    body
+                }
+            };
+            Ok(serde_json::from_slice(&body)?)
+        }
+    }
+
+    impl Default for Client {
+        fn default() -> Self {
+            Self::new()
+        }
+    }
+}
+
+fn etag(headers: &reqwest::header::HeaderMap) -> Option<String> {
+    headers
+        .get(reqwest::header::ETAG)?
+        .to_str()
+        .ok()
+        .map(str::to_string)
+}
+
+/// Files of the API stored on disk with their ETag, so that they can be revalidated
+#[derive(Debug, Clone)]
+struct Cache {
+    dir: PathBuf,
+}
+
+impl Cache {
+    fn body_path(&self, path: &str) -> PathBuf {
+        self.dir.join(path)
+    }
+
+    fn etag_path(&self, path: &str) -> PathBuf {
+        self.dir.join(format!("{path}.etag"))
+    }
+
+    /// The ETag of a cached file, if both the file and its ETag are cached
+    fn etag(&self, path: &str) -> Option<String> {
+        if !self.body_path(path).is_file() {
+            return None;
+        }
+        std::fs::read_to_string(self.etag_path(path)).ok()
+    }
+
+    fn read(&self, path: &str) -> Result<Vec<u8>, Error> {
+        Ok(std::fs::read(self.body_path(path))?)
+    }
+
+    fn store(&self, path: &str, etag: Option<&str>, body: &[u8]) -> Result<(), Error> {
+        let body_path = self.body_path(path);
+        if let Some(parent) = body_path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&body_path, body)?;
+        match etag {
+            Some(etag) => std::fs::write(self.etag_path(path), etag)?,
+            None => remove_if_exists(&self.etag_path(path))?,
+        }
+        Ok(())
+    }
+}
+
+fn remove_if_exists(path: &Path) -> std::io::Result<()> {
+    match std::fs::remove_file(path) {
+        Err(err) if err.kind() != std::io::ErrorKind::NotFound => Err(err),
+        _ => Ok(()),
+    }
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Http(reqwest::Error),
+    Io(std::io::Error),
+    Json(serde_json::Error),
+}
+
+impl From<reqwest::Error> for Error {
+    fn from(err: reqwest::Error) -> Self {
+        Error::Http(err)
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(err: std::io::Error) -> Self {
+        Error::Io(err)
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(err: serde_json::Error) -> Self {
+        Error::Json(err)
+    }
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Error::Http(e) => write!(f, "failed to fetch the team API: {e}"),
+            Error::Io(e) => write!(f, "failed to access the team API cache: {e}"),
+            Error::Json(e) => write!(f, "failed to deserialize the team API: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Http(e) => Some(e),
+            Error::Io(e) => Some(e),
+            Error::Json(e) => Some(e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cache() -> Result<(), Error> {
+        let dir = std::env::temp_dir().join(format!("team-api-cache-{}", std::process::id()));
+        let cache = Cache { dir: dir.clone() };
+
+        assert_eq!(None, cache.etag("teams/foo.json"));
+        cache.store("teams/foo.json", Some("\"abc\""), b"{}")?;
+        assert_eq!(Some("\"abc\"".to_string()), cache.etag("teams/foo.json"));
+        assert_eq!(b"{}".to_vec(), cache.read("teams/foo.json")?);
+
+        // Files without an ETag can't be revalidated
+        cache.store("teams/foo.json", None, b"[]")?;
+        assert_eq!(None, cache.etag("teams/foo.json"));
+        assert_eq!(b"[]".to_vec(), cache.read("teams/foo.json")?);
+
+        std::fs::remove_dir_all(dir)?;
+        Ok(())
+    }
+}
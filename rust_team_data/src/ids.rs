@@ -0,0 +1,119 @@
+//! Identifiers of organizations, teams, repositories and people.
+//!
+//! They are all strings, so wrapping them in distinct types prevents passing one where another
+//! is expected, like the slug of a GitHub team where its name is expected. They dereference to
+//! `str`, so they can still be passed where any string is accepted. They are serialized as
+//! plain strings, and their `Debug` output is the one of the string.
+
+use std::borrow::Borrow;
+use std::fmt;
+use std::ops::Deref;
+
+use serde::{Deserialize, Serialize};
+
+macro_rules! string_id {
+    ($(#[$attr:meta])* $name:ident) => {
+        $(#[$attr])*
+        #[derive(Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+        #[serde(transparent)]
+        pub struct $name(String);
+
+        impl $name {
+            pub fn new(id: impl Into<String>) -> Self {
+                Self(id.into())
+            }
+
+            pub fn as_str(&self) -> &str {
+                &self.0
+            }
+
+            pub fn into_string(self) -> String {
+                self.0
+            }
+        }
+
+        impl From<String> for $name {
+            fn from(id: String) -> Self {
+                Self(id)
+            }
+        }
+
+        impl From<&str> for $name {
+            fn from(id: &str) -> Self {
+                Self(id.to_string())
+            }
+        }
+
+        impl From<&String> for $name {
+            fn from(id: &String) -> Self {
+                Self(id.clone())
+            }
+        }
+
+        impl AsRef<str> for $name {
+            fn as_ref(&self) -> &str {
+                &self.0
+            }
+        }
+
+        impl Deref for $name {
+            type Target = str;
+
+            fn deref(&self) -> &str {
+                &self.0
+            }
+        }
+
+        impl Borrow<str> for $name {
+            fn borrow(&self) -> &str {
+                &self.0
+            }
+        }
+
+        impl PartialEq<str> for $name {
+            fn eq(&self, other: &str) -> bool {
+                self.0 == other
+            }
+        }
+
+        impl PartialEq<&str> for $name {
+            fn eq(&self, other: &&str) -> bool {
+                self.0 == *other
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                fmt::Display::fmt(&self.0, f)
+            }
+        }
+
+        impl fmt::Debug for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                fmt::Debug::fmt(&self.0, f)
+            }
+        }
+    };
+}
+
+string_id!(
+    /// Name of a GitHub organization, like `rust-lang`
+    OrgName
+);
+string_id!(
+    /// Name of a team, or of a GitHub team
+    TeamName
+);
+string_id!(
+    /// Slug of a GitHub team, used in the URLs of the GitHub API. It is derived from the name
+    /// of the team when it is created or renamed, so it can differ from the current name.
+    TeamSlug
+);
+string_id!(
+    /// Name of a repository, without its organization
+    RepoName
+);
+string_id!(
+    /// GitHub username of a person, which identifies them in the team data
+    PersonId
+);
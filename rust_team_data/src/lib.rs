@@ -1,3 +1,7 @@
+#[cfg(any(feature = "client-async", feature = "client-blocking"))]
+pub mod client;
 #[cfg(feature = "email-encryption")]
 pub mod email_encryption;
+pub mod ids;
 pub mod v1;
+pub mod v2;
@@ -1,9 +1,11 @@
 use indexmap::IndexMap;
 use serde::{Deserialize, Serialize};
+use std::convert::TryFrom;
 
 pub static BASE_URL: &str = "https://team-api.infra.rust-lang.org/v1";
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "snake_case")]
 pub enum TeamKind {
     Team,
@@ -15,6 +17,7 @@ pub enum TeamKind {
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 pub struct Team {
     pub name: String,
     pub kind: TeamKind,
@@ -26,9 +29,16 @@ pub struct Team {
     pub github: Option<TeamGitHub>,
     pub website_data: Option<TeamWebsite>,
     pub roles: Vec<MemberRole>,
+    /// Names of the Zulip user groups of the team, detailed in `zulip-groups.json`
+    #[serde(default)]
+    pub zulip_groups: Vec<String>,
+    /// Names of the Zulip streams of the team, detailed in `zulip-streams.json`
+    #[serde(default)]
+    pub zulip_streams: Vec<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 pub struct TeamMember {
     pub name: String,
     pub github: String,
@@ -36,21 +46,58 @@ pub struct TeamMember {
     pub is_lead: bool,
     #[serde(skip_serializing_if = "Vec::is_empty", default)]
     pub roles: Vec<String>,
+    /// When the person joined the team (`YYYY-MM-DD`), if known
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub joined: Option<String>,
+    /// When the person left the team (`YYYY-MM-DD`), if known. Only set for alumni.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub left: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 pub struct TeamGitHub {
     pub teams: Vec<GitHubTeam>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 pub struct GitHubTeam {
     pub org: String,
     pub name: String,
+    /// The ID of the team on GitHub, used to track it across renames
+    #[serde(default)]
+    pub team_id: Option<u64>,
     pub members: Vec<u64>,
+    /// GitHub IDs of the members that are maintainers of this team
+    #[serde(default)]
+    pub maintainers: Vec<u64>,
+    /// Are the members of this team owners of the organization?
+    #[serde(default)]
+    pub org_owners: bool,
+    /// The GitHub team of the same organization this team is nested under
+    #[serde(default)]
+    pub parent: Option<String>,
+    /// Description of the team on GitHub, if it differs from the default one
+    #[serde(default)]
+    pub description: Option<String>,
+    /// Visibility of the team on GitHub, if it differs from the default (closed)
+    #[serde(default)]
+    pub privacy: Option<GitHubTeamPrivacy>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum GitHubTeamPrivacy {
+    /// Visible to all members of the organization
+    Closed,
+    /// Only visible to organization owners and the members of the team
+    Secret,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 pub struct TeamWebsite {
     pub name: String,
     pub description: String,
@@ -63,41 +110,120 @@ pub struct TeamWebsite {
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 pub struct MemberRole {
     pub id: String,
     pub description: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 pub struct Teams {
     #[serde(flatten)]
     pub teams: IndexMap<String, Team>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+pub struct Orgs {
+    #[serde(flatten)]
+    pub orgs: IndexMap<String, Org>,
+}
+
+/// Organization-wide settings of a GitHub organization
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+pub struct Org {
+    pub name: String,
+    /// Permission that all organization members have on its repositories
+    pub default_repository_permission: DefaultRepoPermission,
+    pub members_can_create_repositories: bool,
+    pub members_can_fork_private_repositories: bool,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum DefaultRepoPermission {
+    None,
+    Read,
+    Write,
+    Admin,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 pub struct Repos {
     #[serde(flatten)]
     pub repos: IndexMap<String, Vec<Repo>>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 pub struct List {
     pub address: String,
     pub members: Vec<String>,
+    #[serde(default)]
+    pub access_level: ListAccessLevel,
+    #[serde(default)]
+    pub reply_preference: ListReplyPreference,
+    /// Priority of the routes of the list, lower priorities are evaluated first
+    #[serde(default)]
+    pub priority: i32,
+}
+
+/// Who may send emails to a mailing list
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum ListAccessLevel {
+    #[default]
+    Everyone,
+    /// Only the members of the list
+    Members,
+    /// Only addresses of the domain of the list, for announcement lists
+    Readonly,
+}
+
+/// Where replies to the emails of a mailing list go
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum ListReplyPreference {
+    /// The original sender of the email
+    #[default]
+    Sender,
+    /// The list itself, so that replies reach all its members
+    List,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 pub struct Lists {
     pub lists: IndexMap<String, List>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 pub struct ZulipGroup {
     pub name: String,
     pub members: Vec<ZulipGroupMember>,
+    /// Who may mention the group, left unchanged on Zulip if missing
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mention_policy: Option<ZulipMentionPolicy>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum ZulipMentionPolicy {
+    Everyone,
+    Members,
+    Admins,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "snake_case")]
 pub enum ZulipGroupMember {
     // TODO(rylev): this variant can be removed once
@@ -107,17 +233,23 @@ pub enum ZulipGroupMember {
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 pub struct ZulipGroups {
     pub groups: IndexMap<String, ZulipGroup>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 pub struct ZulipStream {
     pub name: String,
     pub members: Vec<ZulipStreamMember>,
+    /// Whether the stream belongs to a sensitive team, whose member removals are limited
+    #[serde(default)]
+    pub sensitive: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "snake_case")]
 pub enum ZulipStreamMember {
     // TODO(rylev): this variant can be removed once
@@ -127,11 +259,47 @@ pub enum ZulipStreamMember {
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 pub struct ZulipStreams {
     pub streams: IndexMap<String, ZulipStream>,
 }
 
+/// Zulip organization role, ordered by the permissions it grants
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum ZulipRole {
+    Moderator,
+    Administrator,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+pub struct ZulipRoles {
+    /// Zulip ID to the highest role given by the teams of the user
+    pub users: IndexMap<u64, ZulipRole>,
+    /// Roles given by at least one team. Zulip users holding one of them without being
+    /// in `users` are demoted.
+    pub managed_roles: Vec<ZulipRole>,
+}
+
+/// The available permissions
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+pub struct Permissions {
+    /// Permission name as key, and the path of its endpoint relative to the API as value.
+    pub permissions: IndexMap<String, String>,
+}
+
+impl Permissions {
+    /// Path of the endpoint listing the people with a permission, relative to the API
+    pub fn path(permission: &str) -> String {
+        format!("permissions/{}.json", permission.replace('-', "_"))
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 pub struct Permission {
     pub people: Vec<PermissionPerson>,
     pub github_users: Vec<String>,
@@ -140,6 +308,7 @@ pub struct Permission {
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 pub struct PermissionPerson {
     pub github_id: u64,
     pub github: String,
@@ -147,11 +316,13 @@ pub struct PermissionPerson {
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 pub struct Rfcbot {
     pub teams: IndexMap<String, RfcbotTeam>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 pub struct RfcbotTeam {
     pub name: String,
     pub ping: String,
@@ -159,17 +330,22 @@ pub struct RfcbotTeam {
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 pub struct ZulipMapping {
     /// Zulip ID to GitHub ID
     pub users: IndexMap<u64, u64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 pub struct Repo {
     pub org: String,
     pub name: String,
     pub description: String,
     pub homepage: Option<String>,
+    // Topics declared for the repository.
+    #[serde(default)]
+    pub topics: Vec<String>,
     pub bots: Vec<Bot>,
     pub teams: Vec<RepoTeam>,
     pub members: Vec<RepoMember>,
@@ -182,15 +358,59 @@ pub struct Repo {
     // Is the GitHub "Auto-merge" option enabled?
     // https://docs.github.com/en/pull-requests/collaborating-with-pull-requests/incorporating-changes-from-a-pull-request/automatically-merging-a-pull-request
     pub auto_merge_enabled: bool,
+    // Which merge methods are allowed in the GitHub "Merge pull request" button,
+    // for the methods that are managed by sync-team?
+    // https://docs.github.com/en/repositories/configuring-branches-and-merges-in-your-repository/configuring-pull-request-merges/about-merge-methods-on-github
+    pub allow_squash_merge: Option<bool>,
+    pub allow_merge_commit: Option<bool>,
+    pub allow_rebase_merge: Option<bool>,
+    // Are head branches deleted automatically after a pull request is merged, if it is
+    // managed by sync-team?
+    pub delete_branch_on_merge: Option<bool>,
+    // The default branch of the repository, if it is managed by sync-team.
+    pub default_branch: Option<String>,
+    // Which repository features are enabled, for the features that are managed by sync-team?
+    pub has_issues: Option<bool>,
+    pub has_wiki: Option<bool>,
+    pub has_projects: Option<bool>,
+    pub has_discussions: Option<bool>,
+    // Are branch protections synced as repository rulesets instead of
+    // classic branch protections?
+    #[serde(default)]
+    pub use_rulesets: bool,
+    // Labels that are provisioned in the repository.
+    #[serde(default)]
+    pub labels: Vec<Label>,
+    // Webhooks that are provisioned in the repository.
+    #[serde(default)]
+    pub webhooks: Vec<Webhook>,
+    // Autolink references of the repository (key prefix -> URL template).
+    #[serde(default)]
+    pub autolinks: IndexMap<String, String>,
+    // Deploy keys that are allowed in the repository. If set, all other deploy keys
+    // are removed.
+    #[serde(default)]
+    pub deploy_keys: Option<Vec<DeployKey>>,
+    // GitHub Actions permissions of the repository, if they are managed by sync-team.
+    #[serde(default)]
+    pub actions: Option<ActionsPermissions>,
+    // Template repository (`owner/name`) used when the repository is created.
+    #[serde(default)]
+    pub template: Option<String>,
+    // Date (`YYYY-MM-DD`) on which the repository is archived by sync-team.
+    #[serde(default)]
+    pub archive_at: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 pub struct CrateTeamOwner {
     pub org: String,
     pub name: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 pub struct Crate {
     pub name: String,
     pub crates_io_publishing: Option<CratesIoPublishing>,
@@ -200,6 +420,7 @@ pub struct Crate {
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "kebab-case")]
 pub enum Bot {
     Bors,
@@ -215,37 +436,80 @@ pub enum Bot {
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 pub struct RepoTeam {
     pub name: String,
     pub permission: RepoPermission,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 pub struct RepoMember {
     pub name: String,
     pub permission: RepoPermission,
+    /// GitHub usernames the person used before, which GitHub may still report until the
+    /// account is renamed
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub former_names: Vec<String>,
 }
 
+/// Serialized as a string, with custom roles in the `custom:<name>` format
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
-#[serde(rename_all = "snake_case")]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[serde(into = "String", try_from = "String")]
 pub enum RepoPermission {
     Write,
     Admin,
     Maintain,
     Triage,
+    /// A custom repository role of the organization, referenced by its name
+    Custom(String),
+}
+
+impl From<RepoPermission> for String {
+    fn from(permission: RepoPermission) -> Self {
+        match permission {
+            RepoPermission::Write => "write".to_string(),
+            RepoPermission::Admin => "admin".to_string(),
+            RepoPermission::Maintain => "maintain".to_string(),
+            RepoPermission::Triage => "triage".to_string(),
+            RepoPermission::Custom(role) => format!("custom:{role}"),
+        }
+    }
+}
+
+impl TryFrom<String> for RepoPermission {
+    type Error = String;
+
+    fn try_from(permission: String) -> Result<Self, Self::Error> {
+        Ok(match permission.as_str() {
+            "write" => RepoPermission::Write,
+            "admin" => RepoPermission::Admin,
+            "maintain" => RepoPermission::Maintain,
+            "triage" => RepoPermission::Triage,
+            _ => match permission.strip_prefix("custom:") {
+                Some(role) if !role.is_empty() => RepoPermission::Custom(role.to_string()),
+                _ => return Err(format!("unknown repository permission `{permission}`")),
+            },
+        })
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "snake_case")]
 pub enum BranchProtectionMode {
     PrRequired {
         ci_checks: Vec<String>,
         required_approvals: u32,
+        #[serde(default)]
+        requires_code_owner_reviews: bool,
     },
     PrNotRequired,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "snake_case")]
 pub enum MergeBot {
     Homu,
@@ -268,6 +532,7 @@ impl MergeBot {
 }
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "snake_case")]
 pub enum ProtectionTarget {
     #[default]
@@ -276,6 +541,7 @@ pub enum ProtectionTarget {
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 pub struct BranchProtection {
     pub pattern: String,
     #[serde(default, skip_serializing_if = "is_branch_target")]
@@ -287,7 +553,21 @@ pub struct BranchProtection {
     pub allowed_merge_teams: Vec<String>,
     pub merge_bots: Vec<MergeBot>,
     pub allowed_merge_apps: Vec<MergeBot>,
+    /// Slugs of GitHub Apps that are allowed to push to the branch
+    #[serde(default)]
+    pub allowed_push_apps: Vec<String>,
+    /// Teams that are allowed to bypass the pull request requirement of the branch
+    #[serde(default)]
+    pub pr_bypass_teams: Vec<String>,
+    /// Bots that are allowed to bypass the pull request requirement of the branch
+    #[serde(default)]
+    pub pr_bypass_apps: Vec<MergeBot>,
     pub merge_queue: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub merge_queue_settings: Option<MergeQueueSettings>,
+    pub requires_signed_commits: bool,
+    pub required_linear_history: bool,
+    pub requires_conversation_resolution: bool,
     pub prevent_creation: bool,
     pub prevent_update: bool,
     pub prevent_deletion: bool,
@@ -295,33 +575,215 @@ pub struct BranchProtection {
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 pub struct CratesIoPublishing {
     pub workflow_file: String,
     pub environment: String,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 pub struct Environment {
     #[serde(default)]
     pub branches: Vec<String>,
     #[serde(default)]
     pub tags: Vec<String>,
+    /// GitHub teams that have to approve deployments to this environment
+    #[serde(default)]
+    pub reviewers: Vec<String>,
+    /// Minutes to wait before a deployment to this environment can proceed
+    #[serde(default)]
+    pub wait_timer: u32,
+    /// Only allow deployments from protected branches
+    #[serde(default)]
+    pub protected_branches_only: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+pub struct Label {
+    pub name: String,
+    /// Lowercase hexadecimal color code, without the leading `#`
+    pub color: String,
+    pub description: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+pub struct Webhook {
+    pub url: String,
+    pub events: Vec<String>,
+    pub content_type: WebhookContentType,
+    /// Name of the environment variable that contains the secret of the webhook
+    pub secret_env: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum WebhookContentType {
+    Json,
+    Form,
+}
+
+impl WebhookContentType {
+    /// The value of the `content_type` webhook option of the GitHub API
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WebhookContentType::Json => "json",
+            WebhookContentType::Form => "form",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+pub struct DeployKey {
+    pub title: String,
+    /// SHA256 fingerprint of the public key (`SHA256:<base64>`), as printed by `ssh-keygen -l`
+    pub fingerprint: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+pub struct ActionsPermissions {
+    /// Permissions of the `GITHUB_TOKEN` of workflows
+    pub default_workflow_permissions: WorkflowPermissions,
+    /// Can GitHub Actions approve pull requests?
+    pub can_approve_pull_requests: bool,
+    /// Which actions are allowed to run
+    pub allowed_actions: AllowedActions,
+    /// The actions that are allowed when `allowed_actions` is `Selected`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub selected_actions: Option<SelectedActions>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+pub struct SelectedActions {
+    /// Are the actions created by GitHub allowed?
+    pub github_owned_allowed: bool,
+    /// Are the actions of verified Marketplace creators allowed?
+    pub verified_allowed: bool,
+    /// Sorted patterns of the other allowed actions, such as `rust-lang/*` or
+    /// `docker/login-action@v3`
+    pub patterns_allowed: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum WorkflowPermissions {
+    Read,
+    Write,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum AllowedActions {
+    All,
+    LocalOnly,
+    Selected,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 pub struct Person {
     pub name: String,
+    /// Not set for people who disabled their email address
     pub email: Option<String>,
     pub github_id: u64,
     pub github_sponsors: bool,
+    /// Personal website of the person, an `https://` URL. Only set for people who have one.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub website: Option<String>,
+    /// Only set for people who chose to publish them
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub pronouns: Option<String>,
+    /// IANA name of the time zone of the person, like `Europe/Paris`. Only set for people who
+    /// chose to publish it.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub timezone: Option<String>,
+    /// The person is on leave until this date (`YYYY-MM-DD`), included. Past dates are not
+    /// published.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub on_leave_until: Option<String>,
+    /// GitHub usernames the person used before renaming their account
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub former_github_usernames: Vec<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 pub struct People {
     /// GitHub name as key.
     pub people: IndexMap<String, Person>,
 }
 
+/// Information about the build of the API, served at its root next to the `SHA256SUMS`
+/// manifest of all the files
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+pub struct BuildInfo {
+    /// Commit of the team repository the API was built from, if known
+    pub commit: Option<String>,
+    /// When the API was built (RFC 3339), if known. Reproducible builds use the time of the
+    /// commit.
+    pub timestamp: Option<String>,
+    /// Version of the generator of the API
+    pub generator_version: String,
+}
+
+/// Changes of team memberships between the published revisions of the data, oldest first.
+/// Only the last 5000 changes are kept.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+pub struct MembershipChanges {
+    pub changes: Vec<MembershipChange>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+pub struct MembershipChange {
+    /// When the change was published (`YYYY-MM-DD`)
+    pub date: String,
+    pub team: String,
+    pub name: String,
+    pub github: String,
+    pub github_id: u64,
+    pub kind: MembershipChangeKind,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum MembershipChangeKind {
+    Joined,
+    Left,
+}
+
+/// Custom configuration of a merge queue.
+/// Unset values use the sync-team defaults.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+pub struct MergeQueueSettings {
+    pub merge_method: MergeQueueMethod,
+    pub min_entries_to_merge: Option<u32>,
+    pub max_entries_to_merge: Option<u32>,
+    pub max_entries_to_build: Option<u32>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum MergeQueueMethod {
+    #[default]
+    Merge,
+    Squash,
+    Rebase,
+}
+
 fn is_branch_target(target: &ProtectionTarget) -> bool {
     matches!(target, ProtectionTarget::Branch)
 }
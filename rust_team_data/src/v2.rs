@@ -0,0 +1,50 @@
+//! Version 2 of the team API.
+//!
+//! v1 serves every repository in a single `repos.json`, which keeps growing, and serves each
+//! repository at `repos/<name>.json`, where repositories of different organizations with the
+//! same name overwrite each other. v2 splits the repositories by organization instead:
+//!
+//! - `v2/repos.json` lists the organizations that have repositories.
+//! - `v2/repos/<org>.json` contains the repositories of one organization.
+//!
+//! The other endpoints are only served by v1 for now.
+//!
+//! # Compatibility
+//!
+//! Within v2, endpoints and fields are never removed or renamed, and the type of a field never
+//! changes. New endpoints, fields and enum variants can be added, so clients must ignore unknown
+//! fields. Breaking changes require a new version, and the previous version keeps being
+//! generated until its consumers are migrated.
+
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+
+use crate::v1;
+
+pub static BASE_URL: &str = "https://team-api.infra.rust-lang.org/v2";
+
+/// The organizations that have repositories, each served at `repos/<org>.json`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+pub struct RepoOrgs {
+    pub orgs: Vec<String>,
+}
+
+/// The repositories of an organization
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+pub struct OrgRepos {
+    pub org: String,
+    /// Repository name as key.
+    pub repos: IndexMap<String, Repo>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+pub struct Repo {
+    /// GitHub ID of the repository, which is kept when the repository is renamed or transferred
+    /// to another organization. It is missing for repositories whose file doesn't record it.
+    pub id: Option<u64>,
+    #[serde(flatten)]
+    pub repo: v1::Repo,
+}
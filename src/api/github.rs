@@ -6,7 +6,7 @@ use reqwest::Method;
 use reqwest::header::{self, HeaderValue};
 use reqwest::{Client, ClientBuilder, RequestBuilder};
 use std::borrow::Cow;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 static API_BASE: &str = "https://api.github.com/";
 static TOKEN_VAR: &str = "GITHUB_TOKEN";
@@ -36,6 +36,12 @@ struct GraphNodes<T> {
     nodes: Vec<Option<T>>,
 }
 
+pub(crate) struct RepoBranches {
+    /// `None` for empty repositories
+    pub(crate) default_branch: Option<String>,
+    pub(crate) branches: Vec<String>,
+}
+
 pub(crate) struct GitHubApi {
     http: Client,
     token: Option<String>,
@@ -132,6 +138,115 @@ impl GitHubApi {
             .await
     }
 
+    /// The default branch of a repository and the names of all its branches
+    pub(crate) async fn branches(&self, org: &str, repo: &str) -> Result<RepoBranches, Error> {
+        #[derive(serde::Deserialize)]
+        struct Data {
+            repository: Repository,
+        }
+        #[derive(serde::Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct Repository {
+            default_branch_ref: Option<Ref>,
+            refs: Refs,
+        }
+        #[derive(serde::Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct Refs {
+            nodes: Vec<Ref>,
+            page_info: PageInfo,
+        }
+        #[derive(serde::Deserialize)]
+        struct Ref {
+            name: String,
+        }
+        #[derive(serde::Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct PageInfo {
+            has_next_page: bool,
+            end_cursor: Option<String>,
+        }
+        #[derive(serde::Serialize)]
+        struct Params<'a> {
+            org: &'a str,
+            repo: &'a str,
+            cursor: Option<String>,
+        }
+        static QUERY: &str = "
+            query($org: String!, $repo: String!, $cursor: String) {
+                repository(owner: $org, name: $repo) {
+                    defaultBranchRef {
+                        name
+                    }
+                    refs(refPrefix: \"refs/heads/\", first: 100, after: $cursor) {
+                        nodes {
+                            name
+                        }
+                        pageInfo {
+                            hasNextPage
+                            endCursor
+                        }
+                    }
+                }
+            }
+        ";
+
+        let mut result = RepoBranches {
+            default_branch: None,
+            branches: Vec::new(),
+        };
+        let mut cursor = None;
+        loop {
+            let data: Data = self.graphql(QUERY, Params { org, repo, cursor }).await?;
+            let repository = data.repository;
+            result.default_branch = repository.default_branch_ref.map(|r| r.name);
+            result
+                .branches
+                .extend(repository.refs.nodes.into_iter().map(|r| r.name));
+            if !repository.refs.page_info.has_next_page {
+                return Ok(result);
+            }
+            cursor = repository.refs.page_info.end_cursor;
+        }
+    }
+
+    /// Names of the check runs and commit statuses reported on the latest commit of a branch
+    pub(crate) async fn reported_checks(
+        &self,
+        org: &str,
+        repo: &str,
+        branch: &str,
+    ) -> Result<HashSet<String>, Error> {
+        #[derive(serde::Deserialize)]
+        struct CheckRuns {
+            check_runs: Vec<CheckRun>,
+        }
+        #[derive(serde::Deserialize)]
+        struct CheckRun {
+            name: String,
+        }
+        #[derive(serde::Deserialize)]
+        struct CombinedStatus {
+            statuses: Vec<Status>,
+        }
+        #[derive(serde::Deserialize)]
+        struct Status {
+            context: String,
+        }
+
+        let commit = format!("repos/{org}/{repo}/commits/{branch}");
+        let runs: CheckRuns = self
+            .get(&format!("{commit}/check-runs?per_page=100"))
+            .await?;
+        let status: CombinedStatus = self.get(&format!("{commit}/status?per_page=100")).await?;
+        Ok(runs
+            .check_runs
+            .into_iter()
+            .map(|run| run.name)
+            .chain(status.statuses.into_iter().map(|status| status.context))
+            .collect())
+    }
+
     pub(crate) async fn usernames(&self, ids: &[u64]) -> Result<HashMap<u64, String>, Error> {
         #[derive(serde::Deserialize)]
         #[serde(rename_all = "camelCase")]
@@ -120,6 +120,8 @@ pub(crate) struct ZulipUser {
     pub(crate) user_id: u64,
     #[serde(rename = "full_name")]
     pub(crate) name: String,
+    /// Deactivated users are still returned by the API
+    pub(crate) is_active: bool,
     #[serde(default)]
     pub(crate) profile_data: HashMap<String, ProfileValue>,
 }
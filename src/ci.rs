@@ -35,6 +35,9 @@ const PROTECTED_PATHS: &[&str] = &[
     "/repos/rust-lang/rust.toml",
     "/teams/infra-admins.toml",
     "/teams/team-repo-admins.toml",
+    // In case the teams are split across several files
+    "/teams/infra-admins/",
+    "/teams/team-repo-admins/",
 ];
 
 /// We want to allow access to the data files to `team-repo-admins`
@@ -75,8 +78,8 @@ fn generate_codeowners_content(data: Data) -> String {
         .access
         .individuals
         .iter()
-        .filter_map(|(user, permission)| match permission {
-            RepoPermission::Triage => None,
+        .filter_map(|(user, access)| match access.permission {
+            RepoPermission::Triage | RepoPermission::Custom(_) => None,
             RepoPermission::Write | RepoPermission::Maintain | RepoPermission::Admin => {
                 Some(user.as_str())
             }
@@ -88,7 +91,7 @@ fn generate_codeowners_content(data: Data) -> String {
             .teams
             .iter()
             .filter(|(_, permission)| match permission {
-                RepoPermission::Triage => false,
+                RepoPermission::Triage | RepoPermission::Custom(_) => false,
                 RepoPermission::Write | RepoPermission::Maintain | RepoPermission::Admin => true,
             })
             .flat_map(|(team, _)| {
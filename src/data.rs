@@ -1,19 +1,36 @@
-use crate::schema::{Config, List, Person, Repo, Team, ZulipGroup, ZulipStream};
+use crate::schema::{
+    Config, List, NamingConventions, Org, Person, Policies, Policy, PrivilegedAccessLimits, Repo,
+    Team, TeamKind, ZulipGroup, ZulipStream,
+};
 use crate::sync;
 use anyhow::{Context as _, Error, bail};
 use serde::de::DeserializeOwned;
 use std::collections::{HashMap, HashSet};
 use std::ffi::OsStr;
-use std::path::Path;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use toml::Spanned;
+use toml::de::{DeTable, DeValue};
+
+mod sources;
+
+use sources::Sources;
 
 #[derive(Debug)]
 pub(crate) struct Data {
     people: HashMap<String, Person>,
     teams: HashMap<String, Team>,
+    /// The files the people, teams and repos were loaded from, to point at them in validation
+    /// errors
+    people_paths: HashMap<String, PathBuf>,
+    team_paths: HashMap<String, PathBuf>,
+    repo_paths: HashMap<(String, String), PathBuf>,
     archived_teams: Vec<Team>,
     repos: Vec<Repo>,
     archived_repos: Vec<Repo>,
+    orgs: Vec<Org>,
     config: Config,
+    policies: Policies,
 }
 
 impl Data {
@@ -21,12 +38,22 @@ impl Data {
         let mut data = Data {
             people: HashMap::new(),
             teams: HashMap::new(),
+            people_paths: HashMap::new(),
+            team_paths: HashMap::new(),
+            repo_paths: HashMap::new(),
             archived_teams: Vec::new(),
             repos: Vec::new(),
             archived_repos: Vec::new(),
+            orgs: Vec::new(),
             config: load_file(Path::new("config.toml"))?,
+            policies: Policies::default(),
         };
 
+        let policies_path = directory.join("config").join("policies.toml");
+        if policies_path.is_file() {
+            data.policies = load_file(&policies_path)?;
+        }
+
         fn validate_repo(org: &str, repo: &Repo, path: &Path) -> anyhow::Result<()> {
             if repo.org != org {
                 bail!(
@@ -46,54 +73,110 @@ impl Data {
             Ok(())
         }
 
-        data.load_dir(directory.join("repos"), true, |this, org, repo: Repo, path: &Path| {
+        data.load_dir(directory, directory.join("repos"), true, |this, org, repo: Repo, path: &Path| {
             if org == "archive" {
                 bail!("repo '{}' is located in the 'archive/' directory. Move it into the org subdirectory, e.g. 'archive/rust-lang/'", repo.name);
             }
 
             validate_repo(org, &repo, path)?;
+            this.repo_paths
+                .insert((repo.org.clone(), repo.name.clone()), path.to_owned());
             this.repos.push(repo);
             Ok(())
         })?;
 
         let archive_path = directory.join("repos").join("archive");
         if archive_path.is_dir() {
-            data.load_dir(archive_path, true, |this, org, repo: Repo, path: &Path| {
-                validate_repo(org, &repo, path)?;
-                this.archived_repos.push(repo);
-                Ok(())
-            })?;
+            data.load_dir(
+                directory,
+                archive_path,
+                true,
+                |this, org, repo: Repo, path: &Path| {
+                    validate_repo(org, &repo, path)?;
+                    this.archived_repos.push(repo);
+                    Ok(())
+                },
+            )?;
+        }
+
+        let orgs_path = directory.join("orgs");
+        if orgs_path.is_dir() {
+            data.load_dir(
+                directory,
+                orgs_path,
+                false,
+                |this, _dir, org: Org, path: &Path| {
+                    if org.name != path.file_stem().unwrap().to_str().unwrap() {
+                        bail!(
+                            "org '{}' is located in file '{}', please ensure that the name matches",
+                            org.name,
+                            path.file_name().unwrap().to_str().unwrap()
+                        )
+                    }
+                    this.orgs.push(org);
+                    Ok(())
+                },
+            )?;
         }
 
         data.load_dir(
+            directory,
             directory.join("people"),
             false,
-            |this, _dir, person: Person, _path| {
+            |this, _dir, person: Person, path| {
                 person.validate()?;
+                if let Some(other) = this.people_paths.get(person.github()) {
+                    bail!(
+                        "person `{}` is defined in both {} and {}",
+                        person.github(),
+                        other.display(),
+                        path.display()
+                    );
+                }
+                this.people_paths
+                    .insert(person.github().to_string(), path.to_owned());
                 this.people.insert(person.github().to_string(), person);
                 Ok(())
             },
         )?;
 
         let teams_dir = directory.join("teams");
-        data.load_dir(&teams_dir, false, |this, _dir, team: Team, _path| {
+        data.load_team_dir(directory, &teams_dir, |this, team, path| {
+            if let Some(other) = this.team_paths.get(team.name()) {
+                bail!(
+                    "team `{}` is defined in both {} and {}",
+                    team.name(),
+                    other.display(),
+                    path.display()
+                );
+            }
+            this.team_paths
+                .insert(team.name().to_string(), path.to_owned());
             this.teams.insert(team.name().to_string(), team);
             Ok(())
         })?;
 
-        data.load_dir(
-            teams_dir.join("archive"),
-            false,
-            |this, _dir, team: Team, _path| {
+        data.load_team_dir(
+            directory,
+            &teams_dir.join("archive"),
+            |this, team, _path| {
                 this.archived_teams.push(team);
                 Ok(())
             },
         )?;
 
+        data.check_included_teams()?;
+
         Ok(data)
     }
 
-    fn load_dir<P, T, F>(&mut self, dir: P, nested: bool, f: F) -> Result<(), Error>
+    fn load_dir<P, T, F>(
+        &mut self,
+        directory: &Path,
+        dir: P,
+        nested: bool,
+        f: F,
+    ) -> Result<(), Error>
     where
         P: AsRef<Path>,
         T: DeserializeOwned,
@@ -106,15 +189,87 @@ impl Data {
         })? {
             let path = entry?.path();
             if nested && path.is_dir() {
-                self.load_dir(&path, false, f.clone())?;
+                self.load_dir(directory, &path, false, f.clone())?;
             } else if !nested && path.is_file() && path.extension() == Some(OsStr::new("toml")) {
                 fn dir(path: &Path) -> Option<&str> {
                     path.parent()?.file_name()?.to_str()
                 }
-                f(self, dir(&path).unwrap(), load_file(&path)?, &path)?;
+                f(
+                    self,
+                    dir(&path).unwrap(),
+                    load_data_file(directory, &path)?,
+                    &path,
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Load the teams of a directory. A team is either defined in a `<name>.toml` file, or split
+    /// across the TOML files of a `<name>/` directory, to avoid conflicts in the biggest teams.
+    fn load_team_dir<F>(&mut self, directory: &Path, dir: &Path, f: F) -> Result<(), Error>
+    where
+        F: Fn(&mut Self, Team, &Path) -> Result<(), Error>,
+    {
+        for entry in std::fs::read_dir(dir)
+            .with_context(|| format!("failed to read directory '{}'", dir.display()))?
+        {
+            let path = entry?.path();
+            if path.is_dir() {
+                // Archived teams are loaded separately
+                if path.file_name() != Some(OsStr::new("archive")) {
+                    f(self, load_fragments(directory, &path)?, &path)?;
+                }
+            } else if path.extension() == Some(OsStr::new("toml")) {
+                f(self, load_data_file(directory, &path)?, &path)?;
             }
         }
+        Ok(())
+    }
 
+    /// Ensure that the members of every team can be computed, as `Team::members` would recurse
+    /// forever if teams included each other.
+    fn check_included_teams(&self) -> Result<(), Error> {
+        fn visit<'a>(data: &'a Data, team: &'a Team, path: &mut Vec<&'a str>) -> Result<(), Error> {
+            if let Some(start) = path.iter().position(|name| *name == team.name()) {
+                bail!(
+                    "teams include the members of each other: {} -> {}",
+                    path[start..].join(" -> "),
+                    team.name()
+                );
+            }
+            path.push(team.name());
+            let people = team.raw_people();
+            for included in &people.included_teams {
+                let Some(included) = data.team(included) else {
+                    bail!(
+                        "team `{}` includes the members of non-existent team `{included}`",
+                        team.name()
+                    );
+                };
+                visit(data, included, path)?;
+            }
+            if people.include_all_team_members {
+                for included in data.teams() {
+                    if included.kind() == TeamKind::Team
+                        && included.name() != team.name()
+                        && !included.is_alumni_team()
+                    {
+                        visit(data, included, path)?;
+                    }
+                }
+            }
+            path.pop();
+            Ok(())
+        }
+
+        // Sorted so that the same cycle is always reported
+        let mut teams = self.teams.values().collect::<Vec<_>>();
+        teams.sort_by_key(|team| team.name());
+        for team in teams {
+            visit(self, team, &mut Vec::new())?;
+        }
         Ok(())
     }
 
@@ -122,6 +277,18 @@ impl Data {
         &self.config
     }
 
+    pub(crate) fn policies(&self) -> &[Policy] {
+        &self.policies.policies
+    }
+
+    pub(crate) fn naming_conventions(&self) -> &NamingConventions {
+        &self.policies.naming
+    }
+
+    pub(crate) fn privileged_access_limits(&self) -> Option<&PrivilegedAccessLimits> {
+        self.policies.privileged_access.as_ref()
+    }
+
     pub(crate) fn lists(&self) -> Result<HashMap<String, List>, Error> {
         let mut lists = HashMap::new();
         for team in self.teams.values() {
@@ -183,6 +350,23 @@ impl Data {
         self.people.values()
     }
 
+    /// The file a person was loaded from
+    pub(crate) fn person_path(&self, name: &str) -> Option<&Path> {
+        self.people_paths.get(name).map(|path| path.as_path())
+    }
+
+    /// The file a team was loaded from
+    pub(crate) fn team_path(&self, name: &str) -> Option<&Path> {
+        self.team_paths.get(name).map(|path| path.as_path())
+    }
+
+    /// The file a repo, which isn't archived, was loaded from
+    pub(crate) fn repo_path(&self, org: &str, name: &str) -> Option<&Path> {
+        self.repo_paths
+            .get(&(org.to_owned(), name.to_owned()))
+            .map(|path| path.as_path())
+    }
+
     pub(crate) fn active_members(&self) -> Result<HashSet<&str>, Error> {
         let mut active = HashSet::new();
         for team in self.teams.values().filter(|team| !team.is_alumni_team()) {
@@ -195,6 +379,10 @@ impl Data {
         self.repos.iter()
     }
 
+    pub(crate) fn orgs(&self) -> impl Iterator<Item = &Org> {
+        self.orgs.iter()
+    }
+
     pub(crate) fn archived_repos(&self) -> impl Iterator<Item = &Repo> {
         self.archived_repos.iter()
     }
@@ -242,15 +430,216 @@ impl Data {
         Ok(sync::Config {
             special_org_members,
             independent_github_orgs: self.config.independent_github_orgs().clone(),
-            enable_rulesets_repos: self.config.enable_rulesets_repos().clone(),
+            unmanaged_team_deletion_orgs: self.config.unmanaged_team_deletion_orgs().clone(),
+            preserved_github_teams: self.config.preserved_github_teams().clone(),
+            allow_owner_demotions: false,
+            github_cache_dir: None,
+            github_concurrency: None,
+            github_actions_integration_id: None,
+            github_max_deletions: None,
+            force: false,
+            interactive: false,
+            continue_on_error: false,
+            github_audit_log: None,
+            github_notification_stream: None,
+            colored_output: false,
+            email_backend: Default::default(),
+            output_format: Default::default(),
+            github_filter: Default::default(),
+            github_state_source: None,
+            github_state_export: None,
+            github_plan_out: None,
+            github_plan: None,
         })
     }
 }
 
+/// Load the TOML files of a directory as a single document. Tables present in several files are
+/// merged, but any other value can only be defined in one of them.
+fn load_fragments<T: DeserializeOwned>(directory: &Path, dir: &Path) -> Result<T, Error> {
+    let mut paths = Vec::new();
+    for entry in std::fs::read_dir(dir)
+        .with_context(|| format!("failed to read directory '{}'", dir.display()))?
+    {
+        let path = entry?.path();
+        if path.is_file() && path.extension() == Some(OsStr::new("toml")) {
+            paths.push(path);
+        }
+    }
+    if paths.is_empty() {
+        bail!(
+            "directory '{}' doesn't contain any TOML file",
+            dir.display()
+        );
+    }
+    // Conflicts are reported in a stable order
+    paths.sort();
+
+    let mut sources = Sources::default();
+    let files = paths
+        .iter()
+        .map(|path| sources.read(path))
+        .collect::<Result<Vec<_>, _>>()?;
+    let templates = read_templates(directory, &mut sources, &files)?;
+
+    let mut merged = sources.parse(files[0])?;
+    for file in &files[1..] {
+        let table = sources.parse(*file)?.into_inner();
+        merge_tables(&sources, merged.get_mut(), table, "")?;
+    }
+    sources.deserialize(apply_templates(&sources, &templates, merged)?)
+}
+
+fn merge_tables<'a>(
+    sources: &Sources,
+    into: &mut DeTable<'a>,
+    from: DeTable<'a>,
+    prefix: &str,
+) -> Result<(), Error> {
+    for (key, value) in from {
+        let full_key = if prefix.is_empty() {
+            key.get_ref().to_string()
+        } else {
+            format!("{prefix}.{}", key.get_ref())
+        };
+        let span = value.span();
+        match (
+            into.get_mut(key.get_ref().as_ref()).map(Spanned::get_mut),
+            value.into_inner(),
+        ) {
+            (Some(DeValue::Table(existing)), DeValue::Table(value)) => {
+                merge_tables(sources, existing, value, &full_key)?;
+            }
+            (Some(_), _) => {
+                return Err(sources.error(
+                    Some(key.span()),
+                    &format!("`{full_key}` is already defined in another file"),
+                ));
+            }
+            (None, value) => {
+                into.insert(key, Spanned::new(span, value));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Load a file of the data, applying it over the template it `extends`, if any
+fn load_data_file<T: DeserializeOwned>(directory: &Path, path: &Path) -> Result<T, Error> {
+    let mut sources = Sources::default();
+    let file = sources.read(path)?;
+    let templates = read_templates(directory, &mut sources, &[file])?;
+    sources.deserialize(apply_templates(&sources, &templates, sources.parse(file)?)?)
+}
+
+/// Read the templates extended by the files of a document, from the template it extends
+/// directly to the one that doesn't extend any other template.
+fn read_templates(
+    directory: &Path,
+    sources: &mut Sources,
+    files: &[usize],
+) -> Result<Vec<usize>, Error> {
+    let mut templates = Vec::new();
+    let mut extending = files.to_vec();
+    loop {
+        let mut extended = None;
+        for file in &extending {
+            if let Some(template) = extends(directory, sources, *file)? {
+                extended = Some((*file, template));
+            }
+        }
+        let Some((file, (path, span))) = extended else {
+            return Ok(templates);
+        };
+        let mut read = files.iter().chain(&templates);
+        if read.any(|file| sources.path(*file) == path) {
+            return Err(sources.error(
+                Some(span),
+                &format!(
+                    "template {} extends itself through {}",
+                    path.display(),
+                    sources.path(file).display()
+                ),
+            ));
+        }
+        let template = sources.read(&path)?;
+        templates.push(template);
+        extending = vec![template];
+    }
+}
+
+/// The path of the template named by the `extends` key of a file, and the span of the key
+fn extends(
+    directory: &Path,
+    sources: &Sources,
+    file: usize,
+) -> Result<Option<(PathBuf, Range<usize>)>, Error> {
+    let table = sources.parse(file)?;
+    let Some(extends) = table.get_ref().get("extends") else {
+        return Ok(None);
+    };
+    let DeValue::String(name) = extends.get_ref() else {
+        return Err(sources.error(Some(extends.span()), "`extends` must be a string"));
+    };
+    if !name.starts_with("templates/") || name.split('/').any(|part| part == "..") {
+        return Err(sources.error(
+            Some(extends.span()),
+            &format!(
+                "`extends` must name a file of the templates directory, like \
+                 `templates/default-repo`, not `{name}`"
+            ),
+        ));
+    }
+    Ok(Some((
+        directory.join(format!("{name}.toml")),
+        extends.span(),
+    )))
+}
+
+/// Apply a document over the templates it extends, given from the one it extends directly.
+/// Missing keys are still reported in the document.
+fn apply_templates<'a>(
+    sources: &'a Sources,
+    templates: &[usize],
+    document: Spanned<DeTable<'a>>,
+) -> Result<Spanned<DeTable<'a>>, Error> {
+    let span = document.span();
+    let mut document = document.into_inner();
+    document.remove("extends");
+    for template in templates {
+        let mut template = sources.parse(*template)?.into_inner();
+        template.remove("extends");
+        apply_template(&mut template, document);
+        document = template;
+    }
+    Ok(Spanned::new(span, document))
+}
+
+/// Apply the values of a document over those of its template. Tables are merged, while any
+/// other value replaces the one of the template.
+fn apply_template<'a>(template: &mut DeTable<'a>, table: DeTable<'a>) {
+    for (key, value) in table {
+        let span = value.span();
+        match (
+            template
+                .get_mut(key.get_ref().as_ref())
+                .map(Spanned::get_mut),
+            value.into_inner(),
+        ) {
+            (Some(DeValue::Table(existing)), DeValue::Table(value)) => {
+                apply_template(existing, value);
+            }
+            (_, value) => {
+                // Replace the key too, for errors to point at the document
+                template.remove(key.get_ref().as_ref());
+                template.insert(key, Spanned::new(span, value));
+            }
+        }
+    }
+}
+
 fn load_file<T: DeserializeOwned>(path: &Path) -> Result<T, Error> {
-    let content = std::fs::read_to_string(path)
-        .with_context(|| format!("failed to read {}", path.display()))?;
-    let parsed =
-        toml::from_str(&content).with_context(|| format!("failed to parse {}", path.display()))?;
-    Ok(parsed)
+    let mut sources = Sources::default();
+    let file = sources.read(path)?;
+    sources.deserialize(sources.parse(file)?)
 }
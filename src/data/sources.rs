@@ -0,0 +1,179 @@
+//! The files a document of the data is made of: a single file, the files of a team directory,
+//! and the templates they extend.
+//!
+//! The files are parsed with their spans, which stay valid while the documents are merged, so
+//! that errors point at the file, line and column they come from, like the errors of rustc.
+
+use anyhow::{Context as _, Error, format_err};
+use serde::de::DeserializeOwned;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use toml::Spanned;
+use toml::de::{DeTable, DeValue};
+
+struct SourceFile {
+    path: PathBuf,
+    content: String,
+    /// Where the file starts in the spans of the documents, which are shifted so that the spans
+    /// of the different files never overlap
+    offset: usize,
+}
+
+#[derive(Default)]
+pub(super) struct Sources {
+    files: Vec<SourceFile>,
+}
+
+impl Sources {
+    /// Read a file, returning its index in the sources
+    pub(super) fn read(&mut self, path: &Path) -> Result<usize, Error> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("failed to read {}", path.display()))?;
+        let offset = self
+            .files
+            .last()
+            .map_or(0, |file| file.offset + file.content.len() + 1);
+        self.files.push(SourceFile {
+            path: path.to_owned(),
+            content,
+            offset,
+        });
+        Ok(self.files.len() - 1)
+    }
+
+    pub(super) fn path(&self, index: usize) -> &Path {
+        &self.files[index].path
+    }
+
+    /// Parse a file that was read. The root table spans the whole file, so that missing keys are
+    /// reported at its start.
+    pub(super) fn parse(&self, index: usize) -> Result<Spanned<DeTable<'_>>, Error> {
+        let file = &self.files[index];
+        let table = DeTable::parse(&file.content)
+            .map_err(|err| {
+                self.error(
+                    err.span().map(|span| shift(span, file.offset)),
+                    err.message(),
+                )
+            })?
+            .into_inner();
+        let span = file.offset..file.offset + file.content.len();
+        Ok(Spanned::new(span, shift_table(table, file.offset)))
+    }
+
+    pub(super) fn deserialize<T: DeserializeOwned>(
+        &self,
+        document: Spanned<DeTable<'_>>,
+    ) -> Result<T, Error> {
+        T::deserialize(toml::de::Deserializer::from(document))
+            .map_err(|err| self.error(err.span(), err.message()))
+    }
+
+    /// An error pointing at the given span of the sources, like:
+    ///
+    /// ```text
+    /// missing field `name`
+    ///  --> teams/foo.toml:1:1
+    ///   |
+    /// 1 | kind = "working-group"
+    ///   | ^^^^^^^^^^^^^^^^^^^^^^
+    /// ```
+    pub(super) fn error(&self, span: Option<Range<usize>>, message: &str) -> Error {
+        let Some(span) = span else {
+            let paths = self
+                .files
+                .iter()
+                .map(|file| file.path.display().to_string());
+            return format_err!("{message}\n --> {}", paths.collect::<Vec<_>>().join(", "));
+        };
+        let Some(file) = self
+            .files
+            .iter()
+            .find(|file| (file.offset..=file.offset + file.content.len()).contains(&span.start))
+        else {
+            return format_err!("{message}");
+        };
+
+        let start = span.start - file.offset;
+        let line_start = file.content[..start].rfind('\n').map_or(0, |pos| pos + 1);
+        let line_end = file.content[start..]
+            .find('\n')
+            .map_or(file.content.len(), |pos| start + pos);
+        let line = file.content[..start].matches('\n').count() + 1;
+        let column = file.content[line_start..start].chars().count() + 1;
+        let end = (span.end - file.offset).clamp(start, line_end);
+        let text = file.content[line_start..line_end].trim_end_matches('\r');
+
+        let gutter = " ".repeat(line.to_string().len());
+        format_err!(
+            "{message}\n{gutter}--> {}:{line}:{column}\n{gutter} |\n{line} | {text}\n{gutter} | {}{}",
+            file.path.display(),
+            " ".repeat(column - 1),
+            "^".repeat(file.content[start..end].chars().count().max(1)),
+        )
+    }
+}
+
+fn shift(span: Range<usize>, offset: usize) -> Range<usize> {
+    span.start + offset..span.end + offset
+}
+
+fn shift_table(table: DeTable<'_>, offset: usize) -> DeTable<'_> {
+    table
+        .into_iter()
+        .map(|(key, value)| {
+            let key = Spanned::new(shift(key.span(), offset), key.into_inner());
+            (key, shift_value(value, offset))
+        })
+        .collect()
+}
+
+fn shift_value(value: Spanned<DeValue<'_>>, offset: usize) -> Spanned<DeValue<'_>> {
+    let span = shift(value.span(), offset);
+    let value = match value.into_inner() {
+        DeValue::Array(array) => DeValue::Array(
+            array
+                .into_iter()
+                .map(|value| shift_value(value, offset))
+                .collect(),
+        ),
+        DeValue::Table(table) => DeValue::Table(shift_table(table, offset)),
+        value => value,
+    };
+    Spanned::new(span, value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Sources;
+
+    #[derive(serde::Deserialize)]
+    struct Document {
+        #[allow(dead_code)]
+        name: String,
+    }
+
+    #[test]
+    fn errors_point_at_their_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let first = dir.path().join("first.toml");
+        let second = dir.path().join("second.toml");
+        std::fs::write(&first, "name = \"first\"\n").unwrap();
+        std::fs::write(&second, "# The second file\n\nname = 2\n").unwrap();
+
+        let mut sources = Sources::default();
+        sources.read(&first).unwrap();
+        let index = sources.read(&second).unwrap();
+        let document = sources.parse(index).unwrap();
+        let Err(err) = sources.deserialize::<Document>(document) else {
+            panic!("`name` is not a string");
+        };
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "invalid type: integer `2`, expected a string\n --> {}:3:8\n  |\n3 | name = 2\n  |        ^",
+                second.display()
+            )
+        );
+    }
+}
@@ -8,26 +8,34 @@ mod ci;
 mod schema;
 mod static_api;
 mod sync;
+mod team_file;
 mod validate;
 
 const AVAILABLE_SERVICES: &[&str] = &["github", "mailgun", "zulip", "crates-io"];
 
 const USER_AGENT: &str = "https://github.com/rust-lang/team (infra@rust-lang.org)";
 
+/// Exit code of `sync --detailed-exit-code` when there are changes. Code 2 is not used,
+/// because it is the exit code of invalid command line arguments.
+const EXIT_CODE_CHANGES: i32 = 3;
+
 use api::zulip::ZulipApi;
 use data::Data;
-use schema::{Email, Team, TeamKind};
+use schema::{Email, Team, TeamKind, TeamMember};
 
 use crate::ci::{check_codeowners, generate_codeowners_file};
 use crate::schema::RepoPermission;
-use crate::sync::run_sync_team;
 use crate::sync::team_api::TeamApi;
+use crate::sync::{
+    ColorChoice, EmailBackend, GitHubFilter, OutputFormat, diff_github_revisions,
+    report_email_suppressions, rollback_github, run_sync_team,
+};
 use anyhow::{Context, Error, bail, format_err};
 use api::github;
 use clap::Parser;
 use log::{error, info, warn};
-use std::collections::{BTreeMap, HashMap};
-use std::path::PathBuf;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
 
 #[derive(clap::ValueEnum, Clone, Debug)]
@@ -52,9 +60,15 @@ struct Cli {
 enum RootOpts {
     /// Check if the configuration is correct
     Check {
-        /// Fail if optional checks are not executed
+        /// Fail if the checks enabled by `--online` can't be executed
         #[arg(long)]
         strict: bool,
+        /// Also run the checks relying on the GitHub and Zulip APIs
+        #[arg(long)]
+        online: bool,
+        /// Update the data of GitHub users who changed their username
+        #[arg(long, requires = "online")]
+        fix: bool,
         /// Skip one or more validation steps
         #[arg(long, num_args = 1..)]
         skip: Vec<String>,
@@ -65,9 +79,45 @@ enum RootOpts {
         /// Try to fetch the Zulip ID of the user, based on their GitHub handle or e-mail.
         #[arg(long)]
         fetch_zulip_id: bool,
+        /// Also add the person to the members of a team
+        #[arg(long)]
+        team: Option<String>,
+    },
+    /// Move a person to the alumni of all the teams they are a member of
+    RetirePerson { github_name: String },
+    /// Update the data of a person who changed their GitHub username
+    RenameGithubUser { old_name: String, new_name: String },
+    /// Create the file of a new team, with a GitHub team, a Zulip group and a mailing list.
+    /// Its kind is inferred from the prefix of its name, like `wg-` for working groups.
+    CreateTeam {
+        name: String,
+        /// The team it is a subteam of
+        #[arg(long)]
+        parent: String,
+        /// The first lead and member of the team
+        #[arg(long)]
+        lead: String,
+        /// What the team does, shown on the website
+        #[arg(long)]
+        description: String,
     },
     /// Generate the static API
-    StaticApi { dest: String },
+    StaticApi {
+        dest: String,
+        /// A previous build of the static API. The membership changes since
+        /// that build are appended to its `v1/changes.json` feed.
+        #[arg(long)]
+        previous: Option<PathBuf>,
+        /// Commit of the team repository recorded in `build-info.json`.
+        /// The build time is read from `SOURCE_DATE_EPOCH`, and the manifest
+        /// is signed with the Ed25519 PEM key in `STATIC_API_SIGNING_KEY`.
+        #[arg(long)]
+        source_commit: Option<String>,
+        /// Also write gzip and brotli copies of every endpoint, for the CDN to
+        /// serve precompressed.
+        #[arg(long)]
+        compress: bool,
+    },
     /// Print information about a person
     ShowPerson { github_username: String },
     /// List all teams
@@ -113,12 +163,17 @@ enum RootOpts {
     ///
     /// Environment variables:
     /// - GITHUB_TOKEN          Authentication token with GitHub
+    /// - GITHUB_APP_ID         ID of the GitHub App to authenticate as (instead of GITHUB_TOKEN)
+    /// - GITHUB_APP_PRIVATE_KEY  PEM encoded private key of the GitHub App
+    /// - GITHUB_API_URL        Base URL of the GitHub REST API (default https://api.github.com)
+    /// - GITHUB_GRAPHQL_URL    URL of the GitHub GraphQL API (default https://api.github.com/graphql)
+    /// - GITHUB_ACTIONS_INTEGRATION_ID  ID of the GitHub Actions app (on GitHub Enterprise Server)
     /// - MAILGUN_API_TOKEN     Authentication token with Mailgun
     /// - EMAIL_ENCRYPTION_KEY  Key used to decrypt encrypted emails in the team repo
     /// - ZULIP_USERNAME        Username of the Zulip bot
     /// - ZULIP_API_TOKEN       Authentication token of the Zulip bot
     #[clap(verbatim_doc_comment)]
-    Sync(SyncOpts),
+    Sync(Box<SyncOpts>),
 }
 
 #[derive(clap::Parser, Clone, Debug)]
@@ -152,6 +207,71 @@ struct SyncOpts {
     )]
     source: DataSource,
 
+    /// Allow demoting GitHub organization owners that are not members of
+    /// an org owner team.
+    #[clap(long, global(true))]
+    allow_owner_demotions: bool,
+
+    /// Cache GitHub API responses in this directory, and only refetch
+    /// the ones that changed since the previous run. Only allowed in dry runs,
+    /// as GraphQL responses are reused for a few minutes.
+    #[clap(long, global(true))]
+    github_cache_dir: Option<PathBuf>,
+
+    /// Maximum number of GitHub teams or repositories diffed or applied concurrently.
+    #[clap(long, global(true), value_parser = clap::value_parser!(u16).range(1..))]
+    github_concurrency: Option<u16>,
+
+    /// Only synchronize these GitHub organizations.
+    #[clap(long = "org", global(true), value_delimiter = ',')]
+    orgs: Vec<String>,
+
+    /// Only synchronize these GitHub repositories (`repo` or `org/repo`).
+    /// Organization-wide settings, members and owners are skipped.
+    #[clap(long = "repo", global(true), value_delimiter = ',')]
+    repos: Vec<String>,
+
+    /// Only synchronize these GitHub teams.
+    /// Organization-wide settings, members and owners are skipped.
+    #[clap(long = "team", global(true), value_delimiter = ',')]
+    teams: Vec<String>,
+
+    /// Abort the GitHub sync if it would delete more than this many team members,
+    /// teams, repository permissions or branch protections, and the Zulip sync if it
+    /// would remove more than this many members from the streams of sensitive teams.
+    /// Defaults to 25.
+    #[clap(long, global(true))]
+    max_deletions: Option<usize>,
+
+    /// Apply the diffs even if they exceed `--max-deletions`.
+    #[clap(long, global(true))]
+    force: bool,
+
+    /// Diff GitHub against a state file written by `export-state`,
+    /// instead of the live GitHub API.
+    #[clap(long, global(true))]
+    from_state: Option<PathBuf>,
+
+    /// Exit with code 3 instead of 0 when there are changes to apply, or changes
+    /// were applied. Errors always exit with code 1.
+    #[clap(long, global(true))]
+    detailed_exit_code: bool,
+
+    /// Format of the diffs. With `json`, the GitHub and Zulip diffs are printed to
+    /// stdout, and with `markdown`, the GitHub diff is. The diffs of the other
+    /// services are still logged as text.
+    #[clap(long, global(true), value_enum, default_value_t)]
+    format: OutputFormat,
+
+    /// Color the text GitHub diff: additions in green, modifications in yellow
+    /// and deletions in red.
+    #[clap(long, global(true), value_enum, default_value_t)]
+    color: ColorChoice,
+
+    /// Provider hosting the mailing lists synchronized by the `mailgun` service.
+    #[clap(long, global(true), value_enum, default_value_t)]
+    email_backend: EmailBackend,
+
     /// Command that should be performed.
     #[clap(subcommand)]
     command: Option<SyncCommand>,
@@ -195,11 +315,77 @@ impl FromStr for DataSource {
 #[derive(clap::Parser, Clone, Debug)]
 enum SyncCommand {
     /// Try to apply changes, but do not send any outgoing API requests.
-    DryRun,
+    DryRun {
+        /// Save the GitHub diff to this file, to apply it with `apply --plan`
+        #[clap(long)]
+        save_plan: Option<PathBuf>,
+    },
     /// Only print a diff of what would be changed.
-    PrintPlan,
+    PrintPlan {
+        /// Save the GitHub diff to this file, to apply it with `apply --plan`
+        #[clap(long)]
+        save_plan: Option<PathBuf>,
+    },
     /// Apply the changes to the specified services.
-    Apply,
+    Apply {
+        /// Only apply the GitHub changes if they are still the ones saved to this
+        /// file by `--save-plan`
+        #[clap(long)]
+        plan: Option<PathBuf>,
+        /// Show every GitHub change and ask whether to apply it
+        #[clap(long)]
+        interactive: bool,
+        /// Keep applying the remaining GitHub changes when one of them fails,
+        /// and report all the failures at the end
+        #[clap(long)]
+        continue_on_error: bool,
+        /// Append a JSON record of every applied GitHub change to this file
+        #[clap(long)]
+        audit_log: Option<PathBuf>,
+        /// Post a message to this Zulip stream when the applied GitHub changes delete
+        /// teams, organization members or branch protections
+        #[clap(long)]
+        notify_zulip_stream: Option<String>,
+    },
+    /// Undo the GitHub changes recorded in an audit log by `apply --audit-log`.
+    /// Only team members and settings, repository permissions, branch protections,
+    /// organization settings and owners can be restored.
+    Rollback {
+        /// Audit log of the run to undo
+        #[clap(long)]
+        audit_log: PathBuf,
+        /// Run to undo, as recorded in the `run` field of the audit log records.
+        /// Defaults to the last run of the audit log.
+        #[clap(long)]
+        run: Option<String>,
+        /// Only print the changes that would be undone
+        #[clap(long)]
+        dry_run: bool,
+    },
+    /// Print the changes of GitHub team memberships and repository permissions
+    /// between two revisions of the team data, without contacting GitHub.
+    /// Each revision is either a checkout of the team repository or a directory
+    /// rendered by `static-api`.
+    DiffRevisions {
+        /// The previous revision
+        #[clap(long)]
+        old: PathBuf,
+        /// The new revision
+        #[clap(long)]
+        new: PathBuf,
+    },
+    /// Print the people whose email address is on a suppression list of the
+    /// email provider (bounces, complaints and unsubscriptions), and who thus
+    /// silently don't receive the emails of their mailing lists anymore.
+    /// Mailgun suppressions are read for the domains of the mailing lists.
+    EmailSuppressions,
+    /// Print a diff of the GitHub service, and write everything that was
+    /// read from GitHub to a file that can be diffed against with `--from-state`.
+    ExportState {
+        /// File where the GitHub state is written
+        #[clap(long)]
+        out: PathBuf,
+    },
 }
 
 fn main() {
@@ -225,10 +411,22 @@ async fn run() -> Result<(), Error> {
     let cli = Cli::parse();
     let data = Data::load(&cli.data_dir)?;
     match cli.opts {
-        RootOpts::Check { strict, skip } => {
+        RootOpts::Check {
+            strict,
+            online,
+            fix,
+            skip,
+        } => {
+            let data = if fix {
+                crate::validate::fix_github_usernames(&cli.data_dir, &data).await?;
+                Data::load(&cli.data_dir)?
+            } else {
+                data
+            };
             crate::validate::validate(
                 &data,
                 strict,
+                online,
                 &skip.iter().map(|s| s.as_ref()).collect::<Vec<_>>(),
             )
             .await?;
@@ -236,6 +434,7 @@ async fn run() -> Result<(), Error> {
         RootOpts::AddPerson {
             ref github_name,
             fetch_zulip_id,
+            ref team,
         } => {
             #[derive(serde::Serialize, Debug)]
             #[serde(rename_all = "kebab-case")]
@@ -249,6 +448,19 @@ async fn run() -> Result<(), Error> {
                 zulip_id: Option<u64>,
             }
 
+            // Checked first, to not leave a person file behind
+            if let Some(team) = team {
+                let Some(team) = data.team(team) else {
+                    bail!("unknown team: {team}");
+                };
+                if team.raw_people().is_derived() {
+                    bail!(
+                        "the members of team `{}` are computed from other teams",
+                        team.name()
+                    );
+                }
+            }
+
             let github = github::GitHubApi::new();
             let user = github.user(github_name).await?;
             let github_name = user.login;
@@ -298,10 +510,155 @@ async fn run() -> Result<(), Error> {
             )?;
 
             info!("written data to {}", file.display());
+
+            if let Some(team) = team {
+                let file = add_to_team(&data, team, &github_name)?;
+                info!("added {github_name} to the members of {}", file.display());
+            }
+        }
+        RootOpts::RetirePerson { ref github_name } => {
+            if data.person(github_name).is_none() {
+                bail!("unknown person: {github_name}");
+            }
+            let (old_lists, old_repos) = person_access(&data, github_name)?;
+
+            let left = chrono::Utc::now().date_naive();
+            let mut teams = Vec::new();
+            for team in data.teams() {
+                if let Some(member) = team
+                    .explicit_members()
+                    .iter()
+                    .find(|member| member.github == *github_name)
+                {
+                    retire_from_team(&data, team, member, left)?;
+                    teams.push(team.name());
+                }
+            }
+            if teams.is_empty() {
+                bail!("{github_name} is not a member of any team");
+            }
+            teams.sort_unstable();
+            info!("moved {github_name} to the alumni of: {}", teams.join(", "));
+
+            let data = Data::load(&cli.data_dir)?;
+            let (lists, repos) = person_access(&data, github_name)?;
+            let report = |what: &str, items: Vec<&String>| {
+                if !items.is_empty() {
+                    let items: Vec<_> = items.iter().map(|item| item.as_str()).collect();
+                    info!("{what}: {}", items.join(", "));
+                }
+            };
+            report(
+                "removed from the mailing lists",
+                old_lists.difference(&lists).collect(),
+            );
+            report("still on the mailing lists", lists.iter().collect());
+            report(
+                "lost access to the repos",
+                old_repos.difference(&repos).collect(),
+            );
+            report("still has access to the repos", repos.iter().collect());
         }
-        RootOpts::StaticApi { ref dest } => {
+        RootOpts::RenameGithubUser {
+            ref old_name,
+            ref new_name,
+        } => {
+            let Some(person) = data.person(old_name) else {
+                bail!("unknown person: {old_name}");
+            };
+            let user = github::GitHubApi::new().user(new_name).await?;
+            if user.login == *old_name {
+                bail!("`{old_name}` is already the GitHub username of the person");
+            }
+            if user.id != person.github_id() {
+                bail!(
+                    "GitHub user `{}` has ID {}, but `{old_name}` has ID {}: they are not the \
+                     same person",
+                    user.login,
+                    user.id,
+                    person.github_id()
+                );
+            }
+            crate::validate::rename_github_users(
+                &cli.data_dir,
+                &data,
+                &[(old_name.clone(), user.login)],
+            )?;
+
+            // CODEOWNERS lists the members of some teams
+            let data = Data::load(&cli.data_dir)?;
+            generate_codeowners_file(data)?;
+        }
+        RootOpts::CreateTeam {
+            ref name,
+            ref parent,
+            ref lead,
+            ref description,
+        } => {
+            if data.team(name).is_some() || data.archived_teams().any(|team| team.name() == name) {
+                bail!("team `{name}` already exists");
+            }
+            if data.team(parent).is_none() {
+                bail!("unknown parent team: {parent}");
+            }
+            if data.person(lead).is_none() {
+                bail!("unknown person: {lead}, add them with `add-person` first");
+            }
+            let naming = data.naming_conventions();
+            if naming.kebab_case_teams && !crate::validate::ascii_kebab_case(name) {
+                bail!("team names must be lowercase words separated by dashes");
+            }
+            let (kind, short_name) = naming
+                .team_prefixes
+                .iter()
+                .find_map(|(kind, prefix)| Some((*kind, name.strip_prefix(prefix.as_str())?)))
+                .unwrap_or((TeamKind::Team, name));
+
+            let file = cli.data_dir.join("teams").join(format!("{name}.toml"));
+            std::fs::write(
+                &file,
+                team_file::new_team(name, short_name, kind, parent, lead, description),
+            )?;
+            // Ensure the file can be loaded
+            if let Err(err) = Data::load(&cli.data_dir) {
+                std::fs::remove_file(&file)?;
+                return Err(err.context("the new team is invalid"));
+            }
+            info!("created {kind} `{name}` in {}", file.display());
+        }
+        RootOpts::StaticApi {
+            ref dest,
+            ref previous,
+            ref source_commit,
+            compress,
+        } => {
             let dest = PathBuf::from(dest);
-            let generator = crate::static_api::Generator::new(&dest, &data)?;
+            // Loaded first, as the previous build can be overwritten by the new one
+            let previous = previous
+                .as_deref()
+                .map(static_api::Previous::load)
+                .transpose()?;
+            let mut generator = static_api::Generator::new(&dest, &data)?;
+            if let Some(previous) = previous {
+                generator = generator.with_previous(previous);
+            }
+            let timestamp = match std::env::var("SOURCE_DATE_EPOCH") {
+                Ok(epoch) => Some(
+                    epoch
+                        .parse()
+                        .ok()
+                        .and_then(|epoch| chrono::DateTime::from_timestamp(epoch, 0))
+                        .with_context(|| format!("invalid SOURCE_DATE_EPOCH: {epoch}"))?,
+                ),
+                Err(_) => None,
+            };
+            generator = generator.with_source(source_commit.clone(), timestamp);
+            if compress {
+                generator = generator.with_compression();
+            }
+            if let Ok(key) = std::env::var("STATIC_API_SIGNING_KEY") {
+                generator = generator.with_signing_key(static_api::load_signing_key(&key)?);
+            }
             generator.generate()?;
         }
         RootOpts::ShowPerson {
@@ -484,10 +841,13 @@ async fn run() -> Result<(), Error> {
             for repo in data.repos() {
                 let repo_name = format!("{}/{}", repo.org, repo.name);
                 for (user, access) in &repo.access.individuals {
+                    if access.is_expired() {
+                        continue;
+                    }
                     users
                         .entry(user.clone())
                         .or_default()
-                        .push((repo_name.clone(), access.clone()));
+                        .push((repo_name.clone(), access.permission.clone()));
                 }
             }
             let output: HashMap<String, Vec<(String, RepoPermission)>> = match group_by {
@@ -569,13 +929,18 @@ async fn run() -> Result<(), Error> {
             CiOpts::CheckUntrackedRepos => ci::check_untracked_repos(&data).await?,
         },
         RootOpts::Sync(opts) => {
-            if let Err(err) = perform_sync(opts, data).await {
-                // Display shows just the first element of the chain.
-                error!("failed: {err}");
-                for cause in err.chain().skip(1) {
-                    error!("caused by: {cause}");
+            let detailed_exit_code = opts.detailed_exit_code;
+            match perform_sync(*opts, data).await {
+                Ok(true) if detailed_exit_code => std::process::exit(EXIT_CODE_CHANGES),
+                Ok(_) => {}
+                Err(err) => {
+                    // Display shows just the first element of the chain.
+                    error!("failed: {err}");
+                    for cause in err.chain().skip(1) {
+                        error!("caused by: {cause}");
+                    }
+                    std::process::exit(1);
                 }
-                std::process::exit(1);
             }
         }
     }
@@ -583,6 +948,120 @@ async fn run() -> Result<(), Error> {
     Ok(())
 }
 
+/// Add a person to the members of a team, returning the file that was changed
+fn add_to_team(data: &Data, team: &str, github_name: &str) -> anyhow::Result<PathBuf> {
+    let item = format!("\"{github_name}\"");
+    for file in team_file::team_files(data, team)? {
+        let contents = std::fs::read_to_string(&file)?;
+        if let Some(contents) = team_file::append_to_array(&contents, "members", &item) {
+            std::fs::write(&file, contents)?;
+            return Ok(file);
+        }
+    }
+    bail!(
+        "couldn't find the `members` of the `[people]` table of team `{team}`, add \
+         {github_name} to them manually"
+    );
+}
+
+/// Move a member of a team to its alumni, as having left the team on the given date
+fn retire_from_team(
+    data: &Data,
+    team: &Team,
+    member: &TeamMember,
+    left: chrono::NaiveDate,
+) -> anyhow::Result<()> {
+    let mut files = Vec::new();
+    for path in team_file::team_files(data, team.name())? {
+        let contents = std::fs::read_to_string(&path)?;
+        files.push((path, contents));
+    }
+
+    let mut removed = false;
+    for (_, contents) in &mut files {
+        for key in ["leads", "members"] {
+            if let Some(edited) = team_file::remove_from_array(contents, key, &member.github) {
+                removed |= key == "members" && edited != *contents;
+                *contents = edited;
+            }
+        }
+    }
+    if !removed {
+        bail!(
+            "couldn't remove {} from the `members` of team `{}`, move them to its alumni manually",
+            member.github,
+            team.name()
+        );
+    }
+
+    // People who rejoined a team are already among its alumni, their entry is replaced to
+    // record when they left again
+    let previous = team
+        .explicit_alumni()
+        .iter()
+        .find(|alum| alum.github == member.github);
+    if previous.is_some() {
+        for (_, contents) in &mut files {
+            if let Some(edited) = team_file::remove_from_array(contents, "alumni", &member.github) {
+                *contents = edited;
+            }
+        }
+    }
+    let entry = team_file::alumni_entry(member, previous, left);
+    let added = files.iter_mut().any(|(_, contents)| {
+        team_file::append_to_array(contents, "alumni", &entry)
+            .map(|edited| *contents = edited)
+            .is_some()
+    }) || files.iter_mut().any(|(_, contents)| {
+        team_file::add_array_after(contents, "members", "alumni", &entry)
+            .map(|edited| *contents = edited)
+            .is_some()
+    });
+    if !added {
+        bail!(
+            "couldn't add {} to the alumni of team `{}`",
+            member.github,
+            team.name()
+        );
+    }
+
+    for (path, contents) in files {
+        std::fs::write(path, contents)?;
+    }
+    Ok(())
+}
+
+/// The mailing lists and the repos a person has access to
+fn person_access(
+    data: &Data,
+    github_name: &str,
+) -> anyhow::Result<(BTreeSet<String>, BTreeSet<String>)> {
+    let mut lists = BTreeSet::new();
+    if let Some(person) = data.person(github_name)
+        && let Email::Present(email) = person.email()
+    {
+        for list in data.lists()?.values() {
+            if list.emails().iter().any(|list_email| list_email == email) {
+                lists.insert(list.address().to_owned());
+            }
+        }
+    }
+
+    let mut repos = BTreeSet::new();
+    for repo in data.repos() {
+        let mut has_access = repo.access.individuals.contains_key(github_name);
+        for team in repo.access.teams.keys() {
+            if let Some(team) = data.team(team) {
+                has_access |= team.members(data)?.contains(github_name);
+            }
+        }
+        if has_access {
+            repos.insert(format!("{}/{}", repo.org, repo.name));
+        }
+    }
+    Ok((lists, repos))
+}
+
 fn dump_team_members(
     team: &Team,
     data: &Data,
@@ -610,7 +1089,37 @@ fn dump_team_members(
     Ok(())
 }
 
-async fn perform_sync(opts: SyncOpts, data: Data) -> anyhow::Result<()> {
+/// Access the team data of a revision, rendering it to `render_dir` if it is a checkout
+/// of the team repository.
+fn revision_team_api(path: &Path, render_dir: &Path) -> anyhow::Result<TeamApi> {
+    if path.join("v1").is_dir() {
+        return Ok(TeamApi::Prebuilt(path.to_path_buf()));
+    }
+    let data = Data::load(path)
+        .with_context(|| format!("failed to load the team data from {}", path.display()))?;
+    static_api::Generator::new(render_dir, &data)?.generate()?;
+    Ok(TeamApi::Prebuilt(render_dir.to_path_buf()))
+}
+
+/// Returns whether there were changes to apply
+async fn perform_sync(opts: SyncOpts, data: Data) -> anyhow::Result<bool> {
+    if let Some(SyncCommand::Rollback {
+        audit_log,
+        run,
+        dry_run,
+    }) = &opts.command
+    {
+        rollback_github(audit_log, run.as_deref(), *dry_run).await?;
+        return Ok(false);
+    }
+    if let Some(SyncCommand::DiffRevisions { old, new }) = &opts.command {
+        let old_dir = tempfile::tempdir()?;
+        let new_dir = tempfile::tempdir()?;
+        let old = revision_team_api(old, old_dir.path())?;
+        let new = revision_team_api(new, new_dir.path())?;
+        return diff_github_revisions(&old, &new, opts.format, opts.color.enabled()).await;
+    }
+
     // We pregenerate the directory here in case we need it, to make sure it lives
     // long enough.
     let source_dir = tempfile::tempdir()?;
@@ -625,7 +1134,27 @@ async fn perform_sync(opts: SyncOpts, data: Data) -> anyhow::Result<()> {
         DataSource::Production => TeamApi::Production,
     };
 
+    if let Some(SyncCommand::EmailSuppressions) = &opts.command {
+        return report_email_suppressions(&team_api, opts.email_backend).await;
+    }
+
+    let subcmd = opts
+        .command
+        .unwrap_or(SyncCommand::DryRun { save_plan: None });
+
     let mut services = opts.services;
+    if matches!(subcmd, SyncCommand::ExportState { .. }) {
+        if services.iter().any(|s| s != "github") {
+            bail!("only the GitHub state can be exported");
+        }
+        services = vec!["github".to_string()];
+    }
+    if matches!(subcmd, SyncCommand::Apply { plan: Some(_), .. }) {
+        if services.iter().any(|s| s != "github") {
+            bail!("plans only cover the GitHub changes");
+        }
+        services = vec!["github".to_string()];
+    }
     if services.is_empty() {
         info!("no service to synchronize specified, defaulting to all services");
         services = AVAILABLE_SERVICES
@@ -634,16 +1163,60 @@ async fn perform_sync(opts: SyncOpts, data: Data) -> anyhow::Result<()> {
             .collect();
     }
 
-    let subcmd = opts.command.unwrap_or(SyncCommand::DryRun);
-    let only_print_plan = matches!(subcmd, SyncCommand::PrintPlan);
-    let dry_run = only_print_plan || matches!(subcmd, SyncCommand::DryRun);
+    let only_print_plan = matches!(
+        subcmd,
+        SyncCommand::PrintPlan { .. } | SyncCommand::ExportState { .. }
+    );
+    let dry_run = only_print_plan || matches!(subcmd, SyncCommand::DryRun { .. });
+    if opts.from_state.is_some() && !dry_run {
+        bail!("changes cannot be applied to a recorded GitHub state");
+    }
 
-    run_sync_team(
-        team_api,
-        &services,
-        dry_run,
-        only_print_plan,
-        data.get_sync_team_config()?,
-    )
-    .await
+    let mut config = data.get_sync_team_config()?;
+    config.allow_owner_demotions = opts.allow_owner_demotions;
+    config.github_cache_dir = opts.github_cache_dir;
+    config.github_concurrency = opts.github_concurrency.map(usize::from);
+    config.github_state_source = opts.from_state;
+    config.github_max_deletions = opts.max_deletions;
+    config.github_filter = GitHubFilter {
+        orgs: opts.orgs.into_iter().collect(),
+        repos: opts.repos.into_iter().collect(),
+        teams: opts.teams.into_iter().collect(),
+    };
+    config.force = opts.force;
+    config.output_format = opts.format;
+    config.colored_output = opts.color.enabled();
+    config.email_backend = opts.email_backend;
+    if let Ok(id) = std::env::var("GITHUB_ACTIONS_INTEGRATION_ID") {
+        config.github_actions_integration_id = Some(
+            id.parse()
+                .context("GITHUB_ACTIONS_INTEGRATION_ID is not a valid app ID")?,
+        );
+    }
+    match subcmd {
+        SyncCommand::DryRun { save_plan } | SyncCommand::PrintPlan { save_plan } => {
+            config.github_plan_out = save_plan;
+        }
+        SyncCommand::Apply {
+            plan,
+            interactive,
+            continue_on_error,
+            audit_log,
+            notify_zulip_stream,
+        } => {
+            config.github_plan = plan;
+            config.interactive = interactive;
+            config.continue_on_error = continue_on_error;
+            config.github_audit_log = audit_log;
+            config.github_notification_stream = notify_zulip_stream;
+        }
+        SyncCommand::ExportState { out } => config.github_state_export = Some(out),
+        SyncCommand::Rollback { .. }
+        | SyncCommand::DiffRevisions { .. }
+        | SyncCommand::EmailSuppressions => {
+            unreachable!("the command doesn't synchronize services")
+        }
+    }
+
+    run_sync_team(team_api, &services, dry_run, only_print_plan, config).await
 }
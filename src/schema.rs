@@ -11,13 +11,15 @@ pub(crate) struct Config {
     allowed_mailing_lists_domains: HashSet<String>,
     allowed_github_orgs: HashSet<String>,
     independent_github_orgs: BTreeSet<String>,
+    unmanaged_team_deletion_orgs: BTreeSet<String>,
+    preserved_github_teams: BTreeSet<String>,
     permissions_bors_repos: HashSet<String>,
     permissions_bools: HashSet<String>,
     // Use a BTreeSet for consistent ordering in tests
     special_org_members: BTreeSet<String>,
     members_without_zulip_id: BTreeSet<String>,
-    #[serde(default)]
-    enable_rulesets_repos: BTreeSet<String>,
+    allowed_bots: BTreeMap<String, Vec<Bot>>,
+    custom_repo_roles: BTreeMap<String, BTreeSet<String>>,
 }
 
 impl Config {
@@ -41,6 +43,14 @@ impl Config {
         &self.independent_github_orgs
     }
 
+    pub(crate) fn unmanaged_team_deletion_orgs(&self) -> &BTreeSet<String> {
+        &self.unmanaged_team_deletion_orgs
+    }
+
+    pub(crate) fn preserved_github_teams(&self) -> &BTreeSet<String> {
+        &self.preserved_github_teams
+    }
+
     pub(crate) fn special_org_members(&self) -> &BTreeSet<String> {
         &self.special_org_members
     }
@@ -49,8 +59,102 @@ impl Config {
         &self.members_without_zulip_id
     }
 
-    pub(crate) fn enable_rulesets_repos(&self) -> &BTreeSet<String> {
-        &self.enable_rulesets_repos
+    /// The bots that repos of a GitHub organization can use
+    pub(crate) fn allowed_bots(&self, org: &str) -> &[Bot] {
+        self.allowed_bots.get(org).map_or(&[], Vec::as_slice)
+    }
+
+    /// The custom repository roles that repos of a GitHub organization can grant
+    pub(crate) fn custom_repo_roles(&self, org: &str) -> Option<&BTreeSet<String>> {
+        self.custom_repo_roles.get(org)
+    }
+}
+
+/// The org-wide requirements on repositories of `config/policies.toml`
+#[derive(serde::Deserialize, Debug, Default)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct Policies {
+    #[serde(default, rename = "policy")]
+    pub policies: Vec<Policy>,
+    #[serde(default, rename = "privileged-access")]
+    pub privileged_access: Option<PrivilegedAccessLimits>,
+    #[serde(default)]
+    pub naming: NamingConventions,
+}
+
+/// Conventions for the names of the teams and repos which aren't archived
+#[derive(serde::Deserialize, Debug, Default)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub(crate) struct NamingConventions {
+    /// Whether team names must be lowercase words separated by dashes
+    #[serde(default)]
+    pub kebab_case_teams: bool,
+    /// The prefix required in the names of each kind of team
+    #[serde(default)]
+    pub team_prefixes: BTreeMap<TeamKind, String>,
+    /// Regular expression the names of the repos of each org must match
+    #[serde(default)]
+    pub repo_patterns: BTreeMap<String, String>,
+    /// Teams and repos (as `org/name`) that don't follow the conventions
+    #[serde(default)]
+    pub exceptions: Vec<String>,
+}
+
+/// Limits on the `maintain` and `admin` access to repos, to catch privilege creep
+#[derive(serde::Deserialize, Debug)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub(crate) struct PrivilegedAccessLimits {
+    /// How many teams and individuals can have privileged access to a repo
+    pub max_per_repo: usize,
+    /// To how many repos a person can have privileged access, directly or through teams
+    pub max_per_person: usize,
+    /// Repos that can exceed `max-per-repo`, as `org/name`
+    #[serde(default)]
+    pub allowed_repos: Vec<String>,
+    /// People who can exceed `max-per-person`
+    #[serde(default)]
+    pub allowed_people: Vec<String>,
+}
+
+#[derive(serde::Deserialize, Debug)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub(crate) struct Policy {
+    pub rule: PolicyRule,
+    /// Why the policy exists, to be included in the reports of its violations
+    pub reason: String,
+    /// The orgs whose repos must follow the policy, all of them if empty
+    #[serde(default)]
+    pub orgs: Vec<String>,
+    /// Repos that don't have to follow the policy, as `org/name`
+    #[serde(default)]
+    pub exceptions: Vec<String>,
+}
+
+impl Policy {
+    pub(crate) fn applies_to(&self, repo: &Repo) -> bool {
+        (self.orgs.is_empty() || self.orgs.contains(&repo.org))
+            && !self
+                .exceptions
+                .iter()
+                .any(|exception| *exception == format!("{}/{}", repo.org, repo.name))
+    }
+}
+
+#[derive(serde::Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum PolicyRule {
+    /// The default branch of the repo is covered by a branch protection
+    ProtectDefaultBranch,
+    /// Archived repos don't give access to any team, individual or bot
+    NoArchivedCollaborators,
+}
+
+impl std::fmt::Display for PolicyRule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            PolicyRule::ProtectDefaultBranch => "protect-default-branch",
+            PolicyRule::NoArchivedCollaborators => "no-archived-collaborators",
+        })
     }
 }
 
@@ -94,6 +198,15 @@ pub(crate) struct Person {
     email: EmailField,
     discord_id: Option<u64>,
     matrix: Option<String>,
+    website: Option<String>,
+    pronouns: Option<String>,
+    /// IANA name of the time zone of the person, like `Europe/Paris`
+    timezone: Option<String>,
+    /// The person is away until this date, included
+    on_leave_until: Option<chrono::NaiveDate>,
+    /// GitHub usernames the person used before renaming their account
+    #[serde(default)]
+    former_github_usernames: Vec<String>,
     #[serde(default)]
     funding: Funding,
     #[serde(default)]
@@ -121,6 +234,26 @@ impl Person {
         self.funding.github_sponsors
     }
 
+    pub(crate) fn website(&self) -> Option<&str> {
+        self.website.as_deref()
+    }
+
+    pub(crate) fn pronouns(&self) -> Option<&str> {
+        self.pronouns.as_deref()
+    }
+
+    pub(crate) fn timezone(&self) -> Option<&str> {
+        self.timezone.as_deref()
+    }
+
+    pub(crate) fn on_leave_until(&self) -> Option<chrono::NaiveDate> {
+        self.on_leave_until
+    }
+
+    pub(crate) fn former_github_usernames(&self) -> &[String] {
+        &self.former_github_usernames
+    }
+
     #[allow(unused)]
     pub(crate) fn irc(&self) -> &str {
         if let Some(irc) = &self.irc {
@@ -160,7 +293,7 @@ impl Person {
     }
 }
 
-#[derive(serde::Deserialize, Debug, Default, Copy, Clone, PartialEq, Eq)]
+#[derive(serde::Deserialize, Debug, Default, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
 #[serde(rename_all = "kebab-case")]
 pub(crate) enum TeamKind {
     #[default]
@@ -210,6 +343,11 @@ pub(crate) struct Team {
     zulip_groups: Vec<RawZulipGroup>,
     #[serde(default)]
     zulip_streams: Vec<RawZulipStream>,
+    zulip_role: Option<ZulipRole>,
+    #[serde(default)]
+    sensitive: bool,
+    #[serde(default)]
+    meetings: Vec<Meeting>,
 }
 
 impl Team {
@@ -275,6 +413,10 @@ impl Team {
         &self.roles
     }
 
+    pub(crate) fn meetings(&self) -> &[Meeting] {
+        &self.meetings
+    }
+
     /// Exposed only for validation.
     pub(crate) fn raw_people(&self) -> &TeamPeople {
         &self.people
@@ -285,6 +427,7 @@ impl Team {
             .people
             .members
             .iter()
+            .filter(|member| !member.is_expired())
             .map(|s| s.github.as_str())
             .collect();
 
@@ -352,6 +495,9 @@ impl Team {
             let mut list = List {
                 address: raw_list.address.clone(),
                 emails: Vec::new(),
+                access_level: raw_list.access_level,
+                reply_preference: raw_list.reply_preference,
+                priority: raw_list.priority,
             };
 
             let mut members = if raw_list.include_team_members {
@@ -435,6 +581,10 @@ impl Team {
         Ok(final_members)
     }
 
+    pub(crate) fn zulip_role(&self) -> Option<ZulipRole> {
+        self.zulip_role
+    }
+
     pub(crate) fn raw_zulip_groups(&self) -> &[RawZulipGroup] {
         &self.zulip_groups
     }
@@ -444,21 +594,69 @@ impl Team {
         let zulip_groups = &self.zulip_groups;
 
         for raw_group in zulip_groups {
-            groups.push(ZulipGroup(ZulipCommon {
-                name: raw_group.common.name.clone(),
-                includes_team_members: raw_group.common.include_team_members,
-                members: self.expand_zulip_membership(
-                    data,
-                    &raw_group.common,
-                    |excluded| {
-                        format_err!("'{excluded}' was specifically excluded from the Zulip group '{}' but they were already not included", raw_group.common.name)
-                    },
-                )?,
-            }));
+            groups.push(ZulipGroup {
+                common: ZulipCommon {
+                    name: raw_group.common.name.clone(),
+                    includes_team_members: raw_group.common.include_team_members,
+                    members: self.expand_zulip_membership(
+                        data,
+                        &raw_group.common,
+                        |excluded| {
+                            format_err!("'{excluded}' was specifically excluded from the Zulip group '{}' but they were already not included", raw_group.common.name)
+                        },
+                    )?,
+                },
+                mention_policy: raw_group.mention_policy,
+                alumni: false,
+            });
+            if raw_group.alumni_group {
+                groups.push(self.zulip_alumni_group(data, &raw_group.common.name)?);
+            }
         }
         Ok(groups)
     }
 
+    /// Names of the Zulip groups of the team, including the alumni groups, without expanding
+    /// their members
+    pub(crate) fn zulip_group_names(&self) -> Vec<String> {
+        let mut names = Vec::new();
+        for raw_group in &self.zulip_groups {
+            names.push(raw_group.common.name.clone());
+            if raw_group.alumni_group {
+                names.push(alumni_group_name(&raw_group.common.name));
+            }
+        }
+        names
+    }
+
+    /// The alumni of the team, as a group named after another group of the team
+    fn zulip_alumni_group(&self, data: &Data, group_name: &str) -> Result<ZulipGroup, Error> {
+        let mut members = Vec::new();
+        for alum in self.explicit_alumni() {
+            let person = data.person(&alum.github).ok_or_else(|| {
+                format_err!("{} does not have a person configuration", alum.github)
+            })?;
+            members.push(match person.zulip_id {
+                Some(zulip_id) => ZulipMember::MemberWithId {
+                    github: person.github.clone(),
+                    zulip_id,
+                },
+                None => ZulipMember::MemberWithoutId {
+                    github: person.github.clone(),
+                },
+            });
+        }
+        Ok(ZulipGroup {
+            common: ZulipCommon {
+                name: alumni_group_name(group_name),
+                includes_team_members: false,
+                members,
+            },
+            mention_policy: None,
+            alumni: true,
+        })
+    }
+
     pub(crate) fn raw_zulip_streams(&self) -> &[RawZulipStream] {
         &self.zulip_streams
     }
@@ -468,17 +666,20 @@ impl Team {
         let zulip_streams = self.raw_zulip_streams();
 
         for raw_stream in zulip_streams {
-            streams.push(ZulipStream(ZulipCommon {
-                name: raw_stream.common.name.clone(),
-                includes_team_members: raw_stream.common.include_team_members,
-                members: self.expand_zulip_membership(
-                    data,
-                    &raw_stream.common,
-                    |excluded| {
-                        format_err!("'{excluded}' was specifically excluded from the Zulip stream '{}' but they were already not included", raw_stream.common.name)
-                    },
-                )?,
-            }));
+            streams.push(ZulipStream {
+                common: ZulipCommon {
+                    name: raw_stream.common.name.clone(),
+                    includes_team_members: raw_stream.common.include_team_members,
+                    members: self.expand_zulip_membership(
+                        data,
+                        &raw_stream.common,
+                        |excluded| {
+                            format_err!("'{excluded}' was specifically excluded from the Zulip stream '{}' but they were already not included", raw_stream.common.name)
+                        },
+                    )?,
+                },
+                sensitive: self.sensitive,
+            });
         }
         Ok(streams)
     }
@@ -509,13 +710,34 @@ impl Team {
                 );
             }
             members.sort_unstable();
+            let mut maintainers = github
+                .maintainers
+                .iter()
+                .map(|name| {
+                    data.person(name)
+                        .map(|p| (p.github(), p.github_id()))
+                        .ok_or_else(|| format_err!("missing person {}", name))
+                })
+                .collect::<Result<Vec<_>, Error>>()?;
+            maintainers.sort_unstable();
             let name = github.team_name.as_deref().unwrap_or(&self.name);
+            if github.team_id.is_some() && github.orgs.len() != 1 {
+                bail!(
+                    "GitHub team `{name}` has a `team-id`, so it has to be in exactly one organization"
+                );
+            }
 
             for org in &github.orgs {
                 result.push(GitHubTeam {
                     org: org.as_str(),
                     name,
+                    team_id: github.team_id,
                     members: members.clone(),
+                    maintainers: maintainers.clone(),
+                    org_owners: github.org_owners,
+                    parent: github.parent.as_deref(),
+                    description: github.description.as_deref(),
+                    privacy: github.privacy,
                 });
             }
         }
@@ -549,7 +771,13 @@ impl Team {
 pub(crate) struct GitHubTeam<'a> {
     pub(crate) org: &'a str,
     pub(crate) name: &'a str,
+    pub(crate) team_id: Option<u64>,
     pub(crate) members: Vec<(&'a str, u64)>,
+    pub(crate) maintainers: Vec<(&'a str, u64)>,
+    pub(crate) org_owners: bool,
+    pub(crate) parent: Option<&'a str>,
+    pub(crate) description: Option<&'a str>,
+    pub(crate) privacy: Option<GitHubTeamPrivacy>,
 }
 
 impl std::cmp::PartialOrd for GitHubTeam<'_> {
@@ -584,11 +812,54 @@ pub(crate) struct TeamPeople {
     pub include_all_alumni: bool,
 }
 
+impl TeamPeople {
+    /// Whether the members of the team are computed from the whole project, like the leads of
+    /// all teams, in which case they can't be listed directly. Teams which only use
+    /// `included-teams` can still have their own members.
+    pub(crate) fn is_derived(&self) -> bool {
+        // Exhaustive destructuring to ensure this code is touched if a new "include" setting is
+        // introduced.
+        let TeamPeople {
+            leads: _,
+            members: _,
+            alumni: _,
+            included_teams: _,
+            include_team_leads,
+            include_wg_leads,
+            include_project_group_leads,
+            include_all_team_members,
+            include_all_alumni,
+        } = self;
+        *include_team_leads
+            || *include_wg_leads
+            || *include_project_group_leads
+            || *include_all_team_members
+            || *include_all_alumni
+    }
+}
+
 #[derive(serde::Deserialize, Clone, Debug)]
 #[serde(remote = "Self", deny_unknown_fields)]
 pub(crate) struct TeamMember {
     pub github: String,
     pub roles: Vec<String>,
+    /// When the person joined the team
+    pub joined: Option<chrono::NaiveDate>,
+    /// When the person left the team, only for alumni
+    pub left: Option<chrono::NaiveDate>,
+    /// When the membership ends, for temporary members
+    pub expires: Option<chrono::NaiveDate>,
+}
+
+impl TeamMember {
+    pub(crate) fn is_expired(&self) -> bool {
+        is_expired(self.expires)
+    }
+}
+
+/// Whether an access that ends on the given date has ended
+pub(crate) fn is_expired(expires: Option<chrono::NaiveDate>) -> bool {
+    expires.is_some_and(|expires| chrono::Utc::now().date_naive() >= expires)
 }
 
 impl<'de> Deserialize<'de> for TeamMember {
@@ -601,6 +872,9 @@ impl<'de> Deserialize<'de> for TeamMember {
                 Ok(TeamMember {
                     github: github.to_owned(),
                     roles: Vec::new(),
+                    joined: None,
+                    left: None,
+                    expires: None,
                 })
             })
             .map(|map| {
@@ -615,9 +889,53 @@ impl<'de> Deserialize<'de> for TeamMember {
 #[serde(rename_all = "kebab-case", deny_unknown_fields)]
 struct GitHubData {
     team_name: Option<String>,
+    team_id: Option<u64>,
     orgs: Vec<String>,
     #[serde(default)]
     extra_teams: Vec<String>,
+    #[serde(default)]
+    maintainers: Vec<String>,
+    #[serde(default)]
+    org_owners: bool,
+    parent: Option<String>,
+    description: Option<String>,
+    privacy: Option<GitHubTeamPrivacy>,
+}
+
+#[derive(serde::Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum GitHubTeamPrivacy {
+    Closed,
+    Secret,
+}
+
+/// A recurring meeting of a team, published as an iCalendar event
+#[derive(serde::Deserialize, Debug)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub(crate) struct Meeting {
+    pub(crate) name: String,
+    /// First occurrence of the meeting, in its time zone
+    pub(crate) start: chrono::NaiveDateTime,
+    /// Duration in minutes
+    pub(crate) duration: u32,
+    /// IANA name of the time zone, like `Europe/Berlin`
+    pub(crate) timezone: String,
+    /// iCalendar recurrence rule, like `FREQ=WEEKLY;BYDAY=TU`. Meetings without one only happen
+    /// once.
+    pub(crate) recurrence: Option<String>,
+    pub(crate) video_link: Option<String>,
+}
+
+impl Meeting {
+    /// Identifier of the meeting within its team, derived from its name
+    pub(crate) fn id(&self) -> String {
+        self.name
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|word| !word.is_empty())
+            .map(|word| word.to_lowercase())
+            .collect::<Vec<_>>()
+            .join("-")
+    }
 }
 
 #[derive(serde::Deserialize, Debug)]
@@ -699,6 +1017,31 @@ pub(crate) struct TeamList {
     pub(crate) extra_emails: Vec<String>,
     #[serde(default)]
     pub(crate) extra_teams: Vec<String>,
+    #[serde(default)]
+    pub(crate) access_level: ListAccessLevel,
+    #[serde(default)]
+    pub(crate) reply_preference: ListReplyPreference,
+    #[serde(default)]
+    pub(crate) priority: i32,
+}
+
+/// Who may send emails to a mailing list
+#[derive(serde::Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum ListAccessLevel {
+    #[default]
+    Everyone,
+    Members,
+    Readonly,
+}
+
+/// Where replies to the emails of a mailing list go
+#[derive(serde::Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum ListReplyPreference {
+    #[default]
+    Sender,
+    List,
 }
 
 #[derive(serde::Deserialize, Debug)]
@@ -717,11 +1060,32 @@ pub(crate) struct RawZulipCommon {
     pub(crate) excluded_people: Vec<String>,
 }
 
+/// Zulip organization role given to the members of a team
+#[derive(serde::Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum ZulipRole {
+    Moderator,
+    Administrator,
+}
+
 #[derive(serde::Deserialize, Debug)]
 #[serde(rename_all = "kebab-case", deny_unknown_fields)]
 pub(crate) struct RawZulipGroup {
     #[serde(flatten)]
     pub(crate) common: RawZulipCommon,
+    pub(crate) mention_policy: Option<ZulipMentionPolicy>,
+    /// Also generate a `<name>-alumni` group with the alumni of the team
+    #[serde(default)]
+    pub(crate) alumni_group: bool,
+}
+
+/// Who may `@`-mention a Zulip user group
+#[derive(serde::Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum ZulipMentionPolicy {
+    Everyone,
+    Members,
+    Admins,
 }
 
 #[derive(serde::Deserialize, Debug)]
@@ -735,6 +1099,9 @@ pub(crate) struct RawZulipStream {
 pub(crate) struct List {
     address: String,
     emails: Vec<String>,
+    access_level: ListAccessLevel,
+    reply_preference: ListReplyPreference,
+    priority: i32,
 }
 
 impl List {
@@ -745,6 +1112,18 @@ impl List {
     pub(crate) fn emails(&self) -> &[String] {
         &self.emails
     }
+
+    pub(crate) fn access_level(&self) -> ListAccessLevel {
+        self.access_level
+    }
+
+    pub(crate) fn reply_preference(&self) -> ListReplyPreference {
+        self.reply_preference
+    }
+
+    pub(crate) fn priority(&self) -> i32 {
+        self.priority
+    }
 }
 
 #[derive(Debug)]
@@ -770,22 +1149,47 @@ impl ZulipCommon {
 }
 
 #[derive(Debug)]
-pub(crate) struct ZulipGroup(ZulipCommon);
+pub(crate) struct ZulipGroup {
+    common: ZulipCommon,
+    mention_policy: Option<ZulipMentionPolicy>,
+    /// Whether the group is made of the alumni of the team
+    alumni: bool,
+}
+
+impl ZulipGroup {
+    pub(crate) fn mention_policy(&self) -> Option<ZulipMentionPolicy> {
+        self.mention_policy
+    }
+
+    pub(crate) fn is_alumni_group(&self) -> bool {
+        self.alumni
+    }
+}
 
 impl std::ops::Deref for ZulipGroup {
     type Target = ZulipCommon;
     fn deref(&self) -> &Self::Target {
-        &self.0
+        &self.common
     }
 }
 
 #[derive(Debug)]
-pub(crate) struct ZulipStream(ZulipCommon);
+pub(crate) struct ZulipStream {
+    common: ZulipCommon,
+    /// Whether the stream belongs to a sensitive team
+    sensitive: bool,
+}
+
+impl ZulipStream {
+    pub(crate) fn is_sensitive(&self) -> bool {
+        self.sensitive
+    }
+}
 
 impl std::ops::Deref for ZulipStream {
     type Target = ZulipCommon;
     fn deref(&self) -> &Self::Target {
-        &self.0
+        &self.common
     }
 }
 
@@ -804,14 +1208,40 @@ fn default_false() -> bool {
     false
 }
 
+#[derive(serde::Deserialize, Debug)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub(crate) struct Org {
+    pub name: String,
+    #[serde(default)]
+    pub default_repository_permission: DefaultRepoPermission,
+    #[serde(default = "default_false")]
+    pub members_can_create_repositories: bool,
+    #[serde(default = "default_false")]
+    pub members_can_fork_private_repositories: bool,
+}
+
+#[derive(serde::Deserialize, Debug, Clone, Copy, Default)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum DefaultRepoPermission {
+    None,
+    #[default]
+    Read,
+    Write,
+    Admin,
+}
+
 #[derive(serde::Deserialize, Debug)]
 #[serde(deny_unknown_fields, rename_all = "kebab-case")]
 pub(crate) struct Repo {
     pub org: String,
     pub name: String,
+    #[serde(default)]
+    pub github_id: Option<u64>,
     pub description: String,
     pub homepage: Option<String>,
     #[serde(default)]
+    pub topics: Vec<String>,
+    #[serde(default)]
     pub private_non_synced: Option<bool>,
     pub bots: Vec<Bot>,
     pub access: RepoAccess,
@@ -821,6 +1251,44 @@ pub(crate) struct Repo {
     pub crates_io: Vec<CratesIoConfiguration>,
     #[serde(default)]
     pub environments: BTreeMap<String, Environment>,
+    #[serde(default)]
+    pub allow_squash_merge: Option<bool>,
+    #[serde(default)]
+    pub allow_merge_commit: Option<bool>,
+    #[serde(default)]
+    pub allow_rebase_merge: Option<bool>,
+    #[serde(default)]
+    pub delete_branch_on_merge: Option<bool>,
+    #[serde(default)]
+    pub default_branch: Option<String>,
+    #[serde(default)]
+    pub has_issues: Option<bool>,
+    #[serde(default)]
+    pub has_wiki: Option<bool>,
+    #[serde(default)]
+    pub has_projects: Option<bool>,
+    #[serde(default)]
+    pub has_discussions: Option<bool>,
+    #[serde(default = "default_false")]
+    pub use_rulesets: bool,
+    #[serde(default)]
+    pub labels: Vec<Label>,
+    #[serde(default)]
+    pub webhooks: Vec<Webhook>,
+    /// Autolink reference prefix -> URL template
+    #[serde(default)]
+    pub autolinks: BTreeMap<String, String>,
+    /// Deploy keys that are allowed in the repository
+    #[serde(default)]
+    pub deploy_keys: Option<Vec<DeployKey>>,
+    #[serde(default)]
+    pub actions: Option<ActionsPermissions>,
+    /// Template repository (`owner/name`) used when the repository is created
+    #[serde(default)]
+    pub template: Option<String>,
+    /// Date on which the repository is archived
+    #[serde(default)]
+    pub archive_at: Option<chrono::NaiveDate>,
 }
 
 #[derive(serde::Deserialize, Debug, Clone, PartialEq)]
@@ -838,21 +1306,97 @@ pub(crate) enum Bot {
     HerokuDeployAccess,
 }
 
+impl std::fmt::Display for Bot {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Bot::Bors => "bors",
+            Bot::Highfive => "highfive",
+            Bot::Rustbot => "rustbot",
+            Bot::RustTimer => "rust-timer",
+            Bot::Rfcbot => "rfcbot",
+            Bot::Craterbot => "craterbot",
+            Bot::Glacierbot => "glacierbot",
+            Bot::LogAnalyzer => "log-analyzer",
+            Bot::Renovate => "renovate",
+            Bot::HerokuDeployAccess => "heroku-deploy-access",
+        })
+    }
+}
+
 #[derive(serde::Deserialize, Debug)]
 #[serde(deny_unknown_fields, rename_all = "kebab-case")]
 pub(crate) struct RepoAccess {
     pub teams: HashMap<String, RepoPermission>,
     #[serde(default)]
-    pub individuals: HashMap<String, RepoPermission>,
+    pub individuals: HashMap<String, RepoIndividual>,
 }
 
+/// The access of a person to a repository, either only its permission or a table with an
+/// expiration date
 #[derive(serde::Deserialize, Debug, Clone)]
-#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+#[serde(remote = "Self", deny_unknown_fields)]
+pub(crate) struct RepoIndividual {
+    pub permission: RepoPermission,
+    /// When the access ends, for temporary access
+    pub expires: Option<chrono::NaiveDate>,
+}
+
+impl RepoIndividual {
+    pub(crate) fn is_expired(&self) -> bool {
+        is_expired(self.expires)
+    }
+}
+
+impl<'de> Deserialize<'de> for RepoIndividual {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        UntaggedEnumVisitor::new()
+            .string(|permission| {
+                let deserializer = serde::de::value::StrDeserializer::new(permission);
+                Ok(RepoIndividual {
+                    permission: RepoPermission::deserialize(deserializer)?,
+                    expires: None,
+                })
+            })
+            .map(|map| {
+                let deserializer = serde::de::value::MapAccessDeserializer::new(map);
+                RepoIndividual::deserialize(deserializer)
+            })
+            .deserialize(deserializer)
+    }
+}
+
+#[derive(Debug, Clone)]
 pub(crate) enum RepoPermission {
     Triage,
     Write,
     Maintain,
     Admin,
+    /// A custom repository role of the organization, written as `custom:<name>`
+    Custom(String),
+}
+
+impl<'de> Deserialize<'de> for RepoPermission {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let permission = String::deserialize(deserializer)?;
+        Ok(match permission.as_str() {
+            "triage" => RepoPermission::Triage,
+            "write" => RepoPermission::Write,
+            "maintain" => RepoPermission::Maintain,
+            "admin" => RepoPermission::Admin,
+            // Custom roles are explicit, so that a typo isn't taken for a custom role
+            _ => match permission.strip_prefix("custom:") {
+                Some(role) if !role.is_empty() => RepoPermission::Custom(role.to_string()),
+                _ => {
+                    return Err(serde::de::Error::custom(format!(
+                        "unknown permission `{permission}`, expected `triage`, `write`, `maintain`, `admin` or `custom:<role>`"
+                    )));
+                }
+            },
+        })
+    }
 }
 
 #[derive(serde::Deserialize, Debug, PartialEq, Eq)]
@@ -886,6 +1430,8 @@ pub(crate) struct BranchProtection {
     pub dismiss_stale_review: bool,
     #[serde(default)]
     pub required_approvals: Option<u32>,
+    #[serde(default)]
+    pub requires_code_owner_reviews: bool,
     #[serde(default = "default_true")]
     pub pr_required: bool,
     #[serde(default)]
@@ -893,7 +1439,21 @@ pub(crate) struct BranchProtection {
     #[serde(default)]
     pub allowed_merge_apps: Vec<AllowedMergeApp>,
     #[serde(default)]
+    pub allowed_push_apps: Vec<String>,
+    #[serde(default)]
+    pub pr_bypass_teams: Vec<String>,
+    #[serde(default)]
+    pub pr_bypass_apps: Vec<AllowedMergeApp>,
+    #[serde(default)]
     pub merge_queue: bool,
+    #[serde(default)]
+    pub merge_queue_settings: Option<MergeQueueSettings>,
+    #[serde(default)]
+    pub requires_signed_commits: bool,
+    #[serde(default)]
+    pub required_linear_history: bool,
+    #[serde(default)]
+    pub requires_conversation_resolution: bool,
     #[serde(default = "branch_protection_default_prevent_creation")]
     pub prevent_creation: bool,
     #[serde(default = "branch_protection_default_prevent_update")]
@@ -904,6 +1464,28 @@ pub(crate) struct BranchProtection {
     pub prevent_force_push: bool,
 }
 
+#[derive(serde::Deserialize, Debug)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub(crate) struct MergeQueueSettings {
+    #[serde(default)]
+    pub method: MergeQueueMethod,
+    #[serde(default)]
+    pub min_entries_to_merge: Option<u32>,
+    #[serde(default)]
+    pub max_entries_to_merge: Option<u32>,
+    #[serde(default)]
+    pub max_entries_to_build: Option<u32>,
+}
+
+#[derive(serde::Deserialize, Debug, Clone, Copy, Default)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum MergeQueueMethod {
+    #[default]
+    Merge,
+    Squash,
+    Rebase,
+}
+
 #[derive(serde::Deserialize, Debug)]
 #[serde(deny_unknown_fields, rename_all = "kebab-case")]
 pub(crate) struct CratesIoConfiguration {
@@ -927,6 +1509,104 @@ pub(crate) struct Environment {
     /// Tag patterns that can deploy to this environment
     #[serde(default)]
     pub tags: Vec<String>,
+    /// GitHub teams that have to approve deployments to this environment
+    #[serde(default)]
+    pub reviewers: Vec<String>,
+    /// Minutes to wait before a deployment to this environment can proceed
+    #[serde(default)]
+    pub wait_timer: u32,
+    /// Only allow deployments from protected branches
+    #[serde(default)]
+    pub protected_branches_only: bool,
+}
+
+#[derive(serde::Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub(crate) struct Label {
+    pub name: String,
+    /// Hexadecimal color code of the label, without the leading `#`
+    pub color: String,
+    #[serde(default)]
+    pub description: String,
+}
+
+/// The prefix of the environment variables that can contain the secrets of webhooks, so that
+/// webhooks cannot be configured to send the other secrets of sync-team to arbitrary URLs
+pub(crate) const WEBHOOK_SECRET_ENV_PREFIX: &str = "SYNC_TEAM_SECRET_";
+
+#[derive(serde::Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub(crate) struct Webhook {
+    pub url: String,
+    #[serde(default = "webhook_default_events")]
+    pub events: Vec<String>,
+    #[serde(default)]
+    pub content_type: WebhookContentType,
+    /// Name of the environment variable that contains the secret of the webhook
+    #[serde(default)]
+    pub secret_env: Option<String>,
+}
+
+fn webhook_default_events() -> Vec<String> {
+    vec!["push".to_string()]
+}
+
+#[derive(serde::Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub(crate) struct DeployKey {
+    pub title: String,
+    /// SHA256 fingerprint of the public key, as printed by `ssh-keygen -l`
+    pub fingerprint: String,
+}
+
+#[derive(serde::Deserialize, Debug, Clone, Copy, Default)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum WebhookContentType {
+    #[default]
+    Json,
+    Form,
+}
+
+#[derive(serde::Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub(crate) struct ActionsPermissions {
+    #[serde(default)]
+    pub default_workflow_permissions: WorkflowPermissions,
+    #[serde(default)]
+    pub can_approve_pull_requests: bool,
+    #[serde(default)]
+    pub allowed_actions: AllowedActions,
+    /// Only set when `allowed_actions` is `selected`
+    #[serde(default)]
+    pub selected_actions: Option<SelectedActions>,
+}
+
+#[derive(serde::Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub(crate) struct SelectedActions {
+    #[serde(default)]
+    pub github_owned_allowed: bool,
+    #[serde(default)]
+    pub verified_allowed: bool,
+    #[serde(default)]
+    pub patterns_allowed: Vec<String>,
+}
+
+#[derive(serde::Deserialize, Debug, Clone, Copy, Default)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum WorkflowPermissions {
+    #[default]
+    Read,
+    Write,
+}
+
+#[derive(serde::Deserialize, Debug, Clone, Copy, Default)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum AllowedActions {
+    #[default]
+    All,
+    LocalOnly,
+    Selected,
 }
 
 pub const fn branch_protection_default_prevent_creation() -> bool {
@@ -944,3 +1624,7 @@ pub const fn branch_protection_default_prevent_deletion() -> bool {
 pub const fn branch_protection_default_prevent_force_push() -> bool {
     true
 }
+
+fn alumni_group_name(group_name: &str) -> String {
+    format!("{group_name}-alumni")
+}
@@ -1,19 +1,37 @@
 use crate::data::Data;
 use crate::schema;
 use crate::schema::{
-    AllowedMergeApp, Bot, Email, Permissions, RepoPermission, TeamKind, ZulipMember,
+    AllowedMergeApp, Bot, Email, GitHubTeamPrivacy, Permissions, RepoPermission, TeamKind,
+    ZulipMember,
 };
 use anyhow::{Context as _, Error, ensure};
+use chrono::{DateTime, NaiveDate, Utc};
 use indexmap::IndexMap;
 use log::info;
-use rust_team_data::v1;
+use ring::signature::Ed25519KeyPair;
 use rust_team_data::v1::{BranchProtectionMode, Crate, CrateTeamOwner, RepoMember};
+use rust_team_data::{v1, v2};
+use schemars::schema_for;
 use std::collections::HashMap;
 use std::path::Path;
 
+mod changes;
+mod compress;
+mod ics;
+mod index;
+mod integrity;
+
+pub(crate) use changes::Previous;
+pub(crate) use integrity::load_signing_key;
+
 pub(crate) struct Generator<'a> {
     dest: &'a Path,
     data: &'a Data,
+    previous: Option<Previous>,
+    timestamp: Option<DateTime<Utc>>,
+    build_info: v1::BuildInfo,
+    signing_key: Option<Ed25519KeyPair>,
+    compress: bool,
 }
 
 impl<'a> Generator<'a> {
@@ -23,25 +41,82 @@ impl<'a> Generator<'a> {
         }
         std::fs::create_dir_all(dest)?;
 
-        Ok(Generator { dest, data })
+        Ok(Generator {
+            dest,
+            data,
+            previous: None,
+            timestamp: None,
+            build_info: v1::BuildInfo {
+                commit: None,
+                timestamp: None,
+                generator_version: env!("CARGO_PKG_VERSION").to_string(),
+            },
+            signing_key: None,
+            compress: false,
+        })
+    }
+
+    /// Record where the API was built from in `build-info.json`
+    pub(crate) fn with_source(
+        mut self,
+        commit: Option<String>,
+        timestamp: Option<DateTime<Utc>>,
+    ) -> Self {
+        self.build_info.commit = commit;
+        self.build_info.timestamp = timestamp.map(|timestamp| timestamp.to_rfc3339());
+        self.timestamp = timestamp;
+        self
+    }
+
+    /// The day of the build, which is the day of its source timestamp if there's one, so that
+    /// rebuilding the same source gives the same output
+    fn today(&self) -> NaiveDate {
+        self.timestamp.unwrap_or_else(Utc::now).date_naive()
+    }
+
+    /// Write gzip (`.json.gz`) and brotli (`.json.br`) copies of every endpoint
+    pub(crate) fn with_compression(mut self) -> Self {
+        self.compress = true;
+        self
+    }
+
+    /// Sign the `SHA256SUMS` manifest
+    pub(crate) fn with_signing_key(mut self, key: Ed25519KeyPair) -> Self {
+        self.signing_key = Some(key);
+        self
+    }
+
+    /// Append the membership changes since a previous build to its feed
+    pub(crate) fn with_previous(mut self, previous: Previous) -> Self {
+        self.previous = Some(previous);
+        self
     }
 
     pub(crate) fn generate(&self) -> Result<(), Error> {
         self.generate_teams()?;
+        self.generate_changes()?;
         self.generate_repos()?;
+        self.generate_orgs()?;
         self.generate_lists()?;
         self.generate_zulip_groups()?;
         self.generate_zulip_streams()?;
         self.generate_permissions()?;
         self.generate_rfcbot()?;
         self.generate_zulip_map()?;
+        self.generate_zulip_roles()?;
         self.generate_people()?;
+        self.generate_meetings()?;
+        self.generate_schemas()?;
+        self.add("build-info.json", &self.build_info)?;
         self.generate_index_html()?;
+        // Last, as it covers all the other files
+        self.generate_manifest()?;
         Ok(())
     }
 
     fn generate_repos(&self) -> Result<(), Error> {
         let mut repos: IndexMap<String, Vec<v1::Repo>> = IndexMap::new();
+        let mut github_ids = HashMap::new();
         let repo_iter = self
             .data
             .repos()
@@ -64,22 +139,32 @@ impl<'a> Generator<'a> {
                         BranchProtectionMode::PrRequired {
                             ci_checks: b.ci_checks.clone(),
                             required_approvals: b.required_approvals.unwrap_or(1),
+                            requires_code_owner_reviews: b.requires_code_owner_reviews,
                         }
                     } else {
                         BranchProtectionMode::PrNotRequired
                     },
                     allowed_merge_teams: b.allowed_merge_teams.clone(),
-                    allowed_merge_apps: b
-                        .allowed_merge_apps
-                        .iter()
-                        .map(|app| match app {
-                            AllowedMergeApp::RustTimer => v1::MergeBot::RustTimer,
-                            AllowedMergeApp::Bors => v1::MergeBot::Bors,
-                            AllowedMergeApp::WorkflowsCratesIo => v1::MergeBot::WorkflowsCratesIo,
-                            AllowedMergeApp::PromoteRelease => v1::MergeBot::PromoteRelease,
-                        })
-                        .collect(),
+                    allowed_merge_apps: b.allowed_merge_apps.iter().map(merge_bot).collect(),
+                    allowed_push_apps: b.allowed_push_apps.clone(),
+                    pr_bypass_teams: b.pr_bypass_teams.clone(),
+                    pr_bypass_apps: b.pr_bypass_apps.iter().map(merge_bot).collect(),
                     merge_queue: b.merge_queue,
+                    merge_queue_settings: b.merge_queue_settings.as_ref().map(|settings| {
+                        v1::MergeQueueSettings {
+                            merge_method: match settings.method {
+                                schema::MergeQueueMethod::Merge => v1::MergeQueueMethod::Merge,
+                                schema::MergeQueueMethod::Squash => v1::MergeQueueMethod::Squash,
+                                schema::MergeQueueMethod::Rebase => v1::MergeQueueMethod::Rebase,
+                            },
+                            min_entries_to_merge: settings.min_entries_to_merge,
+                            max_entries_to_merge: settings.max_entries_to_merge,
+                            max_entries_to_build: settings.max_entries_to_build,
+                        }
+                    }),
+                    requires_signed_commits: b.requires_signed_commits,
+                    required_linear_history: b.required_linear_history,
+                    requires_conversation_resolution: b.requires_conversation_resolution,
                     prevent_creation: b.prevent_creation,
                     prevent_update: b.prevent_update,
                     prevent_deletion: b.prevent_deletion,
@@ -94,6 +179,7 @@ impl<'a> Generator<'a> {
                 name: r.name.clone(),
                 description: r.description.clone(),
                 homepage: r.homepage.clone(),
+                topics: r.topics.clone(),
                 private: r.private_non_synced.unwrap_or(false),
                 bots: r
                     .bots
@@ -119,6 +205,9 @@ impl<'a> Generator<'a> {
                             RepoPermission::Write => v1::RepoPermission::Write,
                             RepoPermission::Maintain => v1::RepoPermission::Maintain,
                             RepoPermission::Triage => v1::RepoPermission::Triage,
+                            RepoPermission::Custom(role) => {
+                                v1::RepoPermission::Custom(role.clone())
+                            }
                         };
 
                         // Look up the team by name and get all its GitHub teams
@@ -146,16 +235,26 @@ impl<'a> Generator<'a> {
                         .access
                         .individuals
                         .iter()
-                        .map(|(name, permission)| {
-                            let permission = match permission {
+                        // Expired access is removed by the next synchronization
+                        .filter(|(_, access)| !access.is_expired())
+                        .map(|(name, access)| {
+                            let permission = match &access.permission {
                                 RepoPermission::Admin => v1::RepoPermission::Admin,
                                 RepoPermission::Write => v1::RepoPermission::Write,
                                 RepoPermission::Maintain => v1::RepoPermission::Maintain,
                                 RepoPermission::Triage => v1::RepoPermission::Triage,
+                                RepoPermission::Custom(role) => {
+                                    v1::RepoPermission::Custom(role.clone())
+                                }
                             };
                             v1::RepoMember {
                                 name: name.clone(),
                                 permission,
+                                former_names: self
+                                    .data
+                                    .person(name)
+                                    .map(|person| person.former_github_usernames().to_vec())
+                                    .unwrap_or_default(),
                             }
                         })
                         .collect();
@@ -208,6 +307,9 @@ impl<'a> Generator<'a> {
                                 v1::Environment {
                                     branches: env.branches.clone(),
                                     tags: env.tags.clone(),
+                                    reviewers: env.reviewers.clone(),
+                                    wait_timer: env.wait_timer,
+                                    protected_branches_only: env.protected_branches_only,
                                 },
                             )
                         })
@@ -217,19 +319,154 @@ impl<'a> Generator<'a> {
                 },
                 archived,
                 auto_merge_enabled: !managed_by_bors,
+                allow_squash_merge: r.allow_squash_merge,
+                allow_merge_commit: r.allow_merge_commit,
+                allow_rebase_merge: r.allow_rebase_merge,
+                delete_branch_on_merge: r.delete_branch_on_merge,
+                default_branch: r.default_branch.clone(),
+                has_issues: r.has_issues,
+                has_wiki: r.has_wiki,
+                has_projects: r.has_projects,
+                has_discussions: r.has_discussions,
+                use_rulesets: r.use_rulesets,
+                labels: r
+                    .labels
+                    .iter()
+                    .map(|label| v1::Label {
+                        name: label.name.clone(),
+                        color: label.color.to_lowercase(),
+                        description: label.description.clone(),
+                    })
+                    .collect(),
+                webhooks: r
+                    .webhooks
+                    .iter()
+                    .map(|webhook| v1::Webhook {
+                        url: webhook.url.clone(),
+                        events: webhook.events.clone(),
+                        content_type: match webhook.content_type {
+                            schema::WebhookContentType::Json => v1::WebhookContentType::Json,
+                            schema::WebhookContentType::Form => v1::WebhookContentType::Form,
+                        },
+                        secret_env: webhook.secret_env.clone(),
+                    })
+                    .collect(),
+                autolinks: r
+                    .autolinks
+                    .iter()
+                    .map(|(prefix, template)| (prefix.clone(), template.clone()))
+                    .collect(),
+                deploy_keys: r.deploy_keys.as_ref().map(|keys| {
+                    keys.iter()
+                        .map(|key| v1::DeployKey {
+                            title: key.title.clone(),
+                            fingerprint: key.fingerprint.clone(),
+                        })
+                        .collect()
+                }),
+                actions: r.actions.as_ref().map(|actions| v1::ActionsPermissions {
+                    default_workflow_permissions: match actions.default_workflow_permissions {
+                        schema::WorkflowPermissions::Read => v1::WorkflowPermissions::Read,
+                        schema::WorkflowPermissions::Write => v1::WorkflowPermissions::Write,
+                    },
+                    can_approve_pull_requests: actions.can_approve_pull_requests,
+                    allowed_actions: match actions.allowed_actions {
+                        schema::AllowedActions::All => v1::AllowedActions::All,
+                        schema::AllowedActions::LocalOnly => v1::AllowedActions::LocalOnly,
+                        schema::AllowedActions::Selected => v1::AllowedActions::Selected,
+                    },
+                    selected_actions: actions.selected_actions.as_ref().map(|selected| {
+                        let mut patterns_allowed = selected.patterns_allowed.clone();
+                        patterns_allowed.sort();
+                        v1::SelectedActions {
+                            github_owned_allowed: selected.github_owned_allowed,
+                            verified_allowed: selected.verified_allowed,
+                            patterns_allowed,
+                        }
+                    }),
+                }),
+                template: r.template.clone(),
+                archive_at: r.archive_at.map(|date| date.to_string()),
             };
 
             self.add(&format!("v1/repos/{}.json", r.name), &repo)?;
+            if let Some(github_id) = r.github_id {
+                github_ids.insert((r.org.clone(), r.name.clone()), github_id);
+            }
             repos.entry(r.org.clone()).or_default().push(repo);
         }
         repos
             .values_mut()
             .for_each(|r| r.sort_by(|r1, r2| r1.name.cmp(&r2.name)));
+        repos.sort_keys();
+
+        for (org, org_repos) in &repos {
+            let org_repos = v2::OrgRepos {
+                org: org.clone(),
+                repos: org_repos
+                    .iter()
+                    .map(|repo| {
+                        let repo = v2::Repo {
+                            id: github_ids
+                                .get(&(repo.org.clone(), repo.name.clone()))
+                                .copied(),
+                            repo: repo.clone(),
+                        };
+                        (repo.repo.name.clone(), repo)
+                    })
+                    .collect(),
+            };
+            self.add(&format!("v2/repos/{org}.json"), &org_repos)?;
+        }
+        let orgs = repos.keys().cloned().collect();
+        self.add("v2/repos.json", &v2::RepoOrgs { orgs })?;
 
         self.add("v1/repos.json", &v1::Repos { repos })?;
         Ok(())
     }
 
+    fn generate_orgs(&self) -> Result<(), Error> {
+        let mut orgs: IndexMap<String, v1::Org> = self
+            .data
+            .orgs()
+            .map(|org| {
+                let org = v1::Org {
+                    name: org.name.clone(),
+                    default_repository_permission: match org.default_repository_permission {
+                        schema::DefaultRepoPermission::None => v1::DefaultRepoPermission::None,
+                        schema::DefaultRepoPermission::Read => v1::DefaultRepoPermission::Read,
+                        schema::DefaultRepoPermission::Write => v1::DefaultRepoPermission::Write,
+                        schema::DefaultRepoPermission::Admin => v1::DefaultRepoPermission::Admin,
+                    },
+                    members_can_create_repositories: org.members_can_create_repositories,
+                    members_can_fork_private_repositories: org
+                        .members_can_fork_private_repositories,
+                };
+                (org.name.clone(), org)
+            })
+            .collect();
+        orgs.sort_keys();
+
+        self.add("v1/orgs.json", &v1::Orgs { orgs })?;
+        Ok(())
+    }
+
+    /// Without a previous build the feed starts empty
+    fn generate_changes(&self) -> Result<(), Error> {
+        let changes = match &self.previous {
+            Some(previous) => {
+                let teams = convert_teams(
+                    self.data,
+                    self.data.teams().chain(self.data.archived_teams()),
+                )?;
+                let date = self.today().to_string();
+                previous.append_changes(&teams, &date)
+            }
+            None => v1::MembershipChanges::default(),
+        };
+        self.add("v1/changes.json", &changes)
+    }
+
     fn generate_teams(&self) -> Result<(), Error> {
         let teams = convert_teams(self.data, self.data.teams())?;
         for (name, team) in &teams {
@@ -261,6 +498,16 @@ impl<'a> Generator<'a> {
                 v1::List {
                     address: list.address().to_string(),
                     members,
+                    access_level: match list.access_level() {
+                        schema::ListAccessLevel::Everyone => v1::ListAccessLevel::Everyone,
+                        schema::ListAccessLevel::Members => v1::ListAccessLevel::Members,
+                        schema::ListAccessLevel::Readonly => v1::ListAccessLevel::Readonly,
+                    },
+                    reply_preference: match list.reply_preference() {
+                        schema::ListReplyPreference::Sender => v1::ListReplyPreference::Sender,
+                        schema::ListReplyPreference::List => v1::ListReplyPreference::List,
+                    },
+                    priority: list.priority(),
                 },
             );
         }
@@ -292,6 +539,11 @@ impl<'a> Generator<'a> {
                             ZulipMember::MemberWithoutId { .. } => None,
                         })
                         .collect(),
+                    mention_policy: group.mention_policy().map(|policy| match policy {
+                        schema::ZulipMentionPolicy::Everyone => v1::ZulipMentionPolicy::Everyone,
+                        schema::ZulipMentionPolicy::Members => v1::ZulipMentionPolicy::Members,
+                        schema::ZulipMentionPolicy::Admins => v1::ZulipMentionPolicy::Admins,
+                    }),
                 },
             );
         }
@@ -323,6 +575,7 @@ impl<'a> Generator<'a> {
                             ZulipMember::MemberWithoutId { .. } => None,
                         })
                         .collect(),
+                    sensitive: stream.is_sensitive(),
                 },
             );
         }
@@ -333,6 +586,7 @@ impl<'a> Generator<'a> {
     }
 
     fn generate_permissions(&self) -> Result<(), Error> {
+        let mut permissions = IndexMap::new();
         for perm in &Permissions::available(self.data.config()) {
             let allowed = crate::permissions::allowed_people(self.data, perm)?;
             let mut github_users = allowed
@@ -362,8 +616,9 @@ impl<'a> Generator<'a> {
             // The sort operation here is necessary to ensure a stable output for the snapshot tests.
             people.sort();
 
+            let path = v1::Permissions::path(perm);
             self.add(
-                &format!("v1/permissions/{}.json", perm.replace('-', "_")),
+                &format!("v1/{path}"),
                 &v1::Permission {
                     people,
                     github_users,
@@ -371,7 +626,10 @@ impl<'a> Generator<'a> {
                     discord_ids,
                 },
             )?;
+            permissions.insert(perm.clone(), path);
         }
+        permissions.sort_keys();
+        self.add("v1/permissions.json", &v1::Permissions { permissions })?;
         Ok(())
     }
 
@@ -422,22 +680,65 @@ impl<'a> Generator<'a> {
         Ok(())
     }
 
+    fn generate_zulip_roles(&self) -> Result<(), Error> {
+        let mut users = IndexMap::new();
+        let mut managed_roles = Vec::new();
+
+        for team in self.data.teams() {
+            let Some(role) = team.zulip_role() else {
+                continue;
+            };
+            let role = match role {
+                schema::ZulipRole::Moderator => v1::ZulipRole::Moderator,
+                schema::ZulipRole::Administrator => v1::ZulipRole::Administrator,
+            };
+            if !managed_roles.contains(&role) {
+                managed_roles.push(role);
+            }
+            for member in team.members(self.data)? {
+                let Some(zulip_id) = self.data.person(member).and_then(|p| p.zulip_id()) else {
+                    continue;
+                };
+                let current = users.entry(zulip_id).or_insert(role);
+                *current = (*current).max(role);
+            }
+        }
+
+        users.sort_keys();
+        managed_roles.sort();
+        self.add(
+            "v1/zulip-roles.json",
+            &v1::ZulipRoles {
+                users,
+                managed_roles,
+            },
+        )?;
+        Ok(())
+    }
+
     fn generate_people(&self) -> Result<(), Error> {
         let mut people = IndexMap::new();
 
         for person in self.data.people() {
-            people.insert(
-                person.github().into(),
-                v1::Person {
-                    name: person.name().into(),
-                    email: match person.email() {
-                        Email::Missing | Email::Disabled => None,
-                        Email::Present(s) => Some(s.into()),
-                    },
-                    github_id: person.github_id(),
-                    github_sponsors: person.has_github_sponsors(),
+            let data = v1::Person {
+                name: person.name().into(),
+                email: match person.email() {
+                    Email::Missing | Email::Disabled => None,
+                    Email::Present(s) => Some(s.into()),
                 },
-            );
+                github_id: person.github_id(),
+                github_sponsors: person.has_github_sponsors(),
+                website: person.website().map(|website| website.into()),
+                pronouns: person.pronouns().map(|pronouns| pronouns.into()),
+                timezone: person.timezone().map(|timezone| timezone.into()),
+                on_leave_until: person
+                    .on_leave_until()
+                    .filter(|until| *until >= self.today())
+                    .map(|until| until.to_string()),
+                former_github_usernames: person.former_github_usernames().to_vec(),
+            };
+            self.add(&format!("v1/people/{}.json", person.github()), &data)?;
+            people.insert(person.github().into(), data);
         }
 
         people.sort_keys();
@@ -447,13 +748,87 @@ impl<'a> Generator<'a> {
         Ok(())
     }
 
-    fn generate_index_html(&self) -> Result<(), Error> {
-        const CONTENT: &[u8] = b"\
-            <!DOCTYPE html>\n\
-            <meta charset=\"utf-8\">\n\
-            <p>See <a href=\"https://github.com/rust-lang/team\">rust-lang/team</a>.</p>\n\
-        ";
+    /// Calendars of the meetings of each team, and of all the teams
+    fn generate_meetings(&self) -> Result<(), Error> {
+        let mut all = ics::Calendar::new("Rust team meetings");
+        for team in self.data.teams() {
+            if team.meetings().is_empty() {
+                continue;
+            }
+            let name = team
+                .website_data()
+                .map(|website| website.name())
+                .unwrap_or(team.name());
+            let mut calendar = ics::Calendar::new(&format!("{name} meetings"));
+            for meeting in team.meetings() {
+                calendar
+                    .add_meeting(team, meeting)
+                    .and_then(|()| all.add_meeting(team, meeting))
+                    .with_context(|| {
+                        format!(
+                            "failed to render meeting `{}` of `{}`",
+                            meeting.name,
+                            team.name()
+                        )
+                    })?;
+            }
+            let path = format!("v1/teams/{}.ics", team.name());
+            info!("writing calendar {path}...");
+            self.write(&path, calendar.render().as_bytes())?;
+        }
+        info!("writing calendar v1/meetings.ics...");
+        self.write("v1/meetings.ics", all.render().as_bytes())
+    }
+
+    /// JSON Schemas of the endpoints, for consumers that can't use `rust_team_data`
+    fn generate_schemas(&self) -> Result<(), Error> {
+        let schemas = [
+            ("v1/schemas/teams.json", schema_for!(v1::Teams)),
+            ("v1/schemas/team.json", schema_for!(v1::Team)),
+            ("v1/schemas/repos.json", schema_for!(v1::Repos)),
+            ("v1/schemas/repo.json", schema_for!(v1::Repo)),
+            ("v1/schemas/orgs.json", schema_for!(v1::Orgs)),
+            ("v1/schemas/lists.json", schema_for!(v1::Lists)),
+            ("v1/schemas/zulip-groups.json", schema_for!(v1::ZulipGroups)),
+            (
+                "v1/schemas/zulip-streams.json",
+                schema_for!(v1::ZulipStreams),
+            ),
+            ("v1/schemas/zulip-roles.json", schema_for!(v1::ZulipRoles)),
+            ("v1/schemas/zulip-map.json", schema_for!(v1::ZulipMapping)),
+            ("v1/schemas/permissions.json", schema_for!(v1::Permissions)),
+            ("v1/schemas/permission.json", schema_for!(v1::Permission)),
+            ("v1/schemas/rfcbot.json", schema_for!(v1::Rfcbot)),
+            ("v1/schemas/people.json", schema_for!(v1::People)),
+            ("v1/schemas/build-info.json", schema_for!(v1::BuildInfo)),
+            (
+                "v1/schemas/changes.json",
+                schema_for!(v1::MembershipChanges),
+            ),
+            ("v1/schemas/person.json", schema_for!(v1::Person)),
+            ("v2/schemas/repos.json", schema_for!(v2::RepoOrgs)),
+            ("v2/schemas/org-repos.json", schema_for!(v2::OrgRepos)),
+        ];
+        for (path, schema) in schemas {
+            self.add(path, &schema)?;
+        }
+        Ok(())
+    }
 
+    fn generate_manifest(&self) -> Result<(), Error> {
+        let manifest = integrity::manifest(self.dest)?;
+        info!("writing {}...", integrity::MANIFEST);
+        self.write(integrity::MANIFEST, manifest.as_bytes())?;
+        if let Some(key) = &self.signing_key {
+            info!("writing {}...", integrity::SIGNATURE);
+            let signature = key.sign(manifest.as_bytes());
+            self.write(integrity::SIGNATURE, signature.as_ref())?;
+        }
+        Ok(())
+    }
+
+    /// Index pages listing the endpoints of every directory, for humans exploring the API
+    fn generate_index_html(&self) -> Result<(), Error> {
         // GitHub has a security issue where a domain can point to GitHub Pages without any
         // repository attached to it. In that case, anyone can attach their own (malicious)
         // repo to the domain, effectively taking over it.
@@ -466,8 +841,23 @@ impl<'a> Generator<'a> {
         //
         // So let's just generate an index.html and reduce the volume of bogus reports.
 
-        info!("writing index.html...");
-        self.write("index.html", CONTENT)
+        let mut pending = vec![String::new()];
+        while let Some(path) = pending.pop() {
+            let (dirs, mut files) = index::entries(&self.dest.join(&path))?;
+            if path.is_empty() {
+                // Generated after the index pages, as they cover them
+                files.push(integrity::MANIFEST.into());
+                if self.signing_key.is_some() {
+                    files.push(integrity::SIGNATURE.into());
+                }
+                files.sort();
+            }
+            let index = format!("{path}index.html");
+            info!("writing {index}...");
+            self.write(&index, index::render(&path, &dirs, &files).as_bytes())?;
+            pending.extend(dirs.into_iter().map(|dir| format!("{path}{dir}/")));
+        }
+        Ok(())
     }
 
     fn add<T>(&self, path: &str, obj: &T) -> Result<(), Error>
@@ -477,6 +867,10 @@ impl<'a> Generator<'a> {
         info!("writing API object {path}...");
         let json = serde_json::to_string_pretty(obj)?;
         self.write(path, json.as_bytes())?;
+        if self.compress {
+            self.write(&format!("{path}.gz"), &compress::gzip(json.as_bytes())?)?;
+            self.write(&format!("{path}.br"), &compress::brotli(json.as_bytes())?)?;
+        }
 
         let obj2: T =
             serde_json::from_str(&json).with_context(|| format!("failed to deserialize {path}"))?;
@@ -500,6 +894,15 @@ impl<'a> Generator<'a> {
     }
 }
 
+fn merge_bot(app: &AllowedMergeApp) -> v1::MergeBot {
+    match app {
+        AllowedMergeApp::RustTimer => v1::MergeBot::RustTimer,
+        AllowedMergeApp::Bors => v1::MergeBot::Bors,
+        AllowedMergeApp::WorkflowsCratesIo => v1::MergeBot::WorkflowsCratesIo,
+        AllowedMergeApp::PromoteRelease => v1::MergeBot::PromoteRelease,
+    }
+}
+
 fn convert_teams<'a>(
     data: &Data,
     teams: impl Iterator<Item = &'a schema::Team>,
@@ -508,7 +911,11 @@ fn convert_teams<'a>(
 
     for team in teams {
         let mut website_roles = HashMap::new();
+        let mut joined_dates = HashMap::new();
         for member in team.explicit_members().iter().cloned() {
+            if let Some(joined) = member.joined {
+                joined_dates.insert(member.github.clone(), joined);
+            }
             website_roles.insert(member.github, member.roles);
         }
         for alum in team.explicit_alumni().iter().cloned() {
@@ -525,6 +932,8 @@ fn convert_teams<'a>(
                     github_id: person.github_id(),
                     is_lead: leads.contains(github_name),
                     roles: website_roles.get(*github_name).cloned().unwrap_or_default(),
+                    joined: joined_dates.get(*github_name).map(|date| date.to_string()),
+                    left: None,
                 });
             }
         }
@@ -543,6 +952,8 @@ fn convert_teams<'a>(
                         .get(alum.github.as_str())
                         .cloned()
                         .unwrap_or_default(),
+                    joined: alum.joined.map(|date| date.to_string()),
+                    left: alum.left.map(|date| date.to_string()),
                 });
             }
         }
@@ -551,6 +962,15 @@ fn convert_teams<'a>(
         let mut github_teams = team.github_teams(data)?;
         github_teams.sort();
 
+        let mut zulip_groups = team.zulip_group_names();
+        zulip_groups.sort();
+        let mut zulip_streams = team
+            .raw_zulip_streams()
+            .iter()
+            .map(|stream| stream.common.name.clone())
+            .collect::<Vec<_>>();
+        zulip_streams.sort();
+
         let team_data = v1::Team {
             name: team.name().into(),
             kind: match team.kind() {
@@ -569,7 +989,16 @@ fn convert_teams<'a>(
                     .map(|team| v1::GitHubTeam {
                         org: team.org.to_string(),
                         name: team.name.to_string(),
+                        team_id: team.team_id,
                         members: team.members.into_iter().map(|(_, id)| id).collect(),
+                        maintainers: team.maintainers.into_iter().map(|(_, id)| id).collect(),
+                        org_owners: team.org_owners,
+                        parent: team.parent.map(|p| p.to_string()),
+                        description: team.description.map(|d| d.to_string()),
+                        privacy: team.privacy.map(|p| match p {
+                            GitHubTeamPrivacy::Closed => v1::GitHubTeamPrivacy::Closed,
+                            GitHubTeamPrivacy::Secret => v1::GitHubTeamPrivacy::Secret,
+                        }),
                     })
                     .collect::<Vec<_>>(),
             })
@@ -592,6 +1021,8 @@ fn convert_teams<'a>(
                     description: role.description.clone(),
                 })
                 .collect(),
+            zulip_groups,
+            zulip_streams,
         };
         team_map.insert(team.name().into(), team_data);
     }
@@ -0,0 +1,206 @@
+//! Feed of the changes of team memberships between published revisions of the data.
+//!
+//! Each build compares the teams with a previous build of the static API, and appends the
+//! differences to the feed of that build, so that the feed grows with every deployment instead
+//! of consumers having to diff the git history.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use anyhow::{Context as _, Error};
+use indexmap::IndexMap;
+use rust_team_data::v1;
+
+/// Maximum number of changes kept in the feed, the oldest ones are dropped first
+const MAX_CHANGES: usize = 5000;
+
+/// The teams and the feed of a previous build of the static API
+pub(crate) struct Previous {
+    teams: IndexMap<String, v1::Team>,
+    changes: v1::MembershipChanges,
+}
+
+impl Previous {
+    pub(crate) fn load(dir: &Path) -> Result<Self, Error> {
+        let read = |file: &str| -> Result<Option<Vec<u8>>, Error> {
+            let path = dir.join("v1").join(file);
+            match std::fs::read(&path) {
+                Ok(content) => Ok(Some(content)),
+                Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+                Err(err) => Err(err).with_context(|| format!("failed to read {}", path.display())),
+            }
+        };
+
+        let mut teams = IndexMap::new();
+        for file in ["teams.json", "archived-teams.json"] {
+            let content = read(file)?
+                .with_context(|| format!("{} is not a build of the static API", dir.display()))?;
+            let v1::Teams { teams: parsed } = serde_json::from_slice(&content)
+                .with_context(|| format!("failed to deserialize the previous {file}"))?;
+            teams.extend(parsed);
+        }
+        // Builds from before the feed existed start it
+        let changes = match read("changes.json")? {
+            Some(content) => serde_json::from_slice(&content)
+                .context("failed to deserialize the previous changes.json")?,
+            None => v1::MembershipChanges::default(),
+        };
+        Ok(Self { teams, changes })
+    }
+
+    /// The feed of the previous build, followed by the changes made since then, limited to the
+    /// last `MAX_CHANGES`
+    pub(super) fn append_changes(
+        &self,
+        teams: &IndexMap<String, v1::Team>,
+        date: &str,
+    ) -> v1::MembershipChanges {
+        let mut changes = self.changes.clone();
+        changes
+            .changes
+            .extend(diff_memberships(&self.teams, teams, date));
+        let excess = changes.changes.len().saturating_sub(MAX_CHANGES);
+        changes.changes.drain(..excess);
+        changes
+    }
+}
+
+/// Members who joined or left each team, sorted by team. Members are compared by GitHub ID, so
+/// that renaming an account is not a change.
+fn diff_memberships(
+    old: &IndexMap<String, v1::Team>,
+    new: &IndexMap<String, v1::Team>,
+    date: &str,
+) -> Vec<v1::MembershipChange> {
+    fn members(team: Option<&v1::Team>) -> BTreeMap<u64, &v1::TeamMember> {
+        team.iter()
+            .flat_map(|team| &team.members)
+            .map(|member| (member.github_id, member))
+            .collect()
+    }
+
+    let mut names: Vec<&String> = old.keys().chain(new.keys()).collect();
+    names.sort();
+    names.dedup();
+
+    let mut changes = Vec::new();
+    for name in names {
+        let old_members = members(old.get(name));
+        let new_members = members(new.get(name));
+        let joined = new_members
+            .iter()
+            .filter(|(id, _)| !old_members.contains_key(id))
+            .map(|(_, member)| (*member, v1::MembershipChangeKind::Joined));
+        let left = old_members
+            .iter()
+            .filter(|(id, _)| !new_members.contains_key(id))
+            .map(|(_, member)| (*member, v1::MembershipChangeKind::Left));
+
+        let mut team_changes: Vec<_> = joined
+            .chain(left)
+            .map(|(member, kind)| v1::MembershipChange {
+                date: date.to_string(),
+                team: name.clone(),
+                name: member.name.clone(),
+                github: member.github.clone(),
+                github_id: member.github_id,
+                kind,
+            })
+            .collect();
+        team_changes.sort_by(|a, b| (a.kind, &a.github).cmp(&(b.kind, &b.github)));
+        changes.extend(team_changes);
+    }
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn member(github: &str, github_id: u64) -> v1::TeamMember {
+        v1::TeamMember {
+            name: github.to_uppercase(),
+            github: github.to_string(),
+            github_id,
+            is_lead: false,
+            roles: vec![],
+            joined: None,
+            left: None,
+        }
+    }
+
+    fn teams(teams: &[(&str, Vec<v1::TeamMember>)]) -> IndexMap<String, v1::Team> {
+        teams
+            .iter()
+            .map(|(name, members)| {
+                let team = v1::Team {
+                    name: name.to_string(),
+                    kind: v1::TeamKind::Team,
+                    subteam_of: None,
+                    top_level: None,
+                    members: members.clone(),
+                    alumni: vec![],
+                    github: None,
+                    website_data: None,
+                    roles: vec![],
+                    zulip_groups: vec![],
+                    zulip_streams: vec![],
+                };
+                (name.to_string(), team)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_diff_memberships() {
+        let old = teams(&[
+            ("lang", vec![member("alice", 1), member("bob", 2)]),
+            ("removed", vec![member("alice", 1)]),
+        ]);
+        let new = teams(&[
+            // Renamed accounts did not leave the team
+            ("lang", vec![member("alice-renamed", 1), member("carol", 3)]),
+            ("added", vec![member("bob", 2)]),
+        ]);
+
+        let changes: Vec<_> = diff_memberships(&old, &new, "2024-01-01")
+            .into_iter()
+            .map(|change| (change.team, change.github, change.kind))
+            .collect();
+        use v1::MembershipChangeKind::{Joined, Left};
+        assert_eq!(
+            changes,
+            [
+                ("added".to_string(), "bob".to_string(), Joined),
+                ("lang".to_string(), "carol".to_string(), Joined),
+                ("lang".to_string(), "bob".to_string(), Left),
+                ("removed".to_string(), "alice".to_string(), Left),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_append_changes_limit() {
+        let change = |github_id| v1::MembershipChange {
+            date: "2024-01-01".to_string(),
+            team: "lang".to_string(),
+            name: "Someone".to_string(),
+            github: "someone".to_string(),
+            github_id,
+            kind: v1::MembershipChangeKind::Joined,
+        };
+        let previous = Previous {
+            teams: teams(&[("lang", vec![])]),
+            changes: v1::MembershipChanges {
+                changes: (0..MAX_CHANGES as u64).map(change).collect(),
+            },
+        };
+
+        let new = teams(&[("lang", vec![member("alice", u64::MAX)])]);
+        let changes = previous.append_changes(&new, "2024-02-01").changes;
+        assert_eq!(changes.len(), MAX_CHANGES);
+        // The oldest change was dropped to make room for the new one
+        assert_eq!(changes[0].github_id, 1);
+        assert_eq!(changes[MAX_CHANGES - 1].github, "alice");
+    }
+}
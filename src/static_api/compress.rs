@@ -0,0 +1,47 @@
+//! Precompressed copies of the endpoints, so that the CDN can serve them without compressing
+//! the larger files on every request.
+
+use std::io::Write as _;
+
+use anyhow::Error;
+
+/// Gzip, without a modification time in the header so that the output is deterministic
+pub(super) fn gzip(content: &[u8]) -> Result<Vec<u8>, Error> {
+    let mut encoder = flate2::GzBuilder::new().mtime(0).write(
+        Vec::with_capacity(content.len() / 4),
+        flate2::Compression::best(),
+    );
+    encoder.write_all(content)?;
+    Ok(encoder.finish()?)
+}
+
+pub(super) fn brotli(content: &[u8]) -> Result<Vec<u8>, Error> {
+    let mut compressed = Vec::with_capacity(content.len() / 4);
+    let params = brotli::enc::BrotliEncoderParams {
+        quality: 11,
+        ..Default::default()
+    };
+    brotli::BrotliCompress(&mut &*content, &mut compressed, &params)?;
+    Ok(compressed)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read as _;
+
+    use super::*;
+
+    #[test]
+    fn test_roundtrip() -> Result<(), Error> {
+        let content = br#"{"teams": {"foo": {"name": "foo"}}}"#.repeat(100);
+
+        let mut decompressed = Vec::new();
+        flate2::read::GzDecoder::new(&*gzip(&content)?).read_to_end(&mut decompressed)?;
+        assert_eq!(decompressed, content);
+
+        let mut decompressed = Vec::new();
+        brotli::BrotliDecompress(&mut &*brotli(&content)?, &mut decompressed)?;
+        assert_eq!(decompressed, content);
+        Ok(())
+    }
+}
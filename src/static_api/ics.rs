@@ -0,0 +1,279 @@
+//! Rendering of team meetings as iCalendar files (RFC 5545), so that calendars can subscribe to
+//! them instead of teams maintaining their own invites.
+
+use std::collections::BTreeMap;
+
+use anyhow::{Context as _, Error};
+use chrono::{
+    DateTime, Datelike, Duration, NaiveDate, NaiveDateTime, Offset, TimeZone, Timelike, Utc,
+};
+use chrono_tz::{OffsetComponents, OffsetName, Tz, TzOffset};
+
+use crate::schema::{Meeting, Team};
+
+const DOMAIN: &str = "team-api.infra.rust-lang.org";
+
+pub(super) struct Calendar {
+    lines: Vec<String>,
+    /// Time zones referenced by the events, by name, with the year of their earliest event
+    timezones: BTreeMap<String, (Tz, i32)>,
+    events: Vec<String>,
+}
+
+impl Calendar {
+    pub(super) fn new(name: &str) -> Self {
+        Self {
+            lines: vec![
+                "BEGIN:VCALENDAR".into(),
+                "VERSION:2.0".into(),
+                "PRODID:-//rust-lang//team//EN".into(),
+                "CALSCALE:GREGORIAN".into(),
+                format!("X-WR-CALNAME:{}", escape(name)),
+            ],
+            timezones: BTreeMap::new(),
+            events: Vec::new(),
+        }
+    }
+
+    pub(super) fn add_meeting(&mut self, team: &Team, meeting: &Meeting) -> Result<(), Error> {
+        let tz: chrono_tz::Tz = meeting
+            .timezone
+            .parse()
+            .map_err(|_| anyhow::anyhow!("unknown time zone `{}`", meeting.timezone))?;
+        let start = meeting
+            .start
+            .and_local_timezone(tz)
+            .earliest()
+            .with_context(|| format!("{} doesn't exist in {tz}", meeting.start))?;
+        let team_name = team
+            .website_data()
+            .map(|website| website.name())
+            .unwrap_or(team.name());
+        let year = &mut self
+            .timezones
+            .entry(tz.name().to_string())
+            .or_insert((tz, meeting.start.year()))
+            .1;
+        *year = (*year).min(meeting.start.year());
+
+        self.events.push("BEGIN:VEVENT".into());
+        self.events
+            .push(format!("UID:{}-{}@{DOMAIN}", team.name(), meeting.id()));
+        // The stamp has to be deterministic for the output to only change with the data
+        self.events.push(format!(
+            "DTSTAMP:{}",
+            start.with_timezone(&Utc).format("%Y%m%dT%H%M%SZ")
+        ));
+        self.events.push(format!(
+            "DTSTART;TZID={tz}:{}",
+            meeting.start.format("%Y%m%dT%H%M%S")
+        ));
+        self.events
+            .push(format!("DURATION:PT{}M", meeting.duration));
+        if let Some(recurrence) = &meeting.recurrence {
+            self.events.push(format!("RRULE:{recurrence}"));
+        }
+        self.events.push(format!(
+            "SUMMARY:{}",
+            escape(&format!("{team_name}: {}", meeting.name))
+        ));
+        if let Some(link) = &meeting.video_link {
+            self.events.push(format!("LOCATION:{}", escape(link)));
+            self.events.push(format!("URL:{link}"));
+        }
+        self.events.push("END:VEVENT".into());
+        Ok(())
+    }
+
+    pub(super) fn render(mut self) -> String {
+        for (tz, year) in self.timezones.into_values() {
+            // Starting the definition the year before the earliest event ensures it covers all
+            // the events.
+            self.lines.extend(timezone(tz, year - 1));
+        }
+        self.lines.append(&mut self.events);
+        self.lines.push("END:VCALENDAR".into());
+        let mut out = String::new();
+        for line in &self.lines {
+            fold(line, &mut out);
+        }
+        out
+    }
+}
+
+/// Define a time zone, as calendars can't be expected to know the ones referenced by `TZID`.
+///
+/// The offset changes of `year` are assumed to happen on the same weekday of the same week of
+/// the month every year, which holds for most time zones until their rules change.
+fn timezone(tz: Tz, year: i32) -> Vec<String> {
+    let offset = |at: DateTime<Utc>| tz.offset_from_utc_datetime(&at.naive_utc());
+    let mut lines = vec!["BEGIN:VTIMEZONE".into(), format!("TZID:{tz}")];
+    // Observances without a recurrence rule only start at their onset
+    let mut observance = |kind: &str, onset: NaiveDateTime, from, to: TzOffset, recurring| {
+        lines.push(format!("BEGIN:{kind}"));
+        lines.push(format!("DTSTART:{}", onset.format("%Y%m%dT%H%M%S")));
+        lines.push(format!("TZOFFSETFROM:{}", format_offset(from)));
+        lines.push(format!("TZOFFSETTO:{}", format_offset(to.fix())));
+        if let Some(name) = to.abbreviation() {
+            lines.push(format!("TZNAME:{name}"));
+        }
+        if recurring {
+            lines.push(format!(
+                "RRULE:FREQ=YEARLY;BYMONTH={};BYDAY={}",
+                onset.month(),
+                weekday_of_month(onset.date())
+            ));
+        }
+        lines.push(format!("END:{kind}"));
+    };
+
+    let start = Utc.with_ymd_and_hms(year, 1, 1, 0, 0, 0).unwrap();
+    let mut transitions = Vec::new();
+    let mut minute = start;
+    while minute.year() == year {
+        // Offsets change at most once a day, so the exact minute is only looked for in the
+        // days they change in.
+        let step = if offset(minute).fix() == offset(minute + Duration::days(1)).fix() {
+            Duration::days(1)
+        } else {
+            Duration::minutes(1)
+        };
+        let next = minute + step;
+        let (before, after) = (offset(minute), offset(next));
+        if before.fix() != after.fix() {
+            transitions.push((next.naive_utc() + before.fix(), before.fix(), after));
+        }
+        minute = next;
+    }
+
+    if transitions.is_empty() {
+        let current = offset(start);
+        let onset = NaiveDate::from_ymd_opt(1970, 1, 1).unwrap().into();
+        observance("STANDARD", onset, current.fix(), current, false);
+    }
+    for (onset, from, to) in transitions {
+        let kind = if to.dst_offset().is_zero() {
+            "STANDARD"
+        } else {
+            "DAYLIGHT"
+        };
+        observance(kind, onset, from, to, true);
+    }
+    lines.push("END:VTIMEZONE".into());
+    lines
+}
+
+/// Format an offset from UTC as `+HHMM`, or `+HHMMSS` if it has seconds
+fn format_offset(offset: chrono::FixedOffset) -> String {
+    let seconds = offset.local_minus_utc();
+    let sign = if seconds < 0 { '-' } else { '+' };
+    let time = chrono::NaiveTime::MIN + Duration::seconds(seconds.abs().into());
+    if time.second() == 0 {
+        format!("{sign}{}", time.format("%H%M"))
+    } else {
+        format!("{sign}{}", time.format("%H%M%S"))
+    }
+}
+
+/// The weekday of a date and its position in the month, like `2SU` for the second Sunday, or
+/// `-1SU` for the last one
+fn weekday_of_month(date: NaiveDate) -> String {
+    let weekday = &date.weekday().to_string()[..2];
+    let last_week = (date + Duration::days(7)).month() != date.month();
+    if last_week {
+        format!("-1{}", weekday.to_uppercase())
+    } else {
+        format!("{}{}", date.day0() / 7 + 1, weekday.to_uppercase())
+    }
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' | ';' | ',' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            '\n' => escaped.push_str("\\n"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Lines are limited to 75 bytes, longer ones continue on lines starting with a space
+fn fold(line: &str, out: &mut String) {
+    let mut len = 0;
+    for c in line.chars() {
+        if len + c.len_utf8() > 75 {
+            out.push_str("\r\n ");
+            len = 1;
+        }
+        out.push(c);
+        len += c.len_utf8();
+    }
+    out.push_str("\r\n");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(escape("a, b; c\\d\ne"), r"a\, b\; c\\d\ne");
+    }
+
+    #[test]
+    fn test_timezone() {
+        assert_eq!(
+            timezone(chrono_tz::Asia::Kolkata, 2024),
+            [
+                "BEGIN:VTIMEZONE",
+                "TZID:Asia/Kolkata",
+                "BEGIN:STANDARD",
+                "DTSTART:19700101T000000",
+                "TZOFFSETFROM:+0530",
+                "TZOFFSETTO:+0530",
+                "TZNAME:IST",
+                "END:STANDARD",
+                "END:VTIMEZONE",
+            ]
+        );
+        assert_eq!(
+            timezone(chrono_tz::Australia::Sydney, 2024),
+            [
+                "BEGIN:VTIMEZONE",
+                "TZID:Australia/Sydney",
+                "BEGIN:STANDARD",
+                "DTSTART:20240407T030000",
+                "TZOFFSETFROM:+1100",
+                "TZOFFSETTO:+1000",
+                "TZNAME:AEST",
+                "RRULE:FREQ=YEARLY;BYMONTH=4;BYDAY=1SU",
+                "END:STANDARD",
+                "BEGIN:DAYLIGHT",
+                "DTSTART:20241006T020000",
+                "TZOFFSETFROM:+1000",
+                "TZOFFSETTO:+1100",
+                "TZNAME:AEDT",
+                "RRULE:FREQ=YEARLY;BYMONTH=10;BYDAY=1SU",
+                "END:DAYLIGHT",
+                "END:VTIMEZONE",
+            ]
+        );
+        assert_eq!(
+            format_offset(chrono::FixedOffset::west_opt(3 * 3600 + 30 * 60).unwrap()),
+            "-0330"
+        );
+    }
+
+    #[test]
+    fn test_fold() {
+        let mut out = String::new();
+        fold(&"é".repeat(40), &mut out);
+        let lines: Vec<_> = out.split_terminator("\r\n").collect();
+        assert_eq!(lines, [&"é".repeat(37), &format!(" {}", "é".repeat(3))]);
+    }
+}
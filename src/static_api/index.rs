@@ -0,0 +1,57 @@
+//! Browsable index pages listing the endpoints of each directory of the static API.
+
+use std::fmt::Write as _;
+use std::path::Path;
+
+use anyhow::Error;
+
+/// Render the index page of a directory, `path` being relative to the root of the API
+pub(super) fn render(path: &str, dirs: &[String], files: &[String]) -> String {
+    let title = format!("Index of /{path}");
+    let mut html = String::new();
+    html.push_str("<!DOCTYPE html>\n");
+    html.push_str("<meta charset=\"utf-8\">\n");
+    writeln!(html, "<title>{}</title>", escape(&title)).unwrap();
+    html.push_str("<p>See <a href=\"https://github.com/rust-lang/team\">rust-lang/team</a>.</p>\n");
+    writeln!(html, "<h1>{}</h1>", escape(&title)).unwrap();
+    html.push_str("<ul>\n");
+    if !path.is_empty() {
+        html.push_str("<li><a href=\"../\">../</a></li>\n");
+    }
+    let entries = dirs
+        .iter()
+        .map(|dir| format!("{dir}/"))
+        .chain(files.iter().cloned());
+    for entry in entries {
+        let entry = escape(&entry);
+        writeln!(html, "<li><a href=\"{entry}\">{entry}</a></li>").unwrap();
+    }
+    html.push_str("</ul>\n");
+    html
+}
+
+/// The subdirectories and the files of a directory, sorted. Index pages and precompressed
+/// copies are not listed.
+pub(super) fn entries(dir: &Path) -> Result<(Vec<String>, Vec<String>), Error> {
+    let mut dirs = Vec::new();
+    let mut files = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if entry.file_type()?.is_dir() {
+            dirs.push(name);
+        } else if name != "index.html" && !name.ends_with(".gz") && !name.ends_with(".br") {
+            files.push(name);
+        }
+    }
+    dirs.sort();
+    files.sort();
+    Ok((dirs, files))
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
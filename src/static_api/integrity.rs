@@ -0,0 +1,93 @@
+//! Build information and integrity manifest of the static API, so that consumers and mirrors can
+//! check that they have a complete and untampered snapshot.
+//!
+//! `SHA256SUMS` lists the SHA-256 of every generated file in the format of `sha256sum`, and can
+//! be checked with `sha256sum -c SHA256SUMS`. When a signing key is provided, `SHA256SUMS.sig`
+//! contains its raw Ed25519 signature, which can be checked with
+//! `openssl pkeyutl -verify -pubin -inkey <public key> -rawin -in SHA256SUMS -sigfile SHA256SUMS.sig`.
+
+use std::fmt::Write as _;
+use std::path::Path;
+
+use anyhow::{Context as _, Error};
+use base64::Engine as _;
+use ring::signature::Ed25519KeyPair;
+
+pub(super) const MANIFEST: &str = "SHA256SUMS";
+pub(super) const SIGNATURE: &str = "SHA256SUMS.sig";
+
+/// Load an Ed25519 private key, encoded as PKCS#8 PEM like the ones of
+/// `openssl genpkey -algorithm ed25519`
+pub(crate) fn load_signing_key(pem: &str) -> Result<Ed25519KeyPair, Error> {
+    let base64: String = pem
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with("-----"))
+        .collect();
+    let der = base64::engine::general_purpose::STANDARD
+        .decode(base64)
+        .context("the signing key is not valid PEM")?;
+    Ed25519KeyPair::from_pkcs8_maybe_unchecked(&der)
+        .map_err(|err| anyhow::anyhow!("the signing key is not an Ed25519 PKCS#8 key: {err}"))
+}
+
+/// The SHA-256 of every file of the directory, sorted by path
+pub(super) fn manifest(dir: &Path) -> Result<String, Error> {
+    let mut files = Vec::new();
+    collect_files(dir, dir, &mut files)?;
+    files.sort();
+
+    let mut manifest = String::new();
+    for path in files {
+        let content = std::fs::read(dir.join(&path))?;
+        let digest = ring::digest::digest(&ring::digest::SHA256, &content);
+        for byte in digest.as_ref() {
+            write!(manifest, "{byte:02x}").unwrap();
+        }
+        writeln!(manifest, "  {path}").unwrap();
+    }
+    Ok(manifest)
+}
+
+fn collect_files(root: &Path, dir: &Path, files: &mut Vec<String>) -> Result<(), Error> {
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_files(root, &path, files)?;
+            continue;
+        }
+        let relative = path.strip_prefix(root)?;
+        let relative = relative
+            .components()
+            .map(|component| component.as_os_str().to_str())
+            .collect::<Option<Vec<_>>>()
+            .with_context(|| format!("{} is not valid UTF-8", path.display()))?
+            .join("/");
+        if relative != MANIFEST && relative != SIGNATURE {
+            files.push(relative);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_manifest() -> Result<(), Error> {
+        let dir = tempfile::tempdir()?;
+        std::fs::create_dir(dir.path().join("v1"))?;
+        std::fs::write(dir.path().join("v1").join("teams.json"), "{}")?;
+        std::fs::write(dir.path().join("index.html"), "")?;
+        // A previous manifest is not part of the manifest
+        std::fs::write(dir.path().join(MANIFEST), "outdated")?;
+
+        assert_eq!(
+            manifest(dir.path())?,
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855  index.html\n\
+             44136fa355b3678a1146ad16f7e8649e94fb4fc21fe77e8310c060f61caaff8a  v1/teams.json\n"
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,603 @@
+use super::{EmailApi, List, Route, Senders, Suppression, SuppressionReason, pack_members};
+use crate::sync::utils::ResponseExt;
+use anyhow::{Context as _, Error, bail};
+use async_trait::async_trait;
+use log::info;
+use reqwest::{
+    Method,
+    header::{self, HeaderValue},
+    {Client, RequestBuilder},
+};
+use secrecy::{ExposeSecret, SecretString};
+
+const DESCRIPTION: &str = "managed by an automatic script on github";
+
+// Limit (in bytes) of the size of a Mailgun rule's actions list.
+const ACTIONS_SIZE_LIMIT_BYTES: usize = 4000;
+
+pub(super) struct Mailgun {
+    token: SecretString,
+    client: Client,
+    dry_run: bool,
+}
+
+impl Mailgun {
+    pub(super) fn new(token: SecretString, dry_run: bool) -> Self {
+        Self {
+            token,
+            client: Client::new(),
+            dry_run,
+        }
+    }
+
+    async fn get_routes_page(&self, skip: Option<u64>) -> Result<RoutesResponse, Error> {
+        let url = if let Some(skip) = skip {
+            format!("routes?skip={skip}")
+        } else {
+            "routes".into()
+        };
+        self.request(Method::GET, &url)
+            .send()
+            .await?
+            .error_for_status()?
+            .json_annotated()
+            .await
+    }
+
+    async fn create_mailgun_route(
+        &self,
+        priority: i32,
+        description: &str,
+        expression: &str,
+        actions: &[String],
+    ) -> Result<(), Error> {
+        if self.dry_run {
+            return Ok(());
+        }
+
+        let priority_str = priority.to_string();
+        let mut form = vec![
+            ("priority", priority_str.as_str()),
+            ("description", description),
+            ("expression", expression),
+        ];
+        for action in actions {
+            form.push(("action", action.as_str()));
+        }
+
+        self.request(Method::POST, "routes")
+            .form(&form)
+            .send()
+            .await?
+            .error_for_status()?;
+
+        Ok(())
+    }
+
+    async fn update_mailgun_route(
+        &self,
+        id: &str,
+        priority: i32,
+        expression: &str,
+        actions: &[String],
+    ) -> Result<(), Error> {
+        if self.dry_run {
+            return Ok(());
+        }
+
+        let priority_str = priority.to_string();
+        let mut form = vec![
+            ("priority", priority_str.as_str()),
+            ("expression", expression),
+        ];
+        for action in actions {
+            form.push(("action", action.as_str()));
+        }
+
+        self.request(Method::PUT, &format!("routes/{id}"))
+            .form(&form)
+            .send()
+            .await?
+            .error_for_status()?;
+
+        Ok(())
+    }
+
+    async fn delete_mailgun_route(&self, id: &str) -> Result<(), Error> {
+        info!("deleting route with ID {id}");
+        if self.dry_run {
+            return Ok(());
+        }
+
+        self.request(Method::DELETE, &format!("routes/{id}"))
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(())
+    }
+
+    /// Get a suppression list of a domain: `bounces`, `complaints` or `unsubscribes`.
+    ///
+    /// See https://documentation.mailgun.com/docs/mailgun/api-reference/openapi-final/tag/Bounces/
+    async fn get_suppression_list(
+        &self,
+        domain: &str,
+        kind: &str,
+    ) -> Result<Vec<MailgunSuppression>, Error> {
+        let mut suppressions = Vec::new();
+        let mut url = format!("{domain}/{kind}?limit=1000");
+        loop {
+            let page: SuppressionsResponse = self
+                .request(Method::GET, &url)
+                .send()
+                .await?
+                .error_for_status()?
+                .json_annotated()
+                .await?;
+            if page.items.is_empty() {
+                break;
+            }
+            suppressions.extend(page.items);
+            url = page.paging.next;
+        }
+        Ok(suppressions)
+    }
+
+    fn request(&self, method: Method, url: &str) -> RequestBuilder {
+        let url = if url.starts_with("https://") {
+            url.into()
+        } else {
+            format!("https://api.mailgun.net/v3/{url}")
+        };
+
+        self.client
+            .request(method, url)
+            .basic_auth("api", Some(&self.token.expose_secret()))
+            .header(
+                header::USER_AGENT,
+                HeaderValue::from_static(crate::USER_AGENT),
+            )
+    }
+}
+
+#[async_trait]
+impl EmailApi for Mailgun {
+    /// Mailgun only supports at most 4000 bytes of "actions" for each rule, and some of our
+    /// lists have so many members we're going over that limit.
+    ///
+    /// The official workaround for this, as explained in the docs [1], is to create multiple
+    /// rules, all with the same filter but each with a different set of actions.
+    ///
+    /// [1] https://documentation.mailgun.com/en/latest/user_manual.html#routes
+    fn split_members(&self, members: Vec<String>, previous: &[&[String]]) -> Vec<Vec<String>> {
+        pack_members(members, previous, ACTIONS_SIZE_LIMIT_BYTES, |member| {
+            build_route_action(member).len()
+        })
+    }
+
+    async fn get_routes(&self) -> anyhow::Result<Vec<Route>> {
+        let mut routes = Vec::new();
+        let mut response = self.get_routes_page(None).await?;
+        let mut cur = 0u64;
+        while !response.items.is_empty() {
+            cur += response.items.len() as u64;
+            routes.extend(response.items);
+            if cur >= response.total_count {
+                break;
+            }
+            response = self.get_routes_page(Some(cur)).await?;
+        }
+
+        routes
+            .into_iter()
+            .filter(|route| route.description == DESCRIPTION)
+            .map(|route| {
+                let (recipient, senders) = match route.expression.split_once(SENDERS_FILTER) {
+                    Some((recipient, senders)) => {
+                        (recipient, parse_senders(extract(senders, "", "\")"))?)
+                    }
+                    None => (route.expression.as_str(), Senders::Everyone),
+                };
+                let expression = extract(recipient, "match_recipient(\"", "\")");
+                let (address, excluded) = unmangle_recipient(expression)?;
+                Ok(Route {
+                    address,
+                    senders,
+                    excluded,
+                    members: route
+                        .actions
+                        .iter()
+                        .map(|action| extract(action, "forward(\"", "\")").to_string())
+                        .collect(),
+                    id: route.id,
+                    priority: route.priority,
+                })
+            })
+            .collect()
+    }
+
+    async fn create_route(&self, list: &List) -> anyhow::Result<()> {
+        let expr = build_route_expression(list)?;
+        let actions = build_route_actions(list).collect::<Vec<_>>();
+        self.create_mailgun_route(list.priority, DESCRIPTION, &expr, &actions)
+            .await
+    }
+
+    async fn update_route(&self, route: &Route, list: &List) -> anyhow::Result<()> {
+        let expr = build_route_expression(list)?;
+        let actions = build_route_actions(list).collect::<Vec<_>>();
+        self.update_mailgun_route(&route.id, list.priority, &expr, &actions)
+            .await
+    }
+
+    async fn delete_route(&self, route: &Route) -> anyhow::Result<()> {
+        self.delete_mailgun_route(&route.id).await
+    }
+
+    async fn get_suppressions(&self, domains: &[String]) -> anyhow::Result<Vec<Suppression>> {
+        let mut suppressions = Vec::new();
+        for domain in domains {
+            for (kind, reason) in [
+                ("bounces", SuppressionReason::Bounce),
+                ("complaints", SuppressionReason::Complaint),
+                ("unsubscribes", SuppressionReason::Unsubscribe),
+            ] {
+                let list = self
+                    .get_suppression_list(domain, kind)
+                    .await
+                    .with_context(|| format!("failed to get the {kind} of {domain}"))?;
+                suppressions.extend(list.into_iter().map(|suppression| Suppression {
+                    address: suppression.address,
+                    reason,
+                    details: suppression.error.filter(|error| !error.is_empty()),
+                }));
+            }
+        }
+        Ok(suppressions)
+    }
+}
+
+fn mangle_address(addr: &str) -> anyhow::Result<String> {
+    // Escape dots since they have a special meaning in Python regexes
+    let mangled = addr.replace('.', "\\.");
+
+    // Inject (?:\+.+)? before the '@' in the address to support '+' aliases like
+    // infra+botname@rust-lang.org
+    if let Some(at_pos) = mangled.find('@') {
+        let (user, domain) = mangled.split_at(at_pos);
+        Ok(format!("^{user}(?:\\+.+)?{domain}$"))
+    } else {
+        bail!("the address `{}` doesn't have any '@'", addr);
+    }
+}
+
+/// Recover the address of a list from the expression of its route
+fn unmangle_address(mangled: &str) -> anyhow::Result<String> {
+    let Some(inner) = mangled.strip_prefix('^').and_then(|m| m.strip_suffix('$')) else {
+        bail!("the route expression `{mangled}` wasn't created by sync-team");
+    };
+    Ok(inner.replacen("(?:\\+.+)?", "", 1).replace("\\.", "."))
+}
+
+/// Start of the expressions of catch-all lists excluding other addresses, which are followed by
+/// the users of the excluded addresses
+const CATCH_ALL_EXCLUDING: &str = "^(?!(?:";
+
+/// Separates the excluded users from the domain in the expressions of catch-all lists
+const CATCH_ALL_EXCLUDED_END: &str = ")(?:\\+.+)?@)";
+
+/// Matches any user of the domain in the expressions of catch-all lists
+const CATCH_ALL_USER: &str = "[^@]+@";
+
+/// Build the recipient pattern of a list.
+///
+/// Catch-all lists match every address of their domain, except the ones of the other lists
+/// (and their `+` aliases), so that they don't depend on the order the routes are evaluated in.
+fn mangle_recipient(list: &List) -> anyhow::Result<String> {
+    let Some(("*", domain)) = list.address.split_once('@') else {
+        return mangle_address(&list.address);
+    };
+    let domain = domain.replace('.', "\\.");
+    if list.excluded.is_empty() {
+        return Ok(format!("^{CATCH_ALL_USER}{domain}$"));
+    }
+    let users = list
+        .excluded
+        .iter()
+        .map(|address| match address.split_once('@') {
+            Some((user, _)) => Ok(user.replace('.', "\\.")),
+            None => bail!("the address `{address}` doesn't have any '@'"),
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    Ok(format!(
+        "{CATCH_ALL_EXCLUDING}{}{CATCH_ALL_EXCLUDED_END}{CATCH_ALL_USER}{domain}$",
+        users.join("|")
+    ))
+}
+
+/// Recover the address of a list, and the addresses excluded from catch-all lists, from the
+/// expression of its route
+fn unmangle_recipient(mangled: &str) -> anyhow::Result<(String, Vec<String>)> {
+    let (users, rest) = match mangled.strip_prefix(CATCH_ALL_EXCLUDING) {
+        Some(rest) => match rest.split_once(CATCH_ALL_EXCLUDED_END) {
+            Some((users, rest)) => (Some(users), rest),
+            None => bail!("the route expression `{mangled}` wasn't created by sync-team"),
+        },
+        None => match mangled.strip_prefix('^') {
+            Some(rest) if rest.starts_with(CATCH_ALL_USER) => (None, rest),
+            _ => return Ok((unmangle_address(mangled)?, Vec::new())),
+        },
+    };
+    let Some(domain) = rest
+        .strip_prefix(CATCH_ALL_USER)
+        .and_then(|rest| rest.strip_suffix('$'))
+    else {
+        bail!("the route expression `{mangled}` wasn't created by sync-team");
+    };
+    let domain = domain.replace("\\.", ".");
+    let mut excluded = users
+        .into_iter()
+        .flat_map(|users| users.split('|'))
+        .map(|user| format!("{}@{domain}", user.replace("\\.", ".")))
+        .collect::<Vec<_>>();
+    excluded.sort();
+    Ok((format!("*@{domain}"), excluded))
+}
+
+/// Filter added to the expression of the routes of lists restricting their senders
+const SENDERS_FILTER: &str = " and match_header(\"from\", \"";
+
+/// Delimit the alternatives of the pattern allowing a set of senders, so that each of them has to
+/// match the whole address, either alone or between `<` and `>`
+const SENDER_ADDRESSES_START: &str = "(?i)(^|<)(?:";
+const SENDER_ADDRESSES_END: &str = ")>?$";
+
+fn build_route_expression(list: &List) -> anyhow::Result<String> {
+    let mut expr = format!("match_recipient(\"{}\")", mangle_recipient(list)?);
+    let senders = match &list.senders {
+        Senders::Everyone => return Ok(expr),
+        Senders::Addresses(addresses) => {
+            let addresses = addresses
+                .iter()
+                .map(|address| regex::escape(address))
+                .collect::<Vec<_>>();
+            format!(
+                "{SENDER_ADDRESSES_START}{}{SENDER_ADDRESSES_END}",
+                addresses.join("|")
+            )
+        }
+        Senders::Domain(domain) => format!("(?i)@{}>?$", regex::escape(domain)),
+    };
+    expr.push_str(SENDERS_FILTER);
+    expr.push_str(&senders);
+    expr.push_str("\")");
+    Ok(expr)
+}
+
+/// Recover the allowed senders from the pattern matching the `From` header
+fn parse_senders(pattern: &str) -> anyhow::Result<Senders> {
+    let unescape = |s: &str| s.replace('\\', "");
+    if let Some(addresses) = pattern
+        .strip_prefix(SENDER_ADDRESSES_START)
+        .and_then(|p| p.strip_suffix(SENDER_ADDRESSES_END))
+    {
+        let mut addresses = addresses.split('|').map(unescape).collect::<Vec<_>>();
+        addresses.sort();
+        Ok(Senders::Addresses(addresses))
+    } else if let Some(domain) = pattern
+        .strip_prefix("(?i)@")
+        .and_then(|p| p.strip_suffix(">?$"))
+    {
+        Ok(Senders::Domain(unescape(domain)))
+    } else {
+        bail!("the senders pattern `{pattern}` wasn't created by sync-team");
+    }
+}
+
+fn build_route_action(member: &str) -> String {
+    format!("forward(\"{member}\")")
+}
+
+fn build_route_actions(list: &List) -> impl Iterator<Item = String> + '_ {
+    list.members.iter().map(|member| build_route_action(member))
+}
+
+fn extract<'a>(s: &'a str, prefix: &str, suffix: &str) -> &'a str {
+    assert!(s.starts_with(prefix), "`{s}` didn't start with `{prefix}`");
+    assert!(s.ends_with(suffix), "`{s}` didn't end with `{suffix}`");
+    &s[prefix.len()..s.len() - suffix.len()]
+}
+
+#[derive(serde::Deserialize)]
+struct RoutesResponse {
+    items: Vec<MailgunRoute>,
+    total_count: u64,
+}
+
+#[derive(serde::Deserialize)]
+struct MailgunRoute {
+    actions: Vec<String>,
+    expression: String,
+    id: String,
+    priority: i32,
+    description: serde_json::Value,
+}
+
+#[derive(serde::Deserialize)]
+struct SuppressionsResponse {
+    items: Vec<MailgunSuppression>,
+    paging: Paging,
+}
+
+#[derive(serde::Deserialize)]
+struct Paging {
+    next: String,
+}
+
+#[derive(serde::Deserialize)]
+struct MailgunSuppression {
+    address: String,
+    /// Only set for bounces
+    #[serde(default)]
+    error: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_route_actions() {
+        let list = List {
+            address: "list@example.com".into(),
+            members: vec![
+                "foo@example.com".into(),
+                "bar@example.com".into(),
+                "baz@example.net".into(),
+            ],
+            priority: 0,
+            senders: Senders::Everyone,
+            excluded: vec![],
+        };
+
+        assert_eq!(
+            vec![
+                "forward(\"foo@example.com\")",
+                "forward(\"bar@example.com\")",
+                "forward(\"baz@example.net\")",
+            ],
+            build_route_actions(&list).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_mangle_address() {
+        assert_eq!(
+            r"^list-name(?:\+.+)?@example\.com$",
+            mangle_address("list-name@example.com").unwrap()
+        );
+        assert!(mangle_address("list-name.example.com").is_err());
+        assert_eq!(
+            "list.name@example.com",
+            unmangle_address(&mangle_address("list.name@example.com").unwrap()).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_route_expression() {
+        let list = |senders| List {
+            address: "list@example.com".into(),
+            members: vec![],
+            priority: 0,
+            senders,
+            excluded: vec![],
+        };
+        for senders in [
+            Senders::Everyone,
+            Senders::Addresses(vec!["a+b@example.com".into(), "c@example.net".into()]),
+            Senders::Domain("example.com".into()),
+        ] {
+            let expr = build_route_expression(&list(senders.clone())).unwrap();
+            let parsed = match expr.split_once(SENDERS_FILTER) {
+                Some((_, pattern)) => parse_senders(extract(pattern, "", "\")")).unwrap(),
+                None => Senders::Everyone,
+            };
+            assert_eq!(senders, parsed, "{expr}");
+        }
+        assert_eq!(
+            r#"match_recipient("^list(?:\+.+)?@example\.com$") and match_header("from", "(?i)(^|<)(?:a\+b@example\.com|c@example\.net)>?$")"#,
+            build_route_expression(&list(Senders::Addresses(vec![
+                "a+b@example.com".into(),
+                "c@example.net".into()
+            ])))
+            .unwrap()
+        );
+        assert_eq!(
+            r#"match_recipient("^list(?:\+.+)?@example\.com$") and match_header("from", "(?i)@example\.com>?$")"#,
+            build_route_expression(&list(Senders::Domain("example.com".into()))).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_senders_pattern() {
+        let list = |senders| List {
+            address: "list@example.com".into(),
+            members: vec![],
+            priority: 0,
+            senders,
+            excluded: vec![],
+        };
+        let pattern = |senders| {
+            let expr = build_route_expression(&list(senders)).unwrap();
+            let (_, pattern) = expr.split_once(SENDERS_FILTER).unwrap();
+            regex::Regex::new(extract(pattern, "", "\")")).unwrap()
+        };
+
+        let addresses = pattern(Senders::Addresses(vec![
+            "a@example.com".into(),
+            "b@example.net".into(),
+        ]));
+        for from in [
+            "a@example.com",
+            "A@Example.com",
+            "Name <a@example.com>",
+            "b@example.net",
+            "Name <b@example.net>",
+        ] {
+            assert!(addresses.is_match(from), "`{from}` should be allowed");
+        }
+        for from in [
+            "xa@example.com",
+            "Name <xa@example.com>",
+            "a@example.com.evil",
+            "a@example.comm",
+            "b@example.net.evil",
+            "xb@example.net",
+            "a@exampleXcom",
+        ] {
+            assert!(!addresses.is_match(from), "`{from}` should be rejected");
+        }
+
+        let domain = pattern(Senders::Domain("example.com".into()));
+        for from in ["a@example.com", "Name <a@EXAMPLE.com>"] {
+            assert!(domain.is_match(from), "`{from}` should be allowed");
+        }
+        for from in [
+            "a@badexample.com",
+            "a@example.com.evil",
+            "a@sub.example.com",
+        ] {
+            assert!(!domain.is_match(from), "`{from}` should be rejected");
+        }
+    }
+
+    #[test]
+    fn test_mangle_catch_all() {
+        let list = |excluded: &[&str]| List {
+            address: "*@example.com".into(),
+            members: vec![],
+            priority: 0,
+            senders: Senders::Everyone,
+            excluded: excluded.iter().map(|a| a.to_string()).collect(),
+        };
+        assert_eq!(
+            r"^[^@]+@example\.com$",
+            mangle_recipient(&list(&[])).unwrap()
+        );
+        assert_eq!(
+            r"^(?!(?:a\.b|c)(?:\+.+)?@)[^@]+@example\.com$",
+            mangle_recipient(&list(&["a.b@example.com", "c@example.com"])).unwrap()
+        );
+        for excluded in [&[][..], &["a.b@example.com", "c@example.com"]] {
+            let list = list(excluded);
+            assert_eq!(
+                (list.address.clone(), list.excluded.clone()),
+                unmangle_recipient(&mangle_recipient(&list).unwrap()).unwrap()
+            );
+        }
+        assert_eq!(
+            ("list@example.com".to_string(), vec![]),
+            unmangle_recipient(&mangle_address("list@example.com").unwrap()).unwrap()
+        );
+    }
+}
@@ -0,0 +1,871 @@
+mod mailgun;
+mod ses;
+
+use std::collections::{HashMap, HashSet};
+use std::fmt::{Display, Formatter};
+
+use self::mailgun::Mailgun;
+use self::ses::Ses;
+use crate::TeamApi;
+use anyhow::{Context, bail};
+use async_trait::async_trait;
+use rust_team_data::{email_encryption, v1 as team_data};
+use secrecy::SecretString;
+
+/// Provider hosting the mailing lists
+#[derive(clap::ValueEnum, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum EmailBackend {
+    /// Mailgun routes, authenticated with `MAILGUN_API_TOKEN`
+    #[default]
+    Mailgun,
+    /// Rules of an Amazon SES Mail Manager rule set, authenticated with the usual `AWS_*`
+    /// environment variables
+    Ses,
+}
+
+/// A mailing list, as defined by the team data
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct List {
+    address: String,
+    members: Vec<String>,
+    /// Priority of the route, increased by one for each part of lists split over several routes
+    priority: i32,
+    senders: Senders,
+    /// For catch-all lists, the other addresses of the domain, which are not forwarded. Sorted.
+    excluded: Vec<String>,
+}
+
+/// A route forwarding the emails of a mailing list, as configured in the email provider
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Route {
+    id: String,
+    address: String,
+    priority: i32,
+    members: Vec<String>,
+    senders: Senders,
+    excluded: Vec<String>,
+}
+
+/// Who may send emails to a list
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Senders {
+    Everyone,
+    /// Only these addresses, sorted
+    Addresses(Vec<String>),
+    /// Only the addresses of this domain
+    Domain(String),
+}
+
+impl Display for Senders {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Senders::Everyone => write!(f, "everyone"),
+            Senders::Addresses(addresses) => write!(f, "{} addresses", addresses.len()),
+            Senders::Domain(domain) => write!(f, "addresses of {domain}"),
+        }
+    }
+}
+
+/// Why the email provider stopped delivering emails to an address
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum SuppressionReason {
+    Bounce,
+    Complaint,
+    Unsubscribe,
+}
+
+impl Display for SuppressionReason {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SuppressionReason::Bounce => write!(f, "bounced"),
+            SuppressionReason::Complaint => write!(f, "complained"),
+            SuppressionReason::Unsubscribe => write!(f, "unsubscribed"),
+        }
+    }
+}
+
+/// An address the email provider doesn't deliver emails to anymore
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Suppression {
+    address: String,
+    reason: SuppressionReason,
+    /// Explanation given by the provider, like the error of a bounce
+    details: Option<String>,
+}
+
+/// Access to the email provider.
+///
+/// Only the routes created by sync-team are read and changed.
+#[async_trait]
+trait EmailApi: Sync {
+    /// Split the members of a list over several routes, if they don't fit in a single one.
+    ///
+    /// `previous` contains the members of the routes currently configured for the list, ordered
+    /// by priority.
+    fn split_members(&self, members: Vec<String>, _previous: &[&[String]]) -> Vec<Vec<String>> {
+        vec![members]
+    }
+
+    /// Get the routes created by sync-team
+    async fn get_routes(&self) -> anyhow::Result<Vec<Route>>;
+
+    async fn create_route(&self, list: &List) -> anyhow::Result<()>;
+
+    async fn update_route(&self, route: &Route, list: &List) -> anyhow::Result<()>;
+
+    async fn delete_route(&self, route: &Route) -> anyhow::Result<()>;
+
+    /// Write the changes made by the previous calls, for providers that apply all the changes
+    /// of a sync at once
+    async fn commit(&self) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    /// Get the addresses that emails sent from these domains are not delivered to anymore
+    async fn get_suppressions(&self, domains: &[String]) -> anyhow::Result<Vec<Suppression>>;
+}
+
+fn email_api(backend: EmailBackend, dry_run: bool) -> anyhow::Result<Box<dyn EmailApi>> {
+    Ok(match backend {
+        EmailBackend::Mailgun => {
+            let token = SecretString::from(crate::sync::get_env("MAILGUN_API_TOKEN")?);
+            Box::new(Mailgun::new(token, dry_run))
+        }
+        EmailBackend::Ses => Box::new(Ses::from_env(dry_run)?),
+    })
+}
+
+/// Split members into parts whose total weight is at most `limit`.
+///
+/// Members stay in the part they are in `previous` whenever possible, and new members are added
+/// to the last part. This way adding a member to a big list only updates one of its routes,
+/// instead of shifting the members of all the following routes.
+fn pack_members(
+    members: Vec<String>,
+    previous: &[&[String]],
+    limit: usize,
+    weight: impl Fn(&str) -> usize,
+) -> Vec<Vec<String>> {
+    let mut remaining = members.iter().cloned().collect::<HashSet<_>>();
+    let mut parts = Vec::new();
+    for part in previous {
+        let kept = part
+            .iter()
+            .filter(|member| remaining.contains(*member))
+            .cloned()
+            .collect::<Vec<_>>();
+        let total = kept.iter().map(|member| weight(member)).sum::<usize>();
+        // Parts over the limit (if it was lowered) are rebuilt from scratch
+        if kept.is_empty() || total > limit {
+            continue;
+        }
+        for member in &kept {
+            remaining.remove(member);
+        }
+        parts.push((kept, total));
+    }
+
+    for member in members {
+        if !remaining.remove(&member) {
+            continue;
+        }
+        let member_weight = weight(&member);
+        match parts.last_mut() {
+            Some((part, total)) if *total + member_weight <= limit => {
+                part.push(member);
+                *total += member_weight;
+            }
+            _ => parts.push((vec![member], member_weight)),
+        }
+    }
+
+    if parts.is_empty() {
+        return vec![Vec::new()];
+    }
+    parts.into_iter().map(|(part, _)| part).collect()
+}
+
+/// Decrypt the mailing lists, splitting them over several routes if needed.
+///
+/// Returns the lists and the addresses that were encrypted, which must not be shown in the
+/// diff.
+fn split_lists(
+    email_api: &dyn EmailApi,
+    email_encryption_key: &str,
+    lists: team_data::Lists,
+    routes: &[Route],
+) -> anyhow::Result<(Vec<List>, HashSet<String>)> {
+    let mut result = Vec::new();
+    let mut secret_addresses = HashSet::new();
+    let mut decrypt = |value: &str| -> anyhow::Result<String> {
+        let decrypted = email_encryption::try_decrypt(email_encryption_key, value)?;
+        if decrypted != value {
+            secret_addresses.insert(decrypted.clone());
+        }
+        Ok(decrypted)
+    };
+
+    // Handle encrypted list and member addresses.
+    let lists = lists
+        .lists
+        .into_values()
+        .map(|list| Ok((decrypt(&list.address)?, list)))
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    let addresses = lists
+        .iter()
+        .map(|(address, _)| address.clone())
+        .collect::<Vec<_>>();
+
+    for (address, list) in lists {
+        let members = list
+            .members
+            .iter()
+            .map(|member| decrypt(member))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        let senders = match list.access_level {
+            team_data::ListAccessLevel::Everyone => Senders::Everyone,
+            team_data::ListAccessLevel::Members => {
+                let mut addresses = members.clone();
+                addresses.sort();
+                Senders::Addresses(addresses)
+            }
+            team_data::ListAccessLevel::Readonly => {
+                let Some((_, domain)) = address.split_once('@') else {
+                    bail!("the address `{address}` doesn't have any '@'");
+                };
+                Senders::Domain(domain.to_string())
+            }
+        };
+        // Neither Mailgun routes nor SES rules can rewrite the `Reply-To` header of the emails
+        // they forward.
+        if list.reply_preference != team_data::ListReplyPreference::Sender {
+            bail!(
+                "the list `{address}` asks for replies to go to the list, but the emails are \
+                 forwarded unchanged, so replies can only go to their sender"
+            );
+        }
+        let excluded = match address.split_once('@') {
+            Some(("*", domain)) => {
+                let mut excluded = addresses
+                    .iter()
+                    .filter(|other| {
+                        other.split_once('@').is_some_and(|(user, other_domain)| {
+                            user != "*" && other_domain == domain
+                        })
+                    })
+                    .cloned()
+                    .collect::<Vec<_>>();
+                excluded.sort();
+                excluded
+            }
+            _ => Vec::new(),
+        };
+
+        let mut previous = routes
+            .iter()
+            .filter(|route| route.address == address)
+            .collect::<Vec<_>>();
+        previous.sort_by_key(|route| route.priority);
+        let previous = previous
+            .iter()
+            .map(|route| route.members.as_slice())
+            .collect::<Vec<_>>();
+
+        // Since all the parts have the same address, the priority of their route is used to
+        // differentiate them during the sync.
+        let parts = email_api.split_members(members, &previous);
+        for (part, members) in parts.into_iter().enumerate() {
+            result.push(List {
+                address: address.clone(),
+                members,
+                priority: list.priority + part as i32,
+                senders: senders.clone(),
+                excluded: excluded.clone(),
+            });
+        }
+    }
+
+    Ok((result, secret_addresses))
+}
+
+pub(crate) struct SyncEmail {
+    email_api: Box<dyn EmailApi>,
+    lists: Vec<List>,
+    routes: Vec<Route>,
+    /// Addresses that are encrypted in the team data
+    secret_addresses: HashSet<String>,
+}
+
+impl SyncEmail {
+    pub(crate) async fn new(
+        backend: EmailBackend,
+        email_encryption_key: &str,
+        team_api: &TeamApi,
+        dry_run: bool,
+    ) -> anyhow::Result<Self> {
+        let email_api = email_api(backend, dry_run)?;
+        let mailmap = team_api.get_lists().await?;
+        let routes = email_api.get_routes().await?;
+        let (lists, secret_addresses) =
+            split_lists(email_api.as_ref(), email_encryption_key, mailmap, &routes)?;
+        Ok(Self {
+            email_api,
+            lists,
+            routes,
+            secret_addresses,
+        })
+    }
+
+    pub(crate) async fn diff_all(&self) -> anyhow::Result<Diff> {
+        let mut addr2list = HashMap::new();
+        for list in &self.lists {
+            if addr2list
+                .insert((list.address.clone(), list.priority), list)
+                .is_some()
+            {
+                bail!(
+                    "duplicate address: {} (with priority {})",
+                    list.address,
+                    list.priority
+                );
+            }
+        }
+
+        let mut route_diffs = Vec::new();
+        for route in self.routes.iter().cloned() {
+            let key = (route.address.clone(), route.priority);
+            match addr2list.remove(&key) {
+                Some(list) => {
+                    let before = route.members.iter().collect::<HashSet<_>>();
+                    let after = list.members.iter().collect::<HashSet<_>>();
+                    if before != after
+                        || route.senders != list.senders
+                        || route.excluded != list.excluded
+                    {
+                        route_diffs.push(RouteDiff::Update {
+                            route,
+                            list: list.clone(),
+                        });
+                    }
+                }
+                None => route_diffs.push(RouteDiff::Delete(route)),
+            }
+        }
+        let mut new_lists = addr2list.into_values().cloned().collect::<Vec<_>>();
+        new_lists.sort_by(|a, b| (&a.address, a.priority).cmp(&(&b.address, b.priority)));
+        route_diffs.extend(new_lists.into_iter().map(RouteDiff::Create));
+
+        Ok(Diff {
+            route_diffs,
+            secret_addresses: self.secret_addresses.clone(),
+        })
+    }
+}
+
+pub(crate) struct Diff {
+    route_diffs: Vec<RouteDiff>,
+    /// Addresses that are encrypted in the team data, and are hidden when printing the diff
+    secret_addresses: HashSet<String>,
+}
+
+impl Diff {
+    pub(crate) async fn apply(&self, sync: &SyncEmail) -> anyhow::Result<()> {
+        let email_api = sync.email_api.as_ref();
+        for diff in &self.route_diffs {
+            match diff {
+                RouteDiff::Create(list) => email_api
+                    .create_route(list)
+                    .await
+                    .with_context(|| format!("failed to create {}", list.address))?,
+                RouteDiff::Update { route, list } => email_api
+                    .update_route(route, list)
+                    .await
+                    .with_context(|| format!("failed to sync {}", list.address))?,
+                RouteDiff::Delete(route) => email_api
+                    .delete_route(route)
+                    .await
+                    .with_context(|| format!("failed to delete {}", route.address))?,
+            }
+        }
+        email_api.commit().await
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.route_diffs.is_empty()
+    }
+
+    fn show<'a>(&self, address: &'a str) -> &'a str {
+        if self.secret_addresses.contains(address) {
+            "<encrypted address>"
+        } else {
+            address
+        }
+    }
+
+    fn fmt_list(&self, f: &mut Formatter<'_>, address: &str, priority: i32) -> std::fmt::Result {
+        write!(f, "{}", self.show(address))?;
+        if priority != 0 {
+            write!(f, " (priority {priority})")?;
+        }
+        writeln!(f)
+    }
+
+    fn fmt_addresses(&self, addresses: &[String]) -> String {
+        if addresses.is_empty() {
+            return "none".to_string();
+        }
+        addresses
+            .iter()
+            .map(|address| self.show(address))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+impl Display for Diff {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.route_diffs.is_empty() {
+            return Ok(());
+        }
+        writeln!(f, "💻 Mailing List Diffs:")?;
+        for diff in &self.route_diffs {
+            match diff {
+                RouteDiff::Create(list) => {
+                    write!(f, "➕ Creating list ")?;
+                    self.fmt_list(f, &list.address, list.priority)?;
+                    writeln!(f, "  Senders: {}", list.senders)?;
+                    if !list.excluded.is_empty() {
+                        writeln!(f, "  Excluded: {}", self.fmt_addresses(&list.excluded))?;
+                    }
+                    writeln!(f, "  Members:")?;
+                    for member in &list.members {
+                        writeln!(f, "    {}", self.show(member))?;
+                    }
+                }
+                RouteDiff::Update { route, list } => {
+                    write!(f, "📝 Updating list ")?;
+                    self.fmt_list(f, &list.address, list.priority)?;
+                    if route.senders != list.senders {
+                        writeln!(f, "  Senders: {} => {}", route.senders, list.senders)?;
+                    }
+                    if route.excluded != list.excluded {
+                        writeln!(
+                            f,
+                            "  Excluded: {} => {}",
+                            self.fmt_addresses(&route.excluded),
+                            self.fmt_addresses(&list.excluded)
+                        )?;
+                    }
+                    let added = list.members.iter().filter(|m| !route.members.contains(m));
+                    let removed = route.members.iter().filter(|m| !list.members.contains(m));
+                    if added.clone().chain(removed.clone()).next().is_some() {
+                        writeln!(f, "  Members:")?;
+                    }
+                    for member in added {
+                        writeln!(f, "    ➕ {}", self.show(member))?;
+                    }
+                    for member in removed {
+                        writeln!(f, "    − {}", self.show(member))?;
+                    }
+                }
+                RouteDiff::Delete(route) => {
+                    write!(f, "❌ Deleting list ")?;
+                    self.fmt_list(f, &route.address, route.priority)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// People whose email address is suppressed by the email provider, and who silently don't
+/// receive the emails of their lists anymore
+pub(crate) struct SuppressionReport {
+    /// GitHub username of the person and suppression of their address, sorted by username
+    entries: Vec<(String, Suppression)>,
+}
+
+impl SuppressionReport {
+    /// Query the suppressions of the domains of the mailing lists, and look for the addresses
+    /// of the people in them.
+    pub(crate) async fn new(
+        backend: EmailBackend,
+        email_encryption_key: &str,
+        team_api: &TeamApi,
+    ) -> anyhow::Result<Self> {
+        let email_api = email_api(backend, true)?;
+        let decrypt = |value: &str| email_encryption::try_decrypt(email_encryption_key, value);
+
+        let mut domains = team_api
+            .get_lists()
+            .await?
+            .lists
+            .values()
+            .map(|list| {
+                let address = decrypt(&list.address)?;
+                match address.split_once('@') {
+                    Some((_, domain)) => Ok(domain.to_string()),
+                    None => bail!("the address `{address}` doesn't have any '@'"),
+                }
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        domains.sort();
+        domains.dedup();
+
+        let people = team_api
+            .get_people()
+            .await?
+            .people
+            .into_iter()
+            .filter_map(|(github, person)| Some((github, person.email?)))
+            .map(|(github, email)| Ok((github, decrypt(&email)?)))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        let suppressions = email_api.get_suppressions(&domains).await?;
+        Ok(Self::cross_reference(people, suppressions))
+    }
+
+    fn cross_reference(people: Vec<(String, String)>, suppressions: Vec<Suppression>) -> Self {
+        let mut suppressions_by_address = HashMap::<String, Vec<Suppression>>::new();
+        for suppression in suppressions {
+            suppressions_by_address
+                .entry(suppression.address.to_lowercase())
+                .or_default()
+                .push(suppression);
+        }
+
+        let mut entries = Vec::new();
+        for (github, email) in people {
+            for suppression in suppressions_by_address
+                .get(&email.to_lowercase())
+                .into_iter()
+                .flatten()
+            {
+                entries.push((github.clone(), suppression.clone()));
+            }
+        }
+        entries.sort_by(|(a, a_suppression), (b, b_suppression)| {
+            (a, a_suppression.reason).cmp(&(b, b_suppression.reason))
+        });
+        Self { entries }
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+impl Display for SuppressionReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.entries.is_empty() {
+            return writeln!(f, "No suppressed addresses belong to people.");
+        }
+        writeln!(f, "📭 Suppressed addresses:")?;
+        for (github, suppression) in &self.entries {
+            write!(
+                f,
+                "  {github} <{}>: {}",
+                suppression.address, suppression.reason
+            )?;
+            if let Some(details) = &suppression.details {
+                write!(f, " ({details})")?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+/// A change of a route of the email provider
+enum RouteDiff {
+    Create(List),
+    Update { route: Route, list: List },
+    Delete(Route),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_lists() {
+        const ENCRYPTION_KEY: &str = "mGDTk1eIx8P2gTerzKXwvun67d41iUid";
+
+        let secret_list = email_encryption::encrypt(ENCRYPTION_KEY, "secret-list@example.com")
+            .expect("failed to encrypt list");
+        let secret_member = email_encryption::encrypt(ENCRYPTION_KEY, "secret-member@example.com")
+            .expect("failed to encrypt member");
+
+        let original = rust_team_data::v1::Lists {
+            lists: indexmap::indexmap![
+                "small@example.com".to_string() => rust_team_data::v1::List {
+                    address: "small@example.com".into(),
+                    members: vec![
+                        "foo@example.com".into(),
+                        "bar@example.com".into(),
+                        secret_member.clone(),
+                    ],
+                    access_level: rust_team_data::v1::ListAccessLevel::Members,
+                    reply_preference: rust_team_data::v1::ListReplyPreference::Sender,
+                    priority: 0,
+                },
+                secret_list.clone() => rust_team_data::v1::List {
+                    address: secret_list,
+                    members: vec![secret_member, "baz@example.com".into()],
+                    access_level: rust_team_data::v1::ListAccessLevel::Readonly,
+                    reply_preference: rust_team_data::v1::ListReplyPreference::Sender,
+                    priority: 0,
+                },
+                "big@example.com".into() => rust_team_data::v1::List {
+                    address: "big@example.com".into(),
+                    // Generate 300 members automatically to simulate a big list, and test whether the
+                    // partitioning mechanism works.
+                    members: (0..300).map(|i| format!("foo{i:03}@example.com")).collect(),
+                    access_level: rust_team_data::v1::ListAccessLevel::Everyone,
+                    reply_preference: rust_team_data::v1::ListReplyPreference::Sender,
+                    priority: 10,
+                },
+                "*@example.com".into() => rust_team_data::v1::List {
+                    address: "*@example.com".into(),
+                    members: vec!["foo@example.com".into()],
+                    access_level: rust_team_data::v1::ListAccessLevel::Everyone,
+                    reply_preference: rust_team_data::v1::ListReplyPreference::Sender,
+                    priority: 20,
+                },
+            ],
+        };
+
+        let mailgun = Mailgun::new(SecretString::from("token"), true);
+        let (split, secret_addresses) =
+            split_lists(&mailgun, ENCRYPTION_KEY, original, &[]).unwrap();
+        let expected = vec![
+            List {
+                address: "small@example.com".into(),
+                priority: 0,
+                members: vec![
+                    "foo@example.com".into(),
+                    "bar@example.com".into(),
+                    "secret-member@example.com".into(),
+                ],
+                senders: Senders::Addresses(vec![
+                    "bar@example.com".into(),
+                    "foo@example.com".into(),
+                    "secret-member@example.com".into(),
+                ]),
+                excluded: vec![],
+            },
+            List {
+                address: "secret-list@example.com".into(),
+                priority: 0,
+                members: vec!["secret-member@example.com".into(), "baz@example.com".into()],
+                senders: Senders::Domain("example.com".into()),
+                excluded: vec![],
+            },
+            // With ACTIONS_SIZE_LIMIT_BYTES = 4000, each list can contain at most 137 users named
+            // `fooNNN@example.com`. If the limit is changed the numbers will need to be updated.
+            List {
+                address: "big@example.com".into(),
+                priority: 10,
+                members: (0..137)
+                    .map(|i| format!("foo{i:03}@example.com"))
+                    .collect::<Vec<_>>(),
+                senders: Senders::Everyone,
+                excluded: vec![],
+            },
+            List {
+                address: "big@example.com".into(),
+                priority: 11,
+                members: (137..274)
+                    .map(|i| format!("foo{i:03}@example.com"))
+                    .collect::<Vec<_>>(),
+                senders: Senders::Everyone,
+                excluded: vec![],
+            },
+            List {
+                address: "big@example.com".into(),
+                priority: 12,
+                members: (274..300)
+                    .map(|i| format!("foo{i:03}@example.com"))
+                    .collect::<Vec<_>>(),
+                senders: Senders::Everyone,
+                excluded: vec![],
+            },
+            // The catch-all list doesn't forward the emails of the other lists of its domain
+            List {
+                address: "*@example.com".into(),
+                priority: 20,
+                members: vec!["foo@example.com".into()],
+                senders: Senders::Everyone,
+                excluded: vec![
+                    "big@example.com".into(),
+                    "secret-list@example.com".into(),
+                    "small@example.com".into(),
+                ],
+            },
+        ];
+        assert_eq!(expected, split);
+        assert_eq!(
+            HashSet::from([
+                "secret-list@example.com".to_string(),
+                "secret-member@example.com".to_string()
+            ]),
+            secret_addresses
+        );
+    }
+
+    #[test]
+    fn test_split_lists_reply_to_list() {
+        let lists = rust_team_data::v1::Lists {
+            lists: indexmap::indexmap![
+                "list@example.com".to_string() => rust_team_data::v1::List {
+                    address: "list@example.com".into(),
+                    members: vec!["foo@example.com".into()],
+                    access_level: rust_team_data::v1::ListAccessLevel::Everyone,
+                    reply_preference: rust_team_data::v1::ListReplyPreference::List,
+                    priority: 0,
+                },
+            ],
+        };
+        let mailgun = Mailgun::new(SecretString::from("token"), true);
+        let err = split_lists(&mailgun, "mGDTk1eIx8P2gTerzKXwvun67d41iUid", lists, &[])
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("list@example.com"), "{err}");
+    }
+
+    #[test]
+    fn test_pack_members() {
+        let members =
+            |range: std::ops::Range<usize>| range.map(|i| format!("m{i}")).collect::<Vec<_>>();
+        let pack = |all: Vec<String>, previous: &[Vec<String>]| {
+            let previous = previous.iter().map(Vec::as_slice).collect::<Vec<_>>();
+            pack_members(all, &previous, 3, |_| 1)
+        };
+
+        assert_eq!(vec![Vec::<String>::new()], pack(vec![], &[]));
+        assert_eq!(
+            vec![members(0..3), members(3..6), members(6..7)],
+            pack(members(0..7), &[])
+        );
+
+        // Adding a member at the start of the list only changes the last part
+        let mut all = members(0..7);
+        all.insert(0, "new".into());
+        assert_eq!(
+            vec![
+                members(0..3),
+                members(3..6),
+                vec!["m6".into(), "new".into()]
+            ],
+            pack(all, &[members(0..3), members(3..6), members(6..7)])
+        );
+
+        // Removed members leave room in their part, and emptied parts are dropped
+        let mut all = members(1..6);
+        all.push("new".into());
+        assert_eq!(
+            vec![members(1..3), members(3..6), vec!["new".into()]],
+            pack(all, &[members(0..3), members(3..6), members(6..7)])
+        );
+
+        // Parts over the limit are split again
+        assert_eq!(
+            vec![members(0..3), members(3..4)],
+            pack(members(0..4), &[members(0..4)])
+        );
+    }
+
+    #[test]
+    fn test_suppression_report() {
+        let suppression = |address: &str, reason, details: Option<&str>| Suppression {
+            address: address.into(),
+            reason,
+            details: details.map(str::to_string),
+        };
+        let report = SuppressionReport::cross_reference(
+            vec![
+                ("zed".into(), "zed@example.com".into()),
+                ("alice".into(), "Alice@example.com".into()),
+                ("bob".into(), "bob@example.com".into()),
+            ],
+            vec![
+                suppression("alice@example.com", SuppressionReason::Unsubscribe, None),
+                suppression("stranger@example.com", SuppressionReason::Bounce, None),
+                suppression(
+                    "zed@example.com",
+                    SuppressionReason::Bounce,
+                    Some("550 mailbox unavailable"),
+                ),
+                suppression("alice@example.com", SuppressionReason::Bounce, None),
+            ],
+        );
+        assert_eq!(
+            report.to_string(),
+            "📭 Suppressed addresses:
+  alice <alice@example.com>: bounced
+  alice <alice@example.com>: unsubscribed
+  zed <zed@example.com>: bounced (550 mailbox unavailable)
+"
+        );
+    }
+
+    #[test]
+    fn test_diff_display() {
+        let route = |address: &str, priority, members: &[&str]| Route {
+            id: format!("{address}-{priority}"),
+            address: address.into(),
+            priority,
+            members: members.iter().map(|m| m.to_string()).collect(),
+            senders: Senders::Everyone,
+            excluded: vec![],
+        };
+        let list = |address: &str, priority, members: &[&str]| List {
+            address: address.into(),
+            priority,
+            members: members.iter().map(|m| m.to_string()).collect(),
+            senders: Senders::Everyone,
+            excluded: vec![],
+        };
+        let diff = Diff {
+            route_diffs: vec![
+                RouteDiff::Update {
+                    route: route("a@example.com", 1, &["foo@example.com", "bar@example.com"]),
+                    list: List {
+                        senders: Senders::Domain("example.com".into()),
+                        ..list(
+                            "a@example.com",
+                            1,
+                            &["foo@example.com", "secret@example.com"],
+                        )
+                    },
+                },
+                RouteDiff::Update {
+                    route: route("*@example.com", 0, &["foo@example.com"]),
+                    list: List {
+                        excluded: vec!["a@example.com".into(), "secret@example.com".into()],
+                        ..list("*@example.com", 0, &["foo@example.com"])
+                    },
+                },
+                RouteDiff::Delete(route("old@example.com", 0, &["foo@example.com"])),
+                RouteDiff::Create(list("new@example.com", 0, &["foo@example.com"])),
+            ],
+            secret_addresses: HashSet::from(["secret@example.com".to_string()]),
+        };
+        assert_eq!(
+            diff.to_string(),
+            "💻 Mailing List Diffs:
+📝 Updating list a@example.com (priority 1)
+  Senders: everyone => addresses of example.com
+  Members:
+    ➕ <encrypted address>
+    − bar@example.com
+📝 Updating list *@example.com
+  Excluded: none => a@example.com, <encrypted address>
+❌ Deleting list old@example.com
+➕ Creating list new@example.com
+  Senders: everyone
+  Members:
+    foo@example.com
+"
+        );
+    }
+}
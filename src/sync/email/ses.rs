@@ -0,0 +1,829 @@
+//! Mailing lists hosted by Amazon SES, as rules of a Mail Manager rule set.
+//!
+//! Every route is a rule replacing the recipients of the emails sent to the list by its members,
+//! and sending the emails on. A second rule handles the `+` aliases of the list, like
+//! `infra+botname@rust-lang.org`. Catch-all lists only have one rule, matching the addresses of
+//! their domain that are not handled by other lists.
+//!
+//! The other rules of the rule set are left untouched, and the rules created by sync-team are
+//! kept after them, ordered by priority. The changes of a sync are written to the rule set at
+//! once, when they have all been made.
+//!
+//! See https://docs.aws.amazon.com/ses/latest/dg/eb-rules.html
+
+use super::{EmailApi, List, Route, Senders, Suppression, SuppressionReason, pack_members};
+use crate::sync::get_env;
+use crate::sync::utils::ResponseExt;
+use anyhow::{Context as _, bail};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use log::info;
+use reqwest::{Client, Method};
+use ring::{digest, hmac};
+use secrecy::{ExposeSecret, SecretString};
+use serde_json::{Value, json};
+use std::sync::Mutex;
+
+/// Prefix of the names of the rules created by sync-team
+const RULE_PREFIX: &str = "team-sync-";
+
+/// Suffix of the rules matching the `+` aliases of a list
+const ALIAS_RULE_SUFFIX: &str = "-plus";
+
+/// `ReplaceRecipient` actions accept at most 100 addresses
+const MAX_RECIPIENTS: usize = 100;
+
+/// Service name used to sign the Mail Manager requests
+const SIGNING_SERVICE: &str = "ses";
+
+pub(super) struct Ses {
+    client: Client,
+    access_key_id: String,
+    secret_access_key: SecretString,
+    session_token: Option<SecretString>,
+    region: String,
+    rule_set_id: String,
+    /// IAM role allowed to send the forwarded emails
+    send_role_arn: String,
+    dry_run: bool,
+    /// Rules of the rule set with the changes of this sync, written by `commit`
+    pending_rules: Mutex<Option<Vec<Value>>>,
+}
+
+impl Ses {
+    pub(super) fn from_env(dry_run: bool) -> anyhow::Result<Self> {
+        Ok(Self {
+            client: Client::new(),
+            access_key_id: get_env("AWS_ACCESS_KEY_ID")?,
+            secret_access_key: SecretString::from(get_env("AWS_SECRET_ACCESS_KEY")?),
+            session_token: std::env::var("AWS_SESSION_TOKEN")
+                .ok()
+                .map(SecretString::from),
+            region: get_env("AWS_REGION")?,
+            rule_set_id: get_env("SES_RULE_SET_ID")?,
+            send_role_arn: get_env("SES_SEND_ROLE_ARN")?,
+            dry_run,
+            pending_rules: Mutex::new(None),
+        })
+    }
+
+    async fn get_rules(&self) -> anyhow::Result<Vec<Value>> {
+        let response = self
+            .request("GetRuleSet", &json!({ "RuleSetId": self.rule_set_id }))
+            .await?;
+        Ok(match response.get("Rules") {
+            Some(Value::Array(rules)) => rules.clone(),
+            _ => Vec::new(),
+        })
+    }
+
+    /// Replace the rules created by sync-team for a route in the pending rules
+    async fn replace_rules(&self, id: &str, new_rules: Vec<Value>) -> anyhow::Result<()> {
+        if self.dry_run {
+            return Ok(());
+        }
+
+        if self.pending_rules.lock().unwrap().is_none() {
+            let rules = self.get_rules().await?;
+            self.pending_rules.lock().unwrap().get_or_insert(rules);
+        }
+        let mut pending_rules = self.pending_rules.lock().unwrap();
+        replace_rules(pending_rules.as_mut().unwrap(), id, new_rules);
+        Ok(())
+    }
+
+    fn rules(&self, id: &str, list: &List) -> anyhow::Result<Vec<Value>> {
+        let Some((user, domain)) = list.address.split_once('@') else {
+            bail!("the address `{}` doesn't have any '@'", list.address);
+        };
+        let actions = json!([
+            { "ReplaceRecipient": { "ReplaceWith": list.members } },
+            { "Send": { "RoleArn": self.send_role_arn } },
+        ]);
+        let condition = |attribute: &str, operator: &str, values: Vec<String>| {
+            json!({
+                "StringExpression": {
+                    "Evaluate": { "Attribute": attribute },
+                    "Operator": operator,
+                    "Values": values,
+                }
+            })
+        };
+        let recipient = |operator, value| condition("RECIPIENT", operator, vec![value]);
+        let senders = match &list.senders {
+            Senders::Everyone => None,
+            Senders::Addresses(addresses) => {
+                Some(condition("MAIL_FROM", "EQUALS", addresses.clone()))
+            }
+            Senders::Domain(domain) => Some(condition(
+                "MAIL_FROM",
+                "ENDS_WITH",
+                vec![format!("@{domain}")],
+            )),
+        };
+        if user == "*" {
+            let mut conditions = vec![recipient("ENDS_WITH", format!("@{domain}"))];
+            conditions.extend(senders);
+            let mut rule = json!({
+                "Name": id,
+                "Conditions": conditions,
+                "Actions": actions,
+            });
+            if !list.excluded.is_empty() {
+                let aliases = list
+                    .excluded
+                    .iter()
+                    .filter_map(|address| address.split_once('@'))
+                    .map(|(user, _)| format!("{user}+"))
+                    .collect();
+                rule["Unless"] = json!([
+                    condition("RECIPIENT", "EQUALS", list.excluded.clone()),
+                    condition("RECIPIENT", "STARTS_WITH", aliases),
+                ]);
+            }
+            return Ok(vec![rule]);
+        }
+
+        let mut conditions = vec![recipient("EQUALS", list.address.clone())];
+        let mut alias_conditions = vec![
+            recipient("STARTS_WITH", format!("{user}+")),
+            recipient("ENDS_WITH", format!("@{domain}")),
+        ];
+        conditions.extend(senders.clone());
+        alias_conditions.extend(senders);
+        Ok(vec![
+            json!({
+                "Name": id,
+                "Conditions": conditions,
+                "Actions": actions,
+            }),
+            json!({
+                "Name": format!("{id}{ALIAS_RULE_SUFFIX}"),
+                "Conditions": alias_conditions,
+                "Actions": actions,
+            }),
+        ])
+    }
+
+    /// Send a request to the Mail Manager API
+    async fn request(&self, operation: &str, body: &Value) -> anyhow::Result<Value> {
+        let host = format!("mail-manager.{}.amazonaws.com", self.region);
+        self.signed_request(
+            Method::POST,
+            &host,
+            "/",
+            &[],
+            Some(&format!("MailManagerSvc.{operation}")),
+            body.to_string(),
+        )
+        .await
+        .with_context(|| format!("failed to call {operation} on SES Mail Manager"))
+    }
+
+    /// Get the account-level suppression list of SES, which applies to the forwarded emails.
+    ///
+    /// See https://docs.aws.amazon.com/ses/latest/APIReference-V2/API_ListSuppressedDestinations.html
+    async fn get_suppressed_destinations(&self) -> anyhow::Result<Vec<Suppression>> {
+        let host = format!("email.{}.amazonaws.com", self.region);
+        let mut suppressions = Vec::new();
+        let mut next_token = None;
+        loop {
+            let mut query = vec![("PageSize", "1000".to_string())];
+            if let Some(token) = next_token.take() {
+                query.push(("NextToken", token));
+            }
+            let response = self
+                .signed_request(
+                    Method::GET,
+                    &host,
+                    "/v2/email/suppression/addresses",
+                    &query,
+                    None,
+                    String::new(),
+                )
+                .await
+                .context("failed to list the destinations suppressed by SES")?;
+            let summaries = response
+                .get("SuppressedDestinationSummaries")
+                .and_then(Value::as_array)
+                .into_iter()
+                .flatten();
+            for summary in summaries {
+                let Some(address) = summary.get("EmailAddress").and_then(Value::as_str) else {
+                    continue;
+                };
+                let reason = match summary.get("Reason").and_then(Value::as_str) {
+                    Some("COMPLAINT") => SuppressionReason::Complaint,
+                    _ => SuppressionReason::Bounce,
+                };
+                suppressions.push(Suppression {
+                    address: address.to_string(),
+                    reason,
+                    details: None,
+                });
+            }
+            match response.get("NextToken").and_then(Value::as_str) {
+                Some(token) => next_token = Some(token.to_string()),
+                None => break,
+            }
+        }
+        Ok(suppressions)
+    }
+
+    /// Send a request to an AWS API, signed with AWS Signature Version 4.
+    async fn signed_request(
+        &self,
+        method: Method,
+        host: &str,
+        path: &str,
+        query: &[(&str, String)],
+        target: Option<&str>,
+        payload: String,
+    ) -> anyhow::Result<Value> {
+        let now = Utc::now();
+        let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+
+        let mut query = query
+            .iter()
+            .map(|(name, value)| format!("{}={}", uri_encode(name), uri_encode(value)))
+            .collect::<Vec<_>>();
+        query.sort();
+        let query = query.join("&");
+
+        let mut headers = Vec::new();
+        if target.is_some() {
+            headers.push(("content-type", "application/x-amz-json-1.0".to_string()));
+        }
+        headers.push(("host", host.to_string()));
+        headers.push(("x-amz-date", amz_date.clone()));
+        if let Some(token) = &self.session_token {
+            headers.push(("x-amz-security-token", token.expose_secret().to_string()));
+        }
+        if let Some(target) = target {
+            headers.push(("x-amz-target", target.to_string()));
+        }
+
+        let authorization = Signer {
+            access_key_id: &self.access_key_id,
+            secret_access_key: self.secret_access_key.expose_secret(),
+            region: &self.region,
+            service: SIGNING_SERVICE,
+        }
+        .authorization(&method, path, &query, &headers, &payload, now);
+
+        let mut url = format!("https://{host}{path}");
+        if !query.is_empty() {
+            url = format!("{url}?{query}");
+        }
+        let mut req = self
+            .client
+            .request(method, url)
+            .header("authorization", authorization);
+        for (name, value) in headers {
+            if name != "host" {
+                req = req.header(name, value);
+            }
+        }
+        req.body(payload)
+            .send()
+            .await?
+            .custom_error_for_status()
+            .await?
+            .json_annotated()
+            .await
+    }
+}
+
+#[async_trait]
+impl EmailApi for Ses {
+    fn split_members(&self, members: Vec<String>, previous: &[&[String]]) -> Vec<Vec<String>> {
+        pack_members(members, previous, MAX_RECIPIENTS, |_| 1)
+    }
+
+    async fn get_routes(&self) -> anyhow::Result<Vec<Route>> {
+        self.get_rules()
+            .await?
+            .iter()
+            .filter_map(|rule| {
+                let name = rule.get("Name")?.as_str()?;
+                (name.starts_with(RULE_PREFIX) && !name.ends_with(ALIAS_RULE_SUFFIX))
+                    .then(|| parse_route(name, rule))
+            })
+            .collect()
+    }
+
+    async fn create_route(&self, list: &List) -> anyhow::Result<()> {
+        let id = route_id(list);
+        info!("creating SES rules {id} for {}", list.address);
+        self.replace_rules(&id, self.rules(&id, list)?).await
+    }
+
+    async fn update_route(&self, route: &Route, list: &List) -> anyhow::Result<()> {
+        info!("updating SES rules {}", route.id);
+        self.replace_rules(&route.id, self.rules(&route.id, list)?)
+            .await
+    }
+
+    async fn delete_route(&self, route: &Route) -> anyhow::Result<()> {
+        info!("deleting SES rules {}", route.id);
+        self.replace_rules(&route.id, Vec::new()).await
+    }
+
+    async fn commit(&self) -> anyhow::Result<()> {
+        let Some(mut rules) = self.pending_rules.lock().unwrap().take() else {
+            return Ok(());
+        };
+        // The sort is stable, so the other rules keep their order
+        rules.sort_by_key(rule_priority);
+        info!("writing the SES rule set {}", self.rule_set_id);
+        self.request(
+            "UpdateRuleSet",
+            &json!({ "RuleSetId": self.rule_set_id, "Rules": rules }),
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// The suppression list of SES is not scoped to domains
+    async fn get_suppressions(&self, _domains: &[String]) -> anyhow::Result<Vec<Suppression>> {
+        self.get_suppressed_destinations().await
+    }
+}
+
+/// Name of the rule of a list, which must be at most 32 characters long
+fn route_id(list: &List) -> String {
+    let hash = sha256_hex(list.address.as_bytes());
+    format!("{RULE_PREFIX}{}-{}", &hash[..12], list.priority)
+}
+
+/// Replace the rules created by sync-team for a route, keeping the other rules as they are
+fn replace_rules(rules: &mut Vec<Value>, id: &str, new_rules: Vec<Value>) {
+    rules.retain(|rule| !is_rule_of(rule, id));
+    rules.extend(new_rules);
+}
+
+fn is_rule_of(rule: &Value, id: &str) -> bool {
+    rule.get("Name")
+        .and_then(Value::as_str)
+        .is_some_and(|name| name == id || name == format!("{id}{ALIAS_RULE_SUFFIX}"))
+}
+
+/// Priority of a rule created by sync-team, `None` for the other rules
+fn rule_priority(rule: &Value) -> Option<i32> {
+    route_priority(rule.get("Name")?.as_str()?)
+}
+
+/// Priority of the route a rule name was created for by [`route_id`]
+fn route_priority(name: &str) -> Option<i32> {
+    let name = name.strip_suffix(ALIAS_RULE_SUFFIX).unwrap_or(name);
+    // The hash doesn't contain any `-`, so everything after it is the priority
+    let (_hash, priority) = name.strip_prefix(RULE_PREFIX)?.split_once('-')?;
+    priority.parse().ok()
+}
+
+fn parse_route(name: &str, rule: &Value) -> anyhow::Result<Route> {
+    let address = rule
+        .pointer("/Conditions/0/StringExpression/Values/0")
+        .and_then(Value::as_str)
+        .with_context(|| format!("SES rule {name} doesn't match a recipient"))?;
+    // Catch-all lists match the end of the recipient
+    let address = match rule
+        .pointer("/Conditions/0/StringExpression/Operator")
+        .and_then(Value::as_str)
+    {
+        Some("ENDS_WITH") => format!("*{address}"),
+        _ => address.to_string(),
+    };
+    let mut excluded = rule
+        .get("Unless")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(|condition| condition.get("StringExpression"))
+        .filter(|expr| expr.get("Operator") == Some(&json!("EQUALS")))
+        .filter_map(|expr| expr.get("Values")?.as_array())
+        .flatten()
+        .filter_map(|value| value.as_str().map(str::to_string))
+        .collect::<Vec<_>>();
+    excluded.sort();
+    let members = rule
+        .get("Actions")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(|action| action.pointer("/ReplaceRecipient/ReplaceWith")?.as_array())
+        .flatten()
+        .filter_map(|member| member.as_str().map(str::to_string))
+        .collect();
+    let senders = rule
+        .get("Conditions")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(|condition| condition.get("StringExpression"))
+        .find(|expr| expr.pointer("/Evaluate/Attribute") == Some(&json!("MAIL_FROM")))
+        .map(|expr| {
+            let mut values = expr
+                .get("Values")
+                .and_then(Value::as_array)
+                .into_iter()
+                .flatten()
+                .filter_map(|value| value.as_str().map(str::to_string))
+                .collect::<Vec<_>>();
+            match expr.get("Operator").and_then(Value::as_str) {
+                Some("ENDS_WITH") => Senders::Domain(
+                    values
+                        .first()
+                        .map(|value| value.trim_start_matches('@').to_string())
+                        .unwrap_or_default(),
+                ),
+                _ => {
+                    values.sort();
+                    Senders::Addresses(values)
+                }
+            }
+        })
+        .unwrap_or(Senders::Everyone);
+    let priority = route_priority(name)
+        .with_context(|| format!("SES rule {name} wasn't created by sync-team"))?;
+    Ok(Route {
+        id: name.to_string(),
+        address,
+        priority,
+        members,
+        senders,
+        excluded,
+    })
+}
+
+/// Signs requests with AWS Signature Version 4.
+///
+/// See https://docs.aws.amazon.com/IAM/latest/UserGuide/reference_sigv-create-signed-request.html
+struct Signer<'a> {
+    access_key_id: &'a str,
+    secret_access_key: &'a str,
+    region: &'a str,
+    service: &'a str,
+}
+
+impl Signer<'_> {
+    /// The `authorization` header of a request sent at `now`.
+    ///
+    /// `query` is the canonical query string, and `headers` are the signed headers, sorted by
+    /// name and including `host` and `x-amz-date`.
+    fn authorization(
+        &self,
+        method: &Method,
+        path: &str,
+        query: &str,
+        headers: &[(&str, String)],
+        payload: &str,
+        now: DateTime<Utc>,
+    ) -> String {
+        let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+        let date = now.format("%Y%m%d").to_string();
+
+        let signed_headers = headers
+            .iter()
+            .map(|(name, _)| *name)
+            .collect::<Vec<_>>()
+            .join(";");
+        let canonical_headers = headers
+            .iter()
+            .map(|(name, value)| format!("{name}:{value}\n"))
+            .collect::<String>();
+        let canonical_request = format!(
+            "{method}\n{path}\n{query}\n{canonical_headers}\n{signed_headers}\n{}",
+            sha256_hex(payload.as_bytes())
+        );
+        let scope = format!("{date}/{}/{}/aws4_request", self.region, self.service);
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{amz_date}\n{scope}\n{}",
+            sha256_hex(canonical_request.as_bytes())
+        );
+        let secret = format!("AWS4{}", self.secret_access_key);
+        let signing_key = [date.as_str(), self.region, self.service, "aws4_request"]
+            .iter()
+            .fold(secret.into_bytes(), |key, part| hmac_sha256(&key, part));
+        let signature = hex(&hmac_sha256(&signing_key, &string_to_sign));
+        format!(
+            "AWS4-HMAC-SHA256 Credential={}/{scope}, SignedHeaders={signed_headers}, Signature={signature}",
+            self.access_key_id
+        )
+    }
+}
+
+fn sha256_hex(data: &[u8]) -> String {
+    hex(digest::digest(&digest::SHA256, data).as_ref())
+}
+
+fn hmac_sha256(key: &[u8], data: &str) -> Vec<u8> {
+    let key = hmac::Key::new(hmac::HMAC_SHA256, key);
+    hmac::sign(&key, data.as_bytes()).as_ref().to_vec()
+}
+
+/// Percent-encode a query parameter as expected by Signature Version 4
+fn uri_encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                char::from(b).to_string()
+            }
+            _ => format!("%{b:02X}"),
+        })
+        .collect()
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ses() -> Ses {
+        Ses {
+            client: Client::new(),
+            access_key_id: "AKIDEXAMPLE".into(),
+            secret_access_key: SecretString::from("secret"),
+            session_token: None,
+            region: "us-east-1".into(),
+            rule_set_id: "rs-1234".into(),
+            send_role_arn: "arn:aws:iam::123456789012:role/send".into(),
+            dry_run: false,
+            pending_rules: Mutex::new(None),
+        }
+    }
+
+    fn list(address: &str, priority: i32, senders: Senders, excluded: &[&str]) -> List {
+        List {
+            address: address.into(),
+            members: vec!["foo@example.com".into(), "bar@example.net".into()],
+            priority,
+            senders,
+            excluded: excluded.iter().map(|a| a.to_string()).collect(),
+        }
+    }
+
+    /// Vectors of the AWS Signature Version 4 test suite
+    #[test]
+    fn test_signature() {
+        let signer = Signer {
+            access_key_id: "AKIDEXAMPLE",
+            secret_access_key: "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY",
+            region: "us-east-1",
+            service: "service",
+        };
+        let now = DateTime::parse_from_rfc3339("2015-08-30T12:36:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let headers = [
+            ("host", "example.amazonaws.com".to_string()),
+            ("x-amz-date", "20150830T123600Z".to_string()),
+        ];
+
+        // get-vanilla
+        assert_eq!(
+            signer.authorization(&Method::GET, "/", "", &headers, "", now),
+            "AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20150830/us-east-1/service/aws4_request, \
+             SignedHeaders=host;x-amz-date, \
+             Signature=5fa00fa31553b73ebf1942676e86291e8372ff2a2260956d9b8aae1d763fbf31"
+        );
+        // get-vanilla-query-order-key-case
+        assert_eq!(
+            signer.authorization(
+                &Method::GET,
+                "/",
+                "Param1=value1&Param2=value2",
+                &headers,
+                "",
+                now
+            ),
+            "AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20150830/us-east-1/service/aws4_request, \
+             SignedHeaders=host;x-amz-date, \
+             Signature=b97d918cfa904a5beff61c982a1b6f458b799221646efd99d3219ec94cdf2500"
+        );
+    }
+
+    #[test]
+    fn test_route_rules() {
+        let ses = ses();
+        for list in [
+            list("list@example.com", 0, Senders::Everyone, &[]),
+            list(
+                "list@example.com",
+                5,
+                Senders::Addresses(vec!["a+b@example.com".into(), "c@example.net".into()]),
+                &[],
+            ),
+            list(
+                "list@example.com",
+                0,
+                Senders::Domain("example.com".into()),
+                &[],
+            ),
+            list("*@example.com", 0, Senders::Everyone, &[]),
+            list(
+                "*@example.com",
+                0,
+                Senders::Domain("example.com".into()),
+                &["a@example.com", "b@example.com"],
+            ),
+        ] {
+            let id = route_id(&list);
+            let rules = ses.rules(&id, &list).unwrap();
+            // Lists that are not catch-alls have a second rule for their `+` aliases
+            assert_eq!(
+                rules.len(),
+                if list.address.starts_with('*') { 1 } else { 2 }
+            );
+            let route = parse_route(&id, &rules[0]).unwrap();
+            assert_eq!(route.address, list.address);
+            assert_eq!(route.priority, list.priority);
+            assert_eq!(route.members, list.members);
+            assert_eq!(route.senders, list.senders);
+            assert_eq!(route.excluded, list.excluded);
+        }
+    }
+
+    #[test]
+    fn test_route_id() {
+        for priority in [0, 7, 42, -1] {
+            let list = list("list@example.com", priority, Senders::Everyone, &[]);
+            let id = route_id(&list);
+            assert!(id.len() <= 32, "{id}");
+            assert_eq!(route_priority(&id), Some(priority));
+            assert_eq!(
+                route_priority(&format!("{id}{ALIAS_RULE_SUFFIX}")),
+                Some(priority)
+            );
+            let rules = ses().rules(&id, &list).unwrap();
+            assert_eq!(parse_route(&id, &rules[0]).unwrap().priority, priority);
+        }
+        assert_eq!(route_priority("other-rule-1"), None);
+    }
+
+    #[test]
+    fn test_route_rules_json() {
+        let list = list(
+            "list@example.com",
+            0,
+            Senders::Addresses(vec!["a@example.com".into()]),
+            &[],
+        );
+        let rules = ses().rules("team-sync-1234-0", &list).unwrap();
+        insta::assert_snapshot!(serde_json::to_string_pretty(&rules).unwrap(), @r#"
+        [
+          {
+            "Actions": [
+              {
+                "ReplaceRecipient": {
+                  "ReplaceWith": [
+                    "foo@example.com",
+                    "bar@example.net"
+                  ]
+                }
+              },
+              {
+                "Send": {
+                  "RoleArn": "arn:aws:iam::123456789012:role/send"
+                }
+              }
+            ],
+            "Conditions": [
+              {
+                "StringExpression": {
+                  "Evaluate": {
+                    "Attribute": "RECIPIENT"
+                  },
+                  "Operator": "EQUALS",
+                  "Values": [
+                    "list@example.com"
+                  ]
+                }
+              },
+              {
+                "StringExpression": {
+                  "Evaluate": {
+                    "Attribute": "MAIL_FROM"
+                  },
+                  "Operator": "EQUALS",
+                  "Values": [
+                    "a@example.com"
+                  ]
+                }
+              }
+            ],
+            "Name": "team-sync-1234-0"
+          },
+          {
+            "Actions": [
+              {
+                "ReplaceRecipient": {
+                  "ReplaceWith": [
+                    "foo@example.com",
+                    "bar@example.net"
+                  ]
+                }
+              },
+              {
+                "Send": {
+                  "RoleArn": "arn:aws:iam::123456789012:role/send"
+                }
+              }
+            ],
+            "Conditions": [
+              {
+                "StringExpression": {
+                  "Evaluate": {
+                    "Attribute": "RECIPIENT"
+                  },
+                  "Operator": "STARTS_WITH",
+                  "Values": [
+                    "list+"
+                  ]
+                }
+              },
+              {
+                "StringExpression": {
+                  "Evaluate": {
+                    "Attribute": "RECIPIENT"
+                  },
+                  "Operator": "ENDS_WITH",
+                  "Values": [
+                    "@example.com"
+                  ]
+                }
+              },
+              {
+                "StringExpression": {
+                  "Evaluate": {
+                    "Attribute": "MAIL_FROM"
+                  },
+                  "Operator": "EQUALS",
+                  "Values": [
+                    "a@example.com"
+                  ]
+                }
+              }
+            ],
+            "Name": "team-sync-1234-0-plus"
+          }
+        ]
+        "#);
+    }
+
+    #[test]
+    fn test_replace_rules() {
+        let rule = |name: &str| json!({ "Name": name });
+        let mut rules = vec![
+            rule("team-sync-aaa-10"),
+            rule("team-sync-aaa-10-plus"),
+            rule("manual"),
+            rule("team-sync-bbb-0"),
+            rule("team-sync-bbb-0-plus"),
+        ];
+        // Update a route, delete another one and create a third one
+        replace_rules(
+            &mut rules,
+            "team-sync-bbb-0",
+            vec![rule("team-sync-bbb-0"), rule("team-sync-bbb-0-plus")],
+        );
+        replace_rules(&mut rules, "team-sync-aaa-10", vec![]);
+        replace_rules(
+            &mut rules,
+            "team-sync-ccc-5",
+            vec![rule("team-sync-ccc-5"), rule("team-sync-ccc-5-plus")],
+        );
+        rules.sort_by_key(rule_priority);
+        let names = rules
+            .iter()
+            .map(|rule| rule["Name"].as_str().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            [
+                "manual",
+                "team-sync-bbb-0",
+                "team-sync-bbb-0-plus",
+                "team-sync-ccc-5",
+                "team-sync-ccc-5-plus",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_dry_run_changes_nothing() {
+        let ses = Ses {
+            dry_run: true,
+            ..ses()
+        };
+        let list = list("list@example.com", 0, Senders::Everyone, &[]);
+        ses.create_route(&list).await.unwrap();
+        assert!(ses.pending_rules.lock().unwrap().is_none());
+        // Nothing is written, so no request is sent
+        ses.commit().await.unwrap();
+    }
+}
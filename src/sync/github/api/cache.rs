@@ -0,0 +1,455 @@
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use anyhow::Context as _;
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use log::{debug, warn};
+use reqwest::header::{self, HeaderMap, HeaderValue};
+use reqwest::{Method, RequestBuilder, Response, StatusCode};
+use rust_team_data::v1::ActionsPermissions;
+
+use super::{
+    AppPushAllowanceActor, BranchPolicy, BranchProtection, Environment, GitHubApiRead, GithubRead,
+    HttpClient, OrgSettings, Repo, RepoAutolink, RepoDeployKey, RepoLabel, RepoTeam, RepoUser,
+    RepoWebhook, Ruleset, Team, TeamMember, TokenPermissions,
+};
+
+/// How long GraphQL responses are reused, as GraphQL doesn't support conditional requests.
+const GRAPHQL_MAX_AGE_MINUTES: i64 = 10;
+
+/// A [`GithubRead`] that caches the responses of GitHub on disk, so that repeated dry runs only
+/// fetch what changed since the previous run.
+pub(crate) struct CachedGithubRead {
+    inner: GitHubApiRead,
+}
+
+impl CachedGithubRead {
+    pub(crate) fn new(client: HttpClient, dir: &Path) -> anyhow::Result<Self> {
+        let client = client.with_cache(ResponseCache::new(dir)?);
+        Ok(Self {
+            inner: GitHubApiRead::from_client(client)?,
+        })
+    }
+}
+
+#[async_trait]
+impl GithubRead for CachedGithubRead {
+    fn uses_pat(&self) -> bool {
+        self.inner.uses_pat()
+    }
+
+    async fn usernames(&self, ids: &[u64]) -> anyhow::Result<HashMap<u64, String>> {
+        self.inner.usernames(ids).await
+    }
+
+    async fn org_owners(&self, org: &str) -> anyhow::Result<HashSet<u64>> {
+        self.inner.org_owners(org).await
+    }
+
+    async fn org_settings(&self, org: &str) -> anyhow::Result<OrgSettings> {
+        self.inner.org_settings(org).await
+    }
+
+    async fn custom_repo_roles(&self, org: &str) -> anyhow::Result<HashSet<String>> {
+        self.inner.custom_repo_roles(org).await
+    }
+
+    async fn token_permissions(&self, org: &str) -> anyhow::Result<TokenPermissions> {
+        self.inner.token_permissions(org).await
+    }
+
+    async fn org_members(&self, org: &str) -> anyhow::Result<HashMap<u64, String>> {
+        self.inner.org_members(org).await
+    }
+
+    async fn org_teams(&self, org: &str) -> anyhow::Result<Vec<(String, String)>> {
+        self.inner.org_teams(org).await
+    }
+
+    async fn team(&self, org: &str, team: &str) -> anyhow::Result<Option<Team>> {
+        self.inner.team(org, team).await
+    }
+
+    async fn team_by_id(&self, org: &str, id: u64) -> anyhow::Result<Option<Team>> {
+        self.inner.team_by_id(org, id).await
+    }
+
+    async fn team_memberships(
+        &self,
+        team: &Team,
+        org: &str,
+    ) -> anyhow::Result<HashMap<u64, TeamMember>> {
+        self.inner.team_memberships(team, org).await
+    }
+
+    async fn team_membership_invitations(
+        &self,
+        org: &str,
+        team: &str,
+    ) -> anyhow::Result<HashSet<String>> {
+        self.inner.team_membership_invitations(org, team).await
+    }
+
+    async fn repo(&self, org: &str, repo: &str) -> anyhow::Result<Option<Repo>> {
+        self.inner.repo(org, repo).await
+    }
+
+    async fn prefetch_repos(&self, org: &str, repos: &[String]) -> anyhow::Result<()> {
+        self.inner.prefetch_repos(org, repos).await
+    }
+
+    async fn branch_exists(&self, org: &str, repo: &str, branch: &str) -> anyhow::Result<bool> {
+        self.inner.branch_exists(org, repo, branch).await
+    }
+
+    async fn app(&self, org: &str, slug: &str) -> anyhow::Result<Option<AppPushAllowanceActor>> {
+        self.inner.app(org, slug).await
+    }
+
+    async fn repo_teams(&self, org: &str, repo: &str) -> anyhow::Result<Vec<RepoTeam>> {
+        self.inner.repo_teams(org, repo).await
+    }
+
+    async fn repo_collaborators(&self, org: &str, repo: &str) -> anyhow::Result<Vec<RepoUser>> {
+        self.inner.repo_collaborators(org, repo).await
+    }
+
+    async fn repo_invitations(&self, org: &str, repo: &str) -> anyhow::Result<Vec<RepoUser>> {
+        self.inner.repo_invitations(org, repo).await
+    }
+
+    async fn repo_labels(&self, org: &str, repo: &str) -> anyhow::Result<Vec<RepoLabel>> {
+        self.inner.repo_labels(org, repo).await
+    }
+
+    async fn repo_webhooks(&self, org: &str, repo: &str) -> anyhow::Result<Vec<RepoWebhook>> {
+        self.inner.repo_webhooks(org, repo).await
+    }
+
+    async fn repo_autolinks(&self, org: &str, repo: &str) -> anyhow::Result<Vec<RepoAutolink>> {
+        self.inner.repo_autolinks(org, repo).await
+    }
+
+    async fn repo_deploy_keys(&self, org: &str, repo: &str) -> anyhow::Result<Vec<RepoDeployKey>> {
+        self.inner.repo_deploy_keys(org, repo).await
+    }
+
+    async fn repo_actions_permissions(
+        &self,
+        org: &str,
+        repo: &str,
+    ) -> anyhow::Result<Option<ActionsPermissions>> {
+        self.inner.repo_actions_permissions(org, repo).await
+    }
+
+    async fn branch_protections(
+        &self,
+        org: &str,
+        repo: &str,
+    ) -> anyhow::Result<HashMap<String, (String, BranchProtection)>> {
+        self.inner.branch_protections(org, repo).await
+    }
+
+    async fn repo_environments(
+        &self,
+        org: &str,
+        repo: &str,
+    ) -> anyhow::Result<HashMap<String, Environment>> {
+        self.inner.repo_environments(org, repo).await
+    }
+
+    async fn repo_rulesets(&self, org: &str, repo: &str) -> anyhow::Result<Vec<Ruleset>> {
+        self.inner.repo_rulesets(org, repo).await
+    }
+
+    async fn environment_branch_policies(
+        &self,
+        org: &str,
+        repo: &str,
+        environment: &str,
+    ) -> anyhow::Result<Vec<BranchPolicy>> {
+        self.inner
+            .environment_branch_policies(org, repo, environment)
+            .await
+    }
+}
+
+/// On-disk cache of GitHub API responses.
+///
+/// REST responses are revalidated with conditional requests (`If-None-Match`), which GitHub
+/// answers with `304 Not Modified` without counting them against the rate limit. GraphQL
+/// responses are reused for [`GRAPHQL_MAX_AGE_MINUTES`].
+#[derive(Clone, Debug)]
+pub(crate) struct ResponseCache {
+    dir: PathBuf,
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+struct CachedResponse {
+    /// The URL of REST requests, or the URL and body of GraphQL requests
+    key: String,
+    /// Only set for REST responses
+    etag: Option<String>,
+    stored_at: DateTime<Utc>,
+    /// Pagination links of the response
+    link: Option<String>,
+    body: String,
+}
+
+impl ResponseCache {
+    pub(crate) fn new(dir: &Path) -> anyhow::Result<Self> {
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("failed to create cache directory {}", dir.display()))?;
+        Ok(Self {
+            dir: dir.to_path_buf(),
+        })
+    }
+
+    /// Send a request, reusing the cached response of GET requests if GitHub reports it is
+    /// still fresh, and the cached response of GraphQL requests if it is recent enough.
+    pub(crate) async fn send(&self, req: RequestBuilder) -> anyhow::Result<Response> {
+        let (client, request) = req.build_split();
+        let mut request = request?;
+        let url = request.url().to_string();
+        let is_graphql = request.method() == Method::POST && url.ends_with("/graphql");
+        if !is_graphql && request.method() != Method::GET {
+            return super::send_rate_limited(RequestBuilder::from_parts(client, request)).await;
+        }
+
+        let key = match request.body().and_then(|body| body.as_bytes()) {
+            Some(body) if is_graphql => format!("{url}\n{}", String::from_utf8_lossy(body)),
+            _ => url.clone(),
+        };
+        let cached = self.load(&key);
+        if let Some(cached) = &cached {
+            if is_graphql {
+                if cached.stored_at + Duration::minutes(GRAPHQL_MAX_AGE_MINUTES) > Utc::now() {
+                    debug!("using cached GraphQL response for {url}");
+                    return cached.to_response();
+                }
+            } else if let Some(etag) = &cached.etag {
+                request
+                    .headers_mut()
+                    .insert(header::IF_NONE_MATCH, HeaderValue::from_str(etag)?);
+            }
+        }
+        let resp = super::send_rate_limited(RequestBuilder::from_parts(client, request)).await?;
+
+        if resp.status() == StatusCode::NOT_MODIFIED
+            && let Some(cached) = cached
+        {
+            debug!("using cached response for {url}");
+            return cached.to_response();
+        }
+        if resp.status() != StatusCode::OK {
+            return Ok(resp);
+        }
+        let etag = match resp.headers().get(header::ETAG) {
+            Some(etag) => Some(etag.to_str()?.to_string()),
+            None if is_graphql => None,
+            None => return Ok(resp),
+        };
+
+        let status = resp.status();
+        let headers = resp.headers().clone();
+        let body = resp.text().await?;
+        // GraphQL reports errors, such as missing permissions, with a successful status
+        if is_graphql && has_graphql_errors(&body) {
+            return response(status, headers, body);
+        }
+        let cached = CachedResponse {
+            key,
+            etag,
+            stored_at: Utc::now(),
+            link: headers
+                .get(header::LINK)
+                .map(|link| link.to_str())
+                .transpose()?
+                .map(|link| link.to_string()),
+            body: body.clone(),
+        };
+        if let Err(error) = self.store(&cached) {
+            warn!("failed to cache response for {url}: {error:?}");
+        }
+        response(status, headers, body)
+    }
+
+    fn path(&self, key: &str) -> PathBuf {
+        let digest = ring::digest::digest(&ring::digest::SHA256, key.as_bytes());
+        let name: String = digest.as_ref().iter().map(|b| format!("{b:02x}")).collect();
+        self.dir.join(format!("{name}.json"))
+    }
+
+    fn load(&self, key: &str) -> Option<CachedResponse> {
+        let contents = std::fs::read(self.path(key)).ok()?;
+        serde_json::from_slice::<CachedResponse>(&contents)
+            .ok()
+            .filter(|cached| cached.key == key)
+    }
+
+    fn store(&self, cached: &CachedResponse) -> anyhow::Result<()> {
+        std::fs::write(self.path(&cached.key), serde_json::to_vec(cached)?)?;
+        Ok(())
+    }
+}
+
+impl CachedResponse {
+    fn to_response(&self) -> anyhow::Result<Response> {
+        let mut headers = HeaderMap::new();
+        if let Some(etag) = &self.etag {
+            headers.insert(header::ETAG, HeaderValue::from_str(etag)?);
+        }
+        if let Some(link) = &self.link {
+            headers.insert(header::LINK, HeaderValue::from_str(link)?);
+        }
+        response(StatusCode::OK, headers, self.body.clone())
+    }
+}
+
+fn has_graphql_errors(body: &str) -> bool {
+    let Ok(body) = serde_json::from_str::<serde_json::Value>(body) else {
+        return true;
+    };
+    match body.get("errors") {
+        None | Some(serde_json::Value::Null) => false,
+        Some(errors) => errors.as_array().is_none_or(|errors| !errors.is_empty()),
+    }
+}
+
+fn response(status: StatusCode, headers: HeaderMap, body: String) -> anyhow::Result<Response> {
+    let mut resp = http::Response::builder().status(status).body(body)?;
+    *resp.headers_mut() = headers;
+    Ok(Response::from(resp))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use reqwest::Client;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    use super::*;
+
+    /// Serve a REST endpoint that supports conditional requests and a GraphQL endpoint.
+    /// Returns the base URL and the requests received so far.
+    async fn serve() -> (String, Arc<Mutex<Vec<String>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let log = requests.clone();
+        tokio::spawn(async move {
+            loop {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let mut request = Vec::new();
+                let mut buf = [0; 1024];
+                let head_len = loop {
+                    if let Some(pos) = request.windows(4).position(|w| w == b"\r\n\r\n") {
+                        break pos + 4;
+                    }
+                    let read = stream.read(&mut buf).await.unwrap();
+                    request.extend_from_slice(&buf[..read]);
+                };
+                let head = String::from_utf8(request[..head_len].to_vec())
+                    .unwrap()
+                    .to_lowercase();
+                let header = |name: &str| {
+                    head.lines()
+                        .find_map(|line| line.strip_prefix(&format!("{name}: ")))
+                        .map(str::to_string)
+                };
+                let content_length: usize =
+                    header("content-length").map_or(0, |l| l.parse().unwrap());
+                while request.len() < head_len + content_length {
+                    let read = stream.read(&mut buf).await.unwrap();
+                    request.extend_from_slice(&buf[..read]);
+                }
+                let body = String::from_utf8(request[head_len..].to_vec()).unwrap();
+                let request_line = head.lines().next().unwrap().to_string();
+
+                let (status, headers, response_body) = if request_line.starts_with("get /repos ") {
+                    log.lock().unwrap().push(format!(
+                        "GET /repos if-none-match={:?}",
+                        header("if-none-match")
+                    ));
+                    if header("if-none-match").as_deref() == Some("\"v1\"") {
+                        ("304 Not Modified", "etag: \"v1\"\r\n", String::new())
+                    } else {
+                        ("200 OK", "etag: \"v1\"\r\n", "[1]".to_string())
+                    }
+                } else if request_line.starts_with("post /graphql ") {
+                    log.lock().unwrap().push(format!("POST /graphql {body}"));
+                    let n = log.lock().unwrap().len();
+                    ("200 OK", "", format!(r#"{{"data":{{"n":{n}}}}}"#))
+                } else {
+                    panic!("unexpected request {request_line}");
+                };
+                let response = format!(
+                    "HTTP/1.1 {status}\r\n{headers}content-length: {}\r\nconnection: close\r\n\r\n{response_body}",
+                    response_body.len()
+                );
+                stream.write_all(response.as_bytes()).await.unwrap();
+            }
+        });
+        (base, requests)
+    }
+
+    #[tokio::test]
+    async fn rest_responses_are_revalidated() {
+        let (base, requests) = serve().await;
+        let dir = tempfile::tempdir().unwrap();
+        let cache = ResponseCache::new(dir.path()).unwrap();
+        let client = Client::new();
+
+        // Miss: the response is fetched and stored
+        let first = cache
+            .send(client.get(format!("{base}/repos")))
+            .await
+            .unwrap();
+        assert_eq!(first.text().await.unwrap(), "[1]");
+        // Hit: GitHub reports that the stored response is still fresh
+        let second = cache
+            .send(client.get(format!("{base}/repos")))
+            .await
+            .unwrap();
+        assert_eq!(second.status(), StatusCode::OK);
+        assert_eq!(second.text().await.unwrap(), "[1]");
+
+        assert_eq!(
+            *requests.lock().unwrap(),
+            [
+                "GET /repos if-none-match=None",
+                "GET /repos if-none-match=Some(\"\\\"v1\\\"\")",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn graphql_responses_are_reused() {
+        let (base, requests) = serve().await;
+        let dir = tempfile::tempdir().unwrap();
+        let cache = ResponseCache::new(dir.path()).unwrap();
+        let client = Client::new();
+        let query = |body: &str| {
+            client
+                .post(format!("{base}/graphql"))
+                .body(body.to_string())
+        };
+
+        // Miss, then hit without contacting GitHub
+        let first = cache.send(query("a")).await.unwrap();
+        let second = cache.send(query("a")).await.unwrap();
+        assert_eq!(first.text().await.unwrap(), r#"{"data":{"n":1}}"#);
+        assert_eq!(second.text().await.unwrap(), r#"{"data":{"n":1}}"#);
+        // Miss, as the query is different
+        let other = cache.send(query("b")).await.unwrap();
+        assert_eq!(other.text().await.unwrap(), r#"{"data":{"n":2}}"#);
+
+        assert_eq!(
+            *requests.lock().unwrap(),
+            ["POST /graphql a", "POST /graphql b"]
+        );
+    }
+}
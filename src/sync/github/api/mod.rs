@@ -1,4 +1,6 @@
+mod cache;
 mod read;
+mod state;
 mod tokens;
 mod url;
 mod write;
@@ -7,22 +9,28 @@ use crate::sync::utils::ResponseExt;
 use anyhow::{Context, bail};
 use base64::Engine as _;
 use base64::prelude::BASE64_STANDARD;
+use cache::ResponseCache;
 use hyper_old_types::header::{Link, RelationType};
-use log::{debug, trace};
+use log::{debug, trace, warn};
 use reqwest::header::HeaderMap;
 use reqwest::{
     Client, Method, RequestBuilder, Response, StatusCode,
     header::{self, HeaderValue},
 };
+use rust_team_data::v1::DefaultRepoPermission;
 use secrecy::ExposeSecret;
-use serde::{Deserialize, de::DeserializeOwned};
-use std::collections::BTreeSet;
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use std::collections::{BTreeSet, HashMap};
 use std::fmt;
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use thiserror::Error;
 use tokens::GitHubTokens;
-use url::GitHubUrl;
+use url::{GitHubEndpoints, GitHubUrl};
 
+pub(crate) use cache::CachedGithubRead;
 pub(crate) use read::{GitHubApiRead, GithubRead};
+pub(crate) use state::GitHubState;
 pub(crate) use write::GitHubWrite;
 
 #[derive(Debug, Error)]
@@ -39,48 +47,123 @@ pub(crate) enum RestPaginatedError {
     Other(#[from] anyhow::Error),
 }
 
+/// A GraphQL query was not allowed to read some of the data it requested, e.g. the GitHub App
+/// actors of branch protections, which can only be read with a personal access token
+#[derive(Debug, Error)]
+#[error("graphql error: {0}")]
+pub(crate) struct GraphForbiddenError(pub(crate) String);
+
 #[derive(Clone)]
 pub(crate) struct HttpClient {
     client: Client,
     github_tokens: GitHubTokens,
+    endpoints: GitHubEndpoints,
+    cache: Option<ResponseCache>,
+    state: Option<GitHubState>,
 }
 
 impl HttpClient {
     pub(crate) fn new() -> anyhow::Result<Self> {
-        let mut builder = reqwest::ClientBuilder::default();
-        let mut map = HeaderMap::default();
+        Ok(Self {
+            client: build_client()?,
+            github_tokens: GitHubTokens::from_env()?,
+            endpoints: GitHubEndpoints::from_env(),
+            cache: None,
+            state: None,
+        })
+    }
 
-        map.insert(
-            header::USER_AGENT,
-            HeaderValue::from_static(crate::USER_AGENT),
-        );
-        builder = builder.default_headers(map);
+    /// Create a client that answers requests from a GitHub state recorded by
+    /// `sync-team export-state`, without network access.
+    pub(crate) fn from_state(path: &Path) -> anyhow::Result<Self> {
+        Ok(Self {
+            client: build_client()?,
+            // Replayed requests are not authenticated
+            github_tokens: GitHubTokens::Orgs(HashMap::new()),
+            endpoints: GitHubEndpoints::from_env(),
+            cache: None,
+            state: Some(GitHubState::load(path)?),
+        })
+    }
 
+    /// Create a client that sends its requests to a local stand-in for GitHub.
+    #[cfg(test)]
+    pub(crate) fn local(base: &str) -> anyhow::Result<Self> {
         Ok(Self {
-            client: builder.build()?,
-            github_tokens: GitHubTokens::from_env()?,
+            client: build_client()?,
+            github_tokens: GitHubTokens::Pat("token".to_string().into()),
+            endpoints: GitHubEndpoints::local(base),
+            cache: None,
+            state: None,
         })
     }
 
+    /// Record the responses of the requests sent to GitHub, so that they can be saved with
+    /// [`GitHubState::save`].
+    pub(crate) fn recording(mut self) -> (Self, GitHubState) {
+        let state = GitHubState::record(self.uses_pat());
+        self.state = Some(state.clone());
+        (self, state)
+    }
+
+    /// Send the requests that read data through the response cache.
+    fn with_cache(mut self, cache: ResponseCache) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
     pub fn uses_pat(&self) -> bool {
-        matches!(self.github_tokens, GitHubTokens::Pat(_))
+        self.state
+            .as_ref()
+            .and_then(|state| state.uses_pat())
+            .unwrap_or(matches!(self.github_tokens, GitHubTokens::Pat(_)))
     }
 
-    fn auth_header(&self, org: &str) -> anyhow::Result<HeaderValue> {
-        let token = self.github_tokens.get_token(org)?;
+    async fn auth_header(&self, org: &str) -> anyhow::Result<HeaderValue> {
+        let token = self.github_tokens.get_token(&self.client, org).await?;
         let mut auth = HeaderValue::from_str(&format!("token {}", token.expose_secret()))?;
         auth.set_sensitive(true);
         Ok(auth)
     }
 
-    fn req(&self, method: Method, url: &GitHubUrl) -> anyhow::Result<RequestBuilder> {
+    async fn req(&self, method: Method, url: &GitHubUrl) -> anyhow::Result<RequestBuilder> {
         trace!("http request: {} {}", method, url.url());
-        let token = self.auth_header(url.org())?;
-        let client = self
-            .client
-            .request(method, url.url())
-            .header(header::AUTHORIZATION, token);
-        Ok(client)
+        let client = self.client.request(method, url.resolve(&self.endpoints));
+        if let Some(GitHubState::Replay(_)) = self.state {
+            return Ok(client);
+        }
+        let token = self.auth_header(url.org()).await?;
+        Ok(client.header(header::AUTHORIZATION, token))
+    }
+
+    /// Send a request without a body.
+    async fn execute(&self, method: Method, url: &GitHubUrl) -> anyhow::Result<Response> {
+        self.send_request(self.req(method, url).await?).await
+    }
+
+    /// Send a request that reads data from GitHub, going through the recorded GitHub state
+    /// and the response cache if they are enabled.
+    async fn send_request(&self, req: RequestBuilder) -> anyhow::Result<Response> {
+        let Some(state) = &self.state else {
+            return self.send_live(req).await;
+        };
+        let (client, request) = req.build_split();
+        let request = request?;
+        let key = state::request_key(&request);
+        if let Some(resp) = state.replay(&key)? {
+            return Ok(resp);
+        }
+        let resp = self
+            .send_live(RequestBuilder::from_parts(client, request))
+            .await?;
+        state.store(key, resp).await
+    }
+
+    async fn send_live(&self, req: RequestBuilder) -> anyhow::Result<Response> {
+        match &self.cache {
+            Some(cache) => cache.send(req).await,
+            None => send_rate_limited(req).await,
+        }
     }
 
     async fn send<T: serde::Serialize + std::fmt::Debug>(
@@ -89,7 +172,7 @@ impl HttpClient {
         url: &GitHubUrl,
         body: &T,
     ) -> Result<Response, anyhow::Error> {
-        let resp = self.req(method, url)?.json(body).send().await?;
+        let resp = send_rate_limited(self.req(method, url).await?.json(body)).await?;
         resp.custom_error_for_status().await
     }
 
@@ -98,7 +181,7 @@ impl HttpClient {
         method: Method,
         url: &GitHubUrl,
     ) -> Result<Option<T>, anyhow::Error> {
-        let resp = self.req(method.clone(), url)?.send().await?;
+        let resp = self.execute(method.clone(), url).await?;
         match resp.status() {
             StatusCode::OK => Ok(Some(resp.json_annotated().await.with_context(|| {
                 format!(
@@ -111,6 +194,67 @@ impl HttpClient {
         }
     }
 
+    /// Identity of the token used for the organization, as recorded in the audit log
+    async fn actor(&self, org: &str) -> anyhow::Result<String> {
+        match &self.github_tokens {
+            GitHubTokens::App(app) => Ok(format!("app/{}", app.app_id())),
+            GitHubTokens::Orgs(_) => Ok(format!(
+                "token/GITHUB_TOKEN_{}",
+                org.to_uppercase().replace('-', "_")
+            )),
+            GitHubTokens::Pat(_) => {
+                #[derive(serde::Deserialize)]
+                struct User {
+                    login: String,
+                }
+                // REST API: https://docs.github.com/en/rest/users/users#get-the-authenticated-user
+                let user: User = self
+                    .req(Method::GET, &GitHubUrl::new("user", org))
+                    .await?
+                    .send()
+                    .await?
+                    .custom_error_for_status()
+                    .await?
+                    .json_annotated()
+                    .await?;
+                Ok(format!("user/{}", user.login))
+            }
+        }
+    }
+
+    /// Find out what the token used for the organization is allowed to do.
+    async fn token_permissions(&self, org: &str) -> anyhow::Result<TokenPermissions> {
+        if let Some(GitHubState::Replay(_)) = self.state {
+            return Ok(TokenPermissions::Unknown);
+        }
+        if let Some(permissions) = self
+            .github_tokens
+            .installation_permissions(&self.client, org)
+            .await?
+        {
+            return Ok(TokenPermissions::Installation(permissions));
+        }
+
+        // REST API: https://docs.github.com/en/rest/orgs/orgs#get-an-organization
+        let resp = self
+            .execute(Method::GET, &GitHubUrl::orgs(org, "")?)
+            .await?
+            .custom_error_for_status()
+            .await?;
+        // Only classic personal access tokens report their scopes
+        Ok(match resp.headers().get("x-oauth-scopes") {
+            Some(scopes) => TokenPermissions::Scopes(
+                scopes
+                    .to_str()?
+                    .split(',')
+                    .map(|scope| scope.trim().to_string())
+                    .filter(|scope| !scope.is_empty())
+                    .collect(),
+            ),
+            None => TokenPermissions::Unknown,
+        })
+    }
+
     /// Send a request to the GitHub API and return the response.
     async fn graphql<R, V>(&self, query: &str, variables: V, org: &str) -> anyhow::Result<R>
     where
@@ -120,7 +264,7 @@ impl HttpClient {
         let res = self.send_graphql_req(query, variables, org).await?;
 
         if let Some(error) = res.errors.first() {
-            bail!("graphql error: {}", error.message);
+            return Err(error.to_error());
         }
 
         read_graphql_data(res)
@@ -144,12 +288,44 @@ impl HttpClient {
             if error.type_ == Some(GraphErrorType::NotFound) {
                 return Ok(None);
             }
-            bail!("graphql error: {}", error.message);
+            return Err(error.to_error());
         }
 
         read_graphql_data(res)
     }
 
+    /// Send a request to the GitHub API and return the response, along with the errors
+    /// reported for each top-level field of the query, keyed by the name of the field.
+    /// Errors of the type `NOT_FOUND` are ignored, the corresponding fields are `null`.
+    async fn graphql_fields<R, V>(
+        &self,
+        query: &str,
+        variables: V,
+        org: &str,
+    ) -> anyhow::Result<(R, HashMap<String, anyhow::Error>)>
+    where
+        R: serde::de::DeserializeOwned,
+        V: serde::Serialize,
+    {
+        let res = self.send_graphql_req(query, variables, org).await?;
+
+        let mut field_errors = HashMap::new();
+        for error in &res.errors {
+            if error.type_ == Some(GraphErrorType::NotFound) {
+                continue;
+            }
+            // Errors that don't belong to a field affect the whole query
+            let Some(field) = error.path.first().and_then(|field| field.as_str()) else {
+                return Err(error.to_error());
+            };
+            field_errors
+                .entry(field.to_string())
+                .or_insert_with(|| error.to_error());
+        }
+
+        Ok((read_graphql_data(res)?, field_errors))
+    }
+
     async fn send_graphql_req<R, V>(
         &self,
         query: &str,
@@ -165,10 +341,12 @@ impl HttpClient {
             query: &'a str,
             variables: V,
         }
+        let req = self
+            .req(Method::POST, &GitHubUrl::graphql(org))
+            .await?
+            .json(&Request { query, variables });
         let resp = self
-            .req(Method::POST, &GitHubUrl::new("graphql", org))?
-            .json(&Request { query, variables })
-            .send()
+            .send_request(req)
             .await
             .context("failed to send graphql request")?
             .custom_error_for_status()
@@ -192,8 +370,7 @@ impl HttpClient {
         let mut next = Some(url.clone());
         while let Some(next_url) = next.take() {
             let resp = self
-                .req(method.clone(), &next_url)?
-                .send()
+                .execute(method.clone(), &next_url)
                 .await
                 .with_context(|| format!("failed to send request to {}", next_url.url()))?;
 
@@ -237,6 +414,67 @@ impl HttpClient {
     }
 }
 
+fn build_client() -> anyhow::Result<Client> {
+    let mut map = HeaderMap::default();
+    map.insert(
+        header::USER_AGENT,
+        HeaderValue::from_static(crate::USER_AGENT),
+    );
+    Ok(reqwest::ClientBuilder::default()
+        .default_headers(map)
+        .build()?)
+}
+
+/// How many times a request is retried after hitting a rate limit
+const RATE_LIMIT_RETRIES: u32 = 3;
+
+/// Send a request, waiting and retrying when GitHub reports that a rate limit was exceeded.
+async fn send_rate_limited(mut req: RequestBuilder) -> anyhow::Result<Response> {
+    let mut attempt = 0;
+    loop {
+        let retry = req.try_clone();
+        let resp = req.send().await?;
+        let Some(wait) = rate_limit_wait(&resp) else {
+            return Ok(resp);
+        };
+        let Some(retry) = retry.filter(|_| attempt < RATE_LIMIT_RETRIES) else {
+            return Ok(resp);
+        };
+        warn!(
+            "rate limit exceeded on {}, retrying in {} seconds",
+            resp.url(),
+            wait.as_secs()
+        );
+        tokio::time::sleep(wait).await;
+        attempt += 1;
+        req = retry;
+    }
+}
+
+/// Returns how long to wait before retrying, if the response was rejected by a rate limit.
+/// See https://docs.github.com/en/rest/using-the-rest-api/rate-limits-for-the-rest-api#exceeding-the-rate-limit
+fn rate_limit_wait(resp: &Response) -> Option<Duration> {
+    if !matches!(
+        resp.status(),
+        StatusCode::FORBIDDEN | StatusCode::TOO_MANY_REQUESTS
+    ) {
+        return None;
+    }
+    let header =
+        |name: &str| -> Option<u64> { resp.headers().get(name)?.to_str().ok()?.parse().ok() };
+
+    if let Some(retry_after) = header("retry-after") {
+        return Some(Duration::from_secs(retry_after));
+    }
+    if header("x-ratelimit-remaining") == Some(0) {
+        let reset = header("x-ratelimit-reset")?;
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).ok()?.as_secs();
+        return Some(Duration::from_secs(reset.saturating_sub(now) + 1));
+    }
+    // Secondary rate limits without any hint, GitHub recommends waiting at least a minute
+    (resp.status() == StatusCode::TOO_MANY_REQUESTS).then(|| Duration::from_secs(60))
+}
+
 fn read_graphql_data<R>(res: GraphResult<R>) -> anyhow::Result<R>
 where
     R: serde::de::DeserializeOwned,
@@ -272,12 +510,26 @@ struct GraphError {
     #[serde(rename = "type")]
     type_: Option<GraphErrorType>,
     message: String,
+    /// Path of the field that caused the error, if any
+    #[serde(default)]
+    path: Vec<serde_json::Value>,
+}
+
+impl GraphError {
+    fn to_error(&self) -> anyhow::Error {
+        if self.type_ == Some(GraphErrorType::Forbidden) {
+            GraphForbiddenError(self.message.clone()).into()
+        } else {
+            anyhow::anyhow!("graphql error: {}", self.message)
+        }
+    }
 }
 
 #[derive(Debug, serde::Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
 enum GraphErrorType {
     NotFound,
+    Forbidden,
     #[serde(other)]
     Other,
 }
@@ -319,6 +571,17 @@ pub(crate) struct Team {
     /// The slug usually matches the name but can differ.
     /// For example, a team named rustup.rs would have a slug rustup-rs.
     pub(crate) slug: String,
+    /// The team this team is nested under
+    #[serde(default)]
+    pub(crate) parent: Option<ParentTeam>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+pub(crate) struct ParentTeam {
+    pub(crate) name: String,
+    /// `None` for teams that don't exist yet, because they are created or renamed by the same sync
+    #[serde(default)]
+    pub(crate) id: Option<u64>,
 }
 
 #[derive(serde::Deserialize, Debug, Clone)]
@@ -327,6 +590,114 @@ pub(crate) struct RepoTeam {
     pub(crate) permission: RepoPermission,
 }
 
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq, Eq)]
+pub(crate) struct RepoLabel {
+    pub(crate) name: String,
+    pub(crate) color: String,
+    #[serde(default, deserialize_with = "nullable")]
+    pub(crate) description: String,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+pub(crate) struct RepoWebhook {
+    pub(crate) id: u64,
+    pub(crate) active: bool,
+    pub(crate) events: Vec<String>,
+    pub(crate) config: RepoWebhookConfig,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+pub(crate) struct RepoWebhookConfig {
+    #[serde(default)]
+    pub(crate) url: Option<String>,
+    #[serde(default)]
+    pub(crate) content_type: Option<String>,
+}
+
+impl RepoWebhookConfig {
+    /// GitHub uses the `form` content type if none is configured
+    pub(crate) fn content_type(&self) -> &str {
+        self.content_type.as_deref().unwrap_or("form")
+    }
+}
+
+#[derive(serde::Deserialize, Debug, Clone)]
+pub(crate) struct RepoAutolink {
+    pub(crate) id: u64,
+    pub(crate) key_prefix: String,
+    pub(crate) url_template: String,
+}
+
+#[derive(serde::Deserialize, Debug, Clone)]
+pub(crate) struct RepoDeployKey {
+    pub(crate) id: u64,
+    pub(crate) title: String,
+    /// The public key, in the OpenSSH format (`<type> <base64> [comment]`)
+    pub(crate) key: String,
+    pub(crate) read_only: bool,
+}
+
+impl RepoDeployKey {
+    /// The SHA256 fingerprint of the key, in the format of `ssh-keygen -l`.
+    /// Returns `None` if the key cannot be decoded.
+    pub(crate) fn fingerprint(&self) -> Option<String> {
+        use base64::Engine;
+        use base64::prelude::{BASE64_STANDARD, BASE64_STANDARD_NO_PAD};
+
+        let blob = BASE64_STANDARD
+            .decode(self.key.split_whitespace().nth(1)?)
+            .ok()?;
+        let digest = ring::digest::digest(&ring::digest::SHA256, &blob);
+        Some(format!("SHA256:{}", BASE64_STANDARD_NO_PAD.encode(digest)))
+    }
+}
+
+/// An environment of a repository
+#[derive(Clone, Debug, PartialEq, serde::Serialize)]
+pub(crate) struct Environment {
+    pub(crate) branches: Vec<String>,
+    pub(crate) tags: Vec<String>,
+    pub(crate) reviewers: Vec<EnvironmentReviewer>,
+    /// Minutes to wait before a deployment can proceed
+    pub(crate) wait_timer: u32,
+    pub(crate) protected_branches_only: bool,
+}
+
+/// A user or team that has to approve the deployments to an environment, with the ID the REST
+/// API references it by
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum EnvironmentReviewer {
+    User {
+        login: String,
+        id: u64,
+    },
+    Team {
+        slug: String,
+        /// `None` for teams that don't exist yet, because they are created by the same sync
+        id: Option<u64>,
+    },
+}
+
+impl EnvironmentReviewer {
+    /// Reviewers are the same if they have the same login or slug, whether their ID is known
+    pub(crate) fn key(&self) -> (bool, &str) {
+        match self {
+            EnvironmentReviewer::User { login, .. } => (false, login),
+            EnvironmentReviewer::Team { slug, .. } => (true, slug),
+        }
+    }
+}
+
+impl std::fmt::Display for EnvironmentReviewer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EnvironmentReviewer::User { login, .. } => write!(f, "@{login}"),
+            EnvironmentReviewer::Team { slug, .. } => write!(f, "{slug}"),
+        }
+    }
+}
+
 #[derive(serde::Deserialize, Clone)]
 pub(crate) struct RepoUser {
     #[serde(alias = "login")]
@@ -335,7 +706,7 @@ pub(crate) struct RepoUser {
     pub(crate) permission: RepoPermission,
 }
 
-#[derive(Copy, Clone, serde::Serialize, serde::Deserialize, Debug, PartialEq)]
+#[derive(Clone, serde::Serialize, serde::Deserialize, Debug, PartialEq)]
 #[serde(rename_all = "snake_case")]
 pub(crate) enum RepoPermission {
     // While the GitHub UI uses the term 'write', the API still uses the older term 'push'
@@ -346,6 +717,9 @@ pub(crate) enum RepoPermission {
     Triage,
     #[serde(alias = "pull")]
     Read,
+    /// A custom repository role of the organization
+    #[serde(untagged)]
+    Custom(String),
 }
 
 impl fmt::Display for RepoPermission {
@@ -356,6 +730,7 @@ impl fmt::Display for RepoPermission {
             Self::Maintain => write!(f, "maintain"),
             Self::Triage => write!(f, "triage"),
             Self::Read => write!(f, "read"),
+            Self::Custom(role) => write!(f, "{role}"),
         }
     }
 }
@@ -373,6 +748,24 @@ pub(crate) struct Repo {
     pub(crate) private: bool,
     #[serde(default)]
     pub(crate) allow_auto_merge: Option<bool>,
+    #[serde(default)]
+    pub(crate) allow_squash_merge: Option<bool>,
+    #[serde(default)]
+    pub(crate) allow_merge_commit: Option<bool>,
+    #[serde(default)]
+    pub(crate) allow_rebase_merge: Option<bool>,
+    #[serde(default)]
+    pub(crate) delete_branch_on_merge: Option<bool>,
+    #[serde(default)]
+    pub(crate) default_branch: Option<String>,
+    #[serde(default)]
+    pub(crate) has_issues: Option<bool>,
+    #[serde(default)]
+    pub(crate) has_wiki: Option<bool>,
+    #[serde(default)]
+    pub(crate) has_projects: Option<bool>,
+    #[serde(default)]
+    pub(crate) has_discussions: Option<bool>,
 }
 
 fn repo_owner<'de, D>(deserializer: D) -> Result<String, D::Error>
@@ -397,7 +790,7 @@ where
 }
 
 /// An object with a `login` field
-#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 pub(crate) struct Login {
     pub(crate) login: String,
 }
@@ -439,8 +832,8 @@ fn team_node_id(id: u64) -> String {
     BASE64_STANDARD.encode(format!("04:Team{id}"))
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, serde::Deserialize)]
-#[serde(rename_all = "camelCase")]
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all(deserialize = "camelCase"))]
 pub(crate) struct BranchProtection {
     pub(crate) pattern: String,
     pub(crate) is_admin_enforced: bool,
@@ -452,7 +845,13 @@ pub(crate) struct BranchProtection {
     pub(crate) required_status_check_contexts: Vec<String>,
     #[serde(deserialize_with = "allowances")]
     pub(crate) push_allowances: Vec<PushAllowanceActor>,
+    #[serde(deserialize_with = "allowances")]
+    pub(crate) bypass_pull_request_allowances: Vec<PushAllowanceActor>,
     pub(crate) requires_approving_reviews: bool,
+    pub(crate) requires_code_owner_reviews: bool,
+    pub(crate) requires_commit_signatures: bool,
+    pub(crate) requires_linear_history: bool,
+    pub(crate) requires_conversation_resolution: bool,
 }
 
 fn nullable<'de, D, T>(deserializer: D) -> Result<T, D::Error>
@@ -480,8 +879,9 @@ where
     Ok(allowances.nodes.into_iter().map(|a| a.actor).collect())
 }
 
-/// Entities that can be allowed to push to a branch in a repo
-#[derive(Clone, Deserialize, Debug, PartialEq, Eq)]
+/// Entities that can be allowed to push to a branch in a repo, or to bypass its
+/// pull request requirement
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Eq)]
 #[serde(untagged)]
 pub(crate) enum PushAllowanceActor {
     User(UserPushAllowanceActor),
@@ -490,20 +890,20 @@ pub(crate) enum PushAllowanceActor {
 }
 
 /// User who can be allowed to push to a branch in a repo
-#[derive(Clone, Deserialize, Debug, PartialEq, Eq)]
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Eq)]
 pub(crate) struct UserPushAllowanceActor {
     pub(crate) login: String,
 }
 
 /// Team that can be allowed to push to a branch in a repo
-#[derive(Clone, Deserialize, Debug, PartialEq, Eq)]
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Eq)]
 pub(crate) struct TeamPushAllowanceActor {
     pub(crate) organization: Login,
     pub(crate) name: String,
 }
 
 /// GitHub app that can be allowed to push to a branch in a repo
-#[derive(Clone, Deserialize, Debug, PartialEq, Eq)]
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Eq)]
 pub(crate) struct AppPushAllowanceActor {
     pub(crate) name: String,
     /// Node ID, which can be used as a push actor ID
@@ -515,12 +915,41 @@ pub(crate) enum BranchProtectionOp {
     UpdateBranchProtection(String),
 }
 
-#[derive(PartialEq, Debug)]
+#[derive(PartialEq, Debug, serde::Serialize)]
 pub(crate) struct RepoSettings {
     pub description: String,
     pub homepage: Option<String>,
     pub archived: bool,
     pub auto_merge_enabled: bool,
+    pub allow_squash_merge: bool,
+    pub allow_merge_commit: bool,
+    pub allow_rebase_merge: bool,
+    pub delete_branch_on_merge: bool,
+    pub has_issues: bool,
+    pub has_wiki: bool,
+    pub has_projects: bool,
+    pub has_discussions: bool,
+}
+
+/// Organization-wide settings of a GitHub organization
+#[derive(serde::Serialize, serde::Deserialize, Clone, PartialEq, Debug)]
+pub(crate) struct OrgSettings {
+    pub default_repository_permission: DefaultRepoPermission,
+    pub members_can_create_repositories: bool,
+    // Not returned for organizations that cannot have private repositories
+    #[serde(default)]
+    pub members_can_fork_private_repositories: bool,
+}
+
+/// What a GitHub token is allowed to do
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum TokenPermissions {
+    /// OAuth scopes of a classic personal access token
+    Scopes(BTreeSet<String>),
+    /// Permission name -> access level (`read` or `write`) of a GitHub App installation
+    Installation(HashMap<String, String>),
+    /// The permissions cannot be inspected (e.g. fine-grained personal access tokens)
+    Unknown,
 }
 
 /// GitHub Repository Ruleset
@@ -1,16 +1,21 @@
 use crate::sync::github::api;
-use crate::sync::github::api::{BranchPolicy, Ruleset};
 use crate::sync::github::api::{
-    BranchProtection, GraphNode, GraphNodes, GraphPageInfo, HttpClient, Login, Repo, RepoTeam,
-    RepoUser, RestPaginatedError, Team, TeamMember, TeamRole, team_node_id, url::GitHubUrl,
-    user_node_id,
+    AppPushAllowanceActor, BranchProtection, GraphNode, GraphNodes, GraphPageInfo, HttpClient,
+    Login, OrgSettings, Repo, RepoAutolink, RepoDeployKey, RepoLabel, RepoPermission, RepoTeam,
+    RepoUser, RepoWebhook, RestPaginatedError, Team, TeamMember, TeamRole, TokenPermissions,
+    team_node_id, url::GitHubUrl, user_node_id,
 };
+use crate::sync::github::api::{BranchPolicy, Environment, EnvironmentReviewer, Ruleset};
 use crate::sync::utils::ResponseExt;
 use anyhow::Context as _;
 use async_trait::async_trait;
+use log::debug;
 use reqwest::{Method, StatusCode};
-use rust_team_data::v1::Environment;
+use rust_team_data::v1::{
+    ActionsPermissions, AllowedActions, SelectedActions, WorkflowPermissions,
+};
 use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
 
 #[async_trait]
 pub(crate) trait GithubRead {
@@ -22,6 +27,15 @@ pub(crate) trait GithubRead {
     /// Get the owners of an org
     async fn org_owners(&self, org: &str) -> anyhow::Result<HashSet<u64>>;
 
+    /// Get the organization-wide settings of an org
+    async fn org_settings(&self, org: &str) -> anyhow::Result<OrgSettings>;
+
+    /// Get the names of the custom repository roles of an org
+    async fn custom_repo_roles(&self, org: &str) -> anyhow::Result<HashSet<String>>;
+
+    /// Get the permissions of the token used for the organization
+    async fn token_permissions(&self, org: &str) -> anyhow::Result<TokenPermissions>;
+
     /// Get the members of an org
     async fn org_members(&self, org: &str) -> anyhow::Result<HashMap<u64, String>>;
 
@@ -33,6 +47,9 @@ pub(crate) trait GithubRead {
     /// Get the team by name and org
     async fn team(&self, org: &str, team: &str) -> anyhow::Result<Option<Team>>;
 
+    /// Get the team by its ID and org
+    async fn team_by_id(&self, org: &str, id: u64) -> anyhow::Result<Option<Team>>;
+
     async fn team_memberships(
         &self,
         team: &Team,
@@ -49,6 +66,16 @@ pub(crate) trait GithubRead {
     /// Get a repo by org and name
     async fn repo(&self, org: &str, repo: &str) -> anyhow::Result<Option<Repo>>;
 
+    /// Fetch the data of many repositories of an organization with a few batched requests,
+    /// so that later calls for these repositories don't need to send requests of their own.
+    async fn prefetch_repos(&self, org: &str, repos: &[String]) -> anyhow::Result<()>;
+
+    /// Does the given branch exist in the repo?
+    async fn branch_exists(&self, org: &str, repo: &str, branch: &str) -> anyhow::Result<bool>;
+
+    /// Get a GitHub App by its slug
+    async fn app(&self, org: &str, slug: &str) -> anyhow::Result<Option<AppPushAllowanceActor>>;
+
     /// Get teams in a repo
     async fn repo_teams(&self, org: &str, repo: &str) -> anyhow::Result<Vec<RepoTeam>>;
 
@@ -57,6 +84,29 @@ pub(crate) trait GithubRead {
     /// Only fetches those who are direct collaborators (i.e., not a collaborator through a repo team)
     async fn repo_collaborators(&self, org: &str, repo: &str) -> anyhow::Result<Vec<RepoUser>>;
 
+    /// Get users that were invited to become collaborators of a repo, but haven't accepted yet
+    async fn repo_invitations(&self, org: &str, repo: &str) -> anyhow::Result<Vec<RepoUser>>;
+
+    /// Get the labels of a repository
+    async fn repo_labels(&self, org: &str, repo: &str) -> anyhow::Result<Vec<RepoLabel>>;
+
+    /// Get the webhooks of a repository
+    async fn repo_webhooks(&self, org: &str, repo: &str) -> anyhow::Result<Vec<RepoWebhook>>;
+
+    /// Get the autolink references of a repository
+    async fn repo_autolinks(&self, org: &str, repo: &str) -> anyhow::Result<Vec<RepoAutolink>>;
+
+    /// Get the deploy keys of a repository
+    async fn repo_deploy_keys(&self, org: &str, repo: &str) -> anyhow::Result<Vec<RepoDeployKey>>;
+
+    /// Get the GitHub Actions permissions of a repository
+    /// Returns `None` if GitHub Actions are disabled in the repository
+    async fn repo_actions_permissions(
+        &self,
+        org: &str,
+        repo: &str,
+    ) -> anyhow::Result<Option<ActionsPermissions>>;
+
     /// Get branch_protections
     /// Returns a map branch pattern -> (protection ID, protection data)
     async fn branch_protections(
@@ -87,11 +137,16 @@ pub(crate) trait GithubRead {
 
 pub(crate) struct GitHubApiRead {
     client: HttpClient,
+    // (org, repo) -> data fetched by `prefetch_repos`
+    prefetched: Mutex<HashMap<(String, String), PrefetchedRepo>>,
 }
 
 impl GitHubApiRead {
     pub(crate) fn from_client(client: HttpClient) -> anyhow::Result<Self> {
-        Ok(Self { client })
+        Ok(Self {
+            client,
+            prefetched: Mutex::new(HashMap::new()),
+        })
     }
 }
 
@@ -161,6 +216,42 @@ impl GithubRead for GitHubApiRead {
         Ok(owners)
     }
 
+    async fn org_settings(&self, org: &str) -> anyhow::Result<OrgSettings> {
+        // REST API: https://docs.github.com/en/rest/orgs/orgs#get-an-organization
+        self.client
+            .send_option(Method::GET, &GitHubUrl::orgs(org, "")?)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("GitHub organization {org} not found"))
+    }
+
+    async fn custom_repo_roles(&self, org: &str) -> anyhow::Result<HashSet<String>> {
+        #[derive(serde::Deserialize)]
+        struct CustomRoles {
+            custom_roles: Vec<CustomRole>,
+        }
+
+        #[derive(serde::Deserialize)]
+        struct CustomRole {
+            name: String,
+        }
+
+        // REST API: https://docs.github.com/en/rest/orgs/custom-roles#list-custom-repository-roles-in-an-organization
+        let roles: Option<CustomRoles> = self
+            .client
+            .send_option(
+                Method::GET,
+                &GitHubUrl::orgs(org, "custom-repository-roles")?,
+            )
+            .await?;
+        Ok(roles
+            .map(|r| r.custom_roles.into_iter().map(|role| role.name).collect())
+            .unwrap_or_default())
+    }
+
+    async fn token_permissions(&self, org: &str) -> anyhow::Result<TokenPermissions> {
+        self.client.token_permissions(org).await
+    }
+
     async fn org_members(&self, org: &str) -> anyhow::Result<HashMap<u64, String>> {
         #[derive(serde::Deserialize, Eq, PartialEq, Hash)]
         struct User {
@@ -209,6 +300,23 @@ impl GithubRead for GitHubApiRead {
             .await
     }
 
+    async fn team_by_id(&self, org: &str, id: u64) -> anyhow::Result<Option<Team>> {
+        let mut found = None;
+        self.client
+            .rest_paginated(
+                &Method::GET,
+                &GitHubUrl::orgs(org, "teams")?,
+                |resp: Vec<Team>| {
+                    if found.is_none() {
+                        found = resp.into_iter().find(|t| t.id == Some(id));
+                    }
+                    Ok(())
+                },
+            )
+            .await?;
+        Ok(found)
+    }
+
     async fn team_memberships(
         &self,
         team: &Team,
@@ -318,6 +426,10 @@ impl GithubRead for GitHubApiRead {
     }
 
     async fn repo(&self, org: &str, repo: &str) -> anyhow::Result<Option<Repo>> {
+        if let Some(prefetched) = self.prefetched(org, repo) {
+            return Ok(prefetched.repo.clone());
+        }
+
         // We use the GraphQL API instead of REST because of
         // this bug: https://github.com/orgs/community/discussions/153258
         #[derive(serde::Serialize)]
@@ -326,41 +438,26 @@ impl GithubRead for GitHubApiRead {
             name: &'a str,
         }
 
-        static QUERY: &str = r#"
-            query($owner: String!, $name: String!) {
-                repository(owner: $owner, name: $name) {
-                    id
-                    autoMergeAllowed
-                    description
-                    homepageUrl
-                    isArchived
-                    isPrivate
-                }
-            }
-        "#;
+        let query = format!(
+            "
+            query($owner: String!, $name: String!) {{
+                repository(owner: $owner, name: $name) {{
+                    ...RepoSettings
+                }}
+            }}
+            {REPO_SETTINGS_FRAGMENT}
+            "
+        );
 
         #[derive(serde::Deserialize)]
         struct Wrapper {
             repository: Option<RepoResponse>,
         }
 
-        #[derive(serde::Deserialize)]
-        #[serde(rename_all = "camelCase")]
-        struct RepoResponse {
-            // Equivalent of `node_id` of the Rest API
-            id: String,
-            // Equivalent of `id` of the Rest API
-            auto_merge_allowed: Option<bool>,
-            description: Option<String>,
-            homepage_url: Option<String>,
-            is_archived: bool,
-            is_private: bool,
-        }
-
         let result: Option<Wrapper> = self
             .client
             .graphql_opt(
-                QUERY,
+                &query,
                 Params {
                     owner: org,
                     name: repo,
@@ -370,18 +467,50 @@ impl GithubRead for GitHubApiRead {
             .await
             .with_context(|| format!("failed to retrieve repo `{org}/{repo}`"))?;
 
-        let repo = result.and_then(|r| r.repository).map(|repo_response| Repo {
-            node_id: repo_response.id,
-            name: repo.to_string(),
-            description: repo_response.description.unwrap_or_default(),
-            allow_auto_merge: repo_response.auto_merge_allowed,
-            archived: repo_response.is_archived,
-            homepage: repo_response.homepage_url,
-            org: org.to_string(),
-            private: repo_response.is_private,
-        });
+        Ok(result
+            .and_then(|r| r.repository)
+            .map(|repo_response| repo_response.into_repo(org, repo)))
+    }
+
+    async fn prefetch_repos(&self, org: &str, repos: &[String]) -> anyhow::Result<()> {
+        // GraphQL only reports the base role of collaborators, so the REST API has to be used
+        // to find out which collaborators have a custom role
+        let include_collaborators = self.custom_repo_roles(org).await?.is_empty();
+        for batch in repos.chunks(REPO_BATCH_SIZE) {
+            self.prefetch_repo_batch(org, batch, include_collaborators)
+                .await?;
+        }
+        Ok(())
+    }
 
-        Ok(repo)
+    async fn branch_exists(&self, org: &str, repo: &str, branch: &str) -> anyhow::Result<bool> {
+        let branch: Option<serde::de::IgnoredAny> = self
+            .client
+            .send_option(
+                Method::GET,
+                &GitHubUrl::repos(org, repo, &format!("branches/{branch}"))?,
+            )
+            .await
+            .with_context(|| format!("failed to retrieve branch `{branch}` of `{org}/{repo}`"))?;
+        Ok(branch.is_some())
+    }
+
+    async fn app(&self, org: &str, slug: &str) -> anyhow::Result<Option<AppPushAllowanceActor>> {
+        #[derive(serde::Deserialize)]
+        struct App {
+            node_id: String,
+            name: String,
+        }
+
+        let app: Option<App> = self
+            .client
+            .send_option(Method::GET, &GitHubUrl::new(&format!("apps/{slug}"), org))
+            .await
+            .with_context(|| format!("failed to retrieve GitHub App `{slug}`"))?;
+        Ok(app.map(|app| AppPushAllowanceActor {
+            name: app.name,
+            id: app.node_id,
+        }))
     }
 
     async fn repo_teams(&self, org: &str, repo: &str) -> anyhow::Result<Vec<RepoTeam>> {
@@ -401,7 +530,42 @@ impl GithubRead for GitHubApiRead {
         Ok(teams)
     }
 
+    async fn repo_invitations(&self, org: &str, repo: &str) -> anyhow::Result<Vec<RepoUser>> {
+        #[derive(serde::Deserialize)]
+        struct Invitation {
+            // Missing for invitations of email addresses without a GitHub account
+            invitee: Option<Login>,
+            permissions: RepoPermission,
+        }
+
+        let mut users = Vec::new();
+        // REST API: https://docs.github.com/en/rest/collaborators/invitations#list-repository-invitations
+        self.client
+            .rest_paginated(
+                &Method::GET,
+                &GitHubUrl::repos(org, repo, "invitations")?,
+                |resp: Vec<Invitation>| {
+                    users.extend(resp.into_iter().filter_map(|invitation| {
+                        Some(RepoUser {
+                            name: invitation.invitee?.login,
+                            permission: invitation.permissions,
+                        })
+                    }));
+                    Ok(())
+                },
+            )
+            .await?;
+        Ok(users)
+    }
+
     async fn repo_collaborators(&self, org: &str, repo: &str) -> anyhow::Result<Vec<RepoUser>> {
+        if let Some(collaborators) = self
+            .prefetched(org, repo)
+            .and_then(|prefetched| prefetched.collaborators)
+        {
+            return Ok(collaborators);
+        }
+
         let mut users = Vec::new();
 
         self.client
@@ -418,88 +582,180 @@ impl GithubRead for GitHubApiRead {
         Ok(users)
     }
 
+    async fn repo_labels(&self, org: &str, repo: &str) -> anyhow::Result<Vec<RepoLabel>> {
+        let mut labels = Vec::new();
+
+        self.client
+            .rest_paginated(
+                &Method::GET,
+                &GitHubUrl::repos(org, repo, "labels")?,
+                |resp: Vec<RepoLabel>| {
+                    labels.extend(resp);
+                    Ok(())
+                },
+            )
+            .await?;
+
+        Ok(labels)
+    }
+
+    async fn repo_webhooks(&self, org: &str, repo: &str) -> anyhow::Result<Vec<RepoWebhook>> {
+        let mut webhooks = Vec::new();
+
+        self.client
+            .rest_paginated(
+                &Method::GET,
+                &GitHubUrl::repos(org, repo, "hooks")?,
+                |resp: Vec<RepoWebhook>| {
+                    webhooks.extend(resp);
+                    Ok(())
+                },
+            )
+            .await?;
+
+        Ok(webhooks)
+    }
+
+    async fn repo_autolinks(&self, org: &str, repo: &str) -> anyhow::Result<Vec<RepoAutolink>> {
+        let mut autolinks = Vec::new();
+
+        self.client
+            .rest_paginated(
+                &Method::GET,
+                &GitHubUrl::repos(org, repo, "autolinks")?,
+                |resp: Vec<RepoAutolink>| {
+                    autolinks.extend(resp);
+                    Ok(())
+                },
+            )
+            .await?;
+
+        Ok(autolinks)
+    }
+
+    async fn repo_deploy_keys(&self, org: &str, repo: &str) -> anyhow::Result<Vec<RepoDeployKey>> {
+        let mut keys = Vec::new();
+
+        self.client
+            .rest_paginated(
+                &Method::GET,
+                &GitHubUrl::repos(org, repo, "keys")?,
+                |resp: Vec<RepoDeployKey>| {
+                    keys.extend(resp);
+                    Ok(())
+                },
+            )
+            .await?;
+
+        Ok(keys)
+    }
+
+    async fn repo_actions_permissions(
+        &self,
+        org: &str,
+        repo: &str,
+    ) -> anyhow::Result<Option<ActionsPermissions>> {
+        #[derive(serde::Deserialize)]
+        struct RepoActions {
+            enabled: bool,
+            allowed_actions: Option<AllowedActions>,
+        }
+
+        #[derive(serde::Deserialize)]
+        struct WorkflowActions {
+            default_workflow_permissions: WorkflowPermissions,
+            can_approve_pull_request_reviews: bool,
+        }
+
+        // REST API: https://docs.github.com/en/rest/actions/permissions#get-github-actions-permissions-for-a-repository
+        let actions: Option<RepoActions> = self
+            .client
+            .send_option(
+                Method::GET,
+                &GitHubUrl::repos(org, repo, "actions/permissions")?,
+            )
+            .await?;
+        let Some(RepoActions {
+            enabled: true,
+            allowed_actions: Some(allowed_actions),
+        }) = actions
+        else {
+            return Ok(None);
+        };
+
+        // REST API: https://docs.github.com/en/rest/actions/permissions#get-default-workflow-permissions-for-a-repository
+        let workflow: WorkflowActions = self
+            .client
+            .send_option(
+                Method::GET,
+                &GitHubUrl::repos(org, repo, "actions/permissions/workflow")?,
+            )
+            .await?
+            .ok_or_else(|| {
+                anyhow::anyhow!("missing workflow permissions of repository {org}/{repo}")
+            })?;
+
+        let selected_actions = if allowed_actions == AllowedActions::Selected {
+            // REST API: https://docs.github.com/en/rest/actions/permissions#get-allowed-actions-and-reusable-workflows-for-a-repository
+            let mut selected: SelectedActions = self
+                .client
+                .send_option(
+                    Method::GET,
+                    &GitHubUrl::repos(org, repo, "actions/permissions/selected-actions")?,
+                )
+                .await?
+                .ok_or_else(|| {
+                    anyhow::anyhow!("missing selected actions of repository {org}/{repo}")
+                })?;
+            selected.patterns_allowed.sort();
+            Some(selected)
+        } else {
+            None
+        };
+
+        Ok(Some(ActionsPermissions {
+            default_workflow_permissions: workflow.default_workflow_permissions,
+            can_approve_pull_requests: workflow.can_approve_pull_request_reviews,
+            allowed_actions,
+            selected_actions,
+        }))
+    }
+
     async fn branch_protections(
         &self,
         org: &str,
         repo: &str,
     ) -> anyhow::Result<HashMap<String, (String, BranchProtection)>> {
+        if let Some(prefetched) = self.prefetched(org, repo) {
+            return Ok(prefetched.branch_protections.clone());
+        }
+
         #[derive(serde::Serialize)]
         struct Params<'a> {
             org: &'a str,
             repo: &'a str,
         }
-        static QUERY: &str = "
-            query($org:String!,$repo:String!) {
-                repository(owner:$org, name:$repo) {
-                    branchProtectionRules(first:100) {
-                        nodes {
-                            id,
-                            pattern,
-                            isAdminEnforced,
-                            allowsForcePushes,
-                            dismissesStaleReviews,
-                            requiredStatusCheckContexts,
-                            requiredApprovingReviewCount,
-                            requiresApprovingReviews
-                            pushAllowances(first: 100) {
-                                nodes {
-                                    actor {
-                                        ... on Actor {
-                                            login
-                                        }
-                                        ... on Team {
-                                            organization {
-                                                login
-                                            },
-                                            name
-                                        }
-                                        ... on App {
-                                            id,
-                                            name
-                                        }
-                                    }
-                                }
-                            }
-                         }
-                    }
-                }
-            }
-        ";
+        let query = format!(
+            "
+            query($org:String!,$repo:String!) {{
+                repository(owner:$org, name:$repo) {{
+                    ...BranchProtections
+                }}
+            }}
+            {BRANCH_PROTECTIONS_FRAGMENT}
+            "
+        );
 
         #[derive(serde::Deserialize)]
         struct Wrapper {
-            repository: Repository,
-        }
-        #[derive(serde::Deserialize)]
-        #[serde(rename_all = "camelCase")]
-        struct Repository {
-            branch_protection_rules: GraphNodes<BranchProtectionWrapper>,
-        }
-        #[derive(serde::Deserialize)]
-        #[serde(rename_all = "camelCase")]
-        struct BranchProtectionWrapper {
-            id: String,
-            #[serde(flatten)]
-            protection: BranchProtection,
+            repository: BranchProtectionsResponse,
         }
 
-        let mut result = HashMap::new();
         let res: Wrapper = self
             .client
-            .graphql(QUERY, Params { org, repo }, org)
+            .graphql(&query, Params { org, repo }, org)
             .await?;
-        for mut node in res
-            .repository
-            .branch_protection_rules
-            .nodes
-            .into_iter()
-            .flatten()
-        {
-            // Normalize check order to avoid diffs based only on the ordering difference
-            node.protection.required_status_check_contexts.sort();
-            result.insert(node.protection.pattern.clone(), (node.id, node.protection));
-        }
-        Ok(result)
+        Ok(res.repository.into_branch_protections())
     }
 
     async fn repo_environments(
@@ -511,12 +767,39 @@ impl GithubRead for GitHubApiRead {
         struct ProtectionRule {
             #[serde(rename = "type")]
             rule_type: String,
+            #[serde(default)]
+            wait_timer: Option<u32>,
+            #[serde(default)]
+            reviewers: Vec<ProtectionRuleReviewer>,
+        }
+
+        #[derive(serde::Deserialize)]
+        struct ProtectionRuleReviewer {
+            #[serde(rename = "type")]
+            reviewer_type: String,
+            reviewer: EnvironmentReviewerInfo,
+        }
+
+        #[derive(serde::Deserialize)]
+        struct EnvironmentReviewerInfo {
+            id: u64,
+            // Only present for users
+            login: Option<String>,
+            // Only present for teams
+            slug: Option<String>,
+        }
+
+        #[derive(serde::Deserialize)]
+        struct DeploymentBranchPolicy {
+            protected_branches: bool,
         }
 
         #[derive(serde::Deserialize)]
         struct GitHubEnvironment {
             name: String,
+            #[serde(default)]
             protection_rules: Vec<ProtectionRule>,
+            deployment_branch_policy: Option<DeploymentBranchPolicy>,
         }
 
         #[derive(serde::Deserialize)]
@@ -545,11 +828,18 @@ impl GithubRead for GitHubApiRead {
         // REST API: https://docs.github.com/en/rest/deployments/branch-policies#list-deployment-branch-policies
         futures_util::stream::iter(env_infos)
             .then(|env_info| async move {
-                // Check if branch policies exist by looking at protection_rules metadata
-                let has_branch_policies = env_info
-                    .protection_rules
-                    .iter()
-                    .any(|rule| rule.rule_type == "branch_policy");
+                let protected_branches_only = env_info
+                    .deployment_branch_policy
+                    .as_ref()
+                    .is_some_and(|policy| policy.protected_branches);
+
+                // Check if branch policies exist by looking at protection_rules metadata.
+                // Custom policies cannot coexist with the protected branches policy.
+                let has_branch_policies = !protected_branches_only
+                    && env_info
+                        .protection_rules
+                        .iter()
+                        .any(|rule| rule.rule_type == "branch_policy");
 
                 let (branches, tags) = if has_branch_policies {
                     let mut branches = Vec::new();
@@ -574,7 +864,43 @@ impl GithubRead for GitHubApiRead {
                     (Vec::new(), Vec::new())
                 };
 
-                Ok((env_info.name, Environment { branches, tags }))
+                let mut reviewers = Vec::new();
+                let mut wait_timer = 0;
+                for rule in &env_info.protection_rules {
+                    match rule.rule_type.as_str() {
+                        "required_reviewers" => {
+                            for r in &rule.reviewers {
+                                let info = &r.reviewer;
+                                reviewers.push(match (r.reviewer_type.as_str(), &info.login, &info.slug) {
+                                    ("User", Some(login), _) => EnvironmentReviewer::User {
+                                        login: login.clone(),
+                                        id: info.id,
+                                    },
+                                    ("Team", _, Some(slug)) => EnvironmentReviewer::Team {
+                                        slug: slug.clone(),
+                                        id: Some(info.id),
+                                    },
+                                    (reviewer_type, ..) => anyhow::bail!(
+                                        "unexpected reviewer of type '{reviewer_type}' in environment '{}' of '{org}/{repo}'",
+                                        env_info.name
+                                    ),
+                                });
+                            }
+                        }
+                        "wait_timer" => wait_timer = rule.wait_timer.unwrap_or(0),
+                        _ => {}
+                    }
+                }
+                Ok((
+                    env_info.name,
+                    Environment {
+                        branches,
+                        tags,
+                        reviewers,
+                        wait_timer,
+                        protected_branches_only,
+                    },
+                ))
             })
             .collect::<Vec<_>>()
             .await
@@ -609,11 +935,10 @@ impl GithubRead for GitHubApiRead {
         for id in ruleset_ids {
             let ruleset: api::Ruleset = self
                 .client
-                .req(
+                .execute(
                     Method::GET,
                     &GitHubUrl::repos(org, repo, &format!("rulesets/{id}"))?,
-                )?
-                .send()
+                )
                 .await?
                 .json_annotated()
                 .await?;
@@ -669,3 +994,475 @@ impl GithubRead for GitHubApiRead {
         Ok(policies)
     }
 }
+
+/// Number of repositories fetched by a single batched GraphQL query
+const REPO_BATCH_SIZE: usize = 20;
+
+static REPO_SETTINGS_FRAGMENT: &str = "
+    fragment RepoSettings on Repository {
+        id
+        autoMergeAllowed
+        defaultBranchRef {
+            name
+        }
+        deleteBranchOnMerge
+        description
+        hasDiscussionsEnabled
+        hasIssuesEnabled
+        hasProjectsEnabled
+        hasWikiEnabled
+        homepageUrl
+        isArchived
+        isPrivate
+        mergeCommitAllowed
+        rebaseMergeAllowed
+        squashMergeAllowed
+    }
+";
+
+static BRANCH_PROTECTIONS_FRAGMENT: &str = "
+    fragment BranchProtections on Repository {
+        branchProtectionRules(first:100) {
+            nodes {
+                id,
+                pattern,
+                isAdminEnforced,
+                allowsForcePushes,
+                dismissesStaleReviews,
+                requiredStatusCheckContexts,
+                requiredApprovingReviewCount,
+                requiresApprovingReviews,
+                requiresCodeOwnerReviews,
+                requiresCommitSignatures,
+                requiresLinearHistory,
+                requiresConversationResolution
+                pushAllowances(first: 100) {
+                    nodes {
+                        actor {
+                            ... on Actor {
+                                login
+                            }
+                            ... on Team {
+                                organization {
+                                    login
+                                },
+                                name
+                            }
+                            ... on App {
+                                id,
+                                name
+                            }
+                        }
+                    }
+                }
+                bypassPullRequestAllowances(first: 100) {
+                    nodes {
+                        actor {
+                            ... on Actor {
+                                login
+                            }
+                            ... on Team {
+                                organization {
+                                    login
+                                },
+                                name
+                            }
+                            ... on App {
+                                id,
+                                name
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+";
+
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RepoResponse {
+    // Equivalent of `node_id` of the Rest API
+    id: String,
+    // Equivalent of `id` of the Rest API
+    auto_merge_allowed: Option<bool>,
+    default_branch_ref: Option<DefaultBranchRef>,
+    delete_branch_on_merge: Option<bool>,
+    description: Option<String>,
+    has_discussions_enabled: Option<bool>,
+    has_issues_enabled: Option<bool>,
+    has_projects_enabled: Option<bool>,
+    has_wiki_enabled: Option<bool>,
+    homepage_url: Option<String>,
+    is_archived: bool,
+    is_private: bool,
+    merge_commit_allowed: Option<bool>,
+    rebase_merge_allowed: Option<bool>,
+    squash_merge_allowed: Option<bool>,
+}
+
+#[derive(serde::Deserialize)]
+struct DefaultBranchRef {
+    name: String,
+}
+
+impl RepoResponse {
+    fn into_repo(self, org: &str, name: &str) -> Repo {
+        Repo {
+            node_id: self.id,
+            name: name.to_string(),
+            description: self.description.unwrap_or_default(),
+            allow_auto_merge: self.auto_merge_allowed,
+            archived: self.is_archived,
+            homepage: self.homepage_url,
+            org: org.to_string(),
+            private: self.is_private,
+            allow_squash_merge: self.squash_merge_allowed,
+            allow_merge_commit: self.merge_commit_allowed,
+            allow_rebase_merge: self.rebase_merge_allowed,
+            delete_branch_on_merge: self.delete_branch_on_merge,
+            default_branch: self.default_branch_ref.map(|r| r.name),
+            has_issues: self.has_issues_enabled,
+            has_wiki: self.has_wiki_enabled,
+            has_projects: self.has_projects_enabled,
+            has_discussions: self.has_discussions_enabled,
+        }
+    }
+}
+
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BranchProtectionsResponse {
+    branch_protection_rules: GraphNodes<BranchProtectionWrapper>,
+}
+
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BranchProtectionWrapper {
+    id: String,
+    #[serde(flatten)]
+    protection: BranchProtection,
+}
+
+impl BranchProtectionsResponse {
+    fn into_branch_protections(self) -> HashMap<String, (String, BranchProtection)> {
+        let mut result = HashMap::new();
+        for mut node in self.branch_protection_rules.nodes.into_iter().flatten() {
+            // Normalize check order to avoid diffs based only on the ordering difference
+            node.protection.required_status_check_contexts.sort();
+            result.insert(node.protection.pattern.clone(), (node.id, node.protection));
+        }
+        result
+    }
+}
+
+/// Data of a repository fetched ahead of time by [`GithubRead::prefetch_repos`]
+#[derive(Clone)]
+struct PrefetchedRepo {
+    repo: Option<Repo>,
+    branch_protections: HashMap<String, (String, BranchProtection)>,
+    /// `None` if the collaborators have to be fetched separately
+    collaborators: Option<Vec<RepoUser>>,
+}
+
+impl GitHubApiRead {
+    fn prefetched(&self, org: &str, repo: &str) -> Option<PrefetchedRepo> {
+        self.prefetched
+            .lock()
+            .unwrap()
+            .get(&(org.to_string(), repo.to_string()))
+            .cloned()
+    }
+
+    /// Fetch the settings, branch protections and direct collaborators of a batch of
+    /// repositories with a single GraphQL query.
+    async fn prefetch_repo_batch(
+        &self,
+        org: &str,
+        repos: &[String],
+        include_collaborators: bool,
+    ) -> anyhow::Result<()> {
+        #[derive(serde::Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct BatchedRepo {
+            #[serde(flatten)]
+            settings: RepoResponse,
+            #[serde(flatten)]
+            branch_protections: BranchProtectionsResponse,
+            collaborators: Option<Collaborators>,
+        }
+
+        #[derive(serde::Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct Collaborators {
+            edges: Vec<CollaboratorEdge>,
+            page_info: GraphPageInfo,
+        }
+
+        #[derive(serde::Deserialize)]
+        struct CollaboratorEdge {
+            permission: CollaboratorPermission,
+            node: Login,
+        }
+
+        #[derive(serde::Deserialize)]
+        #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+        enum CollaboratorPermission {
+            Admin,
+            Maintain,
+            Write,
+            Triage,
+            Read,
+        }
+
+        let collaborators = if include_collaborators {
+            "collaborators(affiliation: DIRECT, first: 100) {
+                edges { permission node { login } }
+                pageInfo { endCursor hasNextPage }
+            }"
+        } else {
+            ""
+        };
+        let mut variables = serde_json::Map::new();
+        variables.insert("org".to_string(), org.into());
+        let mut params = vec!["$org: String!".to_string()];
+        let mut fields = String::new();
+        for (index, repo) in repos.iter().enumerate() {
+            variables.insert(format!("r{index}"), repo.as_str().into());
+            params.push(format!("$r{index}: String!"));
+            fields.push_str(&format!(
+                "r{index}: repository(owner: $org, name: $r{index}) {{
+                    ...RepoSettings
+                    ...BranchProtections
+                    {collaborators}
+                }}
+                "
+            ));
+        }
+        let query = format!(
+            "query({}) {{ {fields} }} {REPO_SETTINGS_FRAGMENT} {BRANCH_PROTECTIONS_FRAGMENT}",
+            params.join(", ")
+        );
+
+        let (mut result, field_errors): (HashMap<String, Option<BatchedRepo>>, _) = self
+            .client
+            .graphql_fields(&query, variables, org)
+            .await
+            .with_context(|| format!("failed to prefetch repositories of `{org}`"))?;
+
+        let mut prefetched = self.prefetched.lock().unwrap();
+        for (index, name) in repos.iter().enumerate() {
+            let alias = format!("r{index}");
+            // For example, the GitHub App push allowance actors of branch protections cannot
+            // be read without a PAT. Such repositories are read with separate requests, so
+            // that the error only affects the repository it belongs to.
+            if let Some(error) = field_errors.get(&alias) {
+                debug!("Failed to prefetch `{org}/{name}`, reading it separately: {error}");
+                continue;
+            }
+            let entry = match result.remove(&alias).flatten() {
+                Some(batched) => PrefetchedRepo {
+                    repo: Some(batched.settings.into_repo(org, name)),
+                    branch_protections: batched.branch_protections.into_branch_protections(),
+                    collaborators: batched
+                        .collaborators
+                        .filter(|c| !c.page_info.has_next_page)
+                        .map(|c| {
+                            c.edges
+                                .into_iter()
+                                .map(|edge| RepoUser {
+                                    name: edge.node.login,
+                                    permission: match edge.permission {
+                                        CollaboratorPermission::Admin => RepoPermission::Admin,
+                                        CollaboratorPermission::Maintain => {
+                                            RepoPermission::Maintain
+                                        }
+                                        CollaboratorPermission::Write => RepoPermission::Write,
+                                        CollaboratorPermission::Triage => RepoPermission::Triage,
+                                        CollaboratorPermission::Read => RepoPermission::Read,
+                                    },
+                                })
+                                .collect()
+                        }),
+                },
+                // The repository does not exist
+                None => PrefetchedRepo {
+                    repo: None,
+                    branch_protections: HashMap::new(),
+                    collaborators: Some(Vec::new()),
+                },
+            };
+            prefetched.insert((org.to_string(), name.clone()), entry);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    use super::*;
+
+    fn repo_json(id: &str) -> serde_json::Value {
+        serde_json::json!({
+            "id": id,
+            "autoMergeAllowed": false,
+            "defaultBranchRef": { "name": "main" },
+            "deleteBranchOnMerge": false,
+            "description": null,
+            "hasDiscussionsEnabled": false,
+            "hasIssuesEnabled": true,
+            "hasProjectsEnabled": false,
+            "hasWikiEnabled": false,
+            "homepageUrl": null,
+            "isArchived": false,
+            "isPrivate": false,
+            "mergeCommitAllowed": true,
+            "rebaseMergeAllowed": true,
+            "squashMergeAllowed": true,
+            "branchProtectionRules": { "nodes": [] },
+        })
+    }
+
+    /// Serve the GraphQL endpoint of GitHub, answering each request with the next response
+    /// of `responses`. Returns the client and the queries received so far.
+    async fn serve_graphql(
+        responses: Vec<serde_json::Value>,
+    ) -> (HttpClient, Arc<Mutex<Vec<String>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        let queries = Arc::new(Mutex::new(Vec::new()));
+        let log = queries.clone();
+        tokio::spawn(async move {
+            for response in responses {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let mut request = Vec::new();
+                let mut buf = [0; 1024];
+                let head_len = loop {
+                    if let Some(pos) = request.windows(4).position(|w| w == b"\r\n\r\n") {
+                        break pos + 4;
+                    }
+                    let read = stream.read(&mut buf).await.unwrap();
+                    request.extend_from_slice(&buf[..read]);
+                };
+                let head = String::from_utf8(request[..head_len].to_vec())
+                    .unwrap()
+                    .to_lowercase();
+                assert!(
+                    head.starts_with("post /graphql "),
+                    "unexpected request {head}"
+                );
+                let content_length: usize = head
+                    .lines()
+                    .find_map(|line| line.strip_prefix("content-length: "))
+                    .unwrap()
+                    .parse()
+                    .unwrap();
+                while request.len() < head_len + content_length {
+                    let read = stream.read(&mut buf).await.unwrap();
+                    request.extend_from_slice(&buf[..read]);
+                }
+                let body: serde_json::Value = serde_json::from_slice(&request[head_len..]).unwrap();
+                log.lock()
+                    .unwrap()
+                    .push(body["query"].as_str().unwrap().to_string());
+
+                let response = response.to_string();
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{response}",
+                    response.len()
+                );
+                stream.write_all(response.as_bytes()).await.unwrap();
+            }
+        });
+        (HttpClient::local(&base).unwrap(), queries)
+    }
+
+    fn forbidden(path: serde_json::Value) -> serde_json::Value {
+        serde_json::json!({
+            "type": "FORBIDDEN",
+            "message": "Resource not accessible by integration",
+            "path": path,
+        })
+    }
+
+    #[tokio::test]
+    async fn prefetch_repo_batch() {
+        let (client, queries) = serve_graphql(vec![serde_json::json!({
+            "data": { "r0": repo_json("R_0"), "r1": null },
+            "errors": [{ "type": "NOT_FOUND", "message": "not found", "path": ["r1"] }],
+        })])
+        .await;
+        let read = GitHubApiRead::from_client(client).unwrap();
+        read.prefetch_repo_batch("rust-lang", &["a".to_string(), "b".to_string()], false)
+            .await
+            .unwrap();
+
+        assert_eq!(queries.lock().unwrap().len(), 1);
+        let a = read.repo("rust-lang", "a").await.unwrap().unwrap();
+        assert_eq!(a.node_id, "R_0");
+        assert!(read.repo("rust-lang", "b").await.unwrap().is_none());
+        // Both repositories were served from the batch
+        assert_eq!(queries.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn prefetch_repo_batch_with_failing_alias() {
+        let (client, queries) = serve_graphql(vec![
+            serde_json::json!({
+                "data": { "r0": repo_json("R_0"), "r1": null },
+                "errors": [forbidden(serde_json::json!(
+                    ["r1", "branchProtectionRules", "nodes", 0, "pushAllowances", "nodes", 0, "actor"]
+                ))],
+            }),
+            serde_json::json!({
+                "data": null,
+                "errors": [forbidden(serde_json::json!(
+                    ["repository", "branchProtectionRules", "nodes", 0, "pushAllowances", "nodes", 0, "actor"]
+                ))],
+            }),
+        ])
+        .await;
+        let read = GitHubApiRead::from_client(client).unwrap();
+        read.prefetch_repo_batch("rust-lang", &["a".to_string(), "b".to_string()], false)
+            .await
+            .unwrap();
+
+        // The repository without errors is served from the batch
+        let a = read.repo("rust-lang", "a").await.unwrap().unwrap();
+        assert_eq!(a.node_id, "R_0");
+        assert!(
+            read.branch_protections("rust-lang", "a")
+                .await
+                .unwrap()
+                .is_empty()
+        );
+        assert_eq!(queries.lock().unwrap().len(), 1);
+
+        // The failing repository is read separately, and the error is reported for it only
+        let err = read.branch_protections("rust-lang", "b").await.unwrap_err();
+        assert!(err.downcast_ref::<api::GraphForbiddenError>().is_some());
+        let queries = queries.lock().unwrap();
+        assert_eq!(queries.len(), 2);
+        assert!(queries[1].contains("repository(owner:$org, name:$repo)"));
+    }
+
+    #[tokio::test]
+    async fn prefetch_repo_batch_with_query_error() {
+        let (client, _) = serve_graphql(vec![serde_json::json!({
+            "data": null,
+            "errors": [{ "message": "Something went wrong" }],
+        })])
+        .await;
+        let read = GitHubApiRead::from_client(client).unwrap();
+        let err = read
+            .prefetch_repo_batch("rust-lang", &["a".to_string()], false)
+            .await
+            .unwrap_err();
+        assert!(format!("{err:#}").contains("Something went wrong"));
+    }
+}
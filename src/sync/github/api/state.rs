@@ -0,0 +1,133 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use anyhow::Context as _;
+use reqwest::header::{self, HeaderMap, HeaderValue};
+use reqwest::{Request, Response, StatusCode};
+
+/// Responses of the GitHub API, recorded to diff against the state of GitHub without network
+/// access.
+#[derive(Clone)]
+pub(crate) enum GitHubState {
+    /// Record the responses of the requests sent to GitHub
+    Record(Arc<Mutex<RecordedState>>),
+    /// Answer requests with previously recorded responses
+    Replay(Arc<RecordedState>),
+}
+
+#[derive(Default, serde::Serialize, serde::Deserialize)]
+pub(crate) struct RecordedState {
+    /// Whether the state was recorded with a personal access token, which changes how some
+    /// settings are diffed
+    pub(crate) uses_pat: bool,
+    /// Request (method, URL and body) -> response
+    responses: BTreeMap<String, RecordedResponse>,
+}
+
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+struct RecordedResponse {
+    status: u16,
+    /// Pagination links of the response
+    #[serde(skip_serializing_if = "Option::is_none")]
+    link: Option<String>,
+    body: String,
+}
+
+impl GitHubState {
+    pub(crate) fn record(uses_pat: bool) -> Self {
+        GitHubState::Record(Arc::new(Mutex::new(RecordedState {
+            uses_pat,
+            responses: BTreeMap::new(),
+        })))
+    }
+
+    pub(crate) fn load(path: &Path) -> anyhow::Result<Self> {
+        let contents = std::fs::read(path)
+            .with_context(|| format!("failed to read GitHub state from {}", path.display()))?;
+        let state = serde_json::from_slice(&contents)
+            .with_context(|| format!("failed to parse GitHub state from {}", path.display()))?;
+        Ok(GitHubState::Replay(Arc::new(state)))
+    }
+
+    /// Write the recorded responses to a file.
+    pub(crate) fn save(&self, path: &Path) -> anyhow::Result<()> {
+        let GitHubState::Record(state) = self else {
+            anyhow::bail!("only recorded GitHub state can be saved");
+        };
+        let contents = serde_json::to_vec_pretty(&*state.lock().unwrap())?;
+        std::fs::write(path, contents)
+            .with_context(|| format!("failed to write GitHub state to {}", path.display()))
+    }
+
+    pub(crate) fn uses_pat(&self) -> Option<bool> {
+        match self {
+            GitHubState::Record(_) => None,
+            GitHubState::Replay(state) => Some(state.uses_pat),
+        }
+    }
+
+    /// Returns the recorded response to the request when replaying.
+    pub(crate) fn replay(&self, key: &str) -> anyhow::Result<Option<Response>> {
+        match self {
+            GitHubState::Record(_) => Ok(None),
+            GitHubState::Replay(state) => {
+                let recorded = state.responses.get(key).with_context(|| {
+                    // Leave out the request body, GraphQL queries are long
+                    let request = key.splitn(3, ' ').take(2).collect::<Vec<_>>().join(" ");
+                    format!("the GitHub state does not contain a response to `{request}`")
+                })?;
+                Ok(Some(recorded.clone().into_response()?))
+            }
+        }
+    }
+
+    /// Stores the response to the request when recording.
+    pub(crate) async fn store(&self, key: String, resp: Response) -> anyhow::Result<Response> {
+        let GitHubState::Record(state) = self else {
+            return Ok(resp);
+        };
+        let recorded = RecordedResponse {
+            status: resp.status().as_u16(),
+            link: resp
+                .headers()
+                .get(header::LINK)
+                .map(|link| link.to_str())
+                .transpose()?
+                .map(|link| link.to_string()),
+            body: resp.text().await?,
+        };
+        state
+            .lock()
+            .unwrap()
+            .responses
+            .insert(key, recorded.clone());
+        recorded.into_response()
+    }
+}
+
+/// Identifies a request by its method, URL and body.
+pub(crate) fn request_key(request: &Request) -> String {
+    let body = request
+        .body()
+        .and_then(|body| body.as_bytes())
+        .map(String::from_utf8_lossy)
+        .unwrap_or_default();
+    format!("{} {} {body}", request.method(), request.url())
+        .trim_end()
+        .to_string()
+}
+
+impl RecordedResponse {
+    fn into_response(self) -> anyhow::Result<Response> {
+        let mut resp = http::Response::builder()
+            .status(StatusCode::from_u16(self.status)?)
+            .body(self.body)?;
+        let mut headers = HeaderMap::new();
+        if let Some(link) = self.link {
+            headers.insert(header::LINK, HeaderValue::from_str(&link)?);
+        }
+        *resp.headers_mut() = headers;
+        Ok(Response::from(resp))
+    }
+}
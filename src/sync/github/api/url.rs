@@ -1,23 +1,74 @@
-/// A URL to a GitHub API endpoint.
+/// Base URLs of the GitHub API.
+#[derive(Clone, Debug)]
+pub struct GitHubEndpoints {
+    rest: String,
+    graphql: String,
+}
+
+impl Default for GitHubEndpoints {
+    fn default() -> Self {
+        Self {
+            rest: "https://api.github.com".to_string(),
+            graphql: "https://api.github.com/graphql".to_string(),
+        }
+    }
+}
+
+impl GitHubEndpoints {
+    /// Reads the base URLs from the `GITHUB_API_URL` and `GITHUB_GRAPHQL_URL` environment
+    /// variables (the same ones that GitHub Actions provides), defaulting to github.com.
+    ///
+    /// For GitHub Enterprise Server, they are `https://HOSTNAME/api/v3`
+    /// and `https://HOSTNAME/api/graphql`.
+    pub fn from_env() -> Self {
+        let default = Self::default();
+        let rest = std::env::var("GITHUB_API_URL")
+            .map(|url| url.trim_end_matches('/').to_string())
+            .unwrap_or(default.rest);
+        let graphql = std::env::var("GITHUB_GRAPHQL_URL").unwrap_or(default.graphql);
+        Self { rest, graphql }
+    }
+
+    /// Endpoints served by a local stand-in for GitHub.
+    #[cfg(test)]
+    pub fn local(base: &str) -> Self {
+        Self {
+            rest: base.to_string(),
+            graphql: format!("{base}/graphql"),
+        }
+    }
+
+    /// Returns the full URL of a REST API endpoint.
+    pub fn rest(&self, path: &str) -> String {
+        format!("{}/{path}", self.rest)
+    }
+}
+
+/// A URL to a GitHub API endpoint, relative to the configured [`GitHubEndpoints`].
 /// When using a GitHub App instead of a PAT, the token depends on the organization.
 /// So storing the token together with the URL is convenient.
 #[derive(Clone)]
 pub struct GitHubUrl {
+    /// Path of a REST endpoint, or an absolute URL (e.g. a pagination link)
     url: String,
     org: String,
+    graphql: bool,
 }
 
 impl GitHubUrl {
     pub fn new(url: &str, org: &str) -> Self {
-        let https = "https://";
-        let url = if url.starts_with(https) {
-            url.to_string()
-        } else {
-            format!("{https}api.github.com/{url}")
-        };
         Self {
-            url,
+            url: url.to_string(),
+            org: org.to_string(),
+            graphql: false,
+        }
+    }
+
+    pub fn graphql(org: &str) -> Self {
+        Self {
+            url: "graphql".to_string(),
             org: org.to_string(),
+            graphql: true,
         }
     }
 
@@ -33,8 +84,13 @@ impl GitHubUrl {
     }
 
     pub fn orgs(org: &str, remaining_endpoint: &str) -> anyhow::Result<Self> {
-        validate_remaining_endpoint(remaining_endpoint)?;
-        let url = format!("orgs/{org}/{remaining_endpoint}");
+        let remaining_endpoint = if remaining_endpoint.is_empty() {
+            "".to_string()
+        } else {
+            validate_remaining_endpoint(remaining_endpoint)?;
+            format!("/{remaining_endpoint}")
+        };
+        let url = format!("orgs/{org}{remaining_endpoint}");
         Ok(Self::new(&url, org))
     }
 
@@ -42,6 +98,17 @@ impl GitHubUrl {
         &self.url
     }
 
+    /// Returns the full URL of the endpoint.
+    pub fn resolve(&self, endpoints: &GitHubEndpoints) -> String {
+        if self.graphql {
+            endpoints.graphql.clone()
+        } else if self.url.starts_with("https://") || self.url.starts_with("http://") {
+            self.url.clone()
+        } else {
+            endpoints.rest(&self.url)
+        }
+    }
+
     pub fn org(&self) -> &str {
         &self.org
     }
@@ -1,18 +1,26 @@
+use anyhow::Context as _;
 use log::debug;
 use reqwest::Method;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
 
 use crate::sync::github::api::url::GitHubUrl;
 use crate::sync::github::api::{
-    AppPushAllowanceActor, BranchProtection, BranchProtectionOp, GitHubApiRead, GithubRead,
-    HttpClient, Login, PushAllowanceActor, Repo, RepoPermission, RepoSettings, Ruleset, RulesetOp,
-    Team, TeamPrivacy, TeamPushAllowanceActor, TeamRole, UserPushAllowanceActor, allow_not_found,
+    AppPushAllowanceActor, BranchProtection, BranchProtectionOp, Environment, EnvironmentReviewer,
+    GitHubApiRead, GithubRead, HttpClient, Login, OrgSettings, ParentTeam, PushAllowanceActor,
+    Repo, RepoLabel, RepoPermission, RepoSettings, Ruleset, RulesetOp, Team, TeamPrivacy,
+    TeamPushAllowanceActor, TeamRole, UserPushAllowanceActor, allow_not_found,
 };
 use crate::sync::utils::ResponseExt;
+use rust_team_data::ids::{OrgName, PersonId, TeamName, TeamSlug};
+use rust_team_data::v1::{ActionsPermissions, Webhook};
 
 pub(crate) struct GitHubWrite {
     client: HttpClient,
     dry_run: bool,
+    /// (org, name or slug) -> ID of the teams created or renamed by this sync, which could not be
+    /// resolved when the diff was computed
+    created_teams: Mutex<HashMap<(String, String), u64>>,
 }
 
 impl GitHubWrite {
@@ -20,9 +28,34 @@ impl GitHubWrite {
         Ok(Self {
             client: client.clone(),
             dry_run,
+            created_teams: Mutex::new(HashMap::new()),
         })
     }
 
+    /// Remember the ID of a team that was created or renamed by this sync
+    fn register_team(&self, org: &str, team: &Team) {
+        if let Some(id) = team.id {
+            let mut created = self.created_teams.lock().unwrap();
+            created.insert((org.to_string(), team.name.clone()), id);
+            created.insert((org.to_string(), team.slug.clone()), id);
+        }
+    }
+
+    /// The ID of a team that was created or renamed by this sync
+    fn created_team_id(&self, org: &str, team: &str) -> anyhow::Result<u64> {
+        self.created_teams
+            .lock()
+            .unwrap()
+            .get(&(org.to_string(), team.to_string()))
+            .copied()
+            .ok_or_else(|| anyhow::anyhow!("team '{team}' not found in '{org}'"))
+    }
+
+    /// Identity of the token used to change the organization
+    pub(crate) async fn actor(&self, org: &str) -> anyhow::Result<String> {
+        self.client.actor(org).await
+    }
+
     async fn user_id(&self, name: &str, org: &str) -> anyhow::Result<String> {
         #[derive(serde::Serialize)]
         struct Params<'a> {
@@ -83,19 +116,44 @@ impl GitHubWrite {
         Ok(data.organization.team.id)
     }
 
+    /// Resolve the node IDs of branch protection allowance actors
+    async fn actor_ids(
+        &self,
+        actors: &[PushAllowanceActor],
+        org: &str,
+    ) -> anyhow::Result<Vec<String>> {
+        let mut ids = vec![];
+        for actor in actors {
+            match actor {
+                PushAllowanceActor::User(UserPushAllowanceActor { login: name }) => {
+                    ids.push(self.user_id(name, org).await?);
+                }
+                PushAllowanceActor::Team(TeamPushAllowanceActor {
+                    organization: Login { login: org },
+                    name,
+                }) => ids.push(self.team_id(org, name).await?),
+                PushAllowanceActor::App(AppPushAllowanceActor { id, .. }) => ids.push(id.clone()),
+            }
+        }
+        Ok(ids)
+    }
+
     /// Create a team in a org
     pub(crate) async fn create_team(
         &self,
-        org: &str,
-        name: &str,
+        org: &OrgName,
+        name: &TeamName,
         description: &str,
         privacy: TeamPrivacy,
+        parent: Option<&ParentTeam>,
     ) -> anyhow::Result<Team> {
         #[derive(serde::Serialize, Debug)]
         struct Req<'a> {
             name: &'a str,
             description: &'a str,
             privacy: TeamPrivacy,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            parent_team_id: Option<u64>,
         }
         debug!("Creating team '{name}' in '{org}'");
         if self.dry_run {
@@ -107,31 +165,40 @@ impl GitHubWrite {
                 description: Some(description.to_string()),
                 privacy,
                 slug: name.to_string(),
+                parent: parent.cloned(),
             })
         } else {
+            let parent_team_id = match parent {
+                Some(parent) => Some(self.parent_team_id(org, parent)?),
+                None => None,
+            };
             let body = &Req {
-                name,
+                name: name.as_str(),
                 description,
                 privacy,
+                parent_team_id,
             };
-            Ok(self
+            let team: Team = self
                 .client
                 .send(Method::POST, &GitHubUrl::orgs(org, "teams")?, body)
                 .await?
                 .json_annotated()
-                .await?)
+                .await?;
+            self.register_team(org, &team);
+            Ok(team)
         }
     }
 
-    /// Edit a team
+    /// Edit a team, returning its slug after the edit
     pub(crate) async fn edit_team(
         &self,
-        org: &str,
-        name: &str,
-        new_name: Option<&str>,
+        org: &OrgName,
+        slug: &TeamSlug,
+        new_name: Option<&TeamName>,
         new_description: Option<&str>,
         new_privacy: Option<TeamPrivacy>,
-    ) -> anyhow::Result<()> {
+        new_parent: Option<Option<&ParentTeam>>,
+    ) -> anyhow::Result<TeamSlug> {
         #[derive(serde::Serialize, Debug)]
         struct Req<'a> {
             #[serde(skip_serializing_if = "Option::is_none")]
@@ -140,36 +207,63 @@ impl GitHubWrite {
             description: Option<&'a str>,
             #[serde(skip_serializing_if = "Option::is_none")]
             privacy: Option<TeamPrivacy>,
+            // `Some(None)` removes the parent team
+            #[serde(skip_serializing_if = "Option::is_none")]
+            parent_team_id: Option<Option<u64>>,
         }
+        // During a dry run, the parent team might not exist yet, so it is not resolved
+        let parent_team_id = match new_parent {
+            Some(Some(parent)) if !self.dry_run => Some(Some(self.parent_team_id(org, parent)?)),
+            Some(Some(_)) => None,
+            Some(None) => Some(None),
+            None => None,
+        };
         let req = Req {
-            name: new_name,
+            name: new_name.map(TeamName::as_str),
             description: new_description,
             privacy: new_privacy,
+            parent_team_id,
         };
         debug!(
-            "Editing team '{name}' in '{org}' with request: {}",
+            "Editing team '{slug}' in '{org}' with request: {}",
             serde_json::to_string(&req).unwrap_or_else(|_| "INVALID_REQUEST".to_string())
         );
-        if !self.dry_run {
-            self.client
-                .send(
-                    Method::PATCH,
-                    &GitHubUrl::orgs(org, &format!("teams/{name}"))?,
-                    &req,
-                )
-                .await?;
+        if self.dry_run {
+            return Ok(slug.clone());
+        }
+        let team: Team = self
+            .client
+            .send(
+                Method::PATCH,
+                &GitHubUrl::orgs(org.as_str(), &format!("teams/{slug}"))?,
+                &req,
+            )
+            .await?
+            .json_annotated()
+            .await?;
+        if new_name.is_some() {
+            self.register_team(org, &team);
         }
+        Ok(TeamSlug::new(team.slug))
+    }
 
-        Ok(())
+    /// The numeric ID the REST API references a parent team by
+    fn parent_team_id(&self, org: &OrgName, parent: &ParentTeam) -> anyhow::Result<u64> {
+        match parent.id {
+            Some(id) => Ok(id),
+            None => self
+                .created_team_id(org, &parent.name)
+                .context("failed to resolve the parent team"),
+        }
     }
 
     /// Delete a team by name and org
-    pub(crate) async fn delete_team(&self, org: &str, slug: &str) -> anyhow::Result<()> {
+    pub(crate) async fn delete_team(&self, org: &OrgName, slug: &TeamSlug) -> anyhow::Result<()> {
         debug!("Deleting team with slug '{slug}' in '{org}'");
         if !self.dry_run {
             let method = Method::DELETE;
             let url = GitHubUrl::orgs(org, &format!("teams/{slug}"))?;
-            let resp = self.client.req(method.clone(), &url)?.send().await?;
+            let resp = self.client.req(method.clone(), &url).await?.send().await?;
             allow_not_found(resp, method, url.url()).await?;
         }
         Ok(())
@@ -178,9 +272,9 @@ impl GitHubWrite {
     /// Set a user's membership in a team to a role
     pub(crate) async fn set_team_membership(
         &self,
-        org: &str,
-        team: &str,
-        user: &str,
+        org: &OrgName,
+        team: &TeamSlug,
+        user: &PersonId,
         role: TeamRole,
     ) -> anyhow::Result<()> {
         debug!("Setting membership of '{user}' in team '{team}' in org '{org}' to role '{role}'");
@@ -204,15 +298,15 @@ impl GitHubWrite {
     /// Remove a user from a team
     pub(crate) async fn remove_team_membership(
         &self,
-        org: &str,
-        team: &str,
-        user: &str,
+        org: &OrgName,
+        team: &TeamSlug,
+        user: &PersonId,
     ) -> anyhow::Result<()> {
         debug!("Removing membership of '{user}' from team '{team}' in org '{org}'");
         if !self.dry_run {
             let url = &GitHubUrl::orgs(org, &format!("teams/{team}/memberships/{user}"))?;
             let method = Method::DELETE;
-            let resp = self.client.req(method.clone(), url)?.send().await?;
+            let resp = self.client.req(method.clone(), url).await?.send().await?;
             allow_not_found(resp, method, url.url()).await?;
         }
 
@@ -224,6 +318,7 @@ impl GitHubWrite {
         &self,
         org: &str,
         name: &str,
+        template: Option<&str>,
         settings: &RepoSettings,
     ) -> anyhow::Result<Repo> {
         #[derive(serde::Serialize, Debug)]
@@ -233,6 +328,14 @@ impl GitHubWrite {
             homepage: &'a Option<&'a str>,
             auto_init: bool,
             allow_auto_merge: bool,
+            allow_squash_merge: bool,
+            allow_merge_commit: bool,
+            allow_rebase_merge: bool,
+            delete_branch_on_merge: bool,
+            has_issues: bool,
+            has_wiki: bool,
+            has_projects: bool,
+            has_discussions: bool,
         }
         let req = &Req {
             name,
@@ -240,19 +343,23 @@ impl GitHubWrite {
             homepage: &settings.homepage.as_deref(),
             auto_init: true,
             allow_auto_merge: settings.auto_merge_enabled,
+            allow_squash_merge: settings.allow_squash_merge,
+            allow_merge_commit: settings.allow_merge_commit,
+            allow_rebase_merge: settings.allow_rebase_merge,
+            delete_branch_on_merge: settings.delete_branch_on_merge,
+            has_issues: settings.has_issues,
+            has_wiki: settings.has_wiki,
+            has_projects: settings.has_projects,
+            has_discussions: settings.has_discussions,
         };
+        if let Some(template) = template {
+            return self
+                .create_repo_from_template(org, name, template, settings)
+                .await;
+        }
         debug!("Creating the repo {org}/{name} with {req:?}");
         if self.dry_run {
-            Ok(Repo {
-                node_id: String::from("ID"),
-                name: name.to_string(),
-                org: org.to_string(),
-                description: settings.description.clone(),
-                homepage: settings.homepage.clone(),
-                archived: false,
-                private: false,
-                allow_auto_merge: Some(settings.auto_merge_enabled),
-            })
+            Ok(dry_run_repo(org, name, settings))
         } else {
             Ok(self
                 .client
@@ -263,6 +370,54 @@ impl GitHubWrite {
         }
     }
 
+    /// Generate a new repository from a template repository (given as `owner/name`)
+    async fn create_repo_from_template(
+        &self,
+        org: &str,
+        name: &str,
+        template: &str,
+        settings: &RepoSettings,
+    ) -> anyhow::Result<Repo> {
+        #[derive(serde::Serialize, Debug)]
+        struct Req<'a> {
+            owner: &'a str,
+            name: &'a str,
+            description: &'a str,
+            include_all_branches: bool,
+            private: bool,
+        }
+        let (template_org, template_name) = template
+            .split_once('/')
+            .ok_or_else(|| anyhow::anyhow!("invalid template repository '{template}'"))?;
+        let req = &Req {
+            owner: org,
+            name,
+            description: &settings.description,
+            include_all_branches: false,
+            private: false,
+        };
+        debug!("Creating the repo {org}/{name} from template {template} with {req:?}");
+        if self.dry_run {
+            return Ok(dry_run_repo(org, name, settings));
+        }
+
+        // REST API: https://docs.github.com/en/rest/repos/repos#create-a-repository-using-a-template
+        let repo: Repo = self
+            .client
+            .send(
+                Method::POST,
+                &GitHubUrl::repos(template_org, template_name, "generate")?,
+                req,
+            )
+            .await?
+            .json_annotated()
+            .await?;
+
+        // The remaining settings cannot be passed when generating the repository
+        self.edit_repo(org, name, settings).await?;
+        Ok(repo)
+    }
+
     pub(crate) async fn edit_repo(
         &self,
         org: &str,
@@ -275,12 +430,28 @@ impl GitHubWrite {
             homepage: &'a Option<&'a str>,
             archived: bool,
             allow_auto_merge: bool,
+            allow_squash_merge: bool,
+            allow_merge_commit: bool,
+            allow_rebase_merge: bool,
+            delete_branch_on_merge: bool,
+            has_issues: bool,
+            has_wiki: bool,
+            has_projects: bool,
+            has_discussions: bool,
         }
         let req = Req {
             description: &settings.description,
             homepage: &settings.homepage.as_deref(),
             archived: settings.archived,
             allow_auto_merge: settings.auto_merge_enabled,
+            allow_squash_merge: settings.allow_squash_merge,
+            allow_merge_commit: settings.allow_merge_commit,
+            allow_rebase_merge: settings.allow_rebase_merge,
+            delete_branch_on_merge: settings.delete_branch_on_merge,
+            has_issues: settings.has_issues,
+            has_wiki: settings.has_wiki,
+            has_projects: settings.has_projects,
+            has_discussions: settings.has_discussions,
         };
         debug!("Editing repo {org}/{repo_name} with {req:?}");
         if !self.dry_run {
@@ -291,6 +462,263 @@ impl GitHubWrite {
         Ok(())
     }
 
+    /// Switch the default branch of a repo to an already existing branch
+    pub(crate) async fn set_default_branch(
+        &self,
+        org: &str,
+        repo_name: &str,
+        branch: &str,
+    ) -> anyhow::Result<()> {
+        #[derive(serde::Serialize, Debug)]
+        struct Req<'a> {
+            default_branch: &'a str,
+        }
+        debug!("Setting the default branch of {org}/{repo_name} to {branch}");
+        if !self.dry_run {
+            self.client
+                .send(
+                    Method::PATCH,
+                    &GitHubUrl::repos(org, repo_name, "")?,
+                    &Req {
+                        default_branch: branch,
+                    },
+                )
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Create a label in a repo
+    pub(crate) async fn create_label(
+        &self,
+        org: &str,
+        repo_name: &str,
+        label: &RepoLabel,
+    ) -> anyhow::Result<()> {
+        debug!("Creating label {} in {org}/{repo_name}", label.name);
+        if !self.dry_run {
+            self.client
+                .send(
+                    Method::POST,
+                    &GitHubUrl::repos(org, repo_name, "labels")?,
+                    &serde_json::json!({
+                        "name": label.name,
+                        "color": label.color,
+                        "description": label.description,
+                    }),
+                )
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Update the name, color and description of a label in a repo
+    pub(crate) async fn update_label(
+        &self,
+        org: &str,
+        repo_name: &str,
+        name: &str,
+        label: &RepoLabel,
+    ) -> anyhow::Result<()> {
+        debug!("Updating label {name} in {org}/{repo_name}");
+        if !self.dry_run {
+            self.client
+                .send(
+                    Method::PATCH,
+                    &GitHubUrl::repos(org, repo_name, &format!("labels/{name}"))?,
+                    &serde_json::json!({
+                        "new_name": label.name,
+                        "color": label.color,
+                        "description": label.description,
+                    }),
+                )
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Create a webhook in a repo
+    pub(crate) async fn create_webhook(
+        &self,
+        org: &str,
+        repo_name: &str,
+        webhook: &Webhook,
+    ) -> anyhow::Result<()> {
+        debug!("Creating webhook {} in {org}/{repo_name}", webhook.url);
+        if !self.dry_run {
+            let mut body = webhook_body(webhook)?;
+            body["name"] = "web".into();
+            self.client
+                .send(
+                    Method::POST,
+                    &GitHubUrl::repos(org, repo_name, "hooks")?,
+                    &body,
+                )
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Update the configuration of a webhook in a repo
+    pub(crate) async fn update_webhook(
+        &self,
+        org: &str,
+        repo_name: &str,
+        id: u64,
+        webhook: &Webhook,
+    ) -> anyhow::Result<()> {
+        debug!("Updating webhook {} in {org}/{repo_name}", webhook.url);
+        if !self.dry_run {
+            self.client
+                .send(
+                    Method::PATCH,
+                    &GitHubUrl::repos(org, repo_name, &format!("hooks/{id}"))?,
+                    &webhook_body(webhook)?,
+                )
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Create an autolink reference in a repo
+    pub(crate) async fn create_autolink(
+        &self,
+        org: &str,
+        repo_name: &str,
+        key_prefix: &str,
+        url_template: &str,
+    ) -> anyhow::Result<()> {
+        #[derive(serde::Serialize, Debug)]
+        struct Req<'a> {
+            key_prefix: &'a str,
+            url_template: &'a str,
+            is_alphanumeric: bool,
+        }
+        debug!("Creating autolink {key_prefix} in {org}/{repo_name}");
+        if !self.dry_run {
+            self.client
+                .send(
+                    Method::POST,
+                    &GitHubUrl::repos(org, repo_name, "autolinks")?,
+                    &Req {
+                        key_prefix,
+                        url_template,
+                        is_alphanumeric: true,
+                    },
+                )
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Delete an autolink reference from a repo
+    pub(crate) async fn delete_autolink(
+        &self,
+        org: &str,
+        repo_name: &str,
+        id: u64,
+    ) -> anyhow::Result<()> {
+        debug!("Deleting autolink {id} from {org}/{repo_name}");
+        if !self.dry_run {
+            let method = Method::DELETE;
+            let url = GitHubUrl::repos(org, repo_name, &format!("autolinks/{id}"))?;
+            let resp = self.client.req(method.clone(), &url).await?.send().await?;
+            allow_not_found(resp, method, url.url()).await?;
+        }
+        Ok(())
+    }
+
+    /// Delete a deploy key from a repo
+    pub(crate) async fn delete_deploy_key(
+        &self,
+        org: &str,
+        repo_name: &str,
+        id: u64,
+    ) -> anyhow::Result<()> {
+        debug!("Deleting deploy key {id} from {org}/{repo_name}");
+        if !self.dry_run {
+            let method = Method::DELETE;
+            let url = GitHubUrl::repos(org, repo_name, &format!("keys/{id}"))?;
+            let resp = self.client.req(method.clone(), &url).await?.send().await?;
+            allow_not_found(resp, method, url.url()).await?;
+        }
+        Ok(())
+    }
+
+    /// Set the GitHub Actions permissions of a repo
+    pub(crate) async fn set_actions_permissions(
+        &self,
+        org: &str,
+        repo_name: &str,
+        permissions: &ActionsPermissions,
+    ) -> anyhow::Result<()> {
+        debug!("Setting actions permissions of {org}/{repo_name} to {permissions:?}");
+        if !self.dry_run {
+            // REST API: https://docs.github.com/en/rest/actions/permissions#set-github-actions-permissions-for-a-repository
+            self.client
+                .send(
+                    Method::PUT,
+                    &GitHubUrl::repos(org, repo_name, "actions/permissions")?,
+                    &serde_json::json!({
+                        "enabled": true,
+                        "allowed_actions": permissions.allowed_actions,
+                    }),
+                )
+                .await?;
+            // The selected actions can only be set once only selected actions are allowed
+            if let Some(selected) = &permissions.selected_actions {
+                // REST API: https://docs.github.com/en/rest/actions/permissions#set-allowed-actions-and-reusable-workflows-for-a-repository
+                self.client
+                    .send(
+                        Method::PUT,
+                        &GitHubUrl::repos(org, repo_name, "actions/permissions/selected-actions")?,
+                        selected,
+                    )
+                    .await?;
+            }
+            // REST API: https://docs.github.com/en/rest/actions/permissions#set-default-workflow-permissions-for-a-repository
+            self.client
+                .send(
+                    Method::PUT,
+                    &GitHubUrl::repos(org, repo_name, "actions/permissions/workflow")?,
+                    &serde_json::json!({
+                        "default_workflow_permissions": permissions.default_workflow_permissions,
+                        "can_approve_pull_request_reviews": permissions.can_approve_pull_requests,
+                    }),
+                )
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Rename a branch of a repo
+    ///
+    /// If the branch is the default branch, GitHub also updates the default branch,
+    /// open pull requests and branch protections targeting it.
+    pub(crate) async fn rename_branch(
+        &self,
+        org: &str,
+        repo_name: &str,
+        branch: &str,
+        new_name: &str,
+    ) -> anyhow::Result<()> {
+        #[derive(serde::Serialize, Debug)]
+        struct Req<'a> {
+            new_name: &'a str,
+        }
+        debug!("Renaming branch {branch} of {org}/{repo_name} to {new_name}");
+        if !self.dry_run {
+            self.client
+                .send(
+                    Method::POST,
+                    &GitHubUrl::repos(org, repo_name, &format!("branches/{branch}/rename"))?,
+                    &Req { new_name },
+                )
+                .await?;
+        }
+        Ok(())
+    }
+
     /// Update a team's permissions to a repo
     pub(crate) async fn update_team_repo_permissions(
         &self,
@@ -353,7 +781,7 @@ impl GitHubWrite {
         if !self.dry_run {
             let method = Method::DELETE;
             let url = GitHubUrl::orgs(org, &format!("teams/{team}/repos/{org}/{repo}"))?;
-            let resp = self.client.req(method.clone(), &url)?.send().await?;
+            let resp = self.client.req(method.clone(), &url).await?.send().await?;
             allow_not_found(resp, method, url.url()).await?;
         }
 
@@ -363,19 +791,53 @@ impl GitHubWrite {
     /// Remove a member from an org
     pub(crate) async fn remove_gh_member_from_org(
         &self,
-        org: &str,
-        user: &str,
+        org: &OrgName,
+        user: &PersonId,
     ) -> anyhow::Result<()> {
         debug!("Removing user {user} from org {org}");
         if !self.dry_run {
             let method = Method::DELETE;
             let url = GitHubUrl::orgs(org, &format!("members/{user}"))?;
-            let resp = self.client.req(method.clone(), &url)?.send().await?;
+            let resp = self.client.req(method.clone(), &url).await?.send().await?;
             allow_not_found(resp, method, url.url()).await?;
         }
         Ok(())
     }
 
+    /// Edit the organization-wide settings of an org
+    pub(crate) async fn edit_org(&self, org: &str, settings: &OrgSettings) -> anyhow::Result<()> {
+        debug!("Editing org {org} with {settings:?}");
+        if !self.dry_run {
+            // REST API: https://docs.github.com/en/rest/orgs/orgs#update-an-organization
+            self.client
+                .send(Method::PATCH, &GitHubUrl::orgs(org, "")?, settings)
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Promote a user to an owner of an org, or demote them to a regular member
+    pub(crate) async fn set_org_owner(
+        &self,
+        org: &OrgName,
+        user: &PersonId,
+        owner: bool,
+    ) -> anyhow::Result<()> {
+        let role = if owner { "admin" } else { "member" };
+        debug!("Setting the role of user {user} in org {org} to {role}");
+        if !self.dry_run {
+            // REST API: https://docs.github.com/en/rest/orgs/members#set-organization-membership-for-a-user
+            self.client
+                .send(
+                    Method::PUT,
+                    &GitHubUrl::orgs(org, &format!("memberships/{user}"))?,
+                    &serde_json::json!({ "role": role }),
+                )
+                .await?;
+        }
+        Ok(())
+    }
+
     /// Remove a collaborator from a repo
     pub(crate) async fn remove_collaborator_from_repo(
         &self,
@@ -387,7 +849,7 @@ impl GitHubWrite {
         if !self.dry_run {
             let method = Method::DELETE;
             let url = &GitHubUrl::repos(org, repo, &format!("collaborators/{collaborator}"))?;
-            let resp = self.client.req(method.clone(), url)?.send().await?;
+            let resp = self.client.req(method.clone(), url).await?.send().await?;
             allow_not_found(resp, method, url.url()).await?;
         }
         Ok(())
@@ -414,7 +876,12 @@ impl GitHubWrite {
             restricts_pushes: bool,
             // Is a PR required to push into this branch?
             requires_approving_reviews: bool,
+            requires_code_owner_reviews: bool,
+            requires_commit_signatures: bool,
+            requires_linear_history: bool,
+            requires_conversation_resolution: bool,
             push_actor_ids: &'a [String],
+            bypass_pull_request_actor_ids: &'a [String],
         }
         let mutation_name = match op {
             BranchProtectionOp::CreateForRepo(_) => "createBranchProtectionRule",
@@ -429,7 +896,7 @@ impl GitHubWrite {
             BranchProtectionOp::UpdateBranchProtection(id) => id,
         };
         let query = format!("
-        mutation($id: ID!, $pattern:String!, $contexts: [String!], $allowsForcePushes: Boolean, $dismissStale: Boolean, $reviewCount: Int, $pushActorIds: [ID!], $restrictsPushes: Boolean, $requiresApprovingReviews: Boolean) {{
+        mutation($id: ID!, $pattern:String!, $contexts: [String!], $allowsForcePushes: Boolean, $dismissStale: Boolean, $reviewCount: Int, $pushActorIds: [ID!], $restrictsPushes: Boolean, $requiresApprovingReviews: Boolean, $requiresCodeOwnerReviews: Boolean, $requiresCommitSignatures: Boolean, $requiresLinearHistory: Boolean, $requiresConversationResolution: Boolean, $bypassPullRequestActorIds: [ID!]) {{
             {mutation_name}(input: {{
                 {id_field}: $id,
                 pattern: $pattern,
@@ -442,8 +909,13 @@ impl GitHubWrite {
                 requiredApprovingReviewCount: $reviewCount,
                 dismissesStaleReviews: $dismissStale,
                 requiresApprovingReviews: $requiresApprovingReviews,
+                requiresCodeOwnerReviews: $requiresCodeOwnerReviews,
+                requiresCommitSignatures: $requiresCommitSignatures,
+                requiresLinearHistory: $requiresLinearHistory,
+                requiresConversationResolution: $requiresConversationResolution,
                 restrictsPushes: $restrictsPushes,
-                pushActorIds: $pushActorIds
+                pushActorIds: $pushActorIds,
+                bypassPullRequestActorIds: $bypassPullRequestActorIds
             }}) {{
               branchProtectionRule {{
                 id
@@ -451,21 +923,12 @@ impl GitHubWrite {
             }}
           }}
         ");
-        let mut push_actor_ids = vec![];
-        for actor in &branch_protection.push_allowances {
-            match actor {
-                PushAllowanceActor::User(UserPushAllowanceActor { login: name }) => {
-                    push_actor_ids.push(self.user_id(name, org).await?);
-                }
-                PushAllowanceActor::Team(TeamPushAllowanceActor {
-                    organization: Login { login: org },
-                    name,
-                }) => push_actor_ids.push(self.team_id(org, name).await?),
-                PushAllowanceActor::App(AppPushAllowanceActor { id, .. }) => {
-                    push_actor_ids.push(id.clone())
-                }
-            }
-        }
+        let push_actor_ids = self
+            .actor_ids(&branch_protection.push_allowances, org)
+            .await?;
+        let bypass_pull_request_actor_ids = self
+            .actor_ids(&branch_protection.bypass_pull_request_allowances, org)
+            .await?;
 
         if !self.dry_run {
             let _: serde_json::Value = self
@@ -484,7 +947,13 @@ impl GitHubWrite {
                         // to merge *or* we only allow those in `push_actor_ids`)
                         restricts_pushes: !push_actor_ids.is_empty(),
                         push_actor_ids: &push_actor_ids,
+                        bypass_pull_request_actor_ids: &bypass_pull_request_actor_ids,
                         requires_approving_reviews: branch_protection.requires_approving_reviews,
+                        requires_code_owner_reviews: branch_protection.requires_code_owner_reviews,
+                        requires_commit_signatures: branch_protection.requires_commit_signatures,
+                        requires_linear_history: branch_protection.requires_linear_history,
+                        requires_conversation_resolution: branch_protection
+                            .requires_conversation_resolution,
                     },
                     org,
                 )
@@ -526,15 +995,10 @@ impl GitHubWrite {
         org: &str,
         repo: &str,
         name: &str,
-        branches: &[String],
-        tags: &[String],
+        env: &Environment,
     ) -> anyhow::Result<()> {
-        debug!(
-            "Creating environment '{name}' in '{org}/{repo}' with branches: {:?}, tags: {:?}",
-            branches, tags
-        );
-        self.upsert_environment(org, repo, name, branches, tags)
-            .await
+        debug!("Creating environment '{name}' in '{org}/{repo}': {env:?}");
+        self.upsert_environment(org, repo, name, env).await
     }
 
     /// Update an environment in a repository
@@ -543,15 +1007,10 @@ impl GitHubWrite {
         org: &str,
         repo: &str,
         name: &str,
-        branches: &[String],
-        tags: &[String],
+        env: &Environment,
     ) -> anyhow::Result<()> {
-        debug!(
-            "Updating environment '{name}' in '{org}/{repo}' with branches: {:?}, tags: {:?}",
-            branches, tags
-        );
-        self.upsert_environment(org, repo, name, branches, tags)
-            .await
+        debug!("Updating environment '{name}' in '{org}/{repo}': {env:?}");
+        self.upsert_environment(org, repo, name, env).await
     }
 
     /// Internal helper to create or update an environment
@@ -560,32 +1019,60 @@ impl GitHubWrite {
         org: &str,
         repo: &str,
         name: &str,
-        branches: &[String],
-        tags: &[String],
+        env: &Environment,
     ) -> anyhow::Result<()> {
         if !self.dry_run {
             // REST API: PUT /repos/{owner}/{repo}/environments/{environment_name}
             // https://docs.github.com/en/rest/deployments/environments#create-or-update-an-environment
             let url = GitHubUrl::repos(org, repo, &format!("environments/{}", name))?;
 
-            let body = if branches.is_empty() && tags.is_empty() {
+            let deployment_branch_policy = if env.protected_branches_only {
                 serde_json::json!({
-                    "deployment_branch_policy": null
+                    "protected_branches": true,
+                    "custom_branch_policies": false
                 })
+            } else if env.branches.is_empty() && env.tags.is_empty() {
+                serde_json::Value::Null
             } else {
                 serde_json::json!({
-                    "deployment_branch_policy": {
-                        "protected_branches": false,
-                        "custom_branch_policies": true
-                    }
+                    "protected_branches": false,
+                    "custom_branch_policies": true
                 })
             };
 
+            // The REST API identifies reviewers by their numeric ID
+            let mut reviewers = Vec::new();
+            for reviewer in &env.reviewers {
+                reviewers.push(match reviewer {
+                    EnvironmentReviewer::User { id, .. } => {
+                        serde_json::json!({ "type": "User", "id": id })
+                    }
+                    EnvironmentReviewer::Team { slug, id } => {
+                        let id = match id {
+                            Some(id) => *id,
+                            None => self.created_team_id(org, slug).with_context(|| {
+                                format!("failed to resolve a reviewer of environment '{name}'")
+                            })?,
+                        };
+                        serde_json::json!({ "type": "Team", "id": id })
+                    }
+                });
+            }
+
+            let body = serde_json::json!({
+                "wait_timer": env.wait_timer,
+                "reviewers": reviewers,
+                "deployment_branch_policy": deployment_branch_policy,
+            });
+
             self.client.send(Method::PUT, &url, &body).await?;
 
-            // Always sync branch/tag policies to ensure cleanup of old policies
-            self.set_environment_deployment_patterns(org, repo, name, branches, tags)
-                .await?;
+            // Custom policies are dropped by GitHub when switching to protected branches
+            if !env.protected_branches_only {
+                // Always sync branch/tag policies to ensure cleanup of old policies
+                self.set_environment_deployment_patterns(org, repo, name, &env.branches, &env.tags)
+                    .await?;
+            }
         }
         Ok(())
     }
@@ -806,3 +1293,49 @@ impl GitHubWrite {
         Ok(())
     }
 }
+
+/// Request body for creating or updating a webhook.
+///
+/// GitHub replaces the whole webhook configuration on update, so the secret is always sent.
+fn webhook_body(webhook: &Webhook) -> anyhow::Result<serde_json::Value> {
+    let mut config = serde_json::json!({
+        "url": webhook.url,
+        "content_type": webhook.content_type.as_str(),
+    });
+    if let Some(secret_env) = &webhook.secret_env {
+        if !secret_env.starts_with(crate::schema::WEBHOOK_SECRET_ENV_PREFIX) {
+            anyhow::bail!(
+                "refusing to send the {secret_env} environment variable as the secret of a webhook"
+            );
+        }
+        config["secret"] = crate::sync::get_env(secret_env)?.into();
+    }
+    Ok(serde_json::json!({
+        "active": true,
+        "events": webhook.events,
+        "config": config,
+    }))
+}
+
+/// The repository that would have been created by a dry run
+fn dry_run_repo(org: &str, name: &str, settings: &RepoSettings) -> Repo {
+    Repo {
+        node_id: String::from("ID"),
+        name: name.to_string(),
+        org: org.to_string(),
+        description: settings.description.clone(),
+        homepage: settings.homepage.clone(),
+        archived: false,
+        private: false,
+        allow_auto_merge: Some(settings.auto_merge_enabled),
+        allow_squash_merge: Some(settings.allow_squash_merge),
+        allow_merge_commit: Some(settings.allow_merge_commit),
+        allow_rebase_merge: Some(settings.allow_rebase_merge),
+        delete_branch_on_merge: Some(settings.delete_branch_on_merge),
+        default_branch: None,
+        has_issues: Some(settings.has_issues),
+        has_wiki: Some(settings.has_wiki),
+        has_projects: Some(settings.has_projects),
+        has_discussions: Some(settings.has_discussions),
+    }
+}
@@ -0,0 +1,117 @@
+//! JSON Lines log of the changes applied to GitHub, for security reviews of access changes.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+
+use anyhow::Context as _;
+use chrono::{DateTime, Utc};
+use log::info;
+
+use super::{Change, GitHubWrite, InverseOperation};
+
+/// Appends a record to a file for every change applied to GitHub
+pub(crate) struct AuditLog {
+    file: File,
+    /// Identifies the records of this run among the records of previous runs
+    run: String,
+    /// Organization -> identity of the token used for it
+    actors: HashMap<String, String>,
+}
+
+/// A change of the diff as recorded in the audit log, captured before it is applied
+pub(super) struct AuditedChange {
+    org: String,
+    resource: String,
+    operation: &'static str,
+    /// The diff entry, including the old and new values
+    change: serde_json::Value,
+    inverse: Vec<InverseOperation>,
+}
+
+#[derive(serde::Serialize)]
+struct AuditRecord<'a> {
+    timestamp: DateTime<Utc>,
+    run: &'a str,
+    org: &'a str,
+    resource: &'a str,
+    operation: &'static str,
+    actor: &'a str,
+    change: &'a serde_json::Value,
+    /// Operations that undo the change, used by `sync rollback`
+    inverse: &'a [InverseOperation],
+    /// Set if the change could not be applied
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+impl AuditedChange {
+    pub(super) fn new<C: Change>(change: &C) -> anyhow::Result<Self> {
+        let (org, resource, operation) = change.target();
+        Ok(Self {
+            org: org.to_string(),
+            resource,
+            operation,
+            change: serde_json::to_value(change)?,
+            inverse: change.inverse(),
+        })
+    }
+}
+
+impl AuditLog {
+    /// Open the audit log, appending to it if it already exists.
+    /// The records of this run are identified by the time at which it started.
+    pub(crate) fn open(path: &Path) -> anyhow::Result<Self> {
+        let file = File::options()
+            .create(true)
+            .append(true)
+            .open(path)
+            .with_context(|| format!("failed to open the audit log {}", path.display()))?;
+        let run = Utc::now().format("%Y-%m-%dT%H:%M:%S%.6fZ").to_string();
+        info!(
+            "recording the applied changes in the audit log {} as the run `{run}`",
+            path.display()
+        );
+        Ok(Self {
+            file,
+            run,
+            actors: HashMap::new(),
+        })
+    }
+
+    /// Identity of the token used to change the organization
+    pub(super) async fn actor(&mut self, sync: &GitHubWrite, org: &str) -> anyhow::Result<String> {
+        if let Some(actor) = self.actors.get(org) {
+            return Ok(actor.clone());
+        }
+        let actor = sync.actor(org).await?;
+        self.actors.insert(org.to_string(), actor.clone());
+        Ok(actor)
+    }
+
+    pub(super) fn record(
+        &mut self,
+        actor: &str,
+        change: &AuditedChange,
+        result: &anyhow::Result<()>,
+    ) -> anyhow::Result<()> {
+        let record = AuditRecord {
+            timestamp: Utc::now(),
+            run: &self.run,
+            org: &change.org,
+            resource: &change.resource,
+            operation: change.operation,
+            actor,
+            change: &change.change,
+            inverse: &change.inverse,
+            error: result.as_ref().err().map(|err| format!("{err:#}")),
+        };
+        let mut line = serde_json::to_vec(&record)?;
+        line.push(b'\n');
+        // Written right away, so that the log is complete even if sync-team crashes
+        self.file
+            .write_all(&line)
+            .context("failed to write to the audit log")
+    }
+}
@@ -0,0 +1,60 @@
+//! Colored rendering of the GitHub diff, so that deletions stand out when reading dry runs in a
+//! terminal.
+
+use std::fmt::Write;
+
+use super::Diff;
+use super::summary::Severity;
+
+const RESET: &str = "\x1b[0m";
+
+fn color(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Additive => "\x1b[32m",
+        Severity::Modifying => "\x1b[33m",
+        Severity::Destructive => "\x1b[31m",
+    }
+}
+
+/// The kind of change described by a line of the diff, if the line starts with a marker or a
+/// verb that tells it.
+fn line_severity(line: &str) -> Option<Severity> {
+    match line.split_whitespace().next()? {
+        "➕" | "Adding" | "Creating" | "Giving" => Some(Severity::Additive),
+        "📝" | "🔄" | "Changing" | "Updating" => Some(Severity::Modifying),
+        "❌" | "Deleting" | "Delete" | "Removing" | "Remove" => Some(Severity::Destructive),
+        _ => None,
+    }
+}
+
+impl Diff {
+    /// Render the diff as text, with additions in green, modifications in yellow and deletions
+    /// in red.
+    ///
+    /// Every line is colored on its own, so that the colors survive the prefixes of the logger.
+    /// Lines without a marker of their own, such as the details of a created team, take the
+    /// color of the less indented line they belong to. The summary and the section headers are
+    /// not colored.
+    pub(crate) fn to_colored_text(&self) -> String {
+        let mut out = String::new();
+        // Indentation and severity of the lines enclosing the current one
+        let mut enclosing: Vec<(usize, Severity)> = Vec::new();
+        for line in self.to_string().lines() {
+            if line.starts_with("📊") || line.starts_with("💻") {
+                enclosing.clear();
+                writeln!(out, "{line}").unwrap();
+                continue;
+            }
+            let indent = line.len() - line.trim_start().len();
+            while enclosing.last().is_some_and(|(i, _)| *i >= indent) {
+                enclosing.pop();
+            }
+            let severity = line_severity(line)
+                .or_else(|| enclosing.last().map(|(_, severity)| *severity))
+                .unwrap_or(Severity::Modifying);
+            enclosing.push((indent, severity));
+            writeln!(out, "{}{line}{RESET}", color(severity)).unwrap();
+        }
+        out
+    }
+}
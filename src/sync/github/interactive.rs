@@ -0,0 +1,58 @@
+//! Confirmation of every change before it is applied, for careful one-off corrections.
+
+use std::fmt::Display;
+use std::io::{BufRead, Write};
+
+/// Asks whether each change of the diff should be applied
+pub(crate) struct Confirmation {
+    input: Box<dyn BufRead + Send>,
+    output: Box<dyn Write + Send>,
+    /// The user chose to apply all the remaining changes
+    apply_all: bool,
+}
+
+impl Confirmation {
+    /// Prompts on stderr, since stdout may contain the diff in a machine-readable format
+    pub(crate) fn stdio() -> Self {
+        Self::new(
+            Box::new(std::io::BufReader::new(std::io::stdin())),
+            Box::new(std::io::stderr()),
+        )
+    }
+
+    pub(crate) fn new(input: Box<dyn BufRead + Send>, output: Box<dyn Write + Send>) -> Self {
+        Self {
+            input,
+            output,
+            apply_all: false,
+        }
+    }
+
+    /// Show the change and return whether it should be applied.
+    ///
+    /// Quitting returns an error, so that the changes that were not reviewed are not applied.
+    pub(crate) fn confirm(&mut self, change: &dyn Display) -> anyhow::Result<bool> {
+        if self.apply_all {
+            return Ok(true);
+        }
+        write!(self.output, "{change}")?;
+        loop {
+            write!(self.output, "Apply this change? [y]es/[n]o/[a]ll/[q]uit: ")?;
+            self.output.flush()?;
+            let mut answer = String::new();
+            if self.input.read_line(&mut answer)? == 0 {
+                anyhow::bail!("no answer to the confirmation, stopping");
+            }
+            match answer.trim().to_lowercase().as_str() {
+                "y" | "yes" => return Ok(true),
+                "n" | "no" => return Ok(false),
+                "a" | "all" => {
+                    self.apply_all = true;
+                    return Ok(true);
+                }
+                "q" | "quit" => anyhow::bail!("stopped applying the changes"),
+                _ => {}
+            }
+        }
+    }
+}
@@ -0,0 +1,47 @@
+//! Markdown rendering of the GitHub diff, used to post dry-runs as pull request comments.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use super::Diff;
+use super::summary::{DiffEntry, Severity};
+
+fn emoji(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Additive => "🟢",
+        Severity::Modifying => "🟡",
+        Severity::Destructive => "🔴",
+    }
+}
+
+impl Diff {
+    /// Render the diff as Markdown, with a summary followed by a collapsible section for
+    /// every changed team, repository and organization setting, grouped by organization.
+    pub(crate) fn to_markdown(&self) -> String {
+        let mut orgs: BTreeMap<&str, Vec<DiffEntry>> = BTreeMap::new();
+        for entry in self.entries() {
+            orgs.entry(entry.org).or_default().push(entry);
+        }
+
+        if orgs.is_empty() {
+            return "No changes to GitHub.\n".to_string();
+        }
+
+        let mut out = format!("**{}**\n\n", self.summary());
+        for (org, entries) in orgs {
+            writeln!(out, "### `{org}`\n").unwrap();
+            for entry in entries {
+                let details = entry.details.to_string();
+                writeln!(
+                    out,
+                    "<details>\n<summary>{} {}</summary>\n\n```text\n{}\n```\n\n</details>\n",
+                    emoji(entry.severity),
+                    entry.title,
+                    details.trim_end()
+                )
+                .unwrap();
+            }
+        }
+        out
+    }
+}
@@ -1,27 +1,52 @@
 mod api;
+mod audit;
+mod color;
+mod interactive;
+mod markdown;
+mod notification;
+mod plan;
+mod report;
+mod revisions;
+mod rollback;
+mod summary;
 #[cfg(test)]
 mod tests;
 
 use self::api::{BranchProtectionOp, TeamPrivacy, TeamRole};
-pub(crate) use self::api::{GitHubApiRead, GitHubWrite, HttpClient};
+pub(crate) use self::api::{CachedGithubRead, GitHubApiRead, GitHubWrite, GithubRead, HttpClient};
+use self::audit::{AuditLog, AuditedChange};
+use self::interactive::Confirmation;
+use self::notification::DestructiveChanges;
+pub(crate) use self::report::ApplyReport;
+pub(crate) use self::revisions::{Revision, diff_revisions};
+use self::rollback::InverseOperation;
+pub(crate) use self::rollback::rollback;
 use crate::schema;
 use crate::sync::Config;
 use crate::sync::github::api::{
-    GithubRead, Login, PushAllowanceActor, RepoPermission, RepoSettings, Ruleset,
+    Login, PushAllowanceActor, RepoPermission, RepoSettings, Ruleset, TokenPermissions,
 };
+use anyhow::Context as _;
 use futures_util::StreamExt;
-use log::debug;
-use rust_team_data::v1::{Bot, BranchProtectionMode, MergeBot, ProtectionTarget};
+use log::{debug, warn};
+use rust_team_data::ids::{OrgName, PersonId, RepoName, TeamName, TeamSlug};
+use rust_team_data::v1::{
+    ActionsPermissions, Bot, BranchProtectionMode, MergeBot, ProtectionTarget,
+};
+use std::cell::Cell;
 use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::fmt::{Display, Write};
 
 static DEFAULT_DESCRIPTION: &str = "Managed by the rust-lang/team repository.";
 static DEFAULT_PRIVACY: TeamPrivacy = TeamPrivacy::Closed;
 
-/// GitHub Actions integration ID
+/// GitHub Actions integration ID on github.com
 /// Verified via: https://api.github.com/repos/rust-lang/rust/commits/HEAD/check-runs
 const GITHUB_ACTIONS_INTEGRATION_ID: i64 = 15368;
 
+/// Default number of teams or repositories diffed concurrently
+const DEFAULT_CONCURRENCY: usize = 10;
+
 const REQUIRE_CODE_OWNER_REVIEW_DEFAULT: bool = false;
 const REQUIRE_LAST_PUSH_APPROVAL_DEFAULT: bool = false;
 const REQUIRED_REVIEW_THREAD_RESOLUTION_DEFAULT: bool = false;
@@ -31,18 +56,162 @@ pub(crate) async fn create_diff(
     github: Box<dyn GithubRead>,
     teams: Vec<rust_team_data::v1::Team>,
     repos: Vec<rust_team_data::v1::Repo>,
+    orgs: Vec<rust_team_data::v1::Org>,
     config: Config,
+    access: Access,
 ) -> anyhow::Result<Diff> {
-    let github = SyncGitHub::new(github, teams, repos, config).await?;
+    let github = SyncGitHub::new(github, teams, repos, orgs, config, access).await?;
     github.diff_all().await
 }
 
-type OrgName = String;
+/// Access to the GitHub organizations that the token must grant
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Access {
+    /// Only compute the diff
+    Read,
+    /// Compute and apply the diff
+    Write,
+}
+
+/// What sync-team manages in a GitHub organization
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum Capability {
+    Teams,
+    Repos,
+    OrgSettings,
+}
+
+impl Capability {
+    fn describe(self, access: Access) -> &'static str {
+        match (self, access) {
+            (Capability::Teams, Access::Read) => "read teams and members",
+            (Capability::Teams, Access::Write) => "administer teams",
+            (Capability::Repos, Access::Read) => "read repository settings",
+            (Capability::Repos, Access::Write) => "administer repositories",
+            (Capability::OrgSettings, Access::Read) => "read organization settings",
+            (Capability::OrgSettings, Access::Write) => "administer the organization",
+        }
+    }
+
+    /// OAuth scopes of classic personal access tokens that grant the capability
+    fn scopes(self, access: Access) -> &'static [&'static str] {
+        match (self, access) {
+            (Capability::Teams | Capability::OrgSettings, Access::Read) => {
+                &["read:org", "write:org", "admin:org"]
+            }
+            (Capability::Teams | Capability::OrgSettings, Access::Write) => &["admin:org"],
+            (Capability::Repos, _) => &["repo"],
+        }
+    }
+
+    /// Permission of GitHub App installations that grants the capability
+    fn installation_permission(self) -> &'static str {
+        match self {
+            Capability::Teams => "members",
+            Capability::Repos => "administration",
+            Capability::OrgSettings => "organization_administration",
+        }
+    }
+
+    fn is_granted(self, access: Access, permissions: &TokenPermissions) -> bool {
+        match permissions {
+            TokenPermissions::Scopes(scopes) => self
+                .scopes(access)
+                .iter()
+                .any(|scope| scopes.contains(*scope)),
+            TokenPermissions::Installation(permissions) => {
+                match permissions
+                    .get(self.installation_permission())
+                    .map(|s| s.as_str())
+                {
+                    Some("write") => true,
+                    Some("read") => access == Access::Read,
+                    _ => false,
+                }
+            }
+            TokenPermissions::Unknown => true,
+        }
+    }
+}
+
+/// Ensure that the GitHub tokens are allowed to do everything that the sync will need, so that
+/// missing permissions are reported upfront instead of failing halfway through applying the diff.
+async fn check_token_permissions(
+    github: &dyn GithubRead,
+    teams: &[rust_team_data::v1::Team],
+    repos: &[rust_team_data::v1::Repo],
+    orgs: &[rust_team_data::v1::Org],
+    access: Access,
+) -> anyhow::Result<()> {
+    let mut needed: BTreeMap<&str, BTreeSet<Capability>> = BTreeMap::new();
+    for gh_team in teams
+        .iter()
+        .filter_map(|t| t.github.as_ref())
+        .flat_map(|gh| &gh.teams)
+    {
+        needed
+            .entry(&gh_team.org)
+            .or_default()
+            .insert(Capability::Teams);
+    }
+    for repo in repos {
+        needed
+            .entry(&repo.org)
+            .or_default()
+            .insert(Capability::Repos);
+    }
+    for org in orgs {
+        needed
+            .entry(&org.name)
+            .or_default()
+            .insert(Capability::OrgSettings);
+    }
+
+    let mut missing = Vec::new();
+    for (org, capabilities) in needed {
+        let permissions = github.token_permissions(org).await?;
+        if permissions == TokenPermissions::Unknown {
+            debug!("cannot inspect the permissions of the token used for organization {org}");
+            continue;
+        }
+        for capability in capabilities {
+            if !capability.is_granted(access, &permissions) {
+                let required = match permissions {
+                    TokenPermissions::Scopes(_) => format!(
+                        "one of the scopes `{}`",
+                        capability.scopes(access).join("`, `")
+                    ),
+                    _ => format!(
+                        "the `{}` permission with {} access",
+                        capability.installation_permission(),
+                        match access {
+                            Access::Read => "read",
+                            Access::Write => "write",
+                        }
+                    ),
+                };
+                missing.push(format!(
+                    "  - {org}: cannot {} (requires {required})",
+                    capability.describe(access)
+                ));
+            }
+        }
+    }
+
+    if !missing.is_empty() {
+        anyhow::bail!(
+            "the GitHub token is missing permissions needed by sync-team:\n{}",
+            missing.join("\n")
+        );
+    }
+    Ok(())
+}
 
 struct SyncGitHub {
     github: Box<dyn GithubRead>,
     teams: Vec<rust_team_data::v1::Team>,
     repos: Vec<rust_team_data::v1::Repo>,
+    orgs: Vec<rust_team_data::v1::Org>,
     config: Config,
     usernames_cache: HashMap<u64, String>,
     org_owners: HashMap<OrgName, HashSet<u64>>,
@@ -54,8 +223,13 @@ impl SyncGitHub {
         github: Box<dyn GithubRead>,
         teams: Vec<rust_team_data::v1::Team>,
         repos: Vec<rust_team_data::v1::Repo>,
+        orgs: Vec<rust_team_data::v1::Org>,
         config: Config,
+        access: Access,
     ) -> anyhow::Result<Self> {
+        debug!("checking the permissions of the GitHub tokens");
+        check_token_permissions(&*github, &teams, &repos, &orgs, access).await?;
+
         debug!("caching mapping between user ids and usernames");
         let users = teams
             .iter()
@@ -63,13 +237,14 @@ impl SyncGitHub {
             .flatten()
             .flat_map(|team| &team.members)
             .copied()
-            .collect::<HashSet<_>>()
+            // Sorted to send the same requests on every run, see `GitHubState`
+            .collect::<BTreeSet<_>>()
             .into_iter()
             .collect::<Vec<_>>();
         let usernames_cache = github.usernames(&users).await?;
 
         debug!("caching organization owners");
-        let orgs = teams
+        let team_orgs = teams
             .iter()
             .filter_map(|t| t.github.as_ref())
             .flat_map(|gh| &gh.teams)
@@ -79,15 +254,16 @@ impl SyncGitHub {
         let mut org_owners = HashMap::new();
         let mut org_members = HashMap::new();
 
-        for org in &orgs {
-            org_owners.insert((*org).to_string(), github.org_owners(org).await?);
-            org_members.insert((*org).to_string(), github.org_members(org).await?);
+        for org in &team_orgs {
+            org_owners.insert(OrgName::from(*org), github.org_owners(org).await?);
+            org_members.insert(OrgName::from(*org), github.org_members(org).await?);
         }
 
         Ok(SyncGitHub {
             github,
             teams,
             repos,
+            orgs,
             config,
             usernames_cache,
             org_owners,
@@ -95,18 +271,99 @@ impl SyncGitHub {
         })
     }
 
+    /// ID of the GitHub Actions app, which differs between github.com and
+    /// GitHub Enterprise Server instances
+    fn actions_integration_id(&self) -> i64 {
+        self.config
+            .github_actions_integration_id
+            .unwrap_or(GITHUB_ACTIONS_INTEGRATION_ID)
+    }
+
+    /// Maximum number of teams or repositories that are diffed concurrently
+    fn concurrency(&self) -> usize {
+        self.config
+            .github_concurrency
+            .unwrap_or(DEFAULT_CONCURRENCY)
+            .max(1)
+    }
+
+    /// Managed GitHub teams, across all the organizations
+    fn github_teams(&self) -> impl Iterator<Item = &rust_team_data::v1::GitHubTeam> {
+        self.teams
+            .iter()
+            .filter_map(|t| t.github.as_ref())
+            .flat_map(|gh| &gh.teams)
+    }
+
+    /// Ensure that every organization, repository and team selected by the filter is managed,
+    /// so that a typo doesn't silently result in an empty diff
+    fn check_filter(&self) -> anyhow::Result<()> {
+        let filter = &self.config.github_filter;
+        for org in &filter.orgs {
+            let managed = self.orgs.iter().any(|o| &o.name == org)
+                || self.repos.iter().any(|r| &r.org == org)
+                || self.github_teams().any(|t| &t.org == org);
+            if !managed {
+                anyhow::bail!("organization `{org}` is not managed by the team repository");
+            }
+        }
+        for repo in &filter.repos {
+            if !self
+                .repos
+                .iter()
+                .any(|r| &r.name == repo || format!("{}/{}", r.org, r.name) == *repo)
+            {
+                anyhow::bail!("repository `{repo}` is not managed by the team repository");
+            }
+        }
+        for team in &filter.teams {
+            if !self.github_teams().any(|t| &t.name == team) {
+                anyhow::bail!("GitHub team `{team}` is not managed by the team repository");
+            }
+        }
+        Ok(())
+    }
+
     pub(crate) async fn diff_all(&self) -> anyhow::Result<Diff> {
+        self.check_filter()?;
+        let org_diffs = self.diff_orgs().await?;
         let team_diffs = self.diff_teams().await?;
         let repo_diffs = self.diff_repos().await?;
         let org_membership_diffs = self.diff_org_memberships().await?;
+        let org_owner_diffs = self.diff_org_owners()?;
 
         Ok(Diff {
+            org_diffs,
             team_diffs,
             repo_diffs,
             org_membership_diffs,
+            org_owner_diffs,
         })
     }
 
+    /// Diff the organization-wide settings of the configured organizations
+    async fn diff_orgs(&self) -> anyhow::Result<Vec<OrgDiff>> {
+        let mut diffs = Vec::new();
+        for org in &self.orgs {
+            if !self.config.github_filter.includes_org_wide(&org.name) {
+                continue;
+            }
+            let old = self.github.org_settings(&org.name).await?;
+            let new = api::OrgSettings {
+                default_repository_permission: org.default_repository_permission,
+                members_can_create_repositories: org.members_can_create_repositories,
+                members_can_fork_private_repositories: org.members_can_fork_private_repositories,
+            };
+            if old != new {
+                diffs.push(OrgDiff {
+                    org: org.name.as_str().into(),
+                    settings_diff: (old, new),
+                });
+            }
+        }
+        Ok(diffs)
+    }
+
     /// Collect all org members from the respective teams
     fn get_org_members_from_teams(&self) -> HashMap<OrgName, HashSet<u64>> {
         let mut org_team_members: HashMap<OrgName, HashSet<u64>> = HashMap::new();
@@ -115,7 +372,7 @@ impl SyncGitHub {
             if let Some(gh) = &team.github {
                 for toml_gh_team in &gh.teams {
                     org_team_members
-                        .entry(toml_gh_team.org.clone())
+                        .entry(OrgName::from(&toml_gh_team.org))
                         .or_default()
                         .extend(toml_gh_team.members.iter().copied());
                 }
@@ -128,11 +385,14 @@ impl SyncGitHub {
     async fn diff_org_memberships(&self) -> anyhow::Result<Vec<OrgMembershipDiff>> {
         let toml_org_team_members = self.get_org_members_from_teams();
 
-        let mut org_diffs: BTreeMap<String, OrgMembershipDiff> = BTreeMap::new();
+        let mut org_diffs: BTreeMap<OrgName, OrgMembershipDiff> = BTreeMap::new();
 
         for (org, toml_members) in toml_org_team_members {
+            if !self.config.github_filter.includes_org_wide(&org) {
+                continue;
+            }
             // Skip independent organizations - they manage their own members
-            if self.config.independent_github_orgs.contains(&org) {
+            if self.config.independent_github_orgs.contains(org.as_str()) {
                 debug!("Skipping member sync for independent organization: {}", org);
                 continue;
             }
@@ -145,7 +405,10 @@ impl SyncGitHub {
 
             // The rest are members that should be removed
             if !members_to_remove.is_empty() {
-                let mut members_to_remove: Vec<String> = members_to_remove.into_values().collect();
+                let mut members_to_remove: Vec<PersonId> = members_to_remove
+                    .into_values()
+                    .map(PersonId::from)
+                    .collect();
                 members_to_remove.sort();
 
                 org_diffs.insert(
@@ -171,7 +434,8 @@ impl SyncGitHub {
         // Next, we'll delete members from `members_to_remove` that don't respect certain criteria.
         let mut members_to_remove = gh_org_members.clone();
 
-        // People who belong to a team should stay in the org.
+        // People who belong to a team should stay in the org. They are matched by GitHub ID, so
+        // that renamed accounts are not removed.
         for member in toml_members {
             members_to_remove.remove(&member);
         }
@@ -189,6 +453,67 @@ impl SyncGitHub {
         members_to_remove
     }
 
+    /// Diff organization owners between the org owner teams in TOML and GitHub.
+    ///
+    /// Owners are only managed in organizations that have at least one org owner team.
+    fn diff_org_owners(&self) -> anyhow::Result<Vec<OrgOwnersDiff>> {
+        let mut expected_org_owners: BTreeMap<OrgName, HashSet<u64>> = BTreeMap::new();
+        for team in &self.teams {
+            if let Some(gh) = &team.github {
+                for toml_gh_team in gh.teams.iter().filter(|t| t.org_owners) {
+                    expected_org_owners
+                        .entry(OrgName::from(&toml_gh_team.org))
+                        .or_default()
+                        .extend(toml_gh_team.members.iter().copied());
+                }
+            }
+        }
+
+        let mut org_diffs = Vec::new();
+        for (org, expected_owners) in expected_org_owners {
+            if !self.config.github_filter.includes_org_wide(&org) {
+                continue;
+            }
+            if self.config.independent_github_orgs.contains(org.as_str()) {
+                debug!("Skipping owner sync for independent organization: {}", org);
+                continue;
+            }
+
+            let (Some(gh_org_owners), Some(gh_org_members)) =
+                (self.org_owners.get(&org), self.org_members.get(&org))
+            else {
+                return Err(anyhow::anyhow!("GitHub organization {org} not found"));
+            };
+
+            let mut owners_to_add: Vec<PersonId> = expected_owners
+                .difference(gh_org_owners)
+                .map(|id| PersonId::from(&self.usernames_cache[id]))
+                .collect();
+            owners_to_add.sort();
+
+            // Accounts that are explicitly allowed in the org (e.g. the sync bot itself)
+            // are never demoted.
+            let mut owners_to_remove: Vec<PersonId> = gh_org_owners
+                .difference(&expected_owners)
+                .filter_map(|id| gh_org_members.get(id))
+                .filter(|username| !self.config.special_org_members.contains(*username))
+                .map(PersonId::from)
+                .collect();
+            owners_to_remove.sort();
+
+            if !owners_to_add.is_empty() || !owners_to_remove.is_empty() {
+                org_diffs.push(OrgOwnersDiff {
+                    org,
+                    owners_to_add,
+                    owners_to_remove,
+                    demotions_allowed: self.config.allow_owner_demotions,
+                });
+            }
+        }
+
+        Ok(org_diffs)
+    }
+
     async fn diff_teams(&self) -> anyhow::Result<Vec<TeamDiff>> {
         let mut diffs = Vec::new();
         let mut unseen_github_teams = HashMap::new();
@@ -196,6 +521,13 @@ impl SyncGitHub {
         for team in &self.teams {
             if let Some(gh) = &team.github {
                 for github_team in &gh.teams {
+                    if !self
+                        .config
+                        .github_filter
+                        .includes_team(&github_team.org, &github_team.name)
+                    {
+                        continue;
+                    }
                     // Get existing teams we haven't seen yet
                     let unseen_github_teams = match unseen_github_teams.get_mut(&github_team.org) {
                         Some(ts) => ts,
@@ -221,7 +553,7 @@ impl SyncGitHub {
         // Diff teams concurrently
         let mut stream = futures_util::stream::iter(teams_to_diff)
             .map(|team| self.diff_team(team))
-            .buffer_unordered(10);
+            .buffer_unordered(self.concurrency());
         while let Some(diff_team) = stream.next().await {
             let diff_team = diff_team?;
             if !diff_team.noop() {
@@ -229,77 +561,154 @@ impl SyncGitHub {
             }
         }
 
+        // Renamed teams are still present on GitHub under their previous name,
+        // but they are managed, so they must not be deleted
+        for diff in &diffs {
+            if let TeamDiff::Edit(EditTeamDiff {
+                org,
+                name,
+                name_diff: Some(_),
+                ..
+            }) = diff
+                && let Some(unseen) = unseen_github_teams.get_mut(org.as_str())
+            {
+                unseen.remove(name.as_str());
+            }
+        }
+
+        // Parent teams have to exist before their child teams can be nested under them
+        diffs.sort_by_cached_key(|diff| match diff {
+            TeamDiff::Create(CreateTeamDiff { org, name, .. }) => self.team_depth(org, name),
+            TeamDiff::Edit(EditTeamDiff {
+                org,
+                name,
+                name_diff,
+                ..
+            }) => self.team_depth(org, name_diff.as_ref().unwrap_or(name)),
+            TeamDiff::Delete(_) => 0,
+        });
+
         let delete_diffs = unseen_github_teams
             .into_iter()
-            .filter(|(org, _)| matches!(org.as_str(), "rust-lang" | "rust-lang-nursery")) // Only delete unmanaged teams in `rust-lang` and `rust-lang-nursery` for now
+            .filter(|(org, _)| self.config.unmanaged_team_deletion_orgs.contains(org))
+            // Teams that weren't selected by the filter were not marked as seen
+            .filter(|(org, _)| self.config.github_filter.includes_org_wide(org))
             .flat_map(|(org, remaining_github_teams)| {
                 remaining_github_teams
                     .into_iter()
                     .map(move |t| (org.clone(), t))
             })
-            // Don't delete the special bot teams
             .filter(|(_, (remaining_github_team, _))| {
-                !BOTS_TEAMS.contains(&remaining_github_team.as_str())
+                !self
+                    .config
+                    .preserved_github_teams
+                    .contains(remaining_github_team)
             })
-            .map(|(org, (name, slug))| TeamDiff::Delete(DeleteTeamDiff { org, name, slug }));
+            .map(|(org, (name, slug))| {
+                TeamDiff::Delete(DeleteTeamDiff {
+                    org: org.into(),
+                    name: name.into(),
+                    slug: slug.into(),
+                })
+            });
 
         diffs.extend(delete_diffs);
 
         Ok(diffs)
     }
 
+    /// The number of ancestors of a GitHub team in the expected team hierarchy
+    fn team_depth(&self, org: &str, name: &str) -> usize {
+        let parents: HashMap<&str, &str> = self
+            .teams
+            .iter()
+            .filter_map(|t| t.github.as_ref())
+            .flat_map(|gh| &gh.teams)
+            .filter(|gh_team| gh_team.org == org)
+            .filter_map(|gh_team| Some((gh_team.name.as_str(), gh_team.parent.as_deref()?)))
+            .collect();
+
+        let mut depth = 0;
+        let mut current = name;
+        // The depth is bounded by the number of teams in case of a cycle
+        while let Some(parent) = parents.get(current) {
+            if depth > parents.len() {
+                break;
+            }
+            depth += 1;
+            current = parent;
+        }
+        depth
+    }
+
     async fn diff_team(
         &self,
         github_team: &rust_team_data::v1::GitHubTeam,
     ) -> anyhow::Result<TeamDiff> {
         debug!("Diffing team `{}/{}`", github_team.org, github_team.name);
 
+        let expected_description = expected_description(github_team);
+        let expected_privacy = expected_privacy(github_team);
+
         // Ensure the team exists and is consistent
-        let team = match self
+        let mut team = self
             .github
             .team(&github_team.org, &github_team.name)
-            .await?
+            .await?;
+        // If the team was renamed, find it by its ID
+        if team.is_none()
+            && let Some(team_id) = github_team.team_id
         {
+            team = self.github.team_by_id(&github_team.org, team_id).await?;
+        }
+        let team = match team {
             Some(team) => team,
             None => {
                 let members = github_team
                     .members
                     .iter()
                     .map(|member| {
-                        let expected_role = self.expected_role(&github_team.org, *member);
-                        (self.usernames_cache[member].clone(), expected_role)
+                        let expected_role = self.expected_role(github_team, *member);
+                        (PersonId::from(&self.usernames_cache[member]), expected_role)
                     })
                     .collect();
                 return Ok(TeamDiff::Create(CreateTeamDiff {
-                    org: github_team.org.clone(),
-                    name: github_team.name.clone(),
-                    description: DEFAULT_DESCRIPTION.to_owned(),
-                    privacy: DEFAULT_PRIVACY,
+                    org: github_team.org.as_str().into(),
+                    name: github_team.name.as_str().into(),
+                    description: expected_description.to_owned(),
+                    privacy: expected_privacy,
+                    parent: self.expected_parent(github_team).await?,
                     members,
                 }));
             }
         };
         let mut name_diff = None;
         if team.name != github_team.name {
-            name_diff = Some(github_team.name.clone())
+            name_diff = Some(TeamName::from(&github_team.name))
         }
         let mut description_diff = None;
         match &team.description {
             Some(description) => {
-                if description != DEFAULT_DESCRIPTION {
-                    description_diff = Some((description.clone(), DEFAULT_DESCRIPTION.to_owned()));
+                if description != expected_description {
+                    description_diff = Some((description.clone(), expected_description.to_owned()));
                 }
             }
             None => {
-                description_diff = Some((String::new(), DEFAULT_DESCRIPTION.to_owned()));
+                description_diff = Some((String::new(), expected_description.to_owned()));
             }
         }
         let mut privacy_diff = None;
-        if team.privacy != DEFAULT_PRIVACY {
-            privacy_diff = Some((team.privacy, DEFAULT_PRIVACY))
+        if team.privacy != expected_privacy {
+            privacy_diff = Some((team.privacy, expected_privacy))
+        }
+        let mut parent_diff = None;
+        if team.parent.as_ref().map(|p| p.name.as_str()) != github_team.parent.as_deref() {
+            let expected_parent = self.expected_parent(github_team).await?;
+            parent_diff = Some((team.parent.clone(), expected_parent));
         }
 
         let mut member_diffs = Vec::new();
+        let mut unchanged_members = 0;
 
         let mut current_members = self
             .github
@@ -307,54 +716,104 @@ impl SyncGitHub {
             .await?;
         let invites = self
             .github
-            .team_membership_invitations(&github_team.org, &github_team.name)
+            .team_membership_invitations(&github_team.org, &team.slug)
             .await?;
 
-        // Ensure all expected members are in the team
+        // Ensure all expected members are in the team. They are matched by GitHub ID, so that
+        // renamed accounts stay members whichever of the data and GitHub was updated first.
         for member in &github_team.members {
-            let expected_role = self.expected_role(&github_team.org, *member);
+            let expected_role = self.expected_role(github_team, *member);
             let username = &self.usernames_cache[member];
             if let Some(member) = current_members.remove(member) {
                 if member.role != expected_role {
                     member_diffs.push((
-                        username.clone(),
+                        PersonId::from(username),
                         MemberDiff::ChangeRole((member.role, expected_role)),
                     ));
                 } else {
-                    member_diffs.push((username.clone(), MemberDiff::Noop));
+                    unchanged_members += 1;
                 }
             } else {
                 // Check if the user has been invited already
                 if invites.contains(username) {
-                    member_diffs.push((username.clone(), MemberDiff::Noop));
+                    unchanged_members += 1;
                 } else {
-                    member_diffs.push((username.clone(), MemberDiff::Create(expected_role)));
+                    member_diffs
+                        .push((PersonId::from(username), MemberDiff::Create(expected_role)));
                 }
             }
         }
 
         // The previous cycle removed expected members from current_members, so it only contains
-        // members to delete now.
-        for member in current_members.values() {
-            member_diffs.push((member.username.clone(), MemberDiff::Delete));
+        // members to delete now. They are sorted, so that the diff is stable between runs.
+        for member in BTreeMap::from_iter(current_members).values() {
+            member_diffs.push((
+                PersonId::from(&member.username),
+                MemberDiff::Delete(member.role),
+            ));
         }
 
         Ok(TeamDiff::Edit(EditTeamDiff {
-            org: github_team.org.clone(),
-            name: team.name,
+            org: github_team.org.as_str().into(),
+            name: team.name.into(),
+            slug: team.slug.into(),
             name_diff,
             description_diff,
             privacy_diff,
+            parent_diff,
             member_diffs,
+            unchanged_members,
+        }))
+    }
+
+    /// Resolve the parent of a team to the ID the write side references it by
+    async fn expected_parent(
+        &self,
+        github_team: &rust_team_data::v1::GitHubTeam,
+    ) -> anyhow::Result<Option<api::ParentTeam>> {
+        let Some(name) = &github_team.parent else {
+            return Ok(None);
+        };
+        // Parents that are created or renamed by this sync are resolved when they're written
+        let id = self
+            .github
+            .team(&github_team.org, name)
+            .await?
+            .and_then(|team| team.id);
+        Ok(Some(api::ParentTeam {
+            name: name.clone(),
+            id,
         }))
     }
 
     async fn diff_repos(&self) -> anyhow::Result<Vec<RepoDiff>> {
         let mut diffs = Vec::new();
 
-        let mut stream = futures_util::stream::iter(self.repos.iter())
+        let repos = self
+            .repos
+            .iter()
+            .filter(|repo| {
+                self.config
+                    .github_filter
+                    .includes_repo(&repo.org, &repo.name)
+            })
+            .collect::<Vec<_>>();
+
+        debug!("prefetching repositories");
+        let mut repos_by_org: BTreeMap<&str, Vec<String>> = BTreeMap::new();
+        for repo in &repos {
+            repos_by_org
+                .entry(&repo.org)
+                .or_default()
+                .push(repo.name.clone());
+        }
+        for (org, repos) in repos_by_org {
+            self.github.prefetch_repos(org, &repos).await?;
+        }
+
+        let mut stream = futures_util::stream::iter(repos)
             .map(|repo| self.diff_repo(repo))
-            .buffer_unordered(10);
+            .buffer_unordered(self.concurrency());
         while let Some(repo_diff) = stream.next().await {
             let repo_diff = repo_diff?;
             if !repo_diff.noop() {
@@ -364,12 +823,6 @@ impl SyncGitHub {
         Ok(diffs)
     }
 
-    /// Check if a repository should use rulesets instead of branch protections
-    fn should_use_rulesets(&self, repo: &rust_team_data::v1::Repo) -> bool {
-        let repo_full_name = format!("{}/{}", repo.org, repo.name);
-        self.config.enable_rulesets_repos.contains(&repo_full_name)
-    }
-
     async fn diff_repo(
         &self,
         expected_repo: &rust_team_data::v1::Repo,
@@ -379,6 +832,8 @@ impl SyncGitHub {
             expected_repo.org, expected_repo.name
         );
 
+        self.check_custom_roles(expected_repo).await?;
+
         let actual_repo = match self
             .github
             .repo(&expected_repo.org, &expected_repo.name)
@@ -390,33 +845,56 @@ impl SyncGitHub {
                     expected_repo,
                     Default::default(),
                     Default::default(),
+                    Default::default(),
                 )?;
 
                 let mut branch_protections = Vec::new();
                 for branch_protection in &expected_repo.branch_protections {
                     branch_protections.push((
                         branch_protection.pattern.clone(),
-                        construct_branch_protection(expected_repo, branch_protection),
+                        self.expected_branch_protection(expected_repo, branch_protection)
+                            .await?,
                     ));
                 }
 
                 let mut rulesets = Vec::new();
-                let use_rulesets = self.should_use_rulesets(expected_repo);
+                let use_rulesets = expected_repo.use_rulesets;
                 if use_rulesets {
                     for branch_protection in &expected_repo.branch_protections {
-                        let ruleset = construct_ruleset(branch_protection);
+                        let ruleset =
+                            construct_ruleset(branch_protection, self.actions_integration_id())?;
                         rulesets.push(ruleset);
                     }
                 }
 
+                let mut environments = Vec::new();
+                for (name, env) in &expected_repo.environments {
+                    environments.push((
+                        name.clone(),
+                        self.expected_environment(&expected_repo.org, env).await?,
+                    ));
+                }
+
                 return Ok(RepoDiff::Create(CreateRepoDiff {
-                    org: expected_repo.org.clone(),
-                    name: expected_repo.name.clone(),
+                    org: expected_repo.org.as_str().into(),
+                    name: expected_repo.name.as_str().into(),
+                    default_branch: expected_repo.default_branch.clone(),
                     settings: RepoSettings {
                         description: expected_repo.description.clone(),
                         homepage: expected_repo.homepage.clone(),
                         archived: false,
                         auto_merge_enabled: expected_repo.auto_merge_enabled,
+                        // All merge methods are allowed by default on GitHub
+                        allow_squash_merge: expected_repo.allow_squash_merge.unwrap_or(true),
+                        allow_merge_commit: expected_repo.allow_merge_commit.unwrap_or(true),
+                        allow_rebase_merge: expected_repo.allow_rebase_merge.unwrap_or(true),
+                        delete_branch_on_merge: expected_repo
+                            .delete_branch_on_merge
+                            .unwrap_or(false),
+                        has_issues: expected_repo.has_issues.unwrap_or(true),
+                        has_wiki: expected_repo.has_wiki.unwrap_or(true),
+                        has_projects: expected_repo.has_projects.unwrap_or(true),
+                        has_discussions: expected_repo.has_discussions.unwrap_or(false),
                     },
                     permissions,
                     // Don't create branch protections if using rulesets
@@ -426,11 +904,16 @@ impl SyncGitHub {
                         branch_protections
                     },
                     rulesets,
-                    environments: expected_repo
-                        .environments
+                    environments,
+                    labels: expected_labels(expected_repo),
+                    webhooks: expected_repo.webhooks.clone(),
+                    autolinks: expected_repo
+                        .autolinks
                         .iter()
-                        .map(|(name, env)| (name.clone(), env.clone()))
+                        .map(|(prefix, template)| (prefix.clone(), template.clone()))
                         .collect(),
+                    actions: expected_repo.actions.clone(),
+                    template: expected_repo.template.clone(),
                 }));
             }
         };
@@ -449,38 +932,186 @@ impl SyncGitHub {
             .diff_branch_protections(&actual_repo, expected_repo)
             .await?;
 
-        let ruleset_diffs = if self.should_use_rulesets(expected_repo) {
+        let ruleset_diffs = if expected_repo.use_rulesets {
             self.diff_rulesets(expected_repo).await?
         } else {
             Vec::new()
         };
 
         let environment_diffs = self.diff_environments(expected_repo).await?;
+        let label_diffs = self.diff_labels(expected_repo).await?;
+        let webhook_diffs = self.diff_webhooks(expected_repo).await?;
+        let autolink_diffs = self.diff_autolinks(expected_repo).await?;
+        let deploy_key_diffs = self.diff_deploy_keys(expected_repo).await?;
+        let actions_diff = self.diff_actions_permissions(expected_repo).await?;
+        let default_branch_diff = self
+            .diff_default_branch(&actual_repo, expected_repo)
+            .await?;
         let old_settings = RepoSettings {
             description: actual_repo.description.clone(),
             homepage: actual_repo.homepage.clone(),
             archived: actual_repo.archived,
             auto_merge_enabled: actual_repo.allow_auto_merge.unwrap_or(false),
+            // All merge methods are allowed by default on GitHub
+            allow_squash_merge: actual_repo.allow_squash_merge.unwrap_or(true),
+            allow_merge_commit: actual_repo.allow_merge_commit.unwrap_or(true),
+            allow_rebase_merge: actual_repo.allow_rebase_merge.unwrap_or(true),
+            delete_branch_on_merge: actual_repo.delete_branch_on_merge.unwrap_or(false),
+            has_issues: actual_repo.has_issues.unwrap_or(true),
+            has_wiki: actual_repo.has_wiki.unwrap_or(true),
+            has_projects: actual_repo.has_projects.unwrap_or(true),
+            has_discussions: actual_repo.has_discussions.unwrap_or(false),
         };
+        let archived = expected_repo.archived || is_archival_due(expected_repo)?;
         let new_settings = RepoSettings {
             description: expected_repo.description.clone(),
             homepage: expected_repo.homepage.clone(),
-            archived: expected_repo.archived,
+            archived,
             auto_merge_enabled: expected_repo.auto_merge_enabled,
+            // Merge methods that are not managed keep their current value
+            allow_squash_merge: expected_repo
+                .allow_squash_merge
+                .unwrap_or(old_settings.allow_squash_merge),
+            allow_merge_commit: expected_repo
+                .allow_merge_commit
+                .unwrap_or(old_settings.allow_merge_commit),
+            allow_rebase_merge: expected_repo
+                .allow_rebase_merge
+                .unwrap_or(old_settings.allow_rebase_merge),
+            delete_branch_on_merge: expected_repo
+                .delete_branch_on_merge
+                .unwrap_or(old_settings.delete_branch_on_merge),
+            has_issues: expected_repo.has_issues.unwrap_or(old_settings.has_issues),
+            has_wiki: expected_repo.has_wiki.unwrap_or(old_settings.has_wiki),
+            has_projects: expected_repo
+                .has_projects
+                .unwrap_or(old_settings.has_projects),
+            has_discussions: expected_repo
+                .has_discussions
+                .unwrap_or(old_settings.has_discussions),
         };
 
         Ok(RepoDiff::Update(UpdateRepoDiff {
-            org: expected_repo.org.clone(),
-            name: actual_repo.name,
+            org: expected_repo.org.as_str().into(),
+            name: actual_repo.name.into(),
             repo_node_id: actual_repo.node_id,
             settings_diff: (old_settings, new_settings),
+            default_branch_diff,
             permission_diffs,
             branch_protection_diffs,
             ruleset_diffs,
             environment_diffs,
+            label_diffs,
+            webhook_diffs,
+            autolink_diffs,
+            deploy_key_diffs,
+            actions_diff,
+        }))
+    }
+
+    async fn diff_actions_permissions(
+        &self,
+        expected_repo: &rust_team_data::v1::Repo,
+    ) -> anyhow::Result<Option<(ActionsPermissions, ActionsPermissions)>> {
+        // Actions permissions are only managed if they are explicitly configured
+        let Some(expected) = &expected_repo.actions else {
+            return Ok(None);
+        };
+        // Nothing to do if GitHub Actions are disabled in the repository
+        let Some(actual) = self
+            .github
+            .repo_actions_permissions(&expected_repo.org, &expected_repo.name)
+            .await?
+        else {
+            return Ok(None);
+        };
+        if &actual == expected {
+            return Ok(None);
+        }
+        Ok(Some((actual, expected.clone())))
+    }
+
+    async fn diff_default_branch(
+        &self,
+        actual_repo: &api::Repo,
+        expected_repo: &rust_team_data::v1::Repo,
+    ) -> anyhow::Result<Option<DefaultBranchDiff>> {
+        // The default branch is only managed if it is explicitly configured
+        let (Some(old), Some(new)) = (&actual_repo.default_branch, &expected_repo.default_branch)
+        else {
+            return Ok(None);
+        };
+        if old == new {
+            return Ok(None);
+        }
+
+        // If the new branch already exists, we simply switch to it. Otherwise, the current
+        // default branch is renamed.
+        let branch_exists = self
+            .github
+            .branch_exists(&expected_repo.org, &expected_repo.name, new)
+            .await?;
+        let (old, new) = (old.clone(), new.clone());
+        Ok(Some(if branch_exists {
+            DefaultBranchDiff::Switch { old, new }
+        } else {
+            DefaultBranchDiff::Rename { old, new }
         }))
     }
 
+    /// Constructs the expected branch protection, including push allowances of GitHub Apps,
+    /// which have to be resolved through the GitHub API.
+    async fn expected_branch_protection(
+        &self,
+        expected_repo: &rust_team_data::v1::Repo,
+        branch_protection: &rust_team_data::v1::BranchProtection,
+    ) -> anyhow::Result<api::BranchProtection> {
+        let mut protection = construct_branch_protection(expected_repo, branch_protection);
+        for slug in &branch_protection.allowed_push_apps {
+            let app = self
+                .github
+                .app(&expected_repo.org, slug)
+                .await?
+                .with_context(|| format!("GitHub App `{slug}` does not exist"))?;
+            protection
+                .push_allowances
+                .push(PushAllowanceActor::App(app));
+        }
+        Ok(protection)
+    }
+
+    /// Ensure that the custom repository roles used by the repo exist in its org
+    async fn check_custom_roles(
+        &self,
+        expected_repo: &rust_team_data::v1::Repo,
+    ) -> anyhow::Result<()> {
+        let used_roles = expected_repo
+            .teams
+            .iter()
+            .map(|t| &t.permission)
+            .chain(expected_repo.members.iter().map(|m| &m.permission))
+            .filter_map(|p| match p {
+                rust_team_data::v1::RepoPermission::Custom(role) => Some(role.as_str()),
+                _ => None,
+            })
+            .collect::<HashSet<_>>();
+        if used_roles.is_empty() {
+            return Ok(());
+        }
+
+        let custom_roles = self.github.custom_repo_roles(&expected_repo.org).await?;
+        for role in used_roles {
+            if !custom_roles.contains(role) {
+                return Err(anyhow::anyhow!(
+                    "Custom repository role `{role}` used by repo `{}/{}` does not exist in the organization",
+                    expected_repo.org,
+                    expected_repo.name
+                ));
+            }
+        }
+        Ok(())
+    }
+
     async fn diff_permissions(
         &self,
         expected_repo: &rust_team_data::v1::Repo,
@@ -499,8 +1130,20 @@ impl SyncGitHub {
             .into_iter()
             .map(|u| (u.name.clone(), u))
             .collect();
+        let pending_invitations: HashMap<_, _> = self
+            .github
+            .repo_invitations(&expected_repo.org, &expected_repo.name)
+            .await?
+            .into_iter()
+            .map(|u| (u.name.clone(), u))
+            .collect();
 
-        calculate_permission_diffs(expected_repo, actual_teams, actual_collaborators)
+        calculate_permission_diffs(
+            expected_repo,
+            actual_teams,
+            actual_collaborators,
+            pending_invitations,
+        )
     }
 
     async fn diff_branch_protections(
@@ -508,41 +1151,58 @@ impl SyncGitHub {
         actual_repo: &api::Repo,
         expected_repo: &rust_team_data::v1::Repo,
     ) -> anyhow::Result<Vec<BranchProtectionDiff>> {
-        // The rust-lang/rust repository uses GitHub apps push allowance actors for its branch
-        // protections, which cannot be read without a PAT.
-        // To avoid errors, we simply return an empty diff here.
-        if !self.github.uses_pat() && actual_repo.org == "rust-lang" && actual_repo.name == "rust" {
-            return Ok(vec![]);
-        }
-
         let mut branch_protection_diffs = Vec::new();
-        let mut actual_protections = self
+        let mut actual_protections = match self
             .github
             .branch_protections(&actual_repo.org, &actual_repo.name)
-            .await?;
+            .await
+        {
+            Ok(protections) => protections,
+            // The GitHub App push allowance actors of branch protections cannot be read without
+            // a PAT. The branch protections of repos allowing apps to push are left as they are,
+            // rather than failing the sync.
+            Err(err)
+                if !self.github.uses_pat()
+                    && err.downcast_ref::<api::GraphForbiddenError>().is_some()
+                    && expected_repo
+                        .branch_protections
+                        .iter()
+                        .any(|protection| !protection.allowed_push_apps.is_empty()) =>
+            {
+                warn!(
+                    "Skipping the branch protections of {}/{}, which cannot be read without a personal access token: {err}",
+                    actual_repo.org, actual_repo.name
+                );
+                return Ok(vec![]);
+            }
+            Err(err) => return Err(err),
+        };
 
         // If rulesets are enabled, delete all existing branch protections
         // to avoid conflicts between branch protections and rulesets
-        if self.should_use_rulesets(expected_repo) {
-            return Ok(actual_protections
+        if expected_repo.use_rulesets {
+            return Ok(BTreeMap::from_iter(actual_protections)
                 .into_iter()
-                .map(|(name, (id, _))| BranchProtectionDiff {
+                .map(|(name, (id, bp))| BranchProtectionDiff {
                     pattern: name,
-                    operation: BranchProtectionDiffOperation::Delete(id),
+                    operation: BranchProtectionDiffOperation::Delete(id, bp),
                 })
                 .collect());
         }
         for branch_protection in &expected_repo.branch_protections {
             let actual_branch_protection = actual_protections.remove(&branch_protection.pattern);
-            let mut expected_branch_protection =
-                construct_branch_protection(expected_repo, branch_protection);
+            let mut expected_branch_protection = self
+                .expected_branch_protection(expected_repo, branch_protection)
+                .await?;
 
-            // We don't model GitHub App push allowance actors in team.
-            // However, we don't want to remove existing accesses of GH apps to
-            // branches.
-            // So if there is an existing branch protection, we copy its GitHub app
+            // GitHub App push allowance actors are only managed by team if the branch
+            // protection declares them in `allowed_push_apps`.
+            // Otherwise, we don't want to remove existing accesses of GH apps to branches,
+            // so if there is an existing branch protection, we copy its GitHub app
             // push allowances into the expected branch protection, to roundtrip the app access.
-            if let Some((_, actual_branch_protection)) = &actual_branch_protection {
+            if let Some((_, actual_branch_protection)) = &actual_branch_protection
+                && branch_protection.allowed_push_apps.is_empty()
+            {
                 expected_branch_protection.push_allowances.extend(
                     actual_branch_protection
                         .push_allowances
@@ -574,16 +1234,46 @@ impl SyncGitHub {
 
         // `actual_branch_protections` now contains the branch protections that were not expected
         // but are still on GitHub. We want to delete them.
-        branch_protection_diffs.extend(actual_protections.into_iter().map(|(name, (id, _))| {
-            BranchProtectionDiff {
+        branch_protection_diffs.extend(BTreeMap::from_iter(actual_protections).into_iter().map(
+            |(name, (id, bp))| BranchProtectionDiff {
                 pattern: name,
-                operation: BranchProtectionDiffOperation::Delete(id),
-            }
-        }));
+                operation: BranchProtectionDiffOperation::Delete(id, bp),
+            },
+        ));
 
         Ok(branch_protection_diffs)
     }
 
+    /// Resolve the reviewer teams of an environment to their slug and ID on GitHub
+    async fn expected_environment(
+        &self,
+        org: &str,
+        env: &rust_team_data::v1::Environment,
+    ) -> anyhow::Result<api::Environment> {
+        let mut reviewers = Vec::new();
+        for name in &env.reviewers {
+            reviewers.push(match self.github.team(org, name).await? {
+                Some(team) => api::EnvironmentReviewer::Team {
+                    slug: team.slug,
+                    id: team.id,
+                },
+                // The team is created by this sync, its ID is resolved when it's written
+                None => api::EnvironmentReviewer::Team {
+                    slug: name.clone(),
+                    id: None,
+                },
+            });
+        }
+        reviewers.sort_by(|a, b| a.key().cmp(&b.key()));
+        Ok(api::Environment {
+            branches: env.branches.clone(),
+            tags: env.tags.clone(),
+            reviewers,
+            wait_timer: env.wait_timer,
+            protected_branches_only: env.protected_branches_only,
+        })
+    }
+
     async fn diff_environments(
         &self,
         expected_repo: &rust_team_data::v1::Repo,
@@ -603,12 +1293,16 @@ impl SyncGitHub {
         // Environments to create (already sorted via BTreeSet)
         for env_name in expected_environments.difference(&actual_environments) {
             let env = expected_repo.environments.get(env_name).unwrap();
-            environment_diffs.push(EnvironmentDiff::Create(env_name.clone(), env.clone()));
+            let env = self.expected_environment(&expected_repo.org, env).await?;
+            environment_diffs.push(EnvironmentDiff::Create(env_name.clone(), env));
         }
 
         // Environments to update (already sorted via BTreeSet)
         for env_name in expected_environments.intersection(&actual_environments) {
             let expected_env = expected_repo.environments.get(env_name).unwrap();
+            let expected_env = self
+                .expected_environment(&expected_repo.org, expected_env)
+                .await?;
             let actual_env = actual_environments_map.get(env_name).unwrap();
 
             let expected_branches: BTreeSet<_> = expected_env.branches.iter().collect();
@@ -637,15 +1331,46 @@ impl SyncGitHub {
                 .map(|s| s.to_string())
                 .collect();
 
+            // Reviewers are compared by their login or slug, as the IDs of teams created by
+            // this sync aren't known yet
+            let expected_reviewers: BTreeSet<_> =
+                expected_env.reviewers.iter().map(|r| r.key()).collect();
+            let actual_reviewers: BTreeSet<_> =
+                actual_env.reviewers.iter().map(|r| r.key()).collect();
+
+            let add_reviewers: Vec<_> = expected_env
+                .reviewers
+                .iter()
+                .filter(|r| !actual_reviewers.contains(&r.key()))
+                .map(|r| r.to_string())
+                .collect();
+
+            let mut remove_reviewers: Vec<_> = actual_env
+                .reviewers
+                .iter()
+                .filter(|r| !expected_reviewers.contains(&r.key()))
+                .map(|r| r.to_string())
+                .collect();
+            remove_reviewers.sort();
+
+            let wait_timer = (actual_env.wait_timer, expected_env.wait_timer);
+            let protected_branches_only = (
+                actual_env.protected_branches_only,
+                expected_env.protected_branches_only,
+            );
+
             if !add_branches.is_empty()
                 || !remove_branches.is_empty()
                 || !add_tags.is_empty()
                 || !remove_tags.is_empty()
+                || !add_reviewers.is_empty()
+                || !remove_reviewers.is_empty()
+                || wait_timer.0 != wait_timer.1
+                || protected_branches_only.0 != protected_branches_only.1
             {
-                let mut new_branches = expected_env.branches.clone();
-                new_branches.sort();
-                let mut new_tags = expected_env.tags.clone();
-                new_tags.sort();
+                let mut new = expected_env;
+                new.branches.sort();
+                new.tags.sort();
 
                 environment_diffs.push(EnvironmentDiff::Update {
                     name: env_name.clone(),
@@ -653,8 +1378,11 @@ impl SyncGitHub {
                     remove_branches,
                     add_tags,
                     remove_tags,
-                    new_branches,
-                    new_tags,
+                    add_reviewers,
+                    remove_reviewers,
+                    wait_timer,
+                    protected_branches_only,
+                    new,
                 });
             }
         }
@@ -667,6 +1395,156 @@ impl SyncGitHub {
         Ok(environment_diffs)
     }
 
+    async fn diff_labels(
+        &self,
+        expected_repo: &rust_team_data::v1::Repo,
+    ) -> anyhow::Result<Vec<LabelDiff>> {
+        // Labels are only provisioned: labels that are not configured in team are left untouched
+        if expected_repo.labels.is_empty() {
+            return Ok(vec![]);
+        }
+
+        // GitHub treats label names case-insensitively
+        let actual_labels: HashMap<String, api::RepoLabel> = self
+            .github
+            .repo_labels(&expected_repo.org, &expected_repo.name)
+            .await?
+            .into_iter()
+            .map(|label| (label.name.to_lowercase(), label))
+            .collect();
+
+        let mut label_diffs = Vec::new();
+        for label in expected_labels(expected_repo) {
+            match actual_labels.get(&label.name.to_lowercase()) {
+                None => label_diffs.push(LabelDiff::Create(label)),
+                Some(actual) if *actual != label => label_diffs.push(LabelDiff::Update {
+                    old: actual.clone(),
+                    new: label,
+                }),
+                Some(_) => {}
+            }
+        }
+        Ok(label_diffs)
+    }
+
+    async fn diff_webhooks(
+        &self,
+        expected_repo: &rust_team_data::v1::Repo,
+    ) -> anyhow::Result<Vec<WebhookDiff>> {
+        // Webhooks are only provisioned: webhooks that are not configured in team are left
+        // untouched
+        if expected_repo.webhooks.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let actual_webhooks = self
+            .github
+            .repo_webhooks(&expected_repo.org, &expected_repo.name)
+            .await?;
+
+        let mut webhook_diffs = Vec::new();
+        for webhook in &expected_repo.webhooks {
+            let mut webhook = webhook.clone();
+            webhook.events.sort();
+            let Some(actual) = actual_webhooks
+                .iter()
+                .find(|actual| actual.config.url.as_ref() == Some(&webhook.url))
+            else {
+                webhook_diffs.push(WebhookDiff::Create(webhook));
+                continue;
+            };
+
+            // The secret of a webhook cannot be read back, so it is only set when the webhook
+            // is created or updated for other reasons.
+            let mut actual = actual.clone();
+            actual.events.sort();
+            if !actual.active
+                || actual.events != webhook.events
+                || actual.config.content_type() != webhook.content_type.as_str()
+            {
+                webhook_diffs.push(WebhookDiff::Update {
+                    old: actual,
+                    new: webhook,
+                });
+            }
+        }
+        Ok(webhook_diffs)
+    }
+
+    async fn diff_autolinks(
+        &self,
+        expected_repo: &rust_team_data::v1::Repo,
+    ) -> anyhow::Result<Vec<AutolinkDiff>> {
+        // Autolinks are only managed for repositories that configure some of them
+        if expected_repo.autolinks.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let actual_autolinks = self
+            .github
+            .repo_autolinks(&expected_repo.org, &expected_repo.name)
+            .await?;
+
+        let mut autolink_diffs = Vec::new();
+        for (prefix, url_template) in &expected_repo.autolinks {
+            match actual_autolinks.iter().find(|a| &a.key_prefix == prefix) {
+                None => autolink_diffs.push(AutolinkDiff::Create {
+                    key_prefix: prefix.clone(),
+                    url_template: url_template.clone(),
+                }),
+                Some(actual) if &actual.url_template != url_template => {
+                    autolink_diffs.push(AutolinkDiff::Update {
+                        id: actual.id,
+                        key_prefix: prefix.clone(),
+                        old_url_template: actual.url_template.clone(),
+                        new_url_template: url_template.clone(),
+                    })
+                }
+                Some(_) => {}
+            }
+        }
+        for actual in actual_autolinks {
+            if !expected_repo.autolinks.contains_key(&actual.key_prefix) {
+                autolink_diffs.push(AutolinkDiff::Delete {
+                    id: actual.id,
+                    key_prefix: actual.key_prefix,
+                    url_template: actual.url_template,
+                });
+            }
+        }
+        Ok(autolink_diffs)
+    }
+
+    async fn diff_deploy_keys(
+        &self,
+        expected_repo: &rust_team_data::v1::Repo,
+    ) -> anyhow::Result<Vec<DeployKeyDiff>> {
+        // Deploy keys are only audited for repositories that have an allowlist
+        let Some(allowed_keys) = &expected_repo.deploy_keys else {
+            return Ok(vec![]);
+        };
+
+        let actual_keys = self
+            .github
+            .repo_deploy_keys(&expected_repo.org, &expected_repo.name)
+            .await?;
+        Ok(actual_keys
+            .into_iter()
+            .filter_map(|key| {
+                let fingerprint = key.fingerprint();
+                let allowed = allowed_keys
+                    .iter()
+                    .any(|allowed| Some(&allowed.fingerprint) == fingerprint.as_ref());
+                (!allowed).then_some(DeployKeyDiff::Delete {
+                    id: key.id,
+                    title: key.title,
+                    fingerprint,
+                    read_only: key.read_only,
+                })
+            })
+            .collect())
+    }
+
     async fn diff_rulesets(
         &self,
         expected_repo: &rust_team_data::v1::Repo,
@@ -697,7 +1575,8 @@ impl SyncGitHub {
 
         // Process each branch protection as a potential ruleset
         for branch_protection in &expected_repo.branch_protections {
-            let expected_ruleset = construct_ruleset(branch_protection);
+            let expected_ruleset =
+                construct_ruleset(branch_protection, self.actions_integration_id())?;
 
             if let Some(actual_ruleset) = rulesets_by_name.remove(&expected_ruleset.name) {
                 let Ruleset {
@@ -760,7 +1639,7 @@ impl SyncGitHub {
         }
 
         // Delete rulesets that have names not matching any expected branch protection
-        for (_, ruleset) in rulesets_by_name {
+        for ruleset in BTreeMap::from_iter(rulesets_by_name).into_values() {
             if let Some(id) = ruleset.id {
                 ruleset_diffs.push(RulesetDiff {
                     name: ruleset.name.clone(),
@@ -772,12 +1651,12 @@ impl SyncGitHub {
         Ok(ruleset_diffs)
     }
 
-    fn expected_role(&self, org: &str, user: u64) -> TeamRole {
-        if let Some(true) = self
+    fn expected_role(&self, github_team: &rust_team_data::v1::GitHubTeam, user: u64) -> TeamRole {
+        let is_owner = self
             .org_owners
-            .get(org)
-            .map(|owners| owners.contains(&user))
-        {
+            .get(github_team.org.as_str())
+            .is_some_and(|owners| owners.contains(&user));
+        if is_owner || github_team.maintainers.contains(&user) {
             TeamRole::Maintainer
         } else {
             TeamRole::Member
@@ -789,13 +1668,14 @@ fn calculate_permission_diffs(
     expected_repo: &rust_team_data::v1::Repo,
     mut actual_teams: HashMap<String, api::RepoTeam>,
     mut actual_collaborators: HashMap<String, api::RepoUser>,
+    pending_invitations: HashMap<String, api::RepoUser>,
 ) -> anyhow::Result<Vec<RepoPermissionAssignmentDiff>> {
     let mut permissions = Vec::new();
     // Team permissions
     for expected_team in &expected_repo.teams {
         let permission = convert_permission(&expected_team.permission);
         let actual_team = actual_teams.remove(&expected_team.name);
-        let collaborator = RepoCollaborator::Team(expected_team.name.clone());
+        let collaborator = RepoCollaborator::Team(TeamName::from(&expected_team.name));
 
         let diff = match actual_team {
             Some(t) if t.permission != permission => RepoPermissionAssignmentDiff {
@@ -818,18 +1698,35 @@ fn calculate_permission_diffs(
         .filter_map(|b| match BotDetails::from(b) {
             BotDetails::User { name, permission } => {
                 actual_teams.remove(name);
-                Some((name, permission))
+                Some((name, permission, &[][..]))
             }
             BotDetails::GitHubApp => None,
         });
     // Member permissions
-    let members = expected_repo
-        .members
-        .iter()
-        .map(|m| (m.name.as_str(), convert_permission(&m.permission)));
-    for (name, permission) in bots.chain(members) {
-        let actual_collaborator = actual_collaborators.remove(name);
-        let collaborator = RepoCollaborator::User(name.to_owned());
+    let members = expected_repo.members.iter().map(|m| {
+        (
+            m.name.as_str(),
+            convert_permission(&m.permission),
+            m.former_names.as_slice(),
+        )
+    });
+    for (mut name, permission, former_names) in bots.chain(members) {
+        let mut actual_collaborator = actual_collaborators.remove(name);
+        // An account renamed in the data but not on GitHub yet is still the same collaborator,
+        // rather than one to remove and a new one to invite
+        if actual_collaborator.is_none()
+            && let Some(former_name) = former_names
+                .iter()
+                .find(|former| actual_collaborators.contains_key(former.as_str()))
+        {
+            warn!(
+                "`{name}` is still `{former_name}` on GitHub in {}/{}",
+                expected_repo.org, expected_repo.name
+            );
+            actual_collaborator = actual_collaborators.remove(former_name.as_str());
+            name = former_name;
+        }
+        let collaborator = RepoCollaborator::User(PersonId::from(name));
         let diff = match actual_collaborator {
             Some(t) if t.permission != permission => RepoPermissionAssignmentDiff {
                 collaborator,
@@ -837,6 +1734,13 @@ fn calculate_permission_diffs(
             },
             // Collaborator permission does not need to change
             Some(_) => continue,
+            // The user was already invited with the expected permission
+            None if pending_invitations
+                .get(name)
+                .is_some_and(|invitation| invitation.permission == permission) =>
+            {
+                continue;
+            }
             None => RepoPermissionAssignmentDiff {
                 collaborator,
                 diff: RepoPermissionDiff::Create(permission),
@@ -846,7 +1750,7 @@ fn calculate_permission_diffs(
     }
     // `actual_teams` now contains the teams that were not expected
     // but are still on GitHub. We now remove them.
-    for (team, t) in actual_teams {
+    for (team, t) in BTreeMap::from_iter(actual_teams) {
         if t.name == "security" && expected_repo.org == "rust-lang" {
             // Skip removing access permissions from security.
             // If we're in this branch we know that the team repo doesn't mention this team at all,
@@ -859,15 +1763,15 @@ fn calculate_permission_diffs(
             continue;
         }
         permissions.push(RepoPermissionAssignmentDiff {
-            collaborator: RepoCollaborator::Team(team),
+            collaborator: RepoCollaborator::Team(team.into()),
             diff: RepoPermissionDiff::Delete(t.permission),
         });
     }
     // `actual_collaborators` now contains the collaborators that were not expected
     // but are still on GitHub. We now remove them.
-    for (collaborator, u) in actual_collaborators {
+    for (collaborator, u) in BTreeMap::from_iter(actual_collaborators) {
         permissions.push(RepoPermissionAssignmentDiff {
-            collaborator: RepoCollaborator::User(collaborator),
+            collaborator: RepoCollaborator::User(collaborator.into()),
             diff: RepoPermissionDiff::Delete(u.permission),
         });
     }
@@ -907,11 +1811,12 @@ impl From<&Bot> for BotDetails {
 
 pub fn convert_permission(p: &rust_team_data::v1::RepoPermission) -> RepoPermission {
     use rust_team_data::v1;
-    match *p {
+    match p {
         v1::RepoPermission::Write => RepoPermission::Write,
         v1::RepoPermission::Admin => RepoPermission::Admin,
         v1::RepoPermission::Maintain => RepoPermission::Maintain,
         v1::RepoPermission::Triage => RepoPermission::Triage,
+        v1::RepoPermission::Custom(role) => RepoPermission::Custom(role.clone()),
     }
 }
 
@@ -930,35 +1835,22 @@ fn get_branch_protection_mode(
     }
 }
 
-pub fn construct_branch_protection(
-    expected_repo: &rust_team_data::v1::Repo,
-    branch_protection: &rust_team_data::v1::BranchProtection,
-) -> api::BranchProtection {
-    let branch_protection_mode = get_branch_protection_mode(branch_protection);
-
-    let required_approving_review_count: u8 = match branch_protection_mode {
-        BranchProtectionMode::PrRequired {
-            required_approvals, ..
-        } => required_approvals
-            .try_into()
-            .expect("Too large required approval count"),
-        BranchProtectionMode::PrNotRequired => 0,
-    };
-    let mut push_allowances: Vec<PushAllowanceActor> = branch_protection
-        .allowed_merge_teams
+/// Construct the branch protection allowance actors of the given teams and bots.
+fn allowance_actors(org: &str, teams: &[String], bots: &[MergeBot]) -> Vec<PushAllowanceActor> {
+    let mut actors: Vec<PushAllowanceActor> = teams
         .iter()
         .map(|team| {
             api::PushAllowanceActor::Team(api::TeamPushAllowanceActor {
                 organization: Login {
-                    login: expected_repo.org.clone(),
+                    login: org.to_string(),
                 },
                 name: team.to_string(),
             })
         })
         .collect();
 
-    for merge_bot in &branch_protection.allowed_merge_apps {
-        let allowance = match merge_bot {
+    for bot in bots {
+        let actor = match bot {
             MergeBot::Homu => PushAllowanceActor::User(api::UserPushAllowanceActor {
                 login: "bors".to_owned(),
             }),
@@ -967,12 +1859,77 @@ pub fn construct_branch_protection(
             }),
             MergeBot::Bors | MergeBot::WorkflowsCratesIo | MergeBot::PromoteRelease => {
                 // These use GitHub apps, which are not configured through team (set manually).
-                // Their push allowance will be roundtripped by sync-team.
+                // Their push allowance will be roundtripped by sync-team, and validation
+                // rejects them as pull request bypass actors.
                 continue;
             }
         };
-        push_allowances.push(allowance);
+        actors.push(actor);
     }
+    actors
+}
+
+fn expected_description(github_team: &rust_team_data::v1::GitHubTeam) -> &str {
+    github_team
+        .description
+        .as_deref()
+        .unwrap_or(DEFAULT_DESCRIPTION)
+}
+
+fn expected_privacy(github_team: &rust_team_data::v1::GitHubTeam) -> TeamPrivacy {
+    match github_team.privacy {
+        Some(rust_team_data::v1::GitHubTeamPrivacy::Closed) => TeamPrivacy::Closed,
+        Some(rust_team_data::v1::GitHubTeamPrivacy::Secret) => TeamPrivacy::Secret,
+        None => DEFAULT_PRIVACY,
+    }
+}
+
+fn expected_labels(expected_repo: &rust_team_data::v1::Repo) -> Vec<api::RepoLabel> {
+    expected_repo
+        .labels
+        .iter()
+        .map(|label| api::RepoLabel {
+            name: label.name.clone(),
+            color: label.color.clone(),
+            description: label.description.clone(),
+        })
+        .collect()
+}
+
+pub fn construct_branch_protection(
+    expected_repo: &rust_team_data::v1::Repo,
+    branch_protection: &rust_team_data::v1::BranchProtection,
+) -> api::BranchProtection {
+    let branch_protection_mode = get_branch_protection_mode(branch_protection);
+
+    let (required_approving_review_count, requires_code_owner_reviews): (u8, bool) =
+        match branch_protection_mode {
+            BranchProtectionMode::PrRequired {
+                required_approvals,
+                requires_code_owner_reviews,
+                ..
+            } => (
+                required_approvals
+                    .try_into()
+                    .expect("Too large required approval count"),
+                requires_code_owner_reviews,
+            ),
+            BranchProtectionMode::PrNotRequired => (0, false),
+        };
+    let push_allowances = allowance_actors(
+        &expected_repo.org,
+        &branch_protection.allowed_merge_teams,
+        &branch_protection.allowed_merge_apps,
+    );
+    // Bypassing the pull request requirement only makes sense if a PR is required
+    let bypass_pull_request_allowances = match branch_protection_mode {
+        BranchProtectionMode::PrRequired { .. } => allowance_actors(
+            &expected_repo.org,
+            &branch_protection.pr_bypass_teams,
+            &branch_protection.pr_bypass_apps,
+        ),
+        BranchProtectionMode::PrNotRequired => vec![],
+    };
 
     let mut checks = match &branch_protection_mode {
         BranchProtectionMode::PrRequired { ci_checks, .. } => ci_checks.clone(),
@@ -991,10 +1948,15 @@ pub fn construct_branch_protection(
         required_approving_review_count,
         required_status_check_contexts: checks,
         push_allowances,
+        bypass_pull_request_allowances,
         requires_approving_reviews: matches!(
             branch_protection_mode,
             BranchProtectionMode::PrRequired { .. }
         ),
+        requires_code_owner_reviews,
+        requires_commit_signatures: branch_protection.requires_signed_commits,
+        requires_linear_history: branch_protection.required_linear_history,
+        requires_conversation_resolution: branch_protection.requires_conversation_resolution,
     }
 }
 
@@ -1011,7 +1973,10 @@ pub(crate) fn convert_pattern_to_ref_pattern(target: ProtectionTarget, pattern:
     }
 }
 
-pub fn construct_ruleset(branch_protection: &rust_team_data::v1::BranchProtection) -> api::Ruleset {
+pub fn construct_ruleset(
+    branch_protection: &rust_team_data::v1::BranchProtection,
+    actions_integration_id: i64,
+) -> anyhow::Result<api::Ruleset> {
     use api::*;
 
     let branch_protection_mode = get_branch_protection_mode(branch_protection);
@@ -1039,18 +2004,29 @@ pub fn construct_ruleset(branch_protection: &rust_team_data::v1::BranchProtectio
         rules.insert(RulesetRule::NonFastForward);
     }
 
+    if branch_protection.requires_signed_commits {
+        rules.insert(RulesetRule::RequiredSignatures);
+    }
+
+    if branch_protection.required_linear_history {
+        rules.insert(RulesetRule::RequiredLinearHistory);
+    }
+
     // Add pull request rule if PRs are required
     if let BranchProtectionMode::PrRequired {
-        required_approvals, ..
+        required_approvals,
+        requires_code_owner_reviews,
+        ..
     } = &branch_protection_mode
     {
         rules.insert(RulesetRule::PullRequest {
             parameters: PullRequestParameters {
                 dismiss_stale_reviews_on_push: branch_protection.dismiss_stale_review,
-                require_code_owner_review: REQUIRE_CODE_OWNER_REVIEW_DEFAULT,
+                require_code_owner_review: *requires_code_owner_reviews,
                 require_last_push_approval: REQUIRE_LAST_PUSH_APPROVAL_DEFAULT,
                 required_approving_review_count: *required_approvals as i32,
-                required_review_thread_resolution: REQUIRED_REVIEW_THREAD_RESOLUTION_DEFAULT,
+                required_review_thread_resolution: branch_protection
+                    .requires_conversation_resolution,
             },
         });
     }
@@ -1068,7 +2044,7 @@ pub fn construct_ruleset(branch_protection: &rust_team_data::v1::BranchProtectio
                     .iter()
                     .map(|context| RequiredStatusCheck {
                         context: context.clone(),
-                        integration_id: Some(GITHUB_ACTIONS_INTEGRATION_ID),
+                        integration_id: Some(actions_integration_id),
                     })
                     .collect(),
                 strict_required_status_checks_policy: STRICT_REQUIRED_STATUS_CHECKS_POLICY_DEFAULT,
@@ -1077,10 +2053,33 @@ pub fn construct_ruleset(branch_protection: &rust_team_data::v1::BranchProtectio
     }
 
     if branch_protection.merge_queue {
-        // Enable merge queue with default settings
-        rules.insert(RulesetRule::MergeQueue {
-            parameters: MergeQueueParameters::default(),
-        });
+        let mut parameters = MergeQueueParameters::default();
+        if let Some(settings) = &branch_protection.merge_queue_settings {
+            parameters.merge_method = match settings.merge_method {
+                rust_team_data::v1::MergeQueueMethod::Merge => MergeQueueMergeMethod::Merge,
+                rust_team_data::v1::MergeQueueMethod::Squash => MergeQueueMergeMethod::Squash,
+                rust_team_data::v1::MergeQueueMethod::Rebase => MergeQueueMergeMethod::Rebase,
+            };
+            if let Some(min_entries_to_merge) = settings.min_entries_to_merge {
+                parameters.min_entries_to_merge = i32::try_from(min_entries_to_merge)
+                    .with_context(|| {
+                        format!("`min-entries-to-merge` is too large: {min_entries_to_merge}")
+                    })?;
+            }
+            if let Some(max_entries_to_merge) = settings.max_entries_to_merge {
+                parameters.max_entries_to_merge = i32::try_from(max_entries_to_merge)
+                    .with_context(|| {
+                        format!("`max-entries-to-merge` is too large: {max_entries_to_merge}")
+                    })?;
+            }
+            if let Some(max_entries_to_build) = settings.max_entries_to_build {
+                parameters.max_entries_to_build = i32::try_from(max_entries_to_build)
+                    .with_context(|| {
+                        format!("`max-entries-to-build` is too large: {max_entries_to_build}")
+                    })?;
+            }
+        }
+        rules.insert(RulesetRule::MergeQueue { parameters });
     }
 
     // Build bypass actors from allowed merge apps
@@ -1096,7 +2095,7 @@ pub fn construct_ruleset(branch_protection: &rust_team_data::v1::BranchProtectio
         })
         .collect();
 
-    api::Ruleset {
+    Ok(api::Ruleset {
         id: None,
         name: branch_protection
             .name
@@ -1119,44 +2118,314 @@ pub fn construct_ruleset(branch_protection: &rust_team_data::v1::BranchProtectio
             },
         },
         rules,
-    }
+    })
 }
 
-/// The special bot teams
-const BOTS_TEAMS: &[&str] = &["bors", "highfive", "rfcbot", "bots"];
-
 /// A diff between the team repo and the state on GitHub
+#[derive(serde::Serialize)]
 pub(crate) struct Diff {
+    org_diffs: Vec<OrgDiff>,
     team_diffs: Vec<TeamDiff>,
     repo_diffs: Vec<RepoDiff>,
     org_membership_diffs: Vec<OrgMembershipDiff>,
+    org_owner_diffs: Vec<OrgOwnersDiff>,
 }
 
-impl Diff {
-    /// Apply the diff to GitHub
-    pub(crate) async fn apply(self, sync: &GitHubWrite) -> anyhow::Result<()> {
-        for team_diff in self.team_diffs {
-            team_diff.apply(sync).await?;
+/// How the diff is applied to GitHub
+pub(crate) struct ApplyOptions {
+    /// Maximum number of deletions of each kind allowed in the diff, or `None` if the
+    /// limit is disabled with `--force`
+    deletion_limit: Option<usize>,
+    /// Ask for a confirmation before applying each change
+    confirmation: Option<Confirmation>,
+    /// Keep applying the remaining changes when one of them fails
+    continue_on_error: bool,
+    /// Record every applied change
+    audit_log: Option<AuditLog>,
+    /// Maximum number of changes applied concurrently
+    concurrency: usize,
+}
+
+impl ApplyOptions {
+    pub(crate) fn from_config(config: &Config) -> anyhow::Result<Self> {
+        Ok(Self {
+            deletion_limit: config.deletion_limit(),
+            confirmation: config.interactive.then(Confirmation::stdio),
+            continue_on_error: config.continue_on_error,
+            audit_log: config
+                .github_audit_log
+                .as_deref()
+                .map(AuditLog::open)
+                .transpose()?,
+            concurrency: config
+                .github_concurrency
+                .unwrap_or(DEFAULT_CONCURRENCY)
+                .max(1),
+        })
+    }
+
+    /// Confirm, apply and audit independent changes concurrently, recording their outcome
+    /// in the report.
+    async fn apply_concurrently<C: Change>(
+        &mut self,
+        changes: Vec<C>,
+        sync: &GitHubWrite,
+        report: &mut ApplyReport,
+    ) -> anyhow::Result<()> {
+        // Prompts can't be interleaved, so all the changes are confirmed before applying them
+        let mut prepared = Vec::new();
+        for change in changes {
+            if let Some(confirmation) = &mut self.confirmation
+                && !confirmation.confirm(&change)?
+            {
+                continue;
+            }
+            let audited = match &mut self.audit_log {
+                Some(audit_log) => {
+                    let actor = audit_log.actor(sync, change.target().0).await?;
+                    Some((actor, AuditedChange::new(&change)?))
+                }
+                None => None,
+            };
+            let mut removals = DestructiveChanges::default();
+            change.removals(&mut removals);
+            prepared.push((change.to_string(), audited, removals, change));
         }
-        for repo_diff in self.repo_diffs {
-            repo_diff.apply(sync).await?;
+
+        // Once a change fails, the ones that were not started yet are skipped, while the
+        // ones in flight are still awaited and recorded
+        let failed = Cell::new(false);
+        let continue_on_error = self.continue_on_error;
+        let mut outcomes = futures_util::stream::iter(prepared)
+            .map(|(description, audited, removals, change)| {
+                let failed = &failed;
+                async move {
+                    if failed.get() {
+                        return None;
+                    }
+                    let result = change.apply_change(sync).await;
+                    if result.is_err() && !continue_on_error {
+                        failed.set(true);
+                    }
+                    Some((description, audited, removals, result))
+                }
+            })
+            .buffer_unordered(self.concurrency);
+        let mut first_error = None;
+        while let Some(outcome) = outcomes.next().await {
+            let Some((description, audited, removals, result)) = outcome else {
+                continue;
+            };
+            if result.is_ok() {
+                report.removed.extend(removals);
+            }
+            if let (Some(audit_log), Some((actor, audited))) = (&mut self.audit_log, audited) {
+                audit_log.record(&actor, &audited, &result)?;
+            }
+            if let Err(err) = report.record(description, result, continue_on_error) {
+                first_error.get_or_insert(err);
+            }
+        }
+        match first_error {
+            Some(err) => Err(err),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Split the sorted team diffs into batches that can be applied concurrently, so that teams
+/// are only nested under parents created or renamed by an earlier batch.
+fn team_diff_batches(diffs: Vec<TeamDiff>) -> Vec<Vec<TeamDiff>> {
+    let mut batches: Vec<Vec<TeamDiff>> = Vec::new();
+    // Teams that only exist under their name once the current batch is applied
+    let mut introduced: HashSet<(OrgName, TeamName)> = HashSet::new();
+    for diff in diffs {
+        let (parent, name) = match &diff {
+            TeamDiff::Create(c) => (
+                c.parent
+                    .as_ref()
+                    .map(|p| (c.org.clone(), TeamName::from(&p.name))),
+                Some((c.org.clone(), c.name.clone())),
+            ),
+            TeamDiff::Edit(e) => (
+                e.parent_diff
+                    .as_ref()
+                    .and_then(|(_, new)| new.as_ref())
+                    .map(|p| (e.org.clone(), TeamName::from(&p.name))),
+                e.name_diff.as_ref().map(|n| (e.org.clone(), n.clone())),
+            ),
+            TeamDiff::Delete(_) => (None, None),
+        };
+        if batches.is_empty() || parent.is_some_and(|parent| introduced.contains(&parent)) {
+            batches.push(Vec::new());
+            introduced.clear();
+        }
+        introduced.extend(name);
+        batches.last_mut().unwrap().push(diff);
+    }
+    batches
+}
+
+/// A change of the diff that is confirmed, applied and audited on its own
+trait Change: Display + serde::Serialize {
+    /// The organization, resource and operation of the change
+    fn target(&self) -> (&str, String, &'static str);
+
+    /// Operations that undo the change
+    fn inverse(&self) -> Vec<InverseOperation>;
+
+    /// Record the things that the change removes from GitHub
+    fn removals(&self, _changes: &mut DestructiveChanges) {}
+
+    async fn apply_change(self, sync: &GitHubWrite) -> anyhow::Result<()>;
+}
+
+impl Change for OrgDiff {
+    fn inverse(&self) -> Vec<InverseOperation> {
+        OrgDiff::inverse(self)
+    }
+
+    fn target(&self) -> (&str, String, &'static str) {
+        (&self.org, "organization".to_string(), "edit_settings")
+    }
+
+    async fn apply_change(self, sync: &GitHubWrite) -> anyhow::Result<()> {
+        self.apply(sync).await
+    }
+}
+
+impl Change for TeamDiff {
+    fn inverse(&self) -> Vec<InverseOperation> {
+        TeamDiff::inverse(self)
+    }
+
+    fn removals(&self, changes: &mut DestructiveChanges) {
+        TeamDiff::removals(self, changes)
+    }
+
+    fn target(&self) -> (&str, String, &'static str) {
+        let (org, name, operation) = match self {
+            TeamDiff::Create(c) => (&c.org, &c.name, "create"),
+            TeamDiff::Edit(e) => (&e.org, &e.name, "edit"),
+            TeamDiff::Delete(d) => (&d.org, &d.name, "delete"),
+        };
+        (org, format!("team/{name}"), operation)
+    }
+
+    async fn apply_change(self, sync: &GitHubWrite) -> anyhow::Result<()> {
+        self.apply(sync).await
+    }
+}
+
+impl Change for RepoDiff {
+    fn inverse(&self) -> Vec<InverseOperation> {
+        RepoDiff::inverse(self)
+    }
+
+    fn removals(&self, changes: &mut DestructiveChanges) {
+        RepoDiff::removals(self, changes)
+    }
+
+    fn target(&self) -> (&str, String, &'static str) {
+        let (org, name, operation) = match self {
+            RepoDiff::Create(c) => (&c.org, &c.name, "create"),
+            RepoDiff::Update(u) => (&u.org, &u.name, "update"),
+        };
+        (org, format!("repo/{name}"), operation)
+    }
+
+    async fn apply_change(self, sync: &GitHubWrite) -> anyhow::Result<()> {
+        self.apply(sync).await
+    }
+}
+
+impl Change for OrgMembershipDiff {
+    fn inverse(&self) -> Vec<InverseOperation> {
+        OrgMembershipDiff::inverse(self)
+    }
+
+    fn removals(&self, changes: &mut DestructiveChanges) {
+        OrgMembershipDiff::removals(self, changes)
+    }
+
+    fn target(&self) -> (&str, String, &'static str) {
+        (&self.org, "organization".to_string(), "remove_members")
+    }
+
+    async fn apply_change(self, sync: &GitHubWrite) -> anyhow::Result<()> {
+        self.apply(sync).await
+    }
+}
+
+impl Change for OrgOwnersDiff {
+    fn inverse(&self) -> Vec<InverseOperation> {
+        OrgOwnersDiff::inverse(self)
+    }
+
+    fn target(&self) -> (&str, String, &'static str) {
+        (&self.org, "organization".to_string(), "edit_owners")
+    }
+
+    async fn apply_change(self, sync: &GitHubWrite) -> anyhow::Result<()> {
+        self.apply(sync).await
+    }
+}
+
+impl Diff {
+    /// Apply the diff to GitHub, recording the outcome of the changes in the report.
+    ///
+    /// Unless `continue_on_error` is set, the first failed change stops the remaining ones.
+    pub(crate) async fn apply(
+        self,
+        sync: &GitHubWrite,
+        mut options: ApplyOptions,
+        report: &mut ApplyReport,
+    ) -> anyhow::Result<()> {
+        if let Some(limit) = options.deletion_limit {
+            self.check_deletion_limit(limit)?;
         }
-        for org_diff in self.org_membership_diffs {
-            org_diff.apply(sync).await?;
+        options
+            .apply_concurrently(self.org_diffs, sync, report)
+            .await?;
+        // Teams have to exist before repositories can grant them permissions
+        for batch in team_diff_batches(self.team_diffs) {
+            options.apply_concurrently(batch, sync, report).await?;
         }
+        options
+            .apply_concurrently(self.repo_diffs, sync, report)
+            .await?;
+        options
+            .apply_concurrently(self.org_membership_diffs, sync, report)
+            .await?;
+        options
+            .apply_concurrently(self.org_owner_diffs, sync, report)
+            .await?;
 
         Ok(())
     }
 
     pub(crate) fn is_empty(&self) -> bool {
-        self.team_diffs.is_empty()
+        self.org_diffs.is_empty()
+            && self.team_diffs.is_empty()
             && self.repo_diffs.is_empty()
             && self.org_membership_diffs.is_empty()
+            && self.org_owner_diffs.is_empty()
     }
 }
 
 impl std::fmt::Display for Diff {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if !self.is_empty() {
+            writeln!(f, "📊 Summary: {}", self.summary())?;
+        }
+
+        if !self.org_diffs.is_empty() {
+            writeln!(f, "💻 Org Diffs:")?;
+            for org_diff in &self.org_diffs {
+                write!(f, "{org_diff}")?;
+            }
+        }
+
         if !self.team_diffs.is_empty() {
             writeln!(f, "💻 Team Diffs:")?;
             for team_diff in &self.team_diffs {
@@ -1178,11 +2447,19 @@ impl std::fmt::Display for Diff {
             }
         }
 
+        if !&self.org_owner_diffs.is_empty() {
+            writeln!(f, "💻 Org owner Diffs:")?;
+            for org_owner_diff in &self.org_owner_diffs {
+                write!(f, "{org_owner_diff}")?;
+            }
+        }
+
         Ok(())
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
 enum RepoDiff {
     Create(CreateRepoDiff),
     Update(UpdateRepoDiff),
@@ -1213,10 +2490,10 @@ impl std::fmt::Display for RepoDiff {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, serde::Serialize)]
 struct OrgMembershipDiff {
     org: OrgName,
-    members_to_remove: Vec<String>,
+    members_to_remove: Vec<PersonId>,
 }
 
 impl OrgMembershipDiff {
@@ -1241,23 +2518,143 @@ impl std::fmt::Display for OrgMembershipDiff {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, serde::Serialize)]
+struct OrgDiff {
+    org: OrgName,
+    // old, new
+    settings_diff: (api::OrgSettings, api::OrgSettings),
+}
+
+impl OrgDiff {
+    async fn apply(self, sync: &GitHubWrite) -> anyhow::Result<()> {
+        sync.edit_org(&self.org, &self.settings_diff.1).await
+    }
+}
+
+impl std::fmt::Display for OrgDiff {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (old, new) = &self.settings_diff;
+        writeln!(f, "📝 Editing org '{}':", self.org)?;
+        log_field(
+            "Default Repository Permission",
+            &old.default_repository_permission,
+            Some(&new.default_repository_permission),
+            f,
+        )?;
+        log_field(
+            "Members Can Create Repositories",
+            &old.members_can_create_repositories,
+            Some(&new.members_can_create_repositories),
+            f,
+        )?;
+        log_field(
+            "Members Can Fork Private Repositories",
+            &old.members_can_fork_private_repositories,
+            Some(&new.members_can_fork_private_repositories),
+            f,
+        )
+    }
+}
+
+#[derive(Debug, serde::Serialize)]
+struct OrgOwnersDiff {
+    org: OrgName,
+    owners_to_add: Vec<PersonId>,
+    owners_to_remove: Vec<PersonId>,
+    /// Demoting owners is only performed when it was explicitly allowed
+    demotions_allowed: bool,
+}
+
+impl OrgOwnersDiff {
+    async fn apply(self, sync: &GitHubWrite) -> anyhow::Result<()> {
+        for owner in &self.owners_to_add {
+            sync.set_org_owner(&self.org, owner, true).await?;
+        }
+
+        if !self.owners_to_remove.is_empty() && !self.demotions_allowed {
+            log::warn!(
+                "not demoting {} owner(s) of `{}`, pass --allow-owner-demotions to do so",
+                self.owners_to_remove.len(),
+                self.org
+            );
+            return Ok(());
+        }
+        for owner in &self.owners_to_remove {
+            sync.set_org_owner(&self.org, owner, false).await?;
+        }
+
+        Ok(())
+    }
+}
+
+impl std::fmt::Display for OrgOwnersDiff {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if !self.owners_to_add.is_empty() {
+            writeln!(
+                f,
+                "➕ Promoting the following members to owners of `{}`:",
+                self.org
+            )?;
+            for owner in &self.owners_to_add {
+                writeln!(f, "  - {owner}")?;
+            }
+        }
+        if !self.owners_to_remove.is_empty() {
+            if self.demotions_allowed {
+                writeln!(f, "❌ Demoting the following owners of `{}`:", self.org)?;
+            } else {
+                writeln!(
+                    f,
+                    "❌ Demoting the following owners of `{}` (skipped without --allow-owner-demotions):",
+                    self.org
+                )?;
+            }
+            for owner in &self.owners_to_remove {
+                writeln!(f, "  - {owner}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, serde::Serialize)]
 struct CreateRepoDiff {
-    org: String,
-    name: String,
+    org: OrgName,
+    name: RepoName,
+    default_branch: Option<String>,
     settings: RepoSettings,
     permissions: Vec<RepoPermissionAssignmentDiff>,
     branch_protections: Vec<(String, api::BranchProtection)>,
     rulesets: Vec<api::Ruleset>,
-    environments: Vec<(String, rust_team_data::v1::Environment)>,
+    environments: Vec<(String, api::Environment)>,
+    labels: Vec<api::RepoLabel>,
+    webhooks: Vec<rust_team_data::v1::Webhook>,
+    // key prefix, URL template
+    autolinks: Vec<(String, String)>,
+    actions: Option<ActionsPermissions>,
+    template: Option<String>,
 }
 
 impl CreateRepoDiff {
     async fn apply(&self, sync: &GitHubWrite) -> anyhow::Result<()> {
         let repo = sync
-            .create_repo(&self.org, &self.name, &self.settings)
+            .create_repo(
+                &self.org,
+                &self.name,
+                self.template.as_deref(),
+                &self.settings,
+            )
             .await?;
 
+        // The repository is initialized with the org's default branch name
+        if let (Some(created_branch), Some(default_branch)) =
+            (&repo.default_branch, &self.default_branch)
+            && created_branch != default_branch
+        {
+            sync.rename_branch(&self.org, &self.name, created_branch, default_branch)
+                .await?;
+        }
+
         for permission in &self.permissions {
             permission.apply(sync, &self.org, &self.name).await?;
         }
@@ -1282,8 +2679,26 @@ impl CreateRepoDiff {
             .await?;
         }
 
-        for (env_name, env) in &self.environments {
-            sync.create_environment(&self.org, &self.name, env_name, &env.branches, &env.tags)
+        for (env_name, env) in &self.environments {
+            sync.create_environment(&self.org, &self.name, env_name, env)
+                .await?;
+        }
+
+        for label in &self.labels {
+            sync.create_label(&self.org, &self.name, label).await?;
+        }
+
+        for webhook in &self.webhooks {
+            sync.create_webhook(&self.org, &self.name, webhook).await?;
+        }
+
+        for (key_prefix, url_template) in &self.autolinks {
+            sync.create_autolink(&self.org, &self.name, key_prefix, url_template)
+                .await?;
+        }
+
+        if let Some(actions) = &self.actions {
+            sync.set_actions_permissions(&self.org, &self.name, actions)
                 .await?;
         }
 
@@ -1296,11 +2711,17 @@ impl std::fmt::Display for CreateRepoDiff {
         let CreateRepoDiff {
             org,
             name,
+            default_branch,
             settings,
             permissions,
             branch_protections,
             rulesets,
             environments,
+            labels,
+            webhooks,
+            autolinks,
+            actions,
+            template,
         } = self;
 
         let RepoSettings {
@@ -1308,6 +2729,14 @@ impl std::fmt::Display for CreateRepoDiff {
             homepage,
             archived: _,
             auto_merge_enabled,
+            allow_squash_merge,
+            allow_merge_commit,
+            allow_rebase_merge,
+            delete_branch_on_merge,
+            has_issues,
+            has_wiki,
+            has_projects,
+            has_discussions,
         } = &settings;
 
         writeln!(f, "➕ Creating repo:")?;
@@ -1315,7 +2744,21 @@ impl std::fmt::Display for CreateRepoDiff {
         writeln!(f, "  Name: {name}")?;
         writeln!(f, "  Description: {description}")?;
         writeln!(f, "  Homepage: {homepage:?}")?;
+        if let Some(template) = template {
+            writeln!(f, "  Template: {template}")?;
+        }
+        if let Some(default_branch) = default_branch {
+            writeln!(f, "  Default branch: {default_branch}")?;
+        }
         writeln!(f, "  Auto-merge: {auto_merge_enabled}")?;
+        writeln!(f, "  Allow squash merge: {allow_squash_merge}")?;
+        writeln!(f, "  Allow merge commit: {allow_merge_commit}")?;
+        writeln!(f, "  Allow rebase merge: {allow_rebase_merge}")?;
+        writeln!(f, "  Delete branch on merge: {delete_branch_on_merge}")?;
+        writeln!(f, "  Issues: {has_issues}")?;
+        writeln!(f, "  Wiki: {has_wiki}")?;
+        writeln!(f, "  Projects: {has_projects}")?;
+        writeln!(f, "  Discussions: {has_discussions}")?;
         writeln!(f, "  Permissions:")?;
         for diff in permissions {
             write!(f, "{diff}")?;
@@ -1347,40 +2790,368 @@ impl std::fmt::Display for CreateRepoDiff {
                 if !env.tags.is_empty() {
                     writeln!(f, "        Tags: {}", env.tags.join(", "))?;
                 }
+                log_environment_protection(env, &mut f)?;
+            }
+        }
+
+        if !labels.is_empty() {
+            writeln!(f, "  Labels:")?;
+            for label in labels {
+                writeln!(f, "    - {}", LabelDisplay(label))?;
+            }
+        }
+
+        if !webhooks.is_empty() {
+            writeln!(f, "  Webhooks:")?;
+            for webhook in webhooks {
+                writeln!(f, "    - {}", WebhookDisplay(webhook))?;
+            }
+        }
+
+        if !autolinks.is_empty() {
+            writeln!(f, "  Autolinks:")?;
+            for (key_prefix, url_template) in autolinks {
+                writeln!(f, "    - {key_prefix}: {url_template}")?;
             }
         }
+
+        if let Some(actions) = actions {
+            writeln!(f, "  Actions Permissions:")?;
+            log_actions_permissions(actions, None, &mut f)?;
+        }
         Ok(())
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, serde::Serialize)]
 struct UpdateRepoDiff {
-    org: String,
-    name: String,
+    org: OrgName,
+    name: RepoName,
     repo_node_id: String,
     // old, new
     settings_diff: (RepoSettings, RepoSettings),
+    default_branch_diff: Option<DefaultBranchDiff>,
     permission_diffs: Vec<RepoPermissionAssignmentDiff>,
     branch_protection_diffs: Vec<BranchProtectionDiff>,
     ruleset_diffs: Vec<RulesetDiff>,
     environment_diffs: Vec<EnvironmentDiff>,
+    label_diffs: Vec<LabelDiff>,
+    webhook_diffs: Vec<WebhookDiff>,
+    autolink_diffs: Vec<AutolinkDiff>,
+    deploy_key_diffs: Vec<DeployKeyDiff>,
+    // old, new
+    actions_diff: Option<(ActionsPermissions, ActionsPermissions)>,
+}
+
+#[derive(Debug, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+enum DefaultBranchDiff {
+    /// Rename the current default branch
+    Rename { old: String, new: String },
+    /// Make an already existing branch the default branch
+    Switch { old: String, new: String },
+}
+
+impl DefaultBranchDiff {
+    async fn apply(&self, sync: &GitHubWrite, org: &str, repo_name: &str) -> anyhow::Result<()> {
+        match self {
+            DefaultBranchDiff::Rename { old, new } => {
+                sync.rename_branch(org, repo_name, old, new).await
+            }
+            DefaultBranchDiff::Switch { new, .. } => {
+                sync.set_default_branch(org, repo_name, new).await
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for DefaultBranchDiff {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DefaultBranchDiff::Rename { old, new } => {
+                writeln!(f, "  Rename default branch: '{old}' => '{new}'")
+            }
+            DefaultBranchDiff::Switch { old, new } => {
+                writeln!(f, "  Switch default branch: '{old}' => '{new}'")
+            }
+        }
+    }
 }
 
-#[derive(Debug)]
+#[derive(Debug, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
 enum EnvironmentDiff {
-    Create(String, rust_team_data::v1::Environment),
+    Create(String, api::Environment),
     Update {
         name: String,
         add_branches: Vec<String>,
         remove_branches: Vec<String>,
         add_tags: Vec<String>,
         remove_tags: Vec<String>,
-        new_branches: Vec<String>,
-        new_tags: Vec<String>,
+        add_reviewers: Vec<String>,
+        remove_reviewers: Vec<String>,
+        /// (old, new) wait timer in minutes
+        wait_timer: (u32, u32),
+        /// (old, new) protected branches only flag
+        protected_branches_only: (bool, bool),
+        new: api::Environment,
     },
     Delete(String),
 }
 
+#[derive(Debug, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+enum LabelDiff {
+    Create(api::RepoLabel),
+    Update {
+        old: api::RepoLabel,
+        new: api::RepoLabel,
+    },
+}
+
+impl LabelDiff {
+    async fn apply(&self, sync: &GitHubWrite, org: &str, repo_name: &str) -> anyhow::Result<()> {
+        match self {
+            LabelDiff::Create(label) => sync.create_label(org, repo_name, label).await,
+            LabelDiff::Update { old, new } => {
+                sync.update_label(org, repo_name, &old.name, new).await
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for LabelDiff {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LabelDiff::Create(label) => writeln!(f, "    ➕ Create: {}", LabelDisplay(label)),
+            LabelDiff::Update { old, new } => {
+                writeln!(f, "    🔄 Update: {}", old.name)?;
+                if old.name != new.name {
+                    writeln!(f, "        Name: '{}' => '{}'", old.name, new.name)?;
+                }
+                if old.color != new.color {
+                    writeln!(f, "        Color: #{} => #{}", old.color, new.color)?;
+                }
+                if old.description != new.description {
+                    writeln!(
+                        f,
+                        "        Description: '{}' => '{}'",
+                        old.description, new.description
+                    )?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+struct LabelDisplay<'a>(&'a api::RepoLabel);
+
+impl std::fmt::Display for LabelDisplay<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let api::RepoLabel {
+            name,
+            color,
+            description,
+        } = self.0;
+        write!(f, "{name} (#{color})")?;
+        if !description.is_empty() {
+            write!(f, ": {description}")?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+enum WebhookDiff {
+    Create(rust_team_data::v1::Webhook),
+    Update {
+        old: api::RepoWebhook,
+        new: rust_team_data::v1::Webhook,
+    },
+}
+
+impl WebhookDiff {
+    async fn apply(&self, sync: &GitHubWrite, org: &str, repo_name: &str) -> anyhow::Result<()> {
+        match self {
+            WebhookDiff::Create(webhook) => sync.create_webhook(org, repo_name, webhook).await,
+            WebhookDiff::Update { old, new } => {
+                sync.update_webhook(org, repo_name, old.id, new).await
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for WebhookDiff {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WebhookDiff::Create(webhook) => {
+                writeln!(f, "    ➕ Create: {}", WebhookDisplay(webhook))
+            }
+            WebhookDiff::Update { old, new } => {
+                writeln!(f, "    🔄 Update: {}", new.url)?;
+                if !old.active {
+                    writeln!(f, "        Activate")?;
+                }
+                if old.events != new.events {
+                    writeln!(
+                        f,
+                        "        Events: {} => {}",
+                        old.events.join(", "),
+                        new.events.join(", ")
+                    )?;
+                }
+                let old_content_type = old.config.content_type();
+                if old_content_type != new.content_type.as_str() {
+                    writeln!(
+                        f,
+                        "        Content type: {old_content_type} => {}",
+                        new.content_type.as_str()
+                    )?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+struct WebhookDisplay<'a>(&'a rust_team_data::v1::Webhook);
+
+impl std::fmt::Display for WebhookDisplay<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let rust_team_data::v1::Webhook {
+            url,
+            events,
+            content_type,
+            secret_env,
+        } = self.0;
+        write!(
+            f,
+            "{url} ({}, events: {})",
+            content_type.as_str(),
+            events.join(", ")
+        )?;
+        if let Some(secret_env) = secret_env {
+            write!(f, ", secret from ${secret_env}")?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+enum AutolinkDiff {
+    Create {
+        key_prefix: String,
+        url_template: String,
+    },
+    Update {
+        id: u64,
+        key_prefix: String,
+        old_url_template: String,
+        new_url_template: String,
+    },
+    Delete {
+        id: u64,
+        key_prefix: String,
+        url_template: String,
+    },
+}
+
+impl AutolinkDiff {
+    async fn apply(&self, sync: &GitHubWrite, org: &str, repo_name: &str) -> anyhow::Result<()> {
+        match self {
+            AutolinkDiff::Create {
+                key_prefix,
+                url_template,
+            } => {
+                sync.create_autolink(org, repo_name, key_prefix, url_template)
+                    .await
+            }
+            // Autolinks cannot be edited, so they are recreated instead
+            AutolinkDiff::Update {
+                id,
+                key_prefix,
+                new_url_template,
+                ..
+            } => {
+                sync.delete_autolink(org, repo_name, *id).await?;
+                sync.create_autolink(org, repo_name, key_prefix, new_url_template)
+                    .await
+            }
+            AutolinkDiff::Delete { id, .. } => sync.delete_autolink(org, repo_name, *id).await,
+        }
+    }
+}
+
+impl std::fmt::Display for AutolinkDiff {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AutolinkDiff::Create {
+                key_prefix,
+                url_template,
+            } => writeln!(f, "    ➕ Create: {key_prefix}: {url_template}"),
+            AutolinkDiff::Update {
+                key_prefix,
+                old_url_template,
+                new_url_template,
+                ..
+            } => writeln!(
+                f,
+                "    🔄 Update: {key_prefix}: {old_url_template} => {new_url_template}"
+            ),
+            AutolinkDiff::Delete {
+                key_prefix,
+                url_template,
+                ..
+            } => writeln!(f, "    ❌ Delete: {key_prefix}: {url_template}"),
+        }
+    }
+}
+
+#[derive(Debug, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+enum DeployKeyDiff {
+    /// Delete a deploy key that is not in the allowlist of the repository
+    Delete {
+        id: u64,
+        title: String,
+        /// `None` if the key could not be decoded
+        fingerprint: Option<String>,
+        read_only: bool,
+    },
+}
+
+impl DeployKeyDiff {
+    async fn apply(&self, sync: &GitHubWrite, org: &str, repo_name: &str) -> anyhow::Result<()> {
+        match self {
+            DeployKeyDiff::Delete { id, .. } => sync.delete_deploy_key(org, repo_name, *id).await,
+        }
+    }
+}
+
+impl std::fmt::Display for DeployKeyDiff {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DeployKeyDiff::Delete {
+                title,
+                fingerprint,
+                read_only,
+                ..
+            } => {
+                let access = if *read_only {
+                    "read-only"
+                } else {
+                    "read-write"
+                };
+                let fingerprint = fingerprint.as_deref().unwrap_or("unknown fingerprint");
+                writeln!(f, "    ❌ Delete: {title} ({fingerprint}, {access})")
+            }
+        }
+    }
+}
+
 impl UpdateRepoDiff {
     pub(crate) fn noop(&self) -> bool {
         if !self.can_be_modified() {
@@ -1392,17 +3163,29 @@ impl UpdateRepoDiff {
             name: _,
             repo_node_id: _,
             settings_diff,
+            default_branch_diff,
             permission_diffs,
             branch_protection_diffs,
             ruleset_diffs,
             environment_diffs,
+            label_diffs,
+            webhook_diffs,
+            autolink_diffs,
+            deploy_key_diffs,
+            actions_diff,
         } = self;
 
         settings_diff.0 == settings_diff.1
+            && default_branch_diff.is_none()
             && permission_diffs.is_empty()
             && branch_protection_diffs.is_empty()
             && ruleset_diffs.is_empty()
             && environment_diffs.is_empty()
+            && label_diffs.is_empty()
+            && webhook_diffs.is_empty()
+            && autolink_diffs.is_empty()
+            && deploy_key_diffs.is_empty()
+            && actions_diff.is_none()
     }
 
     fn can_be_modified(&self) -> bool {
@@ -1448,16 +3231,11 @@ impl UpdateRepoDiff {
         for env_diff in &self.environment_diffs {
             match env_diff {
                 EnvironmentDiff::Create(name, env) => {
-                    sync.create_environment(&self.org, &self.name, name, &env.branches, &env.tags)
+                    sync.create_environment(&self.org, &self.name, name, env)
                         .await?;
                 }
-                EnvironmentDiff::Update {
-                    name,
-                    new_branches,
-                    new_tags,
-                    ..
-                } => {
-                    sync.update_environment(&self.org, &self.name, name, new_branches, new_tags)
+                EnvironmentDiff::Update { name, new, .. } => {
+                    sync.update_environment(&self.org, &self.name, name, new)
                         .await?;
                 }
                 EnvironmentDiff::Delete(name) => {
@@ -1466,6 +3244,35 @@ impl UpdateRepoDiff {
             }
         }
 
+        for label_diff in &self.label_diffs {
+            label_diff.apply(sync, &self.org, &self.name).await?;
+        }
+
+        for webhook_diff in &self.webhook_diffs {
+            webhook_diff.apply(sync, &self.org, &self.name).await?;
+        }
+
+        for autolink_diff in &self.autolink_diffs {
+            autolink_diff.apply(sync, &self.org, &self.name).await?;
+        }
+
+        for deploy_key_diff in &self.deploy_key_diffs {
+            deploy_key_diff.apply(sync, &self.org, &self.name).await?;
+        }
+
+        if let Some((_, new)) = &self.actions_diff {
+            sync.set_actions_permissions(&self.org, &self.name, new)
+                .await?;
+        }
+
+        // Branch protections are adjusted first, so that a renamed default branch doesn't
+        // conflict with a protection that is about to be created for it.
+        if let Some(default_branch_diff) = &self.default_branch_diff {
+            default_branch_diff
+                .apply(sync, &self.org, &self.name)
+                .await?;
+        }
+
         if !is_unarchive && self.settings_diff.0 != self.settings_diff.1 {
             sync.edit_repo(&self.org, &self.name, &self.settings_diff.1)
                 .await?;
@@ -1486,10 +3293,16 @@ impl std::fmt::Display for UpdateRepoDiff {
             name,
             repo_node_id: _,
             settings_diff,
+            default_branch_diff,
             permission_diffs,
             branch_protection_diffs,
             ruleset_diffs,
             environment_diffs,
+            label_diffs,
+            webhook_diffs,
+            autolink_diffs,
+            deploy_key_diffs,
+            actions_diff,
         } = self;
 
         writeln!(f, "📝 Editing repo '{org}/{name}':")?;
@@ -1499,6 +3312,14 @@ impl std::fmt::Display for UpdateRepoDiff {
             homepage,
             archived,
             auto_merge_enabled,
+            allow_squash_merge,
+            allow_merge_commit,
+            allow_rebase_merge,
+            delete_branch_on_merge,
+            has_issues,
+            has_wiki,
+            has_projects,
+            has_discussions,
         } = settings_old;
         match (description.as_str(), settings_new.description.as_str()) {
             ("", "") => {}
@@ -1525,6 +3346,49 @@ impl std::fmt::Display for UpdateRepoDiff {
             (true, false) => writeln!(f, "  Disable auto-merge")?,
             _ => {}
         }
+        match (allow_squash_merge, &settings_new.allow_squash_merge) {
+            (false, true) => writeln!(f, "  Allow squash merge")?,
+            (true, false) => writeln!(f, "  Disallow squash merge")?,
+            _ => {}
+        }
+        match (allow_merge_commit, &settings_new.allow_merge_commit) {
+            (false, true) => writeln!(f, "  Allow merge commit")?,
+            (true, false) => writeln!(f, "  Disallow merge commit")?,
+            _ => {}
+        }
+        match (allow_rebase_merge, &settings_new.allow_rebase_merge) {
+            (false, true) => writeln!(f, "  Allow rebase merge")?,
+            (true, false) => writeln!(f, "  Disallow rebase merge")?,
+            _ => {}
+        }
+        match (delete_branch_on_merge, &settings_new.delete_branch_on_merge) {
+            (false, true) => writeln!(f, "  Enable deleting branches on merge")?,
+            (true, false) => writeln!(f, "  Disable deleting branches on merge")?,
+            _ => {}
+        }
+        match (has_issues, &settings_new.has_issues) {
+            (false, true) => writeln!(f, "  Enable issues")?,
+            (true, false) => writeln!(f, "  Disable issues")?,
+            _ => {}
+        }
+        match (has_wiki, &settings_new.has_wiki) {
+            (false, true) => writeln!(f, "  Enable wiki")?,
+            (true, false) => writeln!(f, "  Disable wiki")?,
+            _ => {}
+        }
+        match (has_projects, &settings_new.has_projects) {
+            (false, true) => writeln!(f, "  Enable projects")?,
+            (true, false) => writeln!(f, "  Disable projects")?,
+            _ => {}
+        }
+        match (has_discussions, &settings_new.has_discussions) {
+            (false, true) => writeln!(f, "  Enable discussions")?,
+            (true, false) => writeln!(f, "  Disable discussions")?,
+            _ => {}
+        }
+        if let Some(default_branch_diff) = default_branch_diff {
+            write!(f, "{default_branch_diff}")?;
+        }
         if !permission_diffs.is_empty() {
             writeln!(f, "  Permission Changes:")?;
             for permission_diff in permission_diffs {
@@ -1555,6 +3419,7 @@ impl std::fmt::Display for UpdateRepoDiff {
                         if !env.tags.is_empty() {
                             writeln!(f, "        Tags: {}", env.tags.join(", "))?;
                         }
+                        log_environment_protection(env, &mut *f)?;
                     }
                     EnvironmentDiff::Update {
                         name,
@@ -1562,8 +3427,11 @@ impl std::fmt::Display for UpdateRepoDiff {
                         remove_branches,
                         add_tags,
                         remove_tags,
-                        new_branches: _,
-                        new_tags: _,
+                        add_reviewers,
+                        remove_reviewers,
+                        wait_timer,
+                        protected_branches_only,
+                        new: _,
                     } => {
                         writeln!(f, "    🔄 Update: {name}")?;
                         if !add_branches.is_empty() {
@@ -1589,17 +3457,69 @@ impl std::fmt::Display for UpdateRepoDiff {
                         {
                             writeln!(f, "        No pattern changes")?;
                         }
+                        if !add_reviewers.is_empty() {
+                            writeln!(f, "        Adding reviewers: {}", add_reviewers.join(", "))?;
+                        }
+                        if !remove_reviewers.is_empty() {
+                            writeln!(
+                                f,
+                                "        Removing reviewers: {}",
+                                remove_reviewers.join(", ")
+                            )?;
+                        }
+                        if wait_timer.0 != wait_timer.1 {
+                            writeln!(
+                                f,
+                                "        Wait timer: {} => {} minutes",
+                                wait_timer.0, wait_timer.1
+                            )?;
+                        }
+                        if protected_branches_only.0 != protected_branches_only.1 {
+                            writeln!(
+                                f,
+                                "        Protected branches only: {} => {}",
+                                protected_branches_only.0, protected_branches_only.1
+                            )?;
+                        }
                     }
                     EnvironmentDiff::Delete(name) => writeln!(f, "    ❌ Delete: {name}")?,
                 }
             }
         }
+        if !label_diffs.is_empty() {
+            writeln!(f, "  Labels:")?;
+            for label_diff in label_diffs {
+                write!(f, "{label_diff}")?;
+            }
+        }
+        if !webhook_diffs.is_empty() {
+            writeln!(f, "  Webhooks:")?;
+            for webhook_diff in webhook_diffs {
+                write!(f, "{webhook_diff}")?;
+            }
+        }
+        if !autolink_diffs.is_empty() {
+            writeln!(f, "  Autolinks:")?;
+            for autolink_diff in autolink_diffs {
+                write!(f, "{autolink_diff}")?;
+            }
+        }
+        if !deploy_key_diffs.is_empty() {
+            writeln!(f, "  Deploy Keys:")?;
+            for deploy_key_diff in deploy_key_diffs {
+                write!(f, "{deploy_key_diff}")?;
+            }
+        }
+        if let Some((old, new)) = actions_diff {
+            writeln!(f, "  Actions Permissions:")?;
+            log_actions_permissions(old, Some(new), f)?;
+        }
 
         Ok(())
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, serde::Serialize)]
 struct RepoPermissionAssignmentDiff {
     collaborator: RepoCollaborator,
     diff: RepoPermissionDiff,
@@ -1637,12 +3557,11 @@ impl RepoPermissionAssignmentDiff {
 
 impl std::fmt::Display for RepoPermissionAssignmentDiff {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let RepoPermissionAssignmentDiff { collaborator, diff } = self;
+        let RepoPermissionAssignmentDiff {
+            collaborator: name,
+            diff,
+        } = self;
 
-        let name = match &collaborator {
-            RepoCollaborator::Team(name) => format!("team '{name}'"),
-            RepoCollaborator::User(name) => format!("user '{name}'"),
-        };
         match &diff {
             RepoPermissionDiff::Create(p) => {
                 writeln!(f, "    Giving {name} {p} permission")
@@ -1657,20 +3576,31 @@ impl std::fmt::Display for RepoPermissionAssignmentDiff {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
 enum RepoPermissionDiff {
     Create(RepoPermission),
     Update(RepoPermission, RepoPermission),
     Delete(RepoPermission),
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
 enum RepoCollaborator {
-    Team(String),
-    User(String),
+    Team(TeamName),
+    User(PersonId),
+}
+
+impl std::fmt::Display for RepoCollaborator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RepoCollaborator::Team(name) => write!(f, "team '{name}'"),
+            RepoCollaborator::User(name) => write!(f, "user '{name}'"),
+        }
+    }
 }
 
-#[derive(Debug)]
+#[derive(Debug, serde::Serialize)]
 struct BranchProtectionDiff {
     pattern: String,
     operation: BranchProtectionDiffOperation,
@@ -1703,7 +3633,7 @@ impl BranchProtectionDiff {
                 )
                 .await?;
             }
-            BranchProtectionDiffOperation::Delete(id) => {
+            BranchProtectionDiffOperation::Delete(id, _) => {
                 debug!(
                     "Deleting branch protection '{}' on '{}/{}' as \
                 the protection is not in the team repo",
@@ -1725,7 +3655,7 @@ impl std::fmt::Display for BranchProtectionDiff {
             BranchProtectionDiffOperation::Update(_, old, new) => {
                 log_branch_protection(old, Some(new), f)
             }
-            BranchProtectionDiffOperation::Delete(_) => {
+            BranchProtectionDiffOperation::Delete(..) => {
                 writeln!(f, "        Deleting branch protection")
             }
         }
@@ -1770,6 +3700,74 @@ fn log_field_if_not_default<T: PartialEq + std::fmt::Debug + Default>(
     Ok(())
 }
 
+/// Checks whether the scheduled archival date of the repository has been reached.
+/// Warns about the upcoming archival otherwise.
+fn is_archival_due(expected_repo: &rust_team_data::v1::Repo) -> anyhow::Result<bool> {
+    let Some(archive_at) = &expected_repo.archive_at else {
+        return Ok(false);
+    };
+    let archive_at: chrono::NaiveDate = archive_at.parse().with_context(|| {
+        format!(
+            "invalid archival date '{archive_at}' of repo {}/{}",
+            expected_repo.org, expected_repo.name
+        )
+    })?;
+    if chrono::Utc::now().date_naive() >= archive_at {
+        return Ok(true);
+    }
+    log::warn!(
+        "repo {}/{} is scheduled to be archived on {archive_at}",
+        expected_repo.org,
+        expected_repo.name
+    );
+    Ok(false)
+}
+
+fn log_actions_permissions(
+    current: &ActionsPermissions,
+    new: Option<&ActionsPermissions>,
+    result: &mut dyn Write,
+) -> std::fmt::Result {
+    log_field(
+        "Default Workflow Permissions",
+        &current.default_workflow_permissions,
+        new.map(|n| &n.default_workflow_permissions),
+        result,
+    )?;
+    log_field(
+        "Can Approve Pull Requests",
+        &current.can_approve_pull_requests,
+        new.map(|n| &n.can_approve_pull_requests),
+        result,
+    )?;
+    log_field(
+        "Allowed Actions",
+        &current.allowed_actions,
+        new.map(|n| &n.allowed_actions),
+        result,
+    )?;
+    log_field_if_not_default(
+        "Selected Actions",
+        &current.selected_actions,
+        new.map(|n| &n.selected_actions),
+        result,
+    )
+}
+
+fn log_environment_protection(env: &api::Environment, mut result: impl Write) -> std::fmt::Result {
+    if !env.reviewers.is_empty() {
+        let reviewers: Vec<_> = env.reviewers.iter().map(|r| r.to_string()).collect();
+        writeln!(result, "        Reviewers: {}", reviewers.join(", "))?;
+    }
+    if env.wait_timer != 0 {
+        writeln!(result, "        Wait timer: {} minutes", env.wait_timer)?;
+    }
+    if env.protected_branches_only {
+        writeln!(result, "        Protected branches only")?;
+    }
+    Ok(())
+}
+
 fn log_branch_protection(
     current: &api::BranchProtection,
     new: Option<&api::BranchProtection>,
@@ -1799,6 +3797,30 @@ fn log_branch_protection(
         new.map(|n| &n.requires_approving_reviews),
         &mut result,
     )?;
+    log_field(
+        "Requires Code Owner Reviews",
+        &current.requires_code_owner_reviews,
+        new.map(|n| &n.requires_code_owner_reviews),
+        &mut result,
+    )?;
+    log_field(
+        "Requires Signed Commits",
+        &current.requires_commit_signatures,
+        new.map(|n| &n.requires_commit_signatures),
+        &mut result,
+    )?;
+    log_field(
+        "Requires Linear History",
+        &current.requires_linear_history,
+        new.map(|n| &n.requires_linear_history),
+        &mut result,
+    )?;
+    log_field(
+        "Requires Conversation Resolution",
+        &current.requires_conversation_resolution,
+        new.map(|n| &n.requires_conversation_resolution),
+        &mut result,
+    )?;
     log_field(
         "Required Checks",
         &current.required_status_check_contexts,
@@ -1811,6 +3833,12 @@ fn log_branch_protection(
         new.map(|n| &n.push_allowances),
         &mut result,
     )?;
+    log_field_if_not_default(
+        "PR Bypass Allowances",
+        &current.bypass_pull_request_allowances,
+        new.map(|n| &n.bypass_pull_request_allowances),
+        &mut result,
+    )?;
     Ok(())
 }
 
@@ -2168,14 +4196,16 @@ fn log_ruleset(
     Ok(())
 }
 
-#[derive(Debug)]
+#[derive(Debug, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
 enum BranchProtectionDiffOperation {
     Create(api::BranchProtection),
     Update(String, api::BranchProtection, api::BranchProtection),
-    Delete(String),
+    /// ID and previous state of the deleted branch protection
+    Delete(String, api::BranchProtection),
 }
 
-#[derive(Debug)]
+#[derive(Debug, serde::Serialize)]
 struct RulesetDiff {
     name: String,
     operation: RulesetDiffOperation,
@@ -2222,14 +4252,16 @@ impl std::fmt::Display for RulesetDiff {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
 enum RulesetDiffOperation {
     Create(api::Ruleset),
     Update(i64, api::Ruleset, api::Ruleset), // id, old, new
     Delete(i64),
 }
 
-#[derive(Debug)]
+#[derive(Debug, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
 enum TeamDiff {
     Create(CreateTeamDiff),
     Edit(EditTeamDiff),
@@ -2265,22 +4297,32 @@ impl std::fmt::Display for TeamDiff {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, serde::Serialize)]
 struct CreateTeamDiff {
-    org: String,
-    name: String,
+    org: OrgName,
+    name: TeamName,
     description: String,
     privacy: TeamPrivacy,
-    members: Vec<(String, TeamRole)>,
+    parent: Option<api::ParentTeam>,
+    members: Vec<(PersonId, TeamRole)>,
 }
 
 impl CreateTeamDiff {
     async fn apply(self, sync: &GitHubWrite) -> anyhow::Result<()> {
-        sync.create_team(&self.org, &self.name, &self.description, self.privacy)
+        let team = sync
+            .create_team(
+                &self.org,
+                &self.name,
+                &self.description,
+                self.privacy,
+                self.parent.as_ref(),
+            )
             .await?;
+        // The slug of the new team is derived from its name by GitHub
+        let slug = TeamSlug::from(team.slug);
         for (member_name, role) in self.members {
             MemberDiff::Create(role)
-                .apply(&self.org, &self.name, &member_name, sync)
+                .apply(&self.org, &slug, &member_name, sync)
                 .await?;
         }
 
@@ -2295,6 +4337,7 @@ impl std::fmt::Display for CreateTeamDiff {
             name,
             description,
             privacy,
+            parent,
             members,
         } = self;
 
@@ -2310,6 +4353,9 @@ impl std::fmt::Display for CreateTeamDiff {
                 TeamPrivacy::Closed => "closed",
             }
         )?;
+        if let Some(parent) = parent {
+            writeln!(f, "  Parent: {}", parent.name)?;
+        }
         writeln!(f, "  Members:")?;
         for (name, role) in members {
             writeln!(f, "    {name}: {role}")?;
@@ -2318,35 +4364,45 @@ impl std::fmt::Display for CreateTeamDiff {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, serde::Serialize)]
 struct EditTeamDiff {
-    org: String,
-    name: String,
-    name_diff: Option<String>,
+    org: OrgName,
+    name: TeamName,
+    slug: TeamSlug,
+    name_diff: Option<TeamName>,
     description_diff: Option<(String, String)>,
     privacy_diff: Option<(TeamPrivacy, TeamPrivacy)>,
-    member_diffs: Vec<(String, MemberDiff)>,
+    parent_diff: Option<(Option<api::ParentTeam>, Option<api::ParentTeam>)>,
+    /// Only the members that change, large teams would otherwise list all their members
+    member_diffs: Vec<(PersonId, MemberDiff)>,
+    /// Members that are already in the team with the expected role, or were invited to it
+    unchanged_members: usize,
 }
 
 impl EditTeamDiff {
     async fn apply(self, sync: &GitHubWrite) -> anyhow::Result<()> {
+        let mut slug = self.slug;
         if self.name_diff.is_some()
             || self.description_diff.is_some()
             || self.privacy_diff.is_some()
+            || self.parent_diff.is_some()
         {
-            sync.edit_team(
-                &self.org,
-                &self.name,
-                self.name_diff.as_deref(),
-                self.description_diff.as_ref().map(|(_, d)| d.as_str()),
-                self.privacy_diff.map(|(_, p)| p),
-            )
-            .await?;
+            // Renaming a team changes its slug
+            slug = sync
+                .edit_team(
+                    &self.org,
+                    &slug,
+                    self.name_diff.as_ref(),
+                    self.description_diff.as_ref().map(|(_, d)| d.as_str()),
+                    self.privacy_diff.map(|(_, p)| p),
+                    self.parent_diff.as_ref().map(|(_, p)| p.as_ref()),
+                )
+                .await?;
         }
 
         for (member_name, member_diff) in self.member_diffs {
             member_diff
-                .apply(&self.org, &self.name, &member_name, sync)
+                .apply(&self.org, &slug, &member_name, sync)
                 .await?;
         }
 
@@ -2357,16 +4413,20 @@ impl EditTeamDiff {
         let EditTeamDiff {
             org: _,
             name: _,
+            slug: _,
             name_diff,
             description_diff,
             privacy_diff,
+            parent_diff,
             member_diffs,
+            unchanged_members: _,
         } = self;
 
         name_diff.is_none()
             && description_diff.is_none()
             && privacy_diff.is_none()
-            && member_diffs.iter().all(|(_, d)| d.is_noop())
+            && parent_diff.is_none()
+            && member_diffs.is_empty()
     }
 }
 
@@ -2379,10 +4439,13 @@ impl std::fmt::Display for EditTeamDiff {
         let EditTeamDiff {
             org,
             name,
+            slug: _,
             name_diff,
             description_diff,
             privacy_diff,
+            parent_diff,
             member_diffs,
+            unchanged_members,
         } = self;
 
         writeln!(f, "📝 Editing team '{org}/{name}':")?;
@@ -2399,6 +4462,14 @@ impl std::fmt::Display for EditTeamDiff {
             };
             writeln!(f, "  New privacy: '{}' => '{}'", display(old), display(new))?;
         }
+        if let Some((old, new)) = &parent_diff {
+            writeln!(
+                f,
+                "  New parent: '{}' => '{}'",
+                old.as_ref().map_or("<none>", |p| p.name.as_str()),
+                new.as_ref().map_or("<none>", |p| p.name.as_str())
+            )?;
+        }
         for (member, diff) in member_diffs {
             match diff {
                 MemberDiff::Create(r) => {
@@ -2407,53 +4478,54 @@ impl std::fmt::Display for EditTeamDiff {
                 MemberDiff::ChangeRole((o, n)) => {
                     writeln!(f, "  Changing '{member}' role from {o} to {n}")?;
                 }
-                MemberDiff::Delete => {
+                MemberDiff::Delete(_) => {
                     writeln!(f, "  Deleting member '{member}'")?;
                 }
-                MemberDiff::Noop => {}
             }
         }
+        match unchanged_members {
+            _ if member_diffs.is_empty() => {}
+            0 => {}
+            1 => writeln!(f, "  (1 other member unchanged)")?,
+            n => writeln!(f, "  ({n} other members unchanged)")?,
+        }
         Ok(())
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
 enum MemberDiff {
     Create(TeamRole),
     ChangeRole((TeamRole, TeamRole)),
-    Delete,
-    Noop,
+    /// Contains the role of the deleted member
+    Delete(TeamRole),
 }
 
 impl MemberDiff {
     async fn apply(
         self,
-        org: &str,
-        team: &str,
-        member: &str,
+        org: &OrgName,
+        team: &TeamSlug,
+        member: &PersonId,
         sync: &GitHubWrite,
     ) -> anyhow::Result<()> {
         match self {
             MemberDiff::Create(role) | MemberDiff::ChangeRole((_, role)) => {
                 sync.set_team_membership(org, team, member, role).await?;
             }
-            MemberDiff::Delete => sync.remove_team_membership(org, team, member).await?,
-            MemberDiff::Noop => {}
+            MemberDiff::Delete(_) => sync.remove_team_membership(org, team, member).await?,
         }
 
         Ok(())
     }
-
-    fn is_noop(&self) -> bool {
-        matches!(self, Self::Noop)
-    }
 }
 
-#[derive(Debug)]
+#[derive(Debug, serde::Serialize)]
 struct DeleteTeamDiff {
-    org: String,
-    name: String,
-    slug: String,
+    org: OrgName,
+    name: TeamName,
+    slug: TeamSlug,
 }
 
 impl DeleteTeamDiff {
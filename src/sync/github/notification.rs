@@ -0,0 +1,119 @@
+//! Summary of the destructive GitHub changes, posted to Zulip so that moderation and infra
+//! notice them while they are applied.
+
+use std::fmt::Display;
+
+use super::{
+    BranchProtectionDiffOperation, Diff, OrgMembershipDiff, RepoDiff, RulesetDiffOperation,
+    TeamDiff,
+};
+
+/// Things that a diff removes from GitHub
+#[derive(Debug, Default)]
+pub(crate) struct DestructiveChanges {
+    /// `org/team`
+    deleted_teams: Vec<String>,
+    /// `org/user`
+    removed_org_members: Vec<String>,
+    /// Branch protections and rulesets, prefixed by `org/repo`
+    deleted_branch_protections: Vec<String>,
+}
+
+impl Diff {
+    pub(crate) fn destructive_changes(&self) -> DestructiveChanges {
+        let mut changes = DestructiveChanges::default();
+        for diff in &self.team_diffs {
+            diff.removals(&mut changes);
+        }
+        for diff in &self.repo_diffs {
+            diff.removals(&mut changes);
+        }
+        for diff in &self.org_membership_diffs {
+            diff.removals(&mut changes);
+        }
+        changes
+    }
+}
+
+impl TeamDiff {
+    pub(super) fn removals(&self, changes: &mut DestructiveChanges) {
+        if let TeamDiff::Delete(delete) = self {
+            changes
+                .deleted_teams
+                .push(format!("{}/{}", delete.org, delete.name));
+        }
+    }
+}
+
+impl OrgMembershipDiff {
+    pub(super) fn removals(&self, changes: &mut DestructiveChanges) {
+        for member in &self.members_to_remove {
+            changes
+                .removed_org_members
+                .push(format!("{}/{member}", self.org));
+        }
+    }
+}
+
+impl RepoDiff {
+    pub(super) fn removals(&self, changes: &mut DestructiveChanges) {
+        let RepoDiff::Update(update) = self else {
+            return;
+        };
+        let repo = format!("{}/{}", update.org, update.name);
+        for protection in &update.branch_protection_diffs {
+            if let BranchProtectionDiffOperation::Delete(..) = protection.operation {
+                changes.deleted_branch_protections.push(format!(
+                    "{repo}: branch protection `{}`",
+                    protection.pattern
+                ));
+            }
+        }
+        for ruleset in &update.ruleset_diffs {
+            if let RulesetDiffOperation::Delete(_) = ruleset.operation {
+                changes
+                    .deleted_branch_protections
+                    .push(format!("{repo}: ruleset `{}`", ruleset.name));
+            }
+        }
+    }
+}
+
+impl DestructiveChanges {
+    pub(crate) fn is_empty(&self) -> bool {
+        self.deleted_teams.is_empty()
+            && self.removed_org_members.is_empty()
+            && self.deleted_branch_protections.is_empty()
+    }
+
+    pub(super) fn extend(&mut self, other: DestructiveChanges) {
+        self.deleted_teams.extend(other.deleted_teams);
+        self.removed_org_members.extend(other.removed_org_members);
+        self.deleted_branch_protections
+            .extend(other.deleted_branch_protections);
+    }
+}
+
+/// Zulip markdown listing the removed things
+impl Display for DestructiveChanges {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let sections = [
+            ("Deleted teams", &self.deleted_teams),
+            ("Removed organization members", &self.removed_org_members),
+            (
+                "Deleted branch protections",
+                &self.deleted_branch_protections,
+            ),
+        ];
+        for (title, items) in sections {
+            if items.is_empty() {
+                continue;
+            }
+            writeln!(f, "**{title}:**")?;
+            for item in items {
+                writeln!(f, "- {item}")?;
+            }
+        }
+        Ok(())
+    }
+}
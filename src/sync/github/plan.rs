@@ -0,0 +1,85 @@
+//! Plans are diffs saved by a dry run, so that the apply step executes exactly what was reviewed.
+
+use std::path::Path;
+
+use anyhow::Context as _;
+use serde_json::Value;
+
+use super::Diff;
+
+/// Version of the plan file format, bumped whenever the serialized diff changes
+const PLAN_VERSION: u32 = 3;
+
+#[derive(serde::Serialize, serde::Deserialize)]
+struct Plan<T> {
+    version: u32,
+    diff: T,
+}
+
+impl Diff {
+    /// Write the diff to a plan file, to be applied later with `apply --plan`.
+    pub(crate) fn save_plan(&self, path: &Path) -> anyhow::Result<()> {
+        let plan = Plan {
+            version: PLAN_VERSION,
+            diff: self,
+        };
+        let contents = serde_json::to_vec_pretty(&plan)?;
+        std::fs::write(path, contents)
+            .with_context(|| format!("failed to write plan to {}", path.display()))
+    }
+
+    /// Ensure that the diff computed against the live state of GitHub is the one that was saved
+    /// to the plan file.
+    ///
+    /// The diff is not loaded from the plan and applied as-is, because GitHub may have changed
+    /// since the plan was created, which would make the plan apply stale changes.
+    pub(crate) fn check_plan(&self, path: &Path) -> anyhow::Result<()> {
+        let contents = std::fs::read(path)
+            .with_context(|| format!("failed to read plan from {}", path.display()))?;
+        let plan: Plan<Value> = serde_json::from_slice(&contents)
+            .with_context(|| format!("failed to parse plan from {}", path.display()))?;
+        if plan.version != PLAN_VERSION {
+            anyhow::bail!(
+                "the plan in {} was created by an incompatible version of sync-team",
+                path.display()
+            );
+        }
+        if canonicalize(plan.diff) != canonicalize(serde_json::to_value(self)?) {
+            anyhow::bail!(
+                "GitHub or the team data changed since the plan in {} was created, \
+                 create and review a new plan",
+                path.display()
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Sort the team and repository diffs of the serialized diff by organization and name, since
+/// they are computed concurrently and end up in an arbitrary order.
+///
+/// Nested arrays are left as they are: they hold ordered data like `(old, new)` pairs.
+fn canonicalize(mut diff: Value) -> Value {
+    for field in ["team_diffs", "repo_diffs"] {
+        if let Some(Value::Array(diffs)) = diff.get_mut(field) {
+            diffs.sort_by_cached_key(diff_key);
+        }
+    }
+    diff
+}
+
+/// The organization and name of a serialized team or repository diff, e.g.
+/// `{"update": {"org": "rust-lang", "name": "rust", ...}}`
+fn diff_key(diff: &Value) -> (String, String) {
+    let fields = diff
+        .as_object()
+        .and_then(|variants| variants.values().next());
+    let field = |name| {
+        fields
+            .and_then(|fields| fields.get(name))
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_owned()
+    };
+    (field("org"), field("name"))
+}
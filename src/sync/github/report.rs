@@ -0,0 +1,70 @@
+//! Outcome of applying a diff when failed changes don't stop the remaining ones.
+
+use std::fmt::Display;
+
+use log::error;
+
+use super::DestructiveChanges;
+
+/// Changes that were applied, and the ones that failed
+#[derive(Debug, Default, serde::Serialize)]
+pub(crate) struct ApplyReport {
+    pub(crate) applied: usize,
+    pub(crate) failures: Vec<ApplyFailure>,
+    /// Things removed from GitHub by the applied changes
+    #[serde(skip)]
+    pub(crate) removed: DestructiveChanges,
+}
+
+#[derive(Debug, serde::Serialize)]
+pub(crate) struct ApplyFailure {
+    /// Description of the change, as shown in the diff
+    pub(crate) change: String,
+    pub(crate) error: String,
+}
+
+impl ApplyReport {
+    /// Record the result of applying a change, returning the error unless
+    /// `continue_on_error` is set.
+    pub(crate) fn record(
+        &mut self,
+        change: String,
+        result: anyhow::Result<()>,
+        continue_on_error: bool,
+    ) -> anyhow::Result<()> {
+        match result {
+            Ok(()) => self.applied += 1,
+            Err(err) if continue_on_error => {
+                error!("failed to apply a GitHub change: {err:#}");
+                self.failures.push(ApplyFailure {
+                    change,
+                    error: format!("{err:#}"),
+                });
+            }
+            Err(err) => return Err(err),
+        }
+        Ok(())
+    }
+
+    pub(crate) fn is_success(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
+
+impl Display for ApplyReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "{} changes applied, {} failed",
+            self.applied,
+            self.failures.len()
+        )?;
+        for failure in &self.failures {
+            writeln!(f, "❌ {}", failure.error)?;
+            for line in failure.change.lines() {
+                writeln!(f, "    {line}")?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,237 @@
+//! Diff between two revisions of the team data, computed without contacting GitHub.
+//!
+//! It is meant for announcing what changed in a release of the data, so it only covers team
+//! memberships and repository permissions, as GitHub would have them after synchronizing each
+//! revision.
+
+use std::collections::{BTreeMap, HashMap};
+
+use anyhow::Context as _;
+use rust_team_data::ids::PersonId;
+use rust_team_data::v1;
+
+use super::api::{ParentTeam, RepoSettings, RepoTeam, RepoUser, TeamRole};
+use super::{
+    BotDetails, CreateTeamDiff, DeleteTeamDiff, Diff, EditTeamDiff, MemberDiff, RepoDiff, TeamDiff,
+    UpdateRepoDiff, calculate_permission_diffs, convert_permission, expected_description,
+    expected_privacy,
+};
+
+/// The team data at one revision
+pub(crate) struct Revision {
+    pub(crate) teams: Vec<v1::Team>,
+    pub(crate) repos: Vec<v1::Repo>,
+    pub(crate) people: v1::People,
+}
+
+impl Revision {
+    fn github_teams(&self) -> impl Iterator<Item = &v1::GitHubTeam> {
+        self.teams
+            .iter()
+            .filter_map(|team| team.github.as_ref())
+            .flat_map(|github| &github.teams)
+    }
+
+    fn usernames(&self) -> HashMap<u64, &str> {
+        self.people
+            .people
+            .iter()
+            .map(|(username, person)| (person.github_id, username.as_str()))
+            .collect()
+    }
+}
+
+/// Compute the changes of team memberships and repository permissions between two revisions.
+///
+/// Roles only take team maintainers into account: organization owners, who are always
+/// maintainers of their teams, are only known to GitHub.
+pub(crate) fn diff_revisions(old: &Revision, new: &Revision) -> anyhow::Result<Diff> {
+    Ok(Diff {
+        org_diffs: vec![],
+        team_diffs: diff_teams(old, new)?,
+        repo_diffs: diff_repos(old, new)?,
+        org_membership_diffs: vec![],
+        org_owner_diffs: vec![],
+    })
+}
+
+fn diff_teams(old: &Revision, new: &Revision) -> anyhow::Result<Vec<TeamDiff>> {
+    let old_usernames = old.usernames();
+    let new_usernames = new.usernames();
+    let username = |id: &u64| {
+        new_usernames
+            .get(id)
+            .or_else(|| old_usernames.get(id))
+            .map(|name| PersonId::from(*name))
+            .with_context(|| format!("GitHub user {id} is missing from the people data"))
+    };
+
+    let mut old_teams: BTreeMap<(&str, &str), &v1::GitHubTeam> = old
+        .github_teams()
+        .map(|team| ((team.org.as_str(), team.name.as_str()), team))
+        .collect();
+    let mut diffs = Vec::new();
+    for new_team in new.github_teams() {
+        let mut old_team = old_teams.remove(&(new_team.org.as_str(), new_team.name.as_str()));
+        // Renamed teams keep their ID
+        if old_team.is_none()
+            && let Some(team_id) = new_team.team_id
+            && let Some(key) = old_teams
+                .iter()
+                .find(|(_, team)| team.org == new_team.org && team.team_id == Some(team_id))
+                .map(|(key, _)| *key)
+        {
+            old_team = old_teams.remove(&key);
+        }
+
+        let Some(old_team) = old_team else {
+            let members = new_team
+                .members
+                .iter()
+                .map(|id| Ok((username(id)?, role(new_team, *id))))
+                .collect::<anyhow::Result<_>>()?;
+            diffs.push(TeamDiff::Create(CreateTeamDiff {
+                org: new_team.org.as_str().into(),
+                name: new_team.name.as_str().into(),
+                description: expected_description(new_team).to_owned(),
+                privacy: expected_privacy(new_team),
+                // The diff is computed without GitHub, so the parent has no ID
+                parent: new_team
+                    .parent
+                    .clone()
+                    .map(|name| ParentTeam { name, id: None }),
+                members,
+            }));
+            continue;
+        };
+
+        let mut member_diffs = Vec::new();
+        let mut unchanged_members = 0;
+        for id in &new_team.members {
+            let new_role = role(new_team, *id);
+            let diff = if !old_team.members.contains(id) {
+                MemberDiff::Create(new_role)
+            } else if role(old_team, *id) != new_role {
+                MemberDiff::ChangeRole((role(old_team, *id), new_role))
+            } else {
+                unchanged_members += 1;
+                continue;
+            };
+            member_diffs.push((username(id)?, diff));
+        }
+        for id in &old_team.members {
+            if !new_team.members.contains(id) {
+                member_diffs.push((username(id)?, MemberDiff::Delete(role(old_team, *id))));
+            }
+        }
+
+        let diff = TeamDiff::Edit(EditTeamDiff {
+            org: new_team.org.as_str().into(),
+            name: old_team.name.as_str().into(),
+            // The slug is only known to GitHub, and is only needed to apply the diff
+            slug: old_team.name.as_str().into(),
+            name_diff: (old_team.name != new_team.name).then(|| new_team.name.as_str().into()),
+            description_diff: None,
+            privacy_diff: None,
+            parent_diff: None,
+            member_diffs,
+            unchanged_members,
+        });
+        if !diff.noop() {
+            diffs.push(diff);
+        }
+    }
+    for team in old_teams.into_values() {
+        diffs.push(TeamDiff::Delete(DeleteTeamDiff {
+            org: team.org.as_str().into(),
+            name: team.name.as_str().into(),
+            slug: team.name.as_str().into(),
+        }));
+    }
+    Ok(diffs)
+}
+
+fn role(github_team: &v1::GitHubTeam, user: u64) -> TeamRole {
+    if github_team.maintainers.contains(&user) {
+        TeamRole::Maintainer
+    } else {
+        TeamRole::Member
+    }
+}
+
+/// Repositories are never deleted by the synchronization, so only the repositories of the new
+/// revision are diffed.
+fn diff_repos(old: &Revision, new: &Revision) -> anyhow::Result<Vec<RepoDiff>> {
+    let old_repos: HashMap<(&str, &str), &v1::Repo> = old
+        .repos
+        .iter()
+        .map(|repo| ((repo.org.as_str(), repo.name.as_str()), repo))
+        .collect();
+    let mut diffs = Vec::new();
+    for new_repo in &new.repos {
+        let old_repo = old_repos.get(&(new_repo.org.as_str(), new_repo.name.as_str()));
+        let mut teams = HashMap::new();
+        let mut collaborators = HashMap::new();
+        if let Some(old_repo) = old_repo {
+            for team in &old_repo.teams {
+                let permission = convert_permission(&team.permission);
+                let name = team.name.clone();
+                teams.insert(name.clone(), RepoTeam { name, permission });
+            }
+            for bot in &old_repo.bots {
+                if let BotDetails::User { name, permission } = BotDetails::from(bot) {
+                    let name = name.to_string();
+                    collaborators.insert(name.clone(), RepoUser { name, permission });
+                }
+            }
+            for member in &old_repo.members {
+                let permission = convert_permission(&member.permission);
+                let name = member.name.clone();
+                collaborators.insert(name.clone(), RepoUser { name, permission });
+            }
+        }
+        let mut permission_diffs =
+            calculate_permission_diffs(new_repo, teams, collaborators, HashMap::new())?;
+        // Removed collaborators come out of a `HashMap`
+        permission_diffs.sort_by_cached_key(|diff| diff.collaborator.to_string());
+        if permission_diffs.is_empty() {
+            continue;
+        }
+        diffs.push(RepoDiff::Update(UpdateRepoDiff {
+            org: new_repo.org.as_str().into(),
+            name: new_repo.name.as_str().into(),
+            repo_node_id: String::new(),
+            settings_diff: (repo_settings(new_repo), repo_settings(new_repo)),
+            default_branch_diff: None,
+            permission_diffs,
+            branch_protection_diffs: vec![],
+            ruleset_diffs: vec![],
+            environment_diffs: vec![],
+            label_diffs: vec![],
+            webhook_diffs: vec![],
+            autolink_diffs: vec![],
+            deploy_key_diffs: vec![],
+            actions_diff: None,
+        }));
+    }
+    Ok(diffs)
+}
+
+/// Settings are not compared, they are only needed to render the repository
+fn repo_settings(repo: &v1::Repo) -> RepoSettings {
+    RepoSettings {
+        description: repo.description.clone(),
+        homepage: repo.homepage.clone(),
+        // Archived repositories are not rendered
+        archived: false,
+        auto_merge_enabled: repo.auto_merge_enabled,
+        allow_squash_merge: repo.allow_squash_merge.unwrap_or(true),
+        allow_merge_commit: repo.allow_merge_commit.unwrap_or(true),
+        allow_rebase_merge: repo.allow_rebase_merge.unwrap_or(true),
+        delete_branch_on_merge: repo.delete_branch_on_merge.unwrap_or(false),
+        has_issues: repo.has_issues.unwrap_or(true),
+        has_wiki: repo.has_wiki.unwrap_or(true),
+        has_projects: repo.has_projects.unwrap_or(true),
+        has_discussions: repo.has_discussions.unwrap_or(false),
+    }
+}
@@ -0,0 +1,518 @@
+//! Inverse operations of the applied changes, recorded in the audit log so that a mistaken
+//! run can be undone with `sync rollback`.
+//!
+//! Only changes whose previous state is known from the diff can be undone: team members, team
+//! settings, repository permissions, branch protections, organization settings and owners.
+//! Created or deleted teams and repositories, rulesets and organization memberships have to be
+//! restored manually.
+
+use std::fmt::Display;
+use std::path::Path;
+
+use anyhow::Context as _;
+use log::{info, warn};
+use rust_team_data::ids::{OrgName, PersonId, RepoName, TeamName, TeamSlug};
+use serde::Deserialize as _;
+
+use super::api::{
+    BranchProtection, BranchProtectionOp, OrgSettings, ParentTeam, PushAllowanceActor,
+};
+use super::{
+    BranchProtectionDiffOperation, GitHubWrite, MemberDiff, OrgDiff, OrgMembershipDiff,
+    OrgOwnersDiff, RepoCollaborator, RepoDiff, RepoPermission, RepoPermissionDiff, TeamDiff,
+    TeamPrivacy, TeamRole,
+};
+
+/// An operation that restores the state of GitHub from before a change
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "operation", rename_all = "snake_case")]
+pub(crate) enum InverseOperation {
+    SetTeamMembership {
+        org: OrgName,
+        team: TeamSlug,
+        user: PersonId,
+        #[serde(with = "TeamRoleDef")]
+        role: TeamRole,
+    },
+    RemoveTeamMembership {
+        org: OrgName,
+        team: TeamSlug,
+        user: PersonId,
+    },
+    EditTeam {
+        org: OrgName,
+        team: TeamSlug,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        description: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        privacy: Option<TeamPrivacy>,
+        /// `Some(None)` if the team didn't have a parent
+        #[serde(
+            default,
+            skip_serializing_if = "Option::is_none",
+            deserialize_with = "present"
+        )]
+        parent: Option<Option<TeamName>>,
+        /// The ID of the parent team, if the team had one
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        parent_id: Option<u64>,
+    },
+    SetRepoPermission {
+        org: OrgName,
+        repo: RepoName,
+        collaborator: RepoCollaborator,
+        permission: RepoPermission,
+    },
+    RemoveRepoCollaborator {
+        org: OrgName,
+        repo: RepoName,
+        collaborator: RepoCollaborator,
+    },
+    RestoreBranchProtection {
+        org: OrgName,
+        repo: RepoName,
+        repo_node_id: String,
+        #[serde(with = "BranchProtectionDef")]
+        protection: BranchProtection,
+    },
+    RevertBranchProtection {
+        org: OrgName,
+        repo: RepoName,
+        id: String,
+        #[serde(with = "BranchProtectionDef")]
+        protection: BranchProtection,
+    },
+    SetOrgSettings {
+        org: OrgName,
+        settings: OrgSettings,
+    },
+    SetOrgOwner {
+        org: OrgName,
+        user: PersonId,
+        owner: bool,
+    },
+}
+
+/// Distinguishes a `null` field, which is `Some(None)`, from a missing one
+fn present<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: serde::Deserialize<'de>,
+{
+    Ok(Some(Option::deserialize(deserializer)?))
+}
+
+/// `TeamRole` is deserialized in the format of the GitHub API
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(remote = "TeamRole", rename_all = "snake_case")]
+enum TeamRoleDef {
+    Member,
+    Maintainer,
+}
+
+/// `BranchProtection` is deserialized in the format of the GitHub GraphQL API
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(remote = "BranchProtection")]
+struct BranchProtectionDef {
+    pattern: String,
+    is_admin_enforced: bool,
+    allows_force_pushes: bool,
+    dismisses_stale_reviews: bool,
+    required_approving_review_count: u8,
+    required_status_check_contexts: Vec<String>,
+    push_allowances: Vec<PushAllowanceActor>,
+    bypass_pull_request_allowances: Vec<PushAllowanceActor>,
+    requires_approving_reviews: bool,
+    requires_code_owner_reviews: bool,
+    requires_commit_signatures: bool,
+    requires_linear_history: bool,
+    requires_conversation_resolution: bool,
+}
+
+impl InverseOperation {
+    async fn apply(&self, sync: &GitHubWrite) -> anyhow::Result<()> {
+        match self {
+            InverseOperation::SetTeamMembership {
+                org,
+                team,
+                user,
+                role,
+            } => sync.set_team_membership(org, team, user, *role).await,
+            InverseOperation::RemoveTeamMembership { org, team, user } => {
+                sync.remove_team_membership(org, team, user).await
+            }
+            InverseOperation::EditTeam {
+                org,
+                team,
+                description,
+                privacy,
+                parent,
+                parent_id,
+            } => {
+                let parent = parent.as_ref().map(|parent| {
+                    parent.as_ref().map(|name| ParentTeam {
+                        name: name.to_string(),
+                        id: *parent_id,
+                    })
+                });
+                sync.edit_team(
+                    org,
+                    team,
+                    None,
+                    description.as_deref(),
+                    *privacy,
+                    parent.as_ref().map(Option::as_ref),
+                )
+                .await?;
+                Ok(())
+            }
+            InverseOperation::SetRepoPermission {
+                org,
+                repo,
+                collaborator,
+                permission,
+            } => match collaborator {
+                RepoCollaborator::Team(team) => {
+                    sync.update_team_repo_permissions(org, repo, team, permission)
+                        .await
+                }
+                RepoCollaborator::User(user) => {
+                    sync.update_user_repo_permissions(org, repo, user, permission)
+                        .await
+                }
+            },
+            InverseOperation::RemoveRepoCollaborator {
+                org,
+                repo,
+                collaborator,
+            } => match collaborator {
+                RepoCollaborator::Team(team) => sync.remove_team_from_repo(org, repo, team).await,
+                RepoCollaborator::User(user) => {
+                    sync.remove_collaborator_from_repo(org, repo, user).await
+                }
+            },
+            InverseOperation::RestoreBranchProtection {
+                org,
+                repo_node_id,
+                protection,
+                ..
+            } => {
+                sync.upsert_branch_protection(
+                    BranchProtectionOp::CreateForRepo(repo_node_id.clone()),
+                    &protection.pattern,
+                    protection,
+                    org,
+                )
+                .await
+            }
+            InverseOperation::RevertBranchProtection {
+                org,
+                id,
+                protection,
+                ..
+            } => {
+                sync.upsert_branch_protection(
+                    BranchProtectionOp::UpdateBranchProtection(id.clone()),
+                    &protection.pattern,
+                    protection,
+                    org,
+                )
+                .await
+            }
+            InverseOperation::SetOrgSettings { org, settings } => {
+                sync.edit_org(org, settings).await
+            }
+            InverseOperation::SetOrgOwner { org, user, owner } => {
+                sync.set_org_owner(org, user, *owner).await
+            }
+        }
+    }
+}
+
+impl Display for InverseOperation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InverseOperation::SetTeamMembership {
+                org,
+                team,
+                user,
+                role,
+            } => write!(
+                f,
+                "set the role of '{user}' in team '{org}/{team}' to {role}"
+            ),
+            InverseOperation::RemoveTeamMembership { org, team, user } => {
+                write!(f, "remove '{user}' from team '{org}/{team}'")
+            }
+            InverseOperation::EditTeam { org, team, .. } => {
+                write!(f, "restore the settings of team '{org}/{team}'")
+            }
+            InverseOperation::SetRepoPermission {
+                org,
+                repo,
+                collaborator,
+                permission,
+            } => write!(
+                f,
+                "set the permission of {collaborator} on '{org}/{repo}' to {permission}"
+            ),
+            InverseOperation::RemoveRepoCollaborator {
+                org,
+                repo,
+                collaborator,
+            } => write!(f, "remove {collaborator} from '{org}/{repo}'"),
+            InverseOperation::RestoreBranchProtection {
+                org,
+                repo,
+                protection,
+                ..
+            } => write!(
+                f,
+                "restore branch protection '{}' of '{org}/{repo}'",
+                protection.pattern
+            ),
+            InverseOperation::RevertBranchProtection {
+                org,
+                repo,
+                protection,
+                ..
+            } => write!(
+                f,
+                "revert branch protection '{}' of '{org}/{repo}'",
+                protection.pattern
+            ),
+            InverseOperation::SetOrgSettings { org, .. } => {
+                write!(f, "restore the settings of organization '{org}'")
+            }
+            InverseOperation::SetOrgOwner { org, user, owner } => {
+                if *owner {
+                    write!(f, "make '{user}' an owner of '{org}'")
+                } else {
+                    write!(f, "demote '{user}' to a member of '{org}'")
+                }
+            }
+        }
+    }
+}
+
+impl OrgDiff {
+    pub(super) fn inverse(&self) -> Vec<InverseOperation> {
+        vec![InverseOperation::SetOrgSettings {
+            org: self.org.clone(),
+            settings: self.settings_diff.0.clone(),
+        }]
+    }
+}
+
+impl TeamDiff {
+    pub(super) fn inverse(&self) -> Vec<InverseOperation> {
+        // Renaming a team changes its slug, which is not known before the change is applied
+        let TeamDiff::Edit(edit) = self else {
+            return vec![];
+        };
+        if edit.name_diff.is_some() {
+            return vec![];
+        }
+        let (org, team) = (&edit.org, &edit.slug);
+        let mut operations = Vec::new();
+        if edit.description_diff.is_some()
+            || edit.privacy_diff.is_some()
+            || edit.parent_diff.is_some()
+        {
+            operations.push(InverseOperation::EditTeam {
+                org: org.clone(),
+                team: team.clone(),
+                description: edit.description_diff.as_ref().map(|(old, _)| old.clone()),
+                privacy: edit.privacy_diff.map(|(old, _)| old),
+                parent: edit
+                    .parent_diff
+                    .as_ref()
+                    .map(|(old, _)| old.as_ref().map(|p| TeamName::from(&p.name))),
+                parent_id: edit
+                    .parent_diff
+                    .as_ref()
+                    .and_then(|(old, _)| old.as_ref()?.id),
+            });
+        }
+        for (user, diff) in &edit.member_diffs {
+            let user = user.clone();
+            let (org, team) = (org.clone(), team.clone());
+            operations.push(match diff {
+                MemberDiff::Create(_) => InverseOperation::RemoveTeamMembership { org, team, user },
+                MemberDiff::ChangeRole((role, _)) | MemberDiff::Delete(role) => {
+                    InverseOperation::SetTeamMembership {
+                        org,
+                        team,
+                        user,
+                        role: *role,
+                    }
+                }
+            });
+        }
+        operations
+    }
+}
+
+impl RepoDiff {
+    pub(super) fn inverse(&self) -> Vec<InverseOperation> {
+        let RepoDiff::Update(update) = self else {
+            return vec![];
+        };
+        let (org, repo) = (&update.org, &update.name);
+        let mut operations = Vec::new();
+        for permission in &update.permission_diffs {
+            let collaborator = permission.collaborator.clone();
+            let (org, repo) = (org.clone(), repo.clone());
+            operations.push(match &permission.diff {
+                RepoPermissionDiff::Create(_) => InverseOperation::RemoveRepoCollaborator {
+                    org,
+                    repo,
+                    collaborator,
+                },
+                RepoPermissionDiff::Update(old, _) | RepoPermissionDiff::Delete(old) => {
+                    InverseOperation::SetRepoPermission {
+                        org,
+                        repo,
+                        collaborator,
+                        permission: old.clone(),
+                    }
+                }
+            });
+        }
+        for protection in &update.branch_protection_diffs {
+            let (org, repo) = (org.clone(), repo.clone());
+            match &protection.operation {
+                // The ID of the created branch protection is not known
+                BranchProtectionDiffOperation::Create(_) => {}
+                BranchProtectionDiffOperation::Update(id, old, _) => {
+                    operations.push(InverseOperation::RevertBranchProtection {
+                        org,
+                        repo,
+                        id: id.clone(),
+                        protection: old.clone(),
+                    });
+                }
+                BranchProtectionDiffOperation::Delete(_, old) => {
+                    operations.push(InverseOperation::RestoreBranchProtection {
+                        org,
+                        repo,
+                        repo_node_id: update.repo_node_id.clone(),
+                        protection: old.clone(),
+                    });
+                }
+            }
+        }
+        operations
+    }
+}
+
+impl OrgMembershipDiff {
+    /// Removed members have to be invited again, which requires their consent
+    pub(super) fn inverse(&self) -> Vec<InverseOperation> {
+        vec![]
+    }
+}
+
+impl OrgOwnersDiff {
+    pub(super) fn inverse(&self) -> Vec<InverseOperation> {
+        let promoted = self.owners_to_add.iter().map(|user| (user, false));
+        let demoted = self
+            .owners_to_remove
+            .iter()
+            .filter(|_| self.demotions_allowed)
+            .map(|user| (user, true));
+        promoted
+            .chain(demoted)
+            .map(|(user, owner)| InverseOperation::SetOrgOwner {
+                org: self.org.clone(),
+                user: user.clone(),
+                owner,
+            })
+            .collect()
+    }
+}
+
+/// The part of an audit log record needed to undo its change
+#[derive(serde::Deserialize)]
+struct RollbackRecord {
+    run: String,
+    resource: String,
+    #[serde(default)]
+    error: Option<String>,
+    #[serde(default)]
+    inverse: Vec<InverseOperation>,
+}
+
+/// Read the inverse operations of the changes recorded in an audit log by the given run (or by
+/// the last run of the log), in the order in which they have to be applied to undo the run.
+pub(crate) fn load_inverse_operations(
+    audit_log: &Path,
+    run: Option<&str>,
+) -> anyhow::Result<Vec<InverseOperation>> {
+    let contents = std::fs::read_to_string(audit_log)
+        .with_context(|| format!("failed to read the audit log {}", audit_log.display()))?;
+    let records = contents
+        .lines()
+        .enumerate()
+        .map(|(index, line)| {
+            serde_json::from_str::<RollbackRecord>(line).with_context(|| {
+                format!(
+                    "failed to parse line {} of the audit log {}",
+                    index + 1,
+                    audit_log.display()
+                )
+            })
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    let run = match run {
+        Some(run) => {
+            if !records.iter().any(|record| record.run == run) {
+                anyhow::bail!(
+                    "the audit log {} has no records of the run `{run}`",
+                    audit_log.display()
+                );
+            }
+            run.to_string()
+        }
+        None => match records.last() {
+            Some(record) => record.run.clone(),
+            None => return Ok(Vec::new()),
+        },
+    };
+    info!("undoing the run `{run}`");
+
+    let mut operations = Vec::new();
+    for record in records.into_iter().filter(|record| record.run == run) {
+        // A failed change may have been partially applied, so it is left for manual review
+        if let Some(error) = record.error {
+            warn!(
+                "not undoing the change of {} which failed: {error}",
+                record.resource
+            );
+            continue;
+        }
+        operations.extend(record.inverse);
+    }
+    operations.reverse();
+    Ok(operations)
+}
+
+/// Undo the changes recorded in an audit log by the given run (or by the last run of the log).
+pub(crate) async fn rollback(
+    audit_log: &Path,
+    run: Option<&str>,
+    sync: &GitHubWrite,
+) -> anyhow::Result<()> {
+    let operations = load_inverse_operations(audit_log, run)?;
+    if operations.is_empty() {
+        info!("there are no changes to undo");
+    }
+    for operation in operations {
+        info!("undoing: {operation}");
+        operation
+            .apply(sync)
+            .await
+            .with_context(|| format!("failed to {operation}"))?;
+    }
+    Ok(())
+}
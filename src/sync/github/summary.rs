@@ -0,0 +1,264 @@
+//! Classification of the GitHub diff entries, so that reviewers can spot risky changes.
+
+use std::collections::BTreeSet;
+use std::fmt::Display;
+
+use super::{
+    AutolinkDiff, BranchProtectionDiffOperation, Diff, EnvironmentDiff, MemberDiff, RepoDiff,
+    RepoPermissionDiff, RulesetDiffOperation, TeamDiff,
+};
+
+/// How a diff entry affects GitHub
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum Severity {
+    /// Only creates things
+    Additive,
+    /// Changes existing things
+    Modifying,
+    /// Removes things or revokes access
+    Destructive,
+}
+
+/// A single change of the diff, as shown to reviewers
+pub(super) struct DiffEntry<'a> {
+    pub(super) org: &'a str,
+    pub(super) severity: Severity,
+    pub(super) title: String,
+    pub(super) details: &'a dyn Display,
+}
+
+/// Number of diff entries of each severity
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Serialize)]
+pub(crate) struct DiffSummary {
+    pub(crate) additive: usize,
+    pub(crate) modifying: usize,
+    pub(crate) destructive: usize,
+    /// Number of organizations with changes
+    pub(crate) orgs: usize,
+}
+
+impl Display for DiffSummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "+{} ~{} -{} across {} org{}",
+            self.additive,
+            self.modifying,
+            self.destructive,
+            self.orgs,
+            if self.orgs == 1 { "" } else { "s" }
+        )
+    }
+}
+
+impl Diff {
+    /// Count the entries of the diff by severity.
+    pub(crate) fn summary(&self) -> DiffSummary {
+        let entries = self.entries();
+        let count = |severity| entries.iter().filter(|e| e.severity == severity).count();
+        DiffSummary {
+            additive: count(Severity::Additive),
+            modifying: count(Severity::Modifying),
+            destructive: count(Severity::Destructive),
+            orgs: entries.iter().map(|e| e.org).collect::<BTreeSet<_>>().len(),
+        }
+    }
+
+    pub(super) fn entries(&self) -> Vec<DiffEntry<'_>> {
+        let mut entries = Vec::new();
+        for diff in &self.org_diffs {
+            entries.push(DiffEntry {
+                org: &diff.org,
+                severity: Severity::Modifying,
+                title: "Organization settings".to_string(),
+                details: diff,
+            });
+        }
+        for diff in &self.org_membership_diffs {
+            entries.push(DiffEntry {
+                org: &diff.org,
+                severity: Severity::Destructive,
+                title: format!(
+                    "Remove {} organization members",
+                    diff.members_to_remove.len()
+                ),
+                details: diff,
+            });
+        }
+        for diff in &self.org_owner_diffs {
+            let severity = if !diff.owners_to_remove.is_empty() && diff.demotions_allowed {
+                Severity::Destructive
+            } else {
+                Severity::Modifying
+            };
+            entries.push(DiffEntry {
+                org: &diff.org,
+                severity,
+                title: "Organization owners".to_string(),
+                details: diff,
+            });
+        }
+        for diff in &self.team_diffs {
+            let (org, title) = match diff {
+                TeamDiff::Create(c) => (&c.org, format!("Create team `{}`", c.name)),
+                TeamDiff::Edit(e) => (&e.org, format!("Edit team `{}`", e.name)),
+                TeamDiff::Delete(d) => (&d.org, format!("Delete team `{}`", d.name)),
+            };
+            entries.push(DiffEntry {
+                org,
+                severity: diff.severity(),
+                title,
+                details: diff,
+            });
+        }
+        for diff in &self.repo_diffs {
+            let (org, title) = match diff {
+                RepoDiff::Create(c) => (&c.org, format!("Create repo `{}`", c.name)),
+                RepoDiff::Update(u) => (&u.org, format!("Update repo `{}`", u.name)),
+            };
+            entries.push(DiffEntry {
+                org,
+                severity: diff.severity(),
+                title,
+                details: diff,
+            });
+        }
+        entries
+    }
+}
+
+impl TeamDiff {
+    fn severity(&self) -> Severity {
+        match self {
+            TeamDiff::Create(_) => Severity::Additive,
+            TeamDiff::Edit(e) => {
+                if e.member_diffs
+                    .iter()
+                    .any(|(_, diff)| matches!(diff, MemberDiff::Delete(_)))
+                {
+                    Severity::Destructive
+                } else {
+                    Severity::Modifying
+                }
+            }
+            TeamDiff::Delete(_) => Severity::Destructive,
+        }
+    }
+}
+
+impl RepoDiff {
+    fn severity(&self) -> Severity {
+        let u = match self {
+            RepoDiff::Create(_) => return Severity::Additive,
+            RepoDiff::Update(u) => u,
+        };
+        let (old_settings, new_settings) = &u.settings_diff;
+        let destructive = (new_settings.archived && !old_settings.archived)
+            || u.permission_diffs
+                .iter()
+                .any(|p| matches!(p.diff, RepoPermissionDiff::Delete(_)))
+            || u.branch_protection_diffs
+                .iter()
+                .any(|b| matches!(b.operation, BranchProtectionDiffOperation::Delete(..)))
+            || u.ruleset_diffs
+                .iter()
+                .any(|r| matches!(r.operation, RulesetDiffOperation::Delete(_)))
+            || u.environment_diffs
+                .iter()
+                .any(|e| matches!(e, EnvironmentDiff::Delete(_)))
+            || u.autolink_diffs
+                .iter()
+                .any(|a| matches!(a, AutolinkDiff::Delete { .. }))
+            || !u.deploy_key_diffs.is_empty();
+        if destructive {
+            Severity::Destructive
+        } else {
+            Severity::Modifying
+        }
+    }
+}
+
+/// Number of removals of each kind in the diff, checked against the deletion limit
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) struct DeletionCounts {
+    pub(crate) team_members: usize,
+    pub(crate) teams: usize,
+    pub(crate) repo_permissions: usize,
+    /// Branch protections and the rulesets that replace them
+    pub(crate) branch_protections: usize,
+    pub(crate) org_members: usize,
+    pub(crate) deploy_keys: usize,
+}
+
+impl Diff {
+    pub(crate) fn deletion_counts(&self) -> DeletionCounts {
+        let mut counts = DeletionCounts::default();
+        for diff in &self.org_membership_diffs {
+            counts.org_members += diff.members_to_remove.len();
+        }
+        for diff in &self.team_diffs {
+            match diff {
+                TeamDiff::Create(_) => {}
+                TeamDiff::Edit(e) => {
+                    counts.team_members += e
+                        .member_diffs
+                        .iter()
+                        .filter(|(_, diff)| matches!(diff, MemberDiff::Delete(_)))
+                        .count();
+                }
+                TeamDiff::Delete(_) => counts.teams += 1,
+            }
+        }
+        for diff in &self.repo_diffs {
+            let RepoDiff::Update(u) = diff else {
+                continue;
+            };
+            counts.repo_permissions += u
+                .permission_diffs
+                .iter()
+                .filter(|p| matches!(p.diff, RepoPermissionDiff::Delete(_)))
+                .count();
+            counts.branch_protections += u
+                .branch_protection_diffs
+                .iter()
+                .filter(|b| matches!(b.operation, BranchProtectionDiffOperation::Delete(..)))
+                .count();
+            counts.branch_protections += u
+                .ruleset_diffs
+                .iter()
+                .filter(|r| matches!(r.operation, RulesetDiffOperation::Delete(_)))
+                .count();
+            counts.deploy_keys += u.deploy_key_diffs.len();
+        }
+        counts
+    }
+
+    /// Refuse diffs that remove more than `limit` things of any kind.
+    ///
+    /// A bad data edit, or GitHub returning empty lists, should not be able to
+    /// mass-remove access.
+    pub(crate) fn check_deletion_limit(&self, limit: usize) -> anyhow::Result<()> {
+        let counts = self.deletion_counts();
+        let exceeded = [
+            ("team members", counts.team_members),
+            ("teams", counts.teams),
+            ("repository permissions", counts.repo_permissions),
+            ("branch protections", counts.branch_protections),
+            ("organization members", counts.org_members),
+            ("deploy keys", counts.deploy_keys),
+        ]
+        .into_iter()
+        .filter(|(_, count)| *count > limit)
+        .map(|(kind, count)| format!("{count} {kind}"))
+        .collect::<Vec<_>>();
+        if !exceeded.is_empty() {
+            anyhow::bail!(
+                "the diff would delete {}, more than the limit of {limit}; \
+                 review the diff and rerun with `--force` if it is intended",
+                exceeded.join(", ")
+            );
+        }
+        Ok(())
+    }
+}
@@ -1,7 +1,19 @@
 use crate::sync::github::tests::test_utils::{
     BranchProtectionBuilder, DEFAULT_ORG, DataModel, RepoData, TeamData,
 };
-use rust_team_data::v1::{self, BranchProtectionMode, RepoPermission};
+use rust_team_data::v1::{
+    self, BranchProtectionMode, MergeBot, MergeQueueMethod, MergeQueueSettings, RepoPermission,
+};
+
+use crate::sync::GitHubFilter;
+use crate::sync::github::audit::{AuditLog, AuditedChange};
+use crate::sync::github::interactive::Confirmation;
+use crate::sync::github::rollback::{InverseOperation, load_inverse_operations};
+use crate::sync::github::{
+    Access, ApplyOptions, ApplyReport, GitHubWrite, HttpClient, RepoDiff, TeamDiff, api,
+    diff_revisions, team_diff_batches,
+};
+use std::collections::{HashMap, HashSet};
 
 mod test_utils;
 
@@ -21,7 +33,7 @@ async fn team_create() {
     let gh = model.gh_model();
     model.create_team(TeamData::new("admins").gh_team(DEFAULT_ORG, "admins-gh", &[user, user2]));
     let team_diff = model.diff_teams(gh).await;
-    insta::assert_debug_snapshot!(team_diff, @r###"
+    insta::assert_debug_snapshot!(team_diff, @r#"
     [
         Create(
             CreateTeamDiff {
@@ -29,6 +41,7 @@ async fn team_create() {
                 name: "admins-gh",
                 description: "Managed by the rust-lang/team repository.",
                 privacy: Closed,
+                parent: None,
                 members: [
                     (
                         "mark",
@@ -42,7 +55,7 @@ async fn team_create() {
             },
         ),
     ]
-    "###);
+    "#);
 }
 
 #[tokio::test]
@@ -55,20 +68,18 @@ async fn team_add_member() {
 
     model.get_team("admins").add_gh_member("admins-gh", user2);
     let team_diff = model.diff_teams(gh).await;
-    insta::assert_debug_snapshot!(team_diff, @r###"
+    insta::assert_debug_snapshot!(team_diff, @r#"
     [
         Edit(
             EditTeamDiff {
                 org: "rust-lang",
                 name: "admins-gh",
+                slug: "admins-gh",
                 name_diff: None,
                 description_diff: None,
                 privacy_diff: None,
+                parent_diff: None,
                 member_diffs: [
-                    (
-                        "mark",
-                        Noop,
-                    ),
                     (
                         "jan",
                         Create(
@@ -76,10 +87,53 @@ async fn team_add_member() {
                         ),
                     ),
                 ],
+                unchanged_members: 1,
+            },
+        ),
+    ]
+    "#);
+}
+
+#[tokio::test]
+async fn team_promote_maintainer() {
+    let mut model = DataModel::default();
+    let user = model.create_user("mark");
+    let user2 = model.create_user("jan");
+    model.create_team(TeamData::new("admins").gh_team(DEFAULT_ORG, "admins-gh", &[user, user2]));
+    let gh = model.gh_model();
+
+    model
+        .get_team("admins")
+        .add_gh_maintainer("admins-gh", user2);
+
+    let team_diff = model.diff_teams(gh).await;
+    insta::assert_debug_snapshot!(team_diff, @r#"
+    [
+        Edit(
+            EditTeamDiff {
+                org: "rust-lang",
+                name: "admins-gh",
+                slug: "admins-gh",
+                name_diff: None,
+                description_diff: None,
+                privacy_diff: None,
+                parent_diff: None,
+                member_diffs: [
+                    (
+                        "jan",
+                        ChangeRole(
+                            (
+                                Member,
+                                Maintainer,
+                            ),
+                        ),
+                    ),
+                ],
+                unchanged_members: 1,
             },
         ),
     ]
-    "###);
+    "#);
 }
 
 #[tokio::test]
@@ -97,6 +151,32 @@ async fn team_dont_add_member_if_invitation_is_pending() {
     insta::assert_debug_snapshot!(team_diff, @"[]");
 }
 
+#[tokio::test]
+async fn team_member_renamed_only_in_data() {
+    let mut model = DataModel::default();
+    let user = model.create_user("mark");
+    model.create_team(TeamData::new("admins").gh_team(DEFAULT_ORG, "admins-gh", &[user]));
+    let gh = model.gh_model();
+
+    model.rename_user(user, "mark-renamed");
+
+    let team_diff = model.diff_teams(gh).await;
+    insta::assert_debug_snapshot!(team_diff, @"[]");
+}
+
+#[tokio::test]
+async fn org_member_renamed_only_in_data() {
+    let mut model = DataModel::default();
+    let user = model.create_user("sakura");
+    model.create_team(TeamData::new("team-1").gh_team(DEFAULT_ORG, "members-gh", &[user]));
+    let gh = model.gh_model();
+
+    model.rename_user(user, "sakura-renamed");
+
+    let gh_org_diff = model.diff_org_membership(gh).await;
+    insta::assert_debug_snapshot!(gh_org_diff, @"[]");
+}
+
 #[tokio::test]
 async fn remove_org_members() {
     let mut model = DataModel::default();
@@ -125,6 +205,91 @@ async fn remove_org_members() {
     "#);
 }
 
+#[tokio::test]
+async fn org_owners_promote_and_demote() {
+    let mut model = DataModel::default();
+    let user = model.create_user("sakura");
+    let user2 = model.create_user("mark");
+    model.create_team(TeamData::new("admins").org_owners_gh_team(
+        DEFAULT_ORG,
+        "admins-gh",
+        &[user],
+    ));
+    model.create_team(TeamData::new("team-1").gh_team(DEFAULT_ORG, "members-gh", &[user2]));
+    let mut gh = model.gh_model();
+    gh.add_member(DEFAULT_ORG, "martin");
+    gh.add_owner(DEFAULT_ORG, "martin");
+
+    // The sync bot has to stay an owner.
+    let bot = "my-bot";
+    gh.add_member(DEFAULT_ORG, bot);
+    gh.add_owner(DEFAULT_ORG, bot);
+    model.add_allowed_org_member(bot);
+
+    let gh_org_diff = model.diff_org_owners(gh).await;
+    insta::assert_debug_snapshot!(gh_org_diff, @r#"
+    [
+        OrgOwnersDiff {
+            org: "rust-lang",
+            owners_to_add: [
+                "sakura",
+            ],
+            owners_to_remove: [
+                "martin",
+            ],
+            demotions_allowed: false,
+        },
+    ]
+    "#);
+}
+
+#[tokio::test]
+async fn org_owners_not_managed_without_owner_team() {
+    let mut model = DataModel::default();
+    let user = model.create_user("sakura");
+    model.create_team(TeamData::new("team-1").gh_team(DEFAULT_ORG, "members-gh", &[user]));
+    let mut gh = model.gh_model();
+    gh.add_member(DEFAULT_ORG, "martin");
+    gh.add_owner(DEFAULT_ORG, "martin");
+
+    let gh_org_diff = model.diff_org_owners(gh).await;
+    assert!(gh_org_diff.is_empty());
+}
+
+#[tokio::test]
+async fn org_update_settings() {
+    let mut model = DataModel::default();
+    model.create_org(DEFAULT_ORG);
+    let gh = model.gh_model();
+    assert!(model.diff_orgs(gh).await.is_empty());
+
+    let gh = model.gh_model();
+    let org = model.get_org(DEFAULT_ORG);
+    org.default_repository_permission = v1::DefaultRepoPermission::None;
+    org.members_can_fork_private_repositories = true;
+
+    let org_diff = model.diff_orgs(gh).await;
+    insta::assert_debug_snapshot!(org_diff, @r#"
+    [
+        OrgDiff {
+            org: "rust-lang",
+            settings_diff: (
+                OrgSettings {
+                    default_repository_permission: Read,
+                    members_can_create_repositories: false,
+                    members_can_fork_private_repositories: false,
+                },
+                OrgSettings {
+                    default_repository_permission: None,
+                    members_can_create_repositories: false,
+                    members_can_fork_private_repositories: true,
+                },
+            ),
+        },
+    ]
+    "#);
+}
+
 #[tokio::test]
 async fn team_remove_member() {
     let mut model = DataModel::default();
@@ -138,29 +303,30 @@ async fn team_remove_member() {
         .remove_gh_member("admins-gh", user2);
 
     let team_diff = model.diff_teams(gh).await;
-    insta::assert_debug_snapshot!(team_diff, @r###"
+    insta::assert_debug_snapshot!(team_diff, @r#"
     [
         Edit(
             EditTeamDiff {
                 org: "rust-lang",
                 name: "admins-gh",
+                slug: "admins-gh",
                 name_diff: None,
                 description_diff: None,
                 privacy_diff: None,
+                parent_diff: None,
                 member_diffs: [
-                    (
-                        "mark",
-                        Noop,
-                    ),
                     (
                         "jan",
-                        Delete,
+                        Delete(
+                            Member,
+                        ),
                     ),
                 ],
+                unchanged_members: 1,
             },
         ),
     ]
-    "###);
+    "#);
 }
 
 #[tokio::test]
@@ -175,6 +341,7 @@ async fn team_delete() {
             .gh_team(DEFAULT_ORG, "admins-gh", &[user])
             .gh_team(DEFAULT_ORG, "users-gh", &[user]),
     );
+    model.delete_unmanaged_teams_in(DEFAULT_ORG);
     let gh = model.gh_model();
 
     model.get_team("admins").remove_gh_team("users-gh");
@@ -193,6 +360,157 @@ async fn team_delete() {
     "#);
 }
 
+#[tokio::test]
+async fn team_rename() {
+    let mut model = DataModel::default();
+    let user = model.create_user("mark");
+    model.create_team(TeamData::new("admins").gh_team(DEFAULT_ORG, "admins-gh", &[user]));
+    model.delete_unmanaged_teams_in(DEFAULT_ORG);
+    let gh = model.gh_model();
+
+    model
+        .get_team("admins")
+        .rename_gh_team("admins-gh", "root-gh", 0);
+
+    let team_diff = model.diff_teams(gh).await;
+    insta::assert_debug_snapshot!(team_diff, @r#"
+    [
+        Edit(
+            EditTeamDiff {
+                org: "rust-lang",
+                name: "admins-gh",
+                slug: "admins-gh",
+                name_diff: Some(
+                    "root-gh",
+                ),
+                description_diff: None,
+                privacy_diff: None,
+                parent_diff: None,
+                member_diffs: [],
+                unchanged_members: 1,
+            },
+        ),
+    ]
+    "#);
+}
+
+#[tokio::test]
+async fn team_change_parent() {
+    let mut model = DataModel::default();
+    let user = model.create_user("mark");
+    model.create_team(
+        TeamData::new("admins")
+            .gh_team(DEFAULT_ORG, "admins-gh", &[user])
+            .gh_team(DEFAULT_ORG, "users-gh", &[user]),
+    );
+    let gh = model.gh_model();
+
+    model
+        .get_team("admins")
+        .set_gh_parent("users-gh", Some("admins-gh"));
+
+    let team_diff = model.diff_teams(gh).await;
+    insta::assert_debug_snapshot!(team_diff, @r#"
+    [
+        Edit(
+            EditTeamDiff {
+                org: "rust-lang",
+                name: "users-gh",
+                slug: "users-gh",
+                name_diff: None,
+                description_diff: None,
+                privacy_diff: None,
+                parent_diff: Some(
+                    (
+                        None,
+                        Some(
+                            ParentTeam {
+                                name: "admins-gh",
+                                id: Some(
+                                    0,
+                                ),
+                            },
+                        ),
+                    ),
+                ),
+                member_diffs: [],
+                unchanged_members: 1,
+            },
+        ),
+    ]
+    "#);
+}
+
+#[tokio::test]
+async fn team_change_description_and_privacy() {
+    let mut model = DataModel::default();
+    let user = model.create_user("mark");
+    model.create_team(TeamData::new("admins").gh_team(DEFAULT_ORG, "admins-gh", &[user]));
+    let gh = model.gh_model();
+
+    let team = model.get_team("admins");
+    team.set_gh_description("admins-gh", "Secret admins");
+    team.set_gh_privacy("admins-gh", v1::GitHubTeamPrivacy::Secret);
+
+    let team_diff = model.diff_teams(gh).await;
+    insta::assert_debug_snapshot!(team_diff, @r#"
+    [
+        Edit(
+            EditTeamDiff {
+                org: "rust-lang",
+                name: "admins-gh",
+                slug: "admins-gh",
+                name_diff: None,
+                description_diff: Some(
+                    (
+                        "Managed by the rust-lang/team repository.",
+                        "Secret admins",
+                    ),
+                ),
+                privacy_diff: Some(
+                    (
+                        Closed,
+                        Secret,
+                    ),
+                ),
+                parent_diff: None,
+                member_diffs: [],
+                unchanged_members: 1,
+            },
+        ),
+    ]
+    "#);
+}
+
+#[tokio::test]
+async fn team_create_nested_after_parent() {
+    let mut model = DataModel::default();
+    let user = model.create_user("mark");
+    let gh = model.gh_model();
+    model.create_team(TeamData::new("users").gh_team(DEFAULT_ORG, "users-gh", &[user]));
+    model.create_team(TeamData::new("admins").gh_team(DEFAULT_ORG, "admins-gh", &[user]));
+    model
+        .get_team("users")
+        .set_gh_parent("users-gh", Some("admins-gh"));
+
+    let team_diff = model.diff_teams(gh).await;
+    let created = team_diff
+        .iter()
+        .map(|diff| match diff {
+            TeamDiff::Create(c) => (
+                c.name.as_str(),
+                c.parent.as_ref().map(|p| (p.name.as_str(), p.id)),
+            ),
+            _ => panic!("unexpected team diff {diff:?}"),
+        })
+        .collect::<Vec<_>>();
+    // The ID of the parent is only known once it's created
+    assert_eq!(
+        created,
+        vec![("admins-gh", None), ("users-gh", Some(("admins-gh", None)))]
+    );
+}
+
 #[tokio::test]
 async fn repo_noop() {
     let model = DataModel::default();
@@ -222,18 +540,40 @@ async fn repo_change_description() {
                         homepage: None,
                         archived: false,
                         auto_merge_enabled: false,
+                        allow_squash_merge: true,
+                        allow_merge_commit: true,
+                        allow_rebase_merge: true,
+                        delete_branch_on_merge: false,
+                        has_issues: true,
+                        has_wiki: true,
+                        has_projects: true,
+                        has_discussions: false,
                     },
                     RepoSettings {
                         description: "bar",
                         homepage: None,
                         archived: false,
                         auto_merge_enabled: false,
+                        allow_squash_merge: true,
+                        allow_merge_commit: true,
+                        allow_rebase_merge: true,
+                        delete_branch_on_merge: false,
+                        has_issues: true,
+                        has_wiki: true,
+                        has_projects: true,
+                        has_discussions: false,
                     },
                 ),
+                default_branch_diff: None,
                 permission_diffs: [],
                 branch_protection_diffs: [],
                 ruleset_diffs: [],
                 environment_diffs: [],
+                label_diffs: [],
+                webhook_diffs: [],
+                autolink_diffs: [],
+                deploy_key_diffs: [],
+                actions_diff: None,
             },
         ),
     ]
@@ -263,6 +603,14 @@ async fn repo_change_homepage() {
                         ),
                         archived: false,
                         auto_merge_enabled: false,
+                        allow_squash_merge: true,
+                        allow_merge_commit: true,
+                        allow_rebase_merge: true,
+                        delete_branch_on_merge: false,
+                        has_issues: true,
+                        has_wiki: true,
+                        has_projects: true,
+                        has_discussions: false,
                     },
                     RepoSettings {
                         description: "",
@@ -271,12 +619,26 @@ async fn repo_change_homepage() {
                         ),
                         archived: false,
                         auto_merge_enabled: false,
+                        allow_squash_merge: true,
+                        allow_merge_commit: true,
+                        allow_rebase_merge: true,
+                        delete_branch_on_merge: false,
+                        has_issues: true,
+                        has_wiki: true,
+                        has_projects: true,
+                        has_discussions: false,
                     },
                 ),
+                default_branch_diff: None,
                 permission_diffs: [],
                 branch_protection_diffs: [],
                 ruleset_diffs: [],
                 environment_diffs: [],
+                label_diffs: [],
+                webhook_diffs: [],
+                autolink_diffs: [],
+                deploy_key_diffs: [],
+                actions_diff: None,
             },
         ),
     ]
@@ -284,52 +646,417 @@ async fn repo_change_homepage() {
 }
 
 #[tokio::test]
-async fn repo_create() {
+async fn repo_change_merge_methods() {
     let mut model = DataModel::default();
+    model.create_repo(RepoData::new("repo1"));
     let gh = model.gh_model();
+    model.get_repo("repo1").allow_merge_commit = Some(false);
+    model.get_repo("repo1").allow_rebase_merge = Some(false);
 
-    model.create_repo(
-        RepoData::new("repo1")
-            .description("foo".to_string())
-            .member("user1", RepoPermission::Write)
-            .team("team1", RepoPermission::Triage)
-            .branch_protections(vec![
-                BranchProtectionBuilder::pr_required("main", &["test"], 1).build(),
-            ]),
-    );
     let diff = model.diff_repos(gh).await;
     insta::assert_debug_snapshot!(diff, @r#"
     [
-        Create(
-            CreateRepoDiff {
+        Update(
+            UpdateRepoDiff {
                 org: "rust-lang",
                 name: "repo1",
-                settings: RepoSettings {
-                    description: "foo",
-                    homepage: None,
-                    archived: false,
-                    auto_merge_enabled: false,
-                },
-                permissions: [
-                    RepoPermissionAssignmentDiff {
-                        collaborator: Team(
-                            "team1",
-                        ),
-                        diff: Create(
-                            Triage,
-                        ),
-                    },
-                    RepoPermissionAssignmentDiff {
-                        collaborator: User(
-                            "user1",
-                        ),
-                        diff: Create(
-                            Write,
-                        ),
+                repo_node_id: "0",
+                settings_diff: (
+                    RepoSettings {
+                        description: "",
+                        homepage: None,
+                        archived: false,
+                        auto_merge_enabled: false,
+                        allow_squash_merge: true,
+                        allow_merge_commit: true,
+                        allow_rebase_merge: true,
+                        delete_branch_on_merge: false,
+                        has_issues: true,
+                        has_wiki: true,
+                        has_projects: true,
+                        has_discussions: false,
                     },
-                ],
-                branch_protections: [
-                    (
+                    RepoSettings {
+                        description: "",
+                        homepage: None,
+                        archived: false,
+                        auto_merge_enabled: false,
+                        allow_squash_merge: true,
+                        allow_merge_commit: false,
+                        allow_rebase_merge: false,
+                        delete_branch_on_merge: false,
+                        has_issues: true,
+                        has_wiki: true,
+                        has_projects: true,
+                        has_discussions: false,
+                    },
+                ),
+                default_branch_diff: None,
+                permission_diffs: [],
+                branch_protection_diffs: [],
+                ruleset_diffs: [],
+                environment_diffs: [],
+                label_diffs: [],
+                webhook_diffs: [],
+                autolink_diffs: [],
+                deploy_key_diffs: [],
+                actions_diff: None,
+            },
+        ),
+    ]
+    "#);
+}
+
+#[tokio::test]
+async fn repo_ignore_unmanaged_merge_methods() {
+    let mut model = DataModel::default();
+    model.create_repo(RepoData::new("repo1").allow_merge_commit(Some(false)));
+    let gh = model.gh_model();
+    model.get_repo("repo1").allow_merge_commit = None;
+
+    let diff = model.diff_repos(gh).await;
+    assert!(diff.is_empty());
+}
+
+#[tokio::test]
+async fn repo_change_delete_branch_on_merge() {
+    let mut model = DataModel::default();
+    model.create_repo(RepoData::new("repo1"));
+    let gh = model.gh_model();
+    model.get_repo("repo1").delete_branch_on_merge = Some(true);
+
+    let diff = model.diff_repos(gh).await;
+    insta::assert_debug_snapshot!(diff, @r#"
+    [
+        Update(
+            UpdateRepoDiff {
+                org: "rust-lang",
+                name: "repo1",
+                repo_node_id: "0",
+                settings_diff: (
+                    RepoSettings {
+                        description: "",
+                        homepage: None,
+                        archived: false,
+                        auto_merge_enabled: false,
+                        allow_squash_merge: true,
+                        allow_merge_commit: true,
+                        allow_rebase_merge: true,
+                        delete_branch_on_merge: false,
+                        has_issues: true,
+                        has_wiki: true,
+                        has_projects: true,
+                        has_discussions: false,
+                    },
+                    RepoSettings {
+                        description: "",
+                        homepage: None,
+                        archived: false,
+                        auto_merge_enabled: false,
+                        allow_squash_merge: true,
+                        allow_merge_commit: true,
+                        allow_rebase_merge: true,
+                        delete_branch_on_merge: true,
+                        has_issues: true,
+                        has_wiki: true,
+                        has_projects: true,
+                        has_discussions: false,
+                    },
+                ),
+                default_branch_diff: None,
+                permission_diffs: [],
+                branch_protection_diffs: [],
+                ruleset_diffs: [],
+                environment_diffs: [],
+                label_diffs: [],
+                webhook_diffs: [],
+                autolink_diffs: [],
+                deploy_key_diffs: [],
+                actions_diff: None,
+            },
+        ),
+    ]
+    "#);
+}
+
+#[tokio::test]
+async fn repo_ignore_unmanaged_delete_branch_on_merge() {
+    let mut model = DataModel::default();
+    model.create_repo(RepoData::new("repo1").delete_branch_on_merge(Some(true)));
+    let gh = model.gh_model();
+    model.get_repo("repo1").delete_branch_on_merge = None;
+
+    let diff = model.diff_repos(gh).await;
+    assert!(diff.is_empty());
+}
+
+#[tokio::test]
+async fn repo_change_features() {
+    let mut model = DataModel::default();
+    model.create_repo(RepoData::new("repo1"));
+    let gh = model.gh_model();
+    model.get_repo("repo1").has_wiki = Some(false);
+    model.get_repo("repo1").has_discussions = Some(true);
+
+    let diff = model.diff_repos(gh).await;
+    insta::assert_debug_snapshot!(diff, @r#"
+    [
+        Update(
+            UpdateRepoDiff {
+                org: "rust-lang",
+                name: "repo1",
+                repo_node_id: "0",
+                settings_diff: (
+                    RepoSettings {
+                        description: "",
+                        homepage: None,
+                        archived: false,
+                        auto_merge_enabled: false,
+                        allow_squash_merge: true,
+                        allow_merge_commit: true,
+                        allow_rebase_merge: true,
+                        delete_branch_on_merge: false,
+                        has_issues: true,
+                        has_wiki: true,
+                        has_projects: true,
+                        has_discussions: false,
+                    },
+                    RepoSettings {
+                        description: "",
+                        homepage: None,
+                        archived: false,
+                        auto_merge_enabled: false,
+                        allow_squash_merge: true,
+                        allow_merge_commit: true,
+                        allow_rebase_merge: true,
+                        delete_branch_on_merge: false,
+                        has_issues: true,
+                        has_wiki: false,
+                        has_projects: true,
+                        has_discussions: true,
+                    },
+                ),
+                default_branch_diff: None,
+                permission_diffs: [],
+                branch_protection_diffs: [],
+                ruleset_diffs: [],
+                environment_diffs: [],
+                label_diffs: [],
+                webhook_diffs: [],
+                autolink_diffs: [],
+                deploy_key_diffs: [],
+                actions_diff: None,
+            },
+        ),
+    ]
+    "#);
+}
+
+#[tokio::test]
+async fn repo_ignore_unmanaged_features() {
+    let mut model = DataModel::default();
+    model.create_repo(
+        RepoData::new("repo1")
+            .has_wiki(Some(false))
+            .has_discussions(Some(true)),
+    );
+    let gh = model.gh_model();
+    model.get_repo("repo1").has_wiki = None;
+    model.get_repo("repo1").has_discussions = None;
+
+    let diff = model.diff_repos(gh).await;
+    assert!(diff.is_empty());
+}
+
+#[tokio::test]
+async fn repo_rename_default_branch() {
+    let mut model = DataModel::default();
+    model.create_repo(RepoData::new("repo1").default_branch(Some("master".to_string())));
+    let gh = model.gh_model();
+    model.get_repo("repo1").default_branch = Some("main".to_string());
+
+    let diff = model.diff_repos(gh).await;
+    insta::assert_debug_snapshot!(diff, @r#"
+    [
+        Update(
+            UpdateRepoDiff {
+                org: "rust-lang",
+                name: "repo1",
+                repo_node_id: "0",
+                settings_diff: (
+                    RepoSettings {
+                        description: "",
+                        homepage: None,
+                        archived: false,
+                        auto_merge_enabled: false,
+                        allow_squash_merge: true,
+                        allow_merge_commit: true,
+                        allow_rebase_merge: true,
+                        delete_branch_on_merge: false,
+                        has_issues: true,
+                        has_wiki: true,
+                        has_projects: true,
+                        has_discussions: false,
+                    },
+                    RepoSettings {
+                        description: "",
+                        homepage: None,
+                        archived: false,
+                        auto_merge_enabled: false,
+                        allow_squash_merge: true,
+                        allow_merge_commit: true,
+                        allow_rebase_merge: true,
+                        delete_branch_on_merge: false,
+                        has_issues: true,
+                        has_wiki: true,
+                        has_projects: true,
+                        has_discussions: false,
+                    },
+                ),
+                default_branch_diff: Some(
+                    Rename {
+                        old: "master",
+                        new: "main",
+                    },
+                ),
+                permission_diffs: [],
+                branch_protection_diffs: [],
+                ruleset_diffs: [],
+                environment_diffs: [],
+                label_diffs: [],
+                webhook_diffs: [],
+                autolink_diffs: [],
+                deploy_key_diffs: [],
+                actions_diff: None,
+            },
+        ),
+    ]
+    "#);
+}
+
+#[tokio::test]
+async fn repo_switch_default_branch() {
+    let mut model = DataModel::default();
+    model.create_repo(RepoData::new("repo1").default_branch(Some("master".to_string())));
+    let mut gh = model.gh_model();
+    gh.add_branch(DEFAULT_ORG, "repo1", "main");
+    model.get_repo("repo1").default_branch = Some("main".to_string());
+
+    let diff = model.diff_repos(gh).await;
+    insta::assert_debug_snapshot!(diff, @r#"
+    [
+        Update(
+            UpdateRepoDiff {
+                org: "rust-lang",
+                name: "repo1",
+                repo_node_id: "0",
+                settings_diff: (
+                    RepoSettings {
+                        description: "",
+                        homepage: None,
+                        archived: false,
+                        auto_merge_enabled: false,
+                        allow_squash_merge: true,
+                        allow_merge_commit: true,
+                        allow_rebase_merge: true,
+                        delete_branch_on_merge: false,
+                        has_issues: true,
+                        has_wiki: true,
+                        has_projects: true,
+                        has_discussions: false,
+                    },
+                    RepoSettings {
+                        description: "",
+                        homepage: None,
+                        archived: false,
+                        auto_merge_enabled: false,
+                        allow_squash_merge: true,
+                        allow_merge_commit: true,
+                        allow_rebase_merge: true,
+                        delete_branch_on_merge: false,
+                        has_issues: true,
+                        has_wiki: true,
+                        has_projects: true,
+                        has_discussions: false,
+                    },
+                ),
+                default_branch_diff: Some(
+                    Switch {
+                        old: "master",
+                        new: "main",
+                    },
+                ),
+                permission_diffs: [],
+                branch_protection_diffs: [],
+                ruleset_diffs: [],
+                environment_diffs: [],
+                label_diffs: [],
+                webhook_diffs: [],
+                autolink_diffs: [],
+                deploy_key_diffs: [],
+                actions_diff: None,
+            },
+        ),
+    ]
+    "#);
+}
+
+#[tokio::test]
+async fn repo_create() {
+    let mut model = DataModel::default();
+    let gh = model.gh_model();
+
+    model.create_repo(
+        RepoData::new("repo1")
+            .description("foo".to_string())
+            .member("user1", RepoPermission::Write)
+            .team("team1", RepoPermission::Triage)
+            .branch_protections(vec![
+                BranchProtectionBuilder::pr_required("main", &["test"], 1).build(),
+            ]),
+    );
+    let diff = model.diff_repos(gh).await;
+    insta::assert_debug_snapshot!(diff, @r#"
+    [
+        Create(
+            CreateRepoDiff {
+                org: "rust-lang",
+                name: "repo1",
+                default_branch: None,
+                settings: RepoSettings {
+                    description: "foo",
+                    homepage: None,
+                    archived: false,
+                    auto_merge_enabled: false,
+                    allow_squash_merge: true,
+                    allow_merge_commit: true,
+                    allow_rebase_merge: true,
+                    delete_branch_on_merge: false,
+                    has_issues: true,
+                    has_wiki: true,
+                    has_projects: true,
+                    has_discussions: false,
+                },
+                permissions: [
+                    RepoPermissionAssignmentDiff {
+                        collaborator: Team(
+                            "team1",
+                        ),
+                        diff: Create(
+                            Triage,
+                        ),
+                    },
+                    RepoPermissionAssignmentDiff {
+                        collaborator: User(
+                            "user1",
+                        ),
+                        diff: Create(
+                            Write,
+                        ),
+                    },
+                ],
+                branch_protections: [
+                    (
                         "main",
                         BranchProtection {
                             pattern: "main",
@@ -341,12 +1068,22 @@ async fn repo_create() {
                                 "test",
                             ],
                             push_allowances: [],
+                            bypass_pull_request_allowances: [],
                             requires_approving_reviews: true,
+                            requires_code_owner_reviews: false,
+                            requires_commit_signatures: false,
+                            requires_linear_history: false,
+                            requires_conversation_resolution: false,
                         },
                     ),
                 ],
                 rulesets: [],
                 environments: [],
+                labels: [],
+                webhooks: [],
+                autolinks: [],
+                actions: None,
+                template: None,
             },
         ),
     ]
@@ -381,14 +1118,31 @@ async fn repo_add_member() {
                         homepage: None,
                         archived: false,
                         auto_merge_enabled: false,
+                        allow_squash_merge: true,
+                        allow_merge_commit: true,
+                        allow_rebase_merge: true,
+                        delete_branch_on_merge: false,
+                        has_issues: true,
+                        has_wiki: true,
+                        has_projects: true,
+                        has_discussions: false,
                     },
                     RepoSettings {
                         description: "",
                         homepage: None,
                         archived: false,
                         auto_merge_enabled: false,
+                        allow_squash_merge: true,
+                        allow_merge_commit: true,
+                        allow_rebase_merge: true,
+                        delete_branch_on_merge: false,
+                        has_issues: true,
+                        has_wiki: true,
+                        has_projects: true,
+                        has_discussions: false,
                     },
                 ),
+                default_branch_diff: None,
                 permission_diffs: [
                     RepoPermissionAssignmentDiff {
                         collaborator: User(
@@ -402,6 +1156,11 @@ async fn repo_add_member() {
                 branch_protection_diffs: [],
                 ruleset_diffs: [],
                 environment_diffs: [],
+                label_diffs: [],
+                webhook_diffs: [],
+                autolink_diffs: [],
+                deploy_key_diffs: [],
+                actions_diff: None,
             },
         ),
     ]
@@ -435,14 +1194,31 @@ async fn repo_change_member_permissions() {
                         homepage: None,
                         archived: false,
                         auto_merge_enabled: false,
+                        allow_squash_merge: true,
+                        allow_merge_commit: true,
+                        allow_rebase_merge: true,
+                        delete_branch_on_merge: false,
+                        has_issues: true,
+                        has_wiki: true,
+                        has_projects: true,
+                        has_discussions: false,
                     },
                     RepoSettings {
                         description: "",
                         homepage: None,
                         archived: false,
                         auto_merge_enabled: false,
+                        allow_squash_merge: true,
+                        allow_merge_commit: true,
+                        allow_rebase_merge: true,
+                        delete_branch_on_merge: false,
+                        has_issues: true,
+                        has_wiki: true,
+                        has_projects: true,
+                        has_discussions: false,
                     },
                 ),
+                default_branch_diff: None,
                 permission_diffs: [
                     RepoPermissionAssignmentDiff {
                         collaborator: User(
@@ -457,12 +1233,31 @@ async fn repo_change_member_permissions() {
                 branch_protection_diffs: [],
                 ruleset_diffs: [],
                 environment_diffs: [],
+                label_diffs: [],
+                webhook_diffs: [],
+                autolink_diffs: [],
+                deploy_key_diffs: [],
+                actions_diff: None,
             },
         ),
     ]
     "#);
 }
 
+#[tokio::test]
+async fn repo_member_renamed_only_in_data() {
+    let mut model = DataModel::default();
+    model.create_repo(RepoData::new("repo1").member("user1", RepoPermission::Write));
+
+    let gh = model.gh_model();
+    let member = model.get_repo("repo1").members.last_mut().unwrap();
+    member.name = "user1-renamed".to_string();
+    member.former_names = vec!["user1".to_string()];
+
+    let diff = model.diff_repos(gh).await;
+    insta::assert_debug_snapshot!(diff, @"[]");
+}
+
 #[tokio::test]
 async fn repo_remove_member() {
     let mut model = DataModel::default();
@@ -485,14 +1280,31 @@ async fn repo_remove_member() {
                         homepage: None,
                         archived: false,
                         auto_merge_enabled: false,
+                        allow_squash_merge: true,
+                        allow_merge_commit: true,
+                        allow_rebase_merge: true,
+                        delete_branch_on_merge: false,
+                        has_issues: true,
+                        has_wiki: true,
+                        has_projects: true,
+                        has_discussions: false,
                     },
                     RepoSettings {
                         description: "",
                         homepage: None,
                         archived: false,
                         auto_merge_enabled: false,
+                        allow_squash_merge: true,
+                        allow_merge_commit: true,
+                        allow_rebase_merge: true,
+                        delete_branch_on_merge: false,
+                        has_issues: true,
+                        has_wiki: true,
+                        has_projects: true,
+                        has_discussions: false,
                     },
                 ),
+                default_branch_diff: None,
                 permission_diffs: [
                     RepoPermissionAssignmentDiff {
                         collaborator: User(
@@ -506,6 +1318,11 @@ async fn repo_remove_member() {
                 branch_protection_diffs: [],
                 ruleset_diffs: [],
                 environment_diffs: [],
+                label_diffs: [],
+                webhook_diffs: [],
+                autolink_diffs: [],
+                deploy_key_diffs: [],
+                actions_diff: None,
             },
         ),
     ]
@@ -536,14 +1353,31 @@ async fn repo_add_team() {
                         homepage: None,
                         archived: false,
                         auto_merge_enabled: false,
+                        allow_squash_merge: true,
+                        allow_merge_commit: true,
+                        allow_rebase_merge: true,
+                        delete_branch_on_merge: false,
+                        has_issues: true,
+                        has_wiki: true,
+                        has_projects: true,
+                        has_discussions: false,
                     },
                     RepoSettings {
                         description: "",
                         homepage: None,
                         archived: false,
                         auto_merge_enabled: false,
+                        allow_squash_merge: true,
+                        allow_merge_commit: true,
+                        allow_rebase_merge: true,
+                        delete_branch_on_merge: false,
+                        has_issues: true,
+                        has_wiki: true,
+                        has_projects: true,
+                        has_discussions: false,
                     },
                 ),
+                default_branch_diff: None,
                 permission_diffs: [
                     RepoPermissionAssignmentDiff {
                         collaborator: Team(
@@ -557,6 +1391,11 @@ async fn repo_add_team() {
                 branch_protection_diffs: [],
                 ruleset_diffs: [],
                 environment_diffs: [],
+                label_diffs: [],
+                webhook_diffs: [],
+                autolink_diffs: [],
+                deploy_key_diffs: [],
+                actions_diff: None,
             },
         ),
     ]
@@ -585,14 +1424,31 @@ async fn repo_change_team_permissions() {
                         homepage: None,
                         archived: false,
                         auto_merge_enabled: false,
+                        allow_squash_merge: true,
+                        allow_merge_commit: true,
+                        allow_rebase_merge: true,
+                        delete_branch_on_merge: false,
+                        has_issues: true,
+                        has_wiki: true,
+                        has_projects: true,
+                        has_discussions: false,
                     },
                     RepoSettings {
                         description: "",
                         homepage: None,
                         archived: false,
                         auto_merge_enabled: false,
+                        allow_squash_merge: true,
+                        allow_merge_commit: true,
+                        allow_rebase_merge: true,
+                        delete_branch_on_merge: false,
+                        has_issues: true,
+                        has_wiki: true,
+                        has_projects: true,
+                        has_discussions: false,
                     },
                 ),
+                default_branch_diff: None,
                 permission_diffs: [
                     RepoPermissionAssignmentDiff {
                         collaborator: Team(
@@ -607,6 +1463,11 @@ async fn repo_change_team_permissions() {
                 branch_protection_diffs: [],
                 ruleset_diffs: [],
                 environment_diffs: [],
+                label_diffs: [],
+                webhook_diffs: [],
+                autolink_diffs: [],
+                deploy_key_diffs: [],
+                actions_diff: None,
             },
         ),
     ]
@@ -614,13 +1475,18 @@ async fn repo_change_team_permissions() {
 }
 
 #[tokio::test]
-async fn repo_remove_team() {
+async fn repo_add_member_with_pending_invitation() {
     let mut model = DataModel::default();
-    model.create_repo(RepoData::new("repo1").team("team1", RepoPermission::Write));
+    model.create_repo(RepoData::new("repo1"));
 
-    let gh = model.gh_model();
-    model.get_repo("repo1").teams.clear();
+    let mut gh = model.gh_model();
+    gh.add_repo_invitation(DEFAULT_ORG, "repo1", "user1", api::RepoPermission::Write);
+    gh.add_repo_invitation(DEFAULT_ORG, "repo1", "user2", api::RepoPermission::Triage);
+    let repo = model.get_repo("repo1");
+    repo.add_member("user1", RepoPermission::Write);
+    repo.add_member("user2", RepoPermission::Write);
 
+    // Only the invitation with a different permission has to be sent again
     let diff = model.diff_repos(gh).await;
     insta::assert_debug_snapshot!(diff, @r#"
     [
@@ -635,14 +1501,193 @@ async fn repo_remove_team() {
                         homepage: None,
                         archived: false,
                         auto_merge_enabled: false,
+                        allow_squash_merge: true,
+                        allow_merge_commit: true,
+                        allow_rebase_merge: true,
+                        delete_branch_on_merge: false,
+                        has_issues: true,
+                        has_wiki: true,
+                        has_projects: true,
+                        has_discussions: false,
                     },
                     RepoSettings {
                         description: "",
                         homepage: None,
                         archived: false,
                         auto_merge_enabled: false,
+                        allow_squash_merge: true,
+                        allow_merge_commit: true,
+                        allow_rebase_merge: true,
+                        delete_branch_on_merge: false,
+                        has_issues: true,
+                        has_wiki: true,
+                        has_projects: true,
+                        has_discussions: false,
                     },
                 ),
+                default_branch_diff: None,
+                permission_diffs: [
+                    RepoPermissionAssignmentDiff {
+                        collaborator: User(
+                            "user2",
+                        ),
+                        diff: Create(
+                            Write,
+                        ),
+                    },
+                ],
+                branch_protection_diffs: [],
+                ruleset_diffs: [],
+                environment_diffs: [],
+                label_diffs: [],
+                webhook_diffs: [],
+                autolink_diffs: [],
+                deploy_key_diffs: [],
+                actions_diff: None,
+            },
+        ),
+    ]
+    "#);
+}
+
+#[tokio::test]
+async fn repo_change_team_permissions_custom_role() {
+    let mut model = DataModel::default();
+    model.create_repo(RepoData::new("repo1").team("team1", RepoPermission::Triage));
+
+    let mut gh = model.gh_model();
+    gh.add_custom_repo_role(DEFAULT_ORG, "triage-plus");
+    model.get_repo("repo1").teams.last_mut().unwrap().permission =
+        RepoPermission::Custom("triage-plus".to_string());
+
+    let diff = model.diff_repos(gh).await;
+    insta::assert_debug_snapshot!(diff, @r#"
+    [
+        Update(
+            UpdateRepoDiff {
+                org: "rust-lang",
+                name: "repo1",
+                repo_node_id: "0",
+                settings_diff: (
+                    RepoSettings {
+                        description: "",
+                        homepage: None,
+                        archived: false,
+                        auto_merge_enabled: false,
+                        allow_squash_merge: true,
+                        allow_merge_commit: true,
+                        allow_rebase_merge: true,
+                        delete_branch_on_merge: false,
+                        has_issues: true,
+                        has_wiki: true,
+                        has_projects: true,
+                        has_discussions: false,
+                    },
+                    RepoSettings {
+                        description: "",
+                        homepage: None,
+                        archived: false,
+                        auto_merge_enabled: false,
+                        allow_squash_merge: true,
+                        allow_merge_commit: true,
+                        allow_rebase_merge: true,
+                        delete_branch_on_merge: false,
+                        has_issues: true,
+                        has_wiki: true,
+                        has_projects: true,
+                        has_discussions: false,
+                    },
+                ),
+                default_branch_diff: None,
+                permission_diffs: [
+                    RepoPermissionAssignmentDiff {
+                        collaborator: Team(
+                            "team1",
+                        ),
+                        diff: Update(
+                            Triage,
+                            Custom(
+                                "triage-plus",
+                            ),
+                        ),
+                    },
+                ],
+                branch_protection_diffs: [],
+                ruleset_diffs: [],
+                environment_diffs: [],
+                label_diffs: [],
+                webhook_diffs: [],
+                autolink_diffs: [],
+                deploy_key_diffs: [],
+                actions_diff: None,
+            },
+        ),
+    ]
+    "#);
+}
+
+#[tokio::test]
+#[should_panic(
+    expected = "Custom repository role `triage-plus` used by repo `rust-lang/repo1` does not exist"
+)]
+async fn repo_unknown_custom_role() {
+    let mut model = DataModel::default();
+    model.create_repo(RepoData::new("repo1").team("team1", RepoPermission::Triage));
+
+    let gh = model.gh_model();
+    model.get_repo("repo1").teams.last_mut().unwrap().permission =
+        RepoPermission::Custom("triage-plus".to_string());
+
+    model.diff_repos(gh).await;
+}
+
+#[tokio::test]
+async fn repo_remove_team() {
+    let mut model = DataModel::default();
+    model.create_repo(RepoData::new("repo1").team("team1", RepoPermission::Write));
+
+    let gh = model.gh_model();
+    model.get_repo("repo1").teams.clear();
+
+    let diff = model.diff_repos(gh).await;
+    insta::assert_debug_snapshot!(diff, @r#"
+    [
+        Update(
+            UpdateRepoDiff {
+                org: "rust-lang",
+                name: "repo1",
+                repo_node_id: "0",
+                settings_diff: (
+                    RepoSettings {
+                        description: "",
+                        homepage: None,
+                        archived: false,
+                        auto_merge_enabled: false,
+                        allow_squash_merge: true,
+                        allow_merge_commit: true,
+                        allow_rebase_merge: true,
+                        delete_branch_on_merge: false,
+                        has_issues: true,
+                        has_wiki: true,
+                        has_projects: true,
+                        has_discussions: false,
+                    },
+                    RepoSettings {
+                        description: "",
+                        homepage: None,
+                        archived: false,
+                        auto_merge_enabled: false,
+                        allow_squash_merge: true,
+                        allow_merge_commit: true,
+                        allow_rebase_merge: true,
+                        delete_branch_on_merge: false,
+                        has_issues: true,
+                        has_wiki: true,
+                        has_projects: true,
+                        has_discussions: false,
+                    },
+                ),
+                default_branch_diff: None,
                 permission_diffs: [
                     RepoPermissionAssignmentDiff {
                         collaborator: Team(
@@ -656,6 +1701,11 @@ async fn repo_remove_team() {
                 branch_protection_diffs: [],
                 ruleset_diffs: [],
                 environment_diffs: [],
+                label_diffs: [],
+                webhook_diffs: [],
+                autolink_diffs: [],
+                deploy_key_diffs: [],
+                actions_diff: None,
             },
         ),
     ]
@@ -684,24 +1734,135 @@ async fn repo_archive_repo() {
                         homepage: None,
                         archived: false,
                         auto_merge_enabled: false,
+                        allow_squash_merge: true,
+                        allow_merge_commit: true,
+                        allow_rebase_merge: true,
+                        delete_branch_on_merge: false,
+                        has_issues: true,
+                        has_wiki: true,
+                        has_projects: true,
+                        has_discussions: false,
+                    },
+                    RepoSettings {
+                        description: "",
+                        homepage: None,
+                        archived: true,
+                        auto_merge_enabled: false,
+                        allow_squash_merge: true,
+                        allow_merge_commit: true,
+                        allow_rebase_merge: true,
+                        delete_branch_on_merge: false,
+                        has_issues: true,
+                        has_wiki: true,
+                        has_projects: true,
+                        has_discussions: false,
+                    },
+                ),
+                default_branch_diff: None,
+                permission_diffs: [],
+                branch_protection_diffs: [],
+                ruleset_diffs: [],
+                environment_diffs: [],
+                label_diffs: [],
+                webhook_diffs: [],
+                autolink_diffs: [],
+                deploy_key_diffs: [],
+                actions_diff: None,
+            },
+        ),
+    ]
+    "#);
+}
+
+#[tokio::test]
+async fn repo_scheduled_archival_pending() {
+    let mut model = DataModel::default();
+    model.create_repo(RepoData::new("repo1"));
+
+    let gh = model.gh_model();
+    model.get_repo("repo1").archive_at = Some("2999-12-31".to_string());
+
+    let diff = model.diff_repos(gh).await;
+    assert!(diff.is_empty());
+}
+
+#[tokio::test]
+async fn repo_scheduled_archival_due() {
+    let mut model = DataModel::default();
+    model.create_repo(RepoData::new("repo1"));
+
+    let gh = model.gh_model();
+    model.get_repo("repo1").archive_at = Some("2020-01-01".to_string());
+
+    let diff = model.diff_repos(gh).await;
+    insta::assert_debug_snapshot!(diff, @r#"
+    [
+        Update(
+            UpdateRepoDiff {
+                org: "rust-lang",
+                name: "repo1",
+                repo_node_id: "0",
+                settings_diff: (
+                    RepoSettings {
+                        description: "",
+                        homepage: None,
+                        archived: false,
+                        auto_merge_enabled: false,
+                        allow_squash_merge: true,
+                        allow_merge_commit: true,
+                        allow_rebase_merge: true,
+                        delete_branch_on_merge: false,
+                        has_issues: true,
+                        has_wiki: true,
+                        has_projects: true,
+                        has_discussions: false,
                     },
                     RepoSettings {
                         description: "",
                         homepage: None,
                         archived: true,
                         auto_merge_enabled: false,
+                        allow_squash_merge: true,
+                        allow_merge_commit: true,
+                        allow_rebase_merge: true,
+                        delete_branch_on_merge: false,
+                        has_issues: true,
+                        has_wiki: true,
+                        has_projects: true,
+                        has_discussions: false,
                     },
                 ),
+                default_branch_diff: None,
                 permission_diffs: [],
                 branch_protection_diffs: [],
                 ruleset_diffs: [],
                 environment_diffs: [],
+                label_diffs: [],
+                webhook_diffs: [],
+                autolink_diffs: [],
+                deploy_key_diffs: [],
+                actions_diff: None,
             },
         ),
     ]
     "#);
 }
 
+#[tokio::test]
+async fn repo_scheduled_archival_already_archived() {
+    let mut model = DataModel::default();
+    model.create_repo(RepoData::new("repo1").archived(true));
+
+    let gh = model.gh_model();
+    let repo = model.get_repo("repo1");
+    repo.archived = false;
+    repo.archive_at = Some("2020-01-01".to_string());
+
+    // The repository must not be unarchived once its archival date has passed
+    let diff = model.diff_repos(gh).await;
+    assert!(diff.is_empty());
+}
+
 #[tokio::test]
 async fn repo_add_branch_protection() {
     let mut model = DataModel::default();
@@ -727,14 +1888,31 @@ async fn repo_add_branch_protection() {
                         homepage: None,
                         archived: false,
                         auto_merge_enabled: false,
+                        allow_squash_merge: true,
+                        allow_merge_commit: true,
+                        allow_rebase_merge: true,
+                        delete_branch_on_merge: false,
+                        has_issues: true,
+                        has_wiki: true,
+                        has_projects: true,
+                        has_discussions: false,
                     },
                     RepoSettings {
                         description: "",
                         homepage: None,
                         archived: false,
                         auto_merge_enabled: false,
+                        allow_squash_merge: true,
+                        allow_merge_commit: true,
+                        allow_rebase_merge: true,
+                        delete_branch_on_merge: false,
+                        has_issues: true,
+                        has_wiki: true,
+                        has_projects: true,
+                        has_discussions: false,
                     },
                 ),
+                default_branch_diff: None,
                 permission_diffs: [],
                 branch_protection_diffs: [
                     BranchProtectionDiff {
@@ -751,7 +1929,12 @@ async fn repo_add_branch_protection() {
                                     "test 2",
                                 ],
                                 push_allowances: [],
+                                bypass_pull_request_allowances: [],
                                 requires_approving_reviews: true,
+                                requires_code_owner_reviews: false,
+                                requires_commit_signatures: false,
+                                requires_linear_history: false,
+                                requires_conversation_resolution: false,
                             },
                         ),
                     },
@@ -766,13 +1949,23 @@ async fn repo_add_branch_protection() {
                                 required_approving_review_count: 0,
                                 required_status_check_contexts: [],
                                 push_allowances: [],
+                                bypass_pull_request_allowances: [],
                                 requires_approving_reviews: false,
+                                requires_code_owner_reviews: false,
+                                requires_commit_signatures: false,
+                                requires_linear_history: false,
+                                requires_conversation_resolution: false,
                             },
                         ),
                     },
                 ],
                 ruleset_diffs: [],
                 environment_diffs: [],
+                label_diffs: [],
+                webhook_diffs: [],
+                autolink_diffs: [],
+                deploy_key_diffs: [],
+                actions_diff: None,
             },
         ),
     ]
@@ -800,6 +1993,7 @@ async fn repo_update_branch_protection() {
         BranchProtectionMode::PrRequired {
             ci_checks,
             required_approvals,
+            ..
         } => {
             ci_checks.push("Test".to_string());
             *required_approvals = 0;
@@ -823,14 +2017,31 @@ async fn repo_update_branch_protection() {
                         homepage: None,
                         archived: false,
                         auto_merge_enabled: false,
+                        allow_squash_merge: true,
+                        allow_merge_commit: true,
+                        allow_rebase_merge: true,
+                        delete_branch_on_merge: false,
+                        has_issues: true,
+                        has_wiki: true,
+                        has_projects: true,
+                        has_discussions: false,
                     },
                     RepoSettings {
                         description: "",
                         homepage: None,
                         archived: false,
                         auto_merge_enabled: false,
+                        allow_squash_merge: true,
+                        allow_merge_commit: true,
+                        allow_rebase_merge: true,
+                        delete_branch_on_merge: false,
+                        has_issues: true,
+                        has_wiki: true,
+                        has_projects: true,
+                        has_discussions: false,
                     },
                 ),
+                default_branch_diff: None,
                 permission_diffs: [],
                 branch_protection_diffs: [
                     BranchProtectionDiff {
@@ -847,7 +2058,12 @@ async fn repo_update_branch_protection() {
                                     "test",
                                 ],
                                 push_allowances: [],
+                                bypass_pull_request_allowances: [],
                                 requires_approving_reviews: true,
+                                requires_code_owner_reviews: false,
+                                requires_commit_signatures: false,
+                                requires_linear_history: false,
+                                requires_conversation_resolution: false,
                             },
                             BranchProtection {
                                 pattern: "master",
@@ -860,13 +2076,23 @@ async fn repo_update_branch_protection() {
                                     "test",
                                 ],
                                 push_allowances: [],
+                                bypass_pull_request_allowances: [],
                                 requires_approving_reviews: true,
+                                requires_code_owner_reviews: false,
+                                requires_commit_signatures: false,
+                                requires_linear_history: false,
+                                requires_conversation_resolution: false,
                             },
                         ),
                     },
                 ],
                 ruleset_diffs: [],
                 environment_diffs: [],
+                label_diffs: [],
+                webhook_diffs: [],
+                autolink_diffs: [],
+                deploy_key_diffs: [],
+                actions_diff: None,
             },
         ),
     ]
@@ -874,19 +2100,19 @@ async fn repo_update_branch_protection() {
 }
 
 #[tokio::test]
-async fn repo_remove_branch_protection() {
+async fn repo_require_signed_commits() {
     let mut model = DataModel::default();
-    model.create_repo(
-        RepoData::new("repo1")
-            .team("team1", RepoPermission::Write)
-            .branch_protections(vec![
-                BranchProtectionBuilder::pr_required("main", &["test"], 1).build(),
-                BranchProtectionBuilder::pr_required("stable", &["test"], 0).build(),
-            ]),
-    );
+    model.create_repo(RepoData::new("repo1").branch_protections(vec![
+        BranchProtectionBuilder::pr_required("main", &["test"], 1).build(),
+    ]));
 
     let gh = model.gh_model();
-    model.get_repo("repo1").branch_protections.pop().unwrap();
+    let protection = model
+        .get_repo("repo1")
+        .branch_protections
+        .last_mut()
+        .unwrap();
+    protection.requires_signed_commits = true;
 
     let diff = model.diff_repos(gh).await;
     insta::assert_debug_snapshot!(diff, @r#"
@@ -902,25 +2128,81 @@ async fn repo_remove_branch_protection() {
                         homepage: None,
                         archived: false,
                         auto_merge_enabled: false,
+                        allow_squash_merge: true,
+                        allow_merge_commit: true,
+                        allow_rebase_merge: true,
+                        delete_branch_on_merge: false,
+                        has_issues: true,
+                        has_wiki: true,
+                        has_projects: true,
+                        has_discussions: false,
                     },
                     RepoSettings {
                         description: "",
                         homepage: None,
                         archived: false,
                         auto_merge_enabled: false,
+                        allow_squash_merge: true,
+                        allow_merge_commit: true,
+                        allow_rebase_merge: true,
+                        delete_branch_on_merge: false,
+                        has_issues: true,
+                        has_wiki: true,
+                        has_projects: true,
+                        has_discussions: false,
                     },
                 ),
+                default_branch_diff: None,
                 permission_diffs: [],
                 branch_protection_diffs: [
                     BranchProtectionDiff {
-                        pattern: "stable",
-                        operation: Delete(
-                            "1",
+                        pattern: "main",
+                        operation: Update(
+                            "0",
+                            BranchProtection {
+                                pattern: "main",
+                                is_admin_enforced: true,
+                                allows_force_pushes: false,
+                                dismisses_stale_reviews: false,
+                                required_approving_review_count: 1,
+                                required_status_check_contexts: [
+                                    "test",
+                                ],
+                                push_allowances: [],
+                                bypass_pull_request_allowances: [],
+                                requires_approving_reviews: true,
+                                requires_code_owner_reviews: false,
+                                requires_commit_signatures: false,
+                                requires_linear_history: false,
+                                requires_conversation_resolution: false,
+                            },
+                            BranchProtection {
+                                pattern: "main",
+                                is_admin_enforced: true,
+                                allows_force_pushes: false,
+                                dismisses_stale_reviews: false,
+                                required_approving_review_count: 1,
+                                required_status_check_contexts: [
+                                    "test",
+                                ],
+                                push_allowances: [],
+                                bypass_pull_request_allowances: [],
+                                requires_approving_reviews: true,
+                                requires_code_owner_reviews: false,
+                                requires_commit_signatures: true,
+                                requires_linear_history: false,
+                                requires_conversation_resolution: false,
+                            },
                         ),
                     },
                 ],
                 ruleset_diffs: [],
                 environment_diffs: [],
+                label_diffs: [],
+                webhook_diffs: [],
+                autolink_diffs: [],
+                deploy_key_diffs: [],
+                actions_diff: None,
             },
         ),
     ]
@@ -928,61 +2210,20 @@ async fn repo_remove_branch_protection() {
 }
 
 #[tokio::test]
-async fn independent_orgs_are_not_synced() {
-    let mut model = DataModel::default();
-    let user = model.create_user("sakura");
-
-    let independent_org = "independent-org";
-
-    // Create a team, so that membership is synced.
-    model.create_team(TeamData::new("team").gh_team(independent_org, "team-gh", &[user]));
-
-    let mut gh = model.gh_model();
-
-    // Add a member who is not part of any team.
-    gh.add_member(independent_org, "independent-user-1");
-
-    model.add_independent_github_org(independent_org);
-
-    let gh_org_diff = model.diff_org_membership(gh).await;
-
-    // No members should be removed for independent organizations
-    insta::assert_debug_snapshot!(gh_org_diff, @"[]");
-}
-
-#[tokio::test]
-async fn repo_environment_noop() {
+async fn repo_require_linear_history_and_conversation_resolution() {
     let mut model = DataModel::default();
-    model.create_repo(
-        RepoData::new("repo1")
-            .environment("production")
-            .environment("staging"),
-    );
-    let gh = model.gh_model();
-    let diff = model.diff_repos(gh).await;
-    assert!(diff.is_empty());
-}
+    model.create_repo(RepoData::new("repo1").branch_protections(vec![
+        BranchProtectionBuilder::pr_required("main", &["test"], 1).build(),
+    ]));
 
-#[tokio::test]
-async fn repo_environment_create() {
-    let mut model = DataModel::default();
-    model.create_repo(RepoData::new("repo1"));
     let gh = model.gh_model();
-
-    model.get_repo("repo1").environments.insert(
-        "production".to_string(),
-        v1::Environment {
-            branches: vec![],
-            tags: vec![],
-        },
-    );
-    model.get_repo("repo1").environments.insert(
-        "staging".to_string(),
-        v1::Environment {
-            branches: vec![],
-            tags: vec![],
-        },
-    );
+    let protection = model
+        .get_repo("repo1")
+        .branch_protections
+        .last_mut()
+        .unwrap();
+    protection.required_linear_history = true;
+    protection.requires_conversation_resolution = true;
 
     let diff = model.diff_repos(gh).await;
     insta::assert_debug_snapshot!(diff, @r#"
@@ -998,33 +2239,81 @@ async fn repo_environment_create() {
                         homepage: None,
                         archived: false,
                         auto_merge_enabled: false,
+                        allow_squash_merge: true,
+                        allow_merge_commit: true,
+                        allow_rebase_merge: true,
+                        delete_branch_on_merge: false,
+                        has_issues: true,
+                        has_wiki: true,
+                        has_projects: true,
+                        has_discussions: false,
                     },
                     RepoSettings {
                         description: "",
                         homepage: None,
                         archived: false,
                         auto_merge_enabled: false,
+                        allow_squash_merge: true,
+                        allow_merge_commit: true,
+                        allow_rebase_merge: true,
+                        delete_branch_on_merge: false,
+                        has_issues: true,
+                        has_wiki: true,
+                        has_projects: true,
+                        has_discussions: false,
                     },
                 ),
+                default_branch_diff: None,
                 permission_diffs: [],
-                branch_protection_diffs: [],
-                ruleset_diffs: [],
-                environment_diffs: [
-                    Create(
-                        "production",
-                        Environment {
-                            branches: [],
-                            tags: [],
-                        },
-                    ),
-                    Create(
-                        "staging",
-                        Environment {
-                            branches: [],
-                            tags: [],
-                        },
-                    ),
+                branch_protection_diffs: [
+                    BranchProtectionDiff {
+                        pattern: "main",
+                        operation: Update(
+                            "0",
+                            BranchProtection {
+                                pattern: "main",
+                                is_admin_enforced: true,
+                                allows_force_pushes: false,
+                                dismisses_stale_reviews: false,
+                                required_approving_review_count: 1,
+                                required_status_check_contexts: [
+                                    "test",
+                                ],
+                                push_allowances: [],
+                                bypass_pull_request_allowances: [],
+                                requires_approving_reviews: true,
+                                requires_code_owner_reviews: false,
+                                requires_commit_signatures: false,
+                                requires_linear_history: false,
+                                requires_conversation_resolution: false,
+                            },
+                            BranchProtection {
+                                pattern: "main",
+                                is_admin_enforced: true,
+                                allows_force_pushes: false,
+                                dismisses_stale_reviews: false,
+                                required_approving_review_count: 1,
+                                required_status_check_contexts: [
+                                    "test",
+                                ],
+                                push_allowances: [],
+                                bypass_pull_request_allowances: [],
+                                requires_approving_reviews: true,
+                                requires_code_owner_reviews: false,
+                                requires_commit_signatures: false,
+                                requires_linear_history: true,
+                                requires_conversation_resolution: true,
+                            },
+                        ),
+                    },
                 ],
+                ruleset_diffs: [],
+                environment_diffs: [],
+                label_diffs: [],
+                webhook_diffs: [],
+                autolink_diffs: [],
+                deploy_key_diffs: [],
+                actions_diff: None,
             },
         ),
     ]
@@ -1032,16 +2321,19 @@ async fn repo_environment_create() {
 }
 
 #[tokio::test]
-async fn repo_environment_delete() {
+async fn repo_add_push_app_to_branch_protection() {
     let mut model = DataModel::default();
-    model.create_repo(
-        RepoData::new("repo1")
-            .environment("production")
-            .environment("staging"),
-    );
-    let gh = model.gh_model();
+    model.create_repo(RepoData::new("repo1").branch_protections(vec![
+        BranchProtectionBuilder::pr_required("main", &["test"], 1).build(),
+    ]));
 
-    model.get_repo("repo1").environments.clear();
+    let gh = model.gh_model();
+    let protection = model
+        .get_repo("repo1")
+        .branch_protections
+        .last_mut()
+        .unwrap();
+    protection.allowed_push_apps = vec!["my-app".to_string()];
 
     let diff = model.diff_repos(gh).await;
     insta::assert_debug_snapshot!(diff, @r#"
@@ -1057,25 +2349,88 @@ async fn repo_environment_delete() {
                         homepage: None,
                         archived: false,
                         auto_merge_enabled: false,
+                        allow_squash_merge: true,
+                        allow_merge_commit: true,
+                        allow_rebase_merge: true,
+                        delete_branch_on_merge: false,
+                        has_issues: true,
+                        has_wiki: true,
+                        has_projects: true,
+                        has_discussions: false,
                     },
                     RepoSettings {
                         description: "",
                         homepage: None,
                         archived: false,
                         auto_merge_enabled: false,
+                        allow_squash_merge: true,
+                        allow_merge_commit: true,
+                        allow_rebase_merge: true,
+                        delete_branch_on_merge: false,
+                        has_issues: true,
+                        has_wiki: true,
+                        has_projects: true,
+                        has_discussions: false,
                     },
                 ),
+                default_branch_diff: None,
                 permission_diffs: [],
-                branch_protection_diffs: [],
-                ruleset_diffs: [],
-                environment_diffs: [
-                    Delete(
-                        "production",
-                    ),
-                    Delete(
-                        "staging",
-                    ),
+                branch_protection_diffs: [
+                    BranchProtectionDiff {
+                        pattern: "main",
+                        operation: Update(
+                            "0",
+                            BranchProtection {
+                                pattern: "main",
+                                is_admin_enforced: true,
+                                allows_force_pushes: false,
+                                dismisses_stale_reviews: false,
+                                required_approving_review_count: 1,
+                                required_status_check_contexts: [
+                                    "test",
+                                ],
+                                push_allowances: [],
+                                bypass_pull_request_allowances: [],
+                                requires_approving_reviews: true,
+                                requires_code_owner_reviews: false,
+                                requires_commit_signatures: false,
+                                requires_linear_history: false,
+                                requires_conversation_resolution: false,
+                            },
+                            BranchProtection {
+                                pattern: "main",
+                                is_admin_enforced: true,
+                                allows_force_pushes: false,
+                                dismisses_stale_reviews: false,
+                                required_approving_review_count: 1,
+                                required_status_check_contexts: [
+                                    "test",
+                                ],
+                                push_allowances: [
+                                    App(
+                                        AppPushAllowanceActor {
+                                            name: "my-app",
+                                            id: "app-my-app",
+                                        },
+                                    ),
+                                ],
+                                bypass_pull_request_allowances: [],
+                                requires_approving_reviews: true,
+                                requires_code_owner_reviews: false,
+                                requires_commit_signatures: false,
+                                requires_linear_history: false,
+                                requires_conversation_resolution: false,
+                            },
+                        ),
+                    },
                 ],
+                ruleset_diffs: [],
+                environment_diffs: [],
+                label_diffs: [],
+                webhook_diffs: [],
+                autolink_diffs: [],
+                deploy_key_diffs: [],
+                actions_diff: None,
             },
         ),
     ]
@@ -1083,69 +2438,105 @@ async fn repo_environment_delete() {
 }
 
 #[tokio::test]
-async fn repo_environment_update() {
+async fn repo_skip_unreadable_branch_protections_without_pat() {
     let mut model = DataModel::default();
-    model.create_repo(
-        RepoData::new("repo1")
-            .environment("production")
-            .environment("staging"),
+    let mut protection = BranchProtectionBuilder::pr_required("main", &["test"], 1);
+    protection.allowed_push_apps = vec!["my-app".to_string()];
+    model.create_repo(RepoData::new("repo1").branch_protections(vec![protection.build()]));
+
+    let mut gh = model.gh_model();
+    gh.use_app_token();
+    let protection = model
+        .get_repo("repo1")
+        .branch_protections
+        .last_mut()
+        .unwrap();
+    protection.requires_signed_commits = true;
+
+    let diff = model.diff_repos(gh).await;
+    assert!(diff.is_empty());
+}
+
+#[tokio::test]
+async fn repo_unreadable_branch_protections_without_pat_or_push_apps() {
+    let mut model = DataModel::default();
+    let mut protection = BranchProtectionBuilder::pr_required("main", &["test"], 1);
+    protection.allowed_push_apps = vec!["my-app".to_string()];
+    model.create_repo(RepoData::new("repo1").branch_protections(vec![protection.build()]));
+
+    let mut gh = model.gh_model();
+    gh.use_app_token();
+    // Without apps allowed to push, the token lacking permissions is an error
+    model
+        .get_repo("repo1")
+        .branch_protections
+        .last_mut()
+        .unwrap()
+        .allowed_push_apps
+        .clear();
+
+    let error = model.try_diff_repos(gh).await.unwrap_err();
+    assert!(
+        error
+            .chain()
+            .any(|cause| cause.downcast_ref::<api::GraphForbiddenError>().is_some()),
+        "{error:?}"
     );
+}
+
+#[tokio::test]
+async fn repo_create_with_labels() {
+    let mut model = DataModel::default();
     let gh = model.gh_model();
 
-    // Remove staging, keep production, add dev
-    model.get_repo("repo1").environments.clear();
-    model.get_repo("repo1").environments.insert(
-        "production".to_string(),
-        v1::Environment {
-            branches: vec![],
-            tags: vec![],
-        },
-    );
-    model.get_repo("repo1").environments.insert(
-        "dev".to_string(),
-        v1::Environment {
-            branches: vec![],
-            tags: vec![],
-        },
+    model.create_repo(
+        RepoData::new("repo1")
+            .label("I-crash", "e11d21")
+            .label("T-compiler", "bfd4f2"),
     );
 
     let diff = model.diff_repos(gh).await;
     insta::assert_debug_snapshot!(diff, @r#"
     [
-        Update(
-            UpdateRepoDiff {
+        Create(
+            CreateRepoDiff {
                 org: "rust-lang",
                 name: "repo1",
-                repo_node_id: "0",
-                settings_diff: (
-                    RepoSettings {
+                default_branch: None,
+                settings: RepoSettings {
+                    description: "",
+                    homepage: None,
+                    archived: false,
+                    auto_merge_enabled: false,
+                    allow_squash_merge: true,
+                    allow_merge_commit: true,
+                    allow_rebase_merge: true,
+                    delete_branch_on_merge: false,
+                    has_issues: true,
+                    has_wiki: true,
+                    has_projects: true,
+                    has_discussions: false,
+                },
+                permissions: [],
+                branch_protections: [],
+                rulesets: [],
+                environments: [],
+                labels: [
+                    RepoLabel {
+                        name: "I-crash",
+                        color: "e11d21",
                         description: "",
-                        homepage: None,
-                        archived: false,
-                        auto_merge_enabled: false,
                     },
-                    RepoSettings {
+                    RepoLabel {
+                        name: "T-compiler",
+                        color: "bfd4f2",
                         description: "",
-                        homepage: None,
-                        archived: false,
-                        auto_merge_enabled: false,
                     },
-                ),
-                permission_diffs: [],
-                branch_protection_diffs: [],
-                ruleset_diffs: [],
-                environment_diffs: [
-                    Create(
-                        "dev",
-                        Environment {
-                            branches: [],
-                            tags: [],
-                        },
-                    ),
-                    Delete(
-                        "staging",
-                    ),
                 ],
+                webhooks: [],
+                autolinks: [],
+                actions: None,
+                template: None,
             },
         ),
     ]
@@ -1153,21 +2544,24 @@ async fn repo_environment_update() {
 }
 
 #[tokio::test]
-async fn repo_environment_update_branches() {
+async fn repo_provision_labels() {
     let mut model = DataModel::default();
     model.create_repo(
-        RepoData::new("repo1").environment_with_branches("production", &["main", "release/*"]),
+        RepoData::new("repo1")
+            .label("I-crash", "e11d21")
+            .label("T-compiler", "bfd4f2"),
     );
-    let gh = model.gh_model();
 
-    // Update branches for production environment
-    model.get_repo("repo1").environments.insert(
-        "production".to_string(),
-        v1::Environment {
-            branches: vec!["main".to_string(), "stable".to_string()],
-            tags: vec![],
-        },
-    );
+    let gh = model.gh_model();
+    let repo = model.get_repo("repo1");
+    repo.labels[0].color = "b60205".to_string();
+    repo.labels[1].name = "t-compiler".to_string();
+    repo.labels[1].description = "Relevant to the compiler team".to_string();
+    repo.labels.push(v1::Label {
+        name: "E-easy".to_string(),
+        color: "02e10c".to_string(),
+        description: String::new(),
+    });
 
     let diff = model.diff_repos(gh).await;
     insta::assert_debug_snapshot!(diff, @r#"
@@ -1183,37 +2577,2501 @@ async fn repo_environment_update_branches() {
                         homepage: None,
                         archived: false,
                         auto_merge_enabled: false,
+                        allow_squash_merge: true,
+                        allow_merge_commit: true,
+                        allow_rebase_merge: true,
+                        delete_branch_on_merge: false,
+                        has_issues: true,
+                        has_wiki: true,
+                        has_projects: true,
+                        has_discussions: false,
                     },
                     RepoSettings {
                         description: "",
                         homepage: None,
                         archived: false,
                         auto_merge_enabled: false,
+                        allow_squash_merge: true,
+                        allow_merge_commit: true,
+                        allow_rebase_merge: true,
+                        delete_branch_on_merge: false,
+                        has_issues: true,
+                        has_wiki: true,
+                        has_projects: true,
+                        has_discussions: false,
                     },
                 ),
+                default_branch_diff: None,
                 permission_diffs: [],
                 branch_protection_diffs: [],
                 ruleset_diffs: [],
-                environment_diffs: [
+                environment_diffs: [],
+                label_diffs: [
                     Update {
-                        name: "production",
-                        add_branches: [
-                            "stable",
-                        ],
-                        remove_branches: [
-                            "release/*",
-                        ],
-                        add_tags: [],
-                        remove_tags: [],
-                        new_branches: [
-                            "main",
-                            "stable",
-                        ],
-                        new_tags: [],
+                        old: RepoLabel {
+                            name: "I-crash",
+                            color: "e11d21",
+                            description: "",
+                        },
+                        new: RepoLabel {
+                            name: "I-crash",
+                            color: "b60205",
+                            description: "",
+                        },
                     },
+                    Update {
+                        old: RepoLabel {
+                            name: "T-compiler",
+                            color: "bfd4f2",
+                            description: "",
+                        },
+                        new: RepoLabel {
+                            name: "t-compiler",
+                            color: "bfd4f2",
+                            description: "Relevant to the compiler team",
+                        },
+                    },
+                    Create(
+                        RepoLabel {
+                            name: "E-easy",
+                            color: "02e10c",
+                            description: "",
+                        },
+                    ),
                 ],
+                webhook_diffs: [],
+                autolink_diffs: [],
+                deploy_key_diffs: [],
+                actions_diff: None,
+            },
+        ),
+    ]
+    "#);
+}
+
+#[tokio::test]
+async fn repo_create_from_template() {
+    let mut model = DataModel::default();
+    let gh = model.gh_model();
+
+    model.create_repo(RepoData::new("repo1").template(Some("rust-lang/template".to_string())));
+
+    let diff = model.diff_repos(gh).await;
+    insta::assert_debug_snapshot!(diff, @r#"
+    [
+        Create(
+            CreateRepoDiff {
+                org: "rust-lang",
+                name: "repo1",
+                default_branch: None,
+                settings: RepoSettings {
+                    description: "",
+                    homepage: None,
+                    archived: false,
+                    auto_merge_enabled: false,
+                    allow_squash_merge: true,
+                    allow_merge_commit: true,
+                    allow_rebase_merge: true,
+                    delete_branch_on_merge: false,
+                    has_issues: true,
+                    has_wiki: true,
+                    has_projects: true,
+                    has_discussions: false,
+                },
+                permissions: [],
+                branch_protections: [],
+                rulesets: [],
+                environments: [],
+                labels: [],
+                webhooks: [],
+                autolinks: [],
+                actions: None,
+                template: Some(
+                    "rust-lang/template",
+                ),
             },
         ),
     ]
     "#);
 }
+
+#[tokio::test]
+async fn repo_create_with_webhook() {
+    let mut model = DataModel::default();
+    let gh = model.gh_model();
+
+    model.create_repo(
+        RepoData::new("repo1").webhook("https://triagebot.infra.rust-lang.org/github-hook", &["*"]),
+    );
+
+    let diff = model.diff_repos(gh).await;
+    insta::assert_debug_snapshot!(diff, @r#"
+    [
+        Create(
+            CreateRepoDiff {
+                org: "rust-lang",
+                name: "repo1",
+                default_branch: None,
+                settings: RepoSettings {
+                    description: "",
+                    homepage: None,
+                    archived: false,
+                    auto_merge_enabled: false,
+                    allow_squash_merge: true,
+                    allow_merge_commit: true,
+                    allow_rebase_merge: true,
+                    delete_branch_on_merge: false,
+                    has_issues: true,
+                    has_wiki: true,
+                    has_projects: true,
+                    has_discussions: false,
+                },
+                permissions: [],
+                branch_protections: [],
+                rulesets: [],
+                environments: [],
+                labels: [],
+                webhooks: [
+                    Webhook {
+                        url: "https://triagebot.infra.rust-lang.org/github-hook",
+                        events: [
+                            "*",
+                        ],
+                        content_type: Json,
+                        secret_env: None,
+                    },
+                ],
+                autolinks: [],
+                actions: None,
+                template: None,
+            },
+        ),
+    ]
+    "#);
+}
+
+#[tokio::test]
+async fn repo_provision_webhooks() {
+    let mut model = DataModel::default();
+    model.create_repo(RepoData::new("repo1").webhook("https://example.com/hook", &["push"]));
+
+    let gh = model.gh_model();
+    let repo = model.get_repo("repo1");
+    repo.webhooks[0].events = vec!["pull_request".to_string(), "push".to_string()];
+    repo.webhooks[0].secret_env = Some("SYNC_TEAM_SECRET_HOOK".to_string());
+    repo.webhooks.push(v1::Webhook {
+        url: "https://example.com/other-hook".to_string(),
+        events: vec!["issues".to_string()],
+        content_type: v1::WebhookContentType::Form,
+        secret_env: None,
+    });
+
+    let diff = model.diff_repos(gh).await;
+    insta::assert_debug_snapshot!(diff, @r#"
+    [
+        Update(
+            UpdateRepoDiff {
+                org: "rust-lang",
+                name: "repo1",
+                repo_node_id: "0",
+                settings_diff: (
+                    RepoSettings {
+                        description: "",
+                        homepage: None,
+                        archived: false,
+                        auto_merge_enabled: false,
+                        allow_squash_merge: true,
+                        allow_merge_commit: true,
+                        allow_rebase_merge: true,
+                        delete_branch_on_merge: false,
+                        has_issues: true,
+                        has_wiki: true,
+                        has_projects: true,
+                        has_discussions: false,
+                    },
+                    RepoSettings {
+                        description: "",
+                        homepage: None,
+                        archived: false,
+                        auto_merge_enabled: false,
+                        allow_squash_merge: true,
+                        allow_merge_commit: true,
+                        allow_rebase_merge: true,
+                        delete_branch_on_merge: false,
+                        has_issues: true,
+                        has_wiki: true,
+                        has_projects: true,
+                        has_discussions: false,
+                    },
+                ),
+                default_branch_diff: None,
+                permission_diffs: [],
+                branch_protection_diffs: [],
+                ruleset_diffs: [],
+                environment_diffs: [],
+                label_diffs: [],
+                webhook_diffs: [
+                    Update {
+                        old: RepoWebhook {
+                            id: 0,
+                            active: true,
+                            events: [
+                                "push",
+                            ],
+                            config: RepoWebhookConfig {
+                                url: Some(
+                                    "https://example.com/hook",
+                                ),
+                                content_type: Some(
+                                    "json",
+                                ),
+                            },
+                        },
+                        new: Webhook {
+                            url: "https://example.com/hook",
+                            events: [
+                                "pull_request",
+                                "push",
+                            ],
+                            content_type: Json,
+                            secret_env: Some(
+                                "SYNC_TEAM_SECRET_HOOK",
+                            ),
+                        },
+                    },
+                    Create(
+                        Webhook {
+                            url: "https://example.com/other-hook",
+                            events: [
+                                "issues",
+                            ],
+                            content_type: Form,
+                            secret_env: None,
+                        },
+                    ),
+                ],
+                autolink_diffs: [],
+                deploy_key_diffs: [],
+                actions_diff: None,
+            },
+        ),
+    ]
+    "#);
+}
+
+#[tokio::test]
+async fn repo_sync_autolinks() {
+    let mut model = DataModel::default();
+    model.create_repo(
+        RepoData::new("repo1")
+            .autolink("RUST-", "https://github.com/rust-lang/rust/issues/<num>")
+            .autolink("CVE-", "https://www.cve.org/CVERecord?id=CVE-<num>"),
+    );
+
+    let gh = model.gh_model();
+    let repo = model.get_repo("repo1");
+    repo.autolinks.shift_remove("CVE-");
+    repo.autolinks.insert(
+        "RUST-".to_string(),
+        "https://github.com/rust-lang/rust/pull/<num>".to_string(),
+    );
+    repo.autolinks.insert(
+        "ZULIP-".to_string(),
+        "https://rust-lang.zulipchat.com/#narrow/near/<num>".to_string(),
+    );
+
+    let diff = model.diff_repos(gh).await;
+    insta::assert_debug_snapshot!(diff, @r#"
+    [
+        Update(
+            UpdateRepoDiff {
+                org: "rust-lang",
+                name: "repo1",
+                repo_node_id: "0",
+                settings_diff: (
+                    RepoSettings {
+                        description: "",
+                        homepage: None,
+                        archived: false,
+                        auto_merge_enabled: false,
+                        allow_squash_merge: true,
+                        allow_merge_commit: true,
+                        allow_rebase_merge: true,
+                        delete_branch_on_merge: false,
+                        has_issues: true,
+                        has_wiki: true,
+                        has_projects: true,
+                        has_discussions: false,
+                    },
+                    RepoSettings {
+                        description: "",
+                        homepage: None,
+                        archived: false,
+                        auto_merge_enabled: false,
+                        allow_squash_merge: true,
+                        allow_merge_commit: true,
+                        allow_rebase_merge: true,
+                        delete_branch_on_merge: false,
+                        has_issues: true,
+                        has_wiki: true,
+                        has_projects: true,
+                        has_discussions: false,
+                    },
+                ),
+                default_branch_diff: None,
+                permission_diffs: [],
+                branch_protection_diffs: [],
+                ruleset_diffs: [],
+                environment_diffs: [],
+                label_diffs: [],
+                webhook_diffs: [],
+                autolink_diffs: [
+                    Update {
+                        id: 0,
+                        key_prefix: "RUST-",
+                        old_url_template: "https://github.com/rust-lang/rust/issues/<num>",
+                        new_url_template: "https://github.com/rust-lang/rust/pull/<num>",
+                    },
+                    Create {
+                        key_prefix: "ZULIP-",
+                        url_template: "https://rust-lang.zulipchat.com/#narrow/near/<num>",
+                    },
+                    Delete {
+                        id: 1,
+                        key_prefix: "CVE-",
+                        url_template: "https://www.cve.org/CVERecord?id=CVE-<num>",
+                    },
+                ],
+                deploy_key_diffs: [],
+                actions_diff: None,
+            },
+        ),
+    ]
+    "#);
+}
+
+const DOCS_KEY: &str =
+    "ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAIOMSnGqXPZscsMjqQT5Ihi2g7uEBUVCNtpl9KGOD3HYh docs";
+const DOCS_KEY_FINGERPRINT: &str = "SHA256:zNApKpYjjRDAx0rLR7iebjZIKiNdelqg9+4Jm9gzysQ";
+const OTHER_KEY: &str =
+    "ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAIGtC9BWyQMdp9I7D4K+mNNKUNV86LhlfVerQx2zQpSFA other";
+
+#[test]
+fn deploy_key_fingerprint() {
+    let key = api::RepoDeployKey {
+        id: 0,
+        title: "docs".to_string(),
+        key: DOCS_KEY.to_string(),
+        read_only: true,
+    };
+    assert_eq!(key.fingerprint().as_deref(), Some(DOCS_KEY_FINGERPRINT));
+}
+
+#[tokio::test]
+async fn repo_ignore_deploy_keys_without_allowlist() {
+    let mut model = DataModel::default();
+    model.create_repo(RepoData::new("repo1"));
+
+    let mut gh = model.gh_model();
+    gh.add_deploy_key(DEFAULT_ORG, "repo1", "docs", DOCS_KEY, true);
+
+    let diff = model.diff_repos(gh).await;
+    assert!(diff.is_empty());
+}
+
+#[tokio::test]
+async fn repo_remove_deploy_keys_not_in_allowlist() {
+    let mut model = DataModel::default();
+    model.create_repo(RepoData::new("repo1").deploy_keys(Some(vec![v1::DeployKey {
+        title: "docs".to_string(),
+        fingerprint: DOCS_KEY_FINGERPRINT.to_string(),
+    }])));
+
+    let mut gh = model.gh_model();
+    gh.add_deploy_key(DEFAULT_ORG, "repo1", "docs", DOCS_KEY, false);
+    // Keys are recognized by their fingerprint, not by their title
+    gh.add_deploy_key(DEFAULT_ORG, "repo1", "docs", OTHER_KEY, false);
+
+    let diff = model.diff_repos(gh).await;
+    insta::assert_debug_snapshot!(diff, @r#"
+    [
+        Update(
+            UpdateRepoDiff {
+                org: "rust-lang",
+                name: "repo1",
+                repo_node_id: "0",
+                settings_diff: (
+                    RepoSettings {
+                        description: "",
+                        homepage: None,
+                        archived: false,
+                        auto_merge_enabled: false,
+                        allow_squash_merge: true,
+                        allow_merge_commit: true,
+                        allow_rebase_merge: true,
+                        delete_branch_on_merge: false,
+                        has_issues: true,
+                        has_wiki: true,
+                        has_projects: true,
+                        has_discussions: false,
+                    },
+                    RepoSettings {
+                        description: "",
+                        homepage: None,
+                        archived: false,
+                        auto_merge_enabled: false,
+                        allow_squash_merge: true,
+                        allow_merge_commit: true,
+                        allow_rebase_merge: true,
+                        delete_branch_on_merge: false,
+                        has_issues: true,
+                        has_wiki: true,
+                        has_projects: true,
+                        has_discussions: false,
+                    },
+                ),
+                default_branch_diff: None,
+                permission_diffs: [],
+                branch_protection_diffs: [],
+                ruleset_diffs: [],
+                environment_diffs: [],
+                label_diffs: [],
+                webhook_diffs: [],
+                autolink_diffs: [],
+                deploy_key_diffs: [
+                    Delete {
+                        id: 1,
+                        title: "docs",
+                        fingerprint: Some(
+                            "SHA256:V6ckMVbv999olVVoHxke8kydbmkznsCb85kBHgJEVjw",
+                        ),
+                        read_only: false,
+                    },
+                ],
+                actions_diff: None,
+            },
+        ),
+    ]
+    "#);
+}
+
+#[tokio::test]
+async fn repo_sync_actions_permissions() {
+    let mut model = DataModel::default();
+    model.create_repo(RepoData::new("repo1"));
+    let gh = model.gh_model();
+
+    model.get_repo("repo1").actions = Some(v1::ActionsPermissions {
+        default_workflow_permissions: v1::WorkflowPermissions::Write,
+        can_approve_pull_requests: true,
+        allowed_actions: v1::AllowedActions::LocalOnly,
+        selected_actions: None,
+    });
+
+    let diff = model.diff_repos(gh).await;
+    insta::assert_debug_snapshot!(diff, @r#"
+    [
+        Update(
+            UpdateRepoDiff {
+                org: "rust-lang",
+                name: "repo1",
+                repo_node_id: "0",
+                settings_diff: (
+                    RepoSettings {
+                        description: "",
+                        homepage: None,
+                        archived: false,
+                        auto_merge_enabled: false,
+                        allow_squash_merge: true,
+                        allow_merge_commit: true,
+                        allow_rebase_merge: true,
+                        delete_branch_on_merge: false,
+                        has_issues: true,
+                        has_wiki: true,
+                        has_projects: true,
+                        has_discussions: false,
+                    },
+                    RepoSettings {
+                        description: "",
+                        homepage: None,
+                        archived: false,
+                        auto_merge_enabled: false,
+                        allow_squash_merge: true,
+                        allow_merge_commit: true,
+                        allow_rebase_merge: true,
+                        delete_branch_on_merge: false,
+                        has_issues: true,
+                        has_wiki: true,
+                        has_projects: true,
+                        has_discussions: false,
+                    },
+                ),
+                default_branch_diff: None,
+                permission_diffs: [],
+                branch_protection_diffs: [],
+                ruleset_diffs: [],
+                environment_diffs: [],
+                label_diffs: [],
+                webhook_diffs: [],
+                autolink_diffs: [],
+                deploy_key_diffs: [],
+                actions_diff: Some(
+                    (
+                        ActionsPermissions {
+                            default_workflow_permissions: Read,
+                            can_approve_pull_requests: false,
+                            allowed_actions: All,
+                            selected_actions: None,
+                        },
+                        ActionsPermissions {
+                            default_workflow_permissions: Write,
+                            can_approve_pull_requests: true,
+                            allowed_actions: LocalOnly,
+                            selected_actions: None,
+                        },
+                    ),
+                ),
+            },
+        ),
+    ]
+    "#);
+}
+
+#[tokio::test]
+async fn repo_sync_selected_actions() {
+    let mut model = DataModel::default();
+    model.create_repo(RepoData::new("repo1"));
+    let gh = model.gh_model();
+
+    model.get_repo("repo1").actions = Some(v1::ActionsPermissions {
+        default_workflow_permissions: v1::WorkflowPermissions::Read,
+        can_approve_pull_requests: false,
+        allowed_actions: v1::AllowedActions::Selected,
+        selected_actions: Some(v1::SelectedActions {
+            github_owned_allowed: true,
+            verified_allowed: false,
+            patterns_allowed: vec!["rust-lang/*".to_string()],
+        }),
+    });
+
+    let diff = model.diff_repos(gh).await;
+    let [RepoDiff::Update(update)] = diff.as_slice() else {
+        panic!("unexpected diff {diff:?}");
+    };
+    insta::assert_debug_snapshot!(update.actions_diff, @r#"
+    Some(
+        (
+            ActionsPermissions {
+                default_workflow_permissions: Read,
+                can_approve_pull_requests: false,
+                allowed_actions: All,
+                selected_actions: None,
+            },
+            ActionsPermissions {
+                default_workflow_permissions: Read,
+                can_approve_pull_requests: false,
+                allowed_actions: Selected,
+                selected_actions: Some(
+                    SelectedActions {
+                        github_owned_allowed: true,
+                        verified_allowed: false,
+                        patterns_allowed: [
+                            "rust-lang/*",
+                        ],
+                    },
+                ),
+            },
+        ),
+    )
+    "#);
+}
+
+#[tokio::test]
+async fn repo_require_code_owner_reviews() {
+    let mut model = DataModel::default();
+    model.create_repo(RepoData::new("repo1").branch_protections(vec![
+        BranchProtectionBuilder::pr_required("main", &["test"], 1).build(),
+    ]));
+
+    let gh = model.gh_model();
+    let protection = model
+        .get_repo("repo1")
+        .branch_protections
+        .last_mut()
+        .unwrap();
+    match &mut protection.mode {
+        BranchProtectionMode::PrRequired {
+            requires_code_owner_reviews,
+            ..
+        } => {
+            *requires_code_owner_reviews = true;
+        }
+        BranchProtectionMode::PrNotRequired => unreachable!(),
+    }
+
+    let diff = model.diff_repos(gh).await;
+    insta::assert_debug_snapshot!(diff, @r#"
+    [
+        Update(
+            UpdateRepoDiff {
+                org: "rust-lang",
+                name: "repo1",
+                repo_node_id: "0",
+                settings_diff: (
+                    RepoSettings {
+                        description: "",
+                        homepage: None,
+                        archived: false,
+                        auto_merge_enabled: false,
+                        allow_squash_merge: true,
+                        allow_merge_commit: true,
+                        allow_rebase_merge: true,
+                        delete_branch_on_merge: false,
+                        has_issues: true,
+                        has_wiki: true,
+                        has_projects: true,
+                        has_discussions: false,
+                    },
+                    RepoSettings {
+                        description: "",
+                        homepage: None,
+                        archived: false,
+                        auto_merge_enabled: false,
+                        allow_squash_merge: true,
+                        allow_merge_commit: true,
+                        allow_rebase_merge: true,
+                        delete_branch_on_merge: false,
+                        has_issues: true,
+                        has_wiki: true,
+                        has_projects: true,
+                        has_discussions: false,
+                    },
+                ),
+                default_branch_diff: None,
+                permission_diffs: [],
+                branch_protection_diffs: [
+                    BranchProtectionDiff {
+                        pattern: "main",
+                        operation: Update(
+                            "0",
+                            BranchProtection {
+                                pattern: "main",
+                                is_admin_enforced: true,
+                                allows_force_pushes: false,
+                                dismisses_stale_reviews: false,
+                                required_approving_review_count: 1,
+                                required_status_check_contexts: [
+                                    "test",
+                                ],
+                                push_allowances: [],
+                                bypass_pull_request_allowances: [],
+                                requires_approving_reviews: true,
+                                requires_code_owner_reviews: false,
+                                requires_commit_signatures: false,
+                                requires_linear_history: false,
+                                requires_conversation_resolution: false,
+                            },
+                            BranchProtection {
+                                pattern: "main",
+                                is_admin_enforced: true,
+                                allows_force_pushes: false,
+                                dismisses_stale_reviews: false,
+                                required_approving_review_count: 1,
+                                required_status_check_contexts: [
+                                    "test",
+                                ],
+                                push_allowances: [],
+                                bypass_pull_request_allowances: [],
+                                requires_approving_reviews: true,
+                                requires_code_owner_reviews: true,
+                                requires_commit_signatures: false,
+                                requires_linear_history: false,
+                                requires_conversation_resolution: false,
+                            },
+                        ),
+                    },
+                ],
+                ruleset_diffs: [],
+                environment_diffs: [],
+                label_diffs: [],
+                webhook_diffs: [],
+                autolink_diffs: [],
+                deploy_key_diffs: [],
+                actions_diff: None,
+            },
+        ),
+    ]
+    "#);
+}
+
+#[tokio::test]
+async fn repo_add_pr_bypass_to_branch_protection() {
+    let mut model = DataModel::default();
+    model.create_repo(RepoData::new("repo1").branch_protections(vec![
+        BranchProtectionBuilder::pr_required("main", &["test"], 1).build(),
+    ]));
+
+    let gh = model.gh_model();
+    let protection = model
+        .get_repo("repo1")
+        .branch_protections
+        .last_mut()
+        .unwrap();
+    protection.pr_bypass_teams = vec!["team1".to_string()];
+    protection.pr_bypass_apps = vec![MergeBot::RustTimer];
+
+    let diff = model.diff_repos(gh).await;
+    insta::assert_debug_snapshot!(diff, @r#"
+    [
+        Update(
+            UpdateRepoDiff {
+                org: "rust-lang",
+                name: "repo1",
+                repo_node_id: "0",
+                settings_diff: (
+                    RepoSettings {
+                        description: "",
+                        homepage: None,
+                        archived: false,
+                        auto_merge_enabled: false,
+                        allow_squash_merge: true,
+                        allow_merge_commit: true,
+                        allow_rebase_merge: true,
+                        delete_branch_on_merge: false,
+                        has_issues: true,
+                        has_wiki: true,
+                        has_projects: true,
+                        has_discussions: false,
+                    },
+                    RepoSettings {
+                        description: "",
+                        homepage: None,
+                        archived: false,
+                        auto_merge_enabled: false,
+                        allow_squash_merge: true,
+                        allow_merge_commit: true,
+                        allow_rebase_merge: true,
+                        delete_branch_on_merge: false,
+                        has_issues: true,
+                        has_wiki: true,
+                        has_projects: true,
+                        has_discussions: false,
+                    },
+                ),
+                default_branch_diff: None,
+                permission_diffs: [],
+                branch_protection_diffs: [
+                    BranchProtectionDiff {
+                        pattern: "main",
+                        operation: Update(
+                            "0",
+                            BranchProtection {
+                                pattern: "main",
+                                is_admin_enforced: true,
+                                allows_force_pushes: false,
+                                dismisses_stale_reviews: false,
+                                required_approving_review_count: 1,
+                                required_status_check_contexts: [
+                                    "test",
+                                ],
+                                push_allowances: [],
+                                bypass_pull_request_allowances: [],
+                                requires_approving_reviews: true,
+                                requires_code_owner_reviews: false,
+                                requires_commit_signatures: false,
+                                requires_linear_history: false,
+                                requires_conversation_resolution: false,
+                            },
+                            BranchProtection {
+                                pattern: "main",
+                                is_admin_enforced: true,
+                                allows_force_pushes: false,
+                                dismisses_stale_reviews: false,
+                                required_approving_review_count: 1,
+                                required_status_check_contexts: [
+                                    "test",
+                                ],
+                                push_allowances: [],
+                                bypass_pull_request_allowances: [
+                                    Team(
+                                        TeamPushAllowanceActor {
+                                            organization: Login {
+                                                login: "rust-lang",
+                                            },
+                                            name: "team1",
+                                        },
+                                    ),
+                                    User(
+                                        UserPushAllowanceActor {
+                                            login: "rust-timer",
+                                        },
+                                    ),
+                                ],
+                                requires_approving_reviews: true,
+                                requires_code_owner_reviews: false,
+                                requires_commit_signatures: false,
+                                requires_linear_history: false,
+                                requires_conversation_resolution: false,
+                            },
+                        ),
+                    },
+                ],
+                ruleset_diffs: [],
+                environment_diffs: [],
+                label_diffs: [],
+                webhook_diffs: [],
+                autolink_diffs: [],
+                deploy_key_diffs: [],
+                actions_diff: None,
+            },
+        ),
+    ]
+    "#);
+}
+
+#[tokio::test]
+async fn repo_remove_branch_protection() {
+    let mut model = DataModel::default();
+    model.create_repo(
+        RepoData::new("repo1")
+            .team("team1", RepoPermission::Write)
+            .branch_protections(vec![
+                BranchProtectionBuilder::pr_required("main", &["test"], 1).build(),
+                BranchProtectionBuilder::pr_required("stable", &["test"], 0).build(),
+            ]),
+    );
+
+    let gh = model.gh_model();
+    model.get_repo("repo1").branch_protections.pop().unwrap();
+
+    let diff = model.diff_repos(gh).await;
+    insta::assert_debug_snapshot!(diff, @r#"
+    [
+        Update(
+            UpdateRepoDiff {
+                org: "rust-lang",
+                name: "repo1",
+                repo_node_id: "0",
+                settings_diff: (
+                    RepoSettings {
+                        description: "",
+                        homepage: None,
+                        archived: false,
+                        auto_merge_enabled: false,
+                        allow_squash_merge: true,
+                        allow_merge_commit: true,
+                        allow_rebase_merge: true,
+                        delete_branch_on_merge: false,
+                        has_issues: true,
+                        has_wiki: true,
+                        has_projects: true,
+                        has_discussions: false,
+                    },
+                    RepoSettings {
+                        description: "",
+                        homepage: None,
+                        archived: false,
+                        auto_merge_enabled: false,
+                        allow_squash_merge: true,
+                        allow_merge_commit: true,
+                        allow_rebase_merge: true,
+                        delete_branch_on_merge: false,
+                        has_issues: true,
+                        has_wiki: true,
+                        has_projects: true,
+                        has_discussions: false,
+                    },
+                ),
+                default_branch_diff: None,
+                permission_diffs: [],
+                branch_protection_diffs: [
+                    BranchProtectionDiff {
+                        pattern: "stable",
+                        operation: Delete(
+                            "1",
+                            BranchProtection {
+                                pattern: "stable",
+                                is_admin_enforced: true,
+                                allows_force_pushes: false,
+                                dismisses_stale_reviews: false,
+                                required_approving_review_count: 0,
+                                required_status_check_contexts: [
+                                    "test",
+                                ],
+                                push_allowances: [],
+                                bypass_pull_request_allowances: [],
+                                requires_approving_reviews: true,
+                                requires_code_owner_reviews: false,
+                                requires_commit_signatures: false,
+                                requires_linear_history: false,
+                                requires_conversation_resolution: false,
+                            },
+                        ),
+                    },
+                ],
+                ruleset_diffs: [],
+                environment_diffs: [],
+                label_diffs: [],
+                webhook_diffs: [],
+                autolink_diffs: [],
+                deploy_key_diffs: [],
+                actions_diff: None,
+            },
+        ),
+    ]
+    "#);
+}
+
+#[tokio::test]
+async fn repo_create_with_merge_queue() {
+    let mut model = DataModel::default();
+    let gh = model.gh_model();
+
+    let mut protection = BranchProtectionBuilder::pr_required("main", &["test"], 1);
+    protection.merge_queue = true;
+    protection.merge_queue_settings = Some(MergeQueueSettings {
+        merge_method: MergeQueueMethod::Squash,
+        min_entries_to_merge: None,
+        max_entries_to_merge: Some(10),
+        max_entries_to_build: None,
+    });
+    model.create_repo(
+        RepoData::new("repo1")
+            .use_rulesets(true)
+            .branch_protections(vec![protection.build()]),
+    );
+
+    let diff = model.diff_repos(gh).await;
+    insta::assert_debug_snapshot!(diff, @r#"
+    [
+        Create(
+            CreateRepoDiff {
+                org: "rust-lang",
+                name: "repo1",
+                default_branch: None,
+                settings: RepoSettings {
+                    description: "",
+                    homepage: None,
+                    archived: false,
+                    auto_merge_enabled: false,
+                    allow_squash_merge: true,
+                    allow_merge_commit: true,
+                    allow_rebase_merge: true,
+                    delete_branch_on_merge: false,
+                    has_issues: true,
+                    has_wiki: true,
+                    has_projects: true,
+                    has_discussions: false,
+                },
+                permissions: [],
+                branch_protections: [],
+                rulesets: [
+                    Ruleset {
+                        id: None,
+                        name: "main",
+                        target: Branch,
+                        source_type: Repository,
+                        enforcement: Active,
+                        bypass_actors: [],
+                        conditions: RulesetConditions {
+                            ref_name: RulesetRefNameCondition {
+                                include: [
+                                    "refs/heads/main",
+                                ],
+                                exclude: [],
+                            },
+                        },
+                        rules: {
+                            Creation,
+                            Deletion,
+                            MergeQueue {
+                                parameters: MergeQueueParameters {
+                                    check_response_timeout_minutes: 60,
+                                    grouping_strategy: Allgreen,
+                                    max_entries_to_build: 5,
+                                    max_entries_to_merge: 10,
+                                    merge_method: Squash,
+                                    min_entries_to_merge: 1,
+                                    min_entries_to_merge_wait_minutes: 5,
+                                },
+                            },
+                            PullRequest {
+                                parameters: PullRequestParameters {
+                                    dismiss_stale_reviews_on_push: false,
+                                    require_code_owner_review: false,
+                                    require_last_push_approval: false,
+                                    required_approving_review_count: 1,
+                                    required_review_thread_resolution: false,
+                                },
+                            },
+                            RequiredStatusChecks {
+                                parameters: RequiredStatusChecksParameters {
+                                    do_not_enforce_on_create: Some(
+                                        false,
+                                    ),
+                                    required_status_checks: [
+                                        RequiredStatusCheck {
+                                            context: "test",
+                                            integration_id: Some(
+                                                15368,
+                                            ),
+                                        },
+                                    ],
+                                    strict_required_status_checks_policy: false,
+                                },
+                            },
+                            NonFastForward,
+                        },
+                    },
+                ],
+                environments: [],
+                labels: [],
+                webhooks: [],
+                autolinks: [],
+                actions: None,
+                template: None,
+            },
+        ),
+    ]
+    "#);
+}
+
+#[tokio::test]
+async fn independent_orgs_are_not_synced() {
+    let mut model = DataModel::default();
+    let user = model.create_user("sakura");
+
+    let independent_org = "independent-org";
+
+    // Create a team, so that membership is synced.
+    model.create_team(TeamData::new("team").gh_team(independent_org, "team-gh", &[user]));
+
+    let mut gh = model.gh_model();
+
+    // Add a member who is not part of any team.
+    gh.add_member(independent_org, "independent-user-1");
+
+    model.add_independent_github_org(independent_org);
+
+    let gh_org_diff = model.diff_org_membership(gh).await;
+
+    // No members should be removed for independent organizations
+    insta::assert_debug_snapshot!(gh_org_diff, @"[]");
+}
+
+#[tokio::test]
+async fn repo_environment_noop() {
+    let mut model = DataModel::default();
+    model.create_repo(
+        RepoData::new("repo1")
+            .environment("production")
+            .environment("staging"),
+    );
+    let gh = model.gh_model();
+    let diff = model.diff_repos(gh).await;
+    assert!(diff.is_empty());
+}
+
+#[tokio::test]
+async fn repo_environment_create() {
+    let mut model = DataModel::default();
+    model.create_repo(RepoData::new("repo1"));
+    let gh = model.gh_model();
+
+    model.get_repo("repo1").environments.insert(
+        "production".to_string(),
+        v1::Environment {
+            branches: vec![],
+            tags: vec![],
+            ..Default::default()
+        },
+    );
+    model.get_repo("repo1").environments.insert(
+        "staging".to_string(),
+        v1::Environment {
+            branches: vec![],
+            tags: vec![],
+            ..Default::default()
+        },
+    );
+
+    let diff = model.diff_repos(gh).await;
+    insta::assert_debug_snapshot!(diff, @r#"
+    [
+        Update(
+            UpdateRepoDiff {
+                org: "rust-lang",
+                name: "repo1",
+                repo_node_id: "0",
+                settings_diff: (
+                    RepoSettings {
+                        description: "",
+                        homepage: None,
+                        archived: false,
+                        auto_merge_enabled: false,
+                        allow_squash_merge: true,
+                        allow_merge_commit: true,
+                        allow_rebase_merge: true,
+                        delete_branch_on_merge: false,
+                        has_issues: true,
+                        has_wiki: true,
+                        has_projects: true,
+                        has_discussions: false,
+                    },
+                    RepoSettings {
+                        description: "",
+                        homepage: None,
+                        archived: false,
+                        auto_merge_enabled: false,
+                        allow_squash_merge: true,
+                        allow_merge_commit: true,
+                        allow_rebase_merge: true,
+                        delete_branch_on_merge: false,
+                        has_issues: true,
+                        has_wiki: true,
+                        has_projects: true,
+                        has_discussions: false,
+                    },
+                ),
+                default_branch_diff: None,
+                permission_diffs: [],
+                branch_protection_diffs: [],
+                ruleset_diffs: [],
+                environment_diffs: [
+                    Create(
+                        "production",
+                        Environment {
+                            branches: [],
+                            tags: [],
+                            reviewers: [],
+                            wait_timer: 0,
+                            protected_branches_only: false,
+                        },
+                    ),
+                    Create(
+                        "staging",
+                        Environment {
+                            branches: [],
+                            tags: [],
+                            reviewers: [],
+                            wait_timer: 0,
+                            protected_branches_only: false,
+                        },
+                    ),
+                ],
+                label_diffs: [],
+                webhook_diffs: [],
+                autolink_diffs: [],
+                deploy_key_diffs: [],
+                actions_diff: None,
+            },
+        ),
+    ]
+    "#);
+}
+
+#[tokio::test]
+async fn repo_environment_delete() {
+    let mut model = DataModel::default();
+    model.create_repo(
+        RepoData::new("repo1")
+            .environment("production")
+            .environment("staging"),
+    );
+    let gh = model.gh_model();
+
+    model.get_repo("repo1").environments.clear();
+
+    let diff = model.diff_repos(gh).await;
+    insta::assert_debug_snapshot!(diff, @r#"
+    [
+        Update(
+            UpdateRepoDiff {
+                org: "rust-lang",
+                name: "repo1",
+                repo_node_id: "0",
+                settings_diff: (
+                    RepoSettings {
+                        description: "",
+                        homepage: None,
+                        archived: false,
+                        auto_merge_enabled: false,
+                        allow_squash_merge: true,
+                        allow_merge_commit: true,
+                        allow_rebase_merge: true,
+                        delete_branch_on_merge: false,
+                        has_issues: true,
+                        has_wiki: true,
+                        has_projects: true,
+                        has_discussions: false,
+                    },
+                    RepoSettings {
+                        description: "",
+                        homepage: None,
+                        archived: false,
+                        auto_merge_enabled: false,
+                        allow_squash_merge: true,
+                        allow_merge_commit: true,
+                        allow_rebase_merge: true,
+                        delete_branch_on_merge: false,
+                        has_issues: true,
+                        has_wiki: true,
+                        has_projects: true,
+                        has_discussions: false,
+                    },
+                ),
+                default_branch_diff: None,
+                permission_diffs: [],
+                branch_protection_diffs: [],
+                ruleset_diffs: [],
+                environment_diffs: [
+                    Delete(
+                        "production",
+                    ),
+                    Delete(
+                        "staging",
+                    ),
+                ],
+                label_diffs: [],
+                webhook_diffs: [],
+                autolink_diffs: [],
+                deploy_key_diffs: [],
+                actions_diff: None,
+            },
+        ),
+    ]
+    "#);
+}
+
+#[tokio::test]
+async fn repo_environment_update() {
+    let mut model = DataModel::default();
+    model.create_repo(
+        RepoData::new("repo1")
+            .environment("production")
+            .environment("staging"),
+    );
+    let gh = model.gh_model();
+
+    // Remove staging, keep production, add dev
+    model.get_repo("repo1").environments.clear();
+    model.get_repo("repo1").environments.insert(
+        "production".to_string(),
+        v1::Environment {
+            branches: vec![],
+            tags: vec![],
+            ..Default::default()
+        },
+    );
+    model.get_repo("repo1").environments.insert(
+        "dev".to_string(),
+        v1::Environment {
+            branches: vec![],
+            tags: vec![],
+            ..Default::default()
+        },
+    );
+
+    let diff = model.diff_repos(gh).await;
+    insta::assert_debug_snapshot!(diff, @r#"
+    [
+        Update(
+            UpdateRepoDiff {
+                org: "rust-lang",
+                name: "repo1",
+                repo_node_id: "0",
+                settings_diff: (
+                    RepoSettings {
+                        description: "",
+                        homepage: None,
+                        archived: false,
+                        auto_merge_enabled: false,
+                        allow_squash_merge: true,
+                        allow_merge_commit: true,
+                        allow_rebase_merge: true,
+                        delete_branch_on_merge: false,
+                        has_issues: true,
+                        has_wiki: true,
+                        has_projects: true,
+                        has_discussions: false,
+                    },
+                    RepoSettings {
+                        description: "",
+                        homepage: None,
+                        archived: false,
+                        auto_merge_enabled: false,
+                        allow_squash_merge: true,
+                        allow_merge_commit: true,
+                        allow_rebase_merge: true,
+                        delete_branch_on_merge: false,
+                        has_issues: true,
+                        has_wiki: true,
+                        has_projects: true,
+                        has_discussions: false,
+                    },
+                ),
+                default_branch_diff: None,
+                permission_diffs: [],
+                branch_protection_diffs: [],
+                ruleset_diffs: [],
+                environment_diffs: [
+                    Create(
+                        "dev",
+                        Environment {
+                            branches: [],
+                            tags: [],
+                            reviewers: [],
+                            wait_timer: 0,
+                            protected_branches_only: false,
+                        },
+                    ),
+                    Delete(
+                        "staging",
+                    ),
+                ],
+                label_diffs: [],
+                webhook_diffs: [],
+                autolink_diffs: [],
+                deploy_key_diffs: [],
+                actions_diff: None,
+            },
+        ),
+    ]
+    "#);
+}
+
+#[tokio::test]
+async fn repo_environment_update_branches() {
+    let mut model = DataModel::default();
+    model.create_repo(
+        RepoData::new("repo1").environment_with_branches("production", &["main", "release/*"]),
+    );
+    let gh = model.gh_model();
+
+    // Update branches for production environment
+    model.get_repo("repo1").environments.insert(
+        "production".to_string(),
+        v1::Environment {
+            branches: vec!["main".to_string(), "stable".to_string()],
+            tags: vec![],
+            ..Default::default()
+        },
+    );
+
+    let diff = model.diff_repos(gh).await;
+    insta::assert_debug_snapshot!(diff, @r#"
+    [
+        Update(
+            UpdateRepoDiff {
+                org: "rust-lang",
+                name: "repo1",
+                repo_node_id: "0",
+                settings_diff: (
+                    RepoSettings {
+                        description: "",
+                        homepage: None,
+                        archived: false,
+                        auto_merge_enabled: false,
+                        allow_squash_merge: true,
+                        allow_merge_commit: true,
+                        allow_rebase_merge: true,
+                        delete_branch_on_merge: false,
+                        has_issues: true,
+                        has_wiki: true,
+                        has_projects: true,
+                        has_discussions: false,
+                    },
+                    RepoSettings {
+                        description: "",
+                        homepage: None,
+                        archived: false,
+                        auto_merge_enabled: false,
+                        allow_squash_merge: true,
+                        allow_merge_commit: true,
+                        allow_rebase_merge: true,
+                        delete_branch_on_merge: false,
+                        has_issues: true,
+                        has_wiki: true,
+                        has_projects: true,
+                        has_discussions: false,
+                    },
+                ),
+                default_branch_diff: None,
+                permission_diffs: [],
+                branch_protection_diffs: [],
+                ruleset_diffs: [],
+                environment_diffs: [
+                    Update {
+                        name: "production",
+                        add_branches: [
+                            "stable",
+                        ],
+                        remove_branches: [
+                            "release/*",
+                        ],
+                        add_tags: [],
+                        remove_tags: [],
+                        add_reviewers: [],
+                        remove_reviewers: [],
+                        wait_timer: (
+                            0,
+                            0,
+                        ),
+                        protected_branches_only: (
+                            false,
+                            false,
+                        ),
+                        new: Environment {
+                            branches: [
+                                "main",
+                                "stable",
+                            ],
+                            tags: [],
+                            reviewers: [],
+                            wait_timer: 0,
+                            protected_branches_only: false,
+                        },
+                    },
+                ],
+                label_diffs: [],
+                webhook_diffs: [],
+                autolink_diffs: [],
+                deploy_key_diffs: [],
+                actions_diff: None,
+            },
+        ),
+    ]
+    "#);
+}
+
+#[tokio::test]
+async fn repo_environment_update_protection_rules() {
+    let mut model = DataModel::default();
+    model.create_repo(RepoData::new("repo1").environment("production"));
+    let gh = model.gh_model();
+
+    model.get_repo("repo1").environments.insert(
+        "production".to_string(),
+        v1::Environment {
+            reviewers: vec!["release".to_string()],
+            wait_timer: 30,
+            protected_branches_only: true,
+            ..Default::default()
+        },
+    );
+
+    let diff = model.diff_repos(gh).await;
+    insta::assert_debug_snapshot!(diff, @r#"
+    [
+        Update(
+            UpdateRepoDiff {
+                org: "rust-lang",
+                name: "repo1",
+                repo_node_id: "0",
+                settings_diff: (
+                    RepoSettings {
+                        description: "",
+                        homepage: None,
+                        archived: false,
+                        auto_merge_enabled: false,
+                        allow_squash_merge: true,
+                        allow_merge_commit: true,
+                        allow_rebase_merge: true,
+                        delete_branch_on_merge: false,
+                        has_issues: true,
+                        has_wiki: true,
+                        has_projects: true,
+                        has_discussions: false,
+                    },
+                    RepoSettings {
+                        description: "",
+                        homepage: None,
+                        archived: false,
+                        auto_merge_enabled: false,
+                        allow_squash_merge: true,
+                        allow_merge_commit: true,
+                        allow_rebase_merge: true,
+                        delete_branch_on_merge: false,
+                        has_issues: true,
+                        has_wiki: true,
+                        has_projects: true,
+                        has_discussions: false,
+                    },
+                ),
+                default_branch_diff: None,
+                permission_diffs: [],
+                branch_protection_diffs: [],
+                ruleset_diffs: [],
+                environment_diffs: [
+                    Update {
+                        name: "production",
+                        add_branches: [],
+                        remove_branches: [],
+                        add_tags: [],
+                        remove_tags: [],
+                        add_reviewers: [
+                            "release",
+                        ],
+                        remove_reviewers: [],
+                        wait_timer: (
+                            0,
+                            30,
+                        ),
+                        protected_branches_only: (
+                            false,
+                            true,
+                        ),
+                        new: Environment {
+                            branches: [],
+                            tags: [],
+                            reviewers: [
+                                Team {
+                                    slug: "release",
+                                    id: None,
+                                },
+                            ],
+                            wait_timer: 30,
+                            protected_branches_only: true,
+                        },
+                    },
+                ],
+                label_diffs: [],
+                webhook_diffs: [],
+                autolink_diffs: [],
+                deploy_key_diffs: [],
+                actions_diff: None,
+            },
+        ),
+    ]
+    "#);
+}
+
+#[tokio::test]
+async fn repo_environment_remove_user_reviewer() {
+    let mut model = DataModel::default();
+    model.create_team(TeamData::new("release").gh_team(DEFAULT_ORG, "release-gh", &[]));
+    model.create_repo(RepoData::new("repo1").environment("production"));
+    model
+        .get_repo("repo1")
+        .environments
+        .get_mut("production")
+        .unwrap()
+        .reviewers = vec!["release-gh".to_string()];
+    let mut gh = model.gh_model();
+    gh.add_environment_reviewer(
+        DEFAULT_ORG,
+        "repo1",
+        "production",
+        api::EnvironmentReviewer::User {
+            login: "alice".to_string(),
+            id: 42,
+        },
+    );
+
+    let diff = model.diff_repos(gh).await;
+    insta::assert_debug_snapshot!(diff, @r#"
+    [
+        Update(
+            UpdateRepoDiff {
+                org: "rust-lang",
+                name: "repo1",
+                repo_node_id: "0",
+                settings_diff: (
+                    RepoSettings {
+                        description: "",
+                        homepage: None,
+                        archived: false,
+                        auto_merge_enabled: false,
+                        allow_squash_merge: true,
+                        allow_merge_commit: true,
+                        allow_rebase_merge: true,
+                        delete_branch_on_merge: false,
+                        has_issues: true,
+                        has_wiki: true,
+                        has_projects: true,
+                        has_discussions: false,
+                    },
+                    RepoSettings {
+                        description: "",
+                        homepage: None,
+                        archived: false,
+                        auto_merge_enabled: false,
+                        allow_squash_merge: true,
+                        allow_merge_commit: true,
+                        allow_rebase_merge: true,
+                        delete_branch_on_merge: false,
+                        has_issues: true,
+                        has_wiki: true,
+                        has_projects: true,
+                        has_discussions: false,
+                    },
+                ),
+                default_branch_diff: None,
+                permission_diffs: [],
+                branch_protection_diffs: [],
+                ruleset_diffs: [],
+                environment_diffs: [
+                    Update {
+                        name: "production",
+                        add_branches: [],
+                        remove_branches: [],
+                        add_tags: [],
+                        remove_tags: [],
+                        add_reviewers: [],
+                        remove_reviewers: [
+                            "@alice",
+                        ],
+                        wait_timer: (
+                            0,
+                            0,
+                        ),
+                        protected_branches_only: (
+                            false,
+                            false,
+                        ),
+                        new: Environment {
+                            branches: [],
+                            tags: [],
+                            reviewers: [
+                                Team {
+                                    slug: "release-gh",
+                                    id: Some(
+                                        0,
+                                    ),
+                                },
+                            ],
+                            wait_timer: 0,
+                            protected_branches_only: false,
+                        },
+                    },
+                ],
+                label_diffs: [],
+                webhook_diffs: [],
+                autolink_diffs: [],
+                deploy_key_diffs: [],
+                actions_diff: None,
+            },
+        ),
+    ]
+    "#);
+}
+
+#[tokio::test]
+async fn token_missing_permissions() {
+    let mut model = DataModel::default();
+    model.create_team(TeamData::new("team1").gh_team(DEFAULT_ORG, "members-gh", &[]));
+    model.create_repo(RepoData::new("repo1"));
+
+    let gh = || {
+        let mut gh = model.gh_model();
+        gh.set_token_permissions(api::TokenPermissions::Installation(HashMap::from([
+            ("members".to_string(), "write".to_string()),
+            ("administration".to_string(), "read".to_string()),
+        ])));
+        gh
+    };
+
+    model
+        .check_token_permissions(gh(), Access::Read)
+        .await
+        .expect("Read access should be granted");
+    let err = model
+        .check_token_permissions(gh(), Access::Write)
+        .await
+        .unwrap_err();
+    insta::assert_snapshot!(err, @r"
+    the GitHub token is missing permissions needed by sync-team:
+      - rust-lang: cannot administer repositories (requires the `administration` permission with write access)
+    ");
+}
+
+#[tokio::test]
+async fn team_create_json() {
+    let mut model = DataModel::default();
+    let user = model.create_user("mark");
+    let gh = model.gh_model();
+    model.create_team(TeamData::new("admins").gh_team(DEFAULT_ORG, "admins-gh", &[user]));
+    let team_diff = model.diff_teams(gh).await;
+    insta::assert_snapshot!(serde_json::to_string_pretty(&team_diff).unwrap(), @r#"
+    [
+      {
+        "create": {
+          "org": "rust-lang",
+          "name": "admins-gh",
+          "description": "Managed by the rust-lang/team repository.",
+          "privacy": "closed",
+          "parent": null,
+          "members": [
+            [
+              "mark",
+              "member"
+            ]
+          ]
+        }
+      }
+    ]
+    "#);
+}
+
+#[tokio::test]
+async fn diff_markdown() {
+    let mut model = DataModel::default();
+    let user = model.create_user("mark");
+    model.create_team(
+        TeamData::new("admins")
+            .gh_team(DEFAULT_ORG, "admins-gh", &[user])
+            .gh_team(DEFAULT_ORG, "users-gh", &[user]),
+    );
+    model.create_repo(RepoData::new("repo1"));
+    model.delete_unmanaged_teams_in(DEFAULT_ORG);
+    let gh = model.gh_model();
+
+    model.get_team("admins").remove_gh_team("users-gh");
+    model.create_team(TeamData::new("reviewers").gh_team(DEFAULT_ORG, "reviewers-gh", &[user]));
+    model.get_repo("repo1").description = "New description".to_string();
+
+    let diff = model.diff_all(gh).await;
+    insta::assert_snapshot!(diff.to_markdown(), @r"
+    **+1 ~1 -1 across 1 org**
+
+    ### `rust-lang`
+
+    <details>
+    <summary>🟢 Create team `reviewers-gh`</summary>
+
+    ```text
+    ➕ Creating team:
+      Org: rust-lang
+      Name: reviewers-gh
+      Description: Managed by the rust-lang/team repository.
+      Privacy: closed
+      Members:
+        mark: member
+    ```
+
+    </details>
+
+    <details>
+    <summary>🔴 Delete team `users-gh`</summary>
+
+    ```text
+    ❌ Deleting team 'rust-lang/users-gh'
+    ```
+
+    </details>
+
+    <details>
+    <summary>🟡 Update repo `repo1`</summary>
+
+    ```text
+    📝 Editing repo 'rust-lang/repo1':
+      Set description: 'New description'
+    ```
+
+    </details>
+    ");
+}
+
+#[tokio::test]
+async fn diff_summary_severity() {
+    let mut model = DataModel::default();
+    model.create_repo(RepoData::new("repo1").team("team1", RepoPermission::Write));
+    model.create_repo(RepoData::new("repo2"));
+    let gh = model.gh_model();
+
+    model.create_repo(RepoData::new("repo3"));
+    model.get_repo("repo1").teams.clear();
+    model.get_repo("repo2").description = "New description".to_string();
+
+    let summary = model.diff_all(gh).await.summary();
+    insta::assert_snapshot!(summary, @r"
+    +1 ~1 -1 across 1 org
+    ");
+}
+
+#[tokio::test]
+async fn diff_summary_severity_of_autolinks() {
+    let mut model = DataModel::default();
+    model.create_repo(
+        RepoData::new("repo1")
+            .autolink("RUST-", "https://github.com/rust-lang/rust/issues/<num>")
+            .autolink("CVE-", "https://www.cve.org/CVERecord?id=CVE-<num>"),
+    );
+    model.create_repo(RepoData::new("repo2"));
+    let gh = model.gh_model();
+
+    // Deleting an autolink is destructive, creating one only modifies the repo
+    model.get_repo("repo1").autolinks.shift_remove("CVE-");
+    model.get_repo("repo2").autolinks.insert(
+        "CVE-".to_string(),
+        "https://www.cve.org/CVERecord?id=CVE-<num>".to_string(),
+    );
+
+    let summary = model.diff_all(gh).await.summary();
+    insta::assert_snapshot!(summary, @"+0 ~1 -1 across 1 org");
+}
+
+#[tokio::test]
+async fn deletion_limit_exceeded() {
+    let mut model = DataModel::default();
+    let users = ["mark", "jan", "kobzol"].map(|name| model.create_user(name));
+    model.create_team(TeamData::new("admins").gh_team(DEFAULT_ORG, "admins-gh", &users));
+    model.create_repo(RepoData::new("repo1").team("admins", RepoPermission::Write));
+    let gh = model.gh_model();
+
+    model
+        .get_team("admins")
+        .remove_gh_member("admins-gh", users[0]);
+    model
+        .get_team("admins")
+        .remove_gh_member("admins-gh", users[1]);
+    model.get_repo("repo1").teams.clear();
+
+    let diff = model.diff_all(gh).await;
+    diff.check_deletion_limit(2)
+        .expect("two deletions of each kind are allowed");
+    let error = diff.check_deletion_limit(1).unwrap_err();
+    insta::assert_snapshot!(error, @r"
+    the diff would delete 2 team members, 2 organization members, more than the limit of 1; review the diff and rerun with `--force` if it is intended
+    ");
+}
+
+#[tokio::test]
+async fn deletion_limit_counts_org_members_and_deploy_keys() {
+    let mut model = DataModel::default();
+    let user = model.create_user("sakura");
+    model.create_team(TeamData::new("team-1").gh_team(DEFAULT_ORG, "members-gh", &[user]));
+    model.create_repo(RepoData::new("repo1").deploy_keys(Some(vec![])));
+    let mut gh = model.gh_model();
+    gh.add_member(DEFAULT_ORG, "martin");
+    gh.add_member(DEFAULT_ORG, "jana");
+    gh.add_deploy_key(DEFAULT_ORG, "repo1", "docs", DOCS_KEY, true);
+    gh.add_deploy_key(DEFAULT_ORG, "repo1", "other", OTHER_KEY, true);
+
+    let diff = model.diff_all(gh).await;
+    diff.check_deletion_limit(2)
+        .expect("two deletions of each kind are allowed");
+    let error = diff.check_deletion_limit(1).unwrap_err();
+    insta::assert_snapshot!(error, @r"
+    the diff would delete 2 organization members, 2 deploy keys, more than the limit of 1; review the diff and rerun with `--force` if it is intended
+    ");
+}
+
+#[tokio::test]
+async fn filter_single_repo() {
+    let mut model = DataModel::default();
+    let user = model.create_user("mark");
+    let user2 = model.create_user("jan");
+    model.create_team(TeamData::new("admins").gh_team(DEFAULT_ORG, "admins-gh", &[user, user2]));
+    model.create_repo(RepoData::new("repo1"));
+    model.create_repo(RepoData::new("repo2"));
+    let gh = model.gh_model();
+
+    model
+        .get_team("admins")
+        .remove_gh_member("admins-gh", user2);
+    model.get_repo("repo1").description = "New description".to_string();
+    model.get_repo("repo2").description = "New description".to_string();
+    model.set_github_filter(GitHubFilter {
+        repos: ["rust-lang/repo1".to_string()].into(),
+        ..Default::default()
+    });
+
+    let diff = model.diff_all(gh).await;
+    insta::assert_snapshot!(diff, @r"
+    📊 Summary: +0 ~1 -0 across 1 org
+    💻 Repo Diffs:
+    📝 Editing repo 'rust-lang/repo1':
+      Set description: 'New description'
+    ");
+}
+
+#[tokio::test]
+async fn filter_single_team() {
+    let mut model = DataModel::default();
+    let user = model.create_user("mark");
+    let user2 = model.create_user("jan");
+    model.create_team(TeamData::new("admins").gh_team(DEFAULT_ORG, "admins-gh", &[user]));
+    model.create_team(TeamData::new("users").gh_team(DEFAULT_ORG, "users-gh", &[user2]));
+    model.create_repo(RepoData::new("repo1"));
+    let gh = model.gh_model();
+
+    model.get_team("admins").add_gh_member("admins-gh", user2);
+    model.get_team("users").add_gh_member("users-gh", user);
+    model.get_repo("repo1").description = "New description".to_string();
+    model.set_github_filter(GitHubFilter {
+        teams: ["users-gh".to_string()].into(),
+        ..Default::default()
+    });
+
+    let diff = model.diff_all(gh).await;
+    insta::assert_snapshot!(diff, @r"
+    📊 Summary: +0 ~1 -0 across 1 org
+    💻 Team Diffs:
+    📝 Editing team 'rust-lang/users-gh':
+      Adding member 'mark' with member role
+      (1 other member unchanged)
+    ");
+}
+
+#[tokio::test]
+async fn filter_unknown_repo() {
+    let mut model = DataModel::default();
+    model.create_repo(RepoData::new("repo1"));
+    let gh = model.gh_model();
+
+    model.set_github_filter(GitHubFilter {
+        repos: ["repo2".to_string()].into(),
+        ..Default::default()
+    });
+
+    let error = model.check_github_filter(gh).await.unwrap_err();
+    insta::assert_snapshot!(error, @r"
+    repository `repo2` is not managed by the team repository
+    ");
+}
+
+#[tokio::test]
+async fn plan_outdated() {
+    let mut model = DataModel::default();
+    model.create_repo(RepoData::new("repo1"));
+    model.create_repo(RepoData::new("repo2"));
+    let gh = || model.gh_model();
+    let dir = tempfile::tempdir().unwrap();
+    let plan = dir.path().join("plan.json");
+
+    let mut changed = model.clone();
+    changed.get_repo("repo1").description = "New description".to_string();
+    changed.get_repo("repo2").description = "New description".to_string();
+    changed.diff_all(gh()).await.save_plan(&plan).unwrap();
+    changed
+        .diff_all(gh())
+        .await
+        .check_plan(&plan)
+        .expect("the plan should match the same diff");
+
+    changed.get_repo("repo2").description = "Another description".to_string();
+    let error = changed.diff_all(gh()).await.check_plan(&plan).unwrap_err();
+    let error = error
+        .to_string()
+        .replace(&plan.display().to_string(), "plan.json");
+    insta::assert_snapshot!(error, @r"
+    GitHub or the team data changed since the plan in plan.json was created, create and review a new plan
+    ");
+}
+
+#[tokio::test]
+async fn plan_swapped_values() {
+    let mut model = DataModel::default();
+    model.create_repo(RepoData::new("repo1"));
+    let gh = model.gh_model();
+    let dir = tempfile::tempdir().unwrap();
+    let plan = dir.path().join("plan.json");
+
+    model.get_repo("repo1").description = "New description".to_string();
+    let diff = model.diff_all(gh).await;
+    diff.save_plan(&plan).unwrap();
+
+    // Swap the old and new settings, which would revert the change
+    let mut contents: serde_json::Value =
+        serde_json::from_slice(&std::fs::read(&plan).unwrap()).unwrap();
+    let settings_diff = contents["diff"]["repo_diffs"][0]["update"]["settings_diff"]
+        .as_array_mut()
+        .unwrap();
+    settings_diff.swap(0, 1);
+    std::fs::write(&plan, serde_json::to_vec(&contents).unwrap()).unwrap();
+
+    assert!(diff.check_plan(&plan).is_err());
+}
+
+#[test]
+fn confirmation_answers() {
+    let input = std::io::Cursor::new("maybe\nn\ny\na\n");
+    let mut confirmation = Confirmation::new(Box::new(input), Box::new(std::io::sink()));
+    let answers = (0..4)
+        .map(|i| confirmation.confirm(&i).unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(answers, [false, true, true, true]);
+
+    let input = std::io::Cursor::new("q\n");
+    let mut confirmation = Confirmation::new(Box::new(input), Box::new(std::io::sink()));
+    let error = confirmation.confirm(&"change").unwrap_err();
+    insta::assert_snapshot!(error, @r"
+    stopped applying the changes
+    ");
+}
+
+#[test]
+fn apply_report_continue_on_error() {
+    let mut report = ApplyReport::default();
+    report.record("change 1".to_string(), Ok(()), true).unwrap();
+    report
+        .record(
+            "change 2\n  details".to_string(),
+            Err(anyhow::anyhow!("not found").context("cannot edit team")),
+            true,
+        )
+        .unwrap();
+    report.record("change 3".to_string(), Ok(()), true).unwrap();
+    assert!(!report.is_success());
+    insta::assert_snapshot!(report, @r"
+    2 changes applied, 1 failed
+    ❌ cannot edit team: not found
+        change 2
+          details
+    ");
+
+    let error = report
+        .record(
+            "change 4".to_string(),
+            Err(anyhow::anyhow!("forbidden")),
+            false,
+        )
+        .unwrap_err();
+    assert_eq!(error.to_string(), "forbidden");
+}
+
+#[tokio::test]
+async fn audit_log_records() {
+    let mut model = DataModel::default();
+    let user = model.create_user("mark");
+    model.create_team(TeamData::new("admins").gh_team(DEFAULT_ORG, "admins-gh", &[user]));
+    let gh = model.gh_model();
+
+    model.get_team("admins").remove_gh_member("admins-gh", user);
+    model.create_team(TeamData::new("users").gh_team(DEFAULT_ORG, "users-gh", &[]));
+    let diff = model.diff_all(gh).await;
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("audit.jsonl");
+    let mut audit_log = AuditLog::open(&path).unwrap();
+    let mut results = [Ok(()), Err(anyhow::anyhow!("rate limited"))].into_iter();
+    for team_diff in &diff.team_diffs {
+        let change = AuditedChange::new(team_diff).unwrap();
+        let result = results.next().unwrap();
+        audit_log.record("user/sync-bot", &change, &result).unwrap();
+    }
+
+    let mut runs = HashSet::new();
+    let records = std::fs::read_to_string(&path)
+        .unwrap()
+        .lines()
+        .map(|line| {
+            let mut record: serde_json::Value = serde_json::from_str(line).unwrap();
+            record.as_object_mut().unwrap().remove("timestamp").unwrap();
+            runs.insert(record.as_object_mut().unwrap().remove("run").unwrap());
+            record
+        })
+        .collect::<Vec<_>>();
+    // Both records belong to the same run
+    assert_eq!(runs.len(), 1);
+    insta::assert_snapshot!(serde_json::to_string_pretty(&records).unwrap(), @r#"
+    [
+      {
+        "actor": "user/sync-bot",
+        "change": {
+          "edit": {
+            "description_diff": null,
+            "member_diffs": [
+              [
+                "mark",
+                {
+                  "delete": "member"
+                }
+              ]
+            ],
+            "name": "admins-gh",
+            "name_diff": null,
+            "org": "rust-lang",
+            "parent_diff": null,
+            "privacy_diff": null,
+            "slug": "admins-gh",
+            "unchanged_members": 0
+          }
+        },
+        "inverse": [
+          {
+            "operation": "set_team_membership",
+            "org": "rust-lang",
+            "role": "member",
+            "team": "admins-gh",
+            "user": "mark"
+          }
+        ],
+        "operation": "edit",
+        "org": "rust-lang",
+        "resource": "team/admins-gh"
+      },
+      {
+        "actor": "user/sync-bot",
+        "change": {
+          "create": {
+            "description": "Managed by the rust-lang/team repository.",
+            "members": [],
+            "name": "users-gh",
+            "org": "rust-lang",
+            "parent": null,
+            "privacy": "closed"
+          }
+        },
+        "error": "rate limited",
+        "inverse": [],
+        "operation": "create",
+        "org": "rust-lang",
+        "resource": "team/users-gh"
+      }
+    ]
+    "#);
+}
+
+#[tokio::test]
+async fn team_diff_batches_nesting() {
+    let mut model = DataModel::default();
+    let user = model.create_user("mark");
+    model.create_team(TeamData::new("admins").gh_team(DEFAULT_ORG, "admins-gh", &[user]));
+    let gh = model.gh_model();
+
+    model.create_team(
+        TeamData::new("compiler")
+            .gh_team(DEFAULT_ORG, "compiler-gh", &[user])
+            .gh_team(DEFAULT_ORG, "compiler-contributors-gh", &[user]),
+    );
+    model
+        .get_team("compiler")
+        .set_gh_parent("compiler-contributors-gh", Some("compiler-gh"));
+    model.create_team(TeamData::new("libs").gh_team(DEFAULT_ORG, "libs-gh", &[user]));
+    model
+        .get_team("admins")
+        .set_gh_parent("admins-gh", Some("libs-gh"));
+
+    let batches = team_diff_batches(model.diff_teams(gh).await)
+        .into_iter()
+        .map(|batch| {
+            batch
+                .iter()
+                .map(|diff| match diff {
+                    TeamDiff::Create(c) => format!("create {}", c.name),
+                    TeamDiff::Edit(e) => format!("edit {}", e.name),
+                    TeamDiff::Delete(d) => format!("delete {}", d.name),
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    insta::assert_debug_snapshot!(batches, @r#"
+    [
+        [
+            "create compiler-gh",
+            "create libs-gh",
+        ],
+        [
+            "edit admins-gh",
+            "create compiler-contributors-gh",
+        ],
+    ]
+    "#);
+}
+
+#[tokio::test]
+async fn rollback_inverse_operations() {
+    let mut model = DataModel::default();
+    let user = model.create_user("mark");
+    let user2 = model.create_user("jan");
+    model.create_team(TeamData::new("admins").gh_team(DEFAULT_ORG, "admins-gh", &[user, user2]));
+    model.create_repo(
+        RepoData::new("repo1")
+            .team("admins-gh", RepoPermission::Write)
+            .branch_protections(vec![
+                BranchProtectionBuilder::pr_required("main", &["test"], 1).build(),
+            ]),
+    );
+    let gh = model.gh_model();
+
+    model
+        .get_team("admins")
+        .remove_gh_member("admins-gh", user2);
+    model.get_repo("repo1").teams.clear();
+    model.get_repo("repo1").branch_protections.clear();
+    let diff = model.diff_all(gh).await;
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("audit.jsonl");
+    let mut audit_log = AuditLog::open(&path).unwrap();
+    for team_diff in &diff.team_diffs {
+        let change = AuditedChange::new(team_diff).unwrap();
+        audit_log.record("user/sync-bot", &change, &Ok(())).unwrap();
+    }
+    for repo_diff in &diff.repo_diffs {
+        let change = AuditedChange::new(repo_diff).unwrap();
+        audit_log.record("user/sync-bot", &change, &Ok(())).unwrap();
+    }
+
+    let operations = load_inverse_operations(&path, None).unwrap();
+    insta::assert_debug_snapshot!(operations, @r#"
+    [
+        RestoreBranchProtection {
+            org: "rust-lang",
+            repo: "repo1",
+            repo_node_id: "0",
+            protection: BranchProtection {
+                pattern: "main",
+                is_admin_enforced: true,
+                allows_force_pushes: false,
+                dismisses_stale_reviews: false,
+                required_approving_review_count: 1,
+                required_status_check_contexts: [
+                    "test",
+                ],
+                push_allowances: [],
+                bypass_pull_request_allowances: [],
+                requires_approving_reviews: true,
+                requires_code_owner_reviews: false,
+                requires_commit_signatures: false,
+                requires_linear_history: false,
+                requires_conversation_resolution: false,
+            },
+        },
+        SetRepoPermission {
+            org: "rust-lang",
+            repo: "repo1",
+            collaborator: Team(
+                "admins-gh",
+            ),
+            permission: Write,
+        },
+        SetTeamMembership {
+            org: "rust-lang",
+            team: "admins-gh",
+            user: "jan",
+            role: Member,
+        },
+    ]
+    "#);
+}
+
+#[tokio::test]
+async fn rollback_selects_run() {
+    let mut model = DataModel::default();
+    let user = model.create_user("mark");
+    let user2 = model.create_user("jan");
+    model.create_team(TeamData::new("admins").gh_team(DEFAULT_ORG, "admins-gh", &[user, user2]));
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("audit.jsonl");
+    // Each run removes one member
+    for removed in [user, user2] {
+        let gh = model.gh_model();
+        let mut model = model.clone();
+        model
+            .get_team("admins")
+            .remove_gh_member("admins-gh", removed);
+        let diff = model.diff_teams(gh).await;
+        let mut audit_log = AuditLog::open(&path).unwrap();
+        for team_diff in &diff {
+            let change = AuditedChange::new(team_diff).unwrap();
+            audit_log.record("user/sync-bot", &change, &Ok(())).unwrap();
+        }
+    }
+
+    let runs = std::fs::read_to_string(&path)
+        .unwrap()
+        .lines()
+        .map(|line| {
+            let record: serde_json::Value = serde_json::from_str(line).unwrap();
+            record["run"].as_str().unwrap().to_string()
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(runs.len(), 2);
+    assert_ne!(runs[0], runs[1]);
+
+    let restored_users = |run: Option<&str>| {
+        load_inverse_operations(&path, run)
+            .unwrap()
+            .into_iter()
+            .map(|operation| match operation {
+                InverseOperation::SetTeamMembership { user, .. } => user,
+                operation => panic!("unexpected operation {operation:?}"),
+            })
+            .collect::<Vec<_>>()
+    };
+    // The last run is undone by default
+    assert_eq!(restored_users(None), ["jan"]);
+    assert_eq!(restored_users(Some(&runs[1])), ["jan"]);
+    assert_eq!(restored_users(Some(&runs[0])), ["mark"]);
+    let error = load_inverse_operations(&path, Some("unknown")).unwrap_err();
+    assert!(
+        error
+            .to_string()
+            .contains("has no records of the run `unknown`")
+    );
+}
+
+#[tokio::test]
+async fn destructive_changes_notification() {
+    let mut model = DataModel::default();
+    let user = model.create_user("mark");
+    model.create_team(
+        TeamData::new("admins")
+            .gh_team(DEFAULT_ORG, "admins-gh", &[user])
+            .gh_team(DEFAULT_ORG, "users-gh", &[user]),
+    );
+    model.delete_unmanaged_teams_in(DEFAULT_ORG);
+    model.create_repo(
+        RepoData::new("repo1")
+            .team("admins-gh", RepoPermission::Write)
+            .branch_protections(vec![
+                BranchProtectionBuilder::pr_required("main", &["test"], 1).build(),
+            ]),
+    );
+    let mut gh = model.gh_model();
+    gh.add_member(DEFAULT_ORG, "martin");
+
+    model.get_team("admins").remove_gh_team("users-gh");
+    model.get_repo("repo1").branch_protections.clear();
+    let diff = model.diff_all(gh).await;
+
+    let changes = diff.destructive_changes();
+    assert!(!changes.is_empty());
+    insta::assert_snapshot!(changes, @r"
+    **Deleted teams:**
+    - rust-lang/users-gh
+    **Removed organization members:**
+    - rust-lang/martin
+    **Deleted branch protections:**
+    - rust-lang/repo1: branch protection `main`
+    ");
+}
+
+#[test]
+fn revisions_diff() {
+    let mut model = DataModel::default();
+    let user = model.create_user("mark");
+    let user2 = model.create_user("jan");
+    let user3 = model.create_user("niko");
+    model.create_team(
+        TeamData::new("admins")
+            .gh_team(DEFAULT_ORG, "admins-gh", &[user, user2])
+            .gh_team(DEFAULT_ORG, "users-gh", &[user]),
+    );
+    model.create_repo(
+        RepoData::new("repo1")
+            .team("admins-gh", RepoPermission::Write)
+            .member("jan", RepoPermission::Triage),
+    );
+    let old = model.revision();
+
+    let admins = model.get_team("admins");
+    admins.remove_gh_member("admins-gh", user2);
+    admins.add_gh_member("admins-gh", user3);
+    admins.add_gh_maintainer("admins-gh", user);
+    admins.remove_gh_team("users-gh");
+    let repo = model.get_repo("repo1");
+    repo.teams.clear();
+    repo.add_member("niko", RepoPermission::Admin);
+    model.create_team(TeamData::new("leads").gh_team(DEFAULT_ORG, "leads-gh", &[user3]));
+    let new = model.revision();
+
+    let diff = diff_revisions(&old, &new).unwrap();
+    insta::assert_snapshot!(diff, @r"
+    📊 Summary: +1 ~0 -3 across 1 org
+    💻 Team Diffs:
+    📝 Editing team 'rust-lang/admins-gh':
+      Changing 'mark' role from member to maintainer
+      Adding member 'niko' with member role
+      Deleting member 'jan'
+    ➕ Creating team:
+      Org: rust-lang
+      Name: leads-gh
+      Description: Managed by the rust-lang/team repository.
+      Privacy: closed
+      Members:
+        niko: member
+    ❌ Deleting team 'rust-lang/users-gh'
+    💻 Repo Diffs:
+    📝 Editing repo 'rust-lang/repo1':
+      Permission Changes:
+        Removing team 'admins-gh''s write permission 
+        Giving user 'niko' admin permission
+    ");
+}
+
+#[tokio::test]
+async fn diff_colored_text() {
+    let mut model = DataModel::default();
+    let user = model.create_user("mark");
+    let user2 = model.create_user("jan");
+    model.create_team(TeamData::new("admins").gh_team(DEFAULT_ORG, "admins-gh", &[user, user2]));
+    let gh = model.gh_model();
+
+    model
+        .get_team("admins")
+        .remove_gh_member("admins-gh", user2);
+    model.create_team(TeamData::new("leads").gh_team(DEFAULT_ORG, "leads-gh", &[user]));
+    let diff = model.diff_all(gh).await;
+
+    insta::assert_snapshot!(diff.to_colored_text().replace('\x1b', "^["), @r"
+    📊 Summary: +1 ~0 -2 across 1 org
+    💻 Team Diffs:
+    ^[[33m📝 Editing team 'rust-lang/admins-gh':^[[0m
+    ^[[31m  Deleting member 'jan'^[[0m
+    ^[[33m  (1 other member unchanged)^[[0m
+    ^[[32m➕ Creating team:^[[0m
+    ^[[32m  Org: rust-lang^[[0m
+    ^[[32m  Name: leads-gh^[[0m
+    ^[[32m  Description: Managed by the rust-lang/team repository.^[[0m
+    ^[[32m  Privacy: closed^[[0m
+    ^[[32m  Members:^[[0m
+    ^[[32m    mark: member^[[0m
+    💻 Org membership Diffs:
+    ^[[31m❌ Removing the following members from `rust-lang`:^[[0m
+    ^[[31m  - jan^[[0m
+    ");
+}
+
+#[tokio::test]
+async fn destructive_changes_only_applied() {
+    let mut model = DataModel::default();
+    let user = model.create_user("mark");
+    model.create_team(
+        TeamData::new("admins")
+            .gh_team(DEFAULT_ORG, "admins-gh", &[user])
+            .gh_team(DEFAULT_ORG, "users-gh", &[user]),
+    );
+    model.delete_unmanaged_teams_in(DEFAULT_ORG);
+    model.create_repo(
+        RepoData::new("repo1")
+            .team("admins-gh", RepoPermission::Write)
+            .branch_protections(vec![
+                BranchProtectionBuilder::pr_required("main", &["test"], 1).build(),
+            ]),
+    );
+    let mut gh = model.gh_model();
+    gh.add_member(DEFAULT_ORG, "martin");
+
+    model.get_team("admins").remove_gh_team("users-gh");
+    model.get_repo("repo1").branch_protections.clear();
+    let diff = model.diff_all(gh).await;
+
+    // The removal of the branch protection is declined
+    let input = std::io::Cursor::new("y\nn\ny\n");
+    let options = ApplyOptions {
+        deletion_limit: None,
+        confirmation: Some(Confirmation::new(
+            Box::new(input),
+            Box::new(std::io::sink()),
+        )),
+        continue_on_error: false,
+        audit_log: None,
+        concurrency: 1,
+    };
+    let sync = GitHubWrite::new(HttpClient::local("http://127.0.0.1:1").unwrap(), true).unwrap();
+    let mut report = ApplyReport::default();
+    diff.apply(&sync, options, &mut report).await.unwrap();
+
+    assert_eq!(report.applied, 2);
+    insta::assert_snapshot!(report.removed, @r"
+    **Deleted teams:**
+    - rust-lang/users-gh
+    **Removed organization members:**
+    - rust-lang/martin
+    ");
+}
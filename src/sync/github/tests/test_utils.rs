@@ -4,20 +4,20 @@ use std::collections::{BTreeSet, HashMap, HashSet};
 
 use derive_builder::Builder;
 use rust_team_data::v1::{
-    self, Bot, BranchProtectionMode, Environment, GitHubTeam, MergeBot, Person, ProtectionTarget,
+    self, Bot, BranchProtectionMode, GitHubTeam, MergeBot, Person, ProtectionTarget,
     RepoPermission, TeamGitHub, TeamKind,
 };
 
 use crate::schema;
-use crate::sync::Config;
 use crate::sync::github::api::{
     BranchPolicy, BranchProtection, GithubRead, Repo, RepoTeam, RepoUser, Ruleset, Team,
     TeamMember, TeamPrivacy, TeamRole,
 };
 use crate::sync::github::{
-    OrgMembershipDiff, RepoDiff, SyncGitHub, TeamDiff, api, construct_branch_protection,
-    convert_permission,
+    Access, Diff, OrgDiff, OrgMembershipDiff, OrgOwnersDiff, RepoDiff, Revision, SyncGitHub,
+    TeamDiff, api, construct_branch_protection, convert_permission,
 };
+use crate::sync::{Config, GitHubFilter};
 
 pub const DEFAULT_ORG: &str = "rust-lang";
 
@@ -33,6 +33,7 @@ pub struct DataModel {
     people: Vec<Person>,
     teams: Vec<TeamData>,
     repos: Vec<RepoData>,
+    orgs: Vec<v1::Org>,
     config: Config,
 }
 
@@ -44,10 +45,26 @@ impl DataModel {
             email: Some(format!("{name}@rust.com")),
             github_id,
             github_sponsors: false,
+            website: None,
+            pronouns: None,
+            timezone: None,
+            on_leave_until: None,
+            former_github_usernames: vec![],
         });
         github_id
     }
 
+    /// Rename a person in the data, recording their previous name as a former username
+    pub fn rename_user(&mut self, id: UserId, name: &str) {
+        let person = self
+            .people
+            .iter_mut()
+            .find(|person| person.github_id == id)
+            .expect("User not found");
+        let former = std::mem::replace(&mut person.name, name.to_string());
+        person.former_github_usernames.push(former);
+    }
+
     pub fn create_team(&mut self, team: TeamDataBuilder) {
         let team = team.build().expect("Cannot build team");
         self.teams.push(team);
@@ -72,14 +89,40 @@ impl DataModel {
             .expect("Repo not found")
     }
 
+    pub fn create_org(&mut self, name: &str) {
+        self.orgs.push(v1::Org {
+            name: name.to_string(),
+            default_repository_permission: v1::DefaultRepoPermission::Read,
+            members_can_create_repositories: false,
+            members_can_fork_private_repositories: false,
+        });
+    }
+
+    pub fn get_org(&mut self, name: &str) -> &mut v1::Org {
+        self.orgs
+            .iter_mut()
+            .find(|o| o.name == name)
+            .expect("Org not found")
+    }
+
     pub fn add_allowed_org_member(&mut self, member: &str) {
         self.config.special_org_members.insert(member.to_string());
     }
 
+    pub fn delete_unmanaged_teams_in(&mut self, org: &str) {
+        self.config
+            .unmanaged_team_deletion_orgs
+            .insert(org.to_string());
+    }
+
     pub fn add_independent_github_org(&mut self, org: &str) {
         self.config.independent_github_orgs.insert(org.to_string());
     }
 
+    pub fn set_github_filter(&mut self, filter: GitHubFilter) {
+        self.config.github_filter = filter;
+    }
+
     /// Creates a GitHub model from the current team data mock.
     /// Note that all users should have been created before calling this method, so that
     /// GitHub knows about the users' existence.
@@ -105,7 +148,11 @@ impl DataModel {
                                 *member,
                                 TeamMember {
                                     username: users.get(member).expect("User not found").clone(),
-                                    role: TeamRole::Member,
+                                    role: if gh_team.maintainers.contains(member) {
+                                        TeamRole::Maintainer
+                                    } else {
+                                        TeamRole::Member
+                                    },
                                 },
                             )
                         })
@@ -116,9 +163,18 @@ impl DataModel {
                 org.teams.push(api::Team {
                     id: Some(org.teams.len() as u64),
                     name: gh_team.name.clone(),
-                    description: Some("Managed by the rust-lang/team repository.".to_string()),
-                    privacy: TeamPrivacy::Closed,
+                    description: Some(gh_team.description.clone().unwrap_or_else(|| {
+                        "Managed by the rust-lang/team repository.".to_string()
+                    })),
+                    privacy: match gh_team.privacy {
+                        Some(v1::GitHubTeamPrivacy::Secret) => TeamPrivacy::Secret,
+                        Some(v1::GitHubTeamPrivacy::Closed) | None => TeamPrivacy::Closed,
+                    },
                     slug: gh_team.name.clone(),
+                    parent: gh_team
+                        .parent
+                        .clone()
+                        .map(|name| api::ParentTeam { name, id: None }),
                 });
 
                 org.members.extend(
@@ -130,6 +186,16 @@ impl DataModel {
                 );
             }
         }
+        for org in orgs.values_mut() {
+            let ids: HashMap<String, Option<u64>> = org
+                .teams
+                .iter()
+                .map(|team| (team.name.clone(), team.id))
+                .collect();
+            for parent in org.teams.iter_mut().filter_map(|team| team.parent.as_mut()) {
+                parent.id = ids.get(&parent.name).copied().flatten();
+            }
+        }
 
         for repo in &self.repos {
             let org = orgs.entry(repo.org.clone()).or_default();
@@ -141,9 +207,18 @@ impl DataModel {
                     org: repo.org.clone(),
                     description: repo.description.clone(),
                     homepage: repo.homepage.clone(),
-                    archived: false,
+                    archived: repo.archived,
                     private: false,
                     allow_auto_merge: None,
+                    allow_squash_merge: repo.allow_squash_merge,
+                    allow_merge_commit: repo.allow_merge_commit,
+                    allow_rebase_merge: repo.allow_rebase_merge,
+                    delete_branch_on_merge: repo.delete_branch_on_merge,
+                    default_branch: repo.default_branch.clone(),
+                    has_issues: repo.has_issues,
+                    has_wiki: repo.has_wiki,
+                    has_projects: repo.has_projects,
+                    has_discussions: repo.has_discussions,
                 },
             );
             let teams = repo
@@ -152,12 +227,7 @@ impl DataModel {
                 .into_iter()
                 .map(|t| api::RepoTeam {
                     name: t.name,
-                    permission: match t.permission {
-                        RepoPermission::Write => api::RepoPermission::Write,
-                        RepoPermission::Admin => api::RepoPermission::Admin,
-                        RepoPermission::Maintain => api::RepoPermission::Maintain,
-                        RepoPermission::Triage => api::RepoPermission::Triage,
-                    },
+                    permission: convert_permission(&t.permission),
                 })
                 .collect();
             let members = repo
@@ -175,25 +245,109 @@ impl DataModel {
             let repo_v1: v1::Repo = repo.clone().into();
             let mut protections = vec![];
             for protection in &repo.branch_protections {
-                protections.push((
-                    format!("{}", protections.len()),
-                    construct_branch_protection(&repo_v1, protection),
-                ));
+                let mut gh_protection = construct_branch_protection(&repo_v1, protection);
+                gh_protection.push_allowances.extend(
+                    protection
+                        .allowed_push_apps
+                        .iter()
+                        .map(|slug| api::PushAllowanceActor::App(mock_app(slug))),
+                );
+                protections.push((format!("{}", protections.len()), gh_protection));
             }
             org.branch_protections
                 .insert(repo.name.clone(), protections);
 
-            let environments: HashMap<String, Environment> =
-                repo.environments.clone().into_iter().collect();
+            let environments: HashMap<String, api::Environment> = repo
+                .environments
+                .iter()
+                .map(|(name, env)| {
+                    let reviewers = env
+                        .reviewers
+                        .iter()
+                        .map(|name| {
+                            let team = org.teams.iter().find(|t| &t.name == name);
+                            api::EnvironmentReviewer::Team {
+                                slug: team.map(|t| t.slug.clone()).unwrap_or(name.clone()),
+                                id: team.and_then(|t| t.id),
+                            }
+                        })
+                        .collect();
+                    let env = api::Environment {
+                        branches: env.branches.clone(),
+                        tags: env.tags.clone(),
+                        reviewers,
+                        wait_timer: env.wait_timer,
+                        protected_branches_only: env.protected_branches_only,
+                    };
+                    (name.clone(), env)
+                })
+                .collect();
             org.repo_environments
                 .insert(repo.name.clone(), environments);
+
+            let labels = repo
+                .labels
+                .iter()
+                .map(|label| api::RepoLabel {
+                    name: label.name.clone(),
+                    color: label.color.clone(),
+                    description: label.description.clone(),
+                })
+                .collect();
+            org.labels.insert(repo.name.clone(), labels);
+
+            let webhooks = repo
+                .webhooks
+                .iter()
+                .enumerate()
+                .map(|(id, webhook)| api::RepoWebhook {
+                    id: id as u64,
+                    active: true,
+                    events: webhook.events.clone(),
+                    config: api::RepoWebhookConfig {
+                        url: Some(webhook.url.clone()),
+                        content_type: Some(webhook.content_type.as_str().to_string()),
+                    },
+                })
+                .collect();
+            org.webhooks.insert(repo.name.clone(), webhooks);
+
+            let autolinks = repo
+                .autolinks
+                .iter()
+                .enumerate()
+                .map(|(id, (key_prefix, url_template))| api::RepoAutolink {
+                    id: id as u64,
+                    key_prefix: key_prefix.clone(),
+                    url_template: url_template.clone(),
+                })
+                .collect();
+            org.autolinks.insert(repo.name.clone(), autolinks);
+
+            if let Some(actions) = &repo.actions {
+                org.actions_permissions
+                    .insert(repo.name.clone(), actions.clone());
+            }
+        }
+
+        for org in &self.orgs {
+            orgs.entry(org.name.clone()).or_default().settings = api::OrgSettings {
+                default_repository_permission: org.default_repository_permission,
+                members_can_create_repositories: org.members_can_create_repositories,
+                members_can_fork_private_repositories: org.members_can_fork_private_repositories,
+            };
         }
 
         if orgs.is_empty() {
             orgs.insert(DEFAULT_ORG.to_string(), GithubOrg::default());
         }
 
-        GithubMock { users, orgs }
+        GithubMock {
+            users,
+            orgs,
+            token_permissions: None,
+            app_token: false,
+        }
     }
 
     pub async fn diff_org_membership(&self, github: GithubMock) -> Vec<OrgMembershipDiff> {
@@ -204,6 +358,21 @@ impl DataModel {
             .expect("Cannot diff org membership")
     }
 
+    pub async fn diff_org_owners(&self, github: GithubMock) -> Vec<OrgOwnersDiff> {
+        self.create_sync(github)
+            .await
+            .diff_org_owners()
+            .expect("Cannot diff org owners")
+    }
+
+    pub async fn diff_orgs(&self, github: GithubMock) -> Vec<OrgDiff> {
+        self.create_sync(github)
+            .await
+            .diff_orgs()
+            .await
+            .expect("Cannot diff orgs")
+    }
+
     pub async fn diff_teams(&self, github: GithubMock) -> Vec<TeamDiff> {
         self.create_sync(github)
             .await
@@ -212,22 +381,68 @@ impl DataModel {
             .expect("Cannot diff teams")
     }
 
-    pub async fn diff_repos(&self, github: GithubMock) -> Vec<RepoDiff> {
+    pub async fn diff_all(&self, github: GithubMock) -> Diff {
         self.create_sync(github)
             .await
-            .diff_repos()
+            .diff_all()
+            .await
+            .expect("Cannot diff GitHub")
+    }
+
+    pub async fn check_github_filter(&self, github: GithubMock) -> anyhow::Result<()> {
+        self.create_sync(github).await.check_filter()
+    }
+
+    pub async fn diff_repos(&self, github: GithubMock) -> Vec<RepoDiff> {
+        self.try_diff_repos(github)
             .await
             .expect("Cannot diff repos")
     }
 
+    pub async fn try_diff_repos(&self, github: GithubMock) -> anyhow::Result<Vec<RepoDiff>> {
+        self.create_sync(github).await.diff_repos().await
+    }
+
+    pub async fn check_token_permissions(
+        &self,
+        github: GithubMock,
+        access: Access,
+    ) -> anyhow::Result<()> {
+        self.try_create_sync(github, access).await.map(|_| ())
+    }
+
+    /// The data model as a revision of the team data
+    pub fn revision(&self) -> Revision {
+        Revision {
+            teams: self.teams.iter().cloned().map(|t| t.into()).collect(),
+            repos: self.repos.iter().cloned().map(|r| r.into()).collect(),
+            people: v1::People {
+                people: self
+                    .people
+                    .iter()
+                    .map(|person| (person.name.clone(), person.clone()))
+                    .collect(),
+            },
+        }
+    }
+
     async fn create_sync(&self, github: GithubMock) -> SyncGitHub {
+        self.try_create_sync(github, Access::Write)
+            .await
+            .expect("Cannot create SyncGitHub")
+    }
+
+    async fn try_create_sync(
+        &self,
+        github: GithubMock,
+        access: Access,
+    ) -> anyhow::Result<SyncGitHub> {
         let teams = self.teams.iter().cloned().map(|t| t.into()).collect();
         let repos = self.repos.iter().cloned().map(|r| r.into()).collect();
+        let orgs = self.orgs.clone();
         let config = self.config.clone();
 
-        SyncGitHub::new(Box::new(github), teams, repos, config)
-            .await
-            .expect("Cannot create SyncGitHub")
+        SyncGitHub::new(Box::new(github), teams, repos, orgs, config, access).await
     }
 }
 
@@ -255,6 +470,29 @@ impl TeamData {
         self.github_team(team).members.retain(|u| *u != user);
     }
 
+    pub fn set_gh_parent(&mut self, team: &str, parent: Option<&str>) {
+        self.github_team(team).parent = parent.map(|p| p.to_string());
+    }
+
+    /// Renames a GitHub team, tracking it by the ID that it has in the GitHub mock
+    pub fn rename_gh_team(&mut self, team: &str, new_name: &str, team_id: u64) {
+        let gh_team = self.github_team(team);
+        gh_team.name = new_name.to_string();
+        gh_team.team_id = Some(team_id);
+    }
+
+    pub fn add_gh_maintainer(&mut self, team: &str, member: UserId) {
+        self.github_team(team).maintainers.push(member);
+    }
+
+    pub fn set_gh_description(&mut self, team: &str, description: &str) {
+        self.github_team(team).description = Some(description.to_string());
+    }
+
+    pub fn set_gh_privacy(&mut self, team: &str, privacy: v1::GitHubTeamPrivacy) {
+        self.github_team(team).privacy = Some(privacy);
+    }
+
     pub fn remove_gh_team(&mut self, name: &str) {
         self.gh_teams.retain(|t| t.name != name);
     }
@@ -284,6 +522,8 @@ impl From<TeamData> for v1::Team {
             github: (!gh_teams.is_empty()).then_some(TeamGitHub { teams: gh_teams }),
             website_data: None,
             roles: vec![],
+            zulip_groups: vec![],
+            zulip_streams: vec![],
         }
     }
 }
@@ -294,7 +534,31 @@ impl TeamDataBuilder {
         gh_teams.push(GitHubTeam {
             org: org.to_string(),
             name: name.to_string(),
+            team_id: None,
             members: members.to_vec(),
+            maintainers: vec![],
+            org_owners: false,
+            parent: None,
+            description: None,
+            privacy: None,
+        });
+        self.gh_teams = Some(gh_teams);
+        self
+    }
+
+    /// Adds a GitHub team whose members should be owners of the organization
+    pub fn org_owners_gh_team(mut self, org: &str, name: &str, members: &[UserId]) -> Self {
+        let mut gh_teams = self.gh_teams.unwrap_or_default();
+        gh_teams.push(GitHubTeam {
+            org: org.to_string(),
+            name: name.to_string(),
+            team_id: None,
+            members: members.to_vec(),
+            maintainers: vec![],
+            org_owners: true,
+            parent: None,
+            description: None,
+            privacy: None,
         });
         self.gh_teams = Some(gh_teams);
         self
@@ -322,9 +586,43 @@ pub struct RepoData {
     #[builder(default)]
     pub allow_auto_merge: bool,
     #[builder(default)]
+    pub allow_squash_merge: Option<bool>,
+    #[builder(default)]
+    pub allow_merge_commit: Option<bool>,
+    #[builder(default)]
+    pub allow_rebase_merge: Option<bool>,
+    #[builder(default)]
+    pub delete_branch_on_merge: Option<bool>,
+    #[builder(default)]
+    pub default_branch: Option<String>,
+    #[builder(default)]
+    pub has_issues: Option<bool>,
+    #[builder(default)]
+    pub has_wiki: Option<bool>,
+    #[builder(default)]
+    pub has_projects: Option<bool>,
+    #[builder(default)]
+    pub has_discussions: Option<bool>,
+    #[builder(default = false)]
+    pub use_rulesets: bool,
+    #[builder(default)]
     pub branch_protections: Vec<v1::BranchProtection>,
     #[builder(default)]
     pub environments: IndexMap<String, v1::Environment>,
+    #[builder(default)]
+    pub labels: Vec<v1::Label>,
+    #[builder(default)]
+    pub webhooks: Vec<v1::Webhook>,
+    #[builder(default)]
+    pub autolinks: IndexMap<String, String>,
+    #[builder(default)]
+    pub deploy_keys: Option<Vec<v1::DeployKey>>,
+    #[builder(default)]
+    pub actions: Option<v1::ActionsPermissions>,
+    #[builder(default)]
+    pub template: Option<String>,
+    #[builder(default)]
+    pub archive_at: Option<String>,
 }
 
 impl RepoData {
@@ -337,6 +635,7 @@ impl RepoData {
         self.members.push(v1::RepoMember {
             name: name.to_string(),
             permission,
+            former_names: vec![],
         });
     }
 
@@ -360,14 +659,32 @@ impl From<RepoData> for v1::Repo {
             members,
             archived,
             allow_auto_merge,
+            allow_squash_merge,
+            allow_merge_commit,
+            allow_rebase_merge,
+            delete_branch_on_merge,
+            default_branch,
+            has_issues,
+            has_wiki,
+            has_projects,
+            has_discussions,
+            use_rulesets,
             branch_protections,
             environments,
+            labels,
+            webhooks,
+            autolinks,
+            deploy_keys,
+            actions,
+            template,
+            archive_at,
         } = value;
         Self {
             org,
             name: name.clone(),
             description,
             homepage,
+            topics: vec![],
             bots,
             teams: teams.clone(),
             members: members.clone(),
@@ -377,6 +694,23 @@ impl From<RepoData> for v1::Repo {
             archived,
             private: false,
             auto_merge_enabled: allow_auto_merge,
+            allow_squash_merge,
+            allow_merge_commit,
+            allow_rebase_merge,
+            delete_branch_on_merge,
+            default_branch,
+            has_issues,
+            has_wiki,
+            has_projects,
+            has_discussions,
+            use_rulesets,
+            labels,
+            webhooks,
+            autolinks,
+            deploy_keys,
+            actions,
+            template,
+            archive_at,
         }
     }
 }
@@ -397,6 +731,7 @@ impl RepoDataBuilder {
         members.push(v1::RepoMember {
             name: name.to_string(),
             permission,
+            former_names: vec![],
         });
         self.members = Some(members);
         self
@@ -409,6 +744,7 @@ impl RepoDataBuilder {
             v1::Environment {
                 branches: Vec::new(),
                 tags: Vec::new(),
+                ..Default::default()
             },
         );
         self.environments = Some(environments);
@@ -422,11 +758,42 @@ impl RepoDataBuilder {
             v1::Environment {
                 branches: branches.iter().map(|s| s.to_string()).collect(),
                 tags: Vec::new(),
+                ..Default::default()
             },
         );
         self.environments = Some(environments);
         self
     }
+
+    pub fn label(mut self, name: &str, color: &str) -> Self {
+        let mut labels = self.labels.clone().unwrap_or_default();
+        labels.push(v1::Label {
+            name: name.to_string(),
+            color: color.to_string(),
+            description: String::new(),
+        });
+        self.labels = Some(labels);
+        self
+    }
+
+    pub fn webhook(mut self, url: &str, events: &[&str]) -> Self {
+        let mut webhooks = self.webhooks.clone().unwrap_or_default();
+        webhooks.push(v1::Webhook {
+            url: url.to_string(),
+            events: events.iter().map(|e| e.to_string()).collect(),
+            content_type: v1::WebhookContentType::Json,
+            secret_env: None,
+        });
+        self.webhooks = Some(webhooks);
+        self
+    }
+
+    pub fn autolink(mut self, key_prefix: &str, url_template: &str) -> Self {
+        let mut autolinks = self.autolinks.clone().unwrap_or_default();
+        autolinks.insert(key_prefix.to_string(), url_template.to_string());
+        self.autolinks = Some(autolinks);
+        self
+    }
 }
 
 #[derive(Clone)]
@@ -438,7 +805,14 @@ pub struct BranchProtectionBuilder {
     pub mode: BranchProtectionMode,
     pub allowed_merge_teams: Vec<String>,
     pub allowed_merge_apps: Vec<MergeBot>,
+    pub allowed_push_apps: Vec<String>,
+    pub pr_bypass_teams: Vec<String>,
+    pub pr_bypass_apps: Vec<MergeBot>,
     pub merge_queue: bool,
+    pub merge_queue_settings: Option<v1::MergeQueueSettings>,
+    pub requires_signed_commits: bool,
+    pub required_linear_history: bool,
+    pub requires_conversation_resolution: bool,
     pub prevent_creation: bool,
     pub prevent_update: bool,
     pub prevent_deletion: bool,
@@ -452,6 +826,7 @@ impl BranchProtectionBuilder {
             BranchProtectionMode::PrRequired {
                 ci_checks: ci_checks.iter().map(|s| s.to_string()).collect(),
                 required_approvals,
+                requires_code_owner_reviews: false,
             },
         )
     }
@@ -469,7 +844,14 @@ impl BranchProtectionBuilder {
             mode,
             allowed_merge_teams,
             allowed_merge_apps,
+            allowed_push_apps,
+            pr_bypass_teams,
+            pr_bypass_apps,
             merge_queue,
+            merge_queue_settings,
+            requires_signed_commits,
+            required_linear_history,
+            requires_conversation_resolution,
             prevent_creation,
             prevent_update,
             prevent_deletion,
@@ -483,7 +865,14 @@ impl BranchProtectionBuilder {
             mode,
             allowed_merge_teams,
             allowed_merge_apps,
+            allowed_push_apps,
+            pr_bypass_teams,
+            pr_bypass_apps,
             merge_queue,
+            merge_queue_settings,
+            requires_signed_commits,
+            required_linear_history,
+            requires_conversation_resolution,
             prevent_creation,
             prevent_update,
             prevent_deletion,
@@ -502,7 +891,14 @@ impl BranchProtectionBuilder {
             dismiss_stale_review: false,
             allowed_merge_teams: vec![],
             allowed_merge_apps: vec![],
+            allowed_push_apps: vec![],
+            pr_bypass_teams: vec![],
+            pr_bypass_apps: vec![],
             merge_queue: false,
+            merge_queue_settings: None,
+            requires_signed_commits: false,
+            required_linear_history: false,
+            requires_conversation_resolution: false,
             prevent_creation: schema::branch_protection_default_prevent_creation(),
             prevent_update: schema::branch_protection_default_prevent_update(),
             prevent_deletion: schema::branch_protection_default_prevent_deletion(),
@@ -518,9 +914,21 @@ pub struct GithubMock {
     users: HashMap<UserId, String>,
     // org name -> organization data
     orgs: HashMap<String, GithubOrg>,
+    // Permissions of the token, unknown if not set
+    token_permissions: Option<api::TokenPermissions>,
+    // Is the token a GitHub App token instead of a PAT?
+    app_token: bool,
 }
 
 impl GithubMock {
+    pub fn use_app_token(&mut self) {
+        self.app_token = true;
+    }
+
+    pub fn set_token_permissions(&mut self, permissions: api::TokenPermissions) {
+        self.token_permissions = Some(permissions);
+    }
+
     pub fn add_member(&mut self, org: &str, username: &str) {
         let user_id = self.users.len() as UserId;
         self.users.insert(user_id, username.to_string());
@@ -531,6 +939,86 @@ impl GithubMock {
             .insert((user_id, username.to_string()));
     }
 
+    pub fn add_repo_invitation(
+        &mut self,
+        org: &str,
+        repo: &str,
+        user: &str,
+        permission: api::RepoPermission,
+    ) {
+        self.get_org_mut(org)
+            .repo_invitations
+            .entry(repo.to_string())
+            .or_default()
+            .push(RepoUser {
+                name: user.to_string(),
+                permission,
+            });
+    }
+
+    pub fn add_custom_repo_role(&mut self, org: &str, role: &str) {
+        self.get_org_mut(org)
+            .custom_repo_roles
+            .insert(role.to_string());
+    }
+
+    /// Makes an existing org member an owner of the org
+    pub fn add_owner(&mut self, org: &str, username: &str) {
+        let org = self.get_org_mut(org);
+        let user_id = org
+            .members
+            .iter()
+            .find(|(_, name)| name == username)
+            .map(|(id, _)| *id)
+            .expect("User is not a member of the org");
+        org.owners.insert(user_id);
+    }
+
+    pub fn add_environment_reviewer(
+        &mut self,
+        org: &str,
+        repo: &str,
+        environment: &str,
+        reviewer: api::EnvironmentReviewer,
+    ) {
+        self.get_org_mut(org)
+            .repo_environments
+            .get_mut(repo)
+            .and_then(|environments| environments.get_mut(environment))
+            .expect("environment not found")
+            .reviewers
+            .push(reviewer);
+    }
+
+    pub fn add_deploy_key(
+        &mut self,
+        org: &str,
+        repo: &str,
+        title: &str,
+        key: &str,
+        read_only: bool,
+    ) {
+        let keys = self
+            .get_org_mut(org)
+            .deploy_keys
+            .entry(repo.to_string())
+            .or_default();
+        keys.push(api::RepoDeployKey {
+            id: keys.len() as u64,
+            title: title.to_string(),
+            key: key.to_string(),
+            read_only,
+        });
+    }
+
+    pub fn add_branch(&mut self, org: &str, repo: &str, branch: &str) {
+        self.get_org_mut(org)
+            .branches
+            .entry(repo.to_string())
+            .or_default()
+            .push(branch.to_string());
+    }
+
     pub fn add_invitation(&mut self, org: &str, repo: &str, user: &str) {
         self.get_org_mut(org)
             .team_invitations
@@ -555,7 +1043,7 @@ impl GithubMock {
 #[async_trait]
 impl GithubRead for GithubMock {
     fn uses_pat(&self) -> bool {
-        true
+        !self.app_token
     }
 
     async fn usernames(&self, ids: &[UserId]) -> anyhow::Result<HashMap<UserId, String>> {
@@ -571,6 +1059,21 @@ impl GithubRead for GithubMock {
         Ok(self.get_org(org).owners.iter().copied().collect())
     }
 
+    async fn org_settings(&self, org: &str) -> anyhow::Result<api::OrgSettings> {
+        Ok(self.get_org(org).settings.clone())
+    }
+
+    async fn custom_repo_roles(&self, org: &str) -> anyhow::Result<HashSet<String>> {
+        Ok(self.get_org(org).custom_repo_roles.clone())
+    }
+
+    async fn token_permissions(&self, _org: &str) -> anyhow::Result<api::TokenPermissions> {
+        Ok(self
+            .token_permissions
+            .clone()
+            .unwrap_or(api::TokenPermissions::Unknown))
+    }
+
     async fn org_members(&self, org: &str) -> anyhow::Result<HashMap<u64, String>> {
         Ok(self.get_org(org).members.iter().cloned().collect())
     }
@@ -593,6 +1096,15 @@ impl GithubRead for GithubMock {
             .cloned())
     }
 
+    async fn team_by_id(&self, org: &str, id: u64) -> anyhow::Result<Option<Team>> {
+        Ok(self
+            .get_org(org)
+            .teams
+            .iter()
+            .find(|t| t.id == Some(id))
+            .cloned())
+    }
+
     async fn team_memberships(
         &self,
         team: &Team,
@@ -628,6 +1140,26 @@ impl GithubRead for GithubMock {
             .and_then(|org| org.repos.get(repo).cloned()))
     }
 
+    async fn prefetch_repos(&self, _org: &str, _repos: &[String]) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    async fn branch_exists(&self, org: &str, repo: &str, branch: &str) -> anyhow::Result<bool> {
+        Ok(self
+            .get_org(org)
+            .branches
+            .get(repo)
+            .is_some_and(|branches| branches.iter().any(|b| b == branch)))
+    }
+
+    async fn app(
+        &self,
+        _org: &str,
+        slug: &str,
+    ) -> anyhow::Result<Option<api::AppPushAllowanceActor>> {
+        Ok(Some(mock_app(slug)))
+    }
+
     async fn repo_teams(&self, org: &str, repo: &str) -> anyhow::Result<Vec<RepoTeam>> {
         Ok(self
             .get_org(org)
@@ -638,6 +1170,15 @@ impl GithubRead for GithubMock {
             .unwrap_or_default())
     }
 
+    async fn repo_invitations(&self, org: &str, repo: &str) -> anyhow::Result<Vec<RepoUser>> {
+        Ok(self
+            .get_org(org)
+            .repo_invitations
+            .get(repo)
+            .cloned()
+            .unwrap_or_default())
+    }
+
     async fn repo_collaborators(&self, org: &str, repo: &str) -> anyhow::Result<Vec<RepoUser>> {
         Ok(self
             .get_org(org)
@@ -658,12 +1199,86 @@ impl GithubRead for GithubMock {
         };
         let mut result = HashMap::default();
         for (id, protection) in protections {
+            let has_app_actors = protection
+                .push_allowances
+                .iter()
+                .any(|actor| matches!(actor, api::PushAllowanceActor::App(_)));
+            if self.app_token && has_app_actors {
+                return Err(api::GraphForbiddenError(
+                    "Resource not accessible by integration".to_string(),
+                )
+                .into());
+            }
             result.insert(protection.pattern.clone(), (id.clone(), protection.clone()));
         }
 
         Ok(result)
     }
 
+    async fn repo_labels(&self, org: &str, repo: &str) -> anyhow::Result<Vec<api::RepoLabel>> {
+        Ok(self
+            .get_org(org)
+            .labels
+            .get(repo)
+            .cloned()
+            .unwrap_or_default())
+    }
+
+    async fn repo_webhooks(&self, org: &str, repo: &str) -> anyhow::Result<Vec<api::RepoWebhook>> {
+        Ok(self
+            .get_org(org)
+            .webhooks
+            .get(repo)
+            .cloned()
+            .unwrap_or_default())
+    }
+
+    async fn repo_autolinks(
+        &self,
+        org: &str,
+        repo: &str,
+    ) -> anyhow::Result<Vec<api::RepoAutolink>> {
+        Ok(self
+            .get_org(org)
+            .autolinks
+            .get(repo)
+            .cloned()
+            .unwrap_or_default())
+    }
+
+    async fn repo_deploy_keys(
+        &self,
+        org: &str,
+        repo: &str,
+    ) -> anyhow::Result<Vec<api::RepoDeployKey>> {
+        Ok(self
+            .get_org(org)
+            .deploy_keys
+            .get(repo)
+            .cloned()
+            .unwrap_or_default())
+    }
+
+    async fn repo_actions_permissions(
+        &self,
+        org: &str,
+        repo: &str,
+    ) -> anyhow::Result<Option<v1::ActionsPermissions>> {
+        // Repositories without explicitly configured permissions use the GitHub defaults
+        Ok(Some(
+            self.get_org(org)
+                .actions_permissions
+                .get(repo)
+                .cloned()
+                .unwrap_or(v1::ActionsPermissions {
+                    default_workflow_permissions: v1::WorkflowPermissions::Read,
+                    can_approve_pull_requests: false,
+                    allowed_actions: v1::AllowedActions::All,
+                    selected_actions: None,
+                }),
+        ))
+    }
+
     async fn repo_rulesets(&self, org: &str, repo: &str) -> anyhow::Result<Vec<Ruleset>> {
         Ok(self
             .get_org(org)
@@ -677,7 +1292,7 @@ impl GithubRead for GithubMock {
         &self,
         org: &str,
         repo: &str,
-    ) -> anyhow::Result<HashMap<String, Environment>> {
+    ) -> anyhow::Result<HashMap<String, api::Environment>> {
         Ok(self
             .get_org(org)
             .repo_environments
@@ -698,8 +1313,18 @@ impl GithubRead for GithubMock {
     }
 }
 
+/// All GitHub Apps exist in the mock, their name is the same as their slug.
+fn mock_app(slug: &str) -> api::AppPushAllowanceActor {
+    api::AppPushAllowanceActor {
+        name: slug.to_string(),
+        id: format!("app-{slug}"),
+    }
+}
+
 #[derive(Default)]
 struct GithubOrg {
+    settings: api::OrgSettings,
+    custom_repo_roles: HashSet<String>,
     members: BTreeSet<(UserId, String)>,
     owners: BTreeSet<UserId>,
     teams: Vec<Team>,
@@ -709,14 +1334,39 @@ struct GithubOrg {
     team_memberships: HashMap<String, HashMap<UserId, TeamMember>>,
     // Repo name -> repo data
     repos: HashMap<String, Repo>,
+    // Repo name -> branch names (apart from the default branch)
+    branches: HashMap<String, Vec<String>>,
     // Repo name -> (teams, members)
     repo_members: HashMap<String, RepoMembers>,
+    // Repo name -> users invited as collaborators
+    repo_invitations: HashMap<String, Vec<RepoUser>>,
     // Repo name -> Vec<(protection ID, branch protection)>
     branch_protections: HashMap<String, Vec<(String, BranchProtection)>>,
     // Repo name -> Vec<ruleset>
     rulesets: HashMap<String, Vec<Ruleset>>,
     // Repo name -> HashMap<env name, environment>
-    repo_environments: HashMap<String, HashMap<String, Environment>>,
+    repo_environments: HashMap<String, HashMap<String, api::Environment>>,
+    // Repo name -> labels
+    labels: HashMap<String, Vec<api::RepoLabel>>,
+    // Repo name -> webhooks
+    webhooks: HashMap<String, Vec<api::RepoWebhook>>,
+    // Repo name -> autolinks
+    autolinks: HashMap<String, Vec<api::RepoAutolink>>,
+    // Repo name -> deploy keys
+    deploy_keys: HashMap<String, Vec<api::RepoDeployKey>>,
+    // Repo name -> GitHub Actions permissions
+    actions_permissions: HashMap<String, v1::ActionsPermissions>,
+}
+
+/// The settings GitHub uses for newly created organizations.
+impl Default for api::OrgSettings {
+    fn default() -> Self {
+        Self {
+            default_repository_permission: v1::DefaultRepoPermission::Read,
+            members_can_create_repositories: true,
+            members_can_fork_private_repositories: false,
+        }
+    }
 }
 
 #[derive(Clone)]
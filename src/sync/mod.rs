@@ -1,69 +1,297 @@
 mod crates_io;
+mod email;
 mod github;
-mod mailgun;
 pub mod team_api;
 pub mod utils;
 mod zulip;
 
 use std::collections::BTreeSet;
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
 
 use anyhow::Context;
 use crates_io::SyncCratesIo;
-use github::{GitHubApiRead, GitHubWrite, HttpClient, create_diff};
+use email::{SuppressionReport, SyncEmail};
+use github::{
+    Access, ApplyOptions, ApplyReport, CachedGithubRead, Diff, GitHubApiRead, GitHubWrite,
+    GithubRead, HttpClient, Revision, create_diff, diff_revisions,
+};
 use log::{info, warn};
 use secrecy::SecretString;
 use team_api::TeamApi;
-use zulip::SyncZulip;
+use zulip::{SyncZulip, ZulipApi};
+
+pub use email::EmailBackend;
 
 #[derive(Debug, Clone, Default)]
 pub struct Config {
     pub special_org_members: BTreeSet<String>,
     pub independent_github_orgs: BTreeSet<String>,
-    pub enable_rulesets_repos: BTreeSet<String>,
+    /// Organizations where GitHub teams not managed by the team repository are deleted
+    pub unmanaged_team_deletion_orgs: BTreeSet<String>,
+    /// GitHub teams that are never deleted, even if they are not managed
+    pub preserved_github_teams: BTreeSet<String>,
+    /// Allow demoting organization owners that are not members of an org owner team
+    pub allow_owner_demotions: bool,
+    /// Directory where GitHub API responses are cached between runs
+    pub github_cache_dir: Option<PathBuf>,
+    /// Maximum number of GitHub teams or repositories diffed or applied concurrently
+    pub github_concurrency: Option<usize>,
+    /// ID of the GitHub Actions app, if it isn't the one of github.com
+    pub github_actions_integration_id: Option<i64>,
+    /// Maximum number of team members, teams, repository permissions or branch
+    /// protections that the GitHub sync may delete. It also limits the members removed from
+    /// the Zulip streams of sensitive teams.
+    pub github_max_deletions: Option<usize>,
+    /// Apply the diffs even if they delete more than `github_max_deletions`
+    pub force: bool,
+    /// Ask for a confirmation before applying each GitHub change
+    pub interactive: bool,
+    /// Keep applying the remaining GitHub changes when one of them fails
+    pub continue_on_error: bool,
+    /// Append a record of every applied GitHub change to this file
+    pub github_audit_log: Option<PathBuf>,
+    /// Zulip stream notified when applied GitHub changes delete teams, organization members
+    /// or branch protections
+    pub github_notification_stream: Option<String>,
+    /// How the GitHub diff is printed
+    pub output_format: OutputFormat,
+    /// Color the text GitHub diff by the severity of its changes
+    pub colored_output: bool,
+    /// Provider hosting the mailing lists
+    pub email_backend: EmailBackend,
+    /// Subset of GitHub that is diffed and applied
+    pub github_filter: GitHubFilter,
+    /// Diff against a GitHub state recorded by `export-state` instead of the live GitHub API
+    pub github_state_source: Option<PathBuf>,
+    /// Record the state of GitHub read during the diff to this file
+    pub github_state_export: Option<PathBuf>,
+    /// Save the GitHub diff to this plan file
+    pub github_plan_out: Option<PathBuf>,
+    /// Only apply the GitHub diff if it is the one saved to this plan file
+    pub github_plan: Option<PathBuf>,
+}
+
+/// Default maximum number of things of each kind that a single run may delete
+const DEFAULT_MAX_DELETIONS: usize = 25;
+
+impl Config {
+    /// Maximum number of deletions of each kind allowed in a diff, or `None` if the limit is
+    /// disabled with `--force`
+    pub(crate) fn deletion_limit(&self) -> Option<usize> {
+        (!self.force).then(|| self.github_max_deletions.unwrap_or(DEFAULT_MAX_DELETIONS))
+    }
+}
+
+/// Restricts the GitHub sync to some organizations, repositories or teams.
+///
+/// Organization-wide changes (settings, members, owners and deletion of unmanaged
+/// teams) depend on every team of the organization, so they are only synchronized
+/// when no individual repository or team is selected.
+#[derive(Debug, Clone, Default)]
+pub struct GitHubFilter {
+    pub orgs: BTreeSet<String>,
+    /// Repository names, optionally prefixed by their organization (`org/repo`)
+    pub repos: BTreeSet<String>,
+    /// Names of GitHub teams
+    pub teams: BTreeSet<String>,
+}
+
+impl GitHubFilter {
+    fn selects_items(&self) -> bool {
+        !self.repos.is_empty() || !self.teams.is_empty()
+    }
+
+    fn includes_org(&self, org: &str) -> bool {
+        self.orgs.is_empty() || self.orgs.contains(org)
+    }
+
+    /// Whether the organization-wide state of `org` is synchronized
+    pub(crate) fn includes_org_wide(&self, org: &str) -> bool {
+        !self.selects_items() && self.includes_org(org)
+    }
+
+    pub(crate) fn includes_team(&self, org: &str, name: &str) -> bool {
+        self.includes_org(org) && (!self.selects_items() || self.teams.contains(name))
+    }
+
+    pub(crate) fn includes_repo(&self, org: &str, name: &str) -> bool {
+        self.includes_org(org)
+            && (!self.selects_items()
+                || self.repos.contains(name)
+                || self.repos.contains(&format!("{org}/{name}")))
+    }
+}
+
+/// Format of the printed diff
+#[derive(clap::ValueEnum, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// Human-readable text, logged together with the other messages
+    #[default]
+    Text,
+    /// JSON printed to stdout, for consumption by other tools. The GitHub and Zulip
+    /// diffs are printed as separate documents.
+    Json,
+    /// Markdown printed to stdout, for posting as a pull request comment
+    Markdown,
+}
+
+/// Whether the text diff is colored
+#[derive(clap::ValueEnum, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ColorChoice {
+    /// Color the diff if the logs go to a terminal and `NO_COLOR` is not set
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorChoice {
+    pub fn enabled(self) -> bool {
+        match self {
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+            ColorChoice::Auto => {
+                // https://no-color.org: any non-empty value disables colors
+                std::env::var_os("NO_COLOR").is_none_or(|value| value.is_empty())
+                    && std::io::stderr().is_terminal()
+            }
+        }
+    }
 }
 
+/// Synchronize the services, returning whether any of them differed from the team data.
 pub async fn run_sync_team(
     team_api: TeamApi,
     services: &[String],
     dry_run: bool,
     only_print_plan: bool,
     config: Config,
-) -> anyhow::Result<()> {
+) -> anyhow::Result<bool> {
     if dry_run {
         warn!("sync-team is running in dry mode, no changes will be applied.");
     }
 
+    let mut has_changes = false;
     for service in services {
         info!("synchronizing {service}");
         match service.as_str() {
             "github" => {
-                let client = HttpClient::new()?;
-                let gh_read = Box::new(GitHubApiRead::from_client(client.clone())?);
+                let client = match &config.github_state_source {
+                    Some(path) => HttpClient::from_state(path)?,
+                    None => HttpClient::new()?,
+                };
+                let (read_client, recorded_state) = match &config.github_state_export {
+                    Some(_) => {
+                        let (read_client, state) = client.clone().recording();
+                        (read_client, Some(state))
+                    }
+                    None => (client.clone(), None),
+                };
+                let gh_read: Box<dyn GithubRead> = match &config.github_cache_dir {
+                    // Cached GraphQL responses can be slightly outdated, which is only
+                    // acceptable when nothing is written to GitHub
+                    Some(_) if !(dry_run || only_print_plan) => {
+                        anyhow::bail!("the GitHub cache can only be used by dry runs");
+                    }
+                    Some(dir) => Box::new(CachedGithubRead::new(read_client, dir)?),
+                    None => Box::new(GitHubApiRead::from_client(read_client)?),
+                };
                 let teams = team_api.get_teams().await?;
                 let repos = team_api.get_repos().await?;
-                let diff = create_diff(gh_read, teams, repos, config.clone()).await?;
-                if !diff.is_empty() {
-                    info!("{diff}");
+                let orgs = team_api.get_orgs().await?;
+                // Dry runs never write to GitHub, so they can use read-only tokens
+                let access = if dry_run || only_print_plan {
+                    Access::Read
+                } else {
+                    Access::Write
+                };
+                let diff = create_diff(gh_read, teams, repos, orgs, config.clone(), access).await?;
+                has_changes |= !diff.is_empty();
+                print_github_diff(&diff, config.output_format, config.colored_output)?;
+                if let (Some(path), Some(state)) = (&config.github_state_export, recorded_state) {
+                    state.save(path)?;
+                    info!("GitHub state written to {}", path.display());
+                }
+                if let Some(path) = &config.github_plan_out {
+                    diff.save_plan(path)?;
+                    info!("GitHub plan written to {}", path.display());
+                }
+                if let Some(path) = &config.github_plan {
+                    diff.check_plan(path)?;
                 }
                 if !only_print_plan {
+                    let notification = match &config.github_notification_stream {
+                        Some(stream) if !dry_run => {
+                            let changes = diff.destructive_changes();
+                            if changes.is_empty() {
+                                None
+                            } else {
+                                // Credentials are checked before GitHub is changed
+                                let username = get_env("ZULIP_USERNAME")?;
+                                let token = SecretString::from(get_env("ZULIP_API_TOKEN")?);
+                                let zulip = ZulipApi::new(username, token, false);
+                                Some((zulip, stream))
+                            }
+                        }
+                        _ => None,
+                    };
                     let gh_write = GitHubWrite::new(client, dry_run)?;
-                    diff.apply(&gh_write).await?;
+                    let mut report = ApplyReport::default();
+                    let result = diff
+                        .apply(&gh_write, ApplyOptions::from_config(&config)?, &mut report)
+                        .await;
+                    // Only the removals that were actually applied are reported
+                    if let Some((zulip, stream)) = notification
+                        && !report.removed.is_empty()
+                    {
+                        // The changes were already applied, so a failed notification is not fatal
+                        if let Err(err) =
+                            notify_destructive_changes(&zulip, stream, &report, &result).await
+                        {
+                            warn!(
+                                "failed to notify Zulip of the destructive GitHub changes: {err:#}"
+                            );
+                        }
+                    }
+                    result?;
+                    if !report.is_success() {
+                        anyhow::bail!("some GitHub changes could not be applied: {report}");
+                    }
                 }
             }
             "mailgun" => {
-                let token = SecretString::from(get_env("MAILGUN_API_TOKEN")?);
                 let encryption_key = get_env("EMAIL_ENCRYPTION_KEY")?;
-                mailgun::run(token, &encryption_key, &team_api, dry_run).await?;
+                let sync =
+                    SyncEmail::new(config.email_backend, &encryption_key, &team_api, dry_run)
+                        .await?;
+                let diff = sync.diff_all().await?;
+                has_changes |= !diff.is_empty();
+                if !diff.is_empty() {
+                    info!("{diff}");
+                }
+                if !only_print_plan {
+                    diff.apply(&sync).await?;
+                }
             }
             "zulip" => {
                 let username = get_env("ZULIP_USERNAME")?;
                 let token = SecretString::from(get_env("ZULIP_API_TOKEN")?);
                 let sync = SyncZulip::new(username, token, &team_api, dry_run).await?;
                 let diff = sync.diff_all().await?;
-                if !diff.is_empty() {
-                    info!("{diff}");
+                has_changes |= !diff.is_empty();
+                match config.output_format {
+                    OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&diff)?),
+                    OutputFormat::Text | OutputFormat::Markdown => {
+                        if !diff.is_empty() {
+                            info!("{diff}");
+                        }
+                    }
                 }
                 if !only_print_plan {
+                    if let Some(limit) = config.deletion_limit() {
+                        diff.check_deletion_limit(limit)?;
+                    }
                     diff.apply(&sync).await?;
                 }
             }
@@ -72,6 +300,7 @@ pub async fn run_sync_team(
                 let username = get_env("CRATES_IO_USERNAME")?;
                 let sync = SyncCratesIo::new(token, username, &team_api, dry_run).await?;
                 let diff = sync.diff_all().await?;
+                has_changes |= !diff.is_empty();
                 if !diff.is_empty() {
                     info!("{diff}");
                 }
@@ -83,9 +312,107 @@ pub async fn run_sync_team(
         }
     }
 
+    Ok(has_changes)
+}
+
+fn print_github_diff(
+    diff: &Diff,
+    output_format: OutputFormat,
+    colored_output: bool,
+) -> anyhow::Result<()> {
+    match output_format {
+        OutputFormat::Text if diff.is_empty() => {}
+        OutputFormat::Text if colored_output => info!("{}", diff.to_colored_text()),
+        OutputFormat::Text => info!("{diff}"),
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(diff)?),
+        OutputFormat::Markdown => print!("{}", diff.to_markdown()),
+    }
     Ok(())
 }
 
+/// Print the changes of GitHub team memberships and repository permissions between two
+/// revisions of the team data, without contacting GitHub. Returns whether there are changes.
+pub async fn diff_github_revisions(
+    old: &TeamApi,
+    new: &TeamApi,
+    output_format: OutputFormat,
+    colored_output: bool,
+) -> anyhow::Result<bool> {
+    let old = load_revision(old)
+        .await
+        .context("failed to load the old revision")?;
+    let new = load_revision(new)
+        .await
+        .context("failed to load the new revision")?;
+    let diff = diff_revisions(&old, &new)?;
+    if diff.is_empty() {
+        info!("no GitHub changes between the revisions");
+    }
+    print_github_diff(&diff, output_format, colored_output)?;
+    Ok(!diff.is_empty())
+}
+
+async fn load_revision(team_api: &TeamApi) -> anyhow::Result<Revision> {
+    Ok(Revision {
+        teams: team_api.get_teams().await?,
+        repos: team_api.get_repos().await?,
+        people: team_api.get_people().await?,
+    })
+}
+
+const NOTIFICATION_TOPIC: &str = "sync-team destructive changes";
+
+/// Post the destructive changes applied to GitHub to a Zulip stream.
+async fn notify_destructive_changes(
+    zulip: &ZulipApi,
+    stream: &str,
+    report: &ApplyReport,
+    result: &anyhow::Result<()>,
+) -> anyhow::Result<()> {
+    let outcome = match result {
+        Ok(()) if report.is_success() => "All the changes were applied.".to_string(),
+        Ok(()) => format!(
+            "Some changes failed: {} changes applied, {} failed.",
+            report.applied,
+            report.failures.len()
+        ),
+        Err(err) => format!("The sync failed: {err:#}"),
+    };
+    let content = format!(
+        "sync-team applied GitHub changes that removed:\n{}\n{outcome}",
+        report.removed
+    );
+    zulip
+        .send_stream_message(stream, NOTIFICATION_TOPIC, &content)
+        .await
+}
+
+/// Undo the GitHub changes recorded in an audit log written by `apply --audit-log`, by the
+/// given run or by the last run of the log.
+pub async fn rollback_github(
+    audit_log: &Path,
+    run: Option<&str>,
+    dry_run: bool,
+) -> anyhow::Result<()> {
+    if dry_run {
+        warn!("sync-team is running in dry mode, no changes will be applied.");
+    }
+    let gh_write = GitHubWrite::new(HttpClient::new()?, dry_run)?;
+    github::rollback(audit_log, run, &gh_write).await
+}
+
+/// Print the people whose email address is suppressed by the email provider, returning whether
+/// there are any.
+pub async fn report_email_suppressions(
+    team_api: &TeamApi,
+    backend: EmailBackend,
+) -> anyhow::Result<bool> {
+    let encryption_key = get_env("EMAIL_ENCRYPTION_KEY")?;
+    let report = SuppressionReport::new(backend, &encryption_key, team_api).await?;
+    print!("{report}");
+    Ok(!report.is_empty())
+}
+
 fn get_env(key: &str) -> anyhow::Result<String> {
     std::env::var(key).with_context(|| format!("failed to get the {key} environment variable"))
 }
@@ -34,6 +34,21 @@ impl TeamApi {
             .collect())
     }
 
+    pub(crate) async fn get_orgs(&self) -> anyhow::Result<Vec<rust_team_data::v1::Org>> {
+        debug!("loading orgs list from the Team API");
+        Ok(self
+            .req::<rust_team_data::v1::Orgs>("orgs.json")
+            .await?
+            .orgs
+            .into_values()
+            .collect())
+    }
+
+    pub(crate) async fn get_people(&self) -> anyhow::Result<rust_team_data::v1::People> {
+        debug!("loading people list from the Team API");
+        self.req::<rust_team_data::v1::People>("people.json").await
+    }
+
     pub(crate) async fn get_lists(&self) -> anyhow::Result<rust_team_data::v1::Lists> {
         debug!("loading email lists list from the Team API");
         self.req::<rust_team_data::v1::Lists>("lists.json").await
@@ -53,6 +68,12 @@ impl TeamApi {
             .await
     }
 
+    pub(crate) async fn get_zulip_roles(&self) -> anyhow::Result<rust_team_data::v1::ZulipRoles> {
+        debug!("loading Zulip roles from the Team API");
+        self.req::<rust_team_data::v1::ZulipRoles>("zulip-roles.json")
+            .await
+    }
+
     async fn req<T: serde::de::DeserializeOwned>(&self, url: &str) -> anyhow::Result<T> {
         match self {
             TeamApi::Production => {
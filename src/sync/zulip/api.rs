@@ -1,7 +1,9 @@
 use std::collections::HashMap;
+use std::time::Duration;
 
 use anyhow::Context as _;
-use reqwest::Client;
+use reqwest::header::RETRY_AFTER;
+use reqwest::{Client, StatusCode};
 use secrecy::{ExposeSecret, SecretString};
 use serde::Deserialize;
 
@@ -27,20 +29,22 @@ impl ZulipApi {
         }
     }
 
-    /// Creates a Zulip user group with the supplied name, description, and members
+    /// Creates a Zulip user group with the supplied name, description, and members, returning
+    /// its id.
     ///
-    /// This is a noop if the user group already exists.
+    /// This is a noop if the user group already exists, in which case `None` is returned, like
+    /// in dry runs.
     pub(crate) async fn create_user_group(
         &self,
         user_group_name: &str,
         description: &str,
         member_ids: &[u64],
-    ) -> anyhow::Result<()> {
+    ) -> anyhow::Result<Option<u64>> {
         log::info!(
             "creating Zulip user group '{user_group_name}' with description '{description}' and member ids: {member_ids:?}"
         );
         if self.dry_run {
-            return Ok(());
+            return Ok(None);
         }
 
         let member_ids = serialize_as_array(member_ids);
@@ -64,14 +68,74 @@ impl ZulipApi {
             let error = body.get("msg").ok_or_else(err)?.as_str().ok_or_else(err)?;
             if error.contains("already exists") {
                 log::debug!("Zulip user group '{user_group_name}' already existed");
-                return Ok(());
+                return Ok(None);
             } else {
                 return Err(err());
             }
         }
 
-        r.error_for_status()?;
+        #[derive(Deserialize)]
+        struct Created {
+            /// Only returned by Zulip 10 and later
+            group_id: Option<u64>,
+        }
+        let created = r.error_for_status()?.json::<Created>().await?;
+        let id = match created.group_id {
+            Some(id) => id,
+            None => self
+                .get_user_groups()
+                .await?
+                .into_iter()
+                .find(|group| group.name == user_group_name)
+                .map(|group| group.id)
+                .with_context(|| format!("created user group '{user_group_name}' not found"))?,
+        };
+        Ok(Some(id))
+    }
+
+    /// Deactivate a user group, which is how Zulip deletes them
+    pub(crate) async fn deactivate_user_group(&self, user_group_id: u64) -> anyhow::Result<()> {
+        log::info!("deactivating user group {user_group_id}");
+        if self.dry_run {
+            return Ok(());
+        }
+
+        let path = format!("/user_groups/{user_group_id}/deactivate");
+        self.req(reqwest::Method::POST, &path, None)
+            .await?
+            .error_for_status()?;
+        Ok(())
+    }
+
+    /// Change who may mention a user group
+    pub(crate) async fn update_user_group_mention_policy(
+        &self,
+        user_group_id: u64,
+        old: Option<&GroupSettingValue>,
+        new: &GroupSettingValue,
+    ) -> anyhow::Result<()> {
+        log::info!("changing who may mention user group {user_group_id} to {new:?}");
+        if self.dry_run {
+            return Ok(());
+        }
+
+        // Passing the old value makes Zulip reject the change if the setting changed since it
+        // was read
+        let setting = match old {
+            Some(old) => serde_json::json!({ "new": new, "old": old }),
+            None => serde_json::json!({ "new": new }),
+        }
+        .to_string();
+        let mut form = HashMap::new();
+        form.insert("can_mention_group", setting.as_str());
 
+        let path = format!("/user_groups/{user_group_id}");
+        self.req(reqwest::Method::PATCH, &path, Some(form))
+            .await?
+            .error_for_status()
+            .with_context(|| {
+                format!("failed to change who may mention user group {user_group_id}")
+            })?;
         Ok(())
     }
 
@@ -135,6 +199,32 @@ impl ZulipApi {
         Ok(response)
     }
 
+    /// Change the organization role of a user
+    pub(crate) async fn update_user_role(
+        &self,
+        user_id: u64,
+        role: ZulipUserRole,
+    ) -> anyhow::Result<()> {
+        log::info!("changing the role of Zulip user {user_id} to {role}");
+        if self.dry_run {
+            return Ok(());
+        }
+
+        let role_code = role.code().to_string();
+        let mut form = HashMap::new();
+        form.insert("role", role_code.as_str());
+
+        self.req(
+            reqwest::Method::PATCH,
+            &format!("/users/{user_id}"),
+            Some(form),
+        )
+        .await?
+        .error_for_status()
+        .with_context(|| format!("failed to change the role of Zulip user {user_id}"))?;
+        Ok(())
+    }
+
     /// Is a Zulip stream private?
     pub(crate) async fn is_stream_private(&self, stream_id: u64) -> anyhow::Result<bool> {
         let stream = self.get_stream(stream_id).await.with_context(|| {
@@ -265,25 +355,102 @@ impl ZulipApi {
         Ok(())
     }
 
+    /// Post a message to a topic of a stream
+    pub(crate) async fn send_stream_message(
+        &self,
+        stream: &str,
+        topic: &str,
+        content: &str,
+    ) -> anyhow::Result<()> {
+        log::info!("sending a message to Zulip stream '{stream}', topic '{topic}'");
+        if self.dry_run {
+            return Ok(());
+        }
+
+        let mut form = HashMap::new();
+        form.insert("type", "stream");
+        form.insert("to", stream);
+        form.insert("topic", topic);
+        form.insert("content", content);
+
+        self.req(reqwest::Method::POST, "/messages", Some(form))
+            .await?
+            .error_for_status()
+            .with_context(|| format!("failed to send a message to Zulip stream '{stream}'"))?;
+        Ok(())
+    }
+
     /// Perform a request against the Zulip API
+    ///
+    /// Requests rejected by a rate limit are retried after the delay asked by Zulip. Idempotent
+    /// requests are also retried with a backoff when they fail because of a server or network
+    /// error.
     async fn req(
         &self,
         method: reqwest::Method,
         path: &str,
         form: Option<HashMap<&str, &str>>,
     ) -> anyhow::Result<reqwest::Response> {
-        let mut req = self
-            .client
-            .request(method, format!("{ZULIP_BASE_URL}{path}"))
-            .basic_auth(&self.username, Some(&self.token.expose_secret()));
-        if let Some(form) = form {
-            req = req.form(&form);
-        }
+        let mut attempt = 0;
+        loop {
+            let mut req = self
+                .client
+                .request(method.clone(), format!("{ZULIP_BASE_URL}{path}"))
+                .basic_auth(&self.username, Some(&self.token.expose_secret()));
+            if let Some(form) = &form {
+                req = req.form(form);
+            }
 
-        Ok(req.send().await?)
+            let result = req.send().await;
+            let wait = match &result {
+                Ok(resp) if resp.status() == StatusCode::TOO_MANY_REQUESTS => {
+                    Some(rate_limit_wait(resp))
+                }
+                Ok(resp) if resp.status().is_server_error() && method.is_idempotent() => {
+                    Some(backoff(attempt))
+                }
+                Err(_) if method.is_idempotent() => Some(backoff(attempt)),
+                _ => None,
+            };
+            let Some(wait) = wait.filter(|_| attempt < MAX_RETRIES) else {
+                return Ok(result?);
+            };
+            match &result {
+                Ok(resp) => log::warn!(
+                    "{method} {path} failed with {}, retrying in {:.1} seconds",
+                    resp.status(),
+                    wait.as_secs_f64()
+                ),
+                Err(err) => log::warn!(
+                    "{method} {path} failed ({err}), retrying in {:.1} seconds",
+                    wait.as_secs_f64()
+                ),
+            }
+            tokio::time::sleep(wait).await;
+            attempt += 1;
+        }
     }
 }
 
+/// How many times a request is retried
+const MAX_RETRIES: u32 = 5;
+
+/// Returns how long Zulip asks to wait before retrying a rate limited request.
+/// See https://zulip.com/api/http-headers#rate-limiting-response-headers
+fn rate_limit_wait(resp: &reqwest::Response) -> Duration {
+    resp.headers()
+        .get(RETRY_AFTER)
+        .and_then(|value| value.to_str().ok()?.parse::<f64>().ok())
+        .and_then(|secs| Duration::try_from_secs_f64(secs).ok())
+        // Zulip rate limits are computed over a few seconds
+        .unwrap_or(Duration::from_secs(5))
+}
+
+/// Exponential backoff for failed requests: 1s, 2s, 4s...
+fn backoff(attempt: u32) -> Duration {
+    Duration::from_secs(1 << attempt)
+}
+
 /// Serialize a slice of numbers as a JSON array
 fn serialize_as_array(items: &[u64]) -> String {
     serde_json::to_string(&items).expect("cannot serialize JSON array")
@@ -302,6 +469,70 @@ pub(crate) struct ZulipUser {
     #[serde(rename = "delivery_email")]
     pub(crate) email: Option<String>,
     pub(crate) user_id: u64,
+    pub(crate) full_name: String,
+    pub(crate) role: ZulipUserRole,
+    pub(crate) is_bot: bool,
+}
+
+/// Organization role of a Zulip user
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, serde::Serialize)]
+#[serde(try_from = "u16", rename_all = "snake_case")]
+pub(crate) enum ZulipUserRole {
+    Owner,
+    Administrator,
+    Moderator,
+    Member,
+    Guest,
+}
+
+impl ZulipUserRole {
+    /// The value used by the Zulip API
+    fn code(self) -> u16 {
+        match self {
+            ZulipUserRole::Owner => 100,
+            ZulipUserRole::Administrator => 200,
+            ZulipUserRole::Moderator => 300,
+            ZulipUserRole::Member => 400,
+            ZulipUserRole::Guest => 600,
+        }
+    }
+}
+
+impl TryFrom<u16> for ZulipUserRole {
+    type Error = String;
+
+    fn try_from(code: u16) -> Result<Self, Self::Error> {
+        Ok(match code {
+            100 => ZulipUserRole::Owner,
+            200 => ZulipUserRole::Administrator,
+            300 => ZulipUserRole::Moderator,
+            400 => ZulipUserRole::Member,
+            600 => ZulipUserRole::Guest,
+            _ => return Err(format!("unknown Zulip role {code}")),
+        })
+    }
+}
+
+impl From<rust_team_data::v1::ZulipRole> for ZulipUserRole {
+    fn from(role: rust_team_data::v1::ZulipRole) -> Self {
+        match role {
+            rust_team_data::v1::ZulipRole::Moderator => ZulipUserRole::Moderator,
+            rust_team_data::v1::ZulipRole::Administrator => ZulipUserRole::Administrator,
+        }
+    }
+}
+
+impl std::fmt::Display for ZulipUserRole {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            ZulipUserRole::Owner => "owner",
+            ZulipUserRole::Administrator => "administrator",
+            ZulipUserRole::Moderator => "moderator",
+            ZulipUserRole::Member => "member",
+            ZulipUserRole::Guest => "guest",
+        };
+        f.write_str(name)
+    }
 }
 
 /// A collection of Zulip user groups
@@ -315,7 +546,37 @@ struct ZulipUserGroups {
 pub(crate) struct ZulipUserGroup {
     pub(crate) id: u64,
     pub(crate) name: String,
+    pub(crate) description: String,
     pub(crate) members: Vec<u64>,
+    /// Who may mention the group
+    pub(crate) can_mention_group: Option<GroupSettingValue>,
+}
+
+/// Value of a user group setting: either a user group, or an anonymous group made of users and
+/// user groups
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, serde::Serialize)]
+#[serde(untagged)]
+pub(crate) enum GroupSettingValue {
+    Group(u64),
+    Anonymous {
+        direct_members: Vec<u64>,
+        direct_subgroups: Vec<u64>,
+    },
+}
+
+impl GroupSettingValue {
+    /// Zulip may return a group as an anonymous group made of only that group
+    pub(crate) fn normalize(&self) -> Self {
+        match self {
+            GroupSettingValue::Anonymous {
+                direct_members,
+                direct_subgroups,
+            } if direct_members.is_empty() && direct_subgroups.len() == 1 => {
+                GroupSettingValue::Group(direct_subgroups[0])
+            }
+            _ => self.clone(),
+        }
+    }
 }
 
 /// A collection of Zulip streams
@@ -2,17 +2,26 @@ mod api;
 
 use crate::sync::team_api::TeamApi;
 use anyhow::Context;
-use api::{ZulipApi, ZulipStream, ZulipUserGroup};
-use rust_team_data::v1::{ZulipGroupMember, ZulipStreamMember};
+pub(crate) use api::ZulipApi;
+use api::{GroupSettingValue, ZulipStream, ZulipUserGroup, ZulipUserRole};
+use rust_team_data::v1::{ZulipGroupMember, ZulipMentionPolicy, ZulipRoles, ZulipStreamMember};
 
 use futures_util::StreamExt;
 use secrecy::SecretString;
 use std::collections::BTreeMap;
 
+/// Suffix of the description of the user groups created by sync-team
+const MANAGED_USER_GROUP_SUFFIX: &str = "(managed by the Team repo)";
+
 pub(crate) struct SyncZulip {
     zulip_controller: ZulipController,
-    stream_definitions: BTreeMap<String, Vec<u64>>,
-    user_group_definitions: BTreeMap<String, Vec<u64>>,
+    stream_definitions: BTreeMap<String, StreamDefinition>,
+    user_group_definitions: BTreeMap<String, UserGroupDefinition>,
+    /// Zulip user id to full name
+    user_names: BTreeMap<u64, String>,
+    /// Zulip user id to organization role, for the users that are not bots
+    user_roles: BTreeMap<u64, ZulipUserRole>,
+    role_definitions: ZulipRoles,
 }
 
 impl SyncZulip {
@@ -23,8 +32,23 @@ impl SyncZulip {
         dry_run: bool,
     ) -> anyhow::Result<Self> {
         let zulip_api = ZulipApi::new(username, token, dry_run);
-        let mut stream_definitions = get_stream_definitions(team_api, &zulip_api).await?;
-        let user_group_definitions = get_user_group_definitions(team_api, &zulip_api).await?;
+        let users = zulip_api.get_users().await?;
+        let email_map = users
+            .iter()
+            .filter_map(|u| u.email.clone().map(|e| (e, u.user_id)))
+            .collect::<BTreeMap<_, _>>();
+        let user_roles = users
+            .iter()
+            .filter(|u| !u.is_bot)
+            .map(|u| (u.user_id, u.role))
+            .collect();
+        let user_names = users
+            .into_iter()
+            .map(|u| (u.user_id, u.full_name))
+            .collect();
+        let role_definitions = team_api.get_zulip_roles().await?;
+        let mut stream_definitions = get_stream_definitions(team_api, &email_map).await?;
+        let user_group_definitions = get_user_group_definitions(team_api, &email_map).await?;
         let zulip_controller = ZulipController::new(zulip_api).await?;
         // rust-lang-owner is the user who owns the Zulip token.
         // This user needs to be in private streams to be able to
@@ -36,13 +60,16 @@ impl SyncZulip {
             zulip_controller,
             stream_definitions,
             user_group_definitions,
+            user_names,
+            user_roles,
+            role_definitions,
         })
     }
 
     pub(crate) async fn diff_all(&self) -> anyhow::Result<Diff> {
         let stream_membership_diffs = futures_util::stream::iter(&self.stream_definitions)
-            .filter_map(|(stream_name, member_ids)| async move {
-                self.diff_stream_membership(stream_name, member_ids)
+            .filter_map(|(stream_name, definition)| async move {
+                self.diff_stream_membership(stream_name, definition)
                     .await
                     .transpose()
             })
@@ -50,25 +77,94 @@ impl SyncZulip {
             .await
             .into_iter()
             .collect::<anyhow::Result<Vec<_>>>()?;
-        let user_group_diffs = self
+        let mut user_group_diffs = self
             .user_group_definitions
             .iter()
-            .filter_map(|(user_group_name, member_ids)| {
-                self.diff_user_group(user_group_name, member_ids)
+            .filter_map(|(user_group_name, definition)| {
+                self.diff_user_group(user_group_name, definition)
                     .transpose()
             })
             .collect::<anyhow::Result<Vec<_>>>()?;
+        user_group_diffs.extend(self.diff_removed_user_groups());
         Ok(Diff {
             user_group_diffs,
             stream_membership_diffs,
+            role_diffs: self.diff_roles(),
         })
     }
 
+    /// Give the users the role of their teams, and demote the users holding a role managed by
+    /// the team data without being in a team giving it.
+    ///
+    /// Owners, and administrators when no team gives that role, are left alone.
+    fn diff_roles(&self) -> Vec<RoleDiff> {
+        let managed_roles = self
+            .role_definitions
+            .managed_roles
+            .iter()
+            .map(|role| ZulipUserRole::from(*role))
+            .collect::<Vec<_>>();
+        for user_id in self.role_definitions.users.keys() {
+            if !self.user_roles.contains_key(user_id) {
+                log::warn!("Zulip user {user_id} is given a role but was not found on Zulip");
+            }
+        }
+
+        self.user_roles
+            .iter()
+            .filter_map(|(user_id, current)| {
+                let is_managed = managed_roles.contains(current);
+                if !is_managed && !matches!(current, ZulipUserRole::Member | ZulipUserRole::Guest) {
+                    return None;
+                }
+                let expected = match self.role_definitions.users.get(user_id) {
+                    Some(role) => ZulipUserRole::from(*role),
+                    None if is_managed => ZulipUserRole::Member,
+                    None => return None,
+                };
+                (expected != *current).then(|| RoleDiff {
+                    member: self.members(&[*user_id]).remove(0),
+                    old_role: *current,
+                    new_role: expected,
+                })
+            })
+            .collect()
+    }
+
+    fn members(&self, ids: &[u64]) -> Vec<ZulipMember> {
+        ids.iter()
+            .map(|id| ZulipMember {
+                id: *id,
+                name: self.user_names.get(id).cloned(),
+            })
+            .collect()
+    }
+
+    /// Delete the user groups created by sync-team that are not in the team data anymore.
+    fn diff_removed_user_groups(&self) -> Vec<ZulipDiff> {
+        self.zulip_controller
+            .user_group_ids
+            .values()
+            .filter(|group| {
+                group.description.ends_with(MANAGED_USER_GROUP_SUFFIX)
+                    && !self.user_group_definitions.contains_key(&group.name)
+            })
+            .map(|group| {
+                ZulipDiff::Delete(DeleteUserGroupDiff {
+                    name: group.name.clone(),
+                    user_group_id: group.id,
+                    members: self.members(&group.members),
+                })
+            })
+            .collect()
+    }
+
     fn diff_user_group(
         &self,
         user_group_name: &str,
-        member_ids: &[u64],
-    ) -> anyhow::Result<Option<UserGroupDiff>> {
+        definition: &UserGroupDefinition,
+    ) -> anyhow::Result<Option<ZulipDiff>> {
+        let member_ids = &definition.member_ids;
         let id = self
             .zulip_controller
             .user_group_id_from_name(user_group_name);
@@ -79,10 +175,26 @@ impl SyncZulip {
             }
             None => {
                 log::debug!("no '{user_group_name}' user group found on Zulip");
-                return Ok(Some(UserGroupDiff::Create(CreateUserGroupDiff {
+                let mention_policy = definition
+                    .mention_policy
+                    .map(|policy| {
+                        let description = match policy {
+                            // The group doesn't have an id until it is created
+                            ZulipMentionPolicy::Members => user_group_name.to_owned(),
+                            ZulipMentionPolicy::Everyone | ZulipMentionPolicy::Admins => {
+                                let setting =
+                                    self.zulip_controller.mention_policy_setting(policy, 0)?;
+                                self.zulip_controller.describe_group_setting(&setting)
+                            }
+                        };
+                        anyhow::Ok((policy, description))
+                    })
+                    .transpose()?;
+                return Ok(Some(ZulipDiff::Create(CreateUserGroupDiff {
                     name: user_group_name.to_owned(),
-                    description: format!("The {user_group_name} team (managed by the Team repo)"),
-                    member_ids: member_ids.to_owned(),
+                    description: format!("The {user_group_name} team {MANAGED_USER_GROUP_SUFFIX}"),
+                    members: self.members(member_ids),
+                    mention_policy,
                 })));
             }
         };
@@ -104,26 +216,61 @@ impl SyncZulip {
             .filter(|i| !member_ids.contains(i))
             .copied()
             .collect::<Vec<_>>();
-        if add_ids.is_empty() && remove_ids.is_empty() {
+        let mention_policy_diff = definition
+            .mention_policy
+            .map(|policy| self.diff_mention_policy(user_group_name, user_group_id, policy))
+            .transpose()?
+            .flatten();
+        if add_ids.is_empty() && remove_ids.is_empty() && mention_policy_diff.is_none() {
             log::debug!(
                 "'{user_group_name}' user group ({user_group_id}) does not need to be updated"
             );
             Ok(None)
         } else {
-            Ok(Some(UserGroupDiff::Update(UpdateUserGroupDiff {
+            Ok(Some(ZulipDiff::Edit(EditUserGroupDiff {
                 name: user_group_name.to_owned(),
                 user_group_id,
-                member_id_additions: add_ids,
-                member_id_deletions: remove_ids,
+                member_additions: self.members(&add_ids),
+                member_deletions: self.members(&remove_ids),
+                mention_policy_diff,
             })))
         }
     }
 
+    fn diff_mention_policy(
+        &self,
+        user_group_name: &str,
+        user_group_id: u64,
+        policy: ZulipMentionPolicy,
+    ) -> anyhow::Result<Option<MentionPolicyDiff>> {
+        let new = self
+            .zulip_controller
+            .mention_policy_setting(policy, user_group_id)?;
+        let old = self
+            .zulip_controller
+            .user_group_ids
+            .get(user_group_name)
+            .and_then(|group| group.can_mention_group.clone());
+        if old.as_ref().map(GroupSettingValue::normalize).as_ref() == Some(&new) {
+            return Ok(None);
+        }
+        Ok(Some(MentionPolicyDiff {
+            old_description: old
+                .as_ref()
+                .map(|old| self.zulip_controller.describe_group_setting(old))
+                .unwrap_or_else(|| "unknown".to_owned()),
+            new_description: self.zulip_controller.describe_group_setting(&new),
+            old,
+            new,
+        }))
+    }
+
     async fn diff_stream_membership(
         &self,
         stream_name: &str,
-        member_ids: &[u64],
+        definition: &StreamDefinition,
     ) -> anyhow::Result<Option<StreamMembershipDiff>> {
+        let member_ids = &definition.member_ids;
         let stream_id = match self.zulip_controller.stream_id_from_name(stream_name) {
             Some(id) => {
                 log::debug!("'{stream_name}' stream ({id}) found on Zulip");
@@ -135,6 +282,12 @@ impl SyncZulip {
             }
         };
         let is_stream_private = self.zulip_controller.is_stream_private(stream_id).await?;
+        if definition.sensitive && !is_stream_private {
+            log::warn!(
+                "'{stream_name}' stream ({stream_id}) belongs to a sensitive team but is public, \
+                 so its members are never removed"
+            );
+        }
 
         let existing_members = self
             .zulip_controller
@@ -165,8 +318,9 @@ impl SyncZulip {
                 UpdateStreamMembershipDiff {
                     stream_name: stream_name.to_owned(),
                     stream_id,
-                    member_id_additions: add_ids,
-                    member_id_deletions: remove_ids,
+                    sensitive: definition.sensitive,
+                    member_additions: self.members(&add_ids),
+                    member_deletions: self.members(&remove_ids),
                 },
             )))
         }
@@ -174,12 +328,12 @@ impl SyncZulip {
 }
 
 async fn add_rust_lang_owner_to_private_streams(
-    stream_definitions: &mut BTreeMap<String, Vec<u64>>,
+    stream_definitions: &mut BTreeMap<String, StreamDefinition>,
     zulip_controller: &ZulipController,
 ) -> anyhow::Result<()> {
     // Id of the `rust-lang-owner` Zulip user.
     let rust_lang_owner_id = 494485;
-    for (stream_name, members) in stream_definitions {
+    for (stream_name, definition) in stream_definitions {
         let stream_id = zulip_controller
             .stream_id_from_name(stream_name)
             .with_context(|| {
@@ -194,15 +348,17 @@ async fn add_rust_lang_owner_to_private_streams(
             .is_stream_private(stream_id)
             .await?;
         if is_stream_private {
-            members.insert(0, rust_lang_owner_id);
+            definition.member_ids.insert(0, rust_lang_owner_id);
         }
     }
     Ok(())
 }
 
+#[derive(serde::Serialize)]
 pub(crate) struct Diff {
-    user_group_diffs: Vec<UserGroupDiff>,
+    user_group_diffs: Vec<ZulipDiff>,
     stream_membership_diffs: Vec<StreamMembershipDiff>,
+    role_diffs: Vec<RoleDiff>,
 }
 
 impl Diff {
@@ -213,11 +369,51 @@ impl Diff {
         for stream_membership_diff in &self.stream_membership_diffs {
             stream_membership_diff.apply(sync).await?;
         }
+        for role_diff in &self.role_diffs {
+            role_diff.apply(sync).await?;
+        }
+        Ok(())
+    }
+
+    /// Refuse diffs that delete more than `limit` user groups, or remove more than `limit`
+    /// members from the streams of sensitive teams.
+    ///
+    /// A bad data edit should not be able to delete the groups of many teams at once, or lock
+    /// moderation or security out of their private streams.
+    pub(crate) fn check_deletion_limit(&self, limit: usize) -> anyhow::Result<()> {
+        let deleted_groups = self
+            .user_group_diffs
+            .iter()
+            .filter(|diff| matches!(diff, ZulipDiff::Delete(_)))
+            .count();
+        if deleted_groups > limit {
+            anyhow::bail!(
+                "the Zulip diff would delete {deleted_groups} user groups, more than the limit \
+                 of {limit}; review the diff and rerun with `--force` if it is intended"
+            );
+        }
+        let removed_members = self
+            .stream_membership_diffs
+            .iter()
+            .map(|diff| match diff {
+                StreamMembershipDiff::Update(u) if u.sensitive => u.member_deletions.len(),
+                StreamMembershipDiff::Update(_) => 0,
+            })
+            .sum::<usize>();
+        if removed_members > limit {
+            anyhow::bail!(
+                "the Zulip diff would remove {removed_members} members from the streams of \
+                 sensitive teams, more than the limit of {limit}; \
+                 review the diff and rerun with `--force` if it is intended"
+            );
+        }
         Ok(())
     }
 
     pub(crate) fn is_empty(&self) -> bool {
-        self.user_group_diffs.is_empty() && self.stream_membership_diffs.is_empty()
+        self.user_group_diffs.is_empty()
+            && self.stream_membership_diffs.is_empty()
+            && self.role_diffs.is_empty()
     }
 }
 
@@ -237,10 +433,40 @@ impl std::fmt::Display for Diff {
             }
         }
 
+        if !&self.role_diffs.is_empty() {
+            writeln!(f, "💻 Role Diffs:")?;
+            for role_diff in &self.role_diffs {
+                write!(f, "{role_diff}")?;
+            }
+        }
+
         Ok(())
     }
 }
 
+/// A Zulip user, with their name to make the diff readable
+#[derive(serde::Serialize)]
+struct ZulipMember {
+    id: u64,
+    /// Not known for users that are not in the Zulip organization anymore
+    name: Option<String>,
+}
+
+impl std::fmt::Display for ZulipMember {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.name {
+            Some(name) => write!(f, "{name} ({})", self.id),
+            None => write!(f, "{}", self.id),
+        }
+    }
+}
+
+fn member_ids(members: &[ZulipMember]) -> Vec<u64> {
+    members.iter().map(|m| m.id).collect()
+}
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "snake_case")]
 enum StreamMembershipDiff {
     Update(UpdateStreamMembershipDiff),
 }
@@ -261,11 +487,14 @@ impl std::fmt::Display for StreamMembershipDiff {
     }
 }
 
+#[derive(serde::Serialize)]
 struct UpdateStreamMembershipDiff {
     stream_name: String,
     stream_id: u64,
-    member_id_additions: Vec<u64>,
-    member_id_deletions: Vec<u64>,
+    /// Whether the stream belongs to a sensitive team
+    sensitive: bool,
+    member_additions: Vec<ZulipMember>,
+    member_deletions: Vec<ZulipMember>,
 }
 
 impl UpdateStreamMembershipDiff {
@@ -275,8 +504,8 @@ impl UpdateStreamMembershipDiff {
             .update_stream_membership(
                 &self.stream_name,
                 self.stream_id,
-                &self.member_id_additions,
-                &self.member_id_deletions,
+                &member_ids(&self.member_additions),
+                &member_ids(&self.member_deletions),
             )
             .await
     }
@@ -287,51 +516,73 @@ impl std::fmt::Display for UpdateStreamMembershipDiff {
         writeln!(f, "📝 Updating stream membership:")?;
         writeln!(f, "  Name: {}", self.stream_name)?;
         writeln!(f, "  ID: {}", self.stream_id)?;
+        if self.sensitive {
+            writeln!(f, "  ⚠️ Stream of a sensitive team")?;
+        }
         writeln!(f, "  Members:")?;
-        for member_id in &self.member_id_additions {
-            writeln!(f, "    ➕ {member_id}")?;
+        for member in &self.member_additions {
+            writeln!(f, "    ➕ {member}")?;
         }
-        for member_id in &self.member_id_deletions {
-            writeln!(f, "    − {member_id}")?;
+        for member in &self.member_deletions {
+            writeln!(f, "    − {member}")?;
         }
         Ok(())
     }
 }
 
-enum UserGroupDiff {
+/// A change of a Zulip user group
+#[derive(serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+enum ZulipDiff {
     Create(CreateUserGroupDiff),
-    Update(UpdateUserGroupDiff),
+    Edit(EditUserGroupDiff),
+    Delete(DeleteUserGroupDiff),
 }
 
-impl UserGroupDiff {
+impl ZulipDiff {
     async fn apply(&self, sync: &SyncZulip) -> anyhow::Result<()> {
         match self {
-            UserGroupDiff::Create(c) => c.apply(sync).await,
-            UserGroupDiff::Update(u) => u.apply(sync).await,
+            ZulipDiff::Create(c) => c.apply(sync).await,
+            ZulipDiff::Edit(e) => e.apply(sync).await,
+            ZulipDiff::Delete(d) => d.apply(sync).await,
         }
     }
 }
 
-impl std::fmt::Display for UserGroupDiff {
+impl std::fmt::Display for ZulipDiff {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Self::Create(c) => write!(f, "{c}"),
-            Self::Update(u) => write!(f, "{u}"),
+            Self::Edit(e) => write!(f, "{e}"),
+            Self::Delete(d) => write!(f, "{d}"),
         }
     }
 }
 
+#[derive(serde::Serialize)]
 struct CreateUserGroupDiff {
     name: String,
     description: String,
-    member_ids: Vec<u64>,
+    members: Vec<ZulipMember>,
+    /// Who may mention the group, and its description for the diff
+    mention_policy: Option<(ZulipMentionPolicy, String)>,
 }
 
 impl CreateUserGroupDiff {
     async fn apply(&self, sync: &SyncZulip) -> Result<(), anyhow::Error> {
-        sync.zulip_controller
-            .create_user_group(&self.name, &self.description, &self.member_ids)
-            .await
+        let controller = &sync.zulip_controller;
+        let id = controller
+            .create_user_group(&self.name, &self.description, &member_ids(&self.members))
+            .await?;
+        // The policy can refer to the group itself, so it is set once the group exists
+        if let (Some(id), Some((policy, _))) = (id, &self.mention_policy) {
+            let setting = controller.mention_policy_setting(*policy, id)?;
+            controller
+                .zulip_api
+                .update_user_group_mention_policy(id, None, &setting)
+                .await?;
+        }
+        Ok(())
     }
 }
 
@@ -340,60 +591,144 @@ impl std::fmt::Display for CreateUserGroupDiff {
         writeln!(f, "➕ Creating user group:")?;
         writeln!(f, "  Name: {}", self.name)?;
         writeln!(f, "  Description: {}", self.description)?;
+        if let Some((_, description)) = &self.mention_policy {
+            writeln!(f, "  Can mention: {description}")?;
+        }
         writeln!(f, "  Members:")?;
-        for member_id in &self.member_ids {
-            writeln!(f, "    {member_id}")?;
+        for member in &self.members {
+            writeln!(f, "    {member}")?;
         }
         Ok(())
     }
 }
 
-struct UpdateUserGroupDiff {
+#[derive(serde::Serialize)]
+struct EditUserGroupDiff {
     name: String,
     user_group_id: u64,
-    member_id_additions: Vec<u64>,
-    member_id_deletions: Vec<u64>,
+    member_additions: Vec<ZulipMember>,
+    member_deletions: Vec<ZulipMember>,
+    mention_policy_diff: Option<MentionPolicyDiff>,
+}
+
+/// A change of who may mention a user group
+#[derive(serde::Serialize)]
+struct MentionPolicyDiff {
+    old: Option<GroupSettingValue>,
+    new: GroupSettingValue,
+    old_description: String,
+    new_description: String,
 }
 
-impl UpdateUserGroupDiff {
+impl EditUserGroupDiff {
     async fn apply(&self, sync: &SyncZulip) -> Result<(), anyhow::Error> {
-        sync.zulip_controller
-            .zulip_api
+        let zulip_api = &sync.zulip_controller.zulip_api;
+        zulip_api
             .update_user_group_members(
                 self.user_group_id,
-                &self.member_id_additions,
-                &self.member_id_deletions,
+                &member_ids(&self.member_additions),
+                &member_ids(&self.member_deletions),
             )
-            .await
+            .await?;
+        if let Some(diff) = &self.mention_policy_diff {
+            zulip_api
+                .update_user_group_mention_policy(self.user_group_id, diff.old.as_ref(), &diff.new)
+                .await?;
+        }
+        Ok(())
     }
 }
 
-impl std::fmt::Display for UpdateUserGroupDiff {
+impl std::fmt::Display for EditUserGroupDiff {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         writeln!(f, "📝 Updating user group:")?;
         writeln!(f, "  Name: {}", self.name)?;
-        writeln!(f, "  Members:")?;
-        for member_id in &self.member_id_additions {
-            writeln!(f, "    ➕ {member_id}")?;
+        if let Some(diff) = &self.mention_policy_diff {
+            writeln!(
+                f,
+                "  Can mention: {} => {}",
+                diff.old_description, diff.new_description
+            )?;
+        }
+        if !self.member_additions.is_empty() || !self.member_deletions.is_empty() {
+            writeln!(f, "  Members:")?;
+            for member in &self.member_additions {
+                writeln!(f, "    ➕ {member}")?;
+            }
+            for member in &self.member_deletions {
+                writeln!(f, "    − {member}")?;
+            }
         }
-        for member_id in &self.member_id_deletions {
-            writeln!(f, "    − {member_id}")?;
+        Ok(())
+    }
+}
+
+/// Deletes a user group created by sync-team that is not in the team data anymore
+#[derive(serde::Serialize)]
+struct DeleteUserGroupDiff {
+    name: String,
+    user_group_id: u64,
+    members: Vec<ZulipMember>,
+}
+
+impl DeleteUserGroupDiff {
+    async fn apply(&self, sync: &SyncZulip) -> Result<(), anyhow::Error> {
+        sync.zulip_controller
+            .zulip_api
+            .deactivate_user_group(self.user_group_id)
+            .await
+    }
+}
+
+impl std::fmt::Display for DeleteUserGroupDiff {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "❌ Deleting user group '{}'", self.name)?;
+        writeln!(f, "  Members:")?;
+        for member in &self.members {
+            writeln!(f, "    − {member}")?;
         }
         Ok(())
     }
 }
 
+/// A change of the organization role of a Zulip user
+#[derive(serde::Serialize)]
+struct RoleDiff {
+    member: ZulipMember,
+    old_role: ZulipUserRole,
+    new_role: ZulipUserRole,
+}
+
+impl RoleDiff {
+    async fn apply(&self, sync: &SyncZulip) -> anyhow::Result<()> {
+        sync.zulip_controller
+            .zulip_api
+            .update_user_role(self.member.id, self.new_role)
+            .await
+    }
+}
+
+impl std::fmt::Display for RoleDiff {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "📝 Changing the role of {}: {} => {}",
+            self.member, self.old_role, self.new_role
+        )
+    }
+}
+
+/// A user group as defined in the team data
+struct UserGroupDefinition {
+    member_ids: Vec<u64>,
+    mention_policy: Option<ZulipMentionPolicy>,
+}
+
 /// Fetches the definitions of the user groups from the Team API
 async fn get_user_group_definitions(
     team_api: &TeamApi,
-    zulip_api: &ZulipApi,
-) -> anyhow::Result<BTreeMap<String, Vec<u64>>> {
-    let email_map = zulip_api
-        .get_users()
-        .await?
-        .into_iter()
-        .filter_map(|u| u.email.map(|e| (e, u.user_id)))
-        .collect::<BTreeMap<_, _>>();
+    email_map: &BTreeMap<String, u64>,
+) -> anyhow::Result<BTreeMap<String, UserGroupDefinition>> {
     let user_group_definitions = team_api
         .get_zulip_groups()
         .await?
@@ -414,23 +749,28 @@ async fn get_user_group_definitions(
                     ZulipGroupMember::Id(id) => Some(*id),
                 })
                 .collect::<Vec<_>>();
-            (name, member_ids)
+            let definition = UserGroupDefinition {
+                member_ids,
+                mention_policy: group.mention_policy,
+            };
+            (name, definition)
         })
         .collect();
     Ok(user_group_definitions)
 }
 
+/// A stream as defined in the team data
+struct StreamDefinition {
+    member_ids: Vec<u64>,
+    /// Whether the stream belongs to a sensitive team
+    sensitive: bool,
+}
+
 /// Fetches the definitions of the user streams from the Team API
 async fn get_stream_definitions(
     team_api: &TeamApi,
-    zulip_api: &ZulipApi,
-) -> anyhow::Result<BTreeMap<String, Vec<u64>>> {
-    let email_map = zulip_api
-        .get_users()
-        .await?
-        .into_iter()
-        .filter_map(|u| u.email.map(|e| (e, u.user_id)))
-        .collect::<BTreeMap<_, _>>();
+    email_map: &BTreeMap<String, u64>,
+) -> anyhow::Result<BTreeMap<String, StreamDefinition>> {
     let stream_definitions = team_api
         .get_zulip_streams()
         .await?
@@ -451,7 +791,11 @@ async fn get_stream_definitions(
                     ZulipStreamMember::Id(id) => Some(*id),
                 })
                 .collect::<Vec<_>>();
-            (name, member_ids)
+            let definition = StreamDefinition {
+                member_ids,
+                sensitive: stream.sensitive,
+            };
+            (name, definition)
         })
         .collect();
     Ok(stream_definitions)
@@ -498,23 +842,67 @@ impl ZulipController {
         self.user_group_ids.get(user_group_name).map(|u| u.id)
     }
 
+    /// The value of the `can_mention_group` setting of a user group that applies the policy
+    fn mention_policy_setting(
+        &self,
+        policy: ZulipMentionPolicy,
+        user_group_id: u64,
+    ) -> anyhow::Result<GroupSettingValue> {
+        let system_group = |name: &str| {
+            self.user_group_id_from_name(name)
+                .with_context(|| format!("system user group '{name}' not found on Zulip"))
+        };
+        Ok(GroupSettingValue::Group(match policy {
+            ZulipMentionPolicy::Everyone => system_group("role:everyone")?,
+            ZulipMentionPolicy::Members => user_group_id,
+            ZulipMentionPolicy::Admins => system_group("role:administrators")?,
+        }))
+    }
+
+    /// Describe the value of a user group setting for the diff
+    fn describe_group_setting(&self, value: &GroupSettingValue) -> String {
+        let group_name = |id: &u64| {
+            self.user_group_ids
+                .values()
+                .find(|group| group.id == *id)
+                .map(|group| group.name.clone())
+                .unwrap_or_else(|| format!("group {id}"))
+        };
+        match value.normalize() {
+            GroupSettingValue::Group(id) => group_name(&id),
+            GroupSettingValue::Anonymous {
+                direct_members,
+                direct_subgroups,
+            } => {
+                let mut parts = direct_subgroups.iter().map(group_name).collect::<Vec<_>>();
+                if !direct_members.is_empty() {
+                    parts.push(format!("{} users", direct_members.len()));
+                }
+                if parts.is_empty() {
+                    "nobody".to_owned()
+                } else {
+                    parts.join(", ")
+                }
+            }
+        }
+    }
+
     /// Get a stream id for the given stream name
     fn stream_id_from_name(&self, stream_name: &str) -> Option<u64> {
         self.stream_ids.get(stream_name).map(|st| st.stream_id)
     }
 
-    /// Create a user group with a certain name, description, and members
+    /// Create a user group with a certain name, description, and members, returning its id
+    /// unless it already existed or this is a dry run
     async fn create_user_group(
         &self,
         user_group_name: &str,
         description: &str,
         member_ids: &[u64],
-    ) -> anyhow::Result<()> {
+    ) -> anyhow::Result<Option<u64>> {
         self.zulip_api
             .create_user_group(user_group_name, description, member_ids)
-            .await?;
-
-        Ok(())
+            .await
     }
 
     /// Get the members of a user group given its name
@@ -533,3 +921,212 @@ impl ZulipController {
         self.zulip_api.is_stream_private(stream_id).await
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_team_data::v1::ZulipRole;
+
+    fn user_group(id: u64, name: &str, description: &str, members: &[u64]) -> ZulipUserGroup {
+        ZulipUserGroup {
+            id,
+            name: name.to_owned(),
+            description: description.to_owned(),
+            members: members.to_vec(),
+            can_mention_group: None,
+        }
+    }
+
+    /// A sync of the given user group definitions against the given Zulip user groups
+    fn sync(
+        user_groups: Vec<ZulipUserGroup>,
+        definitions: &[(&str, &[u64], Option<ZulipMentionPolicy>)],
+    ) -> SyncZulip {
+        SyncZulip {
+            zulip_controller: ZulipController {
+                user_group_ids: user_groups
+                    .into_iter()
+                    .map(|group| (group.name.clone(), group))
+                    .collect(),
+                stream_ids: BTreeMap::new(),
+                zulip_api: ZulipApi::new("bot".to_owned(), SecretString::from("token"), true),
+            },
+            stream_definitions: BTreeMap::new(),
+            user_group_definitions: definitions
+                .iter()
+                .map(|(name, members, mention_policy)| {
+                    let definition = UserGroupDefinition {
+                        member_ids: members.to_vec(),
+                        mention_policy: *mention_policy,
+                    };
+                    (name.to_string(), definition)
+                })
+                .collect(),
+            user_names: [(1, "mark"), (2, "jan"), (3, "niko")]
+                .into_iter()
+                .map(|(id, name)| (id, name.to_owned()))
+                .collect(),
+            user_roles: BTreeMap::new(),
+            role_definitions: ZulipRoles {
+                users: Default::default(),
+                managed_roles: Vec::new(),
+            },
+        }
+    }
+
+    fn managed(name: &str) -> String {
+        format!("The {name} team {MANAGED_USER_GROUP_SUFFIX}")
+    }
+
+    #[tokio::test]
+    async fn user_group_diff() {
+        let sync = sync(
+            vec![
+                user_group(10, "T-compiler", &managed("T-compiler"), &[1, 2]),
+                user_group(11, "T-lang", &managed("T-lang"), &[1]),
+                user_group(12, "T-removed", &managed("T-removed"), &[2]),
+                // Not created by sync-team, so it is never deleted
+                user_group(13, "hackers", "Created by hand", &[3]),
+            ],
+            &[
+                ("T-compiler", &[1, 3], None),
+                ("T-lang", &[1], None),
+                ("T-libs", &[2], None),
+            ],
+        );
+        let diff = sync.diff_all().await.unwrap();
+        insta::assert_snapshot!(diff, @r"
+        💻 User Group Diffs:
+        📝 Updating user group:
+          Name: T-compiler
+          Members:
+            ➕ niko (3)
+            − jan (2)
+        ➕ Creating user group:
+          Name: T-libs
+          Description: The T-libs team (managed by the Team repo)
+          Members:
+            jan (2)
+        ❌ Deleting user group 'T-removed'
+          Members:
+            − jan (2)
+        ");
+    }
+
+    #[tokio::test]
+    async fn user_group_deletion_limit() {
+        let sync = sync(
+            vec![
+                user_group(10, "T-compiler", &managed("T-compiler"), &[1]),
+                user_group(11, "T-lang", &managed("T-lang"), &[2]),
+            ],
+            &[],
+        );
+        let diff = sync.diff_all().await.unwrap();
+        insta::assert_snapshot!(serde_json::to_string_pretty(&diff).unwrap(), @r#"
+        {
+          "user_group_diffs": [
+            {
+              "delete": {
+                "name": "T-compiler",
+                "user_group_id": 10,
+                "members": [
+                  {
+                    "id": 1,
+                    "name": "mark"
+                  }
+                ]
+              }
+            },
+            {
+              "delete": {
+                "name": "T-lang",
+                "user_group_id": 11,
+                "members": [
+                  {
+                    "id": 2,
+                    "name": "jan"
+                  }
+                ]
+              }
+            }
+          ],
+          "stream_membership_diffs": [],
+          "role_diffs": []
+        }
+        "#);
+        diff.check_deletion_limit(2).unwrap();
+        let error = diff.check_deletion_limit(1).unwrap_err();
+        insta::assert_snapshot!(error, @r"
+        the Zulip diff would delete 2 user groups, more than the limit of 1; review the diff and rerun with `--force` if it is intended
+        ");
+    }
+
+    #[tokio::test]
+    async fn role_diff() {
+        let mut sync = sync(Vec::new(), &[]);
+        sync.user_roles = [
+            (1, ZulipUserRole::Moderator),
+            (2, ZulipUserRole::Member),
+            (3, ZulipUserRole::Owner),
+        ]
+        .into_iter()
+        .collect();
+        sync.role_definitions = ZulipRoles {
+            users: [(2, ZulipRole::Moderator), (3, ZulipRole::Moderator)]
+                .into_iter()
+                .collect(),
+            managed_roles: vec![ZulipRole::Moderator],
+        };
+        let diff = sync.diff_all().await.unwrap();
+        insta::assert_snapshot!(diff, @r"
+        💻 Role Diffs:
+        📝 Changing the role of mark (1): moderator => member
+        📝 Changing the role of jan (2): member => moderator
+        ");
+    }
+
+    #[tokio::test]
+    async fn user_group_mention_policy_diff() {
+        let mut compiler = user_group(10, "T-compiler", &managed("T-compiler"), &[1]);
+        compiler.can_mention_group = Some(GroupSettingValue::Anonymous {
+            direct_members: vec![],
+            direct_subgroups: vec![1000],
+        });
+        let mut lang = user_group(11, "T-lang", &managed("T-lang"), &[1]);
+        lang.can_mention_group = Some(GroupSettingValue::Group(11));
+        let sync = sync(
+            vec![
+                user_group(1000, "role:everyone", "Everyone", &[]),
+                user_group(1001, "role:administrators", "Administrators", &[]),
+                compiler,
+                lang,
+            ],
+            &[
+                ("T-compiler", &[1], Some(ZulipMentionPolicy::Admins)),
+                ("T-lang", &[1], Some(ZulipMentionPolicy::Members)),
+                ("T-libs", &[2], Some(ZulipMentionPolicy::Members)),
+                ("T-types", &[2], Some(ZulipMentionPolicy::Everyone)),
+            ],
+        );
+        let diff = sync.diff_all().await.unwrap();
+        insta::assert_snapshot!(diff, @r"
+        💻 User Group Diffs:
+        📝 Updating user group:
+          Name: T-compiler
+          Can mention: role:everyone => role:administrators
+        ➕ Creating user group:
+          Name: T-libs
+          Description: The T-libs team (managed by the Team repo)
+          Can mention: T-libs
+          Members:
+            jan (2)
+        ➕ Creating user group:
+          Name: T-types
+          Description: The T-types team (managed by the Team repo)
+          Can mention: role:everyone
+          Members:
+            jan (2)
+        ");
+    }
+}
@@ -0,0 +1,361 @@
+//! Edits of the `[people]` table of team files. They are made line by line, so that the rest of
+//! the file, including its formatting and comments, is left untouched.
+
+use crate::data::Data;
+use crate::schema::{TeamKind, TeamMember};
+use anyhow::Error;
+use std::path::PathBuf;
+
+/// The files a team is defined in, in the order they are loaded
+pub(crate) fn team_files(data: &Data, team: &str) -> Result<Vec<PathBuf>, Error> {
+    let Some(path) = data.team_path(team) else {
+        anyhow::bail!("unknown team: {team}");
+    };
+    if !path.is_dir() {
+        return Ok(vec![path.to_owned()]);
+    }
+    let mut files = Vec::new();
+    for entry in std::fs::read_dir(path)? {
+        let path = entry?.path();
+        if path.extension().is_some_and(|ext| ext == "toml") {
+            files.push(path);
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Append an item to an array of the `[people]` table, following the formatting of the array.
+/// Returns `None` if the file doesn't define the array.
+pub(crate) fn append_to_array(contents: &str, key: &str, item: &str) -> Option<String> {
+    let mut lines = lines(contents);
+    let (start, end) = people_array(&lines, key)?;
+    if start == end {
+        // members = ["a", "b"]
+        let line = &lines[start];
+        let close = line.rfind(']')?;
+        let items = line[..close].trim_end();
+        let separator = if items.ends_with('[') { "" } else { ", " };
+        lines[start] = format!("{items}{separator}{item}{}", &line[close..]);
+    } else {
+        // members = [
+        //     "a",
+        // ]
+        let last = (start + 1..end).rev().find(|&i| {
+            let line = lines[i].trim();
+            !line.is_empty() && !line.starts_with('#')
+        });
+        let indent = match last {
+            Some(last) => {
+                let line = lines[last].trim_start();
+                lines[last][..lines[last].len() - line.len()].to_owned()
+            }
+            None => "    ".to_owned(),
+        };
+        if let Some(last) = last {
+            let line = lines[last].trim_end();
+            if !line.ends_with(',') {
+                lines[last] = format!("{line},{}", &lines[last][line.len()..]);
+            }
+        }
+        lines.insert(end, format!("{indent}{item},\n"));
+    }
+    Some(lines.concat())
+}
+
+/// Remove a person from an array of the `[people]` table, whether they are listed by username or
+/// as an inline table. Returns `None` if the file doesn't define the array.
+pub(crate) fn remove_from_array(contents: &str, key: &str, github_name: &str) -> Option<String> {
+    let mut lines = lines(contents);
+    let (start, end) = people_array(&lines, key)?;
+    if start == end {
+        let line = &lines[start];
+        let open = line.find('[')?;
+        let close = line.rfind(']')?;
+        let items: Vec<_> = split_items(&line[open + 1..close])
+            .into_iter()
+            .map(str::trim)
+            .filter(|item| !item.is_empty() && !is_person(item, github_name))
+            .collect();
+        lines[start] = format!("{}{}{}", &line[..=open], items.join(", "), &line[close..]);
+    } else {
+        for i in (start + 1..end).rev() {
+            let item = lines[i].split('#').next().unwrap().trim();
+            if is_person(item.trim_end_matches(','), github_name) {
+                lines.remove(i);
+            }
+        }
+    }
+    Some(lines.concat())
+}
+
+/// Add an array to the `[people]` table, right after another one. Returns `None` if the file
+/// doesn't define the other array.
+pub(crate) fn add_array_after(
+    contents: &str,
+    after: &str,
+    key: &str,
+    item: &str,
+) -> Option<String> {
+    let mut lines = lines(contents);
+    let (_, end) = people_array(&lines, after)?;
+    lines.insert(end + 1, format!("{key} = [{item}]\n"));
+    Some(lines.concat())
+}
+
+/// The entry of a member in the alumni of a team, once they left it. People who rejoined the team
+/// have a `previous` entry, whose joined date and roles are kept.
+pub(crate) fn alumni_entry(
+    member: &TeamMember,
+    previous: Option<&TeamMember>,
+    left: chrono::NaiveDate,
+) -> String {
+    let mut roles = previous.map_or(Vec::new(), |previous| previous.roles.clone());
+    for role in &member.roles {
+        if !roles.contains(role) {
+            roles.push(role.clone());
+        }
+    }
+    let roles = roles
+        .iter()
+        .map(|role| format!("\"{role}\""))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let joined = previous
+        .and_then(|previous| previous.joined)
+        .or(member.joined)
+        .map(|joined| format!(", joined = \"{joined}\""))
+        .unwrap_or_default();
+    format!(
+        "{{ github = \"{}\", roles = [{roles}]{joined}, left = \"{left}\" }}",
+        member.github
+    )
+}
+
+/// The file of a new team, with a GitHub team, a Zulip group and a mailing list named like those
+/// of the existing teams of the same kind. `short_name` is the name without the prefix of its kind.
+pub(crate) fn new_team(
+    name: &str,
+    short_name: &str,
+    kind: TeamKind,
+    parent: &str,
+    lead: &str,
+    description: &str,
+) -> String {
+    let (kind_key, zulip_group, zulip_stream) = match kind {
+        TeamKind::Team => ("", format!("T-{name}"), format!("t-{name}")),
+        TeamKind::WorkingGroup => (
+            "kind = \"working-group\"\n",
+            format!("WG-{short_name}"),
+            name.to_owned(),
+        ),
+        TeamKind::ProjectGroup => (
+            "kind = \"project-group\"\n",
+            name.to_owned(),
+            name.to_owned(),
+        ),
+        TeamKind::MarkerTeam => ("kind = \"marker-team\"\n", name.to_owned(), name.to_owned()),
+    };
+    let mut website_name = short_name.replace('-', " ");
+    if let Some(first) = website_name.get_mut(..1) {
+        first.make_ascii_uppercase();
+    }
+    format!(
+        "name = \"{name}\"\n\
+         subteam-of = \"{parent}\"\n\
+         {kind_key}\
+         \n\
+         [people]\n\
+         leads = [\"{lead}\"]\n\
+         members = [\n    \"{lead}\",\n]\n\
+         alumni = []\n\
+         \n\
+         [[github]]\n\
+         orgs = [\"rust-lang\"]\n\
+         \n\
+         [website]\n\
+         name = \"{website_name} {kind}\"\n\
+         description = {}\n\
+         zulip-stream = \"{zulip_stream}\"\n\
+         \n\
+         [[lists]]\n\
+         address = \"{name}@rust-lang.org\"\n\
+         \n\
+         [[zulip-groups]]\n\
+         name = \"{zulip_group}\"\n",
+        toml::Value::from(description),
+    )
+}
+
+fn lines(contents: &str) -> Vec<String> {
+    contents.split_inclusive('\n').map(String::from).collect()
+}
+
+/// The first and last lines of an array of the `[people]` table
+fn people_array(lines: &[String], key: &str) -> Option<(usize, usize)> {
+    let mut in_people = false;
+    for (i, line) in lines.iter().enumerate() {
+        let line = line.trim_start();
+        if line.starts_with('[') {
+            in_people = line.trim_end() == "[people]";
+            continue;
+        }
+        let is_key = line
+            .strip_prefix(key)
+            .is_some_and(|rest| rest.trim_start().starts_with('='));
+        if in_people && is_key {
+            if line.contains(']') {
+                return Some((i, i));
+            }
+            let end = (i + 1..lines.len()).find(|&end| lines[end].trim_start().starts_with(']'))?;
+            return Some((i, end));
+        }
+    }
+    None
+}
+
+/// Split the items of an inline array, without splitting the strings and tables they contain
+fn split_items(items: &str) -> Vec<&str> {
+    let mut split = Vec::new();
+    let mut depth = 0;
+    let mut quote = None;
+    let mut start = 0;
+    for (i, c) in items.char_indices() {
+        match (quote, c) {
+            (Some(open), c) if c == open => quote = None,
+            (Some(_), _) => {}
+            (None, '"' | '\'') => quote = Some(c),
+            (None, '[' | '{') => depth += 1,
+            (None, ']' | '}') => depth -= 1,
+            (None, ',') if depth == 0 => {
+                split.push(&items[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    split.push(&items[start..]);
+    split
+}
+
+fn is_person(item: &str, github_name: &str) -> bool {
+    let Ok(mut table) = toml::from_str::<toml::Table>(&format!("item = {item}")) else {
+        return false;
+    };
+    match table.remove("item") {
+        Some(toml::Value::String(name)) => name == github_name,
+        Some(toml::Value::Table(member)) => {
+            member.get("github").and_then(toml::Value::as_str) == Some(github_name)
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{add_array_after, alumni_entry, append_to_array, new_team, remove_from_array};
+    use crate::schema::{Team, TeamKind, TeamMember};
+
+    #[test]
+    fn append_to_inline_array() {
+        let team = "name = \"foo\"\n\n[people]\nleads = []\nmembers = [\"a\"]\n";
+        assert_eq!(
+            append_to_array(team, "members", "\"new\"").unwrap(),
+            "name = \"foo\"\n\n[people]\nleads = []\nmembers = [\"a\", \"new\"]\n"
+        );
+        let empty = "[people]\nleads = []\nmembers = []\n";
+        assert_eq!(
+            append_to_array(empty, "members", "\"new\"").unwrap(),
+            "[people]\nleads = []\nmembers = [\"new\"]\n"
+        );
+        assert_eq!(
+            append_to_array("name = \"foo\"\nmembers = []\n", "members", "\"new\""),
+            None
+        );
+    }
+
+    #[test]
+    fn append_to_multiline_array() {
+        let team = "[people]\nleads = [\"a\"]\nmembers = [\n  \"a\",\n  { github = \"b\", roles = [\"lead\"] }\n]\nalumni = []\n\n[website]\nmembers = [\n]\n";
+        assert_eq!(
+            append_to_array(team, "members", "\"new\"").unwrap(),
+            "[people]\nleads = [\"a\"]\nmembers = [\n  \"a\",\n  { github = \"b\", roles = [\"lead\"] },\n  \"new\",\n]\nalumni = []\n\n[website]\nmembers = [\n]\n"
+        );
+    }
+
+    #[test]
+    fn remove_from_arrays() {
+        let team = "[people]\nleads = [\"a\", \"old\"]\nmembers = [\n    \"a\",\n    { github = \"old\", roles = [\"x\"] }, # lead\n    \"older\",\n]\n";
+        let team = remove_from_array(team, "leads", "old").unwrap();
+        let team = remove_from_array(&team, "members", "old").unwrap();
+        assert_eq!(
+            team,
+            "[people]\nleads = [\"a\"]\nmembers = [\n    \"a\",\n    \"older\",\n]\n"
+        );
+        assert_eq!(
+            remove_from_array(&team, "alumni", "old"),
+            None,
+            "the team has no alumni"
+        );
+        assert_eq!(
+            add_array_after(&team, "members", "alumni", "\"old\"").unwrap(),
+            "[people]\nleads = [\"a\"]\nmembers = [\n    \"a\",\n    \"older\",\n]\nalumni = [\"old\"]\n"
+        );
+    }
+
+    #[test]
+    fn new_team_is_valid() {
+        let file = new_team(
+            "wg-foo",
+            "foo",
+            TeamKind::WorkingGroup,
+            "lang",
+            "lead",
+            "Doing \"foo\"",
+        );
+        let team: Team = toml::from_str(&file).unwrap();
+        assert_eq!(team.name(), "wg-foo");
+        assert_eq!(team.kind(), TeamKind::WorkingGroup);
+        assert_eq!(team.leads(), ["lead"].into_iter().collect());
+        assert!(file.contains("name = \"Foo working group\"\n"));
+        assert!(file.contains("name = \"WG-foo\"\n"));
+        assert!(file.contains("address = \"wg-foo@rust-lang.org\"\n"));
+    }
+
+    #[test]
+    fn alumni_entries() {
+        let date = |date: &str| date.parse::<chrono::NaiveDate>().unwrap();
+        let member = TeamMember {
+            github: "a".to_owned(),
+            roles: vec!["cohost".to_owned()],
+            joined: Some(date("2024-01-01")),
+            left: None,
+            expires: None,
+        };
+        assert_eq!(
+            alumni_entry(&member, None, date("2025-01-01")),
+            "{ github = \"a\", roles = [\"cohost\"], joined = \"2024-01-01\", left = \"2025-01-01\" }"
+        );
+
+        // People who rejoined the team keep the joined date and the roles of their first time
+        let previous = TeamMember {
+            github: "a".to_owned(),
+            roles: vec!["lead".to_owned()],
+            joined: Some(date("2020-01-01")),
+            left: Some(date("2022-01-01")),
+            expires: None,
+        };
+        let entry = alumni_entry(&member, Some(&previous), date("2025-01-01"));
+        assert_eq!(
+            entry,
+            "{ github = \"a\", roles = [\"lead\", \"cohost\"], joined = \"2020-01-01\", left = \"2025-01-01\" }"
+        );
+        let team =
+            "[people]\nalumni = [\n    \"b\",\n    { github = \"a\", left = \"2022-01-01\" },\n]\n";
+        let edited = remove_from_array(team, "alumni", "a").unwrap();
+        assert_eq!(
+            append_to_array(&edited, "alumni", &entry).unwrap(),
+            format!("[people]\nalumni = [\n    \"b\",\n    {entry},\n]\n")
+        );
+    }
+}
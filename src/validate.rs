@@ -2,14 +2,20 @@ use crate::api::github::GitHubApi;
 use crate::api::zulip::ZulipApi;
 use crate::data::Data;
 use crate::schema::{
-    AllowedMergeApp, Bot, Email, Permissions, Repo, RepoPermission, Team, TeamKind, TeamPeople,
-    ZulipMember,
+    AllowedActions, AllowedMergeApp, Bot, Email, GitHubTeamPrivacy, ListReplyPreference,
+    Permissions, Person, PolicyRule, ProtectionTarget, Repo, RepoPermission, Team, TeamKind,
+    WEBHOOK_SECRET_ENV_PREFIX, ZulipMember,
 };
 use anyhow::{Context as _, Error, bail};
-use log::{error, warn};
+use futures_util::future::BoxFuture;
+use log::{error, info, warn};
 use regex::Regex;
-use std::collections::HashSet;
 use std::collections::hash_map::{Entry, HashMap};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use toml::Spanned;
+use toml::de::{DeTable, DeValue};
 
 macro_rules! checks {
     ($($f:ident,)*) => {
@@ -23,16 +29,18 @@ macro_rules! checks {
 }
 
 macro_rules! async_checks {
-    ($($f:ident,)*) => {
+    ($api:ty: $($f:ident,)*) => {
         [$(
-            Check {
-                f: Box::pin($f),
+            Check::<AsyncCheck<$api>> {
+                f: |data, api, errors| Box::pin($f(data, api, errors)),
                 name: stringify!($f)
             }
         ),*]
     }
 }
 
+type AsyncCheck<Api> = for<'a> fn(&'a Data, &'a Api, &'a mut Vec<String>) -> BoxFuture<'a, ()>;
+
 #[allow(clippy::type_complexity)]
 static CHECKS: &[Check<fn(&Data, &mut Vec<String>)>] = checks![
     validate_name_prefixes,
@@ -41,23 +49,33 @@ static CHECKS: &[Check<fn(&Data, &mut Vec<String>)>] = checks![
     validate_team_members,
     validate_duplicate_team_entries,
     validate_alumni,
+    validate_membership_dates,
+    validate_derived_teams,
+    validate_expirations,
     validate_archived_teams,
     validate_inactive_members,
     validate_list_email_addresses,
     validate_list_extra_people,
     validate_list_extra_teams,
     validate_list_addresses,
+    validate_list_reply_preferences,
+    validate_list_priorities,
     validate_people_addresses,
+    validate_people_details,
+    validate_duplicate_people,
     validate_duplicate_permissions,
     validate_permissions,
     validate_rfcbot_labels,
     validate_rfcbot_exclude_members,
     validate_team_names,
     validate_github_teams,
+    validate_github_team_maintainers,
+    validate_github_team_parents,
     validate_zulip_stream_name,
     validate_subteam_of_required,
     validate_unique_zulip_groups,
     validate_zulip_group_ids,
+    validate_zulip_role_ids,
     validate_zulip_group_extra_people,
     validate_unique_zulip_streams,
     validate_unique_zulip_user_ids,
@@ -67,12 +85,25 @@ static CHECKS: &[Check<fn(&Data, &mut Vec<String>)>] = checks![
     validate_zulip_stream_extra_people,
     validate_repos,
     validate_archived_repos,
+    validate_policies,
+    validate_privileged_access,
+    validate_naming_conventions,
+    validate_allowed_bots,
     validate_branch_protections,
     validate_environments,
+    validate_labels,
+    validate_topics,
+    validate_webhooks,
+    validate_autolinks,
+    validate_deploy_keys,
+    validate_actions_permissions,
+    validate_repo_templates,
+    validate_orgs,
     validate_trusted_publishing,
     validate_member_roles,
     validate_admin_access,
     validate_website,
+    validate_meetings,
 ];
 
 struct Check<F> {
@@ -80,7 +111,12 @@ struct Check<F> {
     name: &'static str,
 }
 
-pub(crate) async fn validate(data: &Data, strict: bool, skip: &[&str]) -> Result<(), Error> {
+pub(crate) async fn validate(
+    data: &Data,
+    strict: bool,
+    online: bool,
+    skip: &[&str],
+) -> Result<(), Error> {
     let mut errors = Vec::new();
 
     for check in CHECKS {
@@ -92,6 +128,32 @@ pub(crate) async fn validate(data: &Data, strict: bool, skip: &[&str]) -> Result
         (check.f)(data, &mut errors);
     }
 
+    if online {
+        validate_online(data, strict, skip, &mut errors).await?;
+    } else {
+        info!("skipped the checks relying on the GitHub and Zulip APIs, enable them with --online");
+    }
+
+    if !errors.is_empty() {
+        errors.sort();
+        errors.dedup_by(|a, b| a == b);
+
+        for err in &errors {
+            error!("validation error: {err}");
+        }
+
+        bail!("{} validation errors found", errors.len());
+    }
+
+    Ok(())
+}
+
+async fn validate_online(
+    data: &Data,
+    strict: bool,
+    skip: &[&str],
+    errors: &mut Vec<String>,
+) -> Result<(), Error> {
     let github = GitHubApi::new();
     if let Err(err) = github.require_auth() {
         if strict {
@@ -103,14 +165,18 @@ pub(crate) async fn validate(data: &Data, strict: bool, skip: &[&str]) -> Result
             warn!("cause: {err}");
         }
     } else {
-        let github_checks = async_checks!(validate_github_usernames,);
+        let github_checks = async_checks!(
+            GitHubApi: validate_github_usernames,
+            validate_ci_checks,
+            validate_branch_protection_patterns,
+        );
         for check in github_checks {
             if skip.contains(&check.name) {
                 warn!("skipped check: {}", check.name);
                 continue;
             }
 
-            (check.f)(data, &github, &mut errors).await;
+            (check.f)(data, &github, errors).await;
         }
     }
 
@@ -119,26 +185,15 @@ pub(crate) async fn validate(data: &Data, strict: bool, skip: &[&str]) -> Result
         warn!("couldn't perform checks relying on the Zulip API, some errors will not be detected");
         warn!("cause: {err}");
     } else {
-        let zulip_checks = async_checks!(validate_zulip_users,);
+        let zulip_checks = async_checks!(ZulipApi: validate_zulip_users,);
         for check in zulip_checks {
             if skip.contains(&check.name) {
                 warn!("skipped check: {}", check.name);
                 continue;
             }
 
-            (check.f)(data, &zulip, &mut errors).await;
-        }
-    }
-
-    if !errors.is_empty() {
-        errors.sort();
-        errors.dedup_by(|a, b| a == b);
-
-        for err in &errors {
-            error!("validation error: {err}");
+            (check.f)(data, &zulip, errors).await;
         }
-
-        bail!("{} validation errors found", errors.len());
     }
 
     Ok(())
@@ -185,42 +240,67 @@ fn validate_name_prefixes(data: &Data, errors: &mut Vec<String>) {
     });
 }
 
-/// Ensure `subteam-of` points to an existing team
+/// Ensure `subteam-of` points to an existing team, and that following it from any team leads
+/// to a top-level team without going around in a cycle
 fn validate_subteam_of(data: &Data, errors: &mut Vec<String>) {
-    wrapper(data.teams(), errors, |mut team, _| {
-        let mut visited = Vec::new();
-        while let Some(parent) = team.subteam_of() {
-            visited.push(team.name());
-
-            if visited.contains(&parent) {
-                bail!(
-                    "team `{parent}` is a subteam of itself: {} => {parent}",
-                    visited.join(" => "),
-                );
+    wrapper(data.teams(), errors, |team, _| {
+        let mut chain = vec![team.name()];
+        let mut current = team;
+        while let Some(parent) = current.subteam_of() {
+            if let Some(start) = chain.iter().position(|name| *name == parent) {
+                // The cycle is reported once, from its first team in alphabetical order, and
+                // not from the teams leading to it
+                let cycle = &chain[start..];
+                if start == 0 && cycle.iter().all(|name| *name >= team.name()) {
+                    bail!(
+                        "team `{parent}` is a subteam of itself: {} => {parent}",
+                        cycle.join(" => "),
+                    );
+                }
+                return Ok(());
             }
 
             let Some(parent) = data.team(parent) else {
                 bail!(
                     "the parent of team `{}` doesn't exist: `{}`",
-                    team.name(),
+                    current.name(),
                     parent,
                 );
             };
 
-            team = parent;
+            chain.push(parent.name());
+            current = parent;
+        }
+
+        // Teams without a parent that are not top-level are reported by
+        // `validate_subteam_of_required`, so only their subteams are reported here
+        let is_top_level = current.top_level() == Some(true)
+            || matches!(current.name(), "leadership-council" | "core");
+        if chain.len() > 1 && !is_top_level {
+            bail!(
+                "team `{}` is not part of a top-level team: {}",
+                team.name(),
+                chain.join(" => "),
+            );
         }
         Ok(())
     });
 }
 
-/// Ensure team leaders are part of the teams they lead
+/// Ensure team leaders are explicit members of the teams they lead, as the website lists leads
+/// among the explicit members. Expired memberships don't count, as they are not published.
 fn validate_team_leads(data: &Data, errors: &mut Vec<String>) {
     wrapper(data.teams(), errors, |team, errors| {
-        let members = team.members(data)?;
+        let members = team
+            .explicit_members()
+            .iter()
+            .filter(|member| !member.is_expired())
+            .map(|member| member.github.as_str())
+            .collect::<HashSet<_>>();
         wrapper(team.leads().iter(), errors, |lead, _| {
             if !members.contains(lead) {
                 bail!(
-                    "`{}` leads team `{}`, but is not a member of it",
+                    "`{}` leads team `{}`, but is not listed in its members",
                     lead,
                     team.name()
                 );
@@ -328,32 +408,14 @@ fn validate_alumni(data: &Data, errors: &mut Vec<String>) {
     // need `alumni = […]`. For these teams, the correct place to put alumni is
     // in the same team they're being included from.
     wrapper(data.teams(), errors, |team, _| {
-        // Exhaustive destructuring to ensure this code is touched if a new
-        // "include" settings is introduced.
-        let TeamPeople {
-            leads: _,
-            members,
-            alumni,
-            included_teams,
-            include_team_leads,
-            include_wg_leads,
-            include_project_group_leads,
-            include_all_team_members,
-            include_all_alumni,
-        } = team.raw_people();
-
-        if alumni.is_none() {
+        let people = team.raw_people();
+        if people.alumni.is_none() {
             let exempt_team_kind = match team.kind() {
                 TeamKind::MarkerTeam => true,
                 TeamKind::Team | TeamKind::WorkingGroup | TeamKind::ProjectGroup => false,
             };
-            let exempt_composition = members.is_empty() // intentionally not team.members(data).is_empty()
-                && (*include_team_leads
-                    || *include_wg_leads
-                    || *include_project_group_leads
-                    || *include_all_team_members
-                    || *include_all_alumni
-                    || !included_teams.is_empty());
+            let exempt_composition = people.members.is_empty() // intentionally not team.members(data).is_empty()
+                && (people.is_derived() || !people.included_teams.is_empty());
             let exempt = exempt_team_kind || exempt_composition;
             if !exempt {
                 let team_name = team.name();
@@ -364,6 +426,110 @@ fn validate_alumni(data: &Data, errors: &mut Vec<String>) {
     });
 }
 
+/// Ensure that the people of derived teams, like `all` or `leads`, are only changed in the teams
+/// they are computed from
+fn validate_derived_teams(data: &Data, errors: &mut Vec<String>) {
+    wrapper(data.teams(), errors, |team, _| {
+        let people = team.raw_people();
+        if !people.is_derived() {
+            return Ok(());
+        }
+        for (key, is_empty) in [
+            ("leads", people.leads.is_empty()),
+            ("members", people.members.is_empty()),
+            ("alumni", people.alumni.as_ref().is_none_or(Vec::is_empty)),
+        ] {
+            if !is_empty {
+                bail!(
+                    "team `{}` is derived from other teams, so its `{key}` must be empty: change \
+                     the teams its people come from instead",
+                    team.name()
+                );
+            }
+        }
+        Ok(())
+    });
+}
+
+/// Ensure that only alumni have a date they left the team at, and that it's after they joined
+fn validate_membership_dates(data: &Data, errors: &mut Vec<String>) {
+    wrapper(
+        data.teams().chain(data.archived_teams()),
+        errors,
+        |team, errors| {
+            wrapper(team.explicit_members().iter(), errors, |member, _| {
+                if member.left.is_some() {
+                    bail!(
+                        "member `{}` of team `{}` has a `left` date, but only alumni can have one",
+                        member.github,
+                        team.name()
+                    );
+                }
+                Ok(())
+            });
+            wrapper(team.explicit_alumni().iter(), errors, |alum, _| {
+                if alum.expires.is_some() {
+                    bail!(
+                        "alum `{}` of team `{}` has an `expires` date, but only members can have one",
+                        alum.github,
+                        team.name()
+                    );
+                }
+                if let (Some(joined), Some(left)) = (alum.joined, alum.left)
+                    && left < joined
+                {
+                    bail!(
+                        "alum `{}` of team `{}` left ({left}) before joining ({joined})",
+                        alum.github,
+                        team.name()
+                    );
+                }
+                Ok(())
+            });
+            Ok(())
+        },
+    );
+}
+
+/// Warn about team memberships and repository access that expire soon or have expired, as
+/// expired ones are left out of the static API and removed by the synchronization
+fn validate_expirations(data: &Data, _errors: &mut Vec<String>) {
+    let warn_about = |what: String, expires: Option<chrono::NaiveDate>| {
+        let Some(expires) = expires else {
+            return;
+        };
+        let today = chrono::Utc::now().date_naive();
+        if today >= expires {
+            warn!("{what} expired on {expires}, it should be removed from the data");
+        } else if expires - today <= chrono::Duration::days(EXPIRATION_WARNING_DAYS) {
+            warn!("{what} expires on {expires}");
+        }
+    };
+    for team in data.teams() {
+        for member in team.explicit_members() {
+            warn_about(
+                format!(
+                    "the membership of `{}` in team `{}`",
+                    member.github,
+                    team.name()
+                ),
+                member.expires,
+            );
+        }
+    }
+    for repo in data.repos() {
+        for (name, access) in &repo.access.individuals {
+            warn_about(
+                format!("the access of `{name}` to repo {}/{}", repo.org, repo.name),
+                access.expires,
+            );
+        }
+    }
+}
+
+/// How long before an expiration it is warned about
+const EXPIRATION_WARNING_DAYS: i64 = 14;
+
 fn validate_archived_teams(data: &Data, errors: &mut Vec<String>) {
     wrapper(data.archived_teams(), errors, |team, _| {
         if !team.members(data)?.is_empty() {
@@ -393,6 +559,10 @@ fn validate_inactive_members(data: &Data, errors: &mut Vec<String>) {
             for member in members {
                 referenced_members.insert(member);
             }
+            // Expired members are not members anymore, but they are still in the data
+            for person in team.explicit_members() {
+                referenced_members.insert(person.github.as_str());
+            }
             for person in team.explicit_alumni() {
                 referenced_members.insert(person.github.as_str());
             }
@@ -516,7 +686,8 @@ fn validate_list_extra_teams(data: &Data, errors: &mut Vec<String>) {
 
 /// Ensure the list addresses are correct
 fn validate_list_addresses(data: &Data, errors: &mut Vec<String>) {
-    let email_re = Regex::new(r"^[a-zA-Z0-9_\.-]+@([a-zA-Z0-9_\.-]+)$").unwrap();
+    // `*@domain` is the catch-all list of the domain
+    let email_re = Regex::new(r"^(?:\*|[a-zA-Z0-9_\.-]+)@([a-zA-Z0-9_\.-]+)$").unwrap();
     let config = data.config().allowed_mailing_lists_domains();
     wrapper(data.teams(), errors, |team, errors| {
         wrapper(team.raw_lists().iter(), errors, |list, _| {
@@ -533,9 +704,85 @@ fn validate_list_addresses(data: &Data, errors: &mut Vec<String>) {
     });
 }
 
-/// Ensure people email addresses are correct
+/// Ensure replies to the emails of lists can go where the lists ask for
+fn validate_list_reply_preferences(data: &Data, errors: &mut Vec<String>) {
+    wrapper(data.teams(), errors, |team, errors| {
+        wrapper(team.raw_lists().iter(), errors, |list, _| {
+            // The email providers forward emails unchanged, so they can't set `Reply-To`
+            if list.reply_preference != ListReplyPreference::Sender {
+                bail!(
+                    "the list `{}` asks for replies to go to the list, but the emails are \
+                     forwarded unchanged, so `reply-preference` can only be `sender`",
+                    list.address
+                );
+            }
+            Ok(())
+        });
+        Ok(())
+    });
+}
+
+/// Ensure the priorities of the lists are not negative
+fn validate_list_priorities(data: &Data, errors: &mut Vec<String>) {
+    wrapper(data.teams(), errors, |team, errors| {
+        wrapper(team.raw_lists().iter(), errors, |list, _| {
+            if list.priority < 0 {
+                bail!(
+                    "the priority of the list `{}` must not be negative, found {}",
+                    list.address,
+                    list.priority
+                );
+            }
+            Ok(())
+        });
+        Ok(())
+    });
+}
+
+/// Ensure the optional details people publish about themselves are well-formed
+fn validate_people_details(data: &Data, errors: &mut Vec<String>) {
+    wrapper(data.people(), errors, |person, _| {
+        if let Some(timezone) = person.timezone()
+            && timezone.parse::<chrono_tz::Tz>().is_err()
+        {
+            bail!(
+                "the time zone of `{}` must be an IANA time zone name like `Europe/Paris`: {}",
+                person.github(),
+                timezone
+            );
+        }
+        if let Some(pronouns) = person.pronouns() {
+            let pronouns = pronouns.trim();
+            if pronouns.is_empty()
+                || pronouns.len() > MAX_PRONOUNS_LEN
+                || pronouns.chars().any(char::is_control)
+            {
+                bail!(
+                    "the pronouns of `{}` must be a single line of at most {MAX_PRONOUNS_LEN} \
+                     characters, like `they/them`",
+                    person.github()
+                );
+            }
+        }
+        Ok(())
+    });
+}
+
+const MAX_PRONOUNS_LEN: usize = 40;
+
+/// Ensure people email addresses and websites are correct
 fn validate_people_addresses(data: &Data, errors: &mut Vec<String>) {
     wrapper(data.people(), errors, |person, _| {
+        if let Some(website) = person.website()
+            && !reqwest::Url::parse(website)
+                .is_ok_and(|url| url.scheme() == "https" && url.host().is_some())
+        {
+            bail!(
+                "the website of `{}` must be an https:// URL: {}",
+                person.github(),
+                website
+            );
+        }
         if let Email::Present(email) = person.email()
             && !email.contains('@')
         {
@@ -545,6 +792,61 @@ fn validate_people_addresses(data: &Data, errors: &mut Vec<String>) {
     });
 }
 
+/// Ensure no two people have the same GitHub ID, email address or GitHub username, including
+/// their former ones
+fn validate_duplicate_people(data: &Data, errors: &mut Vec<String>) {
+    let mut people = data.people().collect::<Vec<_>>();
+    people.sort_by_key(|person| person.github());
+    let location = |person: &Person| {
+        data.person_path(person.github())
+            .map(|path| path.display().to_string())
+            .unwrap_or_default()
+    };
+
+    let mut github_ids = HashMap::new();
+    let mut emails = HashMap::new();
+    // GitHub usernames are case insensitive
+    let mut usernames = people
+        .iter()
+        .map(|person| (person.github().to_lowercase(), *person))
+        .collect::<HashMap<_, _>>();
+    wrapper(people.into_iter(), errors, |person, errors| {
+        for former in person.former_github_usernames() {
+            if let Some(other) = usernames.insert(former.to_lowercase(), person) {
+                errors.push(format!(
+                    "former GitHub username `{former}` of `{}` ({}) is also used by `{}` ({})",
+                    person.github(),
+                    location(person),
+                    other.github(),
+                    location(other),
+                ));
+            }
+        }
+        if let Some(other) = github_ids.insert(person.github_id(), person) {
+            errors.push(format!(
+                "GitHub ID {} is used by both `{}` ({}) and `{}` ({})",
+                person.github_id(),
+                other.github(),
+                location(other),
+                person.github(),
+                location(person),
+            ));
+        }
+        if let Email::Present(email) = person.email()
+            && let Some(other) = emails.insert(email.to_lowercase(), person)
+        {
+            bail!(
+                "email address {email} is used by both `{}` ({}) and `{}` ({})",
+                other.github(),
+                location(other),
+                person.github(),
+                location(person),
+            );
+        }
+        Ok(())
+    });
+}
+
 /// Ensure members of teams with permissions don't explicitly have those permissions
 fn validate_duplicate_permissions(data: &Data, errors: &mut Vec<String>) {
     wrapper(data.teams(), errors, |team, errors| {
@@ -657,13 +959,22 @@ fn validate_github_teams(data: &Data, errors: &mut Vec<String>) {
                         team.name()
                     );
                 }
-                if let Some(other) = found.insert((gh_team.org, gh_team.name), team.name()) {
+                // Names of GitHub teams are case insensitive
+                let key = (gh_team.org, gh_team.name.to_lowercase());
+                if let Some(other) = found.insert(key, team.name()) {
+                    let location = |team: &str| {
+                        data.team_path(team)
+                            .map(|path| path.display().to_string())
+                            .unwrap_or_default()
+                    };
                     bail!(
-                        "GitHub team `{}/{}` is defined for both the `{}` and `{}` teams",
+                        "GitHub team `{}/{}` is defined for both the `{}` ({}) and `{}` ({}) teams",
                         gh_team.org,
                         gh_team.name,
                         team.name(),
-                        other
+                        location(team.name()),
+                        other,
+                        location(other),
                     );
                 }
                 Ok(())
@@ -675,99 +986,474 @@ fn validate_github_teams(data: &Data, errors: &mut Vec<String>) {
 
 /// Ensure there are no misspelled GitHub account names
 async fn validate_github_usernames(data: &Data, github: &GitHubApi, errors: &mut Vec<String>) {
-    let people = data
-        .people()
-        .map(|p| (p.github_id(), p))
-        .collect::<HashMap<_, _>>();
-    match github
-        .usernames(&people.keys().cloned().collect::<Vec<_>>())
-        .await
-    {
-        Ok(res) => wrapper(res.iter(), errors, |(id, name), _| {
-            let original = people[id].github();
-            if original != name {
-                bail!("GitHub user `{}` changed username to `{}`", original, name);
-            }
-            Ok(())
+    match renamed_github_users(data, github).await {
+        Ok(renames) => wrapper(renames.iter(), errors, |(old, new), _| {
+            bail!(
+                "GitHub user `{old}` changed username to `{new}`, \
+                 run `check --online --fix` to update the data"
+            );
         }),
         Err(err) => errors.push(format!("couldn't verify GitHub usernames: {err}")),
     }
 }
 
-/// Ensure the user doens't put an URL as the Zulip stream name.
-fn validate_zulip_stream_name(data: &Data, errors: &mut Vec<String>) {
-    wrapper(data.teams(), errors, |team, _| {
-        if let Some(stream) = team.website_data().and_then(|ws| ws.zulip_stream())
-            && stream.starts_with("https://")
-        {
-            bail!(
-                "the zulip stream name of the team `{}` is a link: only the name is required",
-                team.name()
-            );
+/// Warn about the required CI checks of branch protections that were not reported on the latest
+/// commit of their branch, as a check that never reports prevents merging any PR.
+///
+/// Only protections of a single branch are checked, patterns with wildcards match branches that
+/// may not exist yet.
+async fn validate_ci_checks(data: &Data, github: &GitHubApi, _errors: &mut Vec<String>) {
+    for repo in data.repos() {
+        for protection in &repo.branch_protections {
+            if protection.target != ProtectionTarget::Branch
+                || protection.ci_checks.is_empty()
+                || protection.pattern.contains(['*', '?', '['])
+            {
+                continue;
+            }
+            let reported = match github
+                .reported_checks(&repo.org, &repo.name, &protection.pattern)
+                .await
+            {
+                Ok(reported) => reported,
+                Err(err) => {
+                    warn!(
+                        "couldn't fetch the CI checks of branch `{}` of repo {}/{}: {err}",
+                        protection.pattern, repo.org, repo.name
+                    );
+                    continue;
+                }
+            };
+            for check in &protection.ci_checks {
+                if !reported.contains(check) {
+                    warn!(
+                        "the CI check `{check}` required on branch `{}` of repo {}/{} was not \
+                         reported on its latest commit, PRs may not be mergeable",
+                        protection.pattern, repo.org, repo.name
+                    );
+                }
+            }
         }
-        Ok(())
-    })
+    }
 }
 
-/// Ensure teams have a parent team.
-fn validate_subteam_of_required(data: &Data, errors: &mut Vec<String>) {
-    wrapper(data.teams(), errors, |team, _| {
-        let top_level = team.top_level().unwrap_or(false);
-        if top_level && team.subteam_of().is_some() {
-            bail!(
-                "team `{}` specifies both top-level=true and subteam-of, \
-                it should only specify one or the other",
-                team.name()
-            );
+/// Warn about branch protections whose pattern doesn't match any branch, which is likely a typo
+/// or a leftover, and about repositories whose default branch isn't protected while other
+/// branches are.
+async fn validate_branch_protection_patterns(
+    data: &Data,
+    github: &GitHubApi,
+    _errors: &mut Vec<String>,
+) {
+    for repo in data.repos() {
+        let patterns = repo
+            .branch_protections
+            .iter()
+            .filter(|protection| protection.target == ProtectionTarget::Branch)
+            .map(|protection| protection.pattern.as_str())
+            .collect::<Vec<_>>();
+        if patterns.is_empty() {
+            continue;
         }
-        if top_level && team.kind() != TeamKind::Team {
-            bail!(
-                "team `{}` is top-level, but is a `{}` team kind, \
-                 it must be a normal team (don't specify \"kind\")",
-                team.name(),
-                team.kind()
-            );
+        let branches = match github.branches(&repo.org, &repo.name).await {
+            Ok(branches) => branches,
+            Err(err) => {
+                warn!(
+                    "couldn't fetch the branches of repo {}/{}: {err}",
+                    repo.org, repo.name
+                );
+                continue;
+            }
+        };
+        for pattern in &patterns {
+            if !branches
+                .branches
+                .iter()
+                .any(|branch| branch_pattern_matches(pattern, branch))
+            {
+                warn!(
+                    "the branch protection pattern `{pattern}` of repo {}/{} doesn't match any \
+                     branch",
+                    repo.org, repo.name
+                );
+            }
         }
-        if team.kind() != TeamKind::MarkerTeam
-            && team.subteam_of().is_none()
-            && !matches!(team.name(), "leadership-council" | "core")
-            && !top_level
+        if let Some(default_branch) = &branches.default_branch
+            && !patterns
+                .iter()
+                .any(|pattern| branch_pattern_matches(pattern, default_branch))
         {
-            bail!(
-                "team `{}` must specify `subteam-of` or top-level=true",
-                team.name()
+            warn!(
+                "the default branch `{default_branch}` of repo {}/{} isn't covered by any \
+                 branch protection",
+                repo.org, repo.name
             );
         }
-        Ok(())
-    })
+    }
 }
 
-/// Ensure every member of a team that has a Zulip group has a Zulip id
-async fn validate_zulip_users(data: &Data, zulip: &ZulipApi, errors: &mut Vec<String>) {
-    let by_id = match zulip.get_users(false).await {
-        Ok(u) => u.iter().map(|u| u.user_id).collect::<HashSet<_>>(),
-        Err(err) => {
-            errors.push(format!("couldn't verify Zulip users: {err}"));
-            return;
-        }
-    };
-    let zulip_groups = match data.zulip_groups() {
-        Ok(zgs) => zgs,
-        Err(err) => {
-            errors.push(format!("couldn't get all the Zulip groups: {err}"));
-            return;
+/// Whether a branch matches a protection pattern, where `*` matches any characters except `/`,
+/// `**` matches any characters and `?` matches one character except `/`.
+///
+/// Character classes are not supported, so patterns containing them match every branch.
+fn branch_pattern_matches(pattern: &str, branch: &str) -> bool {
+    fn matches(pattern: &[u8], branch: &[u8]) -> bool {
+        match pattern {
+            [] => branch.is_empty(),
+            [b'*', b'*', rest @ ..] => (0..=branch.len()).any(|i| matches(rest, &branch[i..])),
+            [b'*', rest @ ..] => {
+                let segment = branch
+                    .iter()
+                    .position(|&c| c == b'/')
+                    .unwrap_or(branch.len());
+                (0..=segment).any(|i| matches(rest, &branch[i..]))
+            }
+            [b'?', rest @ ..] => {
+                matches!(branch.first(), Some(&c) if c != b'/') && matches(rest, &branch[1..])
+            }
+            [c, rest @ ..] => branch.first() == Some(c) && matches(rest, &branch[1..]),
         }
-    };
-    wrapper(zulip_groups.iter(), errors, |(group_name, group), _| {
-        let missing_members = group
-            .members()
-            .iter()
-            .filter_map(|m| match m {
-                ZulipMember::MemberWithId { github, zulip_id } if !by_id.contains(zulip_id) => {
-                    Some(github.clone())
-                }
-                ZulipMember::JustId(zulip_id) if !by_id.contains(zulip_id) => {
-                    Some(format!("ID: {zulip_id}"))
+    }
+    pattern.contains('[') || matches(pattern.as_bytes(), branch.as_bytes())
+}
+
+/// The people whose GitHub username doesn't match their GitHub ID anymore, as pairs of the
+/// stored username and the current one
+async fn renamed_github_users(
+    data: &Data,
+    github: &GitHubApi,
+) -> Result<Vec<(String, String)>, Error> {
+    let people = data
+        .people()
+        .map(|p| (p.github_id(), p.github()))
+        .collect::<HashMap<_, _>>();
+    let usernames = github
+        .usernames(&people.keys().cloned().collect::<Vec<_>>())
+        .await?;
+    let mut renames = usernames
+        .into_iter()
+        .filter(|(id, name)| people[id] != name)
+        .map(|(id, name)| (people[&id].to_string(), name))
+        .collect::<Vec<_>>();
+    renames.sort();
+    Ok(renames)
+}
+
+/// Replace the stored usernames of the people who changed their GitHub username, in their own
+/// file and wherever they are referenced.
+pub(crate) async fn fix_github_usernames(data_dir: &Path, data: &Data) -> Result<(), Error> {
+    let github = GitHubApi::new();
+    github.require_auth()?;
+    let renames = renamed_github_users(data, &github).await?;
+    if renames.is_empty() {
+        info!("no GitHub user changed username");
+        return Ok(());
+    }
+    rename_github_users(data_dir, data, &renames)
+}
+
+/// Rename people, given as pairs of their old and new GitHub usernames, in their own file and
+/// wherever they are referenced. Their old username is recorded in their file.
+pub(crate) fn rename_github_users(
+    data_dir: &Path,
+    data: &Data,
+    renames: &[(String, String)],
+) -> Result<(), Error> {
+    for (old, new) in renames {
+        if data.person(new).is_some() {
+            bail!("GitHub user `{old}` changed username to `{new}`, who is already in the data");
+        }
+    }
+
+    let mut files = Vec::new();
+    for dir in ["people", "teams", "repos"] {
+        collect_toml_files(&data_dir.join(dir), &mut files)?;
+    }
+    if data_dir.join("templates").is_dir() {
+        collect_toml_files(&data_dir.join("templates"), &mut files)?;
+    }
+    for file in files {
+        let contents = std::fs::read_to_string(&file)
+            .with_context(|| format!("failed to read {}", file.display()))?;
+        let mut fixed = contents.clone();
+        for (old, new) in renames {
+            fixed = rename_github_user(&fixed, old, new)
+                .with_context(|| format!("failed to parse {}", file.display()))?;
+        }
+        if fixed != contents {
+            std::fs::write(&file, fixed)
+                .with_context(|| format!("failed to write {}", file.display()))?;
+        }
+    }
+
+    for (old, new) in renames {
+        let old_path = data_dir.join("people").join(format!("{old}.toml"));
+        if old_path.exists() {
+            let new_path = data_dir.join("people").join(format!("{new}.toml"));
+            std::fs::rename(&old_path, &new_path).with_context(|| {
+                format!(
+                    "failed to rename {} to {}",
+                    old_path.display(),
+                    new_path.display()
+                )
+            })?;
+            let contents = std::fs::read_to_string(&new_path)
+                .with_context(|| format!("failed to read {}", new_path.display()))?;
+            std::fs::write(&new_path, record_former_github_username(&contents, old))
+                .with_context(|| format!("failed to write {}", new_path.display()))?;
+        }
+        info!("renamed GitHub user `{old}` to `{new}`");
+    }
+    Ok(())
+}
+
+fn collect_toml_files(dir: &Path, files: &mut Vec<PathBuf>) -> Result<(), Error> {
+    for entry in
+        std::fs::read_dir(dir).with_context(|| format!("failed to read {}", dir.display()))?
+    {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_toml_files(&path, files)?;
+        } else if path.extension().is_some_and(|ext| ext == "toml") {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Replace a username in a TOML file of the data, only where people are referenced: the
+/// `github` key of people, the people of teams, and the individuals with access to repositories
+fn rename_github_user(contents: &str, old: &str, new: &str) -> Result<String, Error> {
+    let document = DeTable::parse(contents)?;
+    let document = document.get_ref();
+    let mut spans = Vec::new();
+
+    if let Some(github) = document.get("github") {
+        username_span(github, old, &mut spans);
+    }
+    if let Some(people) = table(document.get("people")) {
+        for key in ["leads", "members", "alumni"] {
+            username_spans(people.get(key), old, &mut spans);
+        }
+    }
+    for github in tables(document.get("github")) {
+        username_spans(github.get("maintainers"), old, &mut spans);
+    }
+    if let Some(rfcbot) = table(document.get("rfcbot")) {
+        username_spans(rfcbot.get("exclude-members"), old, &mut spans);
+    }
+    for list in tables(document.get("lists")) {
+        username_spans(list.get("extra-people"), old, &mut spans);
+    }
+    let zulip = tables(document.get("zulip-groups")).chain(tables(document.get("zulip-streams")));
+    for zulip in zulip {
+        username_spans(zulip.get("extra-people"), old, &mut spans);
+        username_spans(zulip.get("excluded-people"), old, &mut spans);
+    }
+    if let Some(access) = table(document.get("access"))
+        && let Some(individuals) = table(access.get("individuals"))
+    {
+        spans.extend(
+            individuals
+                .keys()
+                .filter(|person| person.get_ref() == old)
+                .map(Spanned::span),
+        );
+    }
+
+    // Replaced from the end, so that the spans left to replace stay valid
+    spans.sort_by_key(|span| std::cmp::Reverse(span.start));
+    let mut renamed = contents.to_string();
+    for span in spans {
+        // Keep the quotes of the username, if any
+        let quote = &contents[span.start..span.start + 1];
+        let replacement = match quote {
+            "\"" | "'" => format!("{quote}{new}{quote}"),
+            _ => new.to_string(),
+        };
+        renamed.replace_range(span, &replacement);
+    }
+    Ok(renamed)
+}
+
+fn table<'a, 'i>(value: Option<&'a Spanned<DeValue<'i>>>) -> Option<&'a DeTable<'i>> {
+    match value.map(Spanned::get_ref) {
+        Some(DeValue::Table(table)) => Some(table),
+        _ => None,
+    }
+}
+
+/// The tables of an array of tables, like `[[lists]]`
+fn tables<'a, 'i>(
+    value: Option<&'a Spanned<DeValue<'i>>>,
+) -> impl Iterator<Item = &'a DeTable<'i>> {
+    let items = match value.map(Spanned::get_ref) {
+        Some(DeValue::Array(items)) => items.as_ref(),
+        _ => &[],
+    };
+    items.iter().filter_map(|item| table(Some(item)))
+}
+
+/// Record the span of a value if it's the username
+fn username_span(value: &Spanned<DeValue<'_>>, old: &str, spans: &mut Vec<Range<usize>>) {
+    if let DeValue::String(name) = value.get_ref()
+        && name == old
+    {
+        spans.push(value.span());
+    }
+}
+
+/// Record the spans of the username in a list of people, given either by their username or as
+/// tables with a `github` key
+fn username_spans(value: Option<&Spanned<DeValue<'_>>>, old: &str, spans: &mut Vec<Range<usize>>) {
+    let Some(DeValue::Array(people)) = value.map(Spanned::get_ref) else {
+        return;
+    };
+    for person in people {
+        match table(Some(person)) {
+            Some(person) => {
+                if let Some(github) = person.get("github") {
+                    username_span(github, old, spans);
+                }
+            }
+            None => username_span(person, old, spans),
+        }
+    }
+}
+
+/// Add a username to the `former-github-usernames` of a person file, creating the list after the
+/// current username if needed
+fn record_former_github_username(contents: &str, old: &str) -> String {
+    let has_list = contents
+        .lines()
+        .any(|line| line.starts_with("former-github-usernames"));
+    let mut recorded = String::with_capacity(contents.len());
+    for line in contents.split_inclusive('\n') {
+        if has_list
+            && line.starts_with("former-github-usernames")
+            && let Some(end) = line.rfind(']')
+        {
+            let items = line[..end].trim_end();
+            let separator = if items.ends_with('[') { "" } else { ", " };
+            recorded.push_str(&format!("{items}{separator}\"{old}\""));
+            recorded.push_str(&line[end..]);
+            continue;
+        }
+        recorded.push_str(line);
+        if !has_list && line.starts_with("github =") {
+            recorded.push_str(&format!("former-github-usernames = [\"{old}\"]\n"));
+        }
+    }
+    recorded
+}
+
+/// Ensure the user doens't put an URL as the Zulip stream name.
+fn validate_zulip_stream_name(data: &Data, errors: &mut Vec<String>) {
+    wrapper(data.teams(), errors, |team, _| {
+        if let Some(stream) = team.website_data().and_then(|ws| ws.zulip_stream())
+            && stream.starts_with("https://")
+        {
+            bail!(
+                "the zulip stream name of the team `{}` is a link: only the name is required",
+                team.name()
+            );
+        }
+        Ok(())
+    })
+}
+
+/// Ensure teams have a parent team.
+fn validate_subteam_of_required(data: &Data, errors: &mut Vec<String>) {
+    wrapper(data.teams(), errors, |team, _| {
+        let top_level = team.top_level().unwrap_or(false);
+        if top_level && team.subteam_of().is_some() {
+            bail!(
+                "team `{}` specifies both top-level=true and subteam-of, \
+                it should only specify one or the other",
+                team.name()
+            );
+        }
+        if top_level && team.kind() != TeamKind::Team {
+            bail!(
+                "team `{}` is top-level, but is a `{}` team kind, \
+                 it must be a normal team (don't specify \"kind\")",
+                team.name(),
+                team.kind()
+            );
+        }
+        if team.kind() != TeamKind::MarkerTeam
+            && team.subteam_of().is_none()
+            && !matches!(team.name(), "leadership-council" | "core")
+            && !top_level
+        {
+            bail!(
+                "team `{}` must specify `subteam-of` or top-level=true",
+                team.name()
+            );
+        }
+        Ok(())
+    })
+}
+
+/// Ensure the Zulip IDs of people exist, and every member of a team that has a Zulip group has
+/// the Zulip ID of an active account
+async fn validate_zulip_users(data: &Data, zulip: &ZulipApi, errors: &mut Vec<String>) {
+    let users = match zulip.get_users(false).await {
+        Ok(u) => u
+            .iter()
+            .map(|u| (u.user_id, u.is_active))
+            .collect::<HashMap<_, _>>(),
+        Err(err) => {
+            errors.push(format!("couldn't verify Zulip users: {err}"));
+            return;
+        }
+    };
+
+    // People who left Zulip can keep their ID, as long as they are not in a Zulip group
+    let mut deactivated = Vec::new();
+    wrapper(data.people(), errors, |person, _| {
+        if let Some(zulip_id) = person.zulip_id() {
+            match users.get(&zulip_id) {
+                None => bail!(
+                    "`{}` has the Zulip ID {zulip_id}, which doesn't exist on Zulip",
+                    person.github()
+                ),
+                Some(false) => deactivated.push(person.github()),
+                Some(true) => {}
+            }
+        }
+        Ok(())
+    });
+    if !deactivated.is_empty() {
+        deactivated.sort_unstable();
+        warn!(
+            "the Zulip accounts of these people are deactivated: {}",
+            deactivated.join(", ")
+        );
+    }
+
+    let by_id = users
+        .into_iter()
+        .filter(|(_, active)| *active)
+        .map(|(id, _)| id)
+        .collect::<HashSet<_>>();
+    let zulip_groups = match data.zulip_groups() {
+        Ok(zgs) => zgs,
+        Err(err) => {
+            errors.push(format!("couldn't get all the Zulip groups: {err}"));
+            return;
+        }
+    };
+    wrapper(zulip_groups.iter(), errors, |(group_name, group), _| {
+        let missing_members = group
+            .members()
+            .iter()
+            .filter_map(|m| match m {
+                ZulipMember::MemberWithId { github, zulip_id } if !by_id.contains(zulip_id) => {
+                    Some(github.clone())
+                }
+                ZulipMember::JustId(zulip_id) if !by_id.contains(zulip_id) => {
+                    Some(format!("ID: {zulip_id}"))
                 }
                 ZulipMember::MemberWithoutId { github } => Some(github.clone()),
                 _ => None,
@@ -775,7 +1461,7 @@ async fn validate_zulip_users(data: &Data, zulip: &ZulipApi, errors: &mut Vec<St
             .collect::<HashSet<_>>();
         if !missing_members.is_empty() {
             bail!(
-                "the \"{}\" Zulip group includes members who don't appear on Zulip: {}",
+                "the \"{}\" Zulip group includes members without an active Zulip account: {}",
                 group_name,
                 missing_members.into_iter().collect::<Vec<_>>().join(", ")
             );
@@ -858,7 +1544,8 @@ fn validate_zulip_group_ids(data: &Data, errors: &mut Vec<String>) {
             return Ok(());
         }
 
-        for group in groups {
+        // Alumni without a Zulip id are left out of the alumni groups
+        for group in groups.iter().filter(|g| !g.is_alumni_group()) {
             wrapper(group.members().iter(), errors, |member, _| {
                 match member {
                     ZulipMember::MemberWithId { .. } | ZulipMember::JustId(_) => {
@@ -886,6 +1573,27 @@ fn validate_zulip_group_ids(data: &Data, errors: &mut Vec<String>) {
     });
 }
 
+/// Ensure members of teams with a Zulip role have a Zulip id
+fn validate_zulip_role_ids(data: &Data, errors: &mut Vec<String>) {
+    wrapper(data.teams(), errors, |team, errors| {
+        if team.zulip_role().is_none() {
+            return Ok(());
+        }
+        wrapper(team.members(data)?.into_iter(), errors, |member, _| {
+            if let Some(person) = data.person(member)
+                && person.zulip_id().is_none()
+            {
+                bail!(
+                    "person `{member}` in '{}' is given a Zulip role but has no Zulip id",
+                    team.name()
+                );
+            }
+            Ok(())
+        });
+        Ok(())
+    });
+}
+
 /// Ensure team members in Zulip streams have a Zulip id
 fn validate_zulip_stream_ids(data: &Data, errors: &mut Vec<String>) {
     wrapper(data.teams(), errors, |team, errors| {
@@ -995,42 +1703,743 @@ fn validate_zulip_stream_extra_people(data: &Data, errors: &mut Vec<String>) {
                     );
                 }
             }
-            Ok(())
-        });
+            Ok(())
+        });
+        Ok(())
+    });
+}
+
+/// Ensure repos reference valid teams and that they are unique
+fn validate_repos(data: &Data, errors: &mut Vec<String>) {
+    let allowed_orgs = data.config().allowed_github_orgs();
+    // Names of repositories are case insensitive on GitHub
+    let mut repo_map = HashMap::new();
+    let mut github_ids = HashMap::new();
+
+    let repos = (data.repos().map(|repo| (repo, false)))
+        .chain(data.archived_repos().map(|repo| (repo, true)));
+    wrapper(repos, errors, |(repo, archived), _| {
+        let key = format!("{}/{}", repo.org, repo.name).to_lowercase();
+        if let Some((other, other_archived)) = repo_map.insert(key, (repo, archived)) {
+            bail!(
+                "The repo {}/{} is duplicated: it is defined in both {} and {}",
+                repo.org,
+                repo.name,
+                repo_location(other, other_archived),
+                repo_location(repo, archived),
+            );
+        }
+
+        if let Some(github_id) = repo.github_id
+            && let Some(other) = github_ids.insert(github_id, repo)
+        {
+            bail!(
+                "The repos {}/{} and {}/{} have the same GitHub ID {github_id}",
+                other.org,
+                other.name,
+                repo.org,
+                repo.name,
+            );
+        }
+
+        if !allowed_orgs.contains(&repo.org) {
+            bail!(
+                "The repo '{}' is in an invalid org '{}'",
+                repo.name,
+                repo.org
+            );
+        }
+        let merge_methods = [
+            repo.allow_squash_merge,
+            repo.allow_merge_commit,
+            repo.allow_rebase_merge,
+        ];
+        if merge_methods.iter().all(|allowed| *allowed == Some(false)) {
+            bail!(
+                "The repo {}/{} must allow at least one merge method",
+                repo.org,
+                repo.name
+            );
+        }
+        for team_name in repo.access.teams.keys() {
+            check_team_access(data, repo, team_name).with_context(|| {
+                format!("Access for the repo {}/{} is invalid", repo.org, repo.name)
+            })?;
+        }
+
+        for name in repo.access.individuals.keys() {
+            if data.person(name).is_none() {
+                bail!(
+                    "Access for the repo {}/{} is invalid: '{}' is not the name of a person in the team repo",
+                    repo.org,
+                    repo.name,
+                    name
+                );
+            }
+        }
+
+        let custom_roles = data.config().custom_repo_roles(&repo.org);
+        let permissions = (repo.access.teams.values())
+            .chain(repo.access.individuals.values().map(|a| &a.permission));
+        for permission in permissions {
+            if let RepoPermission::Custom(role) = permission
+                && !custom_roles.is_some_and(|roles| roles.contains(role))
+            {
+                bail!(
+                    "Access for the repo {}/{} is invalid: the custom role '{}' is not listed for the org in the `custom-repo-roles` of config.toml",
+                    repo.org,
+                    repo.name,
+                    role
+                );
+            }
+        }
+        Ok(())
+    });
+}
+
+fn check_team_access(data: &Data, repo: &Repo, team_name: &str) -> anyhow::Result<()> {
+    let team = data
+        .team(team_name)
+        .with_context(|| format!("The file 'teams/{team_name}.toml' does not exist."))?;
+    if data.is_team_archived(team.name(), &repo.org) {
+        bail!(
+            "The team '{}/{team_name}' is archived. Please remove the team access from the `[access.teams]` section of the repo",
+            repo.org
+        );
+    }
+
+    let are_gh_teams_in_org = team.github_teams(data)?.iter().any(|t| t.org == repo.org);
+    anyhow::ensure!(
+        are_gh_teams_in_org,
+        "The team '{team_name}' does not have any GitHub teams in the org '{}'.",
+        repo.org
+    );
+    Ok(())
+}
+
+/// The file a repo is defined in, relative to the data directory
+fn repo_location(repo: &Repo, archived: bool) -> String {
+    let dir = if archived { "repos/archive" } else { "repos" };
+    format!("{dir}/{}/{}.toml", repo.org, repo.name)
+}
+
+/// Ensure repos follow the org-wide policies of `config/policies.toml`
+fn validate_policies(data: &Data, errors: &mut Vec<String>) {
+    let repos = || {
+        (data.repos().map(|repo| (repo, false)))
+            .chain(data.archived_repos().map(|repo| (repo, true)))
+    };
+    wrapper(data.policies().iter(), errors, |policy, errors| {
+        for exception in &policy.exceptions {
+            if !repos().any(|(repo, _)| *exception == format!("{}/{}", repo.org, repo.name)) {
+                bail!(
+                    "the exception `{exception}` of the `{}` policy is not a known repo, remove it \
+                     from config/policies.toml",
+                    policy.rule
+                );
+            }
+        }
+        let repos = repos().filter(|(repo, _)| policy.applies_to(repo));
+        wrapper(repos, errors, |(repo, archived), _| {
+            if let Some(fix) = policy_violation(policy.rule, repo, archived) {
+                bail!(
+                    "repo {}/{} violates the `{}` policy ({}): {fix} in {}, or add it to the \
+                     exceptions of the policy",
+                    repo.org,
+                    repo.name,
+                    policy.rule,
+                    policy.reason,
+                    repo_location(repo, archived)
+                );
+            }
+            Ok(())
+        });
+        Ok(())
+    });
+}
+
+/// Catch privilege creep: repos which too many teams and individuals can maintain, and people
+/// who can maintain too many repos
+fn validate_privileged_access(data: &Data, errors: &mut Vec<String>) {
+    let Some(limits) = data.privileged_access_limits() else {
+        return;
+    };
+    // Admin access is rejected by `validate_admin_access`, which leaves `maintain` as the most
+    // privileged access
+    let is_privileged = |permission: &RepoPermission| {
+        matches!(permission, RepoPermission::Maintain | RepoPermission::Admin)
+    };
+
+    let mut repos_per_person: BTreeMap<&str, BTreeSet<String>> = BTreeMap::new();
+    wrapper(data.repos(), errors, |repo, _| {
+        let full_name = format!("{}/{}", repo.org, repo.name);
+        let mut collaborators = Vec::new();
+        for (team_name, permission) in &repo.access.teams {
+            if !is_privileged(permission) {
+                continue;
+            }
+            collaborators.push(format!("team `{team_name}`"));
+            if let Some(team) = data.team(team_name) {
+                for member in team.members(data)? {
+                    repos_per_person
+                        .entry(member)
+                        .or_default()
+                        .insert(full_name.clone());
+                }
+            }
+        }
+        for (name, access) in &repo.access.individuals {
+            if !is_privileged(&access.permission) || access.is_expired() {
+                continue;
+            }
+            collaborators.push(format!("`{name}`"));
+            repos_per_person
+                .entry(name)
+                .or_default()
+                .insert(full_name.clone());
+        }
+
+        if collaborators.len() > limits.max_per_repo && !limits.allowed_repos.contains(&full_name) {
+            collaborators.sort();
+            bail!(
+                "repo {full_name} gives `maintain` access to {} teams and individuals, more than \
+                 the limit of {}: {}. Reduce their access in {}, or add the repo to \
+                 `allowed-repos` in config/policies.toml",
+                collaborators.len(),
+                limits.max_per_repo,
+                collaborators.join(", "),
+                repo_location(repo, false)
+            );
+        }
+        Ok(())
+    });
+
+    for (person, repos) in repos_per_person {
+        if repos.len() > limits.max_per_person
+            && !limits
+                .allowed_people
+                .iter()
+                .any(|allowed| allowed == person)
+        {
+            errors.push(format!(
+                "`{person}` has `maintain` access to {} repos, more than the limit of {}: {}. \
+                 Reduce their access, or add them to `allowed-people` in config/policies.toml",
+                repos.len(),
+                limits.max_per_person,
+                repos.into_iter().collect::<Vec<_>>().join(", ")
+            ));
+        }
+    }
+}
+
+/// Ensure team and repo names follow the conventions of `config/policies.toml`
+fn validate_naming_conventions(data: &Data, errors: &mut Vec<String>) {
+    let naming = data.naming_conventions();
+    let is_exception = |name: &str| naming.exceptions.iter().any(|exception| exception == name);
+
+    wrapper(data.teams(), errors, |team, _| {
+        let name = team.name();
+        if is_exception(name) {
+            return Ok(());
+        }
+        if naming.kebab_case_teams && !ascii_kebab_case(name) {
+            bail!(
+                "{}: team name `{name}` must be lowercase words separated by dashes",
+                name_location(data.team_path(name))
+            );
+        }
+        if let Some(prefix) = naming.team_prefixes.get(&team.kind())
+            && !name.starts_with(prefix.as_str())
+        {
+            bail!(
+                "{}: the name of {} `{name}` must start with `{prefix}`",
+                name_location(data.team_path(name)),
+                team.kind()
+            );
+        }
+        Ok(())
+    });
+
+    wrapper(
+        naming.repo_patterns.iter(),
+        errors,
+        |(org, pattern), errors| {
+            let regex = Regex::new(&format!("^(?:{pattern})$")).with_context(|| {
+                format!("the pattern of the repo names of {org} in config/policies.toml is invalid")
+            })?;
+            let repos = data.repos().filter(|repo| repo.org == *org);
+            wrapper(repos, errors, |repo, _| {
+                if !regex.is_match(&repo.name)
+                    && !is_exception(&format!("{}/{}", repo.org, repo.name))
+                {
+                    bail!(
+                        "{}: repo name `{}` doesn't match `{pattern}`, the pattern of the repo \
+                         names of {org}",
+                        name_location(data.repo_path(&repo.org, &repo.name)),
+                        repo.name
+                    );
+                }
+                Ok(())
+            });
+            Ok(())
+        },
+    );
+}
+
+/// Point at the line setting the `name` of a team or repo, as `file:line`
+fn name_location(path: Option<&Path>) -> String {
+    let Some(path) = path else {
+        return "unknown file".to_owned();
+    };
+    // Teams split across several files are in a directory, which can't be read
+    let line = std::fs::read_to_string(path).ok().and_then(|contents| {
+        contents.lines().position(|line| {
+            line.split_once('=')
+                .is_some_and(|(key, _)| key.trim() == "name")
+        })
+    });
+    match line {
+        Some(line) => format!("{}:{}", path.display(), line + 1),
+        None => path.display().to_string(),
+    }
+}
+
+/// Describe how to fix a repo which doesn't follow a policy rule
+fn policy_violation(rule: PolicyRule, repo: &Repo, archived: bool) -> Option<String> {
+    match rule {
+        PolicyRule::ProtectDefaultBranch => {
+            // Repos which don't configure their default branch use one of the usual names
+            let default_branches = match &repo.default_branch {
+                Some(branch) => vec![branch.as_str()],
+                None => vec!["main", "master"],
+            };
+            let protected = repo.branch_protections.iter().any(|protection| {
+                protection.target == ProtectionTarget::Branch
+                    && default_branches
+                        .iter()
+                        .any(|branch| branch_pattern_matches(&protection.pattern, branch))
+            });
+            (!protected && !archived).then(|| {
+                format!(
+                    "add a `[[branch-protections]]` entry for `{}`",
+                    default_branches[0]
+                )
+            })
+        }
+        PolicyRule::NoArchivedCollaborators => {
+            let has_collaborators = !repo.access.teams.is_empty()
+                || !repo.access.individuals.is_empty()
+                || !repo.bots.is_empty();
+            (archived && has_collaborators)
+                .then(|| "remove its `bots`, `access.teams` and `access.individuals`".to_owned())
+        }
+    }
+}
+
+fn validate_archived_repos(data: &Data, errors: &mut Vec<String>) {
+    wrapper(data.archived_repos(), errors, |repo, _| {
+        if !repo.access.teams.is_empty() {
+            bail!("archived repo '{}' should not have any teams", repo.name);
+        }
+        if repo.archive_at.is_some() {
+            bail!(
+                "archived repo '{}' should not have a scheduled archival date",
+                repo.name
+            );
+        }
+        Ok(())
+    });
+}
+
+/// Ensure that GitHub team maintainers are members of the GitHub team
+fn validate_github_team_maintainers(data: &Data, errors: &mut Vec<String>) {
+    wrapper(data.teams(), errors, |team, errors| {
+        wrapper(
+            team.github_teams(data)?.into_iter(),
+            errors,
+            |gh_team, _| {
+                for (maintainer, id) in &gh_team.maintainers {
+                    if !gh_team.members.iter().any(|(_, member)| member == id) {
+                        bail!(
+                            "maintainer `{maintainer}` of GitHub team `{}/{}` is not a member of the team",
+                            gh_team.org,
+                            gh_team.name
+                        );
+                    }
+                }
+                Ok(())
+            },
+        );
+        Ok(())
+    });
+}
+
+/// Ensure that parent GitHub teams are managed in the same org, are not secret
+/// and don't form cycles
+fn validate_github_team_parents(data: &Data, errors: &mut Vec<String>) {
+    let mut parents = HashMap::new();
+    let mut secret_teams = HashSet::new();
+    for team in data.teams() {
+        // Errors in the GitHub teams are reported by `validate_github_teams`
+        if let Ok(gh_teams) = team.github_teams(data) {
+            for gh_team in gh_teams {
+                parents.insert((gh_team.org, gh_team.name), gh_team.parent);
+                if gh_team.privacy == Some(GitHubTeamPrivacy::Secret) {
+                    secret_teams.insert((gh_team.org, gh_team.name));
+                }
+            }
+        }
+    }
+
+    wrapper(parents.iter(), errors, |((org, name), parent), _| {
+        if let Some(parent) = parent
+            && (secret_teams.contains(&(*org, *name)) || secret_teams.contains(&(*org, *parent)))
+        {
+            bail!(
+                "GitHub team `{org}/{name}` cannot be nested under `{parent}`, as secret teams cannot be nested"
+            );
+        }
+        let mut current = *parent;
+        let mut seen = HashSet::new();
+        while let Some(parent) = current {
+            if !seen.insert(parent) || parent == *name {
+                bail!("GitHub team `{org}/{name}` is nested under itself");
+            }
+            let Some(grandparent) = parents.get(&(*org, parent)) else {
+                bail!(
+                    "parent `{parent}` of GitHub team `{org}/{name}` is not a GitHub team managed in `{org}`"
+                );
+            };
+            current = *grandparent;
+        }
+        Ok(())
+    });
+}
+
+/// Ensure that org settings are only configured for allowed orgs
+fn validate_orgs(data: &Data, errors: &mut Vec<String>) {
+    let allowed = data.config().allowed_github_orgs();
+    wrapper(data.orgs(), errors, |org, _| {
+        if !allowed.contains(&org.name) {
+            bail!("GitHub organization `{}` isn't allowed", org.name);
+        }
+        Ok(())
+    });
+}
+
+/// Validate that environments have valid names (non-empty), deployment patterns and protection rules
+fn validate_environments(data: &Data, errors: &mut Vec<String>) {
+    let github_teams = data.github_teams();
+
+    wrapper(data.all_repos(), errors, |repo, _| {
+        for (env_name, env) in &repo.environments {
+            if env_name.is_empty() {
+                bail!(
+                    "repo {}/{} has an environment with an empty name",
+                    repo.org,
+                    repo.name
+                );
+            }
+            // Environment names should not contain special characters that could cause issues
+            // with GitHub API URLs or shell commands. Forward and backward slashes are particularly
+            // problematic as they can be interpreted as path separators
+            //in URLs (/repos/{owner}/{repo}/environments/{name}) and file systems,
+            //potentially leading to security vulnerabilities or API routing issues.
+            if env_name.contains('/') || env_name.contains('\\') {
+                bail!(
+                    "repo {}/{} has an environment '{}' with invalid characters (/, \\)",
+                    repo.org,
+                    repo.name,
+                    env_name
+                );
+            }
+
+            // Validate branch and tag patterns are not empty and not duplicated
+            let mut seen_branches = HashSet::new();
+            let mut seen_tags = HashSet::new();
+
+            // Validate branch patterns (new field)
+            for branch in &env.branches {
+                if branch.is_empty() {
+                    bail!(
+                        "repo {}/{} environment '{}' has an empty branch pattern",
+                        repo.org,
+                        repo.name,
+                        env_name
+                    );
+                }
+                if !seen_branches.insert(branch) {
+                    bail!(
+                        "repo {}/{} environment '{}' has duplicate branch pattern '{}'",
+                        repo.org,
+                        repo.name,
+                        env_name,
+                        branch
+                    );
+                }
+            }
+
+            // Validate tag patterns (new field)
+            for tag in &env.tags {
+                if tag.is_empty() {
+                    bail!(
+                        "repo {}/{} environment '{}' has an empty tag pattern",
+                        repo.org,
+                        repo.name,
+                        env_name
+                    );
+                }
+                if !seen_tags.insert(tag) {
+                    bail!(
+                        "repo {}/{} environment '{}' has duplicate tag pattern '{}'",
+                        repo.org,
+                        repo.name,
+                        env_name,
+                        tag
+                    );
+                }
+            }
+
+            if env.protected_branches_only && !(env.branches.is_empty() && env.tags.is_empty()) {
+                bail!(
+                    "repo {}/{} environment '{}' allows only protected branches, so it cannot have branch or tag patterns",
+                    repo.org,
+                    repo.name,
+                    env_name
+                );
+            }
+
+            // GitHub limits both of these settings
+            if env.reviewers.len() > 6 {
+                bail!(
+                    "repo {}/{} environment '{}' has more than 6 reviewers",
+                    repo.org,
+                    repo.name,
+                    env_name
+                );
+            }
+            if env.wait_timer > 43200 {
+                bail!(
+                    "repo {}/{} environment '{}' has a wait timer longer than 43200 minutes (30 days)",
+                    repo.org,
+                    repo.name,
+                    env_name
+                );
+            }
+
+            let mut seen_reviewers = HashSet::new();
+            for team in &env.reviewers {
+                if !seen_reviewers.insert(team) {
+                    bail!(
+                        "repo {}/{} environment '{}' has duplicate reviewer '{}'",
+                        repo.org,
+                        repo.name,
+                        env_name,
+                        team
+                    );
+                }
+                if !github_teams.contains(&(repo.org.clone(), team.clone())) {
+                    bail!(
+                        "repo {}/{} environment '{}' mentions the '{}' github team as a reviewer, but that team does not seem to exist",
+                        repo.org,
+                        repo.name,
+                        env_name,
+                        team
+                    );
+                }
+                // Reviewers need at least read access to the repository
+                if !repo.access.teams.contains_key(team) {
+                    bail!(
+                        "repo {}/{} environment '{}' mentions the '{}' team as a reviewer, but that team is not mentioned in [access.teams]",
+                        repo.org,
+                        repo.name,
+                        env_name,
+                        team
+                    );
+                }
+            }
+        }
+
+        // No need to check for duplicate environment names since HashMap keys are unique
+        Ok(())
+    });
+}
+
+/// Ensure topics follow the rules of GitHub
+fn validate_topics(data: &Data, errors: &mut Vec<String>) {
+    wrapper(data.all_repos(), errors, |repo, _| {
+        if repo.topics.len() > 20 {
+            bail!("repo {}/{} has more than 20 topics", repo.org, repo.name);
+        }
+        let mut topics = HashSet::new();
+        for topic in &repo.topics {
+            if topic.is_empty()
+                || topic.len() > 50
+                || topic.starts_with('-')
+                || !topic
+                    .chars()
+                    .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+            {
+                bail!(
+                    "repo {}/{} has an invalid topic '{topic}': topics have at most 50 lowercase \
+                    letters, numbers and hyphens, and can't start with a hyphen",
+                    repo.org,
+                    repo.name
+                );
+            }
+            if !topics.insert(topic) {
+                bail!(
+                    "repo {}/{} has duplicate topic '{topic}'",
+                    repo.org,
+                    repo.name
+                );
+            }
+        }
+        Ok(())
+    });
+}
+
+/// Validate that repository labels have valid colors and unique names.
+fn validate_labels(data: &Data, errors: &mut Vec<String>) {
+    wrapper(data.all_repos(), errors, |repo, _| {
+        let mut names = HashSet::new();
+        for label in &repo.labels {
+            // Label names are used in GitHub API URLs (/repos/{owner}/{repo}/labels/{name})
+            if label.name.is_empty() || label.name.contains(['/', '?', '#', '%']) {
+                bail!(
+                    "repo {}/{} has a label '{}' with an empty name or invalid characters (/, ?, #, %)",
+                    repo.org,
+                    repo.name,
+                    label.name
+                );
+            }
+            // GitHub treats label names case-insensitively
+            if !names.insert(label.name.to_lowercase()) {
+                bail!(
+                    "repo {}/{} has duplicate label '{}'",
+                    repo.org,
+                    repo.name,
+                    label.name
+                );
+            }
+            if label.color.len() != 6 || !label.color.chars().all(|c| c.is_ascii_hexdigit()) {
+                bail!(
+                    "repo {}/{} label '{}' has invalid color '{}', expected six hexadecimal digits without a leading `#`",
+                    repo.org,
+                    repo.name,
+                    label.name,
+                    label.color
+                );
+            }
+        }
+        Ok(())
+    });
+}
+
+/// Validate that repository webhooks are unique and properly configured.
+fn validate_webhooks(data: &Data, errors: &mut Vec<String>) {
+    let secret_env_re = Regex::new(r"^[A-Z][A-Z0-9_]*$").unwrap();
+    wrapper(data.all_repos(), errors, |repo, _| {
+        let mut urls = HashSet::new();
+        for webhook in &repo.webhooks {
+            if !webhook.url.starts_with("https://") {
+                bail!(
+                    "repo {}/{} has a webhook with a non-HTTPS URL '{}'",
+                    repo.org,
+                    repo.name,
+                    webhook.url
+                );
+            }
+            if !urls.insert(&webhook.url) {
+                bail!(
+                    "repo {}/{} has multiple webhooks with the URL '{}'",
+                    repo.org,
+                    repo.name,
+                    webhook.url
+                );
+            }
+            if webhook.events.is_empty() {
+                bail!(
+                    "repo {}/{} webhook '{}' does not subscribe to any events",
+                    repo.org,
+                    repo.name,
+                    webhook.url
+                );
+            }
+            if let Some(secret_env) = &webhook.secret_env
+                && !secret_env_re.is_match(secret_env)
+            {
+                bail!(
+                    "repo {}/{} webhook '{}' has an invalid `secret-env` '{}', expected an uppercase environment variable name",
+                    repo.org,
+                    repo.name,
+                    webhook.url,
+                    secret_env
+                );
+            }
+            if let Some(secret_env) = &webhook.secret_env
+                && !secret_env.starts_with(WEBHOOK_SECRET_ENV_PREFIX)
+            {
+                bail!(
+                    "repo {}/{} webhook '{}' has an invalid `secret-env` '{}', it must start with `{WEBHOOK_SECRET_ENV_PREFIX}`",
+                    repo.org,
+                    repo.name,
+                    webhook.url,
+                    secret_env
+                );
+            }
+        }
+        Ok(())
+    });
+}
+
+/// Validate that autolink references have a prefix and a URL template with a `<num>` placeholder.
+fn validate_autolinks(data: &Data, errors: &mut Vec<String>) {
+    wrapper(data.all_repos(), errors, |repo, _| {
+        for (prefix, url_template) in &repo.autolinks {
+            if prefix.is_empty() {
+                bail!(
+                    "repo {}/{} has an autolink with an empty prefix",
+                    repo.org,
+                    repo.name
+                );
+            }
+            if !url_template.starts_with("https://") || !url_template.contains("<num>") {
+                bail!(
+                    "repo {}/{} autolink '{}' has an invalid URL template '{}', expected an HTTPS URL containing `<num>`",
+                    repo.org,
+                    repo.name,
+                    prefix,
+                    url_template
+                );
+            }
+        }
         Ok(())
     });
 }
 
-/// Ensure repos reference valid teams and that they are unique
-fn validate_repos(data: &Data, errors: &mut Vec<String>) {
-    let allowed_orgs = data.config().allowed_github_orgs();
-    let mut repo_map = HashSet::new();
-
+/// Validate that the fingerprints of allowed deploy keys are SHA256 fingerprints, like those
+/// printed by `ssh-keygen -l`.
+fn validate_deploy_keys(data: &Data, errors: &mut Vec<String>) {
+    let fingerprint_re = Regex::new(r"^SHA256:[A-Za-z0-9+/]{43}$").unwrap();
     wrapper(data.all_repos(), errors, |repo, _| {
-        if !repo_map.insert(format!("{}/{}", repo.org, repo.name)) {
-            bail!("The repo {}/{} is duplicated", repo.org, repo.name);
-        }
-
-        if !allowed_orgs.contains(&repo.org) {
-            bail!(
-                "The repo '{}' is in an invalid org '{}'",
-                repo.name,
-                repo.org
-            );
-        }
-        for team_name in repo.access.teams.keys() {
-            check_team_access(data, repo, team_name).with_context(|| {
-                format!("Access for the repo {}/{} is invalid", repo.org, repo.name)
-            })?;
-        }
-
-        for name in repo.access.individuals.keys() {
-            if data.person(name).is_none() {
+        let mut fingerprints = HashSet::new();
+        for key in repo.deploy_keys.iter().flatten() {
+            if !fingerprint_re.is_match(&key.fingerprint) {
                 bail!(
-                    "Access for the repo {}/{} is invalid: '{}' is not the name of a person in the team repo",
+                    "repo {}/{} deploy key '{}' has an invalid fingerprint '{}', expected `SHA256:` followed by the base64 digest",
                     repo.org,
                     repo.name,
-                    name
+                    key.title,
+                    key.fingerprint
+                );
+            }
+            if !fingerprints.insert(&key.fingerprint) {
+                bail!(
+                    "repo {}/{} allows the deploy key '{}' multiple times",
+                    repo.org,
+                    repo.name,
+                    key.fingerprint
                 );
             }
         }
@@ -1038,112 +2447,111 @@ fn validate_repos(data: &Data, errors: &mut Vec<String>) {
     });
 }
 
-fn check_team_access(data: &Data, repo: &Repo, team_name: &str) -> anyhow::Result<()> {
-    let team = data
-        .team(team_name)
-        .with_context(|| format!("The file 'teams/{team_name}.toml' does not exist."))?;
-    if data.is_team_archived(team.name(), &repo.org) {
-        bail!(
-            "The team '{}/{team_name}' is archived. Please remove the team access from the `[access.teams]` section of the repo",
-            repo.org
-        );
-    }
-
-    let are_gh_teams_in_org = team.github_teams(data)?.iter().any(|t| t.org == repo.org);
-    anyhow::ensure!(
-        are_gh_teams_in_org,
-        "The team '{team_name}' does not have any GitHub teams in the org '{}'.",
-        repo.org
-    );
-    Ok(())
-}
-
-fn validate_archived_repos(data: &Data, errors: &mut Vec<String>) {
-    wrapper(data.archived_repos(), errors, |repo, _| {
-        if !repo.access.teams.is_empty() {
-            bail!("archived repo '{}' should not have any teams", repo.name);
+/// Validate that the selected actions are configured exactly when only selected actions are
+/// allowed, so that the actions allowed by GitHub are always known.
+fn validate_actions_permissions(data: &Data, errors: &mut Vec<String>) {
+    wrapper(data.all_repos(), errors, |repo, _| {
+        let Some(actions) = &repo.actions else {
+            return Ok(());
+        };
+        match (actions.allowed_actions, &actions.selected_actions) {
+            (AllowedActions::Selected, None) => bail!(
+                "repo {}/{} allows selected actions, but doesn't configure them in `[actions.selected-actions]`",
+                repo.org,
+                repo.name
+            ),
+            (AllowedActions::All | AllowedActions::LocalOnly, Some(_)) => bail!(
+                "repo {}/{} configures `[actions.selected-actions]`, but doesn't set `allowed-actions = \"selected\"`",
+                repo.org,
+                repo.name
+            ),
+            _ => {}
+        }
+        let mut patterns = HashSet::new();
+        for pattern in actions
+            .selected_actions
+            .iter()
+            .flat_map(|s| &s.patterns_allowed)
+        {
+            if pattern.is_empty() || pattern.contains(char::is_whitespace) || pattern.contains(',')
+            {
+                bail!(
+                    "repo {}/{} allows the invalid action pattern '{}'",
+                    repo.org,
+                    repo.name,
+                    pattern
+                );
+            }
+            if !patterns.insert(pattern) {
+                bail!(
+                    "repo {}/{} allows the action pattern '{}' multiple times",
+                    repo.org,
+                    repo.name,
+                    pattern
+                );
+            }
         }
         Ok(())
     });
 }
 
-/// Validate that environments have valid names (non-empty) and deployment patterns
-fn validate_environments(data: &Data, errors: &mut Vec<String>) {
+/// Validate that template repositories are referenced as `owner/name`.
+fn validate_repo_templates(data: &Data, errors: &mut Vec<String>) {
     wrapper(data.all_repos(), errors, |repo, _| {
-        for (env_name, env) in &repo.environments {
-            if env_name.is_empty() {
+        if let Some(template) = &repo.template {
+            let valid = template.split_once('/').is_some_and(|(owner, name)| {
+                !owner.is_empty() && !name.is_empty() && !name.contains('/')
+            });
+            if !valid {
                 bail!(
-                    "repo {}/{} has an environment with an empty name",
+                    "repo {}/{} has an invalid template '{}', expected `owner/name`",
                     repo.org,
-                    repo.name
+                    repo.name,
+                    template
                 );
             }
-            // Environment names should not contain special characters that could cause issues
-            // with GitHub API URLs or shell commands. Forward and backward slashes are particularly
-            // problematic as they can be interpreted as path separators
-            //in URLs (/repos/{owner}/{repo}/environments/{name}) and file systems,
-            //potentially leading to security vulnerabilities or API routing issues.
-            if env_name.contains('/') || env_name.contains('\\') {
+            if *template == format!("{}/{}", repo.org, repo.name) {
                 bail!(
-                    "repo {}/{} has an environment '{}' with invalid characters (/, \\)",
+                    "repo {}/{} cannot use itself as its template",
                     repo.org,
-                    repo.name,
-                    env_name
+                    repo.name
                 );
             }
+        }
+        Ok(())
+    });
+}
 
-            // Validate branch and tag patterns are not empty and not duplicated
-            let mut seen_branches = HashSet::new();
-            let mut seen_tags = HashSet::new();
-
-            // Validate branch patterns (new field)
-            for branch in &env.branches {
-                if branch.is_empty() {
-                    bail!(
-                        "repo {}/{} environment '{}' has an empty branch pattern",
-                        repo.org,
-                        repo.name,
-                        env_name
-                    );
-                }
-                if !seen_branches.insert(branch) {
-                    bail!(
-                        "repo {}/{} environment '{}' has duplicate branch pattern '{}'",
-                        repo.org,
-                        repo.name,
-                        env_name,
-                        branch
-                    );
-                }
-            }
-
-            // Validate tag patterns (new field)
-            for tag in &env.tags {
-                if tag.is_empty() {
-                    bail!(
-                        "repo {}/{} environment '{}' has an empty tag pattern",
-                        repo.org,
-                        repo.name,
-                        env_name
-                    );
-                }
-                if !seen_tags.insert(tag) {
-                    bail!(
-                        "repo {}/{} environment '{}' has duplicate tag pattern '{}'",
-                        repo.org,
-                        repo.name,
-                        env_name,
-                        tag
-                    );
-                }
+/// Ensure repos only use the bots deployed in their organization, as listed in `config.toml`
+fn validate_allowed_bots(data: &Data, errors: &mut Vec<String>) {
+    wrapper(data.repos(), errors, |repo, _| {
+        let allowed = data.config().allowed_bots(&repo.org);
+        for bot in &repo.bots {
+            if !allowed.contains(bot) {
+                bail!(
+                    "{}: bot `{bot}` is not deployed in the {} organization (allowed bots: {}). \
+                     Once it has access to the organization, add it to `allowed-bots` in config.toml",
+                    repo_location(repo, false),
+                    repo.org,
+                    if allowed.is_empty() {
+                        "none".to_owned()
+                    } else {
+                        allowed
+                            .iter()
+                            .map(|bot| format!("`{bot}`"))
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    }
+                );
             }
         }
-
-        // No need to check for duplicate environment names since HashMap keys are unique
         Ok(())
     });
 }
 
+/// The largest number of pull requests GitHub accepts in the settings of a merge queue
+const MAX_MERGE_QUEUE_ENTRIES: u32 = 100;
+
 /// Validate that branch protections make sense in combination with used bots.
 fn validate_branch_protections(data: &Data, errors: &mut Vec<String>) {
     let github_teams = data.github_teams();
@@ -1162,7 +2570,11 @@ fn validate_branch_protections(data: &Data, errors: &mut Vec<String>) {
                 );
             }
 
-            for team in &protection.allowed_merge_teams {
+            for team in protection
+                .allowed_merge_teams
+                .iter()
+                .chain(&protection.pr_bypass_teams)
+            {
                 let key = (repo.org.clone(), team.clone());
                 if !github_teams.contains(&key) {
                     bail!(
@@ -1175,7 +2587,7 @@ but that team does not seem to exist"#,
                 }
                 if !repo.access.teams.contains_key(team) {
                     bail!(
-                        r#"repo '{}' uses a branch protection for {} that has an allowed merge team '{}',
+                        r#"repo '{}' uses a branch protection for {} that mentions the '{}' team,
 but that team is not mentioned in [access.teams]"#,
                         repo.name,
                         protection.pattern,
@@ -1203,6 +2615,83 @@ but that team is not mentioned in [access.teams]"#,
                         protection.pattern,
                     );
                 }
+                if protection.requires_code_owner_reviews {
+                    bail!(
+                        r#"repo '{}' uses a branch protection for {} that does not require a PR, but requires code owner reviews"#,
+                        repo.name,
+                        protection.pattern,
+                    );
+                }
+            }
+
+            let bypasses_pr =
+                !protection.pr_bypass_teams.is_empty() || !protection.pr_bypass_apps.is_empty();
+            if bypasses_pr {
+                if !protection.pr_required {
+                    bail!(
+                        r#"repo '{}' uses a branch protection for {} that does not require a PR, but has PR bypass allowances"#,
+                        repo.name,
+                        protection.pattern,
+                    );
+                }
+                if repo.use_rulesets {
+                    bail!(
+                        r#"repo '{}' uses rulesets, which do not support the PR bypass allowances of the branch protection for {}"#,
+                        repo.name,
+                        protection.pattern,
+                    );
+                }
+                if let Some(app) = protection
+                    .pr_bypass_apps
+                    .iter()
+                    .find(|app| **app != AllowedMergeApp::RustTimer)
+                {
+                    bail!(
+                        r#"repo '{}' uses a branch protection for {} that allows '{app:?}' to bypass PRs, but only `rust-timer` is supported in `pr-bypass-apps`"#,
+                        repo.name,
+                        protection.pattern,
+                    );
+                }
+            }
+
+            if protection.merge_queue && !repo.use_rulesets {
+                bail!(
+                    r#"repo '{}' enables `merge-queue` for {}, but merge queues can only be configured for repos that use rulesets"#,
+                    repo.name,
+                    protection.pattern,
+                );
+            }
+            if let Some(settings) = &protection.merge_queue_settings {
+                if !protection.merge_queue {
+                    bail!(
+                        r#"repo '{}' configures `merge-queue-settings` for {}, but `merge-queue` is not enabled"#,
+                        repo.name,
+                        protection.pattern,
+                    );
+                }
+                for (name, entries) in [
+                    ("min-entries-to-merge", settings.min_entries_to_merge),
+                    ("max-entries-to-merge", settings.max_entries_to_merge),
+                    ("max-entries-to-build", settings.max_entries_to_build),
+                ] {
+                    if entries.is_some_and(|entries| entries > MAX_MERGE_QUEUE_ENTRIES) {
+                        bail!(
+                            r#"repo '{}' uses a merge queue for {} with `{name}` greater than {MAX_MERGE_QUEUE_ENTRIES}"#,
+                            repo.name,
+                            protection.pattern,
+                        );
+                    }
+                }
+                if let (Some(min), Some(max)) =
+                    (settings.min_entries_to_merge, settings.max_entries_to_merge)
+                    && min > max
+                {
+                    bail!(
+                        r#"repo '{}' uses a merge queue for {} with `min-entries-to-merge` greater than `max-entries-to-merge`"#,
+                        repo.name,
+                        protection.pattern,
+                    );
+                }
             }
 
             let managed_by_bors = protection
@@ -1217,13 +2706,15 @@ but that team is not mentioned in [access.teams]"#,
                     );
                 }
                 if protection.required_approvals.is_some()
+                    || protection.requires_code_owner_reviews
                     || protection.dismiss_stale_review
                     || !protection.pr_required
                     || !protection.allowed_merge_teams.is_empty()
                 {
                     bail!(
                         r#"repo '{}' uses bors, but its branch protection for {} uses invalid
-attributes (`required-approvals`, `dismiss-stale-review`, `pr-required` or `allowed-merge-teams`).
+attributes (`required-approvals`, `requires-code-owner-reviews`, `dismiss-stale-review`, `pr-required`
+or `allowed-merge-teams`).
 Please remove the attributes when using bors"#,
                         repo.name,
                         protection.pattern,
@@ -1335,7 +2826,8 @@ fn validate_member_roles(data: &Data, errors: &mut Vec<String>) {
                 }
             }
 
-            for member in team.explicit_members() {
+            // Alumni keep the roles they had as members
+            for member in team.explicit_members().iter().chain(team.explicit_alumni()) {
                 for role in &member.roles {
                     if !role_ids.contains(role) {
                         errors.push(format!(
@@ -1368,8 +2860,8 @@ fn validate_admin_access(data: &Data, errors: &mut Vec<String>) {
         wrapper(
             repo.access.individuals.iter(),
             errors,
-            |(member, permission), _| {
-                if let RepoPermission::Admin = permission {
+            |(member, access), _| {
+                if let RepoPermission::Admin = access.permission {
                     bail!(
                         "Repository {}/{} uses `admin` permission for member `{member}`",
                         repo.org,
@@ -1386,7 +2878,7 @@ fn validate_admin_access(data: &Data, errors: &mut Vec<String>) {
 }
 
 /// We use Fluent ids which are lowercase alphanumeric with hyphens.
-fn ascii_kebab_case(s: &str) -> bool {
+pub(crate) fn ascii_kebab_case(s: &str) -> bool {
     s.chars()
         .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
 }
@@ -1415,3 +2907,172 @@ fn validate_website(data: &Data, errors: &mut Vec<String>) {
         Ok(())
     })
 }
+
+/// Ensure meetings can be published as iCalendar events
+fn validate_meetings(data: &Data, errors: &mut Vec<String>) {
+    const FREQUENCIES: &[&str] = &[
+        "SECONDLY", "MINUTELY", "HOURLY", "DAILY", "WEEKLY", "MONTHLY", "YEARLY",
+    ];
+
+    wrapper(data.teams(), errors, |team, errors| {
+        let mut ids = HashSet::new();
+        wrapper(team.meetings().iter(), errors, |meeting, _| {
+            let name = &meeting.name;
+            if !ids.insert(meeting.id()) {
+                bail!(
+                    "team `{}` has multiple meetings named `{name}`",
+                    team.name()
+                );
+            }
+            let tz: chrono_tz::Tz = meeting.timezone.parse().map_err(|_| {
+                anyhow::anyhow!(
+                    "meeting `{name}` of team `{}` has an unknown time zone `{}`",
+                    team.name(),
+                    meeting.timezone
+                )
+            })?;
+            if meeting.start.and_local_timezone(tz).earliest().is_none() {
+                bail!(
+                    "meeting `{name}` of team `{}` starts at {}, which doesn't exist in {tz}",
+                    team.name(),
+                    meeting.start
+                );
+            }
+            if meeting.duration == 0 {
+                bail!("meeting `{name}` of team `{}` has no duration", team.name());
+            }
+            if let Some(recurrence) = &meeting.recurrence {
+                let mut frequency = None;
+                for part in recurrence.split(';') {
+                    let Some((key, value)) = part.split_once('=') else {
+                        bail!(
+                            "recurrence `{recurrence}` of meeting `{name}` of team `{}` must be \
+                            a list of `KEY=VALUE` separated by `;`",
+                            team.name()
+                        );
+                    };
+                    if key == "FREQ" {
+                        frequency = Some(value);
+                    }
+                }
+                if !frequency.is_some_and(|frequency| FREQUENCIES.contains(&frequency)) {
+                    bail!(
+                        "recurrence `{recurrence}` of meeting `{name}` of team `{}` must have a \
+                        valid `FREQ`, one of {}",
+                        team.name(),
+                        FREQUENCIES.join(", ")
+                    );
+                }
+            }
+            if let Some(link) = &meeting.video_link
+                && !link.starts_with("https://")
+            {
+                bail!(
+                    "video link `{link}` of meeting `{name}` of team `{}` must use https",
+                    team.name()
+                );
+            }
+            Ok(())
+        });
+        Ok(())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        branch_pattern_matches, record_former_github_username, rename_github_user,
+        validate_team_leads,
+    };
+    use crate::data::Data;
+    use std::path::Path;
+
+    /// Load a copy of the test data, with the given team file replaced
+    fn load_data(team: &str, content: &str) -> Data {
+        let source = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/static-api");
+        let dir = tempfile::tempdir().unwrap();
+        for entry in walkdir::WalkDir::new(&source) {
+            let entry = entry.unwrap();
+            let relative = entry.path().strip_prefix(&source).unwrap();
+            if relative.starts_with("_expected") || relative.starts_with("_output") {
+                continue;
+            }
+            let target = dir.path().join(relative);
+            if entry.file_type().is_dir() {
+                std::fs::create_dir_all(&target).unwrap();
+            } else {
+                std::fs::copy(entry.path(), &target).unwrap();
+            }
+        }
+        std::fs::write(dir.path().join("teams").join(team), content).unwrap();
+        Data::load(dir.path()).unwrap()
+    }
+
+    #[test]
+    fn expired_leads() {
+        let foo = std::fs::read_to_string(
+            Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/static-api/teams/foo.toml"),
+        )
+        .unwrap();
+        let mut errors = Vec::new();
+        validate_team_leads(&load_data("foo.toml", &foo), &mut errors);
+        assert!(errors.is_empty(), "{errors:?}");
+
+        // user-2's membership expired in 2020
+        let foo = foo.replace("leads = [\"user-0\"]", "leads = [\"user-0\", \"user-2\"]");
+        let mut errors = Vec::new();
+        validate_team_leads(&load_data("foo.toml", &foo), &mut errors);
+        assert_eq!(errors.len(), 1, "{errors:?}");
+        assert!(
+            errors[0].contains("`user-2` leads team `foo`"),
+            "{errors:?}"
+        );
+    }
+
+    #[test]
+    fn branch_patterns() {
+        assert!(branch_pattern_matches("main", "main"));
+        assert!(!branch_pattern_matches("masterr", "master"));
+        assert!(branch_pattern_matches("release-*", "release-1.0"));
+        assert!(!branch_pattern_matches("release-*", "release-1.0/hotfix"));
+        assert!(branch_pattern_matches("release/**", "release/1.0/hotfix"));
+        assert!(branch_pattern_matches("v?", "v1"));
+        assert!(!branch_pattern_matches("v?", "v10"));
+        assert!(branch_pattern_matches("*", "main"));
+    }
+
+    #[test]
+    fn rename_github_user_in_toml() {
+        let person = "name = \"old\"\ngithub = \"old\"\ngithub-id = 1\n";
+        assert_eq!(
+            rename_github_user(person, "old", "new").unwrap(),
+            "name = \"old\"\ngithub = \"new\"\ngithub-id = 1\n"
+        );
+
+        let team = "name = \"old\"\nsubteam-of = \"old\"\n\n[people]\nleads = ['old']\nmembers = [\n    \"old\",\n    \"older\",\n    { github = \"old\", roles = [\"lead\"] },\n]\nalumni = [\"old\"]\nincluded-teams = [\"old\"]\n\n[[github]]\norgs = [\"old\"]\nmaintainers = [\"old\"]\n\n[rfcbot]\nlabel = \"old\"\nname = \"old\"\nping = \"old\"\nexclude-members = [\"old\"]\n\n[[lists]]\naddress = \"old@example.com\"\nextra-people = [\"old\"]\nextra-teams = [\"old\"]\n\n[[zulip-groups]]\nname = \"old\"\nextra-people = [\"old\"]\nexcluded-people = ['old']\n";
+        assert_eq!(
+            rename_github_user(team, "old", "new").unwrap(),
+            "name = \"old\"\nsubteam-of = \"old\"\n\n[people]\nleads = ['new']\nmembers = [\n    \"new\",\n    \"older\",\n    { github = \"new\", roles = [\"lead\"] },\n]\nalumni = [\"new\"]\nincluded-teams = [\"old\"]\n\n[[github]]\norgs = [\"old\"]\nmaintainers = [\"new\"]\n\n[rfcbot]\nlabel = \"old\"\nname = \"old\"\nping = \"old\"\nexclude-members = [\"new\"]\n\n[[lists]]\naddress = \"old@example.com\"\nextra-people = [\"new\"]\nextra-teams = [\"old\"]\n\n[[zulip-groups]]\nname = \"old\"\nextra-people = [\"new\"]\nexcluded-people = ['new']\n"
+        );
+
+        let repo = "org = \"rust-lang\"\nname = \"old\"\n\n[access.individuals]\nold = \"write\"\nolder = \"read\"\n\n[access.teams]\nold = \"read\"\n";
+        assert_eq!(
+            rename_github_user(repo, "old", "new").unwrap(),
+            "org = \"rust-lang\"\nname = \"old\"\n\n[access.individuals]\nnew = \"write\"\nolder = \"read\"\n\n[access.teams]\nold = \"read\"\n"
+        );
+    }
+
+    #[test]
+    fn record_former_github_username_in_toml() {
+        let person = "name = \"New\"\ngithub = \"new\"\ngithub-id = 1\n";
+        let recorded = record_former_github_username(person, "old");
+        assert_eq!(
+            recorded,
+            "name = \"New\"\ngithub = \"new\"\nformer-github-usernames = [\"old\"]\ngithub-id = 1\n"
+        );
+        assert_eq!(
+            record_former_github_username(&recorded, "older"),
+            "name = \"New\"\ngithub = \"new\"\nformer-github-usernames = [\"old\", \"older\"]\ngithub-id = 1\n"
+        );
+    }
+}